@@ -23,7 +23,7 @@ _start_services() {
 
     mkdir -p data{core,index,ldk0,ldk1,ldk2}
     # see docker-compose.yml for the exposed ports
-    EXPOSED_PORTS=(3000 50001)
+    EXPOSED_PORTS=(3000 5432 50001)
     for port in "${EXPOSED_PORTS[@]}"; do
         if [ -n "$(ss -HOlnt "sport = :$port")" ];then
             _die "port $port is already bound, services can't be started"
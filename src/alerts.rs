@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::events::NodeEvent;
+use crate::utils::{get_current_timestamp, AppState};
+
+const ALERT_WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub(crate) enum AlertLogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl AlertLogLevel {
+    fn matches(&self, level: &Level) -> bool {
+        let min_level = match self {
+            AlertLogLevel::Error => Level::ERROR,
+            AlertLogLevel::Warn => Level::WARN,
+            AlertLogLevel::Info => Level::INFO,
+            AlertLogLevel::Debug => Level::DEBUG,
+        };
+        // more severe levels compare as lower
+        *level <= min_level
+    }
+}
+
+fn default_threshold() -> u32 {
+    1
+}
+
+fn default_window_secs() -> u64 {
+    60
+}
+
+/// A rule turning log records into alerts: an alert is raised when `threshold` matching records
+/// are logged within `window_secs`
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct AlertRule {
+    pub(crate) name: String,
+    /// Text the log message has to contain
+    pub(crate) pattern: String,
+    /// Prefix the target (module path) of the log record has to start with
+    pub(crate) target: Option<String>,
+    /// Least severe level of the log records to match, all levels are matched if not set
+    pub(crate) min_level: Option<AlertLogLevel>,
+    pub(crate) severity: AlertSeverity,
+    #[serde(default = "default_threshold")]
+    pub(crate) threshold: u32,
+    #[serde(default = "default_window_secs")]
+    pub(crate) window_secs: u64,
+}
+
+impl AlertRule {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.pattern.is_empty() {
+            return Err(format!("rule {} has an empty pattern", self.name));
+        }
+        if self.threshold == 0 || self.window_secs == 0 {
+            return Err(format!(
+                "rule {} must have a positive threshold and window",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    fn matches(&self, level: &Level, target: &str, message: &str) -> bool {
+        self.min_level.map_or(true, |l| l.matches(level))
+            && self.target.as_ref().map_or(true, |t| target.starts_with(t))
+            && message.contains(&self.pattern)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Alert {
+    pub(crate) rule: String,
+    pub(crate) severity: AlertSeverity,
+    /// Message of the log record that triggered the alert
+    pub(crate) message: String,
+    pub(crate) occurrences: u32,
+    pub(crate) raised_at: u64,
+}
+
+/// Evaluates the alert rules over the log records
+pub(crate) struct AlertEngine {
+    rules: Vec<AlertRule>,
+    // timestamps of the matching records within the window, by rule
+    occurrences: Mutex<Vec<VecDeque<u64>>>,
+}
+
+impl AlertEngine {
+    pub(crate) fn new(rules: Vec<AlertRule>) -> Self {
+        let occurrences = Mutex::new(vec![VecDeque::new(); rules.len()]);
+        Self { rules, occurrences }
+    }
+
+    /// Account a log record, returning the alerts it raises.
+    ///
+    /// Once a rule raises an alert its count starts over, so a burst of records raises one alert.
+    pub(crate) fn process(
+        &self,
+        level: &Level,
+        target: &str,
+        message: &str,
+        now: u64,
+    ) -> Vec<Alert> {
+        let mut alerts = vec![];
+        for (rule, occurrences) in self
+            .rules
+            .iter()
+            .zip(self.occurrences.lock().unwrap().iter_mut())
+        {
+            if !rule.matches(level, target, message) {
+                continue;
+            }
+            occurrences.push_back(now);
+            while occurrences
+                .front()
+                .is_some_and(|t| t + rule.window_secs <= now)
+            {
+                occurrences.pop_front();
+            }
+            if occurrences.len() >= rule.threshold as usize {
+                alerts.push(Alert {
+                    rule: rule.name.clone(),
+                    severity: rule.severity,
+                    message: message.to_string(),
+                    occurrences: occurrences.len() as u32,
+                    raised_at: now,
+                });
+                occurrences.clear();
+            }
+        }
+        alerts
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+/// Tracing layer feeding the log records to the alert engine
+pub(crate) struct AlertLayer {
+    engine: AlertEngine,
+    alert_sender: mpsc::UnboundedSender<Alert>,
+}
+
+impl AlertLayer {
+    pub(crate) fn new(rules: Vec<AlertRule>) -> (Self, mpsc::UnboundedReceiver<Alert>) {
+        let (alert_sender, alert_receiver) = mpsc::unbounded_channel();
+        let layer = Self {
+            engine: AlertEngine::new(rules),
+            alert_sender,
+        };
+        (layer, alert_receiver)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for AlertLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if self.engine.rules.is_empty() {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        for alert in self.engine.process(
+            metadata.level(),
+            metadata.target(),
+            &visitor.0,
+            get_current_timestamp(),
+        ) {
+            // sending only fails once the daemon has stopped
+            let _ = self.alert_sender.send(alert);
+        }
+    }
+}
+
+/// Deliver the raised alerts on the event stream and, if configured, to the webhook
+pub(crate) async fn forward_alerts(
+    mut alert_receiver: mpsc::UnboundedReceiver<Alert>,
+    app_state: Arc<AppState>,
+) {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(ALERT_WEBHOOK_TIMEOUT_SECS))
+        .build()
+        .expect("valid client");
+    loop {
+        let alert = tokio::select! {
+            _ = app_state.cancel_token.cancelled() => break,
+            alert = alert_receiver.recv() => match alert {
+                Some(alert) => alert,
+                None => break,
+            },
+        };
+        let event = NodeEvent::Alert {
+            rule: alert.rule,
+            severity: alert.severity,
+            message: alert.message,
+            occurrences: alert.occurrences,
+            raised_at: alert.raised_at,
+        };
+        if let Some(webhook_url) = &app_state.static_state.alert_webhook_url {
+            // not logged on failure, as that could raise further alerts
+            let _ = client.post(webhook_url).json(&event).send().await;
+        }
+        let _ = app_state.event_sender.send(event);
+    }
+}
@@ -0,0 +1,207 @@
+use amplify::s;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::middleware::Next;
+use axum::response::Response;
+use bitcoin::hashes::sha256::Hash as Sha256;
+use bitcoin::hashes::Hash;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::disk::write_file_atomically;
+use crate::error::APIError;
+use crate::utils::{get_current_timestamp, hex_str, AppState};
+
+/// API tokens of the node, in its storage dir so that they can be checked while it's locked
+pub(crate) const API_TOKENS_FNAME: &str = "api_tokens";
+
+/// Routes that can be called without a token, being either public (LNURL, web UI) or protected
+/// by the node password
+const PUBLIC_ROUTES: &[&str] = &[
+    "/.well-known/lnurlp/:username",
+    "/createapitoken",
+    "/init",
+    "/lnurlp/:username/callback",
+    "/lnurlw/:k1",
+    "/lnurlw/:k1/callback",
+    "/ui",
+    "/ui/:file",
+    "/unlock",
+];
+
+/// Routes read-only tokens can call, which don't move funds nor change the node state
+const READ_ONLY_ROUTES: &[&str] = &[
+    "/assetbalance",
+    "/backups",
+    "/btcbalance",
+    "/channelrequests",
+    "/decodelninvoice",
+    "/decodergbinvoice",
+    "/events",
+    "/feeorders",
+    "/feereport",
+    "/forwardinghistory",
+    "/getassetmedia",
+    "/getchannelid",
+    "/getswap",
+    "/inspectconsignment",
+    "/invoicestatus",
+    "/listassets",
+    "/listchannelconversions",
+    "/listchannels",
+    "/listclosedchannels",
+    "/listfundingpsbts",
+    "/listpayments",
+    "/listpeers",
+    "/listproxypins",
+    "/listsubmarineswaps",
+    "/listswapoffers",
+    "/listswaps",
+    "/listtransactions",
+    "/listtransfers",
+    "/listunspents",
+    "/maxsendableasset",
+    "/networkgraph/channel",
+    "/networkgraph/export",
+    "/networkgraph/node",
+    "/networkinfo",
+    "/nodeinfo",
+    "/peers/policy",
+    "/pendingintercepts",
+    "/pendingsweeps",
+    "/schedules",
+    "/storagestatus",
+];
+
+/// An API token, of which only the hash is kept
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct ApiTokenData {
+    pub(crate) token_hash: String,
+    pub(crate) read_only: bool,
+    pub(crate) created_at: u64,
+}
+
+/// API tokens, keyed by name
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ApiTokenMap {
+    pub(crate) tokens: HashMap<String, ApiTokenData>,
+}
+
+pub(crate) fn read_api_tokens(storage_dir_path: &Path) -> Result<ApiTokenMap, String> {
+    let path = storage_dir_path.join(API_TOKENS_FNAME);
+    if !path.exists() {
+        return Ok(ApiTokenMap::default());
+    }
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&data).map_err(|e| e.to_string())
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::hash(token.as_bytes()).to_string()
+}
+
+impl AppState {
+    /// Issue a token with the given name, returning it. Only its hash is saved.
+    pub(crate) fn create_api_token(
+        &self,
+        name: String,
+        read_only: bool,
+    ) -> Result<String, APIError> {
+        let mut random_bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut random_bytes);
+        let token = hex_str(&random_bytes);
+        let mut api_tokens = self.get_api_tokens();
+        if api_tokens.tokens.contains_key(&name) {
+            return Err(APIError::InvalidName(s!(
+                "a token with this name already exists"
+            )));
+        }
+        api_tokens.tokens.insert(
+            name,
+            ApiTokenData {
+                token_hash: hash_token(&token),
+                read_only,
+                created_at: get_current_timestamp(),
+            },
+        );
+        self.save_api_tokens(&api_tokens)?;
+        Ok(token)
+    }
+
+    pub(crate) fn revoke_api_token(&self, name: &str) -> Result<(), APIError> {
+        let mut api_tokens = self.get_api_tokens();
+        if api_tokens.tokens.remove(name).is_none() {
+            return Err(APIError::UnknownApiToken);
+        }
+        self.save_api_tokens(&api_tokens)
+    }
+
+    fn save_api_tokens(&self, api_tokens: &ApiTokenMap) -> Result<(), APIError> {
+        let data = serde_json::to_vec(api_tokens).expect("valid tokens");
+        write_file_atomically(
+            &self.static_state.storage_dir_path.join(API_TOKENS_FNAME),
+            &data,
+        )?;
+        Ok(())
+    }
+
+    /// Whether the given token is read-only, None if it's unknown
+    fn api_token_read_only(&self, token: &str) -> Option<bool> {
+        let token_hash = hash_token(token);
+        self.get_api_tokens()
+            .tokens
+            .values()
+            .find(|t| t.token_hash == token_hash)
+            .map(|t| t.read_only)
+    }
+}
+
+/// Authorization middleware shared by all routes.
+///
+/// Requests carrying a bearer token are checked against its scope. Requests without one are
+/// refused once a token has been created, or always if tokens are required.
+pub(crate) async fn authorize(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, APIError> {
+    let path = match request.extensions().get::<MatchedPath>() {
+        Some(matched_path) => matched_path.as_str(),
+        None => request.uri().path(),
+    };
+    if PUBLIC_ROUTES.contains(&path) {
+        return Ok(next.run(request).await);
+    }
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .ok_or(APIError::InvalidApiToken)
+        })
+        .transpose()?;
+    match token {
+        Some(token) => {
+            let read_only = state
+                .api_token_read_only(token)
+                .ok_or(APIError::InvalidApiToken)?;
+            if read_only && !READ_ONLY_ROUTES.contains(&path) {
+                return Err(APIError::ReadOnlyApiToken);
+            }
+        }
+        None if state.static_state.require_api_token
+            || !state.get_api_tokens().tokens.is_empty() =>
+        {
+            return Err(APIError::MissingApiToken)
+        }
+        None => {}
+    }
+    Ok(next.run(request).await)
+}
@@ -1,28 +1,41 @@
+use amplify::s;
+use bitcoin::hashes::{sha256, Hash, HashEngine};
 use chacha20poly1305::aead::{generic_array::GenericArray, stream};
 use chacha20poly1305::{Key, KeyInit, XChaCha20Poly1305};
 use rand::{distributions::Alphanumeric, Rng};
 use scrypt::password_hash::{PasswordHasher, Salt};
 use scrypt::Scrypt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tempfile::TempDir;
 use typenum::consts::U32;
 use walkdir::WalkDir;
 use zip::write::SimpleFileOptions;
 
-use std::fs::{create_dir_all, read_to_string, remove_file, write, File};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{
+    canonicalize, create_dir_all, read, read_dir, read_to_string, remove_file, write, File,
+};
 use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
 
 use crate::error::APIError;
-use crate::utils::LOGS_DIR;
+use crate::utils::{hex_str, LDK_DIR, LOGS_DIR};
 
 const BACKUP_BUFFER_LEN_ENCRYPT: usize = 239; // 255 max, leaving 16 for the checksum
 const BACKUP_BUFFER_LEN_DECRYPT: usize = BACKUP_BUFFER_LEN_ENCRYPT + 16;
 const BACKUP_KEY_LENGTH: usize = 32;
 const BACKUP_NONCE_LENGTH: usize = 19;
-const BACKUP_VERSION: u8 = 1;
+const BACKUP_VERSION: u8 = 2;
+const BACKUP_VERSION_NO_MANIFEST: u8 = 1;
+const SCB_VERSION: u8 = 1;
+
+/// Manifest of the last backup, kept in the wallet directory and included in each backup archive
+const BACKUP_MANIFEST_FNAME: &str = "backup.manifest";
 
 struct BackupPaths {
     encrypted: PathBuf,
+    manifest: PathBuf,
     nonce: PathBuf,
     salt: PathBuf,
     tempdir: TempDir,
@@ -30,6 +43,23 @@ struct BackupPaths {
     zip: PathBuf,
 }
 
+/// Description of the wallet files at the time of a backup.
+///
+/// Incremental backups link to the backup they build upon via `parent_id`, forming a chain that
+/// starts from a full backup.
+///
+/// `storage_dir` is the absolute path of the backed up directory, used to re-root the paths
+/// stored in the node data when restoring to a different one.
+#[derive(Deserialize, Serialize)]
+struct BackupManifest {
+    backup_id: String,
+    parent_id: Option<String>,
+    files: BTreeMap<String, String>,
+    deleted: Vec<String>,
+    #[serde(default)]
+    storage_dir: Option<String>,
+}
+
 struct CypherSecrets {
     key: GenericArray<u8, U32>,
     nonce: [u8; BACKUP_NONCE_LENGTH],
@@ -40,11 +70,15 @@ struct CypherSecrets {
 ///
 /// Scrypt is used for hashing and xchacha20poly1305 is used for encryption. A random salt for
 /// hashing and a random nonce for encrypting are randomly generated and included in the final
-/// backup file, along with the backup version
+/// backup file, along with the backup version.
+///
+/// When `incremental` is set, only the files that changed since the previous backup are included,
+/// along with a manifest linking the backup to the previous one and listing the deleted files.
 pub(crate) fn do_backup(
     wallet_dir: &Path,
     backup_file: &Path,
     password: &str,
+    incremental: bool,
 ) -> Result<(), APIError> {
     // setup
     tracing::info!("starting backup...");
@@ -52,7 +86,7 @@ pub(crate) fn do_backup(
         Err(APIError::InvalidBackupPath)?;
     }
     let tmp_base_path = _get_parent_path(backup_file)?;
-    let files = _get_backup_paths(&tmp_base_path)?;
+    let files = _get_backup_paths(&tmp_base_path, "backup")?;
     let salt: String = rand::thread_rng()
         .sample_iter(&Alphanumeric)
         .take(BACKUP_KEY_LENGTH)
@@ -66,9 +100,58 @@ pub(crate) fn do_backup(
         .collect();
     tracing::debug!("using generated nonce: {}", &nonce);
 
+    // compare wallet files with the ones of the previous backup
+    let hashes = _hash_dir(wallet_dir)?;
+    let manifest_path = wallet_dir.join(BACKUP_MANIFEST_FNAME);
+    let (parent_id, selected, deleted) = if incremental {
+        if !manifest_path.exists() {
+            return Err(APIError::CannotCreateIncrementalBackup(s!(
+                "no previous backup found"
+            )));
+        }
+        let previous = _read_manifest(&read_to_string(&manifest_path)?)?;
+        let changed: HashSet<String> = hashes
+            .iter()
+            .filter(|(name, hash)| previous.files.get(*name) != Some(hash))
+            .map(|(name, _)| name.clone())
+            .collect();
+        let deleted: Vec<String> = previous
+            .files
+            .keys()
+            .filter(|name| !hashes.contains_key(*name))
+            .cloned()
+            .collect();
+        tracing::debug!(
+            "{} changed and {} deleted files since backup {}",
+            changed.len(),
+            deleted.len(),
+            previous.backup_id
+        );
+        (Some(previous.backup_id), changed, deleted)
+    } else {
+        (None, hashes.keys().cloned().collect(), vec![])
+    };
+    let mut backup_id_bytes = [0u8; 16];
+    rand::thread_rng().fill(&mut backup_id_bytes);
+    let manifest = BackupManifest {
+        backup_id: hex_str(&backup_id_bytes),
+        parent_id,
+        files: hashes,
+        deleted,
+        storage_dir: canonicalize(wallet_dir)?.to_str().map(|s| s.to_string()),
+    };
+    let manifest_json = serde_json::to_string(&manifest).map_err(|_| APIError::Unexpected)?;
+    write(&files.manifest, &manifest_json)?;
+
     // create zip archive of wallet data
     tracing::debug!("\nzipping {:?} to {:?}", &wallet_dir, &files.zip);
-    _zip_dir(wallet_dir, &files.zip)?;
+    _zip_dir(
+        wallet_dir,
+        &files.zip,
+        Some(&selected),
+        Some(&files.manifest),
+    )?;
+    remove_file(&files.manifest)?;
 
     // encrypt the backup file
     tracing::debug!("\nencrypting {:?} to {:?}", &files.zip, &files.encrypted);
@@ -79,62 +162,305 @@ pub(crate) fn do_backup(
     write(files.salt, salt)?;
     write(files.version, BACKUP_VERSION.to_string())?;
     tracing::debug!("\nzipping {:?} to {:?}", &files.tempdir, &backup_file);
-    _zip_dir(files.tempdir.path(), backup_file)?;
+    _zip_dir(files.tempdir.path(), backup_file, None, None)?;
+
+    // the next incremental backup will build upon this one
+    write(manifest_path, manifest_json)?;
 
-    tracing::info!("backup completed");
+    tracing::info!("backup {} completed", manifest.backup_id);
     Ok(())
 }
 
+/// Create a backup of the wallet as [`do_backup`] does, returning its content instead of writing
+/// it to a file
+pub(crate) fn export_backup(
+    wallet_dir: &Path,
+    password: &str,
+    incremental: bool,
+) -> Result<Vec<u8>, APIError> {
+    let tempdir = tempfile::tempdir()?;
+    let backup_file = tempdir.path().join("backup");
+    do_backup(wallet_dir, &backup_file, password, incremental)?;
+    Ok(read(backup_file)?)
+}
+
 /// Restore a backup from the given file and password to the provided target directory.
+///
+/// The incremental backups, if any, are applied in the given order on top of the full backup. The
+/// whole chain is validated before anything is written to the target directory.
 pub(crate) fn restore_backup(
     backup_path: &Path,
+    incremental_backup_paths: &[PathBuf],
     password: &str,
     target_dir: &Path,
 ) -> Result<(), APIError> {
     // setup
     tracing::info!("starting restore...");
+    let target_dir_path = PathBuf::from(&target_dir);
+
+    // decrypt all backups
+    let full = _decrypt_backup(backup_path, password)?;
+    let mut incrementals = vec![];
+    for incremental_backup_path in incremental_backup_paths {
+        incrementals.push(_decrypt_backup(incremental_backup_path, password)?);
+    }
+
+    // validate the backup chain
+    if let Some(manifest) = &full.1 {
+        if manifest.parent_id.is_some() {
+            return Err(APIError::InvalidBackupChain(s!(
+                "the first backup must be a full backup"
+            )));
+        }
+    }
+    let mut previous_id = full.1.as_ref().map(|m| m.backup_id.clone());
+    for (_, manifest) in &incrementals {
+        let manifest = manifest.as_ref().ok_or_else(|| {
+            APIError::InvalidBackupChain(s!("missing manifest in incremental backup"))
+        })?;
+        if manifest.parent_id.is_none() {
+            return Err(APIError::InvalidBackupChain(s!(
+                "only the first backup can be a full backup"
+            )));
+        }
+        if manifest.parent_id != previous_id {
+            return Err(APIError::InvalidBackupChain(format!(
+                "backup {} does not follow the previous one",
+                manifest.backup_id
+            )));
+        }
+        if manifest.deleted.iter().any(|name| !_is_relative(name)) {
+            return Err(APIError::InvalidBackupChain(format!(
+                "backup {} lists an invalid deleted file",
+                manifest.backup_id
+            )));
+        }
+        previous_id = Some(manifest.backup_id.clone());
+    }
+
+    // restore files
+    tracing::info!("unzipping {:?} to {:?}", &full.0.zip, &target_dir_path);
+    _unzip(&full.0.zip, &target_dir_path)?;
+    for (files, manifest) in &incrementals {
+        let manifest = manifest.as_ref().expect("validated chain");
+        tracing::info!(
+            "applying incremental backup {} to {:?}",
+            manifest.backup_id,
+            &target_dir_path
+        );
+        _unzip(&files.zip, &target_dir_path)?;
+        for name in &manifest.deleted {
+            let path = target_dir_path.join(name);
+            if path.is_file() {
+                tracing::debug!("removing deleted file {}", path.display());
+                remove_file(path)?;
+            }
+        }
+    }
+
+    // the last backup of the chain tells where the node data was
+    let storage_dir = full
+        .1
+        .iter()
+        .chain(incrementals.iter().filter_map(|(_, m)| m.as_ref()))
+        .last()
+        .and_then(|m| m.storage_dir.clone());
+    if let Some(storage_dir) = storage_dir {
+        _reroot_color_source(&storage_dir, &target_dir_path)?;
+    }
+
+    tracing::info!("restore completed");
+    Ok(())
+}
+
+/// Restore a backup as [`restore_backup`] does, from the content of the backup files
+pub(crate) fn import_backup(
+    backup: &[u8],
+    incremental_backups: &[Vec<u8>],
+    password: &str,
+    target_dir: &Path,
+) -> Result<(), APIError> {
+    let tempdir = tempfile::tempdir()?;
+    let backup_path = tempdir.path().join("backup");
+    write(&backup_path, backup)?;
+    let mut incremental_backup_paths = vec![];
+    for (i, incremental_backup) in incremental_backups.iter().enumerate() {
+        let incremental_backup_path = tempdir.path().join(format!("backup_{i}"));
+        write(&incremental_backup_path, incremental_backup)?;
+        incremental_backup_paths.push(incremental_backup_path);
+    }
+    restore_backup(
+        &backup_path,
+        &incremental_backup_paths,
+        password,
+        target_dir,
+    )
+}
+
+/// Write the given static channel backup to a file with the provided name, encrypted with the
+/// provided password in the same way as wallet backups
+pub(crate) fn do_scb_backup(
+    data: &[u8],
+    backup_file: &Path,
+    password: &str,
+) -> Result<(), APIError> {
+    if backup_file.exists() {
+        Err(APIError::InvalidBackupPath)?;
+    }
+    let tmp_base_path = _get_parent_path(backup_file)?;
+    let files = _get_backup_paths(&tmp_base_path, "scb")?;
+    let salt: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(BACKUP_KEY_LENGTH)
+        .map(char::from)
+        .collect();
+    let nonce: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(BACKUP_NONCE_LENGTH)
+        .map(char::from)
+        .collect();
+
+    write(&files.zip, data)?;
+    _encrypt_file(&files.zip, &files.encrypted, password, &salt, &nonce)?;
+    write(files.nonce, nonce)?;
+    write(files.salt, salt)?;
+    write(files.version, SCB_VERSION.to_string())?;
+    _zip_dir(files.tempdir.path(), backup_file, None, None)?;
+
+    tracing::info!("static channel backup completed");
+    Ok(())
+}
+
+/// Read a static channel backup from the given file, decrypting it with the provided password
+pub(crate) fn read_scb_backup(backup_path: &Path, password: &str) -> Result<Vec<u8>, APIError> {
+    let tmp_base_path = _get_parent_path(backup_path)?;
+    let files = _get_backup_paths(&tmp_base_path, "scb")?;
+    _unzip(&PathBuf::from(backup_path), files.tempdir.path())?;
+    if !files.version.exists() {
+        return Err(APIError::InvalidScb(s!(
+            "file is not a static channel backup"
+        )));
+    }
+    let version = read_to_string(&files.version)?
+        .parse::<u8>()
+        .map_err(|_| APIError::Unexpected)?;
+    if version != SCB_VERSION {
+        return Err(APIError::UnsupportedBackupVersion {
+            version: version.to_string(),
+        });
+    }
+    let nonce = read_to_string(&files.nonce)?;
+    let salt = read_to_string(&files.salt)?;
+    _decrypt_file(&files.encrypted, &files.zip, password, &salt, &nonce)?;
+    Ok(std::fs::read(&files.zip)?)
+}
+
+/// Unpack and decrypt the given backup file, returning the paths of the decrypted data along with
+/// the backup manifest, which is missing for backups made before manifests were introduced
+fn _decrypt_backup(
+    backup_path: &Path,
+    password: &str,
+) -> Result<(BackupPaths, Option<BackupManifest>), APIError> {
     let backup_file = PathBuf::from(backup_path);
     let tmp_base_path = _get_parent_path(&backup_file)?;
-    let files = _get_backup_paths(&tmp_base_path)?;
-    let target_dir_path = PathBuf::from(&target_dir);
+    let files = _get_backup_paths(&tmp_base_path, "backup")?;
 
     // unpack given zip file and retrieve backup data
     tracing::info!("unzipping {:?}", backup_file);
     _unzip(&backup_file, &PathBuf::from(files.tempdir.path()))?;
-    let nonce = read_to_string(files.nonce)?;
+    let nonce = read_to_string(&files.nonce)?;
     tracing::debug!("using retrieved nonce: {}", &nonce);
-    let salt = read_to_string(files.salt)?;
+    let salt = read_to_string(&files.salt)?;
     tracing::debug!("using retrieved salt: {}", &salt);
-    let version = read_to_string(files.version)?
+    let version = read_to_string(&files.version)?
         .parse::<u8>()
         .map_err(|_| APIError::Unexpected)?;
     tracing::debug!("retrieved version: {}", &version);
-    if version != BACKUP_VERSION {
+    if !(BACKUP_VERSION_NO_MANIFEST..=BACKUP_VERSION).contains(&version) {
         return Err(APIError::UnsupportedBackupVersion {
             version: version.to_string(),
         });
     }
 
-    // decrypt backup and restore files
+    // decrypt backup and read its manifest
     tracing::info!("decrypting {:?} to {:?}", files.encrypted, files.zip);
     _decrypt_file(&files.encrypted, &files.zip, password, &salt, &nonce)?;
-    tracing::info!("unzipping {:?} to {:?}", &files.zip, &target_dir_path);
-    _unzip(&files.zip, &target_dir_path)?;
+    let manifest = if version == BACKUP_VERSION_NO_MANIFEST {
+        None
+    } else {
+        let file = File::open(&files.zip)?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|_| APIError::Unexpected)?;
+        let mut manifest_json = String::new();
+        archive
+            .by_name(BACKUP_MANIFEST_FNAME)
+            .map_err(|_| APIError::Unexpected)?
+            .read_to_string(&mut manifest_json)?;
+        Some(_read_manifest(&manifest_json)?)
+    };
+
+    Ok((files, manifest))
+}
 
-    tracing::info!("restore completed");
+/// Re-root the absolute paths in the RGB files of the LDK data dir (the color source) from the
+/// directory the backup was made from to the one it has been restored to.
+///
+/// These files are JSON, the binary ones (including the encrypted entries) are left untouched.
+fn _reroot_color_source(origin_dir: &str, target_dir: &Path) -> Result<(), APIError> {
+    let target_dir = canonicalize(target_dir)?;
+    let target_dir_str = target_dir.to_str().ok_or_else(|| APIError::Unexpected)?;
+    let color_source = target_dir.join(LDK_DIR);
+    if origin_dir == target_dir_str || !color_source.is_dir() {
+        return Ok(());
+    }
+    tracing::info!("re-rooting paths from {origin_dir} to {target_dir_str}");
+    for entry in read_dir(&color_source)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Ok(mut value) = serde_json::from_slice::<Value>(&read(&path)?) else {
+            continue;
+        };
+        if _reroot_value(&mut value, origin_dir, target_dir_str) {
+            tracing::debug!("re-rooted paths in {}", path.display());
+            let data = serde_json::to_vec(&value).map_err(|_| APIError::Unexpected)?;
+            write(&path, data)?;
+        }
+    }
     Ok(())
 }
 
-fn _get_backup_paths(tmp_base_path: &Path) -> Result<BackupPaths, APIError> {
+fn _reroot_value(value: &mut Value, origin_dir: &str, target_dir: &str) -> bool {
+    match value {
+        Value::String(s) => match s.strip_prefix(origin_dir) {
+            Some(rest) if rest.is_empty() || rest.starts_with(MAIN_SEPARATOR) => {
+                *s = format!("{target_dir}{rest}");
+                true
+            }
+            _ => false,
+        },
+        Value::Array(values) => values.iter_mut().fold(false, |rerooted, v| {
+            _reroot_value(v, origin_dir, target_dir) || rerooted
+        }),
+        Value::Object(map) => map.values_mut().fold(false, |rerooted, v| {
+            _reroot_value(v, origin_dir, target_dir) || rerooted
+        }),
+        _ => false,
+    }
+}
+
+fn _get_backup_paths(tmp_base_path: &Path, prefix: &str) -> Result<BackupPaths, APIError> {
     create_dir_all(tmp_base_path)?;
     let tempdir = tempfile::tempdir_in(tmp_base_path)?;
-    let encrypted = tempdir.path().join("backup.enc");
-    let nonce = tempdir.path().join("backup.nonce");
-    let salt = tempdir.path().join("backup.salt");
-    let version = tempdir.path().join("backup.version");
-    let zip = tempdir.path().join("backup.zip");
+    let encrypted = tempdir.path().join(format!("{prefix}.enc"));
+    let manifest = tempdir.path().join(BACKUP_MANIFEST_FNAME);
+    let nonce = tempdir.path().join(format!("{prefix}.nonce"));
+    let salt = tempdir.path().join(format!("{prefix}.salt"));
+    let version = tempdir.path().join(format!("{prefix}.version"));
+    let zip = tempdir.path().join(format!("{prefix}.zip"));
     Ok(BackupPaths {
         encrypted,
+        manifest,
         nonce,
         salt,
         tempdir,
@@ -151,7 +477,58 @@ fn _get_parent_path(file: &Path) -> Result<PathBuf, APIError> {
     }
 }
 
-fn _zip_dir(path_in: &Path, path_out: &Path) -> Result<(), APIError> {
+fn _hash_dir(path_in: &Path) -> Result<BTreeMap<String, String>, APIError> {
+    let mut hashes = BTreeMap::new();
+    let mut buffer = [0u8; 4096];
+    let entry_iterator = WalkDir::new(path_in).into_iter().filter_map(|e| e.ok());
+    for entry in entry_iterator {
+        let path = entry.path();
+        if !path.is_file() || path.ends_with("log") {
+            continue;
+        }
+        let name = path
+            .strip_prefix(path_in)
+            .map_err(|_| APIError::Unexpected)?;
+        let name_str = name.to_str().ok_or_else(|| APIError::Unexpected)?;
+        if name_str == BACKUP_MANIFEST_FNAME {
+            continue;
+        }
+        let mut engine = sha256::Hash::engine();
+        let mut f = File::open(path)?;
+        loop {
+            let read_count = f.read(&mut buffer)?;
+            if read_count == 0 {
+                break;
+            }
+            engine.input(&buffer[..read_count]);
+        }
+        let hash = sha256::Hash::from_engine(engine);
+        hashes.insert(name_str.to_string(), hex_str(&hash.to_byte_array()));
+    }
+    Ok(hashes)
+}
+
+fn _is_relative(name: &str) -> bool {
+    Path::new(name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn _read_manifest(manifest_json: &str) -> Result<BackupManifest, APIError> {
+    serde_json::from_str(manifest_json)
+        .map_err(|_| APIError::InvalidBackupChain(s!("invalid backup manifest")))
+}
+
+/// Zip the given directory, skipping logs.
+///
+/// When `selected` is given, only the listed files (relative to the directory) are added, while
+/// `extra` is added at the root of the archive.
+fn _zip_dir(
+    path_in: &Path,
+    path_out: &Path,
+    selected: Option<&HashSet<String>>,
+    extra: Option<&Path>,
+) -> Result<(), APIError> {
     // setup
     let writer = File::create(path_out)?;
     let mut zip = zip::ZipWriter::new(writer);
@@ -170,6 +547,11 @@ fn _zip_dir(path_in: &Path, path_out: &Path) -> Result<(), APIError> {
             if path.ends_with("log") {
                 continue;
             }
+            if let Some(selected) = selected {
+                if !selected.contains(name_str) {
+                    continue;
+                }
+            }
             tracing::debug!("adding file {path:?} as {name:?}");
             zip.start_file(name_str, options)
                 .map_err(|_| APIError::Unexpected)?;
@@ -192,6 +574,17 @@ fn _zip_dir(path_in: &Path, path_out: &Path) -> Result<(), APIError> {
         }
     }
 
+    if let Some(extra) = extra {
+        let name_str = extra
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| APIError::Unexpected)?;
+        tracing::debug!("adding file {extra:?} as {name_str:?}");
+        zip.start_file(name_str, options)
+            .map_err(|_| APIError::Unexpected)?;
+        zip.write_all(&std::fs::read(extra)?)?;
+    }
+
     // finalize
     let mut file = zip.finish().map_err(|_| APIError::Unexpected)?;
     file.flush()?;
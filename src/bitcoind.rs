@@ -1,5 +1,7 @@
 use base64::{engine::general_purpose, Engine as _};
-use bitcoin::blockdata::transaction::Transaction;
+use bitcoin::blockdata::block::Block;
+use bitcoin::blockdata::script::ScriptBuf;
+use bitcoin::blockdata::transaction::{OutPoint, Transaction};
 use bitcoin::consensus::encode;
 use bitcoin::hash_types::{BlockHash, Txid};
 use lightning::chain::chaininterface::{BroadcasterInterface, ConfirmationTarget, FeeEstimator};
@@ -17,12 +19,14 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::disk::FilesystemLogger;
+use crate::utils::hex_str_to_vec;
 
 pub struct BitcoindClient {
     pub(crate) bitcoind_rpc_client: Arc<RpcClient>,
     fees: Arc<HashMap<ConfirmationTarget, AtomicU32>>,
     handle: tokio::runtime::Handle,
     logger: Arc<FilesystemLogger>,
+    closing_min_feerate: u32,
 }
 
 impl BlockSource for BitcoindClient {
@@ -107,6 +111,53 @@ impl TryInto<FeeResponse> for JsonResponse {
     }
 }
 
+/// Unspent output as returned by `gettxout`
+pub struct TxOutInfo {
+    pub value_sat: u64,
+    pub script_pubkey: ScriptBuf,
+    pub confirmations: u32,
+}
+
+pub struct TxOutResponse(pub Option<TxOutInfo>);
+
+impl TryInto<TxOutResponse> for JsonResponse {
+    type Error = std::io::Error;
+    fn try_into(self) -> std::io::Result<TxOutResponse> {
+        // null means the output is spent or doesn't exist
+        if self.0.is_null() {
+            return Ok(TxOutResponse(None));
+        }
+        let invalid_data =
+            || std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid gettxout response");
+        let value_btc = self.0["value"].as_f64().ok_or_else(invalid_data)?;
+        let script_hex = self.0["scriptPubKey"]["hex"]
+            .as_str()
+            .ok_or_else(invalid_data)?;
+        let script_pubkey =
+            ScriptBuf::from_bytes(hex_str_to_vec(script_hex).ok_or_else(invalid_data)?);
+        Ok(TxOutResponse(Some(TxOutInfo {
+            value_sat: (value_btc * 100_000_000.0).round() as u64,
+            script_pubkey,
+            confirmations: self.0["confirmations"].as_u64().unwrap_or(0) as u32,
+        })))
+    }
+}
+
+pub struct HexResponse(pub String);
+
+impl TryInto<HexResponse> for JsonResponse {
+    type Error = std::io::Error;
+    fn try_into(self) -> std::io::Result<HexResponse> {
+        match self.0.as_str() {
+            Some(hex) => Ok(HexResponse(hex.to_string())),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "expected a hex string",
+            )),
+        }
+    }
+}
+
 /// The minimum feerate we are allowed to send, as specify by LDK.
 const MIN_FEERATE: u32 = 253;
 
@@ -118,6 +169,7 @@ impl BitcoindClient {
         rpc_password: String,
         handle: tokio::runtime::Handle,
         logger: Arc<FilesystemLogger>,
+        min_closing_fee_rate: f32,
     ) -> std::io::Result<Self> {
         let http_endpoint = HttpEndpoint::for_host(host.clone()).with_port(port);
         let rpc_credentials = general_purpose::STANDARD.encode(format!(
@@ -165,6 +217,8 @@ impl BitcoindClient {
             fees: Arc::new(fees),
             handle: handle.clone(),
             logger,
+            // 1 sat/vB = 250 sat/kw
+            closing_min_feerate: std::cmp::max((min_closing_fee_rate * 250.0) as u32, MIN_FEERATE),
         };
         BitcoindClient::poll_for_fee_estimates(
             client.fees.clone(),
@@ -267,20 +321,125 @@ impl BitcoindClient {
         });
     }
 
+    /// Estimate the fee rate (in sat/kw) needed to confirm within the provided number of blocks
+    pub async fn estimate_fee_rate(&self, conf_target: u16) -> Option<u32> {
+        let conf_target = serde_json::json!(conf_target);
+        let estimate_mode = serde_json::json!("ECONOMICAL");
+        let resp = self
+            .bitcoind_rpc_client
+            .call_method::<FeeResponse>("estimatesmartfee", &[conf_target, estimate_mode])
+            .await
+            .ok()?;
+        resp.feerate_sat_per_kw
+            .map(|feerate| std::cmp::max(feerate, MIN_FEERATE))
+    }
+
     pub async fn get_blockchain_info(&self) -> BlockchainInfo {
         self.bitcoind_rpc_client
             .call_method::<BlockchainInfo>("getblockchaininfo", &[])
             .await
             .unwrap()
     }
+
+    /// Get the raw transaction with the provided txid, mined at the provided height, along with
+    /// the merkle proof of its inclusion in the block
+    pub async fn get_tx_with_proof(
+        &self,
+        txid: Txid,
+        height: u32,
+    ) -> std::io::Result<(String, String)> {
+        let block_hash = self
+            .bitcoind_rpc_client
+            .call_method::<HexResponse>("getblockhash", &[serde_json::json!(height)])
+            .await?
+            .0;
+        let tx_hex = self
+            .bitcoind_rpc_client
+            .call_method::<HexResponse>(
+                "getrawtransaction",
+                &[
+                    serde_json::json!(txid.to_string()),
+                    serde_json::json!(false),
+                    serde_json::json!(block_hash),
+                ],
+            )
+            .await?
+            .0;
+        let proof_hex = self
+            .bitcoind_rpc_client
+            .call_method::<HexResponse>(
+                "gettxoutproof",
+                &[
+                    serde_json::json!([txid.to_string()]),
+                    serde_json::json!(block_hash),
+                ],
+            )
+            .await?
+            .0;
+        Ok((tx_hex, proof_hex))
+    }
+
+    /// Get the given output if it's unspent, also looking at the mempool
+    pub async fn get_tx_out(&self, outpoint: &OutPoint) -> std::io::Result<Option<TxOutInfo>> {
+        let resp = self
+            .bitcoind_rpc_client
+            .call_method::<TxOutResponse>(
+                "gettxout",
+                &[
+                    serde_json::json!(outpoint.txid.to_string()),
+                    serde_json::json!(outpoint.vout),
+                    serde_json::json!(true),
+                ],
+            )
+            .await?;
+        Ok(resp.0)
+    }
+
+    /// Get the block at the given height
+    pub async fn get_block_at_height(&self, height: u32) -> std::io::Result<Block> {
+        let block_hash = self
+            .bitcoind_rpc_client
+            .call_method::<HexResponse>("getblockhash", &[serde_json::json!(height)])
+            .await?
+            .0;
+        let block_hex = self
+            .bitcoind_rpc_client
+            .call_method::<HexResponse>(
+                "getblock",
+                &[serde_json::json!(block_hash), serde_json::json!(0)],
+            )
+            .await?
+            .0;
+        hex_str_to_vec(&block_hex)
+            .and_then(|bytes| encode::deserialize(&bytes).ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid block"))
+    }
+
+    /// Broadcast the given transaction, unlike `broadcast_transactions` returning any error
+    pub async fn send_raw_transaction(&self, tx: &Transaction) -> std::io::Result<Txid> {
+        self.bitcoind_rpc_client
+            .call_method::<Txid>(
+                "sendrawtransaction",
+                &[serde_json::json!(encode::serialize_hex(tx))],
+            )
+            .await
+    }
 }
 
 impl FeeEstimator for BitcoindClient {
     fn get_est_sat_per_1000_weight(&self, confirmation_target: ConfirmationTarget) -> u32 {
-        self.fees
+        let feerate = self
+            .fees
             .get(&confirmation_target)
             .unwrap()
-            .load(Ordering::Acquire)
+            .load(Ordering::Acquire);
+        match confirmation_target {
+            // lower bound of the fee range accepted during cooperative close negotiation
+            ConfirmationTarget::ChannelCloseMinimum => {
+                std::cmp::max(feerate, self.closing_min_feerate)
+            }
+            _ => feerate,
+        }
     }
 }
 
@@ -0,0 +1,148 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::ChannelId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::routes::{do_open_channel, ChannelConversionStatus, OpenChannelRequest};
+use crate::utils::{check_channel_id, get_current_timestamp, AppState};
+
+/// Time given to reopen a converted channel, e.g. waiting for the funds of the closed channel to
+/// confirm, after which the conversion fails
+pub(crate) const CHANNEL_CONVERSION_TIMEOUT_SECS: u64 = 86400;
+
+const CHANNEL_CONVERSION_TICK_SECS: u64 = 30;
+
+/// Conversion of one of our private channels to an announced one.
+///
+/// The announcement preference is negotiated when opening a channel and cannot be changed
+/// afterwards, so the channel is closed cooperatively and reopened as announced with the same
+/// peer, capacity, asset and fees, once the funds of the closed channel are back in the wallet.
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelConversionData {
+    pub(crate) peer_pubkey: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) fee_base_msat: Option<u32>,
+    pub(crate) fee_proportional_millionths: Option<u32>,
+    pub(crate) close_address: Option<String>,
+    pub(crate) status: ChannelConversionStatus,
+    pub(crate) requested_at: u64,
+    pub(crate) completed_at: Option<u64>,
+    /// Temporary ID of the announced channel, once opened
+    pub(crate) temporary_channel_id: Option<ChannelId>,
+    /// Why the last attempt to reopen the channel failed
+    pub(crate) error: Option<String>,
+}
+
+impl_writeable_tlv_based!(ChannelConversionData, {
+    (0, peer_pubkey, required),
+    (2, capacity_sat, required),
+    (4, asset_id, option),
+    (6, asset_amount, option),
+    (8, fee_base_msat, option),
+    (10, fee_proportional_millionths, option),
+    (12, close_address, option),
+    (14, status, required),
+    (16, requested_at, required),
+    (18, completed_at, option),
+    (20, temporary_channel_id, option),
+    (22, error, option),
+});
+
+/// Channel conversions, keyed by the ID of the channel being converted
+pub(crate) struct ChannelConversionMap {
+    pub(crate) conversions: HashMap<ChannelId, ChannelConversionData>,
+}
+
+impl_writeable_tlv_based!(ChannelConversionMap, {
+    (0, conversions, required),
+});
+
+/// Regularly try to reopen the channels closed to be converted, until the node is locked
+pub(crate) async fn run_channel_conversions(
+    app_state: Arc<AppState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(CHANNEL_CONVERSION_TICK_SECS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let unlocked_state = match app_state.check_unlocked().await {
+            Ok(unlocked_state) => unlocked_state.clone().unwrap(),
+            Err(_) => continue,
+        };
+
+        for (channel_id, conversion) in unlocked_state
+            .channel_conversions()
+            .into_iter()
+            .filter(|(_, c)| c.status == ChannelConversionStatus::Reopening)
+        {
+            let res = reopen_channel(app_state.clone(), &conversion).await;
+            let update = unlocked_state.update_channel_conversion(&channel_id, |c| match res {
+                Ok(temporary_channel_id) => {
+                    tracing::info!(
+                        "EVENT: reopened channel {channel_id} as announced channel \
+                        {temporary_channel_id}"
+                    );
+                    c.status = ChannelConversionStatus::Completed;
+                    c.completed_at = Some(get_current_timestamp());
+                    c.temporary_channel_id = Some(temporary_channel_id);
+                    c.error = None;
+                }
+                Err(error) => {
+                    // the funds of the closed channel are usually not spendable yet
+                    if c.requested_at + CHANNEL_CONVERSION_TIMEOUT_SECS <= get_current_timestamp() {
+                        tracing::error!("Failed to reopen channel {channel_id}: {error}");
+                        c.status = ChannelConversionStatus::Failed;
+                        c.completed_at = Some(get_current_timestamp());
+                    } else {
+                        tracing::debug!("Cannot reopen channel {channel_id} yet: {error}");
+                    }
+                    c.error = Some(error);
+                }
+            });
+            if let Err(e) = update {
+                tracing::error!("Failed to update the conversion of channel {channel_id}: {e}");
+            }
+        }
+    }
+}
+
+/// Open the announced channel replacing the given closed one, returning its temporary ID
+async fn reopen_channel(
+    app_state: Arc<AppState>,
+    conversion: &ChannelConversionData,
+) -> Result<ChannelId, String> {
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: conversion.peer_pubkey.to_string(),
+        capacity_sat: conversion.capacity_sat,
+        push_msat: 0,
+        asset_amount: conversion.asset_amount,
+        asset_id: conversion.asset_id.clone(),
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: conversion.fee_base_msat,
+        fee_proportional_millionths: conversion.fee_proportional_millionths,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: conversion.close_address.clone(),
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = do_open_channel(app_state, payload, false)
+        .await
+        .map_err(|e| e.to_string())?;
+    check_channel_id(&res.temporary_channel_id).map_err(|e| e.to_string())
+}
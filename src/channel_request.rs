@@ -0,0 +1,64 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::wire::Type;
+use rgb_lib::ContractId;
+use std::collections::HashMap;
+
+use crate::routes::ChannelRequestStatus;
+
+/// Type of the custom message carrying a channel request, odd so that it can be ignored
+pub(crate) const CHANNEL_REQUEST_MESSAGE_TYPE: u16 = 32801;
+
+/// Max number of pending channel requests, further requests are dropped until some are handled
+pub(crate) const MAX_PENDING_CHANNEL_REQUESTS: usize = 100;
+
+/// Request sent by a peer asking us to open a channel to it
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelRequestMessage {
+    pub(crate) capacity_sat: u64,
+    pub(crate) push_msat: u64,
+    pub(crate) asset_id: Option<ContractId>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) fee_sat: u64,
+}
+
+impl_writeable_tlv_based!(ChannelRequestMessage, {
+    (0, capacity_sat, required),
+    (2, push_msat, required),
+    (4, asset_id, option),
+    (6, asset_amount, option),
+    (8, fee_sat, required),
+});
+
+impl Type for ChannelRequestMessage {
+    fn type_id(&self) -> u16 {
+        CHANNEL_REQUEST_MESSAGE_TYPE
+    }
+}
+
+/// A channel request received from a peer, waiting for the operator to handle it
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelRequestData {
+    pub(crate) peer_pubkey: PublicKey,
+    pub(crate) request: ChannelRequestMessage,
+    pub(crate) status: ChannelRequestStatus,
+    pub(crate) received_at: u64,
+    pub(crate) temporary_channel_id: Option<String>,
+}
+
+impl_writeable_tlv_based!(ChannelRequestData, {
+    (0, peer_pubkey, required),
+    (2, request, required),
+    (4, status, required),
+    (6, received_at, required),
+    (8, temporary_channel_id, option),
+});
+
+/// Channel requests received from peers, keyed by request ID
+pub(crate) struct ChannelRequestMap {
+    pub(crate) requests: HashMap<String, ChannelRequestData>,
+}
+
+impl_writeable_tlv_based!(ChannelRequestMap, {
+    (0, requests, required),
+});
@@ -0,0 +1,59 @@
+use amplify::s;
+use rgb_lib::{AssetSchema as RgbLibAssetSchema, ConsignmentExt, FileContent, RgbTransfer};
+use std::path::Path;
+
+use crate::error::APIError;
+use crate::routes::{ConsignmentTransition, ConsignmentValidation, InspectedConsignment};
+
+pub(crate) fn load_consignment(path: &Path) -> Result<RgbTransfer, APIError> {
+    RgbTransfer::load_file(path).map_err(|e| {
+        tracing::error!("cannot parse consignment {}: {e}", path.display());
+        APIError::InvalidConsignment(s!("cannot parse the file"))
+    })
+}
+
+/// Describe a consignment: its contract and the state transitions it carries, along with the
+/// fungible amounts that are revealed in it (the ones concealed to us are only counted)
+pub(crate) fn describe_consignment(
+    consignment: &RgbTransfer,
+    txid: Option<String>,
+    recipient_id: Option<String>,
+    validation: ConsignmentValidation,
+) -> Result<InspectedConsignment, APIError> {
+    let schema = RgbLibAssetSchema::from_schema_id(consignment.schema_id().to_string())
+        .map_err(|_| APIError::InvalidConsignment(s!("unsupported schema")))?;
+
+    let mut transitions = vec![];
+    for bundled_witness in consignment.bundled_witnesses() {
+        let witness_id = bundled_witness.witness_id().to_string();
+        for bundle in bundled_witness.anchored_bundles.bundles() {
+            for (opid, transition) in bundle.known_transitions.iter() {
+                let mut amounts = vec![];
+                let mut concealed_amounts = 0;
+                for assignments in transition.assignments.values() {
+                    for assignment in assignments.as_fungible() {
+                        match assignment.as_revealed_state() {
+                            Some(state) => amounts.push(state.value.as_u64()),
+                            None => concealed_amounts += 1,
+                        }
+                    }
+                }
+                transitions.push(ConsignmentTransition {
+                    opid: opid.to_string(),
+                    witness_id: witness_id.clone(),
+                    amounts,
+                    concealed_amounts,
+                });
+            }
+        }
+    }
+
+    Ok(InspectedConsignment {
+        asset_id: consignment.contract_id().to_string(),
+        schema: schema.into(),
+        txid,
+        recipient_id,
+        transitions,
+        validation,
+    })
+}
@@ -0,0 +1,209 @@
+use amplify::s;
+use axum::{
+    extract::{Path as UrlPath, State},
+    Json,
+};
+use axum_extra::extract::WithRejection;
+use lightning::ln::ChannelId;
+use lightning::rgb_utils::{get_rgb_channel_info_path, RgbPaymentInfo, TransferInfo};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::disk::read_rgb_data_file;
+use crate::encryption::StoreCipher;
+use crate::error::APIError;
+use crate::routes::ChannelShutdownState;
+use crate::utils::{hex_str, hex_str_to_vec, AppState};
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelMonitorState {
+    pub(crate) latest_update_id: u64,
+    pub(crate) pending_updates: usize,
+    pub(crate) best_block_height: u32,
+    pub(crate) claimable_balances: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelStateResponse {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) funding_txo: Option<String>,
+    pub(crate) short_channel_id: Option<u64>,
+    pub(crate) is_outbound: bool,
+    pub(crate) is_channel_ready: bool,
+    pub(crate) is_usable: bool,
+    pub(crate) shutdown_state: Option<ChannelShutdownState>,
+    pub(crate) confirmations: Option<u32>,
+    pub(crate) capacity_sat: u64,
+    pub(crate) outbound_capacity_msat: u64,
+    pub(crate) inbound_capacity_msat: u64,
+    pub(crate) pending_inbound_htlcs: usize,
+    pub(crate) pending_outbound_htlcs: usize,
+    pub(crate) monitor: Option<ChannelMonitorState>,
+    pub(crate) rgb_info: Option<serde_json::Value>,
+    pub(crate) rgb_info_pending: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DecodeRgbInfoRequest {
+    pub(crate) kind: RgbInfoKind,
+    pub(crate) data: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DecodeRgbInfoResponse {
+    pub(crate) info: serde_json::Value,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EncodeRgbInfoRequest {
+    pub(crate) kind: RgbInfoKind,
+    pub(crate) info: serde_json::Value,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EncodeRgbInfoResponse {
+    pub(crate) data: String,
+}
+
+/// The RGB structures stored as files under the color source directory
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum RgbInfoKind {
+    PaymentInfo,
+    TransferInfo,
+}
+
+/// Read an RGB channel info file, if present, as it is stored
+fn read_channel_info(
+    channel_id: &str,
+    ldk_data_dir: &Path,
+    pending: bool,
+    cipher: Option<&StoreCipher>,
+) -> Result<Option<serde_json::Value>, APIError> {
+    let path = get_rgb_channel_info_path(channel_id, ldk_data_dir, pending);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = read_rgb_data_file(&path, cipher)?;
+    let info =
+        serde_json::from_slice(&data).map_err(|e| APIError::InvalidRgbInfo(e.to_string()))?;
+    Ok(Some(info))
+}
+
+fn decode_info<T: DeserializeOwned + Serialize>(
+    data: &[u8],
+) -> Result<serde_json::Value, APIError> {
+    let info: T =
+        serde_json::from_slice(data).map_err(|e| APIError::InvalidRgbInfo(e.to_string()))?;
+    serde_json::to_value(info).map_err(|e| APIError::InvalidRgbInfo(e.to_string()))
+}
+
+fn encode_info<T: DeserializeOwned + Serialize>(
+    info: serde_json::Value,
+) -> Result<Vec<u8>, APIError> {
+    let info: T =
+        serde_json::from_value(info).map_err(|e| APIError::InvalidRgbInfo(e.to_string()))?;
+    // same serialization used by rgb_utils when writing the info files
+    let serialized = serde_json::to_string(&info).expect("valid info");
+    Ok(serialized.into_bytes())
+}
+
+pub(crate) async fn channel_state(
+    State(state): State<Arc<AppState>>,
+    UrlPath(channel_id): UrlPath<String>,
+) -> Result<Json<ChannelStateResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let channel_id_vec = hex_str_to_vec(&channel_id);
+    if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
+        return Err(APIError::InvalidChannelID);
+    }
+    let mut channel_id_bytes = [0; 32];
+    channel_id_bytes.copy_from_slice(&channel_id_vec.unwrap());
+    let channel_id = ChannelId(channel_id_bytes);
+
+    let chan_info = unlocked_state
+        .channel_manager
+        .list_channels()
+        .into_iter()
+        .find(|c| c.channel_id == channel_id)
+        .ok_or(APIError::UnknownChannelId)?;
+
+    // commitment numbers are not exposed by LDK, the latest update ID tracks the monitor progress
+    let monitor = chan_info.funding_txo.and_then(|funding_txo| {
+        let monitor = unlocked_state.chain_monitor.get_monitor(funding_txo).ok()?;
+        let pending_updates = unlocked_state
+            .chain_monitor
+            .list_pending_monitor_updates()
+            .get(&funding_txo)
+            .map(|u| u.len())
+            .unwrap_or(0);
+        Some(ChannelMonitorState {
+            latest_update_id: monitor.get_latest_update_id(),
+            pending_updates,
+            best_block_height: monitor.current_best_block().height,
+            claimable_balances: monitor
+                .get_claimable_balances()
+                .iter()
+                .map(|b| format!("{b:?}"))
+                .collect(),
+        })
+    });
+
+    let channel_id_str = hex_str(&channel_id.0);
+    let ldk_data_dir = &state.static_state.ldk_data_dir;
+    let cipher = unlocked_state.kv_store.cipher();
+
+    Ok(Json(ChannelStateResponse {
+        rgb_info: read_channel_info(&channel_id_str, ldk_data_dir, false, cipher)?,
+        rgb_info_pending: read_channel_info(&channel_id_str, ldk_data_dir, true, cipher)?,
+        channel_id: channel_id_str,
+        peer_pubkey: chan_info.counterparty.node_id.to_string(),
+        funding_txo: chan_info
+            .funding_txo
+            .map(|o| format!("{}:{}", o.txid, o.index)),
+        short_channel_id: chan_info.short_channel_id,
+        is_outbound: chan_info.is_outbound,
+        is_channel_ready: chan_info.is_channel_ready,
+        is_usable: chan_info.is_usable,
+        shutdown_state: chan_info.channel_shutdown_state.map(|s| s.into()),
+        confirmations: chan_info.confirmations,
+        capacity_sat: chan_info.channel_value_satoshis,
+        outbound_capacity_msat: chan_info.outbound_capacity_msat,
+        inbound_capacity_msat: chan_info.inbound_capacity_msat,
+        pending_inbound_htlcs: chan_info.pending_inbound_htlcs.len(),
+        pending_outbound_htlcs: chan_info.pending_outbound_htlcs.len(),
+        monitor,
+    }))
+}
+
+pub(crate) async fn decode_rgb_info(
+    State(_state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodeRgbInfoRequest>, APIError>,
+) -> Result<Json<DecodeRgbInfoResponse>, APIError> {
+    let data = hex_str_to_vec(&payload.data).ok_or(APIError::InvalidRgbInfo(s!(
+        "data is not a valid hex string"
+    )))?;
+
+    let info = match payload.kind {
+        RgbInfoKind::PaymentInfo => decode_info::<RgbPaymentInfo>(&data)?,
+        RgbInfoKind::TransferInfo => decode_info::<TransferInfo>(&data)?,
+    };
+
+    Ok(Json(DecodeRgbInfoResponse { info }))
+}
+
+pub(crate) async fn encode_rgb_info(
+    State(_state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<EncodeRgbInfoRequest>, APIError>,
+) -> Result<Json<EncodeRgbInfoResponse>, APIError> {
+    let data = match payload.kind {
+        RgbInfoKind::PaymentInfo => encode_info::<RgbPaymentInfo>(payload.info)?,
+        RgbInfoKind::TransferInfo => encode_info::<TransferInfo>(payload.info)?,
+    };
+
+    Ok(Json(EncodeRgbInfoResponse {
+        data: hex_str(&data),
+    }))
+}
@@ -1,35 +1,111 @@
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::Network;
 use chrono::Utc;
+use lightning::ln::channelmanager::PaymentId;
+use lightning::ln::msgs::SocketAddress;
+use lightning::ln::PaymentHash;
+use lightning::rgb_utils::get_rgb_channel_info_path;
 use lightning::routing::scoring::{ProbabilisticScorer, ProbabilisticScoringDecayParameters};
 use lightning::util::logger::{Logger, Record};
-use lightning::util::ser::{Readable, ReadableArgs, Writer};
+use lightning::util::persist::KVStore;
+use lightning::util::ser::{Readable, ReadableArgs, Writeable, Writer};
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::net::SocketAddr;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::channel_conversion::ChannelConversionMap;
+use crate::channel_request::ChannelRequestMap;
+use crate::encryption::{
+    finish_storage_migration, is_encrypted, is_storage_migration_pending, StoreCipher,
+    STORAGE_KEY_FNAME, STORAGE_MIGRATION_FNAME,
+};
 use crate::error::APIError;
+use crate::fee_order::FeeOrderMap;
+use crate::fee_report::FeeReportMap;
+use crate::forwarding_history::ForwardingHistory;
+use crate::journal::STATE_JOURNAL_FNAME;
+use crate::kv_store::NodeStore;
 use crate::ldk::{
-    ChannelIdsMap, InboundPaymentInfoStorage, NetworkGraph, OutboundPaymentInfoStorage,
-    OutputSpenderTxes, SwapMap,
+    AssetHtlcLimitMap, ChannelClosureMap, ChannelIdsMap, ChannelTransferMap, CloseAddressMap,
+    InboundPaymentInfoStorage, LnurlWithdrawMap, NetworkGraph, OutboundPaymentInfoStorage,
+    OutputSpenderTxes, PaymentInfo, SwapMap,
 };
-use crate::utils::{parse_peer_info, LOGS_DIR};
+use crate::peer_policy::PeerPolicy;
+use crate::proxy::{ConsignmentProxyMap, ProxyPinMap};
+use crate::rotation::{NodeIdRotation, NODE_ID_ROTATION_ARCHIVE_DIR};
+use crate::schedule::ScheduleMap;
+use crate::scheduled_backup::ScheduledBackupMap;
+use crate::spend_limits::SpendHistory;
+use crate::submarine_swap::SubmarineSwapMap;
+use crate::swap_offer::SwapOfferMap;
+use crate::tor::TOR_ONION_KEY_FNAME;
+use crate::utils::{hex_str, hex_str_to_vec, parse_peer_info, LOGS_DIR};
 
 pub(crate) const LDK_LOGS_FILE: &str = "logs.txt";
 
 pub(crate) const INBOUND_PAYMENTS_FNAME: &str = "inbound_payments";
+pub(crate) const INBOUND_PAYMENTS_NAMESPACE: &str = "inbound_payment_info";
 pub(crate) const OUTBOUND_PAYMENTS_FNAME: &str = "outbound_payments";
+pub(crate) const OUTBOUND_PAYMENTS_NAMESPACE: &str = "outbound_payment_info";
 
 pub(crate) const CHANNEL_PEER_DATA: &str = "channel_peer_data";
 
+/// Prefix of the funding PSBTs kept in the LDK data dir until the channel is ready
+pub(crate) const PSBT_PREFIX: &str = "psbt_";
+
+/// Prefix of the funding and sweep consignments kept in the LDK data dir
+pub(crate) const CONSIGNMENT_PREFIX: &str = "consignment_";
+
 pub(crate) const OUTPUT_SPENDER_TXES: &str = "output_spender_txes";
 
 pub(crate) const CHANNEL_IDS_FNAME: &str = "channel_ids";
 
+pub(crate) const ASSET_HTLC_LIMITS_FNAME: &str = "asset_htlc_limits";
+
+pub(crate) const CLOSE_ADDRESSES_FNAME: &str = "close_addresses";
+
+pub(crate) const CHANNEL_CLOSURES_FNAME: &str = "channel_closures";
+
+pub(crate) const CHANNEL_CONVERSIONS_FNAME: &str = "channel_conversions";
+
+pub(crate) const CHANNEL_REQUESTS_FNAME: &str = "channel_requests";
+
+pub(crate) const CHANNEL_TRANSFERS_FNAME: &str = "channel_transfers";
+
+pub(crate) const FEE_ORDERS_FNAME: &str = "fee_orders";
+
+pub(crate) const FEE_REPORT_FNAME: &str = "fee_report";
+
+pub(crate) const FORWARDING_HISTORY_FNAME: &str = "forwarding_history";
+
+pub(crate) const LNURL_WITHDRAWS_FNAME: &str = "lnurl_withdraws";
+
+pub(crate) const NODE_ID_ROTATION_FNAME: &str = "node_id_rotation";
+
+pub(crate) const RELAY_KEYS_FNAME: &str = "relay_keys";
+
+pub(crate) const PEER_POLICY_FNAME: &str = "peer_policy";
+/// Whether new channels are announced by default, once set at runtime
+pub(crate) const CHANNEL_ANNOUNCEMENT_FNAME: &str = "channel_announcement";
+
+pub(crate) const PROXY_PINS_FNAME: &str = "proxy_pins";
+
+pub(crate) const CONSIGNMENT_PROXIES_FNAME: &str = "consignment_proxies";
+
+pub(crate) const SCHEDULES_FNAME: &str = "schedules";
+
+pub(crate) const SCHEDULED_BACKUPS_FNAME: &str = "scheduled_backups";
+
+pub(crate) const SPEND_HISTORY_FNAME: &str = "spend_history";
+
+pub(crate) const SWAP_OFFERS_FNAME: &str = "swap_offers";
+
+pub(crate) const SUBMARINE_SWAPS_FNAME: &str = "submarine_swaps";
+
 pub(crate) const MAKER_SWAPS_FNAME: &str = "maker_swaps";
 pub(crate) const TAKER_SWAPS_FNAME: &str = "taker_swaps";
 
@@ -72,14 +148,82 @@ impl Logger for FilesystemLogger {
     }
 }
 
+/// Replace the file at the given path with the given data, through a synced temporary file so
+/// that the file is never left half written
+pub(crate) fn write_file_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut tmp_file = File::create(&tmp_path)?;
+    tmp_file.write_all(data)?;
+    tmp_file.sync_all()?;
+    fs::rename(tmp_path, path)?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+fn encrypted_file_error(path: &Path) -> std::io::Error {
+    std::io::Error::other(format!(
+        "{} is encrypted, start with --encrypt-storage",
+        path.display()
+    ))
+}
+
+/// Read a data file of the LDK data dir, decrypting it with the storage cipher if any
+pub(crate) fn read_data_file(
+    path: &Path,
+    cipher: Option<&StoreCipher>,
+) -> std::io::Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    match cipher {
+        Some(cipher) => cipher.decrypt(data),
+        None if is_encrypted(&data) => Err(encrypted_file_error(path)),
+        None => Ok(data),
+    }
+}
+
+/// Write a data file of the LDK data dir, encrypting it with the storage cipher if any
+pub(crate) fn write_data_file(
+    path: &Path,
+    data: &[u8],
+    cipher: Option<&StoreCipher>,
+) -> std::io::Result<()> {
+    match cipher {
+        Some(cipher) => write_file_atomically(path, &cipher.encrypt(data)?),
+        None => write_file_atomically(path, data),
+    }
+}
+
+fn read_data_file_to_string(path: &Path, cipher: Option<&StoreCipher>) -> std::io::Result<String> {
+    String::from_utf8(read_data_file(path, cipher)?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn read_channel_peer_lines(path: &Path, cipher: Option<&StoreCipher>) -> Result<String, APIError> {
+    Ok(read_data_file_to_string(path, cipher)?)
+}
+
+/// Path of the funding PSBT of the given transaction
+pub(crate) fn funding_psbt_path(ldk_data_dir: &Path, funding_txid: impl Display) -> PathBuf {
+    ldk_data_dir.join(format!("{PSBT_PREFIX}{funding_txid}"))
+}
+
+pub(crate) fn read_funding_psbt(
+    path: &Path,
+    cipher: Option<&StoreCipher>,
+) -> std::io::Result<String> {
+    read_data_file_to_string(path, cipher)
+}
+
 pub(crate) fn persist_channel_peer(
     path: &Path,
     pubkey: &PublicKey,
-    address: &SocketAddr,
+    address: &SocketAddress,
+    cipher: Option<&StoreCipher>,
 ) -> Result<(), APIError> {
     let pubkey = pubkey.to_string();
     let peer_info = if path.exists() {
-        let mut updated_peer_info = fs::read_to_string(path)?
+        let mut updated_peer_info = read_channel_peer_lines(path, cipher)?
             .lines()
             .filter(|&line| !line.trim().starts_with(&pubkey))
             .map(|line| line.trim())
@@ -98,41 +242,38 @@ pub(crate) fn persist_channel_peer(
     } else {
         format!("{pubkey}@{address}")
     };
-    let mut tmp_path = path.to_path_buf();
-    tmp_path.set_extension("ptmp");
-    fs::write(&tmp_path, peer_info.to_string().as_bytes())?;
-    fs::rename(tmp_path, path)?;
+    write_data_file(path, peer_info.as_bytes(), cipher)?;
     tracing::info!("persisted peer (pubkey: {pubkey}, addr: {address})");
     Ok(())
 }
 
-pub(crate) fn delete_channel_peer(path: &Path, pubkey: String) -> Result<(), APIError> {
+pub(crate) fn delete_channel_peer(
+    path: &Path,
+    pubkey: String,
+    cipher: Option<&StoreCipher>,
+) -> Result<(), APIError> {
     if path.exists() {
-        let updated_peer_info = fs::read_to_string(path)?
+        let updated_peer_info = read_channel_peer_lines(path, cipher)?
             .lines()
             .filter(|&line| !line.trim().starts_with(&pubkey))
             .map(|line| line.trim())
             .collect::<Vec<_>>()
             .join("\n");
-        let mut tmp_path = path.to_path_buf();
-        tmp_path.set_extension("dtmp");
-        fs::write(&tmp_path, updated_peer_info.to_string().as_bytes())?;
-        fs::rename(tmp_path, path)?;
+        write_data_file(path, updated_peer_info.as_bytes(), cipher)?;
     }
     Ok(())
 }
 
 pub(crate) fn read_channel_peer_data(
     path: &Path,
-) -> Result<HashMap<PublicKey, SocketAddr>, APIError> {
+    cipher: Option<&StoreCipher>,
+) -> Result<HashMap<PublicKey, SocketAddress>, APIError> {
     let mut peer_data = HashMap::new();
     if !path.exists() {
         return Ok(HashMap::new());
     }
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        match parse_peer_info(line.unwrap()) {
+    for line in read_channel_peer_lines(path, cipher)?.lines() {
+        match parse_peer_info(line.to_string()) {
             Ok((pubkey, socket_addr)) => {
                 peer_data.insert(pubkey, socket_addr.expect("saved info with address"));
             }
@@ -142,53 +283,253 @@ pub(crate) fn read_channel_peer_data(
     Ok(peer_data)
 }
 
-pub(crate) fn read_network(
+/// ID of the channel the given file is the RGB info (pending or not) of, if it is one
+pub(crate) fn rgb_info_channel_id<'a>(
+    path: &Path,
+    name: &'a str,
+    ldk_data_dir: &Path,
+) -> Option<&'a str> {
+    let channel_id = name.get(..64)?;
+    if !channel_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    [false, true]
+        .into_iter()
+        .any(|pending| get_rgb_channel_info_path(channel_id, ldk_data_dir, pending) == path)
+        .then_some(channel_id)
+}
+
+fn is_hex_id(id: &str) -> bool {
+    id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether the given file of the LDK data dir holds RGB data handled by rgb_utils: the channel
+/// and payment info, named after the channel ID or payment hash, the transfer info, named after
+/// the TXID, and the consignments
+fn is_rgb_data_file(name: &str) -> bool {
+    let name = name.strip_prefix(CONSIGNMENT_PREFIX).unwrap_or(name);
+    name.get(..64).is_some_and(is_hex_id) && name[64..].chars().next().map_or(true, |c| c == '_')
+}
+
+/// Whether the given file or dir of the LDK data dir is kept outside of the node store
+fn is_outside_store(name: &str) -> bool {
+    [
+        LOGS_DIR,
+        NODE_ID_ROTATION_ARCHIVE_DIR,
+        STORAGE_KEY_FNAME,
+        STORAGE_MIGRATION_FNAME,
+        STATE_JOURNAL_FNAME,
+        CHANNEL_PEER_DATA,
+        INBOUND_PAYMENTS_FNAME,
+        TOR_ONION_KEY_FNAME,
+    ]
+    .contains(&name)
+        || name.starts_with(PSBT_PREFIX)
+        || is_rgb_data_file(name)
+}
+
+/// Files of the LDK data dir the given filter matches
+fn data_files(ldk_data_dir: &Path, filter: impl Fn(&str) -> bool) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(ldk_data_dir)? {
+        let path = entry?.path();
+        let matches = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(&filter);
+        if matches && path.is_file() {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Read an RGB data file, decrypting it with the storage cipher if it has been encrypted at rest,
+/// see [`encrypt_rgb_data_files`]
+pub(crate) fn read_rgb_data_file(
     path: &Path,
+    cipher: Option<&StoreCipher>,
+) -> std::io::Result<Vec<u8>> {
+    let data = fs::read(path)?;
+    match cipher {
+        Some(cipher) if is_encrypted(&data) => cipher.decrypt(data),
+        None if is_encrypted(&data) => Err(encrypted_file_error(path)),
+        _ => Ok(data),
+    }
+}
+
+/// Encrypt the RGB data files with the storage cipher once LDK has stopped.
+///
+/// The channel, payment and transfer info and the consignments are read and written by rgb_utils
+/// directly while LDK runs, so they can only be encrypted at rest, while the node is locked
+pub(crate) fn encrypt_rgb_data_files(
+    ldk_data_dir: &Path,
+    cipher: &StoreCipher,
+) -> std::io::Result<()> {
+    for path in data_files(ldk_data_dir, is_rgb_data_file)? {
+        let data = fs::read(&path)?;
+        if !is_encrypted(&data) {
+            write_file_atomically(&path, &cipher.encrypt(&data)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Decrypt the RGB data files encrypted by [`encrypt_rgb_data_files`], before LDK starts
+pub(crate) fn decrypt_rgb_data_files(
+    ldk_data_dir: &Path,
+    cipher: Option<&StoreCipher>,
+) -> std::io::Result<()> {
+    for path in data_files(ldk_data_dir, is_rgb_data_file)? {
+        let data = fs::read(&path)?;
+        if is_encrypted(&data) {
+            write_file_atomically(&path, &read_data_file(&path, cipher)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Encrypt the store entries, the channel peer data and the funding PSBTs written before storage
+/// encryption was enabled.
+///
+/// This only happens on the first unlock after the storage key has been created, unencrypted data
+/// being refused afterwards
+pub(crate) fn migrate_to_encrypted_storage(
+    kv_store: &NodeStore,
+    ldk_data_dir: &Path,
+) -> std::io::Result<()> {
+    let Some(cipher) = kv_store.cipher() else {
+        return Ok(());
+    };
+    if !is_storage_migration_pending(ldk_data_dir) {
+        return Ok(());
+    }
+    let entries = kv_store.encrypt_plaintext_entries(ldk_data_dir, is_outside_store)?;
+    let mut files = 0;
+    let is_crate_data_file =
+        |name: &str| name == CHANNEL_PEER_DATA || name.starts_with(PSBT_PREFIX);
+    for path in data_files(ldk_data_dir, is_crate_data_file)? {
+        let data = fs::read(&path)?;
+        if !is_encrypted(&data) {
+            write_file_atomically(&path, &cipher.encrypt(&data)?)?;
+            files += 1;
+        }
+    }
+    finish_storage_migration(ldk_data_dir)?;
+    tracing::info!("Encrypted {entries} store entries and {files} data files");
+    Ok(())
+}
+
+pub(crate) fn read_network(
+    kv_store: &NodeStore,
+    key: &str,
     network: Network,
     logger: Arc<FilesystemLogger>,
 ) -> NetworkGraph {
-    if let Ok(file) = File::open(path) {
-        if let Ok(graph) = NetworkGraph::read(&mut BufReader::new(file), logger.clone()) {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(graph) = NetworkGraph::read(&mut &data[..], logger.clone()) {
             return graph;
         }
     }
     NetworkGraph::new(network, logger)
 }
 
-pub(crate) fn read_inbound_payment_info(path: &Path) -> InboundPaymentInfoStorage {
-    if let Ok(file) = File::open(path) {
+/// Read the inbound payments, each stored under its payment hash.
+///
+/// Payments found in the single file they used to be stored in are moved to their own entries
+pub(crate) fn read_inbound_payment_info(
+    kv_store: &NodeStore,
+    legacy_path: &Path,
+) -> InboundPaymentInfoStorage {
+    if let Ok(file) = File::open(legacy_path) {
         if let Ok(info) = InboundPaymentInfoStorage::read(&mut BufReader::new(file)) {
-            return info;
+            let migrated = info
+                .payments
+                .iter()
+                .try_for_each(|(payment_hash, payment_info)| {
+                    kv_store.write(
+                        INBOUND_PAYMENTS_NAMESPACE,
+                        "",
+                        &hex_str(&payment_hash.0),
+                        &payment_info.encode(),
+                    )
+                });
+            // the file is kept, to be moved again on the next start, until all payments are moved
+            if let Err(e) = migrated.and_then(|_| fs::remove_file(legacy_path)) {
+                tracing::error!("Failed to move the inbound payments to their own entries: {e}");
+                return info;
+            }
         }
     }
-    InboundPaymentInfoStorage {
-        payments: HashMap::new(),
-    }
+    let payments = _read_payment_entries(kv_store, INBOUND_PAYMENTS_NAMESPACE)
+        .map(|(payment_hash, payment_info)| (PaymentHash(payment_hash), payment_info))
+        .collect();
+    InboundPaymentInfoStorage { payments }
 }
 
-pub(crate) fn read_outbound_payment_info(path: &Path) -> OutboundPaymentInfoStorage {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = OutboundPaymentInfoStorage::read(&mut BufReader::new(file)) {
-            return info;
+/// Read the outbound payments, each stored under its payment ID.
+///
+/// Payments found in the single entry they used to be stored in are moved to their own entries
+pub(crate) fn read_outbound_payment_info(
+    kv_store: &NodeStore,
+    legacy_key: &str,
+) -> OutboundPaymentInfoStorage {
+    if let Ok(data) = kv_store.read("", "", legacy_key) {
+        if let Ok(info) = OutboundPaymentInfoStorage::read(&mut &data[..]) {
+            let migrated = info
+                .payments
+                .iter()
+                .try_for_each(|(payment_id, payment_info)| {
+                    kv_store.write(
+                        OUTBOUND_PAYMENTS_NAMESPACE,
+                        "",
+                        &hex_str(&payment_id.0),
+                        &payment_info.encode(),
+                    )
+                });
+            // the entry is kept, to be moved again on the next start, until all payments are moved
+            if let Err(e) = migrated.and_then(|_| kv_store.remove("", "", legacy_key, false)) {
+                tracing::error!("Failed to move the outbound payments to their own entries: {e}");
+                return info;
+            }
         }
     }
-    OutboundPaymentInfoStorage {
-        payments: HashMap::new(),
-    }
+    let payments = _read_payment_entries(kv_store, OUTBOUND_PAYMENTS_NAMESPACE)
+        .map(|(payment_id, payment_info)| (PaymentId(payment_id), payment_info))
+        .collect();
+    OutboundPaymentInfoStorage { payments }
+}
+
+/// Read the payments stored in the given namespace, keyed by their hex-encoded 32-byte
+/// identifier, the namespace listing serving as their index
+fn _read_payment_entries<'a>(
+    kv_store: &'a NodeStore,
+    namespace: &'a str,
+) -> impl Iterator<Item = ([u8; 32], PaymentInfo)> + 'a {
+    kv_store
+        .list(namespace, "")
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(move |key| {
+            let id = hex_str_to_vec(&key).and_then(|h| h.try_into().ok())?;
+            let data = kv_store.read(namespace, "", &key).ok()?;
+            let payment_info = PaymentInfo::read(&mut &data[..]).ok()?;
+            Some((id, payment_info))
+        })
 }
 
-pub(crate) fn read_output_spender_txes(path: &Path) -> OutputSpenderTxes {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = OutputSpenderTxes::read(&mut BufReader::new(file)) {
+pub(crate) fn read_output_spender_txes(kv_store: &NodeStore, key: &str) -> OutputSpenderTxes {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = OutputSpenderTxes::read(&mut &data[..]) {
             return info;
         }
     }
     HashMap::new()
 }
 
-pub(crate) fn read_swaps_info(path: &Path) -> SwapMap {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = SwapMap::read(&mut BufReader::new(file)) {
+pub(crate) fn read_swaps_info(kv_store: &NodeStore, key: &str) -> SwapMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = SwapMap::read(&mut &data[..]) {
             return info;
         }
     }
@@ -198,23 +539,24 @@ pub(crate) fn read_swaps_info(path: &Path) -> SwapMap {
 }
 
 pub(crate) fn read_scorer(
-    path: &Path,
+    kv_store: &NodeStore,
+    key: &str,
     graph: Arc<NetworkGraph>,
     logger: Arc<FilesystemLogger>,
 ) -> ProbabilisticScorer<Arc<NetworkGraph>, Arc<FilesystemLogger>> {
     let params = ProbabilisticScoringDecayParameters::default();
-    if let Ok(file) = File::open(path) {
+    if let Ok(data) = kv_store.read("", "", key) {
         let args = (params, Arc::clone(&graph), Arc::clone(&logger));
-        if let Ok(scorer) = ProbabilisticScorer::read(&mut BufReader::new(file), args) {
+        if let Ok(scorer) = ProbabilisticScorer::read(&mut &data[..], args) {
             return scorer;
         }
     }
     ProbabilisticScorer::new(params, graph, logger)
 }
 
-pub(crate) fn read_channel_ids_info(path: &Path) -> ChannelIdsMap {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = ChannelIdsMap::read(&mut BufReader::new(file)) {
+pub(crate) fn read_channel_ids_info(kv_store: &NodeStore, key: &str) -> ChannelIdsMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelIdsMap::read(&mut &data[..]) {
             return info;
         }
     }
@@ -222,3 +564,209 @@ pub(crate) fn read_channel_ids_info(path: &Path) -> ChannelIdsMap {
         channel_ids: HashMap::new(),
     }
 }
+
+pub(crate) fn read_asset_htlc_limits(kv_store: &NodeStore, key: &str) -> AssetHtlcLimitMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = AssetHtlcLimitMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    AssetHtlcLimitMap {
+        limits: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_channel_transfers(kv_store: &NodeStore, key: &str) -> ChannelTransferMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelTransferMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ChannelTransferMap {
+        transfers: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_close_addresses(kv_store: &NodeStore, key: &str) -> CloseAddressMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = CloseAddressMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    CloseAddressMap {
+        addresses: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_channel_closures(kv_store: &NodeStore, key: &str) -> ChannelClosureMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelClosureMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ChannelClosureMap {
+        closures: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_channel_conversions(kv_store: &NodeStore, key: &str) -> ChannelConversionMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelConversionMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ChannelConversionMap {
+        conversions: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_lnurl_withdraws_info(kv_store: &NodeStore, key: &str) -> LnurlWithdrawMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = LnurlWithdrawMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    LnurlWithdrawMap {
+        withdraws: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_node_id_rotation(kv_store: &NodeStore, key: &str) -> Option<NodeIdRotation> {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = NodeIdRotation::read(&mut &data[..]) {
+            return Some(info);
+        }
+    }
+    None
+}
+
+pub(crate) fn read_channel_requests(kv_store: &NodeStore, key: &str) -> ChannelRequestMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelRequestMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ChannelRequestMap {
+        requests: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_fee_orders(kv_store: &NodeStore, key: &str) -> FeeOrderMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = FeeOrderMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    FeeOrderMap {
+        orders: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_fee_report(kv_store: &NodeStore, key: &str) -> FeeReportMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = FeeReportMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    FeeReportMap {
+        channels: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_forwarding_history(kv_store: &NodeStore, key: &str) -> ForwardingHistory {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ForwardingHistory::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ForwardingHistory { forwards: vec![] }
+}
+
+pub(crate) fn read_spend_history(kv_store: &NodeStore, key: &str) -> SpendHistory {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = SpendHistory::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    SpendHistory { spends: vec![] }
+}
+
+pub(crate) fn read_channel_announcement(kv_store: &NodeStore, key: &str) -> Option<bool> {
+    let data = kv_store.read("", "", key).ok()?;
+    bool::read(&mut &data[..]).ok()
+}
+
+pub(crate) fn read_peer_policy(kv_store: &NodeStore, key: &str) -> PeerPolicy {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = PeerPolicy::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    PeerPolicy::default()
+}
+
+pub(crate) fn read_proxy_pins(kv_store: &NodeStore, key: &str) -> ProxyPinMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ProxyPinMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ProxyPinMap {
+        pins: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_consignment_proxies(kv_store: &NodeStore, key: &str) -> ConsignmentProxyMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ConsignmentProxyMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ConsignmentProxyMap {
+        proxies: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_schedules(kv_store: &NodeStore, key: &str) -> ScheduleMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ScheduleMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ScheduleMap {
+        schedules: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_scheduled_backups(kv_store: &NodeStore, key: &str) -> ScheduledBackupMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = ScheduledBackupMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    ScheduledBackupMap {
+        backups: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_swap_offers(kv_store: &NodeStore, key: &str) -> SwapOfferMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = SwapOfferMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    SwapOfferMap {
+        offers: HashMap::new(),
+    }
+}
+
+pub(crate) fn read_submarine_swaps(kv_store: &NodeStore, key: &str) -> SubmarineSwapMap {
+    if let Ok(data) = kv_store.read("", "", key) {
+        if let Ok(info) = SubmarineSwapMap::read(&mut &data[..]) {
+            return info;
+        }
+    }
+    SubmarineSwapMap {
+        swaps: HashMap::new(),
+    }
+}
@@ -0,0 +1,167 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::{distributions::Alphanumeric, Rng};
+use scrypt::password_hash::{PasswordHasher, Salt};
+use scrypt::Scrypt;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use crate::disk::write_file_atomically;
+use crate::error::APIError;
+
+/// File holding the storage key, encrypted with the unlock password, in the LDK data dir
+pub(crate) const STORAGE_KEY_FNAME: &str = "storage_key";
+
+/// Marker of the migration to encrypted storage, created along with the storage key and removed
+/// once the entries written before encryption was enabled have been encrypted
+pub(crate) const STORAGE_MIGRATION_FNAME: &str = "storage_migration";
+
+/// Prefix of the encrypted entries
+const ENCRYPTED_ENTRY_MAGIC: &[u8; 4] = b"RLNE";
+
+const STORAGE_KEY_LENGTH: usize = 32;
+const STORAGE_KEY_SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+
+/// Cipher of the entries of the node store
+pub(crate) struct StoreCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl StoreCipher {
+    pub(crate) fn new(storage_key: &[u8; STORAGE_KEY_LENGTH]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(storage_key)),
+        }
+    }
+
+    pub(crate) fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| Error::other("cannot encrypt the entry"))?;
+        let mut entry = ENCRYPTED_ENTRY_MAGIC.to_vec();
+        entry.extend(nonce);
+        entry.extend(ciphertext);
+        Ok(entry)
+    }
+
+    /// Decrypt an entry, refusing the unencrypted ones, see [`is_storage_migration_pending`]
+    pub(crate) fn decrypt(&self, entry: Vec<u8>) -> Result<Vec<u8>, Error> {
+        if !is_encrypted(&entry) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "the entry is not encrypted",
+            ));
+        }
+        let (nonce, ciphertext) = entry[ENCRYPTED_ENTRY_MAGIC.len()..].split_at(NONCE_LENGTH);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::other("cannot decrypt the entry, the storage key doesn't match"))
+    }
+}
+
+pub(crate) fn is_encrypted(entry: &[u8]) -> bool {
+    entry.len() >= ENCRYPTED_ENTRY_MAGIC.len() + NONCE_LENGTH
+        && entry.starts_with(ENCRYPTED_ENTRY_MAGIC)
+}
+
+fn _password_cipher(password: &str, salt: &str) -> Result<XChaCha20Poly1305, APIError> {
+    let salt = Salt::from_b64(salt).map_err(|_| APIError::Unexpected)?;
+    let password_hash = Scrypt
+        .hash_password(password.as_bytes(), salt)
+        .map_err(|_| APIError::Unexpected)?;
+    let hash_output = password_hash.hash.ok_or(APIError::Unexpected)?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(
+        &hash_output.as_bytes()[..STORAGE_KEY_LENGTH],
+    )))
+}
+
+/// Save the storage key, encrypted with the given password, as the salt followed by the nonce
+/// and the encrypted key
+fn _save_storage_key(
+    ldk_data_dir: &Path,
+    storage_key: &[u8; STORAGE_KEY_LENGTH],
+    password: &str,
+) -> Result<(), APIError> {
+    let salt: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(STORAGE_KEY_SALT_LENGTH)
+        .map(char::from)
+        .collect();
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let encrypted_key = _password_cipher(password, &salt)?
+        .encrypt(&nonce, storage_key.as_slice())
+        .map_err(|_| APIError::Unexpected)?;
+    let mut data = salt.into_bytes();
+    data.extend(nonce);
+    data.extend(encrypted_key);
+    write_file_atomically(&ldk_data_dir.join(STORAGE_KEY_FNAME), &data)?;
+    Ok(())
+}
+
+fn _read_storage_key(
+    ldk_data_dir: &Path,
+    password: &str,
+) -> Result<Option<[u8; STORAGE_KEY_LENGTH]>, APIError> {
+    let path = ldk_data_dir.join(STORAGE_KEY_FNAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read(path)?;
+    if data.len() < STORAGE_KEY_SALT_LENGTH + NONCE_LENGTH {
+        return Err(APIError::Unexpected);
+    }
+    let (salt, data) = data.split_at(STORAGE_KEY_SALT_LENGTH);
+    let (nonce, encrypted_key) = data.split_at(NONCE_LENGTH);
+    let salt = std::str::from_utf8(salt).map_err(|_| APIError::Unexpected)?;
+    let storage_key = _password_cipher(password, salt)?
+        .decrypt(XNonce::from_slice(nonce), encrypted_key)
+        .map_err(|_| APIError::WrongPassword)?;
+    Ok(Some(
+        storage_key.try_into().map_err(|_| APIError::Unexpected)?,
+    ))
+}
+
+/// Get the key the node store is encrypted with, creating it on the first unlock.
+///
+/// The migration marker is created before the key, so that data written before encryption was
+/// enabled keeps being accepted until it has all been encrypted, even if the node stops halfway
+pub(crate) fn load_storage_key(
+    ldk_data_dir: &Path,
+    password: &str,
+) -> Result<[u8; STORAGE_KEY_LENGTH], APIError> {
+    if let Some(storage_key) = _read_storage_key(ldk_data_dir, password)? {
+        return Ok(storage_key);
+    }
+    let storage_key: [u8; STORAGE_KEY_LENGTH] = rand::thread_rng().gen();
+    write_file_atomically(&ldk_data_dir.join(STORAGE_MIGRATION_FNAME), &[])?;
+    _save_storage_key(ldk_data_dir, &storage_key, password)?;
+    tracing::info!("Created the storage key");
+    Ok(storage_key)
+}
+
+/// Encrypt the storage key, if any, with a new password
+pub(crate) fn change_storage_key_password(
+    ldk_data_dir: &Path,
+    old_password: &str,
+    new_password: &str,
+) -> Result<(), APIError> {
+    if let Some(storage_key) = _read_storage_key(ldk_data_dir, old_password)? {
+        _save_storage_key(ldk_data_dir, &storage_key, new_password)?;
+    }
+    Ok(())
+}
+
+/// Whether the data written before encryption was enabled still has to be encrypted, the only
+/// case in which unencrypted data is accepted
+pub(crate) fn is_storage_migration_pending(ldk_data_dir: &Path) -> bool {
+    ldk_data_dir.join(STORAGE_MIGRATION_FNAME).exists()
+}
+
+/// Mark the migration to encrypted storage as completed, unencrypted data being refused from now
+pub(crate) fn finish_storage_migration(ldk_data_dir: &Path) -> Result<(), Error> {
+    fs::remove_file(ldk_data_dir.join(STORAGE_MIGRATION_FNAME))
+}
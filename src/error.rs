@@ -1,5 +1,6 @@
+use amplify::s;
 use axum::{
-    extract::rejection::JsonRejection,
+    extract::rejection::{JsonRejection, QueryRejection},
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
@@ -11,6 +12,9 @@ use serde::{Deserialize, Serialize};
 pub(crate) struct APIErrorResponse {
     pub(crate) error: String,
     pub(crate) code: u16,
+    /// Token to confirm an operation going over the spend limits with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) confirmation_token: Option<String>,
 }
 
 /// The error variants returned by APIs
@@ -25,24 +29,93 @@ pub enum APIError {
     #[error("Anchor outputs are required for RGB channels")]
     AnchorsRequired,
 
+    #[error("Cannot abandon funding: {0}")]
+    CannotAbandonFunding(String),
+
+    #[error("Cannot abandon payment: {0}")]
+    CannotAbandonPayment(String),
+
+    #[error("Cannot accept swap offer: {0}")]
+    CannotAcceptSwapOffer(String),
+
+    #[error("Cannot announce channel: {0}")]
+    CannotAnnounceChannel(String),
+
+    #[error("Cannot bump close transaction: {0}")]
+    CannotBumpCloseTx(String),
+
+    #[error("Cannot cancel fee order: {0}")]
+    CannotCancelFeeOrder(String),
+
+    #[error("Cannot cancel invoice: {0}")]
+    CannotCancelInvoice(String),
+
+    #[error("Cannot close channel: {0}")]
+    CannotCloseChannel(String),
+
+    #[error("Cannot create fee order: {0}")]
+    CannotCreateFeeOrder(String),
+
+    #[error("Cannot create incremental backup: {0}")]
+    CannotCreateIncrementalBackup(String),
+
+    #[error("Cannot execute fee order: {0}")]
+    CannotExecuteFeeOrder(String),
+
+    #[error("Cannot export transfer proof: {0}")]
+    CannotExportTransferProof(String),
+
+    #[error("Cannot import contract: {0}")]
+    CannotImportContract(String),
+
+    #[error("Cannot withdraw: {0}")]
+    CannotLnurlWithdraw(String),
+
     #[error("Cannot open channel: {0}")]
     CannotOpenChannel(String),
 
+    #[error("Cannot quote swap: {0}")]
+    CannotQuoteSwap(String),
+
+    #[error("Cannot request channel: {0}")]
+    CannotRequestChannel(String),
+
+    #[error("Cannot settle invoice: {0}")]
+    CannotSettleInvoice(String),
+
+    #[error("Cannot set log level: {0}")]
+    CannotSetLogLevel(String),
+
+    #[error("Cannot start submarine swap: {0}")]
+    CannotStartSubmarineSwap(String),
+
+    #[error("Cannot use proxy: {0}")]
+    CannotUseProxy(String),
+
     #[error("Cannot call other APIs while node is changing state")]
     ChangingState,
 
+    #[error("Channel request has already been handled")]
+    ChannelRequestAlreadyHandled,
+
     #[error("The swap offer has expired")]
     ExpiredSwapOffer,
 
     #[error("Failed closing channel: {0}")]
     FailedClosingChannel(String),
 
+    #[error("Failed to update channel config: {0}")]
+    FailedChannelConfigUpdate(String),
+
     #[error("Failed to create invoice: {0}")]
     FailedInvoiceCreation(String),
 
     #[error("Failed to issue asset: {0}")]
     FailedIssuingAsset(String),
 
+    #[error("Failed LNURL request: {0}")]
+    FailedLnurlRequest(String),
+
     #[error("Unable to create keys seed file {0}: {1}")]
     FailedKeysCreation(String, String),
 
@@ -61,6 +134,9 @@ pub enum APIError {
     #[error("Failed to disconnect to peer: {0}")]
     FailedPeerDisconnection(String),
 
+    #[error("Failed to persist the node data: {0}")]
+    FailedPersisting(String),
+
     #[error("Failed to send onion message: {0}")]
     FailedSendingOnionMessage(String),
 
@@ -76,24 +152,66 @@ pub enum APIError {
     #[error("Not enough funds, call getaddress and send {0} satoshis")]
     InsufficientFunds(u64),
 
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
     #[error("Invalid amount: {0}")]
     InvalidAmount(String),
 
+    #[error("Invalid API token")]
+    InvalidApiToken,
+
     #[error("Invalid asset ID: {0}")]
     InvalidAssetID(String),
 
+    #[error("Invalid backup: {0}")]
+    InvalidBackup(String),
+
+    #[error("Invalid backup chain: {0}")]
+    InvalidBackupChain(String),
+
     #[error("Invalid backup path")]
     InvalidBackupPath,
 
+    #[error("Invalid channel batch: {0}")]
+    InvalidChannelBatch(String),
+
     #[error("Invalid channel ID")]
     InvalidChannelID,
 
+    #[error("Invalid channel policy: {0}")]
+    InvalidChannelPolicy(String),
+
+    #[error("Invalid consignment: {0}")]
+    InvalidConsignment(String),
+
+    #[error("Invalid contract: {0}")]
+    InvalidContract(String),
+
     #[error("Invalid fee rate: {0}")]
     InvalidFeeRate(String),
 
+    #[error("Invalid funding outpoints: {0}")]
+    InvalidFundingOutpoints(String),
+
+    #[error("Invalid funding PSBT: {0}")]
+    InvalidFundingPsbt(String),
+
+    #[error("Invalid HTLC limits: {0}")]
+    InvalidHtlcLimits(String),
+
+    #[error("Invalid intercept ID")]
+    InvalidInterceptId,
+
     #[error("Invalid invoice: {0}")]
     InvalidInvoice(String),
 
+    #[error("Invalid lightning address: {0}")]
+    InvalidLightningAddress(String),
+
+    #[error("Invalid log filter: {0}")]
+    InvalidLogFilter(String),
+
     #[error("Invalid media digest")]
     InvalidMediaDigest,
 
@@ -106,6 +224,18 @@ pub enum APIError {
     #[error("Invalid onion data: {0}")]
     InvalidOnionData(String),
 
+    #[error("Invalid payment hash")]
+    InvalidPaymentHash,
+
+    #[error("Invalid payment ID")]
+    InvalidPaymentId,
+
+    #[error("Invalid payment preimage")]
+    InvalidPaymentPreimage,
+
+    #[error("Invalid payment retry: {0}")]
+    InvalidPaymentRetry(String),
+
     #[error("Invalid payment secret")]
     InvalidPaymentSecret,
 
@@ -115,21 +245,52 @@ pub enum APIError {
     #[error("Invalid peer info: {0}")]
     InvalidPeerInfo(String),
 
+    #[error("Invalid peer policy: {0}")]
+    InvalidPeerPolicy(String),
+
     #[error("Invalid precision: {0}")]
     InvalidPrecision(String),
 
+    #[error("Invalid transfer proof path")]
+    InvalidProofPath,
+
+    #[error("Invalid proxy pin: {0}")]
+    InvalidProxyPin(String),
+
     #[error("Invalid pubkey")]
     InvalidPubkey,
 
+    #[error("Invalid rebalance: {0}")]
+    InvalidRebalance(String),
+
     #[error("The provided recipient ID is neither a blinded UTXO or a script")]
     InvalidRecipientID,
 
     #[error("The provided recipient ID is for a different network than the wallet's one")]
     InvalidRecipientNetwork,
 
+    #[cfg(feature = "debug-api")]
+    #[error("Invalid RGB info: {0}")]
+    InvalidRgbInfo(String),
+
+    #[error("Invalid route hints: {0}")]
+    InvalidRouteHints(String),
+
+    #[error("Invalid static channel backup: {0}")]
+    InvalidScb(String),
+
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(String),
+
+    #[error("Invalid seed words: {0}")]
+    InvalidSeedWords(String),
+
     #[error("Invalid swap: {0}")]
     InvalidSwap(String),
 
+    #[error("Invalid swap offer: {0}")]
+    InvalidSwapOffer(String),
+
     #[error("Invalid swap string '{0}': {1}")]
     InvalidSwapString(String, String),
 
@@ -142,12 +303,18 @@ pub enum APIError {
     #[error("Invalid transport endpoints: {0}")]
     InvalidTransportEndpoints(String),
 
+    #[error("Invalid txid")]
+    InvalidTxid,
+
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
 
     #[error(transparent)]
     JsonExtractorRejection(#[from] JsonRejection),
 
+    #[error("LNURL support is disabled (hint: set --lnurl-base-url)")]
+    LnurlDisabled,
+
     #[error("Node is locked (hint: call unlock)")]
     LockedNode,
 
@@ -160,6 +327,9 @@ pub enum APIError {
     #[error("Min fee not met for transfer with TXID: {0}")]
     MinFeeNotMet(String),
 
+    #[error("An API token is required")]
+    MissingApiToken,
+
     #[error("Unable to find payment preimage, be sure you've provided the correct swap info")]
     MissingSwapPaymentPreimage,
 
@@ -169,6 +339,9 @@ pub enum APIError {
     #[error("No route found")]
     NoRoute,
 
+    #[error("No route found for the swap {0} leg")]
+    NoSwapRoute(String),
+
     #[error("Wallet has not been initialized (hint: call init)")]
     NotInitialized,
 
@@ -178,21 +351,93 @@ pub enum APIError {
     #[error("Output below the dust limit")]
     OutputBelowDustLimit,
 
+    #[error("Payment hash already used")]
+    PaymentHashAlreadyUsed,
+
+    #[error("Peer not allowed: {0}")]
+    PeerNotAllowed(String),
+
+    #[error("The peer is not banned")]
+    PeerNotBanned,
+
+    #[error(transparent)]
+    QueryExtractorRejection(#[from] QueryRejection),
+
+    #[error("The API token is read-only")]
+    ReadOnlyApiToken,
+
     #[error("Recipient ID already used")]
     RecipientIDAlreadyUsed,
 
+    #[error("The mnemonic has already been revealed")]
+    SeedAlreadyRevealed,
+
+    #[error("Spend limits exceeded ({reason}), the operation needs to be confirmed")]
+    SpendConfirmationRequired { reason: String, token: String },
+
+    #[error("Spend limits exceeded: {0}")]
+    SpendLimitExceeded(String),
+
+    #[error("Node is on standby: it will unlock once the lease held by another instance is free")]
+    StandbyNode,
+
     #[error("Temporary channel ID already used")]
     TemporaryChannelIdAlreadyUsed,
 
     #[error("Unexpected error")]
     Unexpected,
 
+    #[error("Unknown API token")]
+    UnknownApiToken,
+
+    #[error("Unknown channel ID")]
+    UnknownChannelId,
+
+    #[error("Unknown channel request")]
+    UnknownChannelRequest,
+
+    #[error("Unknown consignment")]
+    UnknownConsignment,
+
     #[error("Unknown RGB contract ID")]
     UnknownContractId,
 
+    #[error("Unknown fee order")]
+    UnknownFeeOrder,
+
+    #[error("Unknown channel in the network graph")]
+    UnknownGraphChannel,
+
+    #[error("Unknown node in the network graph")]
+    UnknownGraphNode,
+
+    #[error("Unknown intercept ID")]
+    UnknownInterceptId,
+
     #[error("Unknown LN invoice")]
     UnknownLNInvoice,
 
+    #[error("Unknown LNURL-withdraw k1")]
+    UnknownLnurlWithdraw,
+
+    #[error("Unknown payment ID")]
+    UnknownPaymentId,
+
+    #[error("Unknown proxy pin")]
+    UnknownProxyPin,
+
+    #[error("Unknown schedule")]
+    UnknownSchedule,
+
+    #[error("Unknown or expired spend confirmation token")]
+    UnknownSpendConfirmation,
+
+    #[error("Unknown swap")]
+    UnknownSwap,
+
+    #[error("Unknown swap offer")]
+    UnknownSwapOffer,
+
     #[error("Unknown temporary channel ID")]
     UnknownTemporaryChannelId,
 
@@ -202,25 +447,41 @@ pub enum APIError {
     #[error("The provided backup has an unsupported version: {version}")]
     UnsupportedBackupVersion { version: String },
 
+    #[error("The counterparty node doesn't support the swap pair")]
+    UnsupportedSwapPair,
+
+    #[error("The counterparty node doesn't support the swap protocol")]
+    UnsupportedSwapProtocol,
+
     #[error("The provided password is incorrect")]
     WrongPassword,
 }
 
 impl IntoResponse for APIError {
     fn into_response(self) -> Response {
+        let confirmation_token = match &self {
+            APIError::SpendConfirmationRequired { token, .. } => Some(token.clone()),
+            _ => None,
+        };
         let (status, error_message) = match self {
             APIError::JsonExtractorRejection(json_rejection) => {
                 (json_rejection.status(), json_rejection.body_text())
             }
-            APIError::FailedClosingChannel(_)
+            APIError::QueryExtractorRejection(query_rejection) => {
+                (query_rejection.status(), query_rejection.body_text())
+            }
+            APIError::FailedChannelConfigUpdate(_)
+            | APIError::FailedClosingChannel(_)
             | APIError::FailedInvoiceCreation(_)
             | APIError::FailedIssuingAsset(_)
+            | APIError::FailedLnurlRequest(_)
             | APIError::FailedKeysCreation(_, _)
             | APIError::FailedMessageSigning(_)
             | APIError::FailedOpenChannel(_)
             | APIError::FailedPayment(_)
             | APIError::FailedPeerConnection
             | APIError::FailedPeerDisconnection(_)
+            | APIError::FailedPersisting(_)
             | APIError::FailedSendingOnionMessage(_)
             | APIError::FailedStartingLDK(_)
             | APIError::IO(_)
@@ -228,28 +489,55 @@ impl IntoResponse for APIError {
             APIError::AnchorsRequired
             | APIError::ExpiredSwapOffer
             | APIError::IncompleteRGBInfo
+            | APIError::InvalidAddress(_)
             | APIError::InvalidAmount(_)
             | APIError::InvalidAssetID(_)
+            | APIError::InvalidBackup(_)
+            | APIError::InvalidBackupChain(_)
             | APIError::InvalidBackupPath
+            | APIError::InvalidChannelBatch(_)
             | APIError::InvalidChannelID
+            | APIError::InvalidChannelPolicy(_)
+            | APIError::InvalidConsignment(_)
+            | APIError::InvalidContract(_)
+            | APIError::InvalidLogFilter(_)
             | APIError::InvalidMediaDigest
             | APIError::InvalidFeeRate(_)
+            | APIError::InvalidFundingOutpoints(_)
+            | APIError::InvalidFundingPsbt(_)
+            | APIError::InvalidHtlcLimits(_)
+            | APIError::InvalidInterceptId
             | APIError::InvalidInvoice(_)
+            | APIError::InvalidLightningAddress(_)
             | APIError::InvalidName(_)
             | APIError::InvalidNodeIds(_)
             | APIError::InvalidOnionData(_)
+            | APIError::InvalidPaymentHash
+            | APIError::InvalidPaymentId
+            | APIError::InvalidPaymentPreimage
+            | APIError::InvalidPaymentRetry(_)
             | APIError::InvalidPaymentSecret
             | APIError::InvalidPassword(_)
             | APIError::InvalidPeerInfo(_)
+            | APIError::InvalidPeerPolicy(_)
             | APIError::InvalidPrecision(_)
+            | APIError::InvalidProofPath
+            | APIError::InvalidProxyPin(_)
             | APIError::InvalidPubkey
+            | APIError::InvalidRebalance(_)
             | APIError::InvalidRecipientID
             | APIError::InvalidRecipientNetwork
+            | APIError::InvalidRouteHints(_)
+            | APIError::InvalidScb(_)
+            | APIError::InvalidSchedule(_)
+            | APIError::InvalidSeedWords(_)
             | APIError::InvalidSwap(_)
+            | APIError::InvalidSwapOffer(_)
             | APIError::InvalidSwapString(_, _)
             | APIError::InvalidTicker(_)
             | APIError::InvalidTlvType(_)
             | APIError::InvalidTransportEndpoints(_)
+            | APIError::InvalidTxid
             | APIError::MediaFileEmpty
             | APIError::MediaFileNotProvided
             | APIError::MissingSwapPaymentPreimage
@@ -257,31 +545,84 @@ impl IntoResponse for APIError {
             | APIError::UnsupportedBackupVersion { .. } => {
                 (StatusCode::BAD_REQUEST, self.to_string())
             }
-            APIError::WrongPassword => (StatusCode::UNAUTHORIZED, self.to_string()),
+            #[cfg(feature = "debug-api")]
+            APIError::InvalidRgbInfo(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            APIError::InvalidApiToken | APIError::MissingApiToken | APIError::WrongPassword => {
+                (StatusCode::UNAUTHORIZED, self.to_string())
+            }
             APIError::AllocationsAlreadyAvailable
             | APIError::AlreadyInitialized
+            | APIError::CannotAbandonFunding(_)
+            | APIError::CannotAbandonPayment(_)
+            | APIError::CannotAcceptSwapOffer(_)
+            | APIError::CannotAnnounceChannel(_)
+            | APIError::CannotBumpCloseTx(_)
+            | APIError::CannotCancelFeeOrder(_)
+            | APIError::CannotCancelInvoice(_)
+            | APIError::CannotCloseChannel(_)
+            | APIError::CannotCreateFeeOrder(_)
+            | APIError::CannotCreateIncrementalBackup(_)
+            | APIError::CannotExecuteFeeOrder(_)
+            | APIError::CannotExportTransferProof(_)
+            | APIError::CannotImportContract(_)
+            | APIError::CannotLnurlWithdraw(_)
             | APIError::CannotOpenChannel(_)
+            | APIError::CannotQuoteSwap(_)
+            | APIError::CannotRequestChannel(_)
+            | APIError::CannotSettleInvoice(_)
+            | APIError::CannotSetLogLevel(_)
+            | APIError::CannotStartSubmarineSwap(_)
+            | APIError::CannotUseProxy(_)
             | APIError::ChangingState
+            | APIError::ChannelRequestAlreadyHandled
             | APIError::InsufficientAssets
             | APIError::InsufficientFunds(_)
+            | APIError::LnurlDisabled
             | APIError::LockedNode
             | APIError::MinFeeNotMet(_)
             | APIError::NoAvailableUtxos
             | APIError::NoRoute
+            | APIError::NoSwapRoute(_)
             | APIError::NotInitialized
             | APIError::OpenChannelInProgress
+            | APIError::PaymentHashAlreadyUsed
+            | APIError::PeerNotAllowed(_)
+            | APIError::PeerNotBanned
+            | APIError::ReadOnlyApiToken
             | APIError::RecipientIDAlreadyUsed
+            | APIError::SeedAlreadyRevealed
+            | APIError::SpendConfirmationRequired { .. }
+            | APIError::SpendLimitExceeded(_)
+            | APIError::StandbyNode
             | APIError::TemporaryChannelIdAlreadyUsed
+            | APIError::UnknownApiToken
+            | APIError::UnknownChannelId
+            | APIError::UnknownChannelRequest
+            | APIError::UnknownConsignment
             | APIError::UnknownContractId
+            | APIError::UnknownFeeOrder
+            | APIError::UnknownGraphChannel
+            | APIError::UnknownGraphNode
+            | APIError::UnknownInterceptId
             | APIError::UnknownLNInvoice
+            | APIError::UnknownLnurlWithdraw
+            | APIError::UnknownPaymentId
+            | APIError::UnknownProxyPin
+            | APIError::UnknownSchedule
+            | APIError::UnknownSpendConfirmation
+            | APIError::UnknownSwap
+            | APIError::UnknownSwapOffer
             | APIError::UnknownTemporaryChannelId
-            | APIError::UnlockedNode => (StatusCode::FORBIDDEN, self.to_string()),
+            | APIError::UnlockedNode
+            | APIError::UnsupportedSwapPair
+            | APIError::UnsupportedSwapProtocol => (StatusCode::FORBIDDEN, self.to_string()),
         };
 
         let body = Json(
             serde_json::to_value(APIErrorResponse {
                 error: error_message,
                 code: status.as_u16(),
+                confirmation_token,
             })
             .unwrap(),
         );
@@ -290,24 +631,113 @@ impl IntoResponse for APIError {
     }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LnurlErrorResponse {
+    pub(crate) status: String,
+    pub(crate) reason: String,
+}
+
+/// An error returned by the LNURL APIs, reported in the format expected by LNURL wallets
+#[derive(Debug)]
+pub(crate) struct LnurlError {
+    status: StatusCode,
+    reason: String,
+}
+
+impl From<APIError> for LnurlError {
+    fn from(error: APIError) -> Self {
+        let reason = error.to_string();
+        let status = error.into_response().status();
+        Self { status, reason }
+    }
+}
+
+impl From<QueryRejection> for LnurlError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            reason: rejection.body_text(),
+        }
+    }
+}
+
+impl IntoResponse for LnurlError {
+    fn into_response(self) -> Response {
+        let body = Json(LnurlErrorResponse {
+            status: s!("ERROR"),
+            reason: self.reason,
+        });
+
+        (self.status, body).into_response()
+    }
+}
+
 /// The error variants returned by the app
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error("Failed to connect to bitcoind client: {0}")]
     FailedBitcoindConnection(String),
 
+    #[error("Failed to create the onion service: {0}")]
+    FailedOnionService(String),
+
+    #[error("Invalid alert config: {0}")]
+    InvalidAlertConfig(String),
+
     #[error("Invalid announced listen addresses: {0}")]
     InvalidAnnouncedListenAddresses(String),
 
+    #[error("Invalid API tokens file: {0}")]
+    InvalidApiTokens(String),
+
     #[error("Chain argument ({0}) didn't match bitcoind chain ({1})")]
     InvalidBitcoinNetwork(Network, String),
 
     #[error("Invalid bitcoind RPC info: {0}")]
     InvalidBitcoinRPCInfo(String),
 
+    #[error("Invalid backup config: {0}")]
+    InvalidBackupConfig(String),
+
+    #[error("Invalid closing fee rates: {0}")]
+    InvalidClosingFeeRates(String),
+
+    #[error("Invalid fee rate: {0}")]
+    InvalidFeeRate(String),
+
+    #[error("Invalid HTLC limits: {0}")]
+    InvalidHtlcLimits(String),
+
+    #[error("Invalid LNURL config: {0}")]
+    InvalidLnurlConfig(String),
+
     #[error("Invalid node alias: {0}")]
     InvalidNodeAlias(String),
 
+    #[error("Invalid peer listen addresses: {0}")]
+    InvalidPeerListenAddresses(String),
+
+    #[error("Invalid price oracle config: {0}")]
+    InvalidPriceOracleConfig(String),
+
+    #[error("Invalid proxy endpoints: {0}")]
+    InvalidProxyEndpoints(String),
+
+    #[error("Invalid RGB refresh config: {0}")]
+    InvalidRgbRefreshConfig(String),
+
+    #[error("Invalid spend limits: {0}")]
+    InvalidSpendLimits(String),
+
+    #[error("Invalid Tor config: {0}")]
+    InvalidTorConfig(String),
+
+    #[error("Invalid UTXO parameters: {0}")]
+    InvalidUtxoParams(String),
+
+    #[error("Invalid VSS config: {0}")]
+    InvalidVssConfig(String),
+
     #[error("PoC does not support selected network")]
     UnsupportedBitcoinNetwork,
 }
@@ -0,0 +1,202 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use bitcoin::blockdata::block::Header;
+use bitcoin::{OutPoint, Txid};
+use lightning::chain;
+use lightning::chain::channelmonitor::ANTI_REORG_DELAY;
+use lightning::chain::transaction::TransactionData;
+use lightning::util::sweep::OutputSpendStatus;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::alerts::AlertSeverity;
+use crate::ldk::{ChannelManager, OutputSweeper};
+use crate::utils::AppState;
+
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum NodeEvent {
+    Alert {
+        rule: String,
+        severity: AlertSeverity,
+        message: String,
+        occurrences: u32,
+        raised_at: u64,
+    },
+    BlockConnected {
+        height: u32,
+        block_hash: String,
+        channels_affected: usize,
+        sweeps_affected: usize,
+    },
+    BlockDisconnected {
+        height: u32,
+        block_hash: String,
+        channels_affected: usize,
+        sweeps_affected: usize,
+    },
+    ChannelRequestReceived {
+        request_id: String,
+        peer_pubkey: String,
+    },
+    SwapOfferAccepted {
+        offer_id: String,
+        taker_pubkey: String,
+        swapstring: String,
+    },
+    SyncProgress {
+        height: u32,
+        best_height: u32,
+        synced: bool,
+    },
+}
+
+pub(crate) fn new_event_sender() -> broadcast::Sender<NodeEvent> {
+    broadcast::channel(EVENT_CHANNEL_CAPACITY).0
+}
+
+/// Chain listener wrapper notifying connected and disconnected blocks on the event stream
+pub(crate) struct BlockNotifier<L: chain::Listen> {
+    listener: L,
+    channel_manager: Arc<ChannelManager>,
+    output_sweeper: Arc<OutputSweeper>,
+    event_sender: broadcast::Sender<NodeEvent>,
+    // channels and sweeps affected by the blocks that could still be reorged out, by height
+    recent_blocks: Mutex<BTreeMap<u32, (usize, usize)>>,
+}
+
+impl<L: chain::Listen> BlockNotifier<L> {
+    pub(crate) fn new(
+        listener: L,
+        channel_manager: Arc<ChannelManager>,
+        output_sweeper: Arc<OutputSweeper>,
+        event_sender: broadcast::Sender<NodeEvent>,
+    ) -> Self {
+        Self {
+            listener,
+            channel_manager,
+            output_sweeper,
+            event_sender,
+            recent_blocks: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn channels_affected(&self, txids: &HashSet<Txid>, spent: &HashSet<OutPoint>) -> usize {
+        self.channel_manager
+            .list_channels()
+            .iter()
+            .filter_map(|c| c.funding_txo)
+            .filter(|o| txids.contains(&o.txid) || spent.contains(&o.into_bitcoin_outpoint()))
+            .count()
+    }
+
+    fn sweeps_affected(&self, txids: &HashSet<Txid>) -> usize {
+        self.output_sweeper
+            .tracked_spendable_outputs()
+            .iter()
+            .filter(|o| match &o.status {
+                OutputSpendStatus::PendingFirstConfirmation {
+                    latest_spending_tx, ..
+                }
+                | OutputSpendStatus::PendingThresholdConfirmations {
+                    latest_spending_tx, ..
+                } => txids.contains(&latest_spending_tx.txid()),
+                OutputSpendStatus::PendingInitialBroadcast { .. } => false,
+            })
+            .count()
+    }
+}
+
+impl<L: chain::Listen> chain::Listen for BlockNotifier<L> {
+    fn filtered_block_connected(&self, header: &Header, txdata: &TransactionData, height: u32) {
+        // computed before the listener updates, as closed channels get dropped on confirmation
+        let txids = txdata.iter().map(|(_, tx)| tx.txid()).collect();
+        let spent = txdata
+            .iter()
+            .flat_map(|(_, tx)| tx.input.iter().map(|i| i.previous_output))
+            .collect();
+        let channels_affected = self.channels_affected(&txids, &spent);
+        let sweeps_affected = self.sweeps_affected(&txids);
+
+        self.listener
+            .filtered_block_connected(header, txdata, height);
+
+        let mut recent_blocks = self.recent_blocks.lock().unwrap();
+        recent_blocks.insert(height, (channels_affected, sweeps_affected));
+        recent_blocks.retain(|h, _| h + ANTI_REORG_DELAY > height);
+        drop(recent_blocks);
+
+        // sending only fails when there are no subscribers
+        let _ = self.event_sender.send(NodeEvent::BlockConnected {
+            height,
+            block_hash: header.block_hash().to_string(),
+            channels_affected,
+            sweeps_affected,
+        });
+    }
+
+    fn block_disconnected(&self, header: &Header, height: u32) {
+        self.listener.block_disconnected(header, height);
+
+        let (channels_affected, sweeps_affected) = self
+            .recent_blocks
+            .lock()
+            .unwrap()
+            .remove(&height)
+            .unwrap_or_default();
+
+        let _ = self.event_sender.send(NodeEvent::BlockDisconnected {
+            height,
+            block_hash: header.block_hash().to_string(),
+            channels_affected,
+            sweeps_affected,
+        });
+    }
+}
+
+pub(crate) async fn event_stream(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+) -> Response {
+    let receiver = state.event_sender.subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, receiver, state))
+}
+
+async fn stream_events(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<NodeEvent>,
+    state: Arc<AppState>,
+) {
+    loop {
+        tokio::select! {
+            _ = state.cancel_token.cancelled() => break,
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    let text = serde_json::to_string(&event).expect("valid event");
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("event stream subscriber lagged, {skipped} events skipped");
+                }
+                Err(RecvError::Closed) => break,
+            },
+            msg = socket.recv() => match msg {
+                // subscribers are not expected to send anything but close frames
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    let _ = socket.close().await;
+}
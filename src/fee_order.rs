@@ -0,0 +1,55 @@
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::{PaymentHash, PaymentPreimage};
+use std::collections::HashMap;
+
+use crate::routes::FeeOrderStatus;
+
+/// Default expiry of the invoice of a fee order
+pub(crate) const FEE_ORDER_INVOICE_EXPIRY_SECS: u32 = 3600;
+
+/// Upfront fee for opening the channel of a channel request.
+///
+/// The client prepays the fee to a hold invoice whose preimage only we know. The HTLC is held
+/// while the channel gets opened, then it's claimed once the channel is ready or failed back,
+/// refunding the client, if the channel cannot be opened.
+#[derive(Clone, Debug)]
+pub(crate) struct FeeOrderData {
+    pub(crate) request_id: String,
+    pub(crate) fee_msat: u64,
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) payment_preimage: PaymentPreimage,
+    pub(crate) invoice: String,
+    pub(crate) status: FeeOrderStatus,
+    pub(crate) created_at: u64,
+    pub(crate) temporary_channel_id: Option<String>,
+}
+
+impl_writeable_tlv_based!(FeeOrderData, {
+    (0, request_id, required),
+    (2, fee_msat, required),
+    (4, payment_hash, required),
+    (6, payment_preimage, required),
+    (8, invoice, required),
+    (10, status, required),
+    (12, created_at, required),
+    (14, temporary_channel_id, option),
+});
+
+impl FeeOrderData {
+    /// Whether the order has reached a final status
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(
+            self.status,
+            FeeOrderStatus::Completed | FeeOrderStatus::Refunded | FeeOrderStatus::Cancelled
+        )
+    }
+}
+
+/// Upfront fee orders, keyed by order ID
+pub(crate) struct FeeOrderMap {
+    pub(crate) orders: HashMap<String, FeeOrderData>,
+}
+
+impl_writeable_tlv_based!(FeeOrderMap, {
+    (0, orders, required),
+});
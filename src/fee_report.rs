@@ -0,0 +1,52 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::impl_writeable_tlv_based;
+use std::collections::HashMap;
+
+/// Routing fees and skimmed amounts accounted to a channel, kept after the channel is closed.
+///
+/// Forwards are accounted to the outbound channel. Amounts skimmed by the counterparty (e.g. an
+/// LSP taking its fee out of a payment it forwards to us) are accounted to the inbound channel.
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelFeeData {
+    pub(crate) peer_pubkey: Option<PublicKey>,
+    pub(crate) forwards: u64,
+    /// Total fee earned, including the skimmed amounts
+    pub(crate) fee_earned_msat: u64,
+    pub(crate) skimmed_forwards: u64,
+    pub(crate) skimmed_fee_msat: u64,
+    pub(crate) counterparty_skimmed_payments: u64,
+    pub(crate) counterparty_skimmed_fee_msat: u64,
+}
+
+impl_writeable_tlv_based!(ChannelFeeData, {
+    (0, peer_pubkey, option),
+    (2, forwards, required),
+    (4, fee_earned_msat, required),
+    (6, skimmed_forwards, required),
+    (8, skimmed_fee_msat, required),
+    (10, counterparty_skimmed_payments, required),
+    (12, counterparty_skimmed_fee_msat, required),
+});
+
+impl ChannelFeeData {
+    pub(crate) fn new(peer_pubkey: Option<PublicKey>) -> Self {
+        Self {
+            peer_pubkey,
+            forwards: 0,
+            fee_earned_msat: 0,
+            skimmed_forwards: 0,
+            skimmed_fee_msat: 0,
+            counterparty_skimmed_payments: 0,
+            counterparty_skimmed_fee_msat: 0,
+        }
+    }
+}
+
+/// Fee accounting, keyed by channel ID
+pub(crate) struct FeeReportMap {
+    pub(crate) channels: HashMap<String, ChannelFeeData>,
+}
+
+impl_writeable_tlv_based!(FeeReportMap, {
+    (0, channels, required),
+});
@@ -0,0 +1,49 @@
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::{ChannelId, PaymentHash};
+
+/// A payment forwarded by the node, kept after its channels are closed
+#[derive(Clone, Debug)]
+pub(crate) struct ForwardData {
+    pub(crate) prev_channel_id: ChannelId,
+    pub(crate) next_channel_id: ChannelId,
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) outbound_amount_msat: Option<u64>,
+    /// Total fee earned, including the skimmed amount
+    pub(crate) fee_earned_msat: Option<u64>,
+    pub(crate) skimmed_fee_msat: Option<u64>,
+    pub(crate) inbound_asset_id: Option<String>,
+    pub(crate) inbound_asset_amount: Option<u64>,
+    pub(crate) outbound_asset_id: Option<String>,
+    pub(crate) outbound_asset_amount: Option<u64>,
+    pub(crate) forwarded_at: u64,
+}
+
+impl_writeable_tlv_based!(ForwardData, {
+    (0, prev_channel_id, required),
+    (2, next_channel_id, required),
+    (4, payment_hash, required),
+    (6, outbound_amount_msat, option),
+    (8, fee_earned_msat, option),
+    (10, skimmed_fee_msat, option),
+    (12, inbound_asset_id, option),
+    (14, inbound_asset_amount, option),
+    (16, outbound_asset_id, option),
+    (18, outbound_asset_amount, option),
+    (20, forwarded_at, required),
+});
+
+impl ForwardData {
+    /// Amount received on the inbound channel, if LDK reported both the outbound amount and fee
+    pub(crate) fn inbound_amount_msat(&self) -> Option<u64> {
+        Some(self.outbound_amount_msat? + self.fee_earned_msat?)
+    }
+}
+
+/// Forwarded payments, in the order they have been claimed
+pub(crate) struct ForwardingHistory {
+    pub(crate) forwards: Vec<ForwardData>,
+}
+
+impl_writeable_tlv_based!(ForwardingHistory, {
+    (0, forwards, required_vec),
+});
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Error, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::disk::write_file_atomically;
+use crate::utils::get_current_timestamp;
+
+/// Append-only journal of the payment and swap status changes, in the LDK data dir
+pub(crate) const STATE_JOURNAL_FNAME: &str = "state_journal";
+
+/// What a journal record is about
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JournalEntryKind {
+    InboundPayment,
+    OutboundPayment,
+    MakerSwap,
+    TakerSwap,
+    SubmarineSwap,
+}
+
+/// A status change, written as a JSON line before it's applied
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct JournalRecord {
+    pub(crate) timestamp: u64,
+    pub(crate) kind: JournalEntryKind,
+    /// Payment hash of swaps and inbound payments, payment ID of outbound payments
+    pub(crate) id: String,
+    pub(crate) status: String,
+}
+
+/// Status of an entry in the store and whether the entry is done with
+pub(crate) struct StoredStatus {
+    pub(crate) status: String,
+    pub(crate) is_final: bool,
+}
+
+/// Write-ahead journal of the payment and swap status changes.
+///
+/// Each change is appended and synced before the in-memory maps are changed, so that after a
+/// crash the changes that didn't make it to the store can be told apart. The journal is not
+/// encrypted, holding no more than IDs and statuses.
+pub(crate) struct StateJournal {
+    file: Mutex<File>,
+}
+
+impl StateJournal {
+    /// Open the journal in the given dir, checking it against the statuses in the store.
+    ///
+    /// Changes the store doesn't reflect are logged, then only the records of the entries that are
+    /// still in flight are kept, so that the journal doesn't grow forever
+    pub(crate) fn open(
+        ldk_data_dir: &Path,
+        stored: &HashMap<(JournalEntryKind, String), StoredStatus>,
+    ) -> Result<Self, Error> {
+        let path = ldk_data_dir.join(STATE_JOURNAL_FNAME);
+        let records = read_journal(&path)?;
+
+        let mut last_statuses: HashMap<(JournalEntryKind, &str), &str> = HashMap::new();
+        for record in &records {
+            last_statuses.insert((record.kind, &record.id), &record.status);
+        }
+        let mut kept = HashMap::new();
+        for ((kind, id), journaled) in last_statuses {
+            let stored_status = stored.get(&(kind, id.to_string()));
+            match stored_status {
+                Some(s) if s.status != journaled => tracing::warn!(
+                    "Journal records {kind:?} {id} as {journaled} but the store has {}",
+                    s.status
+                ),
+                None => {
+                    tracing::warn!("Journal records {kind:?} {id} as {journaled}, not in the store")
+                }
+                _ => {}
+            }
+            kept.insert((kind, id), stored_status.is_some_and(|s| !s.is_final));
+        }
+
+        let mut data = vec![];
+        for record in records.iter().filter(|r| kept[&(r.kind, r.id.as_str())]) {
+            data.extend(serde_json::to_vec(record).map_err(Error::other)?);
+            data.push(b'\n');
+        }
+        write_file_atomically(&path, &data)?;
+
+        let file = OpenOptions::new().append(true).open(&path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append a status change of the given entry, failures being logged so that the change can
+    /// still be applied
+    pub(crate) fn record(&self, kind: JournalEntryKind, id: String, status: &impl Debug) {
+        let record = JournalRecord {
+            timestamp: get_current_timestamp(),
+            kind,
+            id,
+            status: format!("{status:?}"),
+        };
+        let mut line = serde_json::to_vec(&record).expect("valid record");
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap();
+        if let Err(e) = file.write_all(&line).and_then(|_| file.sync_data()) {
+            tracing::error!(
+                "Failed to journal {kind:?} {} as {}: {e}",
+                record.id,
+                record.status
+            );
+        }
+    }
+}
+
+/// Read the records of the journal at the given path, the last line being dropped if it was
+/// only partly written
+pub(crate) fn read_journal(path: &Path) -> Result<Vec<JournalRecord>, Error> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let mut records = vec![];
+    for line in fs::read_to_string(path)?.lines() {
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(e) => {
+                tracing::warn!("Ignoring truncated journal record: {e}");
+                break;
+            }
+        }
+    }
+    Ok(records)
+}
@@ -0,0 +1,981 @@
+use lightning::util::persist::{
+    KVStore, CHANNEL_MANAGER_PERSISTENCE_KEY, CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE, KVSTORE_NAMESPACE_KEY_ALPHABET,
+};
+use lightning_persister::fs_store::FilesystemStore;
+use native_tls::TlsConnector;
+use postgres::Client;
+use postgres_native_tls::MakeTlsConnector;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use crate::encryption::{is_encrypted, StoreCipher};
+use crate::utils::get_current_timestamp;
+use crate::vss::VssMirror;
+
+/// Name of the SQLite database holding the LDK data, in the LDK data dir
+pub(crate) const SQLITE_STORE_FNAME: &str = "kv_store.sqlite";
+
+/// File the channel manager is stored in by the filesystem store, telling it holds the node data
+const FILESYSTEM_STORE_MARKER_FNAME: &str = "manager";
+
+/// Time a SQLite write waits for the database to be unlocked by another connection
+const SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Times a Postgres query failing with a transient error is attempted before giving up
+const POSTGRES_QUERY_ATTEMPTS: u32 = 3;
+
+/// Wait before retrying a failed Postgres query, doubled at each attempt
+const POSTGRES_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Schema migrations of the Postgres store, applied in order, never to be changed once released
+const POSTGRES_MIGRATIONS: &[&str] = &[
+    "CREATE TABLE kv_store (
+        primary_namespace TEXT NOT NULL,
+        secondary_namespace TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value BYTEA NOT NULL,
+        PRIMARY KEY (primary_namespace, secondary_namespace, key)
+    )",
+    "ALTER TABLE kv_store ADD COLUMN node_id TEXT NOT NULL DEFAULT '';
+    ALTER TABLE kv_store ALTER COLUMN node_id DROP DEFAULT;
+    ALTER TABLE kv_store DROP CONSTRAINT kv_store_pkey;
+    ALTER TABLE kv_store ADD PRIMARY KEY (node_id, primary_namespace, secondary_namespace, key);",
+];
+
+/// Where the LDK data (channel monitors, channel manager, payments, swaps, etc.) is stored
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub(crate) enum StoreBackend {
+    /// One file per key under the LDK data dir
+    Filesystem,
+    /// A single SQLite database, written transactionally
+    Sqlite,
+    /// A Postgres database, given with --postgres-url
+    Postgres,
+}
+
+/// An entry of the store, as (primary namespace, secondary namespace, key, value)
+type StoreEntry = (String, String, String, Vec<u8>);
+
+/// The key of an entry of the store, as (primary namespace, secondary namespace, key)
+type StoreKey = (String, String, String);
+
+/// Key-value store over SQLite, keeping every entry in a single table
+pub(crate) struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub(crate) fn new(data_dir: &Path) -> Result<Self, Error> {
+        let connection =
+            Connection::open(data_dir.join(SQLITE_STORE_FNAME)).map_err(to_io_error)?;
+        connection
+            .execute_batch(
+                "PRAGMA synchronous = FULL;
+                CREATE TABLE IF NOT EXISTS kv_store (
+                    primary_namespace TEXT NOT NULL,
+                    secondary_namespace TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    value BLOB NOT NULL,
+                    PRIMARY KEY (primary_namespace, secondary_namespace, key)
+                );",
+            )
+            .map_err(to_io_error)?;
+        connection
+            .busy_timeout(SQLITE_BUSY_TIMEOUT)
+            .map_err(to_io_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn list_all(&self) -> Result<Vec<StoreKey>, Error> {
+        let connection = self.connection.lock().unwrap();
+        let mut stmt = connection
+            .prepare("SELECT primary_namespace, secondary_namespace, key FROM kv_store")
+            .map_err(to_io_error)?;
+        let keys = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(to_io_error)?
+            .collect::<Result<Vec<StoreKey>, _>>()
+            .map_err(to_io_error)?;
+        Ok(keys)
+    }
+
+    /// Move the given keys (without namespace) and primary namespaces out of the store, into files
+    /// laid out as the filesystem store would
+    fn archive(&self, names: &[&str], archive_dir: &Path) -> Result<(), Error> {
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.transaction().map_err(to_io_error)?;
+        for name in names {
+            let entries = {
+                let mut stmt = tx
+                    .prepare(
+                        "SELECT primary_namespace, secondary_namespace, key, value FROM kv_store
+                        WHERE (primary_namespace = '' AND key = ?1) OR primary_namespace = ?1",
+                    )
+                    .map_err(to_io_error)?;
+                let rows = stmt
+                    .query_map(params![name], |row| {
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                    })
+                    .map_err(to_io_error)?;
+                rows.collect::<Result<Vec<StoreEntry>, _>>()
+                    .map_err(to_io_error)?
+            };
+            write_archive(entries, archive_dir)?;
+            tx.execute(
+                "DELETE FROM kv_store
+                WHERE (primary_namespace = '' AND key = ?1) OR primary_namespace = ?1",
+                params![name],
+            )
+            .map_err(to_io_error)?;
+        }
+        tx.commit().map_err(to_io_error)
+    }
+}
+
+impl KVStore for SqliteStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> Result<Vec<u8>, Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT value FROM kv_store
+                WHERE primary_namespace = ?1 AND secondary_namespace = ?2 AND key = ?3",
+                params![primary_namespace, secondary_namespace, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_io_error)?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("key {key} not found")))
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT OR REPLACE INTO kv_store
+                (primary_namespace, secondary_namespace, key, value) VALUES (?1, ?2, ?3, ?4)",
+                params![primary_namespace, secondary_namespace, key, buf],
+            )
+            .map_err(to_io_error)?;
+        Ok(())
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        _lazy: bool,
+    ) -> Result<(), Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "DELETE FROM kv_store
+                WHERE primary_namespace = ?1 AND secondary_namespace = ?2 AND key = ?3",
+                params![primary_namespace, secondary_namespace, key],
+            )
+            .map_err(to_io_error)?;
+        Ok(())
+    }
+
+    fn list(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+    ) -> Result<Vec<String>, Error> {
+        let connection = self.connection.lock().unwrap();
+        let mut stmt = connection
+            .prepare(
+                "SELECT key FROM kv_store WHERE primary_namespace = ?1 AND secondary_namespace = ?2",
+            )
+            .map_err(to_io_error)?;
+        let keys = stmt
+            .query_map(params![primary_namespace, secondary_namespace], |row| {
+                row.get(0)
+            })
+            .map_err(to_io_error)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(to_io_error)?;
+        Ok(keys)
+    }
+}
+
+/// A connection to Postgres, owned by the thread the queries are sent to
+struct PostgresConnection {
+    url: String,
+    client: Client,
+}
+
+impl PostgresConnection {
+    /// Run the query, reconnecting once the connection has been closed and retrying the transient
+    /// errors a few times, which blocks only the connection thread
+    fn run<T>(
+        &mut self,
+        mut query: impl FnMut(&mut Client) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut attempt = 1;
+        let mut delay = POSTGRES_RETRY_DELAY;
+        loop {
+            if self.client.is_closed() {
+                match connect_postgres(&self.url) {
+                    Ok(client) => {
+                        tracing::info!("reconnected to Postgres");
+                        self.client = client;
+                    }
+                    Err(e) => tracing::warn!("Failed to reconnect to Postgres: {e}"),
+                }
+            }
+            match query(&mut self.client) {
+                Err(e) if attempt < POSTGRES_QUERY_ATTEMPTS && is_transient(&e) => {
+                    tracing::warn!("Retrying Postgres query: {e}");
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                    delay *= 2;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+type PostgresJob = Box<dyn FnOnce(&mut PostgresConnection) + Send>;
+
+/// Key-value store over Postgres.
+///
+/// The blocking client cannot run inside the tokio runtime, so it's owned by a dedicated thread
+/// the queries are sent to. Entries are keyed by the node they belong to, so that several nodes
+/// can share the database. TLS is used when the server supports it, or always with
+/// `sslmode=require` in the URL, the server certificate being verified only with
+/// `sslmode=verify-ca` or `sslmode=verify-full`
+pub(crate) struct PostgresStore {
+    jobs: mpsc::Sender<PostgresJob>,
+    node_id: String,
+}
+
+impl PostgresStore {
+    pub(crate) fn new(url: &str, node_id: &str) -> Result<Self, Error> {
+        let (jobs, job_receiver) = mpsc::channel::<PostgresJob>();
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let url = url.to_string();
+        let owned_node_id = node_id.to_string();
+        std::thread::spawn(move || {
+            let mut connection = match connect_postgres(&url).and_then(|mut client| {
+                migrate_postgres(&mut client, &owned_node_id).map(|_| client)
+            }) {
+                Ok(client) => PostgresConnection { url, client },
+                Err(e) => {
+                    let _ = ready_sender.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_sender.send(Ok(()));
+            // the thread exits once the store is dropped
+            for job in job_receiver {
+                job(&mut connection);
+            }
+        });
+        ready_receiver
+            .recv()
+            .map_err(|_| Error::other("the Postgres connection thread has exited"))??;
+        Ok(Self {
+            jobs,
+            node_id: node_id.to_string(),
+        })
+    }
+
+    fn run<T: Send + 'static>(
+        &self,
+        query: impl FnMut(&mut Client) -> Result<T, Error> + Send + 'static,
+    ) -> Result<T, Error> {
+        let (result_sender, result_receiver) = mpsc::channel();
+        self.jobs
+            .send(Box::new(move |connection| {
+                let _ = result_sender.send(connection.run(query));
+            }))
+            .map_err(|_| Error::other("the Postgres connection thread has exited"))?;
+        result_receiver
+            .recv()
+            .map_err(|_| Error::other("the Postgres connection thread has exited"))?
+    }
+
+    fn list_all(&self) -> Result<Vec<StoreKey>, Error> {
+        let node_id = self.node_id.clone();
+        self.run(move |client| {
+            client
+                .query(
+                    "SELECT primary_namespace, secondary_namespace, key FROM kv_store
+                    WHERE node_id = $1",
+                    &[&node_id],
+                )
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| (row.get(0), row.get(1), row.get(2)))
+                        .collect()
+                })
+                .map_err(postgres_error)
+        })
+    }
+
+    /// Move the given keys (without namespace) and primary namespaces out of the store, into files
+    /// laid out as the filesystem store would
+    fn archive(&self, names: &[&str], archive_dir: &Path) -> Result<(), Error> {
+        let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        let archive_dir = archive_dir.to_path_buf();
+        let node_id = self.node_id.clone();
+        self.run(move |client| {
+            let mut tx = client.transaction().map_err(postgres_error)?;
+            for name in &names {
+                let entries: Vec<StoreEntry> = tx
+                    .query(
+                        "SELECT primary_namespace, secondary_namespace, key, value FROM kv_store
+                        WHERE node_id = $1
+                        AND ((primary_namespace = '' AND key = $2) OR primary_namespace = $2)",
+                        &[&node_id, name],
+                    )
+                    .map_err(postgres_error)?
+                    .iter()
+                    .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+                    .collect();
+                write_archive(entries, &archive_dir)?;
+                tx.execute(
+                    "DELETE FROM kv_store
+                    WHERE node_id = $1
+                    AND ((primary_namespace = '' AND key = $2) OR primary_namespace = $2)",
+                    &[&node_id, name],
+                )
+                .map_err(postgres_error)?;
+            }
+            tx.commit().map_err(postgres_error)
+        })
+    }
+}
+
+impl KVStore for PostgresStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let (node_id, primary_namespace, secondary_namespace, owned_key) = (
+            self.node_id.clone(),
+            primary_namespace.to_string(),
+            secondary_namespace.to_string(),
+            key.to_string(),
+        );
+        self.run(move |client| {
+            client
+                .query_opt(
+                    "SELECT value FROM kv_store WHERE node_id = $1
+                    AND primary_namespace = $2 AND secondary_namespace = $3 AND key = $4",
+                    &[
+                        &node_id,
+                        &primary_namespace,
+                        &secondary_namespace,
+                        &owned_key,
+                    ],
+                )
+                .map(|row| row.map(|r| r.get(0)))
+                .map_err(postgres_error)
+        })?
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("key {key} not found")))
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        let (node_id, primary_namespace, secondary_namespace, key, buf) = (
+            self.node_id.clone(),
+            primary_namespace.to_string(),
+            secondary_namespace.to_string(),
+            key.to_string(),
+            buf.to_vec(),
+        );
+        self.run(move |client| {
+            client
+                .execute(
+                    "INSERT INTO kv_store
+                (node_id, primary_namespace, secondary_namespace, key, value)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (node_id, primary_namespace, secondary_namespace, key)
+                DO UPDATE SET value = EXCLUDED.value",
+                    &[
+                        &node_id,
+                        &primary_namespace,
+                        &secondary_namespace,
+                        &key,
+                        &buf,
+                    ],
+                )
+                .map_err(postgres_error)
+        })?;
+        Ok(())
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        _lazy: bool,
+    ) -> Result<(), Error> {
+        let (node_id, primary_namespace, secondary_namespace, key) = (
+            self.node_id.clone(),
+            primary_namespace.to_string(),
+            secondary_namespace.to_string(),
+            key.to_string(),
+        );
+        self.run(move |client| {
+            client
+                .execute(
+                    "DELETE FROM kv_store WHERE node_id = $1
+                AND primary_namespace = $2 AND secondary_namespace = $3 AND key = $4",
+                    &[&node_id, &primary_namespace, &secondary_namespace, &key],
+                )
+                .map_err(postgres_error)
+        })?;
+        Ok(())
+    }
+
+    fn list(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+    ) -> Result<Vec<String>, Error> {
+        let (node_id, primary_namespace, secondary_namespace) = (
+            self.node_id.clone(),
+            primary_namespace.to_string(),
+            secondary_namespace.to_string(),
+        );
+        self.run(move |client| {
+            client
+                .query(
+                    "SELECT key FROM kv_store
+                    WHERE node_id = $1 AND primary_namespace = $2 AND secondary_namespace = $3",
+                    &[&node_id, &primary_namespace, &secondary_namespace],
+                )
+                .map(|rows| rows.iter().map(|row| row.get(0)).collect())
+                .map_err(postgres_error)
+        })
+    }
+}
+
+/// Connect to Postgres, verifying the server certificate only with `sslmode=verify-ca` (and its
+/// hostname with `sslmode=verify-full`) as libpq does, so that servers with a self-signed
+/// certificate can be used with the other modes
+fn connect_postgres(url: &str) -> Result<Client, Error> {
+    let (url, ssl_mode) = split_postgres_ssl_mode(url);
+    let verify_ca = matches!(ssl_mode.as_deref(), Some("verify-ca" | "verify-full"));
+    let verify_hostname = ssl_mode.as_deref() == Some("verify-full");
+    let connector = TlsConnector::builder()
+        .danger_accept_invalid_certs(!verify_ca)
+        .danger_accept_invalid_hostnames(!verify_hostname)
+        .build()
+        .map_err(to_io_error)?;
+    Client::connect(&url, MakeTlsConnector::new(connector)).map_err(postgres_error)
+}
+
+/// The `sslmode` of the URL, along with the URL the client accepts, which doesn't know the modes
+/// verifying the server certificate, so they're replaced with `require`
+fn split_postgres_ssl_mode(url: &str) -> (String, Option<String>) {
+    let Some((base, query)) = url.split_once('?') else {
+        return (url.to_string(), None);
+    };
+    let mut ssl_mode = None;
+    let params: Vec<String> = query
+        .split('&')
+        .map(|param| match param.strip_prefix("sslmode=") {
+            Some(mode @ ("verify-ca" | "verify-full")) => {
+                ssl_mode = Some(mode.to_string());
+                "sslmode=require".to_string()
+            }
+            Some(mode) => {
+                ssl_mode = Some(mode.to_string());
+                param.to_string()
+            }
+            None => param.to_string(),
+        })
+        .collect();
+    (format!("{base}?{}", params.join("&")), ssl_mode)
+}
+
+/// Apply the schema migrations the database is missing.
+///
+/// The version table is locked, so that instances sharing the database don't migrate it twice.
+/// The entries written before they were keyed by node belong to the first node opening the store
+fn migrate_postgres(client: &mut Client, node_id: &str) -> Result<(), Error> {
+    let mut tx = client.transaction().map_err(postgres_error)?;
+    tx.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL);
+        LOCK TABLE schema_version IN EXCLUSIVE MODE;",
+    )
+    .map_err(postgres_error)?;
+    let version: i32 = tx
+        .query_one("SELECT COALESCE(MAX(version), 0) FROM schema_version", &[])
+        .map_err(postgres_error)?
+        .get(0);
+    let version = version as usize;
+    if version > POSTGRES_MIGRATIONS.len() {
+        return Err(Error::other(format!(
+            "the database schema (version {version}) is newer than the supported one"
+        )));
+    }
+    for (idx, migration) in POSTGRES_MIGRATIONS.iter().enumerate().skip(version) {
+        tracing::info!("migrating the Postgres store to schema version {}", idx + 1);
+        tx.batch_execute(migration).map_err(postgres_error)?;
+        tx.execute(
+            "INSERT INTO schema_version (version) VALUES ($1)",
+            &[&(idx as i32 + 1)],
+        )
+        .map_err(postgres_error)?;
+    }
+    tx.execute(
+        "UPDATE kv_store SET node_id = $1 WHERE node_id = ''",
+        &[&node_id],
+    )
+    .map_err(postgres_error)?;
+    tx.commit().map_err(postgres_error)
+}
+
+/// Write the archived entries as files laid out as the filesystem store would
+fn write_archive(entries: Vec<StoreEntry>, archive_dir: &Path) -> Result<(), Error> {
+    for (primary_namespace, secondary_namespace, key, value) in entries {
+        let path: PathBuf = [&primary_namespace, &secondary_namespace, &key]
+            .iter()
+            .filter(|p| !p.is_empty())
+            .fold(archive_dir.to_path_buf(), |path, p| path.join(p));
+        fs::create_dir_all(path.parent().expect("archive path has a parent"))?;
+        fs::write(path, value)?;
+    }
+    Ok(())
+}
+
+fn is_store_key(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| KVSTORE_NAMESPACE_KEY_ALPHABET.contains(c))
+}
+
+/// Keys of the entries of the filesystem store in the given namespace dir
+fn list_filesystem_namespace(
+    dir: &Path,
+    primary_namespace: &str,
+    keys: &mut Vec<StoreKey>,
+) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_store_key(&name) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            for sub_entry in fs::read_dir(entry.path())? {
+                let sub_entry = sub_entry?;
+                let key = sub_entry.file_name().to_string_lossy().to_string();
+                if is_store_key(&key) && sub_entry.file_type()?.is_file() {
+                    keys.push((primary_namespace.to_string(), name.clone(), key));
+                }
+            }
+        } else {
+            keys.push((primary_namespace.to_string(), String::new(), name));
+        }
+    }
+    Ok(())
+}
+
+fn to_io_error(e: impl Display) -> Error {
+    Error::other(e.to_string())
+}
+
+/// Map the errors of a Postgres query, telling apart the ones due to the connection being lost as
+/// they can be retried once the client has reconnected
+fn postgres_error(e: postgres::Error) -> Error {
+    let connection_lost =
+        e.is_closed() || std::error::Error::source(&e).is_some_and(|s| s.is::<Error>());
+    if connection_lost {
+        Error::new(ErrorKind::ConnectionReset, e.to_string())
+    } else {
+        to_io_error(e)
+    }
+}
+
+fn is_transient(e: &Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::Interrupted
+            | ErrorKind::TimedOut
+            | ErrorKind::WouldBlock
+            | ErrorKind::ConnectionReset
+    )
+}
+
+/// Local store of the LDK data, on the backend chosen at startup
+enum LocalStore {
+    Filesystem(FilesystemStore),
+    Sqlite(SqliteStore),
+    Postgres(PostgresStore),
+}
+
+impl LocalStore {
+    /// Open the store in the given LDK data dir, refusing to switch the backend of existing data
+    fn new(
+        backend: StoreBackend,
+        postgres_url: Option<&str>,
+        data_dir: &Path,
+        node_id: &str,
+    ) -> Result<Self, Error> {
+        let has_sqlite_store = data_dir.join(SQLITE_STORE_FNAME).exists();
+        let has_filesystem_store = data_dir.join(FILESYSTEM_STORE_MARKER_FNAME).exists();
+        match backend {
+            StoreBackend::Filesystem if has_sqlite_store => Err(Error::other(
+                "the node data is stored in SQLite, start with --store-backend sqlite",
+            )),
+            StoreBackend::Filesystem => Ok(Self::Filesystem(FilesystemStore::new(
+                data_dir.to_path_buf(),
+            ))),
+            StoreBackend::Sqlite if has_filesystem_store && !has_sqlite_store => Err(Error::other(
+                "the node data is stored on the filesystem, start with --store-backend filesystem",
+            )),
+            StoreBackend::Sqlite => Ok(Self::Sqlite(SqliteStore::new(data_dir)?)),
+            StoreBackend::Postgres if has_filesystem_store || has_sqlite_store => Err(Error::other(
+                "the node data is stored locally, start with the backend it has been created with",
+            )),
+            StoreBackend::Postgres => Ok(Self::Postgres(PostgresStore::new(
+                postgres_url.expect("checked when parsing args"),
+                node_id,
+            )?)),
+        }
+    }
+
+    /// Move the given keys (without namespace) and primary namespaces to the given dir
+    fn archive(&self, data_dir: &Path, names: &[&str], archive_dir: &Path) -> Result<(), Error> {
+        match self {
+            Self::Filesystem(_) => {
+                for name in names {
+                    let path = data_dir.join(name);
+                    if path.exists() {
+                        fs::rename(&path, archive_dir.join(name))?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Sqlite(store) => store.archive(names, archive_dir),
+            Self::Postgres(store) => store.archive(names, archive_dir),
+        }
+    }
+
+    /// Keys of all the entries, the files and dirs of the data dir `is_outside_store` tells apart
+    /// being skipped when the store is on the filesystem
+    fn list_all(
+        &self,
+        data_dir: &Path,
+        is_outside_store: impl Fn(&str) -> bool,
+    ) -> Result<Vec<StoreKey>, Error> {
+        match self {
+            Self::Filesystem(_) => {
+                let mut keys = vec![];
+                for entry in fs::read_dir(data_dir)? {
+                    let entry = entry?;
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if !is_store_key(&name) || is_outside_store(&name) {
+                        continue;
+                    }
+                    if entry.file_type()?.is_dir() {
+                        list_filesystem_namespace(&entry.path(), &name, &mut keys)?;
+                    } else {
+                        keys.push((String::new(), String::new(), name));
+                    }
+                }
+                Ok(keys)
+            }
+            Self::Sqlite(store) => store.list_all(),
+            Self::Postgres(store) => store.list_all(),
+        }
+    }
+}
+
+impl KVStore for LocalStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Filesystem(store) => store.read(primary_namespace, secondary_namespace, key),
+            Self::Sqlite(store) => store.read(primary_namespace, secondary_namespace, key),
+            Self::Postgres(store) => store.read(primary_namespace, secondary_namespace, key),
+        }
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        match self {
+            Self::Filesystem(store) => {
+                store.write(primary_namespace, secondary_namespace, key, buf)
+            }
+            Self::Sqlite(store) => store.write(primary_namespace, secondary_namespace, key, buf),
+            Self::Postgres(store) => store.write(primary_namespace, secondary_namespace, key, buf),
+        }
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        lazy: bool,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Filesystem(store) => {
+                store.remove(primary_namespace, secondary_namespace, key, lazy)
+            }
+            Self::Sqlite(store) => store.remove(primary_namespace, secondary_namespace, key, lazy),
+            Self::Postgres(store) => {
+                store.remove(primary_namespace, secondary_namespace, key, lazy)
+            }
+        }
+    }
+
+    fn list(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+    ) -> Result<Vec<String>, Error> {
+        match self {
+            Self::Filesystem(store) => store.list(primary_namespace, secondary_namespace),
+            Self::Sqlite(store) => store.list(primary_namespace, secondary_namespace),
+            Self::Postgres(store) => store.list(primary_namespace, secondary_namespace),
+        }
+    }
+}
+
+/// The store of the LDK data, optionally encrypted and mirrored to a VSS server
+pub(crate) struct NodeStore {
+    local: LocalStore,
+    cipher: Option<StoreCipher>,
+    mirror: Option<VssMirror>,
+    /// Time of the last write to each store, see [`Self::last_writes`]
+    last_writes: Mutex<HashMap<String, u64>>,
+}
+
+impl NodeStore {
+    /// Open the store in the given LDK data dir, refusing to switch the backend of existing data.
+    ///
+    /// The node ID tells apart the entries of the nodes sharing a database. When the local store
+    /// is empty, the data found on the mirror is restored into it
+    pub(crate) fn new(
+        backend: StoreBackend,
+        postgres_url: Option<&str>,
+        data_dir: &Path,
+        node_id: &str,
+        cipher: Option<StoreCipher>,
+        mirror: Option<VssMirror>,
+    ) -> Result<Self, Error> {
+        let store = Self {
+            local: LocalStore::new(backend, postgres_url, data_dir, node_id)?,
+            cipher,
+            mirror,
+            last_writes: Mutex::new(HashMap::new()),
+        };
+        let channel_manager = store.local.read(
+            CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE,
+            CHANNEL_MANAGER_PERSISTENCE_KEY,
+        );
+        match (&channel_manager, &store.cipher) {
+            (Ok(data), None) if is_encrypted(data) => {
+                return Err(Error::other(
+                    "the node data is encrypted, start with --encrypt-storage",
+                ));
+            }
+            // unencrypted entries are refused when read, unless they're being migrated
+            (Ok(data), Some(cipher)) if is_encrypted(data) => {
+                cipher.decrypt(data.clone())?;
+            }
+            _ => {}
+        }
+        if let (Err(_), Some(mirror)) = (&channel_manager, &store.mirror) {
+            let entries = mirror.fetch_all()?;
+            if !entries.is_empty() {
+                tracing::info!("restoring {} entries from VSS", entries.len());
+            }
+            for (primary_namespace, secondary_namespace, key, value) in entries {
+                store.write_local(&primary_namespace, &secondary_namespace, &key, &value)?;
+            }
+        }
+        Ok(store)
+    }
+
+    /// Write an entry to the local store.
+    ///
+    /// The filesystem store writes to a temporary file, synced and then renamed over the entry,
+    /// while the database stores commit each write, so a write that succeeds is durable. Writes
+    /// failing with a transient error are retried by the Postgres connection thread, while SQLite
+    /// waits for the database to be unlocked
+    fn write_local(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        let encrypted;
+        let buf = match &self.cipher {
+            Some(cipher) => {
+                encrypted = cipher.encrypt(buf)?;
+                &encrypted
+            }
+            None => buf,
+        };
+        self.local
+            .write(primary_namespace, secondary_namespace, key, buf)
+    }
+
+    /// Encrypt the entries written before encryption was enabled, returning how many there were
+    pub(crate) fn encrypt_plaintext_entries(
+        &self,
+        data_dir: &Path,
+        is_outside_store: impl Fn(&str) -> bool,
+    ) -> Result<usize, Error> {
+        if self.cipher.is_none() {
+            return Ok(0);
+        }
+        let mut encrypted = 0;
+        for (primary_namespace, secondary_namespace, key) in
+            self.local.list_all(data_dir, is_outside_store)?
+        {
+            let data = self
+                .local
+                .read(&primary_namespace, &secondary_namespace, &key)?;
+            if !is_encrypted(&data) {
+                self.write_local(&primary_namespace, &secondary_namespace, &key, &data)?;
+                encrypted += 1;
+            }
+        }
+        Ok(encrypted)
+    }
+
+    /// Cipher of the entries, also used for the data files kept outside of the store
+    pub(crate) fn cipher(&self) -> Option<&StoreCipher> {
+        self.cipher.as_ref()
+    }
+
+    /// Time of the last successful write to each store since the store has been opened.
+    ///
+    /// Entries in a primary namespace (e.g. the channel monitors) belong to the store named after
+    /// it, while the other ones (e.g. the channel manager) are stores on their own
+    pub(crate) fn last_writes(&self) -> HashMap<String, u64> {
+        self.last_writes.lock().unwrap().clone()
+    }
+
+    /// Move the given keys (without namespace) and primary namespaces to the given dir, removing
+    /// them from the mirror
+    pub(crate) fn archive(
+        &self,
+        data_dir: &Path,
+        names: &[&str],
+        archive_dir: &Path,
+    ) -> Result<(), Error> {
+        self.local.archive(data_dir, names, archive_dir)?;
+        if let Some(mirror) = &self.mirror {
+            mirror.delete(names);
+        }
+        Ok(())
+    }
+}
+
+impl KVStore for NodeStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let data = self
+            .local
+            .read(primary_namespace, secondary_namespace, key)?;
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(data),
+            None => Ok(data),
+        }
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        self.write_local(primary_namespace, secondary_namespace, key, buf)?;
+        let store_name = if primary_namespace.is_empty() {
+            key
+        } else {
+            primary_namespace
+        };
+        self.last_writes
+            .lock()
+            .unwrap()
+            .insert(store_name.to_string(), get_current_timestamp());
+        if let Some(mirror) = &self.mirror {
+            mirror.mirror(primary_namespace, secondary_namespace, key, Some(buf));
+        }
+        Ok(())
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        lazy: bool,
+    ) -> Result<(), Error> {
+        self.local
+            .remove(primary_namespace, secondary_namespace, key, lazy)?;
+        if let Some(mirror) = &self.mirror {
+            mirror.mirror(primary_namespace, secondary_namespace, key, None);
+        }
+        Ok(())
+    }
+
+    fn list(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+    ) -> Result<Vec<String>, Error> {
+        self.local.list(primary_namespace, secondary_namespace)
+    }
+}
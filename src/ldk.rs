@@ -1,25 +1,34 @@
-use amplify::map;
+use amplify::{map, s};
+use bitcoin::blockdata::constants::WITNESS_SCALE_FACTOR;
 use bitcoin::blockdata::locktime::absolute::LockTime;
+use bitcoin::hashes::sha256::Hash as Sha256;
+use bitcoin::hashes::Hash;
 use bitcoin::network::constants::Network;
 use bitcoin::psbt::Psbt;
-use bitcoin::secp256k1::Secp256k1;
-use bitcoin::{BlockHash, TxOut};
+use bitcoin::secp256k1::{PublicKey, Secp256k1};
+use bitcoin::{BlockHash, OutPoint, Sequence, Transaction, TxIn, TxOut, Txid, Witness};
 use bitcoin_bech32::WitnessProgram;
 use lightning::chain::{chainmonitor, ChannelMonitorUpdateStatus};
 use lightning::chain::{BestBlock, Filter, Watch};
-use lightning::events::bump_transaction::{BumpTransactionEventHandler, Wallet};
-use lightning::events::{Event, PaymentFailureReason, PaymentPurpose};
+use lightning::events::bump_transaction::{
+    BumpTransactionEvent, BumpTransactionEventHandler, Utxo, Wallet, WalletSource,
+};
+use lightning::events::{
+    ClosureReason, Event, HTLCDestination, PaymentFailureReason, PaymentPurpose,
+};
 use lightning::ln::channelmanager::{self, PaymentId, RecentPaymentDetails};
 use lightning::ln::channelmanager::{
-    ChainParameters, ChannelManagerReadArgs, SimpleArcChannelManager,
+    ChainParameters, ChannelDetails, ChannelManagerReadArgs, SimpleArcChannelManager,
+};
+use lightning::ln::peer_handler::{
+    IgnoringMessageHandler, MessageHandler, PeerManager as LdkPeerManager,
 };
-use lightning::ln::peer_handler::{IgnoringMessageHandler, MessageHandler, SimpleArcPeerManager};
 use lightning::ln::{ChannelId, PaymentHash, PaymentPreimage, PaymentSecret};
 use lightning::onion_message::messenger::{DefaultMessageRouter, SimpleArcOnionMessenger};
 use lightning::rgb_utils::{
-    get_rgb_channel_info_pending, is_channel_rgb, parse_rgb_payment_info, read_rgb_transfer_info,
-    update_rgb_channel_amount, STATIC_BLINDING, WALLET_ACCOUNT_XPUB_FNAME,
-    WALLET_FINGERPRINT_FNAME,
+    get_rgb_channel_info_pending, get_rgb_payment_info_path, is_channel_rgb,
+    parse_rgb_payment_info, read_rgb_transfer_info, update_rgb_channel_amount, STATIC_BLINDING,
+    WALLET_ACCOUNT_XPUB_FNAME, WALLET_FINGERPRINT_FNAME,
 };
 use lightning::routing::gossip;
 use lightning::routing::gossip::{NodeId, P2PGossipSync};
@@ -28,10 +37,13 @@ use lightning::routing::scoring::{ProbabilisticScorer, ProbabilisticScoringFeePa
 use lightning::sign::{
     EntropySource, InMemorySigner, KeysManager, OutputSpender, SpendableOutputDescriptor,
 };
-use lightning::util::config::UserConfig;
+use lightning::util::config::{ChannelHandshakeConfig, UserConfig};
 use lightning::util::persist::{
-    KVStore, MonitorUpdatingPersister, OUTPUT_SWEEPER_PERSISTENCE_KEY,
+    KVStore, MonitorUpdatingPersister, CHANNEL_MANAGER_PERSISTENCE_KEY,
+    CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE, CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE,
+    NETWORK_GRAPH_PERSISTENCE_KEY, OUTPUT_SWEEPER_PERSISTENCE_KEY,
     OUTPUT_SWEEPER_PERSISTENCE_PRIMARY_NAMESPACE, OUTPUT_SWEEPER_PERSISTENCE_SECONDARY_NAMESPACE,
+    SCORER_PERSISTENCE_KEY,
 };
 use lightning::util::ser::{ReadableArgs, Writeable};
 use lightning::util::sweep as ldk_sweep;
@@ -39,15 +51,15 @@ use lightning::{chain, impl_writeable_tlv_based};
 use lightning_background_processor::{process_events_async, GossipSync};
 use lightning_block_sync::init;
 use lightning_block_sync::poll;
+use lightning_block_sync::BlockSource;
 use lightning_block_sync::SpvClient;
 use lightning_block_sync::UnboundedCache;
 use lightning_net_tokio::SocketDescriptor;
-use lightning_persister::fs_store::FilesystemStore;
 use rand::{thread_rng, Rng, RngCore};
 use rgb_lib::{
     bdk::keys::{bip39::Mnemonic, DerivableKey, ExtendedKey},
     bitcoin::{
-        bip32::{ChildNumber, ExtendedPrivKey},
+        bip32::{ChildNumber, ExtendedPrivKey, ExtendedPubKey},
         psbt::PartiallySignedTransaction as RgbLibPsbt,
         secp256k1::Secp256k1 as Secp256k1_30,
         ScriptBuf,
@@ -55,46 +67,90 @@ use rgb_lib::{
     utils::{get_account_xpub, recipient_id_from_script_buf, script_buf_from_recipient_id},
     wallet::{
         rust_only::{AssetColoringInfo, ColoringInfo},
-        AssetIface, DatabaseType, Outpoint, Recipient, TransportEndpoint, Wallet as RgbLibWallet,
-        WalletData, WitnessData,
+        DatabaseType, Outpoint, Recipient, TransportEndpoint, Wallet as RgbLibWallet, WalletData,
+        WitnessData,
     },
     AssetSchema, ConsignmentExt, ContractId, FileContent, RgbTransfer,
 };
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::fs;
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::net::ToSocketAddrs;
-use std::net::{SocketAddr, TcpListener};
+use std::net::TcpListener;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime};
 use time::OffsetDateTime;
-use tokio::runtime::Handle;
+use tokio::sync::oneshot;
 use tokio::sync::watch::Sender;
 use tokio::task::JoinHandle;
 
 use crate::bitcoind::BitcoindClient;
+use crate::channel_conversion::{
+    run_channel_conversions, ChannelConversionData, ChannelConversionMap,
+};
+use crate::channel_request::{ChannelRequestData, ChannelRequestMap};
 use crate::disk::{
-    self, FilesystemLogger, CHANNEL_IDS_FNAME, CHANNEL_PEER_DATA, INBOUND_PAYMENTS_FNAME,
-    MAKER_SWAPS_FNAME, OUTBOUND_PAYMENTS_FNAME, OUTPUT_SPENDER_TXES, TAKER_SWAPS_FNAME,
+    self, FilesystemLogger, ASSET_HTLC_LIMITS_FNAME, CHANNEL_ANNOUNCEMENT_FNAME,
+    CHANNEL_CLOSURES_FNAME, CHANNEL_CONVERSIONS_FNAME, CHANNEL_IDS_FNAME, CHANNEL_PEER_DATA,
+    CHANNEL_REQUESTS_FNAME, CHANNEL_TRANSFERS_FNAME, CLOSE_ADDRESSES_FNAME,
+    CONSIGNMENT_PROXIES_FNAME, FEE_ORDERS_FNAME, FEE_REPORT_FNAME, FORWARDING_HISTORY_FNAME,
+    INBOUND_PAYMENTS_FNAME, INBOUND_PAYMENTS_NAMESPACE, LNURL_WITHDRAWS_FNAME, MAKER_SWAPS_FNAME,
+    NODE_ID_ROTATION_FNAME, OUTBOUND_PAYMENTS_FNAME, OUTBOUND_PAYMENTS_NAMESPACE,
+    OUTPUT_SPENDER_TXES, PEER_POLICY_FNAME, PROXY_PINS_FNAME, RELAY_KEYS_FNAME,
+    SCHEDULED_BACKUPS_FNAME, SCHEDULES_FNAME, SPEND_HISTORY_FNAME, SUBMARINE_SWAPS_FNAME,
+    SWAP_OFFERS_FNAME, TAKER_SWAPS_FNAME,
 };
+use crate::encryption::StoreCipher;
 use crate::error::APIError;
+use crate::events::{BlockNotifier, NodeEvent};
+use crate::fee_order::{FeeOrderData, FeeOrderMap};
+use crate::fee_report::{ChannelFeeData, FeeReportMap};
+use crate::forwarding_history::{ForwardData, ForwardingHistory};
+use crate::journal::{JournalEntryKind, StateJournal, StoredStatus};
+use crate::kv_store::NodeStore;
+use crate::lease::run_lease_renewal;
+use crate::locks::{lock, log_lock_stats, AuditedGuard};
+use crate::peer_messages::PeerMessageHandler;
+use crate::peer_policy::{num_peers, run_inbound_connection};
+use crate::proxy::{
+    check_proxy_pins, usable_proxy_endpoints, ConsignmentProxyMap, ProxyPin, ProxyPinMap,
+};
+use crate::refresh::run_rgb_refresh;
 use crate::rgb::{get_rgb_channel_info_optional, RgbLibWalletWrapper};
-use crate::routes::{HTLCStatus, SwapStatus, DUST_LIMIT_MSAT};
-use crate::swap::SwapData;
+use crate::rotation::{archive_ldk_state, derive_ldk_seed, NodeIdRotation};
+use crate::routes::{
+    ChannelConversionStatus, ChannelRequestStatus, ChannelTransferKind, FeeOrderStatus, HTLCStatus,
+    NodeIdRotationStatus, SubmarineSwapStatus, SwapStatus, DUST_LIMIT_MSAT,
+};
+use crate::schedule::{
+    run_scheduler, ScheduleData, ScheduleMap, ScheduleRunData, MAX_SCHEDULE_RUNS,
+};
+use crate::scheduled_backup::{derive_backup_key, run_scheduled_backups, ScheduledBackupData};
+use crate::snapshot::{SnapshotTracker, StateSnapshot};
+use crate::submarine_swap::{run_submarine_swaps, SubmarineSwapData, SubmarineSwapMap};
+use crate::swap::{SwapData, SwapTransitionError};
+use crate::swap_offer::{announce_swap_pairs, run_swap_offers, SwapOfferBook};
 use crate::utils::{
     connect_peer_if_necessary, do_connect_peer, get_current_timestamp, hex_str, AppState,
     StaticState, UnlockedAppState,
 };
+use crate::vss::{derive_vss_key, VssMirror};
 
 pub(crate) const FEE_RATE: f32 = 7.0;
+/// Bounds of the fee rates (in sat/vB) accepted for on-chain sends and channel fundings
+pub(crate) const MIN_FEE_RATE: f32 = 1.0;
+pub(crate) const MAX_FEE_RATE: f32 = 1000.0;
 pub(crate) const UTXO_SIZE_SAT: u32 = 32000;
 pub(crate) const MIN_CHANNEL_CONFIRMATIONS: u8 = 6;
 
+// witness weight of a P2WPKH key spend, the largest among the inputs of the wallet
+const MAX_INPUT_WITNESS_WEIGHT: u64 = 108;
+
 pub(crate) struct LdkBackgroundServices {
     stop_processing: Arc<AtomicBool>,
     peer_manager: Arc<PeerManager>,
@@ -108,6 +164,17 @@ pub(crate) struct PaymentInfo {
     pub(crate) secret: Option<PaymentSecret>,
     pub(crate) status: HTLCStatus,
     pub(crate) amt_msat: Option<u64>,
+    pub(crate) custom_records: Vec<(u64, Vec<u8>)>,
+    pub(crate) retry_attempts: Option<u32>,
+    pub(crate) retry_timeout_secs: Option<u64>,
+    pub(crate) failed_attempts: u32,
+    pub(crate) expires_at: Option<u64>,
+    /// RGB amount received, recorded when an inbound payment is claimed
+    pub(crate) asset_amount: Option<u64>,
+    /// Whether this is a spontaneous payment, sent without an invoice
+    pub(crate) keysend: bool,
+    /// Height at which LDK fails back the HTLCs held for a hold invoice
+    pub(crate) claim_deadline: Option<u32>,
 }
 
 impl_writeable_tlv_based!(PaymentInfo, {
@@ -115,6 +182,14 @@ impl_writeable_tlv_based!(PaymentInfo, {
     (2, secret, required),
     (4, status, required),
     (6, amt_msat, required),
+    (7, custom_records, optional_vec),
+    (9, retry_attempts, option),
+    (11, retry_timeout_secs, option),
+    (13, failed_attempts, (default_value, 0u32)),
+    (15, expires_at, option),
+    (17, asset_amount, option),
+    (19, keysend, (default_value, false)),
+    (21, claim_deadline, option),
 });
 
 pub(crate) struct InboundPaymentInfoStorage {
@@ -125,6 +200,19 @@ impl_writeable_tlv_based!(InboundPaymentInfoStorage, {
     (0, payments, required),
 });
 
+/// Time between the attempts to write the inbound payments that failed to be persisted
+const INBOUND_PAYMENTS_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of attempts to write the inbound payments before answering a flush
+const INBOUND_PAYMENTS_FLUSH_ATTEMPTS: u8 = 5;
+
+/// Message for the thread persisting the inbound payments
+pub(crate) enum InboundPaymentUpdate {
+    /// The payment with the given hash has been added or modified
+    Changed(PaymentHash),
+    /// Notify once the changes sent so far have been persisted
+    Flush(oneshot::Sender<()>),
+}
+
 pub(crate) struct OutboundPaymentInfoStorage {
     pub(crate) payments: HashMap<PaymentId, PaymentInfo>,
 }
@@ -149,65 +237,641 @@ impl_writeable_tlv_based!(ChannelIdsMap, {
     (0, channel_ids, required),
 });
 
+/// Max RGB amount accepted in a single inbound HTLC, by channel
+pub(crate) struct AssetHtlcLimitMap {
+    pub(crate) limits: HashMap<ChannelId, u64>,
+}
+
+impl_writeable_tlv_based!(AssetHtlcLimitMap, {
+    (0, limits, required),
+});
+
+/// RGB transfer made by the node for one of its channels
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelTransferData {
+    pub(crate) kind: ChannelTransferKind,
+    /// Set for fundings once the channel is pending
+    pub(crate) channel_id: Option<ChannelId>,
+    /// TXs of the closed channels whose outputs are swept, for sweeps
+    pub(crate) closing_txids: Vec<String>,
+    /// Blinding of the seal of the allocation moved by the transfer, unless left to rgb-lib
+    pub(crate) blinding: Option<u64>,
+}
+
+impl_writeable_tlv_based!(ChannelTransferData, {
+    (0, kind, required),
+    (2, channel_id, option),
+    (4, closing_txids, required_vec),
+    (6, blinding, option),
+});
+
+/// Channel transfers, keyed by TXID
+pub(crate) struct ChannelTransferMap {
+    pub(crate) transfers: HashMap<String, ChannelTransferData>,
+}
+
+impl_writeable_tlv_based!(ChannelTransferMap, {
+    (0, transfers, required),
+});
+
+/// Address committed to when opening a channel, the only one it can be cooperatively closed to
+pub(crate) struct CloseAddressMap {
+    pub(crate) addresses: HashMap<ChannelId, String>,
+}
+
+impl_writeable_tlv_based!(CloseAddressMap, {
+    (0, addresses, required),
+});
+
+/// Why a channel has been closed, kept once the channel manager no longer lists it
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelClosure {
+    pub(crate) counterparty_node_id: Option<PublicKey>,
+    pub(crate) reason: String,
+    /// Error the channel has been closed with, e.g. a cooperative close whose fee negotiation
+    /// didn't converge
+    pub(crate) error: Option<String>,
+    pub(crate) closed_at: u64,
+}
+
+impl_writeable_tlv_based!(ChannelClosure, {
+    (0, counterparty_node_id, option),
+    (2, reason, required),
+    (4, error, option),
+    (6, closed_at, required),
+});
+
+pub(crate) struct ChannelClosureMap {
+    pub(crate) closures: HashMap<ChannelId, ChannelClosure>,
+}
+
+impl_writeable_tlv_based!(ChannelClosureMap, {
+    (0, closures, required),
+});
+
+/// A withdraw offered via LNURL-withdraw, which can be claimed only once with its k1
+#[derive(Clone, Debug)]
+pub(crate) struct LnurlWithdraw {
+    pub(crate) min_withdrawable_msat: u64,
+    pub(crate) max_withdrawable_msat: u64,
+    pub(crate) description: String,
+    pub(crate) contract_id: Option<ContractId>,
+    pub(crate) max_asset_amount: Option<u64>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: Option<u64>,
+    pub(crate) claimed_payment_hash: Option<PaymentHash>,
+}
+
+impl_writeable_tlv_based!(LnurlWithdraw, {
+    (0, min_withdrawable_msat, required),
+    (2, max_withdrawable_msat, required),
+    (4, description, required),
+    (6, contract_id, option),
+    (8, max_asset_amount, option),
+    (10, created_at, required),
+    (12, expires_at, option),
+    (14, claimed_payment_hash, option),
+});
+
+pub(crate) struct LnurlWithdrawMap {
+    pub(crate) withdraws: HashMap<String, LnurlWithdraw>,
+}
+
+impl_writeable_tlv_based!(LnurlWithdrawMap, {
+    (0, withdraws, required),
+});
+
+/// Keys the node relays HTLCs with while locked, kept in memory from the last unlock.
+///
+/// They hold the LDK seed, needed to sign for existing channels, and the wallet account xpub, used
+/// to derive the addresses funds are swept to, but never the mnemonic. As the LDK seed controls
+/// the channel funds they're never written to the storage dir
+#[derive(Clone)]
+pub(crate) struct RelayKeys {
+    pub(crate) ldk_seed: [u8; 32],
+    pub(crate) account_xpub: String,
+    /// Key of the VSS mirror, so that it's kept up to date while relaying
+    pub(crate) vss_key: Option<[u8; 32]>,
+    /// Key of the encrypted LDK data, when storage encryption is enabled
+    pub(crate) storage_key: Option<[u8; 32]>,
+}
+
+/// Key material LDK is started with
+pub(crate) enum LdkKeys {
+    /// Full access, after an unlock with the node password, with the storage key when the LDK
+    /// data is encrypted
+    Mnemonic(Mnemonic, Option<[u8; 32]>),
+    /// Relay-only access, with a watch-only RGB wallet
+    Relay(RelayKeys),
+}
+
+/// Destination for the change of a channel funding, requested when opening the channel
+pub(crate) struct FundingChange {
+    pub(crate) script: ScriptBuf,
+    pub(crate) outpoint_sender: oneshot::Sender<OutPoint>,
+}
+
+/// Channels opened together and funded by a single transaction
+pub(crate) struct FundingBatch {
+    pub(crate) utxos: Vec<Utxo>,
+    pub(crate) temporary_channel_ids: Vec<ChannelId>,
+    /// Peer and funding output of the channels LDK is ready to fund
+    pub(crate) funding_outputs: HashMap<ChannelId, (PublicKey, TxOut)>,
+    pub(crate) txid_sender: Option<oneshot::Sender<Txid>>,
+    /// Fee rate of the funding transaction (in sat/vB)
+    pub(crate) fee_rate: f32,
+    /// Set once the funding transaction has been handed to LDK
+    pub(crate) funding_txid: Option<Txid>,
+}
+
+/// Funding transaction of a channel, built but not signed yet
+pub(crate) struct PendingFunding {
+    pub(crate) counterparty_node_id: PublicKey,
+    pub(crate) unsigned_psbt: Psbt,
+    /// Asset and recipient ID of the RGB transfer funding a colored channel
+    pub(crate) rgb_transfer: Option<(String, String)>,
+    pub(crate) proxy_endpoint: String,
+    pub(crate) blinding: u64,
+}
+
+/// Max number of HTLCs a peer can be allowed to have pending towards us, as per BOLT 2
+const MAX_ACCEPTED_HTLCS: u16 = 483;
+/// Max reserve we require peers to keep, as higher ones are generally refused
+const MAX_CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS: u32 = 200_000;
+
+/// Limits on the HTLCs a peer can add to a channel, overriding the LDK defaults when set
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct HtlcLimits {
+    /// Max share of the channel capacity that can be in inbound HTLCs (in percent)
+    pub(crate) max_htlc_value_in_flight_percent: Option<u8>,
+    /// Max number of inbound HTLCs that can be pending at once
+    pub(crate) max_accepted_htlcs: Option<u16>,
+    /// Min value of inbound HTLCs
+    pub(crate) htlc_minimum_msat: Option<u64>,
+    /// Reserve the peer must keep in the channel (in millionths of the channel capacity)
+    pub(crate) channel_reserve_proportional_millionths: Option<u32>,
+}
+
+impl HtlcLimits {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if let Some(percent) = self.max_htlc_value_in_flight_percent {
+            if percent == 0 || percent > 100 {
+                return Err(s!(
+                    "max HTLC value in flight must be between 1 and 100 percent"
+                ));
+            }
+        }
+        if let Some(max_accepted_htlcs) = self.max_accepted_htlcs {
+            if max_accepted_htlcs == 0 || max_accepted_htlcs > MAX_ACCEPTED_HTLCS {
+                return Err(format!(
+                    "max accepted HTLCs must be between 1 and {MAX_ACCEPTED_HTLCS}"
+                ));
+            }
+        }
+        if self.htlc_minimum_msat == Some(0) {
+            return Err(s!("HTLC minimum must be positive"));
+        }
+        if let Some(millionths) = self.channel_reserve_proportional_millionths {
+            if millionths > MAX_CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS {
+                return Err(format!(
+                    "channel reserve cannot be higher than {MAX_CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS} millionths"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Limits set here, falling back to the given ones for the others
+    pub(crate) fn or(self, fallback: HtlcLimits) -> HtlcLimits {
+        HtlcLimits {
+            max_htlc_value_in_flight_percent: self
+                .max_htlc_value_in_flight_percent
+                .or(fallback.max_htlc_value_in_flight_percent),
+            max_accepted_htlcs: self.max_accepted_htlcs.or(fallback.max_accepted_htlcs),
+            htlc_minimum_msat: self.htlc_minimum_msat.or(fallback.htlc_minimum_msat),
+            channel_reserve_proportional_millionths: self
+                .channel_reserve_proportional_millionths
+                .or(fallback.channel_reserve_proportional_millionths),
+        }
+    }
+
+    pub(crate) fn apply(&self, config: &mut ChannelHandshakeConfig) {
+        if let Some(percent) = self.max_htlc_value_in_flight_percent {
+            config.max_inbound_htlc_value_in_flight_percent_of_channel = percent;
+        }
+        if let Some(max_accepted_htlcs) = self.max_accepted_htlcs {
+            config.our_max_accepted_htlcs = max_accepted_htlcs;
+        }
+        if let Some(htlc_minimum_msat) = self.htlc_minimum_msat {
+            config.our_htlc_minimum_msat = htlc_minimum_msat;
+        }
+        if let Some(millionths) = self.channel_reserve_proportional_millionths {
+            config.their_channel_reserve_proportional_millionths = millionths;
+        }
+    }
+}
+
+/// An intercepted HTLC that couldn't be forwarded and is still held by the channel manager
+#[derive(Clone, Debug)]
+pub(crate) struct HeldIntercept {
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) inbound_amount_msat: u64,
+    pub(crate) expected_outbound_amount_msat: u64,
+    pub(crate) inbound_rgb_amount: Option<u64>,
+    pub(crate) expected_outbound_rgb_amount: Option<u64>,
+    pub(crate) inbound_contract_id: Option<ContractId>,
+    pub(crate) outbound_contract_id: Option<ContractId>,
+    pub(crate) prev_channel_id: ChannelId,
+    pub(crate) requested_next_hop_scid: u64,
+    pub(crate) intercepted_at: u64,
+    pub(crate) expires_at: u64,
+}
+
 impl UnlockedAppState {
-    pub(crate) fn add_maker_swap(&self, payment_hash: PaymentHash, swap: SwapData) {
+    /// Get the confirmed UTXOs of the vanilla wallet at the given outpoints
+    pub(crate) fn vanilla_utxos(&self, outpoints: &[OutPoint]) -> Result<Vec<Utxo>, APIError> {
+        let wallet_utxos = self
+            .rgb_wallet_wrapper
+            .list_confirmed_utxos()
+            .map_err(|_| APIError::Unexpected)?;
+        outpoints
+            .iter()
+            .map(|outpoint| {
+                wallet_utxos
+                    .iter()
+                    .find(|u| u.outpoint == *outpoint)
+                    .cloned()
+                    .ok_or_else(|| {
+                        APIError::InvalidFundingOutpoints(format!(
+                            "{outpoint} is not a confirmed UTXO of the vanilla wallet"
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// Select confirmed UTXOs of the vanilla wallet, largest first, until they cover the given
+    /// funding outputs and the fees
+    pub(crate) fn select_funding_utxos(
+        &self,
+        funding_outputs: &[TxOut],
+        fee_rate: f32,
+    ) -> Result<Vec<Utxo>, APIError> {
+        let mut wallet_utxos = self
+            .rgb_wallet_wrapper
+            .list_confirmed_utxos()
+            .map_err(|_| APIError::Unexpected)?;
+        wallet_utxos.sort_by(|a, b| b.output.value.cmp(&a.output.value));
+        let mut selected = vec![];
+        for utxo in wallet_utxos {
+            selected.push(utxo);
+            match funding_psbt_from_utxos(
+                &selected,
+                funding_outputs.to_vec(),
+                placeholder_funding_script(),
+                fee_rate,
+            ) {
+                Ok(_) => return Ok(selected),
+                Err(APIError::InsufficientFunds(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        // reports the missing amount
+        funding_psbt_from_utxos(
+            &selected,
+            funding_outputs.to_vec(),
+            placeholder_funding_script(),
+            fee_rate,
+        )?;
+        Ok(selected)
+    }
+
+    /// Drop the funding batch including the given channel, closing all of its channels if not
+    /// funded yet (LDK closes the others otherwise)
+    pub(crate) fn abort_funding_batch(&self, temporary_channel_id: &ChannelId) {
+        let batch = {
+            let mut funding_batches = self.get_funding_batches();
+            let Some(idx) = funding_batches
+                .iter()
+                .position(|b| b.temporary_channel_ids.contains(temporary_channel_id))
+            else {
+                return;
+            };
+            funding_batches.remove(idx)
+        };
+        if batch.funding_txid.is_some() {
+            return;
+        }
+        tracing::warn!(
+            "Aborting the funding batch of channel {}",
+            temporary_channel_id
+        );
+        self.close_unfunded_channels(&batch.temporary_channel_ids);
+        *self.rgb_send_lock.lock().unwrap() = false;
+        tracing::debug!("RGB send lock set to false (funding batch aborted)");
+    }
+
+    /// Record a channel of a funded batch has become pending, returning whether it was the last
+    /// one of its batch (or it's not part of a batch)
+    pub(crate) fn funding_batch_channel_pending(&self, temporary_channel_id: &ChannelId) -> bool {
+        let mut funding_batches = self.get_funding_batches();
+        let Some(idx) = funding_batches.iter().position(|b| {
+            b.funding_txid.is_some() && b.temporary_channel_ids.contains(temporary_channel_id)
+        }) else {
+            return true;
+        };
+        let batch = &mut funding_batches[idx];
+        batch
+            .temporary_channel_ids
+            .retain(|id| id != temporary_channel_id);
+        if !batch.temporary_channel_ids.is_empty() {
+            return false;
+        }
+        funding_batches.remove(idx);
+        true
+    }
+
+    /// Close the given channels that are still waiting to be funded
+    pub(crate) fn close_unfunded_channels(&self, temporary_channel_ids: &[ChannelId]) {
+        for chan_info in self.channel_manager.list_channels() {
+            if chan_info.funding_txo.is_none()
+                && temporary_channel_ids.contains(&chan_info.channel_id)
+            {
+                let _ = self.channel_manager.force_close_without_broadcasting_txn(
+                    &chan_info.channel_id,
+                    &chan_info.counterparty.node_id,
+                );
+            }
+        }
+    }
+
+    /// Outpoints reserved for funding channels: the UTXOs requested for channels and batches being
+    /// opened and the inputs of funding transactions built but not broadcast yet
+    pub(crate) fn funding_locked_outpoints(&self, ldk_data_dir: &Path) -> HashSet<OutPoint> {
+        let mut outpoints: HashSet<OutPoint> = self
+            .get_funding_utxos()
+            .values()
+            .flatten()
+            .map(|u| u.outpoint)
+            .collect();
+        outpoints.extend(
+            self.get_funding_batches()
+                .iter()
+                .flat_map(|b| b.utxos.iter().map(|u| u.outpoint)),
+        );
+        for chan_info in self.channel_manager.list_channels() {
+            if chan_info.is_channel_ready {
+                continue;
+            }
+            let Some(funding_txo) = chan_info.funding_txo else {
+                continue;
+            };
+            let psbt_path = disk::funding_psbt_path(ldk_data_dir, funding_txo.txid);
+            let Ok(psbt_str) = disk::read_funding_psbt(&psbt_path, self.kv_store.cipher()) else {
+                continue;
+            };
+            if let Ok(psbt) = Psbt::from_str(&psbt_str) {
+                outpoints.extend(
+                    psbt.unsigned_tx
+                        .input
+                        .iter()
+                        .map(|input| input.previous_output),
+                );
+            }
+        }
+        outpoints
+    }
+
+    pub(crate) fn add_maker_swap(
+        &self,
+        payment_hash: PaymentHash,
+        swap: SwapData,
+    ) -> Result<(), APIError> {
         let mut maker_swaps = self.get_maker_swaps();
+        self.journal.record(
+            JournalEntryKind::MakerSwap,
+            hex_str(&payment_hash.0),
+            &swap.status,
+        );
         maker_swaps.swaps.insert(payment_hash, swap);
-        self.save_maker_swaps(maker_swaps);
+        self.snapshot_tracker.changed();
+        // the caller is told the swap couldn't be added, so it's not kept
+        self.persist(MAKER_SWAPS_FNAME, &*maker_swaps)
+            .inspect_err(|_| {
+                maker_swaps.swaps.remove(&payment_hash);
+            })
     }
 
-    pub(crate) fn update_maker_swap_status(&self, payment_hash: &PaymentHash, status: SwapStatus) {
-        let mut maker_swaps = self.get_maker_swaps();
-        let maker_swap = maker_swaps.swaps.get_mut(payment_hash).unwrap();
-        match &status {
-            SwapStatus::Succeeded | SwapStatus::Failed | SwapStatus::Expired => {
-                maker_swap.completed_at = Some(get_current_timestamp())
+    pub(crate) fn update_maker_swap_status(
+        &self,
+        payment_hash: &PaymentHash,
+        status: SwapStatus,
+    ) -> Result<(), SwapTransitionError> {
+        self.set_maker_swap_status(payment_hash, status, None)
+    }
+
+    /// Mark a maker swap as failed, recording why
+    pub(crate) fn fail_maker_swap(
+        &self,
+        payment_hash: &PaymentHash,
+        reason: String,
+    ) -> Result<(), SwapTransitionError> {
+        self.set_maker_swap_status(payment_hash, SwapStatus::Failed, Some(reason))
+    }
+
+    /// Mark a maker swap as succeeded, which is reported both when its payment is sent and when
+    /// it's claimed back
+    fn complete_maker_swap(&self, payment_hash: &PaymentHash) {
+        let succeeded = self
+            .get_maker_swaps()
+            .swaps
+            .get(payment_hash)
+            .is_some_and(|s| s.status == SwapStatus::Succeeded);
+        if !succeeded {
+            if let Err(e) = self.update_maker_swap_status(payment_hash, SwapStatus::Succeeded) {
+                tracing::error!("Failed to mark maker swap {payment_hash} as succeeded: {e}");
             }
-            SwapStatus::Pending => maker_swap.initiated_at = Some(get_current_timestamp()),
-            SwapStatus::Waiting => panic!("this doesn't make sense: swap starts in Waiting status"),
         }
-        maker_swap.status = status;
-        self.save_maker_swaps(maker_swaps);
+    }
+
+    fn set_maker_swap_status(
+        &self,
+        payment_hash: &PaymentHash,
+        status: SwapStatus,
+        failure_reason: Option<String>,
+    ) -> Result<(), SwapTransitionError> {
+        let mut maker_swaps = self.get_maker_swaps();
+        let maker_swap = maker_swaps
+            .swaps
+            .get_mut(payment_hash)
+            .ok_or(SwapTransitionError::UnknownSwap)?;
+        if maker_swap.status.can_transition_to(&status) {
+            self.journal.record(
+                JournalEntryKind::MakerSwap,
+                hex_str(&payment_hash.0),
+                &status,
+            );
+        }
+        maker_swap
+            .transition(status, failure_reason)
+            .inspect_err(|e| tracing::warn!("Maker swap {payment_hash}: {e}"))?;
+        self.save_maker_swaps(maker_swaps)?;
+        Ok(())
+    }
+
+    /// Record the quantities a maker swap is executed for
+    pub(crate) fn set_maker_swap_fill(
+        &self,
+        payment_hash: &PaymentHash,
+        qty_from: u64,
+        qty_to: u64,
+    ) -> Result<(), SwapTransitionError> {
+        let mut maker_swaps = self.get_maker_swaps();
+        let maker_swap = maker_swaps
+            .swaps
+            .get_mut(payment_hash)
+            .ok_or(SwapTransitionError::UnknownSwap)?;
+        maker_swap.filled_qty_from = Some(qty_from);
+        maker_swap.filled_qty_to = Some(qty_to);
+        self.save_maker_swaps(maker_swaps)?;
+        Ok(())
     }
 
     pub(crate) fn is_maker_swap(&self, payment_hash: &PaymentHash) -> bool {
         self.maker_swaps().contains_key(payment_hash)
     }
 
-    pub(crate) fn add_taker_swap(&self, payment_hash: PaymentHash, swap: SwapData) {
+    pub(crate) fn add_taker_swap(
+        &self,
+        payment_hash: PaymentHash,
+        swap: SwapData,
+    ) -> Result<(), APIError> {
         let mut taker_swaps = self.get_taker_swaps();
+        self.journal.record(
+            JournalEntryKind::TakerSwap,
+            hex_str(&payment_hash.0),
+            &swap.status,
+        );
         taker_swaps.swaps.insert(payment_hash, swap);
-        self.save_taker_swaps(taker_swaps);
+        self.snapshot_tracker.changed();
+        self.persist(TAKER_SWAPS_FNAME, &*taker_swaps)
+            .inspect_err(|_| {
+                taker_swaps.swaps.remove(&payment_hash);
+            })
     }
 
-    pub(crate) fn update_taker_swap_status(&self, payment_hash: &PaymentHash, status: SwapStatus) {
+    pub(crate) fn update_taker_swap_status(
+        &self,
+        payment_hash: &PaymentHash,
+        status: SwapStatus,
+    ) -> Result<(), SwapTransitionError> {
+        self.set_taker_swap_status(payment_hash, status, None)
+    }
+
+    /// Mark a taker swap as failed (or expired), recording why
+    fn fail_taker_swap(
+        &self,
+        payment_hash: &PaymentHash,
+        status: SwapStatus,
+        reason: String,
+    ) -> Result<(), SwapTransitionError> {
+        self.set_taker_swap_status(payment_hash, status, Some(reason))
+    }
+
+    fn set_taker_swap_status(
+        &self,
+        payment_hash: &PaymentHash,
+        status: SwapStatus,
+        failure_reason: Option<String>,
+    ) -> Result<(), SwapTransitionError> {
         let mut taker_swaps = self.get_taker_swaps();
-        let taker_swap = taker_swaps.swaps.get_mut(payment_hash).unwrap();
-        match &status {
-            SwapStatus::Succeeded | SwapStatus::Failed | SwapStatus::Expired => {
-                taker_swap.completed_at = Some(get_current_timestamp())
-            }
-            SwapStatus::Pending => taker_swap.initiated_at = Some(get_current_timestamp()),
-            SwapStatus::Waiting => panic!("this doesn't make sense: swap starts in Waiting status"),
+        let taker_swap = taker_swaps
+            .swaps
+            .get_mut(payment_hash)
+            .ok_or(SwapTransitionError::UnknownSwap)?;
+        if taker_swap.status.can_transition_to(&status) {
+            self.journal.record(
+                JournalEntryKind::TakerSwap,
+                hex_str(&payment_hash.0),
+                &status,
+            );
         }
-        taker_swap.status = status;
-        self.save_taker_swaps(taker_swaps);
+        taker_swap
+            .transition(status, failure_reason)
+            .inspect_err(|e| tracing::warn!("Taker swap {payment_hash}: {e}"))?;
+        self.save_taker_swaps(taker_swaps)?;
+        Ok(())
+    }
+
+    /// Record why a taker swap couldn't be forwarded, without changing its status
+    fn set_taker_swap_failure_reason(
+        &self,
+        payment_hash: &PaymentHash,
+        reason: String,
+    ) -> Result<(), SwapTransitionError> {
+        let mut taker_swaps = self.get_taker_swaps();
+        let taker_swap = taker_swaps
+            .swaps
+            .get_mut(payment_hash)
+            .ok_or(SwapTransitionError::UnknownSwap)?;
+        taker_swap.failure_reason = Some(reason);
+        self.save_taker_swaps(taker_swaps)?;
+        Ok(())
+    }
+
+    /// Record the quantities a taker swap is forwarded for
+    fn set_taker_swap_fill(
+        &self,
+        payment_hash: &PaymentHash,
+        qty_from: u64,
+        qty_to: u64,
+    ) -> Result<(), SwapTransitionError> {
+        let mut taker_swaps = self.get_taker_swaps();
+        let taker_swap = taker_swaps
+            .swaps
+            .get_mut(payment_hash)
+            .ok_or(SwapTransitionError::UnknownSwap)?;
+        taker_swap.filled_qty_from = Some(qty_from);
+        taker_swap.filled_qty_to = Some(qty_to);
+        self.save_taker_swaps(taker_swaps)?;
+        Ok(())
     }
 
     pub(crate) fn is_taker_swap(&self, payment_hash: &PaymentHash) -> bool {
         self.taker_swaps().contains_key(payment_hash)
     }
 
-    fn save_maker_swaps(&self, swaps: MutexGuard<SwapMap>) {
-        self.fs_store
-            .write("", "", MAKER_SWAPS_FNAME, &swaps.encode())
-            .unwrap();
+    /// Write an entry of the node store.
+    ///
+    /// Failures are logged, so that callers that can't report them (e.g. the event handler) can go
+    /// on with the state kept in memory, which gets written again with its next change
+    pub(crate) fn persist(&self, key: &str, value: &impl Writeable) -> Result<(), APIError> {
+        self.persist_entry("", key, value)
     }
 
-    fn save_taker_swaps(&self, swaps: MutexGuard<SwapMap>) {
-        self.fs_store
-            .write("", "", TAKER_SWAPS_FNAME, &swaps.encode())
-            .unwrap();
+    /// Write an entry of the node store in the given namespace, see [`Self::persist`]
+    fn persist_entry(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &impl Writeable,
+    ) -> Result<(), APIError> {
+        self.kv_store
+            .write(namespace, "", key, &value.encode())
+            .map_err(|e| {
+                tracing::error!("Failed to persist {namespace}/{key}: {e}");
+                APIError::FailedPersisting(e.to_string())
+            })
+    }
+
+    fn save_maker_swaps(&self, swaps: AuditedGuard<SwapMap>) -> Result<(), APIError> {
+        self.snapshot_tracker.changed();
+        self.persist(MAKER_SWAPS_FNAME, &*swaps)
+    }
+
+    fn save_taker_swaps(&self, swaps: AuditedGuard<SwapMap>) -> Result<(), APIError> {
+        self.snapshot_tracker.changed();
+        self.persist(TAKER_SWAPS_FNAME, &*swaps)
     }
 
     pub(crate) fn maker_swaps(&self) -> HashMap<PaymentHash, SwapData> {
@@ -220,14 +884,29 @@ impl UnlockedAppState {
 
     pub(crate) fn add_inbound_payment(&self, payment_hash: PaymentHash, payment_info: PaymentInfo) {
         let mut inbound = self.get_inbound_payments();
+        self.journal.record(
+            JournalEntryKind::InboundPayment,
+            hex_str(&payment_hash.0),
+            &payment_info.status,
+        );
         inbound.payments.insert(payment_hash, payment_info);
-        self.save_inbound_payments(inbound);
+        self.save_inbound_payments(inbound, &[payment_hash]);
     }
 
-    pub(crate) fn add_outbound_payment(&self, payment_id: PaymentId, payment_info: PaymentInfo) {
+    pub(crate) fn add_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        payment_info: PaymentInfo,
+    ) -> Result<(), APIError> {
         let mut outbound = self.get_outbound_payments();
+        self.journal.record(
+            JournalEntryKind::OutboundPayment,
+            hex_str(&payment_id.0),
+            &payment_info.status,
+        );
+        self.save_outbound_payment(payment_id, &payment_info)?;
         outbound.payments.insert(payment_id, payment_info);
-        self.save_outbound_payments(outbound);
+        Ok(())
     }
 
     fn fail_outbound_pending_payments(&self, recent_payments_payment_ids: Vec<PaymentId>) {
@@ -238,30 +917,150 @@ impl UnlockedAppState {
             .filter(|(_, i)| matches!(i.status, HTLCStatus::Pending))
         {
             if !recent_payments_payment_ids.contains(payment_id) {
+                self.journal.record(
+                    JournalEntryKind::OutboundPayment,
+                    hex_str(&payment_id.0),
+                    &HTLCStatus::Failed,
+                );
                 payment_info.status = HTLCStatus::Failed;
+                if let Err(e) = self.save_outbound_payment(*payment_id, payment_info) {
+                    tracing::error!(
+                        "Failed to save failed outbound payment {}: {e}",
+                        hex_str(&payment_id.0)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Mark the pending inbound payments whose invoice has expired as expired
+    fn expire_inbound_payments(&self) {
+        let now = get_current_timestamp();
+        let mut expired = vec![];
+        let mut inbound = self.get_inbound_payments();
+        for (payment_hash, payment_info) in inbound.payments.iter_mut().filter(|(_, i)| {
+            i.status == HTLCStatus::Pending && i.expires_at.is_some_and(|e| e <= now)
+        }) {
+            self.journal.record(
+                JournalEntryKind::InboundPayment,
+                hex_str(&payment_hash.0),
+                &HTLCStatus::Expired,
+            );
+            payment_info.status = HTLCStatus::Expired;
+            expired.push(*payment_hash);
+        }
+        if !expired.is_empty() {
+            self.save_inbound_payments(inbound, &expired);
+        }
+    }
+
+    /// Time out the swaps that are past their expiry, failing back the HTLCs still held for them
+    fn expire_swaps(&self) {
+        let now = get_current_timestamp();
+
+        let mut expired_intercepts = vec![];
+        self.get_held_intercepts().retain(|intercept_id, i| {
+            if i.expires_at < now {
+                expired_intercepts.push((*intercept_id, i.payment_hash));
+                return false;
+            }
+            true
+        });
+        for (intercept_id, payment_hash) in expired_intercepts {
+            tracing::info!("Failing back held HTLC of expired swap {payment_hash}");
+            if let Err(e) = self.channel_manager.fail_intercepted_htlc(intercept_id) {
+                tracing::warn!("Failed to fail back HTLC of expired swap {payment_hash}: {e:?}");
+            }
+            if self.is_taker_swap(&payment_hash) {
+                let _ = self.fail_taker_swap(
+                    &payment_hash,
+                    SwapStatus::Expired,
+                    s!("the swap expired while its HTLC was held"),
+                );
             }
         }
-        self.save_outbound_payments(outbound);
+
+        let timed_out = |swaps: HashMap<PaymentHash, SwapData>| {
+            swaps
+                .into_iter()
+                .filter_map(|(payment_hash, s)| {
+                    s.timed_out_status(now).map(|st| (payment_hash, st))
+                })
+                .collect::<Vec<_>>()
+        };
+        let timed_out_maker = timed_out(self.maker_swaps());
+        let timed_out_taker = timed_out(self.taker_swaps());
+        if timed_out_maker.is_empty() && timed_out_taker.is_empty() {
+            return;
+        }
+        let _update = self.snapshot_tracker.begin_update();
+        for (payment_hash, status) in timed_out_maker {
+            tracing::info!("Maker swap {payment_hash} timed out, marking it as {status:?}");
+            let _ = self.update_maker_swap_status(&payment_hash, status);
+        }
+        for (payment_hash, status) in timed_out_taker {
+            tracing::info!("Taker swap {payment_hash} timed out, marking it as {status:?}");
+            let _ = self.update_taker_swap_status(&payment_hash, status);
+        }
     }
 
     pub(crate) fn inbound_payments(&self) -> HashMap<PaymentHash, PaymentInfo> {
         self.get_inbound_payments().payments.clone()
     }
 
+    pub(crate) fn inbound_payment(&self, payment_hash: &PaymentHash) -> Option<PaymentInfo> {
+        self.get_inbound_payments()
+            .payments
+            .get(payment_hash)
+            .cloned()
+    }
+
     pub(crate) fn outbound_payments(&self) -> HashMap<PaymentId, PaymentInfo> {
         self.get_outbound_payments().payments.clone()
     }
 
-    fn save_inbound_payments(&self, inbound: MutexGuard<InboundPaymentInfoStorage>) {
-        self.fs_store
-            .write("", "", INBOUND_PAYMENTS_FNAME, &inbound.encode())
-            .unwrap();
+    /// Queue the given inbound payments to be persisted, which happens in the background so that
+    /// claims don't wait on disk writes. The change is recorded before releasing the lock, so that
+    /// snapshots can't include it under the previous version.
+    fn save_inbound_payments(
+        &self,
+        inbound: AuditedGuard<InboundPaymentInfoStorage>,
+        payment_hashes: &[PaymentHash],
+    ) {
+        self.snapshot_tracker.changed();
+        drop(inbound);
+        for payment_hash in payment_hashes {
+            self.inbound_payment_updates
+                .send(InboundPaymentUpdate::Changed(*payment_hash))
+                .expect("inbound payments persister is running");
+        }
     }
 
-    fn save_outbound_payments(&self, outbound: MutexGuard<OutboundPaymentInfoStorage>) {
-        self.fs_store
-            .write("", "", OUTBOUND_PAYMENTS_FNAME, &outbound.encode())
-            .unwrap();
+    /// Wait for the inbound payment changes made so far to be persisted
+    pub(crate) async fn flush_inbound_payments(&self) {
+        let (done, flushed) = oneshot::channel();
+        if self
+            .inbound_payment_updates
+            .send(InboundPaymentUpdate::Flush(done))
+            .is_ok()
+        {
+            let _ = flushed.await;
+        }
+    }
+
+    /// Persist the outbound payment with the given ID to its own entry, leaving the other
+    /// payments untouched
+    fn save_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        payment_info: &PaymentInfo,
+    ) -> Result<(), APIError> {
+        self.snapshot_tracker.changed();
+        self.persist_entry(
+            OUTBOUND_PAYMENTS_NAMESPACE,
+            &hex_str(&payment_id.0),
+            payment_info,
+        )
     }
 
     fn upsert_inbound_payment(
@@ -271,14 +1070,24 @@ impl UnlockedAppState {
         preimage: Option<PaymentPreimage>,
         secret: Option<PaymentSecret>,
         amt_msat: Option<u64>,
+        asset_amount: Option<u64>,
+        keysend: bool,
     ) {
         let mut inbound = self.get_inbound_payments();
+        if inbound.payments.get(&payment_hash).map(|p| p.status) != Some(status) {
+            self.journal.record(
+                JournalEntryKind::InboundPayment,
+                hex_str(&payment_hash.0),
+                &status,
+            );
+        }
         match inbound.payments.entry(payment_hash) {
             Entry::Occupied(mut e) => {
                 let payment = e.get_mut();
                 payment.status = status;
                 payment.preimage = preimage;
                 payment.secret = secret;
+                payment.asset_amount = asset_amount;
             }
             Entry::Vacant(e) => {
                 e.insert(PaymentInfo {
@@ -286,10 +1095,53 @@ impl UnlockedAppState {
                     secret,
                     status,
                     amt_msat,
+                    custom_records: vec![],
+                    retry_attempts: None,
+                    retry_timeout_secs: None,
+                    failed_attempts: 0,
+                    expires_at: None,
+                    asset_amount,
+                    keysend,
+                    claim_deadline: None,
                 });
             }
         }
-        self.save_inbound_payments(inbound);
+        self.save_inbound_payments(inbound, &[payment_hash]);
+    }
+
+    /// Record the sender data of a claimable inbound payment, creating the payment if it isn't
+    /// tracked yet (i.e. for keysends)
+    fn save_inbound_claimable(
+        &self,
+        payment_hash: PaymentHash,
+        amt_msat: u64,
+        custom_records: Vec<(u64, Vec<u8>)>,
+        keysend: bool,
+    ) {
+        let mut inbound = self.get_inbound_payments();
+        if !inbound.payments.contains_key(&payment_hash) {
+            self.journal.record(
+                JournalEntryKind::InboundPayment,
+                hex_str(&payment_hash.0),
+                &HTLCStatus::Pending,
+            );
+        }
+        let payment = inbound.payments.entry(payment_hash).or_insert(PaymentInfo {
+            preimage: None,
+            secret: None,
+            status: HTLCStatus::Pending,
+            amt_msat: Some(amt_msat),
+            custom_records: vec![],
+            retry_attempts: None,
+            retry_timeout_secs: None,
+            failed_attempts: 0,
+            expires_at: None,
+            asset_amount: None,
+            keysend,
+            claim_deadline: None,
+        });
+        payment.custom_records = custom_records;
+        self.save_inbound_payments(inbound, &[payment_hash]);
     }
 
     pub(crate) fn update_outbound_payment(
@@ -300,74 +1152,897 @@ impl UnlockedAppState {
     ) -> PaymentInfo {
         let mut outbound = self.get_outbound_payments();
         let outbound_payment = outbound.payments.get_mut(&payment_id).unwrap();
+        self.journal.record(
+            JournalEntryKind::OutboundPayment,
+            hex_str(&payment_id.0),
+            &status,
+        );
         outbound_payment.status = status;
         outbound_payment.preimage = preimage;
         let payment = (*outbound_payment).clone();
-        self.save_outbound_payments(outbound);
+        if let Err(e) = self.save_outbound_payment(payment_id, &payment) {
+            tracing::error!(
+                "Failed to save outbound payment {}: {e}",
+                hex_str(&payment_id.0)
+            );
+        }
         payment
     }
 
-    pub(crate) fn update_outbound_payment_status(&self, payment_id: PaymentId, status: HTLCStatus) {
+    pub(crate) fn update_outbound_payment_status(
+        &self,
+        payment_id: PaymentId,
+        status: HTLCStatus,
+    ) -> Result<(), APIError> {
         let mut outbound = self.get_outbound_payments();
         let payment = outbound.payments.get_mut(&payment_id).unwrap();
+        self.journal.record(
+            JournalEntryKind::OutboundPayment,
+            hex_str(&payment_id.0),
+            &status,
+        );
+        payment.status = status;
+        self.save_outbound_payment(payment_id, payment)
+    }
+
+    fn increment_outbound_payment_failed_attempts(&self, payment_id: PaymentId) {
+        let mut outbound = self.get_outbound_payments();
+        // swap payments are not tracked among the outbound ones
+        if let Some(payment) = outbound.payments.get_mut(&payment_id) {
+            payment.failed_attempts += 1;
+            if let Err(e) = self.save_outbound_payment(payment_id, payment) {
+                tracing::error!(
+                    "Failed to save outbound payment {}: {e}",
+                    hex_str(&payment_id.0)
+                );
+            }
+        }
+    }
+
+    pub(crate) fn update_inbound_payment_status(
+        &self,
+        payment_hash: PaymentHash,
+        status: HTLCStatus,
+    ) {
+        let mut inbound = self.get_inbound_payments();
+        let payment = inbound.payments.get_mut(&payment_hash).unwrap();
+        self.journal.record(
+            JournalEntryKind::InboundPayment,
+            hex_str(&payment_hash.0),
+            &status,
+        );
+        payment.status = status;
+        self.save_inbound_payments(inbound, &[payment_hash]);
+    }
+
+    /// Mark a hold invoice whose HTLCs have been failed back by the channel manager as expired if
+    /// they were held until their claim deadline, or as failed otherwise
+    pub(crate) fn fail_held_inbound_payment(&self, payment_hash: PaymentHash, height: u32) {
+        let mut inbound = self.get_inbound_payments();
+        let Some(payment) = inbound.payments.get_mut(&payment_hash) else {
+            return;
+        };
+        // cancelled hold invoices are already failed
+        if payment.status != HTLCStatus::Claimable {
+            return;
+        }
+        let status = if payment.claim_deadline.is_some_and(|d| height >= d) {
+            HTLCStatus::Expired
+        } else {
+            HTLCStatus::Failed
+        };
+        self.journal.record(
+            JournalEntryKind::InboundPayment,
+            hex_str(&payment_hash.0),
+            &status,
+        );
         payment.status = status;
-        self.save_outbound_payments(outbound);
+        self.save_inbound_payments(inbound, &[payment_hash]);
+    }
+
+    /// Mark a hold invoice as paid, its HTLCs being held until the given deadline
+    pub(crate) fn hold_inbound_payment(
+        &self,
+        payment_hash: PaymentHash,
+        claim_deadline: Option<u32>,
+    ) {
+        let mut inbound = self.get_inbound_payments();
+        let payment = inbound.payments.get_mut(&payment_hash).unwrap();
+        self.journal.record(
+            JournalEntryKind::InboundPayment,
+            hex_str(&payment_hash.0),
+            &HTLCStatus::Claimable,
+        );
+        payment.status = HTLCStatus::Claimable;
+        payment.claim_deadline = claim_deadline;
+        self.save_inbound_payments(inbound, &[payment_hash]);
+    }
+
+    pub(crate) fn channel_ids(&self) -> HashMap<ChannelId, ChannelId> {
+        self.get_channel_ids_map().channel_ids.clone()
+    }
+
+    /// Consistent view of the payment, swap and channel ID maps, taking a new snapshot only if the
+    /// maps changed since the latest one
+    pub(crate) async fn snapshot(&self) -> Arc<StateSnapshot> {
+        loop {
+            self.snapshot_tracker.wait_for_updates().await;
+            // all maps are locked while checking and taking the snapshot, so that a single
+            // snapshot is taken for each version
+            let inbound = self.get_inbound_payments();
+            let outbound = self.get_outbound_payments();
+            let maker_swaps = self.get_maker_swaps();
+            let taker_swaps = self.get_taker_swaps();
+            let channel_ids_map = self.get_channel_ids_map();
+            if self.snapshot_tracker.is_updating() {
+                // an update started in the meantime
+                continue;
+            }
+            if let Some(snapshot) = self.snapshot_tracker.current() {
+                return snapshot;
+            }
+            let snapshot = Arc::new(StateSnapshot {
+                id: self.snapshot_tracker.version(),
+                inbound_payments: inbound.payments.clone(),
+                outbound_payments: outbound.payments.clone(),
+                maker_swaps: maker_swaps.swaps.clone(),
+                taker_swaps: taker_swaps.swaps.clone(),
+                channel_ids: channel_ids_map.channel_ids.clone(),
+            });
+            self.snapshot_tracker.set_latest(snapshot.clone());
+            return snapshot;
+        }
+    }
+
+    pub(crate) fn add_channel_id(
+        &self,
+        former_temporary_channel_id: ChannelId,
+        channel_id: ChannelId,
+    ) {
+        let mut channel_ids_map = self.get_channel_ids_map();
+        channel_ids_map
+            .channel_ids
+            .insert(former_temporary_channel_id, channel_id);
+        if let Err(e) = self.save_channel_ids_map(channel_ids_map) {
+            tracing::error!("Failed to save the ID of channel {channel_id}: {e}");
+        }
+    }
+
+    pub(crate) fn delete_channel_id(&self, channel_id: ChannelId) {
+        let mut channel_ids_map = self.get_channel_ids_map();
+        if let Some(temporary_channel_id) = channel_ids_map
+            .channel_ids
+            .clone()
+            .into_iter()
+            .find_map(|(tmp_chan_id, chan_id)| {
+                if chan_id == channel_id {
+                    Some(tmp_chan_id)
+                } else {
+                    None
+                }
+            })
+        {
+            channel_ids_map.channel_ids.remove(&temporary_channel_id);
+            if let Err(e) = self.save_channel_ids_map(channel_ids_map) {
+                tracing::error!("Failed to delete the ID of channel {channel_id}: {e}");
+            }
+        }
+    }
+
+    fn save_channel_ids_map(
+        &self,
+        channel_ids: AuditedGuard<ChannelIdsMap>,
+    ) -> Result<(), APIError> {
+        self.snapshot_tracker.changed();
+        self.persist(CHANNEL_IDS_FNAME, &*channel_ids)
+    }
+
+    pub(crate) fn asset_htlc_limits(&self) -> HashMap<ChannelId, u64> {
+        self.get_asset_htlc_limits().limits.clone()
+    }
+
+    /// Set the asset HTLC limit of the given channel, or remove it if no limit is given
+    pub(crate) fn set_asset_htlc_limit(
+        &self,
+        channel_id: ChannelId,
+        limit: Option<u64>,
+    ) -> Result<(), APIError> {
+        let mut asset_htlc_limits = self.get_asset_htlc_limits();
+        let changed = match limit {
+            Some(limit) => asset_htlc_limits.limits.insert(channel_id, limit) != Some(limit),
+            None => asset_htlc_limits.limits.remove(&channel_id).is_some(),
+        };
+        if changed {
+            self.persist(ASSET_HTLC_LIMITS_FNAME, &*asset_htlc_limits)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn channel_transfer(&self, txid: &str) -> Option<ChannelTransferData> {
+        self.get_channel_transfers().transfers.get(txid).cloned()
+    }
+
+    pub(crate) fn record_channel_transfer(&self, txid: String, transfer: ChannelTransferData) {
+        let mut channel_transfers = self.get_channel_transfers();
+        channel_transfers.transfers.insert(txid, transfer);
+        let _ = self.persist(CHANNEL_TRANSFERS_FNAME, &*channel_transfers);
+    }
+
+    /// Link the funding transfer of a channel, if any, to the channel once it's known
+    fn set_funding_transfer_channel(&self, funding_txid: &str, channel_id: ChannelId) {
+        let mut channel_transfers = self.get_channel_transfers();
+        let Some(transfer) = channel_transfers.transfers.get_mut(funding_txid) else {
+            return;
+        };
+        transfer.channel_id = Some(channel_id);
+        let _ = self.persist(CHANNEL_TRANSFERS_FNAME, &*channel_transfers);
+    }
+
+    pub(crate) fn close_address(&self, channel_id: &ChannelId) -> Option<String> {
+        self.get_close_addresses()
+            .addresses
+            .get(channel_id)
+            .cloned()
+    }
+
+    /// Set the close address of the given channel, or remove it if no address is given
+    pub(crate) fn set_close_address(
+        &self,
+        channel_id: ChannelId,
+        address: Option<String>,
+    ) -> Result<(), APIError> {
+        let mut close_addresses = self.get_close_addresses();
+        let changed = match address {
+            Some(address) => {
+                close_addresses
+                    .addresses
+                    .insert(channel_id, address.clone())
+                    != Some(address)
+            }
+            None => close_addresses.addresses.remove(&channel_id).is_some(),
+        };
+        if changed {
+            self.persist(CLOSE_ADDRESSES_FNAME, &*close_addresses)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn channel_closures(&self) -> HashMap<ChannelId, ChannelClosure> {
+        self.get_channel_closures().closures.clone()
+    }
+
+    fn save_channel_closure(
+        &self,
+        channel_id: ChannelId,
+        counterparty_node_id: Option<PublicKey>,
+        reason: &ClosureReason,
+    ) -> Result<(), APIError> {
+        let error = match reason {
+            ClosureReason::ProcessingError { err } => Some(err.clone()),
+            ClosureReason::CounterpartyForceClosed { peer_msg } => Some(peer_msg.to_string()),
+            _ => None,
+        };
+        let mut channel_closures = self.get_channel_closures();
+        channel_closures.closures.insert(
+            channel_id,
+            ChannelClosure {
+                counterparty_node_id,
+                reason: reason.to_string(),
+                error,
+                closed_at: get_current_timestamp(),
+            },
+        );
+        self.persist(CHANNEL_CLOSURES_FNAME, &*channel_closures)
+    }
+
+    pub(crate) fn channel_conversions(&self) -> HashMap<ChannelId, ChannelConversionData> {
+        self.get_channel_conversions().conversions.clone()
+    }
+
+    pub(crate) fn add_channel_conversion(
+        &self,
+        channel_id: ChannelId,
+        conversion: ChannelConversionData,
+    ) -> Result<(), APIError> {
+        let mut channel_conversions = self.get_channel_conversions();
+        channel_conversions
+            .conversions
+            .insert(channel_id, conversion);
+        self.persist(CHANNEL_CONVERSIONS_FNAME, &*channel_conversions)
+            .inspect_err(|_| {
+                channel_conversions.conversions.remove(&channel_id);
+            })
+    }
+
+    /// Update the conversion of the given channel, if it's being converted
+    pub(crate) fn update_channel_conversion<F>(
+        &self,
+        channel_id: &ChannelId,
+        update: F,
+    ) -> Result<(), APIError>
+    where
+        F: FnOnce(&mut ChannelConversionData),
+    {
+        let mut channel_conversions = self.get_channel_conversions();
+        let Some(conversion) = channel_conversions.conversions.get_mut(channel_id) else {
+            return Ok(());
+        };
+        update(conversion);
+        self.persist(CHANNEL_CONVERSIONS_FNAME, &*channel_conversions)
+    }
+
+    fn exceeds_asset_htlc_limit(&self, channel_id: &ChannelId, rgb_amount: u64) -> bool {
+        self.get_asset_htlc_limits()
+            .limits
+            .get(channel_id)
+            .is_some_and(|limit| rgb_amount > *limit)
+    }
+
+    /// Get the claims of the force-closed channel with the given funding outpoint fee-bumped to at
+    /// least the given fee rate, making LDK regenerate the bump events of all pending claims
+    pub(crate) fn bump_close_fee_rate(
+        &self,
+        funding_txo: OutPoint,
+        feerate_sat_per_1000_weight: u32,
+    ) {
+        {
+            let mut bump_fee_rates = self.get_bump_fee_rates();
+            let fee_rate = bump_fee_rates.entry(funding_txo).or_default();
+            *fee_rate = (*fee_rate).max(feerate_sat_per_1000_weight);
+        }
+        self.chain_monitor.rebroadcast_pending_claims();
+    }
+
+    /// Raise the target fee rate of a bump event to the one requested for its channel, if any
+    fn apply_bump_fee_rate(&self, event: &mut BumpTransactionEvent) {
+        let (funding_txo, target_feerate) = match event {
+            BumpTransactionEvent::ChannelClose {
+                anchor_descriptor,
+                package_target_feerate_sat_per_1000_weight,
+                ..
+            } => (
+                anchor_descriptor
+                    .channel_derivation_parameters
+                    .transaction_parameters
+                    .funding_outpoint,
+                package_target_feerate_sat_per_1000_weight,
+            ),
+            BumpTransactionEvent::HTLCResolution {
+                htlc_descriptors,
+                target_feerate_sat_per_1000_weight,
+                ..
+            } => (
+                htlc_descriptors.first().and_then(|d| {
+                    d.channel_derivation_parameters
+                        .transaction_parameters
+                        .funding_outpoint
+                }),
+                target_feerate_sat_per_1000_weight,
+            ),
+        };
+        let Some(funding_txo) = funding_txo else {
+            return;
+        };
+        if let Some(fee_rate) = self
+            .get_bump_fee_rates()
+            .get(&funding_txo.into_bitcoin_outpoint())
+        {
+            *target_feerate = (*target_feerate).max(*fee_rate);
+        }
+    }
+
+    pub(crate) fn add_lnurl_withdraw(
+        &self,
+        k1: String,
+        withdraw: LnurlWithdraw,
+    ) -> Result<(), APIError> {
+        let mut lnurl_withdraws = self.get_lnurl_withdraws();
+        lnurl_withdraws.withdraws.insert(k1.clone(), withdraw);
+        self.persist(LNURL_WITHDRAWS_FNAME, &*lnurl_withdraws)
+            .inspect_err(|_| {
+                lnurl_withdraws.withdraws.remove(&k1);
+            })
+    }
+
+    /// Claim the withdraw for the payment with the given hash, failing if it has already been
+    /// claimed, so that concurrent callbacks can't pay it twice
+    pub(crate) fn claim_lnurl_withdraw(
+        &self,
+        k1: &str,
+        payment_hash: PaymentHash,
+    ) -> Result<(), APIError> {
+        let mut lnurl_withdraws = self.get_lnurl_withdraws();
+        let withdraw = lnurl_withdraws
+            .withdraws
+            .get_mut(k1)
+            .ok_or(APIError::UnknownLnurlWithdraw)?;
+        if withdraw.claimed_payment_hash.is_some() {
+            return Err(APIError::CannotLnurlWithdraw(s!(
+                "k1 has already been used"
+            )));
+        }
+        withdraw.claimed_payment_hash = Some(payment_hash);
+        self.save_lnurl_withdraws(lnurl_withdraws)
+    }
+
+    pub(crate) fn release_lnurl_withdraw(&self, k1: &str) {
+        let mut lnurl_withdraws = self.get_lnurl_withdraws();
+        if let Some(withdraw) = lnurl_withdraws.withdraws.get_mut(k1) {
+            withdraw.claimed_payment_hash = None;
+        }
+        if let Err(e) = self.save_lnurl_withdraws(lnurl_withdraws) {
+            tracing::error!("Failed to release LNURL-withdraw {k1}: {e}");
+        }
+    }
+
+    pub(crate) fn lnurl_withdraws(&self) -> HashMap<String, LnurlWithdraw> {
+        self.get_lnurl_withdraws().withdraws.clone()
+    }
+
+    pub(crate) fn node_id_rotation(&self) -> Option<NodeIdRotation> {
+        (*self.get_node_id_rotation()).clone()
+    }
+
+    pub(crate) fn save_node_id_rotation(&self, rotation: NodeIdRotation) -> Result<(), APIError> {
+        let mut node_id_rotation = self.get_node_id_rotation();
+        self.persist(NODE_ID_ROTATION_FNAME, &rotation)?;
+        *node_id_rotation = Some(rotation);
+        Ok(())
+    }
+
+    fn save_lnurl_withdraws(
+        &self,
+        lnurl_withdraws: AuditedGuard<LnurlWithdrawMap>,
+    ) -> Result<(), APIError> {
+        self.persist(LNURL_WITHDRAWS_FNAME, &*lnurl_withdraws)
+    }
+
+    pub(crate) fn add_proxy_pin(&self, proxy: String, pin: ProxyPin) -> Result<(), APIError> {
+        let mut proxy_pins = self.get_proxy_pins();
+        let previous_pin = proxy_pins.pins.insert(proxy.clone(), pin);
+        self.persist(PROXY_PINS_FNAME, &*proxy_pins)
+            .inspect_err(|_| match previous_pin {
+                Some(previous_pin) => {
+                    proxy_pins.pins.insert(proxy, previous_pin);
+                }
+                None => {
+                    proxy_pins.pins.remove(&proxy);
+                }
+            })
+    }
+
+    pub(crate) fn remove_proxy_pin(&self, proxy: &str) -> Result<(), APIError> {
+        let mut proxy_pins = self.get_proxy_pins();
+        proxy_pins
+            .pins
+            .remove(proxy)
+            .ok_or(APIError::UnknownProxyPin)?;
+        self.save_proxy_pins(proxy_pins)
+    }
+
+    pub(crate) fn proxy_pins(&self) -> HashMap<String, ProxyPin> {
+        self.get_proxy_pins().pins.clone()
+    }
+
+    /// Refuse exchanging consignments with the given proxies if they don't match the pins
+    pub(crate) async fn check_proxy_endpoints(
+        &self,
+        proxy_endpoints: &[String],
+    ) -> Result<(), APIError> {
+        check_proxy_pins(&self.proxy_pins(), proxy_endpoints).await
+    }
+
+    /// The given proxies that can be used right now, in order of priority
+    pub(crate) async fn available_proxy_endpoints(
+        &self,
+        proxy_endpoints: &[String],
+    ) -> Result<Vec<String>, APIError> {
+        usable_proxy_endpoints(&self.proxy_pins(), proxy_endpoints).await
+    }
+
+    /// The first of the given proxies, in order of priority, that can be used right now
+    pub(crate) async fn select_proxy_endpoint(
+        &self,
+        proxy_endpoints: &[String],
+    ) -> Result<String, APIError> {
+        Ok(self
+            .available_proxy_endpoints(proxy_endpoints)
+            .await?
+            .swap_remove(0))
+    }
+
+    /// Remember the proxy the consignment of the given TX has been posted to
+    pub(crate) fn record_consignment_proxy(&self, txid: String, proxy_endpoint: String) {
+        let mut consignment_proxies = self.get_consignment_proxies();
+        consignment_proxies.proxies.insert(txid, proxy_endpoint);
+        let _ = self.persist(CONSIGNMENT_PROXIES_FNAME, &*consignment_proxies);
+    }
+
+    /// URL of the proxy the consignment of the given TX has been posted to, if recorded
+    pub(crate) fn consignment_proxy(&self, txid: &str) -> Option<String> {
+        let proxy_endpoint = self.get_consignment_proxies().proxies.get(txid)?.clone();
+        TransportEndpoint::new(proxy_endpoint)
+            .ok()
+            .map(|e| e.endpoint)
+    }
+
+    fn save_proxy_pins(&self, proxy_pins: AuditedGuard<ProxyPinMap>) -> Result<(), APIError> {
+        self.persist(PROXY_PINS_FNAME, &*proxy_pins)
+    }
+
+    pub(crate) fn channel_requests(&self) -> HashMap<String, ChannelRequestData> {
+        self.get_channel_requests().requests.clone()
+    }
+
+    /// Mark the given pending channel request as handled, returning it
+    pub(crate) fn handle_channel_request(
+        &self,
+        request_id: &str,
+        status: ChannelRequestStatus,
+    ) -> Result<ChannelRequestData, APIError> {
+        let mut channel_requests = self.get_channel_requests();
+        let request = channel_requests
+            .requests
+            .get_mut(request_id)
+            .ok_or(APIError::UnknownChannelRequest)?;
+        if request.status != ChannelRequestStatus::Pending {
+            return Err(APIError::ChannelRequestAlreadyHandled);
+        }
+        request.status = status;
+        let request = request.clone();
+        self.save_channel_requests(channel_requests)?;
+        Ok(request)
+    }
+
+    pub(crate) fn update_channel_request(
+        &self,
+        request_id: &str,
+        status: ChannelRequestStatus,
+        temporary_channel_id: Option<String>,
+    ) {
+        let mut channel_requests = self.get_channel_requests();
+        if let Some(request) = channel_requests.requests.get_mut(request_id) {
+            request.status = status;
+            request.temporary_channel_id = temporary_channel_id;
+        }
+        if let Err(e) = self.save_channel_requests(channel_requests) {
+            tracing::error!("Failed to update channel request {request_id}: {e}");
+        }
+    }
+
+    fn save_channel_requests(
+        &self,
+        channel_requests: AuditedGuard<ChannelRequestMap>,
+    ) -> Result<(), APIError> {
+        self.persist(CHANNEL_REQUESTS_FNAME, &*channel_requests)
+    }
+
+    pub(crate) fn fee_orders(&self) -> HashMap<String, FeeOrderData> {
+        self.get_fee_orders().orders.clone()
+    }
+
+    pub(crate) fn add_fee_order(
+        &self,
+        order_id: String,
+        order: FeeOrderData,
+    ) -> Result<(), APIError> {
+        let mut fee_orders = self.get_fee_orders();
+        fee_orders.orders.insert(order_id.clone(), order);
+        self.persist(FEE_ORDERS_FNAME, &*fee_orders)
+            .inspect_err(|_| {
+                fee_orders.orders.remove(&order_id);
+            })
+    }
+
+    /// Update the given fee order, returning it as updated
+    pub(crate) fn update_fee_order<F>(
+        &self,
+        order_id: &str,
+        update: F,
+    ) -> Result<FeeOrderData, APIError>
+    where
+        F: FnOnce(&mut FeeOrderData) -> Result<(), APIError>,
+    {
+        let mut fee_orders = self.get_fee_orders();
+        let order = fee_orders
+            .orders
+            .get_mut(order_id)
+            .ok_or(APIError::UnknownFeeOrder)?;
+        update(order)?;
+        let order = order.clone();
+        self.save_fee_orders(fee_orders)?;
+        Ok(order)
+    }
+
+    /// Give the prepaid fee back to the client by failing the held HTLC
+    pub(crate) fn refund_fee_order(&self, order: &FeeOrderData) {
+        // mark the payment as failed first so HTLCs arriving later get failed as well
+        self.update_inbound_payment_status(order.payment_hash, HTLCStatus::Failed);
+        self.channel_manager
+            .fail_htlc_backwards(&order.payment_hash);
+    }
+
+    fn fee_order_paid(&self, payment_hash: &PaymentHash) {
+        let mut fee_orders = self.get_fee_orders();
+        if let Some(order) = fee_orders.orders.values_mut().find(|o| {
+            o.payment_hash == *payment_hash && o.status == FeeOrderStatus::AwaitingPayment
+        }) {
+            order.status = FeeOrderStatus::Paid;
+            if let Err(e) = self.save_fee_orders(fee_orders) {
+                tracing::error!(
+                    "Failed to mark the fee order of payment {payment_hash} as paid: {e}"
+                );
+            }
+        }
+    }
+
+    /// Close the fee order of the channel being opened with the given ID, if any, claiming the
+    /// prepaid fee if the channel is ready or refunding it if the channel has been closed
+    fn close_channel_fee_order(&self, channel_id: &ChannelId, channel_ready: bool) {
+        // orders refer to the channel by its temporary ID
+        let temporary_channel_id = self
+            .channel_ids()
+            .into_iter()
+            .find_map(|(tmp_chan_id, chan_id)| (chan_id == *channel_id).then_some(tmp_chan_id))
+            .unwrap_or(*channel_id);
+        let temporary_channel_id = hex_str(&temporary_channel_id.0);
+        let mut fee_orders = self.get_fee_orders();
+        let Some(order) = fee_orders.orders.values_mut().find(|o| {
+            o.status == FeeOrderStatus::ChannelOpening
+                && o.temporary_channel_id.as_ref() == Some(&temporary_channel_id)
+        }) else {
+            return;
+        };
+        order.status = if channel_ready {
+            FeeOrderStatus::Completed
+        } else {
+            FeeOrderStatus::Refunded
+        };
+        let order = order.clone();
+        if let Err(e) = self.save_fee_orders(fee_orders) {
+            tracing::error!("Failed to close the fee order of channel {channel_id}: {e}");
+        }
+        if channel_ready {
+            tracing::info!("EVENT: claiming the upfront fee for channel {}", channel_id);
+            self.channel_manager.claim_funds(order.payment_preimage);
+        } else {
+            tracing::info!(
+                "EVENT: refunding the upfront fee for channel {}",
+                channel_id
+            );
+            self.refund_fee_order(&order);
+        }
+    }
+
+    fn save_fee_orders(&self, fee_orders: AuditedGuard<FeeOrderMap>) -> Result<(), APIError> {
+        self.persist(FEE_ORDERS_FNAME, &*fee_orders)
+    }
+
+    pub(crate) fn submarine_swaps(&self) -> HashMap<PaymentHash, SubmarineSwapData> {
+        self.get_submarine_swaps().swaps.clone()
+    }
+
+    pub(crate) fn submarine_swap(&self, payment_hash: &PaymentHash) -> Option<SubmarineSwapData> {
+        self.get_submarine_swaps().swaps.get(payment_hash).cloned()
+    }
+
+    pub(crate) fn add_submarine_swap(
+        &self,
+        payment_hash: PaymentHash,
+        swap: SubmarineSwapData,
+    ) -> Result<(), APIError> {
+        let mut submarine_swaps = self.get_submarine_swaps();
+        self.journal.record(
+            JournalEntryKind::SubmarineSwap,
+            hex_str(&payment_hash.0),
+            &swap.status,
+        );
+        submarine_swaps.swaps.insert(payment_hash, swap);
+        self.persist(SUBMARINE_SWAPS_FNAME, &*submarine_swaps)
+            .inspect_err(|_| {
+                submarine_swaps.swaps.remove(&payment_hash);
+            })
+    }
+
+    pub(crate) fn update_submarine_swap<F>(&self, payment_hash: &PaymentHash, update: F)
+    where
+        F: FnOnce(&mut SubmarineSwapData),
+    {
+        let mut submarine_swaps = self.get_submarine_swaps();
+        if let Some(swap) = submarine_swaps.swaps.get_mut(payment_hash) {
+            let mut updated = swap.clone();
+            update(&mut updated);
+            if updated.status != swap.status {
+                self.journal.record(
+                    JournalEntryKind::SubmarineSwap,
+                    hex_str(&payment_hash.0),
+                    &updated.status,
+                );
+            }
+            *swap = updated;
+            if let Err(e) = self.save_submarine_swaps(submarine_swaps) {
+                tracing::error!("Failed to save submarine swap {payment_hash}: {e}");
+            }
+        }
+    }
+
+    pub(crate) fn fail_submarine_swap(&self, payment_hash: &PaymentHash, reason: String) {
+        tracing::error!("Submarine swap {payment_hash} failed: {reason}");
+        self.update_submarine_swap(payment_hash, |s| {
+            s.status = SubmarineSwapStatus::Failed;
+            s.failure_reason = Some(reason);
+        });
+    }
+
+    /// Give the client of a loop out its payment back by failing the held HTLC
+    pub(crate) fn refund_submarine_swap_payment(&self, payment_hash: &PaymentHash) {
+        // mark the payment as failed first so HTLCs arriving later get failed as well
+        self.update_inbound_payment_status(*payment_hash, HTLCStatus::Failed);
+        self.channel_manager.fail_htlc_backwards(payment_hash);
+    }
+
+    fn save_submarine_swaps(
+        &self,
+        submarine_swaps: AuditedGuard<SubmarineSwapMap>,
+    ) -> Result<(), APIError> {
+        self.persist(SUBMARINE_SWAPS_FNAME, &*submarine_swaps)
+    }
+
+    pub(crate) fn schedules(&self) -> HashMap<String, ScheduleData> {
+        self.get_schedules().schedules.clone()
+    }
+
+    pub(crate) fn add_schedule(
+        &self,
+        schedule_id: String,
+        schedule: ScheduleData,
+    ) -> Result<(), APIError> {
+        let mut schedules = self.get_schedules();
+        schedules.schedules.insert(schedule_id.clone(), schedule);
+        self.persist(SCHEDULES_FNAME, &*schedules).inspect_err(|_| {
+            schedules.schedules.remove(&schedule_id);
+        })
+    }
+
+    pub(crate) fn update_schedule<F>(&self, schedule_id: &str, update: F) -> Result<(), APIError>
+    where
+        F: FnOnce(&mut ScheduleData) -> Result<(), APIError>,
+    {
+        let mut schedules = self.get_schedules();
+        let schedule = schedules
+            .schedules
+            .get_mut(schedule_id)
+            .ok_or(APIError::UnknownSchedule)?;
+        update(schedule)?;
+        self.save_schedules(schedules)
+    }
+
+    pub(crate) fn remove_schedule(&self, schedule_id: &str) -> Result<(), APIError> {
+        let mut schedules = self.get_schedules();
+        schedules
+            .schedules
+            .remove(schedule_id)
+            .ok_or(APIError::UnknownSchedule)?;
+        self.save_schedules(schedules)
+    }
+
+    /// Record a run of the given schedule and move it to its next run, skipping the runs that
+    /// have been missed
+    pub(crate) fn add_schedule_run(&self, schedule_id: &str, run: ScheduleRunData) {
+        let mut schedules = self.get_schedules();
+        // the schedule could have been deleted while running
+        if let Some(schedule) = schedules.schedules.get_mut(schedule_id) {
+            if schedule.next_run_at <= run.ran_at {
+                let missed_runs = (run.ran_at - schedule.next_run_at) / schedule.interval_secs;
+                schedule.next_run_at += (missed_runs + 1) * schedule.interval_secs;
+            }
+            schedule.runs.push(run);
+            if schedule.runs.len() > MAX_SCHEDULE_RUNS {
+                schedule.runs.remove(0);
+            }
+        }
+        if let Err(e) = self.save_schedules(schedules) {
+            tracing::error!("Failed to record a run of schedule {schedule_id}: {e}");
+        }
+    }
+
+    fn save_schedules(&self, schedules: AuditedGuard<ScheduleMap>) -> Result<(), APIError> {
+        self.persist(SCHEDULES_FNAME, &*schedules)
+    }
+
+    pub(crate) fn scheduled_backups(&self) -> HashMap<String, ScheduledBackupData> {
+        self.get_scheduled_backups().backups.clone()
+    }
+
+    pub(crate) fn add_scheduled_backup(
+        &self,
+        name: String,
+        backup: ScheduledBackupData,
+    ) -> Result<(), APIError> {
+        let mut backups = self.get_scheduled_backups();
+        backups.backups.insert(name.clone(), backup);
+        self.persist(SCHEDULED_BACKUPS_FNAME, &*backups)
+            .inspect_err(|_| {
+                backups.backups.remove(&name);
+            })
     }
 
-    pub(crate) fn update_inbound_payment_status(
-        &self,
-        payment_hash: PaymentHash,
-        status: HTLCStatus,
-    ) {
-        let mut inbound = self.get_inbound_payments();
-        let payment = inbound.payments.get_mut(&payment_hash).unwrap();
-        payment.status = status;
-        self.save_inbound_payments(inbound);
+    pub(crate) fn remove_scheduled_backup(&self, name: &str) -> Result<(), APIError> {
+        let mut backups = self.get_scheduled_backups();
+        backups.backups.remove(name);
+        self.persist(SCHEDULED_BACKUPS_FNAME, &*backups)
     }
 
-    pub(crate) fn channel_ids(&self) -> HashMap<ChannelId, ChannelId> {
-        self.get_channel_ids_map().channel_ids.clone()
+    pub(crate) fn fee_report(&self) -> HashMap<String, ChannelFeeData> {
+        self.get_fee_report().channels.clone()
     }
 
-    pub(crate) fn add_channel_id(
+    fn channel_peer(&self, channel_id: &ChannelId) -> Option<PublicKey> {
+        self.channel_manager
+            .list_channels()
+            .into_iter()
+            .find(|c| c.channel_id == *channel_id)
+            .map(|c| c.counterparty.node_id)
+    }
+
+    /// Account a forward to its outbound channel
+    fn record_forward(
         &self,
-        former_temporary_channel_id: ChannelId,
         channel_id: ChannelId,
+        fee_earned_msat: Option<u64>,
+        skimmed_fee_msat: Option<u64>,
     ) {
-        let mut channel_ids_map = self.get_channel_ids_map();
-        channel_ids_map
-            .channel_ids
-            .insert(former_temporary_channel_id, channel_id);
-        self.save_channel_ids_map(channel_ids_map);
+        let peer_pubkey = self.channel_peer(&channel_id);
+        let mut fee_report = self.get_fee_report();
+        let channel_fees = fee_report
+            .channels
+            .entry(channel_id.to_string())
+            .or_insert_with(|| ChannelFeeData::new(peer_pubkey));
+        channel_fees.peer_pubkey = channel_fees.peer_pubkey.or(peer_pubkey);
+        channel_fees.forwards += 1;
+        channel_fees.fee_earned_msat += fee_earned_msat.unwrap_or(0);
+        if let Some(skimmed_fee_msat) = skimmed_fee_msat.filter(|s| *s > 0) {
+            channel_fees.skimmed_forwards += 1;
+            channel_fees.skimmed_fee_msat += skimmed_fee_msat;
+        }
+        if let Err(e) = self.save_fee_report(fee_report) {
+            tracing::error!("Failed to record a forward through channel {channel_id}: {e}");
+        }
     }
 
-    pub(crate) fn delete_channel_id(&self, channel_id: ChannelId) {
-        let mut channel_ids_map = self.get_channel_ids_map();
-        if let Some(temporary_channel_id) = channel_ids_map
-            .channel_ids
-            .clone()
-            .into_iter()
-            .find_map(|(tmp_chan_id, chan_id)| {
-                if chan_id == channel_id {
-                    Some(tmp_chan_id)
-                } else {
-                    None
-                }
-            })
-        {
-            channel_ids_map.channel_ids.remove(&temporary_channel_id);
-            self.save_channel_ids_map(channel_ids_map);
+    /// Account an amount skimmed by the counterparty from a received payment to its inbound
+    /// channel
+    fn record_counterparty_skim(&self, channel_id: ChannelId, skimmed_fee_msat: u64) {
+        let peer_pubkey = self.channel_peer(&channel_id);
+        let mut fee_report = self.get_fee_report();
+        let channel_fees = fee_report
+            .channels
+            .entry(channel_id.to_string())
+            .or_insert_with(|| ChannelFeeData::new(peer_pubkey));
+        channel_fees.peer_pubkey = channel_fees.peer_pubkey.or(peer_pubkey);
+        channel_fees.counterparty_skimmed_payments += 1;
+        channel_fees.counterparty_skimmed_fee_msat += skimmed_fee_msat;
+        if let Err(e) = self.save_fee_report(fee_report) {
+            tracing::error!("Failed to record a skim on channel {channel_id}: {e}");
         }
     }
 
-    fn save_channel_ids_map(&self, channel_ids: MutexGuard<ChannelIdsMap>) {
-        self.fs_store
-            .write("", "", CHANNEL_IDS_FNAME, &channel_ids.encode())
-            .unwrap();
+    fn save_fee_report(&self, fee_report: AuditedGuard<FeeReportMap>) -> Result<(), APIError> {
+        self.persist(FEE_REPORT_FNAME, &*fee_report)
+    }
+
+    pub(crate) fn forwarding_history(&self) -> Vec<ForwardData> {
+        self.get_forwarding_history().forwards.clone()
+    }
+
+    fn add_to_forwarding_history(&self, forward: ForwardData) {
+        let mut forwarding_history = self.get_forwarding_history();
+        forwarding_history.forwards.push(forward);
+        let _ = self.persist(FORWARDING_HISTORY_FNAME, &*forwarding_history);
     }
 }
 
-type ChainMonitor = chainmonitor::ChainMonitor<
+pub(crate) type ChainMonitor = chainmonitor::ChainMonitor<
     InMemorySigner,
     Arc<dyn Filter + Send + Sync>,
     Arc<BitcoindClient>,
@@ -375,7 +2050,7 @@ type ChainMonitor = chainmonitor::ChainMonitor<
     Arc<FilesystemLogger>,
     Arc<
         MonitorUpdatingPersister<
-            Arc<FilesystemStore>,
+            Arc<NodeStore>,
             Arc<FilesystemLogger>,
             Arc<KeysManager>,
             Arc<KeysManager>,
@@ -389,13 +2064,14 @@ pub(crate) type GossipVerifier = lightning_block_sync::gossip::GossipVerifier<
     Arc<FilesystemLogger>,
 >;
 
-pub(crate) type PeerManager = SimpleArcPeerManager<
+pub(crate) type PeerManager = LdkPeerManager<
     SocketDescriptor,
-    ChainMonitor,
-    BitcoindClient,
-    BitcoindClient,
-    GossipVerifier,
-    FilesystemLogger,
+    Arc<ChannelManager>,
+    Arc<P2PGossipSync<Arc<NetworkGraph>, GossipVerifier, Arc<FilesystemLogger>>>,
+    Arc<OnionMessenger>,
+    Arc<FilesystemLogger>,
+    Arc<PeerMessageHandler>,
+    Arc<KeysManager>,
 >;
 
 pub(crate) type Scorer = ProbabilisticScorer<Arc<NetworkGraph>, Arc<FilesystemLogger>>;
@@ -430,8 +2106,11 @@ pub(crate) struct RgbOutputSpender {
     static_state: Arc<StaticState>,
     rgb_wallet_wrapper: Arc<RgbLibWalletWrapper>,
     keys_manager: Arc<KeysManager>,
-    fs_store: Arc<FilesystemStore>,
+    kv_store: Arc<NodeStore>,
     txes: Arc<Mutex<OutputSpenderTxes>>,
+    proxy_pins: Arc<Mutex<ProxyPinMap>>,
+    consignment_proxies: Arc<Mutex<ConsignmentProxyMap>>,
+    channel_transfers: Arc<Mutex<ChannelTransferMap>>,
 }
 
 pub(crate) type OutputSweeper = ldk_sweep::OutputSweeper<
@@ -439,7 +2118,7 @@ pub(crate) type OutputSweeper = ldk_sweep::OutputSweeper<
     Arc<RgbLibWalletWrapper>,
     Arc<BitcoindClient>,
     Arc<dyn Filter + Send + Sync>,
-    Arc<FilesystemStore>,
+    Arc<NodeStore>,
     Arc<FilesystemLogger>,
     Arc<RgbOutputSpender>,
 >;
@@ -472,6 +2151,396 @@ fn _update_rgb_channel_amount(color_source: &Path, payment_hash: &PaymentHash, r
     }
 }
 
+fn redirect_funding_change(
+    unsigned_psbt: String,
+    funding_script: &ScriptBuf,
+    change_script: &ScriptBuf,
+) -> String {
+    let mut psbt = Psbt::from_str(&unsigned_psbt).unwrap();
+    // a vanilla funding PSBT only has the funding output and (optionally) the change one
+    for (txout, output) in psbt
+        .unsigned_tx
+        .output
+        .iter_mut()
+        .zip(psbt.outputs.iter_mut())
+    {
+        if txout.script_pubkey == *funding_script {
+            continue;
+        }
+        txout.script_pubkey = change_script.clone();
+        output.bip32_derivation.clear();
+        output.tap_internal_key = None;
+        output.tap_key_origins.clear();
+    }
+    psbt.to_string()
+}
+
+/// Kinds of the transfers blinded with derived factors, telling their derivations apart
+const FUNDING_BLINDING_TAG: &[u8] = b"rgb-funding-blinding";
+const SWEEP_BLINDING_TAG: &[u8] = b"rgb-sweep-blinding";
+const PAYMENT_BLINDING_TAG: &[u8] = b"rgb-payment-blinding";
+
+/// Blinding of the seal of an allocation moved by a transfer of the node (a channel funding, the
+/// sweep of closed channel outputs or an on-chain payment).
+///
+/// It's derived from the node key and the ID of the transfer (the channel, the swept outputs or
+/// the recipient), so it's unique to each transfer but can be derived again by the node alone,
+/// while other nodes cannot link transfers through it.
+fn derive_transfer_blinding(keys_manager: &KeysManager, tag: &[u8], transfer_id: &[u8]) -> u64 {
+    let mut engine = Sha256::engine();
+    engine.input(tag);
+    engine.input(&keys_manager.get_node_secret_key().secret_bytes());
+    engine.input(transfer_id);
+    let hash = Sha256::from_engine(engine);
+    u64::from_le_bytes(hash[..8].try_into().expect("8 bytes"))
+}
+
+/// Blinding of the seal of an on-chain payment to the given witness recipient, derived when
+/// enabled and otherwise left to rgb-lib. Receivers generate a witness recipient ID for each
+/// invoice, so it identifies the payment
+pub(crate) fn payment_blinding(
+    static_state: &StaticState,
+    keys_manager: &KeysManager,
+    recipient_id: &str,
+) -> Option<u64> {
+    static_state.derived_blinding.then(|| {
+        derive_transfer_blinding(keys_manager, PAYMENT_BLINDING_TAG, recipient_id.as_bytes())
+    })
+}
+
+/// Script with the size of a channel funding one, to estimate fees before it is known
+pub(crate) fn placeholder_funding_script() -> ScriptBuf {
+    let mut fake_p2wsh: [u8; 34] = [0; 34];
+    fake_p2wsh[1] = 32;
+    ScriptBuf::from_bytes(fake_p2wsh.to_vec())
+}
+
+/// Build the PSBT of a vanilla funding transaction spending all and only the given UTXOs, sending
+/// the change to the provided script unless it would be dust
+pub(crate) fn funding_psbt_from_utxos(
+    utxos: &[Utxo],
+    funding_outputs: Vec<TxOut>,
+    change_script: ScriptBuf,
+    fee_rate: f32,
+) -> Result<Psbt, APIError> {
+    let funding_value: u64 = funding_outputs.iter().map(|o| o.value).sum();
+    let input_value: u64 = utxos.iter().map(|u| u.output.value).sum();
+    let satisfaction_weight: u64 = utxos.iter().map(|u| u.satisfaction_weight).sum();
+    let fee = |tx: &Transaction| {
+        let weight = tx.weight().to_wu() + satisfaction_weight;
+        (weight as f32 / WITNESS_SCALE_FACTOR as f32 * fee_rate).ceil() as u64
+    };
+
+    let mut tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: utxos
+            .iter()
+            .map(|u| TxIn {
+                previous_output: u.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: funding_outputs,
+    };
+    tx.output.push(TxOut {
+        value: 0,
+        script_pubkey: change_script,
+    });
+    match input_value.checked_sub(funding_value + fee(&tx)) {
+        Some(change) if change >= DUST_LIMIT_MSAT / 1000 => {
+            tx.output.last_mut().expect("change output").value = change
+        }
+        _ => {
+            // the leftover, if any, goes to fees
+            tx.output.pop();
+            let needed = funding_value + fee(&tx);
+            if input_value < needed {
+                return Err(APIError::InsufficientFunds(needed - input_value));
+            }
+        }
+    }
+
+    let mut psbt = Psbt::from_unsigned_tx(tx).expect("unsigned TX");
+    for (psbt_input, utxo) in psbt.inputs.iter_mut().zip(utxos) {
+        psbt_input.witness_utxo = Some(utxo.output.clone());
+    }
+    Ok(psbt)
+}
+
+/// Build a PSBT spending the given inputs of a funding transaction back to the provided script,
+/// paying enough fees for it to replace the funding transaction
+pub(crate) fn funding_double_spend_psbt(
+    funding_psbt: &Psbt,
+    inputs: &[usize],
+    script_pubkey: ScriptBuf,
+    fee_rate: f32,
+) -> Result<Psbt, APIError> {
+    let input_value = |idx: usize| {
+        funding_psbt.inputs[idx]
+            .witness_utxo
+            .as_ref()
+            .expect("segwit input")
+            .value
+    };
+    let funding_input_value: u64 = (0..funding_psbt.inputs.len()).map(input_value).sum();
+    let funding_output_value: u64 = funding_psbt
+        .unsigned_tx
+        .output
+        .iter()
+        .map(|o| o.value)
+        .sum();
+    let funding_fee = funding_input_value - funding_output_value;
+    let spent_value: u64 = inputs.iter().map(|idx| input_value(*idx)).sum();
+
+    let mut tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: inputs
+            .iter()
+            .map(|idx| TxIn {
+                previous_output: funding_psbt.unsigned_tx.input[*idx].previous_output,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey,
+        }],
+    };
+    // a replacement needs to pay more than the replaced transaction, both in total and in rate
+    let weight = tx.weight().to_wu() + tx.input.len() as u64 * MAX_INPUT_WITNESS_WEIGHT;
+    let fee = funding_fee + (weight as f32 / WITNESS_SCALE_FACTOR as f32 * fee_rate).ceil() as u64;
+    tx.output[0].value = match spent_value.checked_sub(fee) {
+        Some(value) if value >= DUST_LIMIT_MSAT / 1000 => value,
+        _ => {
+            return Err(APIError::CannotAbandonFunding(s!(
+                "the inputs that can be double-spent don't cover the replacement fee"
+            )))
+        }
+    };
+
+    let mut psbt = Psbt::from_unsigned_tx(tx).expect("unsigned TX");
+    for (psbt_input, idx) in psbt.inputs.iter_mut().zip(inputs) {
+        psbt_input.witness_utxo = funding_psbt.inputs[*idx].witness_utxo.clone();
+    }
+    Ok(psbt)
+}
+
+/// Record the funding output of a channel opened in a batch, funding all the channels of the batch
+/// with a single transaction once all of their outputs are known. Returns whether the channel
+/// belongs to a batch.
+fn handle_batch_funding(
+    unlocked_state: &UnlockedAppState,
+    static_state: &StaticState,
+    temporary_channel_id: ChannelId,
+    counterparty_node_id: PublicKey,
+    funding_output: TxOut,
+) -> bool {
+    let mut batch = {
+        let mut funding_batches = unlocked_state.get_funding_batches();
+        let Some(idx) = funding_batches.iter().position(|b| {
+            b.funding_txid.is_none() && b.temporary_channel_ids.contains(&temporary_channel_id)
+        }) else {
+            return false;
+        };
+        let batch = &mut funding_batches[idx];
+        batch
+            .funding_outputs
+            .insert(temporary_channel_id, (counterparty_node_id, funding_output));
+        if batch.funding_outputs.len() < batch.temporary_channel_ids.len() {
+            return true;
+        }
+        funding_batches.remove(idx)
+    };
+
+    // the UTXOs may have been spent since the channels were opened
+    let outpoints: Vec<OutPoint> = batch.utxos.iter().map(|u| u.outpoint).collect();
+    let funding_outputs = batch
+        .temporary_channel_ids
+        .iter()
+        .map(|id| batch.funding_outputs[id].1.clone())
+        .collect();
+    let psbt = unlocked_state.vanilla_utxos(&outpoints).and_then(|utxos| {
+        let change_script = unlocked_state
+            .rgb_wallet_wrapper
+            .get_change_script()
+            .unwrap();
+        funding_psbt_from_utxos(&utxos, funding_outputs, change_script, batch.fee_rate)
+    });
+    let psbt = match psbt {
+        Ok(psbt) => psbt,
+        Err(e) => {
+            tracing::error!("cannot fund channel batch: {e}");
+            unlocked_state.close_unfunded_channels(&batch.temporary_channel_ids);
+            *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+            return true;
+        }
+    };
+
+    let signed_psbt = unlocked_state.rgb_sign_psbt(psbt.to_string()).unwrap();
+    let psbt = Psbt::from_str(&signed_psbt).unwrap();
+    let funding_tx = psbt.clone().extract_tx();
+    let funding_txid = funding_tx.txid();
+    let psbt_path = disk::funding_psbt_path(&static_state.color_source, funding_txid);
+    disk::write_data_file(
+        &psbt_path,
+        psbt.to_string().as_bytes(),
+        unlocked_state.kv_store.cipher(),
+    )
+    .unwrap();
+
+    let channels: Vec<(&ChannelId, &PublicKey)> = batch
+        .temporary_channel_ids
+        .iter()
+        .map(|id| (id, &batch.funding_outputs[id].0))
+        .collect();
+    if let Err(e) = unlocked_state
+        .channel_manager
+        .batch_funding_transaction_generated(&channels, funding_tx)
+    {
+        tracing::error!("ERROR: cannot fund channel batch: {e:?}");
+        *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+        return true;
+    }
+    if let Some(txid_sender) = batch.txid_sender.take() {
+        // the requester may have stopped waiting, nothing to do in that case
+        let _ = txid_sender.send(funding_txid);
+    }
+    // kept until all of its channels are pending
+    batch.funding_txid = Some(funding_txid);
+    unlocked_state.get_funding_batches().push(batch);
+    true
+}
+
+/// Finalize the inputs of a funding PSBT signed externally, which are either final already or
+/// carry the signature of a P2WPKH or P2TR key path spend
+pub(crate) fn finalize_funding_psbt(psbt: &mut Psbt) -> Result<(), APIError> {
+    for (idx, (psbt_input, txin)) in psbt
+        .inputs
+        .iter_mut()
+        .zip(&psbt.unsigned_tx.input)
+        .enumerate()
+    {
+        if psbt_input.final_script_witness.is_some() || psbt_input.final_script_sig.is_some() {
+            continue;
+        }
+        let prevout = psbt_input.witness_utxo.clone().or_else(|| {
+            psbt_input
+                .non_witness_utxo
+                .as_ref()
+                .and_then(|tx| tx.output.get(txin.previous_output.vout as usize).cloned())
+        });
+        let Some(prevout) = prevout else {
+            return Err(APIError::InvalidFundingPsbt(format!(
+                "input {idx} has no previous output"
+            )));
+        };
+        let witness = if prevout.script_pubkey.is_v0_p2wpkh() {
+            psbt_input
+                .partial_sigs
+                .iter()
+                .next()
+                .map(|(pubkey, sig)| Witness::from_slice(&[sig.to_vec(), pubkey.to_bytes()]))
+        } else if prevout.script_pubkey.is_v1_p2tr() {
+            psbt_input
+                .tap_key_sig
+                .map(|sig| Witness::from_slice(&[sig.to_vec()]))
+        } else {
+            return Err(APIError::InvalidFundingPsbt(format!(
+                "input {idx} has an unsupported script type"
+            )));
+        };
+        let Some(witness) = witness else {
+            return Err(APIError::InvalidFundingPsbt(format!(
+                "input {idx} is not signed"
+            )));
+        };
+        psbt_input.final_script_witness = Some(witness);
+        psbt_input.partial_sigs.clear();
+        psbt_input.tap_key_sig = None;
+    }
+    Ok(())
+}
+
+/// Hand the signed funding transaction of a channel to LDK, after saving its PSBT and, for a
+/// colored channel, posting the funding consignment
+pub(crate) async fn fund_channel(
+    unlocked_state: &Arc<UnlockedAppState>,
+    static_state: &StaticState,
+    temporary_channel_id: ChannelId,
+    funding: PendingFunding,
+    psbt: Psbt,
+) -> Result<Txid, APIError> {
+    let funding_tx = psbt.clone().extract_tx();
+    let funding_txid = funding_tx.txid().to_string();
+
+    let psbt_path = disk::funding_psbt_path(&static_state.color_source, &funding_txid);
+    disk::write_data_file(
+        &psbt_path,
+        psbt.to_string().as_bytes(),
+        unlocked_state.kv_store.cipher(),
+    )?;
+
+    if let Some((asset_id, recipient_id)) = funding.rgb_transfer {
+        let transfers_dir = unlocked_state
+            .rgb_get_transfers_dir()
+            .join(funding_txid.clone());
+        let asset_transfer_dir =
+            unlocked_state.rgb_get_asset_transfer_dir(transfers_dir, &asset_id);
+        let consignment_path =
+            unlocked_state.rgb_get_send_consignment_path(asset_transfer_dir, &recipient_id);
+        unlocked_state
+            .check_proxy_endpoints(&[funding.proxy_endpoint.clone()])
+            .await?;
+        let proxy_url = TransportEndpoint::new(funding.proxy_endpoint.clone())
+            .unwrap()
+            .endpoint;
+        let unlocked_state_copy = unlocked_state.clone();
+        let txid = funding_txid.clone();
+        tokio::task::spawn_blocking(move || {
+            unlocked_state_copy.rgb_post_consignment(
+                &proxy_url,
+                txid.clone(),
+                &consignment_path,
+                txid,
+                Some(0),
+            )
+        })
+        .await
+        .unwrap()?;
+        unlocked_state.record_consignment_proxy(funding_txid.clone(), funding.proxy_endpoint);
+        unlocked_state.record_channel_transfer(
+            funding_txid,
+            ChannelTransferData {
+                kind: ChannelTransferKind::Funding,
+                channel_id: None,
+                closing_txids: vec![],
+                blinding: Some(funding.blinding),
+            },
+        );
+    }
+
+    // Give the funding transaction back to LDK for opening the channel.
+    unlocked_state
+        .channel_manager
+        .funding_transaction_generated(
+            &temporary_channel_id,
+            &funding.counterparty_node_id,
+            funding_tx.clone(),
+        )
+        .map_err(|_| {
+            APIError::FailedOpenChannel(s!(
+                "the channel went away before it could be funded, the peer disconnected or refused it"
+            ))
+        })?;
+    Ok(funding_tx.txid())
+}
+
 async fn handle_ldk_events(
     event: Event,
     unlocked_state: Arc<UnlockedAppState>,
@@ -498,10 +2567,48 @@ async fn handle_ldk_events(
             .expect("Lightning funding tx should always be to a SegWit output");
             let script_buf = ScriptBuf::from_bytes(addr.to_scriptpubkey());
 
+            let funding_output = TxOut {
+                value: channel_value_satoshis,
+                script_pubkey: script_buf.clone(),
+            };
+            if handle_batch_funding(
+                &unlocked_state,
+                &static_state,
+                temporary_channel_id,
+                counterparty_node_id,
+                funding_output,
+            ) {
+                return;
+            }
+
             let is_colored = is_channel_rgb(
                 &temporary_channel_id,
                 &PathBuf::from(&static_state.color_source),
             );
+            let funding_change = unlocked_state
+                .get_funding_changes()
+                .remove(&temporary_channel_id);
+            let funding_utxos = unlocked_state
+                .get_funding_utxos()
+                .remove(&temporary_channel_id);
+            // the proxy selected when opening the channel, the peer fetches the consignment from it
+            let proxy_endpoint = unlocked_state
+                .get_funding_proxies()
+                .remove(&temporary_channel_id)
+                .unwrap_or_else(|| static_state.proxy_endpoints[0].clone());
+            let fee_rate = unlocked_state
+                .get_funding_fee_rates()
+                .remove(&temporary_channel_id)
+                .unwrap_or(static_state.fee_rate);
+            let blinding = if static_state.derived_blinding {
+                derive_transfer_blinding(
+                    &unlocked_state.keys_manager,
+                    FUNDING_BLINDING_TAG,
+                    &temporary_channel_id.0,
+                )
+            } else {
+                STATIC_BLINDING
+            };
             let (unsigned_psbt, asset_id, recipient_id) = if is_colored {
                 let (rgb_info, _) = get_rgb_channel_info_pending(
                     &temporary_channel_id,
@@ -519,84 +2626,119 @@ async fn handle_ldk_events(
                         recipient_id: recipient_id.clone(),
                         witness_data: Some(WitnessData {
                             amount_sat: channel_value_satoshis,
-                            blinding: Some(STATIC_BLINDING),
+                            blinding: Some(blinding),
                         }),
                         amount: channel_rgb_amount,
-                        transport_endpoints: vec![static_state.proxy_endpoint.clone()]
+                        transport_endpoints: vec![proxy_endpoint.clone()]
                 }]};
 
                 let unlocked_state_copy = unlocked_state.clone();
                 let unsigned_psbt = tokio::task::spawn_blocking(move || {
                     unlocked_state_copy
-                        .rgb_send_begin(recipient_map, true, FEE_RATE, MIN_CHANNEL_CONFIRMATIONS)
+                        .rgb_send_begin(recipient_map, true, fee_rate, MIN_CHANNEL_CONFIRMATIONS)
                         .unwrap()
                 })
                 .await
                 .unwrap();
                 (unsigned_psbt, Some(asset_id), Some(recipient_id))
+            } else if let Some(utxos) = funding_utxos {
+                // the requested UTXOs may have been spent since the channel was opened
+                let outpoints: Vec<OutPoint> = utxos.iter().map(|u| u.outpoint).collect();
+                let psbt = unlocked_state.vanilla_utxos(&outpoints).and_then(|utxos| {
+                    let change_script = funding_change
+                        .as_ref()
+                        .map(|c| c.script.clone())
+                        .unwrap_or_else(|| {
+                            unlocked_state
+                                .rgb_wallet_wrapper
+                                .get_change_script()
+                                .unwrap()
+                        });
+                    let funding_output = TxOut {
+                        value: channel_value_satoshis,
+                        script_pubkey: script_buf.clone(),
+                    };
+                    funding_psbt_from_utxos(&utxos, vec![funding_output], change_script, fee_rate)
+                });
+                match psbt {
+                    Ok(psbt) => (psbt.to_string(), None, None),
+                    Err(e) => {
+                        tracing::error!("cannot fund channel from the requested outpoints: {e}");
+                        let _ = unlocked_state
+                            .channel_manager
+                            .force_close_without_broadcasting_txn(
+                                &temporary_channel_id,
+                                &counterparty_node_id,
+                            );
+                        *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                        return;
+                    }
+                }
             } else {
                 let unsigned_psbt = unlocked_state
-                    .rgb_send_btc_begin(addr.to_address(), channel_value_satoshis, FEE_RATE)
+                    .rgb_send_btc_begin(addr.to_address(), channel_value_satoshis, fee_rate)
                     .unwrap();
+                let unsigned_psbt = if let Some(funding_change) = &funding_change {
+                    redirect_funding_change(unsigned_psbt, &script_buf, &funding_change.script)
+                } else {
+                    unsigned_psbt
+                };
                 (unsigned_psbt, None, None)
             };
 
-            let signed_psbt = unlocked_state.rgb_sign_psbt(unsigned_psbt).unwrap();
-            let psbt = Psbt::from_str(&signed_psbt).unwrap();
-
-            let funding_tx = psbt.clone().extract_tx();
-            let funding_txid = funding_tx.txid().to_string();
-
-            let psbt_path = static_state
-                .color_source
-                .join(format!("psbt_{funding_txid}"));
-            fs::write(psbt_path, psbt.to_string()).unwrap();
-
-            if is_colored {
-                let asset_id = asset_id.expect("is present");
-                let recipient_id = recipient_id.expect("is present");
-                let transfers_dir = unlocked_state
-                    .rgb_get_transfers_dir()
-                    .join(funding_txid.clone());
-                let asset_transfer_dir =
-                    unlocked_state.rgb_get_asset_transfer_dir(transfers_dir, &asset_id);
-                let consignment_path =
-                    unlocked_state.rgb_get_send_consignment_path(asset_transfer_dir, &recipient_id);
-                let proxy_url = TransportEndpoint::new(static_state.proxy_endpoint.clone())
-                    .unwrap()
-                    .endpoint;
-                let unlocked_state_copy = unlocked_state.clone();
-                let res = tokio::task::spawn_blocking(move || {
-                    unlocked_state_copy.rgb_post_consignment(
-                        &proxy_url,
-                        funding_txid.clone(),
-                        &consignment_path,
-                        funding_txid,
-                        Some(0),
-                    )
-                })
-                .await
-                .unwrap();
+            let unsigned_psbt = Psbt::from_str(&unsigned_psbt).unwrap();
 
-                if let Err(e) = res {
-                    tracing::error!("cannot post consignment: {e}");
-                    return;
+            if let Some(funding_change) = funding_change {
+                if let Some(vout) = unsigned_psbt
+                    .unsigned_tx
+                    .output
+                    .iter()
+                    .position(|o| o.script_pubkey == funding_change.script)
+                {
+                    // the requester may have stopped waiting, nothing to do in that case
+                    let _ = funding_change.outpoint_sender.send(OutPoint {
+                        // the TXID of a segwit transaction doesn't depend on its signatures
+                        txid: unsigned_psbt.unsigned_tx.txid(),
+                        vout: vout as u32,
+                    });
                 }
             }
 
-            let channel_manager_copy = unlocked_state.channel_manager.clone();
+            let funding = PendingFunding {
+                counterparty_node_id,
+                unsigned_psbt,
+                rgb_transfer: asset_id.zip(recipient_id),
+                proxy_endpoint,
+                blinding,
+            };
+            if unlocked_state
+                .get_external_funding()
+                .remove(&temporary_channel_id)
+            {
+                tracing::info!(
+                    "EVENT: funding PSBT of channel {} waiting for external signatures",
+                    temporary_channel_id
+                );
+                unlocked_state
+                    .get_pending_fundings()
+                    .insert(temporary_channel_id, funding);
+                return;
+            }
 
-            // Give the funding transaction back to LDK for opening the channel.
-            if channel_manager_copy
-                .funding_transaction_generated(
-                    &temporary_channel_id,
-                    &counterparty_node_id,
-                    funding_tx,
-                )
-                .is_err()
+            let signed_psbt = unlocked_state
+                .rgb_sign_psbt(funding.unsigned_psbt.to_string())
+                .unwrap();
+            let psbt = Psbt::from_str(&signed_psbt).unwrap();
+            if let Err(e) = fund_channel(
+                &unlocked_state,
+                &static_state,
+                temporary_channel_id,
+                funding,
+                psbt,
+            )
+            .await
             {
-                tracing::error!(
-                        "ERROR: Channel went away before we could fund it. The peer disconnected or refused the channel.");
+                tracing::error!("ERROR: cannot fund channel {temporary_channel_id}: {e}");
             }
         }
         Event::PaymentClaimable {
@@ -604,17 +2746,64 @@ async fn handle_ldk_events(
             purpose,
             amount_msat,
             receiver_node_id: _,
-            via_channel_id: _,
+            via_channel_id,
             via_user_channel_id: _,
-            claim_deadline: _,
-            onion_fields: _,
-            counterparty_skimmed_fee_msat: _,
+            claim_deadline,
+            onion_fields,
+            counterparty_skimmed_fee_msat,
         } => {
             tracing::info!(
                 "EVENT: received payment from payment hash {} of {} millisatoshis",
                 payment_hash,
                 amount_msat,
             );
+            let keysend = matches!(purpose, PaymentPurpose::SpontaneousPayment(_));
+            if keysend && static_state.reject_keysend {
+                tracing::info!("EVENT: failing HTLC for rejected spontaneous payment");
+                unlocked_state
+                    .channel_manager
+                    .fail_htlc_backwards(&payment_hash);
+                return;
+            }
+            if let Some(via_channel_id) = via_channel_id {
+                let rgb_payment_info_path =
+                    get_rgb_payment_info_path(&payment_hash, &static_state.ldk_data_dir, true);
+                if rgb_payment_info_path.exists() {
+                    let rgb_amount = parse_rgb_payment_info(&rgb_payment_info_path).amount;
+                    if unlocked_state.exceeds_asset_htlc_limit(&via_channel_id, rgb_amount) {
+                        tracing::info!(
+                            "EVENT: failing HTLC exceeding the asset HTLC limit of channel {}",
+                            via_channel_id
+                        );
+                        unlocked_state
+                            .channel_manager
+                            .fail_htlc_backwards(&payment_hash);
+                        return;
+                    }
+                }
+            }
+            let custom_records = onion_fields
+                .map(|f| f.custom_tlvs().clone())
+                .unwrap_or_default();
+            if keysend || !custom_records.is_empty() {
+                unlocked_state.save_inbound_claimable(
+                    payment_hash,
+                    amount_msat,
+                    custom_records,
+                    keysend,
+                );
+            }
+            if counterparty_skimmed_fee_msat > 0 {
+                tracing::info!(
+                    "EVENT: counterparty skimmed {} millisatoshis from payment hash {}",
+                    counterparty_skimmed_fee_msat,
+                    payment_hash,
+                );
+                if let Some(via_channel_id) = via_channel_id {
+                    unlocked_state
+                        .record_counterparty_skim(via_channel_id, counterparty_skimmed_fee_msat);
+                }
+            }
             let payment_preimage = match purpose {
                 PaymentPurpose::Bolt11InvoicePayment {
                     payment_preimage, ..
@@ -627,9 +2816,38 @@ async fn handle_ldk_events(
                 } => payment_preimage,
                 PaymentPurpose::SpontaneousPayment(preimage) => Some(preimage),
             };
-            unlocked_state
-                .channel_manager
-                .claim_funds(payment_preimage.unwrap());
+            match payment_preimage {
+                Some(payment_preimage) => {
+                    unlocked_state.channel_manager.claim_funds(payment_preimage);
+                }
+                None => {
+                    // hold invoice: the preimage is unknown until the invoice gets settled
+                    match unlocked_state.inbound_payment(&payment_hash) {
+                        Some(payment)
+                            if matches!(
+                                payment.status,
+                                HTLCStatus::Failed | HTLCStatus::Expired
+                            ) =>
+                        {
+                            tracing::info!("EVENT: failing HTLC for cancelled hold invoice");
+                            unlocked_state
+                                .channel_manager
+                                .fail_htlc_backwards(&payment_hash);
+                        }
+                        Some(_) => {
+                            tracing::info!("EVENT: holding HTLC until the invoice gets settled");
+                            unlocked_state.hold_inbound_payment(payment_hash, claim_deadline);
+                            unlocked_state.fee_order_paid(&payment_hash);
+                        }
+                        None => {
+                            tracing::error!("ERROR: failing HTLC for unknown hold invoice");
+                            unlocked_state
+                                .channel_manager
+                                .fail_htlc_backwards(&payment_hash);
+                        }
+                    }
+                }
+            }
         }
         Event::PaymentClaimed {
             payment_hash,
@@ -644,6 +2862,7 @@ async fn handle_ldk_events(
                 payment_hash,
                 amount_msat,
             );
+            let keysend = matches!(purpose, PaymentPurpose::SpontaneousPayment(_));
             let (payment_preimage, payment_secret) = match purpose {
                 PaymentPurpose::Bolt11InvoicePayment {
                     payment_preimage,
@@ -666,15 +2885,29 @@ async fn handle_ldk_events(
             static_state.color_source.lock().unwrap().update_rgb_channel_amount(&payment_hash, true);
 
             if unlocked_state.is_maker_swap(&payment_hash) {
-                unlocked_state.update_maker_swap_status(&payment_hash, SwapStatus::Succeeded);
+                unlocked_state.complete_maker_swap(&payment_hash);
             } else {
+                // the RGB amount is the one carried by the HTLCs, which for invoices without an
+                // RGB amount is chosen by the payer
+                let rgb_payment_info_path =
+                    get_rgb_payment_info_path(&payment_hash, &static_state.ldk_data_dir, true);
+                let asset_amount = if rgb_payment_info_path.exists() {
+                    Some(parse_rgb_payment_info(&rgb_payment_info_path).amount)
+                } else {
+                    None
+                };
                 unlocked_state.upsert_inbound_payment(
                     payment_hash,
                     HTLCStatus::Succeeded,
                     payment_preimage,
                     payment_secret,
                     Some(amount_msat),
+                    asset_amount,
+                    keysend,
                 );
+                // LDK doesn't replay the event once handled, so make sure the payment isn't left
+                // unsettled on disk
+                unlocked_state.flush_inbound_payments().await;
             }
         }
         Event::PaymentSent {
@@ -692,7 +2925,7 @@ async fn handle_ldk_events(
                     payment_hash,
                     payment_preimage
                 );
-                unlocked_state.update_maker_swap_status(&payment_hash, SwapStatus::Succeeded);
+                unlocked_state.complete_maker_swap(&payment_hash);
             } else {
                 let payment = unlocked_state.update_outbound_payment(
                     payment_id.unwrap(),
@@ -718,6 +2951,38 @@ async fn handle_ldk_events(
             ref counterparty_node_id,
             ..
         } => {
+            // channel opens stay locked in relay-only mode
+            if unlocked_state.relay_only {
+                tracing::info!(
+                    "EVENT: Rejecting inbound channel ({}) from {} while relaying only",
+                    temporary_channel_id,
+                    hex_str(&counterparty_node_id.serialize()),
+                );
+                let _ = unlocked_state
+                    .channel_manager
+                    .force_close_without_broadcasting_txn(
+                        temporary_channel_id,
+                        counterparty_node_id,
+                    );
+                return;
+            }
+            let refusal = unlocked_state
+                .get_peer_policy()
+                .refusal(counterparty_node_id);
+            if let Some(reason) = refusal {
+                tracing::info!(
+                    "EVENT: Rejecting inbound channel ({}): {}",
+                    temporary_channel_id,
+                    reason,
+                );
+                let _ = unlocked_state
+                    .channel_manager
+                    .force_close_without_broadcasting_txn(
+                        temporary_channel_id,
+                        counterparty_node_id,
+                    );
+                return;
+            }
             let mut random_bytes = [0u8; 16];
             random_bytes
                 .copy_from_slice(&unlocked_state.keys_manager.get_secure_random_bytes()[..16]);
@@ -744,7 +3009,21 @@ async fn handle_ldk_events(
             }
         }
         Event::PaymentPathSuccessful { .. } => {}
-        Event::PaymentPathFailed { .. } => {}
+        Event::PaymentPathFailed {
+            payment_id,
+            payment_hash,
+            payment_failed_permanently,
+            ..
+        } => {
+            tracing::debug!(
+                "EVENT: payment path failed for payment hash {} (permanently: {})",
+                payment_hash,
+                payment_failed_permanently
+            );
+            if let Some(payment_id) = payment_id {
+                unlocked_state.increment_outbound_payment_failed_attempts(payment_id);
+            }
+        }
         Event::ProbeSuccessful { .. } => {}
         Event::ProbeFailed { .. } => {}
         Event::PaymentFailed {
@@ -753,20 +3032,20 @@ async fn handle_ldk_events(
             payment_id,
             ..
         } => {
+            let reason = reason.unwrap_or(PaymentFailureReason::RetriesExhausted);
             tracing::error!(
                 "EVENT: Failed to send payment to payment hash {:?}: {:?}",
                 payment_hash,
-                if let Some(r) = reason {
-                    r
-                } else {
-                    PaymentFailureReason::RetriesExhausted
-                }
+                reason
             );
 
             if unlocked_state.is_maker_swap(&payment_hash) {
-                unlocked_state.update_maker_swap_status(&payment_hash, SwapStatus::Failed);
-            } else {
-                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+                let _ = unlocked_state
+                    .fail_maker_swap(&payment_hash, format!("the payment failed: {reason:?}"));
+            } else if let Err(e) =
+                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed)
+            {
+                tracing::error!("ERROR: failed to mark the payment as failed: {e}");
             }
         }
         Event::InvoiceRequestFailed { payment_id } => {
@@ -775,7 +3054,11 @@ async fn handle_ldk_events(
                 payment_id,
             );
 
-            unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+            if let Err(e) =
+                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed)
+            {
+                tracing::error!("ERROR: failed to mark the payment as failed: {e}");
+            }
         }
         Event::PaymentForwarded {
             prev_channel_id,
@@ -783,7 +3066,7 @@ async fn handle_ldk_events(
             total_fee_earned_msat,
             claim_from_onchain_tx,
             outbound_amount_forwarded_msat,
-            skimmed_fee_msat: _,
+            skimmed_fee_msat,
             prev_user_channel_id: _,
             next_user_channel_id: _,
             outbound_amount_forwarded_rgb,
@@ -813,9 +3096,40 @@ async fn handle_ldk_events(
             }
 
             if unlocked_state.is_taker_swap(&payment_hash) {
-                unlocked_state.update_taker_swap_status(&payment_hash, SwapStatus::Succeeded);
+                let _ =
+                    unlocked_state.update_taker_swap_status(&payment_hash, SwapStatus::Succeeded);
             }
 
+            unlocked_state.record_forward(
+                next_channel_id.expect("next_channel_id"),
+                total_fee_earned_msat,
+                skimmed_fee_msat,
+            );
+
+            let asset_id = |channel_id| {
+                get_rgb_channel_info_optional(
+                    channel_id,
+                    &PathBuf::from(&static_state.color_source),
+                    false,
+                )
+                .map(|(rgb_info, _)| rgb_info.contract_id.to_string())
+            };
+            unlocked_state.add_to_forwarding_history(ForwardData {
+                prev_channel_id: prev_channel_id.expect("prev_channel_id"),
+                next_channel_id: next_channel_id.expect("next_channel_id"),
+                payment_hash,
+                outbound_amount_msat: outbound_amount_forwarded_msat,
+                fee_earned_msat: total_fee_earned_msat,
+                skimmed_fee_msat,
+                inbound_asset_id: inbound_amount_forwarded_rgb
+                    .and(asset_id(&prev_channel_id.expect("prev_channel_id"))),
+                inbound_asset_amount: inbound_amount_forwarded_rgb,
+                outbound_asset_id: outbound_amount_forwarded_rgb
+                    .and(asset_id(&next_channel_id.expect("next_channel_id"))),
+                outbound_asset_amount: outbound_amount_forwarded_rgb,
+                forwarded_at: get_current_timestamp(),
+            });
+
             let read_only_network_graph = unlocked_state.network_graph.read_only();
             let nodes = read_only_network_graph.nodes();
             let channels = unlocked_state.channel_manager.list_channels();
@@ -882,7 +3196,28 @@ async fn handle_ldk_events(
                 );
             }
         }
-        Event::HTLCHandlingFailed { .. } => {}
+        Event::HTLCHandlingFailed {
+            prev_channel_id,
+            failed_next_destination,
+        } => {
+            match failed_next_destination {
+                // held intercepts get failed back by the channel manager when close to expiry
+                HTLCDestination::InvalidForward {
+                    requested_forward_scid,
+                } => {
+                    unlocked_state.get_held_intercepts().retain(|_, i| {
+                        i.prev_channel_id != prev_channel_id
+                            || i.requested_next_hop_scid != requested_forward_scid
+                    });
+                }
+                // and so do the HTLCs of hold invoices not settled before their claim deadline
+                HTLCDestination::FailedPayment { payment_hash } => {
+                    let height = unlocked_state.channel_manager.current_best_block().height;
+                    unlocked_state.fail_held_inbound_payment(payment_hash, height);
+                }
+                _ => {}
+            }
+        }
         Event::PendingHTLCsForwardable { time_forwardable } => {
             let forwarding_channel_manager = unlocked_state.channel_manager.clone();
             let min = time_forwardable.as_millis() as u64;
@@ -917,14 +3252,31 @@ async fn handle_ldk_events(
             );
 
             unlocked_state.add_channel_id(former_temporary_channel_id.unwrap(), channel_id);
+            if let Some(funding_txo) = funding_txo {
+                unlocked_state
+                    .set_funding_transfer_channel(&funding_txo.txid.to_string(), channel_id);
+            }
+            // the close address was recorded with the temporary channel ID
+            if let Some(address) =
+                unlocked_state.close_address(&former_temporary_channel_id.unwrap())
+            {
+                let _ =
+                    unlocked_state.set_close_address(former_temporary_channel_id.unwrap(), None);
+                let _ = unlocked_state.set_close_address(channel_id, Some(address));
+            }
+
+            // a batch funding transaction is completed once, after all of its channels are pending
+            if !unlocked_state.funding_batch_channel_pending(&former_temporary_channel_id.unwrap())
+            {
+                return;
+            }
 
             let funding_txid = funding_txo.txid.to_string();
-            let psbt_path = static_state
-                .color_source
-                .join(format!("psbt_{funding_txid}"));
+            let psbt_path = disk::funding_psbt_path(&static_state.color_source, &funding_txid);
 
             if psbt_path.exists() {
-                let psbt_str = fs::read_to_string(psbt_path).unwrap();
+                let psbt_str =
+                    disk::read_funding_psbt(&psbt_path, unlocked_state.kv_store.cipher()).unwrap();
 
                 let state_copy = unlocked_state.clone();
                 let psbt_str_copy = psbt_str.clone();
@@ -972,6 +3324,16 @@ async fn handle_ldk_events(
                 hex_str(&counterparty_node_id.serialize()),
             );
 
+            unlocked_state.close_channel_fee_order(channel_id, true);
+            announce_swap_pairs(&unlocked_state, &static_state.ldk_data_dir);
+
+            if let Err(e) = unlocked_state
+                .select_proxy_endpoint(&static_state.proxy_endpoints)
+                .await
+            {
+                tracing::error!("cannot refresh transfers: {e}");
+                return;
+            }
             tokio::task::spawn_blocking(move || {
                 unlocked_state.rgb_refresh().unwrap();
                 unlocked_state.rgb_refresh().unwrap()
@@ -996,9 +3358,62 @@ async fn handle_ldk_events(
                 reason
             );
 
+            // drop the funding options of a channel closed before being funded
+            unlocked_state.get_funding_changes().remove(&channel_id);
+            unlocked_state.get_funding_utxos().remove(&channel_id);
+            unlocked_state.get_funding_proxies().remove(&channel_id);
+            unlocked_state.get_funding_fee_rates().remove(&channel_id);
+            unlocked_state.get_external_funding().remove(&channel_id);
+            if unlocked_state
+                .get_pending_fundings()
+                .remove(&channel_id)
+                .is_some()
+            {
+                *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                tracing::debug!("RGB send lock set to false (channel closed before being signed)");
+            }
+            unlocked_state.abort_funding_batch(&channel_id);
+
+            if let ClosureReason::ProcessingError { err } = &reason {
+                // e.g. a cooperative close whose fee negotiation didn't converge
+                tracing::warn!("Channel {} closed with error: {}", channel_id, err);
+            }
+            if let Err(e) =
+                unlocked_state.save_channel_closure(channel_id, counterparty_node_id, &reason)
+            {
+                tracing::error!("Failed to save the closure of channel {channel_id}: {e}");
+            }
+            // a channel being converted gets reopened once its funds are back in the wallet
+            if let Err(e) = unlocked_state.update_channel_conversion(&channel_id, |c| {
+                if c.status == ChannelConversionStatus::Closing {
+                    c.status = ChannelConversionStatus::Reopening;
+                }
+            }) {
+                tracing::error!("Failed to update the conversion of channel {channel_id}: {e}");
+            }
+            announce_swap_pairs(&unlocked_state, &static_state.ldk_data_dir);
+
+            if let Some(close_address) = unlocked_state.close_address(&channel_id) {
+                // our shutdown script is only chosen by us when we initiate the close
+                if matches!(
+                    reason,
+                    ClosureReason::CounterpartyInitiatedCooperativeClosure
+                        | ClosureReason::HolderForceClosed
+                        | ClosureReason::CommitmentTxConfirmed
+                ) {
+                    tracing::warn!(
+                        "Channel {} has not been closed to its close address {}, its balance goes to the node wallet",
+                        channel_id,
+                        close_address
+                    );
+                }
+                let _ = unlocked_state.set_close_address(channel_id, None);
+            }
+
             let inbound_payments = unlocked_state.inbound_payments();
             let outbound_payments = unlocked_state.outbound_payments();
 
+            let _update = unlocked_state.snapshot_tracker.begin_update();
             for (payment_hash, payment_info) in &inbound_payments {
                 if payment_info.status == HTLCStatus::Pending {
                     unlocked_state.update_inbound_payment_status(*payment_hash, HTLCStatus::Failed);
@@ -1007,10 +3422,17 @@ async fn handle_ldk_events(
 
             for (payment_id, payment_info) in &outbound_payments {
                 if payment_info.status == HTLCStatus::Pending {
-                    unlocked_state.update_outbound_payment_status(*payment_id, HTLCStatus::Failed);
+                    if let Err(e) = unlocked_state
+                        .update_outbound_payment_status(*payment_id, HTLCStatus::Failed)
+                    {
+                        tracing::error!("ERROR: failed to mark the payment as failed: {e}");
+                    }
                 }
             }
 
+            unlocked_state.close_channel_fee_order(&channel_id, false);
+            let _ = unlocked_state.set_asset_htlc_limit(channel_id, None);
+
             unlocked_state.delete_channel_id(channel_id);
         }
         Event::DiscardFunding { channel_id, .. } => {
@@ -1054,17 +3476,23 @@ async fn handle_ldk_events(
                 })
             };
 
+            // channels can be referred to by their real SCID or by one of their aliases
+            let has_scid = |details: &ChannelDetails, scid| {
+                details.short_channel_id == Some(scid)
+                    || details.inbound_scid_alias == Some(scid)
+                    || details.outbound_scid_alias == Some(scid)
+            };
             let inbound_channel = unlocked_state
                 .channel_manager
                 .list_channels()
                 .into_iter()
-                .find(|details| details.short_channel_id == Some(prev_short_channel_id))
+                .find(|details| has_scid(details, prev_short_channel_id))
                 .expect("Should always be a valid channel");
             let outbound_channel = unlocked_state
                 .channel_manager
                 .list_channels()
                 .into_iter()
-                .find(|details| details.short_channel_id == Some(requested_next_hop_scid))
+                .find(|details| has_scid(details, requested_next_hop_scid))
                 .expect("Should always be a valid channel");
 
             let inbound_rgb_info = get_rgb_info(&inbound_channel.channel_id);
@@ -1072,7 +3500,7 @@ async fn handle_ldk_events(
 
             tracing::debug!("EVENT: Requested swap with params inbound_msat={} outbound_msat={} inbound_rgb={:?} outbound_rgb={:?} inbound_contract_id={:?}, outbound_contract_id={:?}", inbound_amount_msat, expected_outbound_amount_msat, inbound_rgb_amount, expected_outbound_rgb_amount, inbound_rgb_info.map(|i| i.0), outbound_rgb_info.map(|i| i.0));
 
-            let swaps_lock = unlocked_state.taker_swaps.lock().unwrap();
+            let swaps_lock = unlocked_state.get_taker_swaps();
             let whitelist_swap = match swaps_lock.swaps.get(&payment_hash) {
                 None => {
                     tracing::error!("ERROR: rejecting non-whitelisted swap");
@@ -1085,44 +3513,106 @@ async fn handle_ldk_events(
                 Some(x) => x,
             };
 
-            let mut fail = false;
-            if whitelist_swap.swap_info.is_from_btc() {
-                let net_msat_diff = expected_outbound_amount_msat.checked_sub(inbound_amount_msat);
+            // an expired swap is not expected anymore, even if not yet marked as such
+            if whitelist_swap.swap_info.expiry < get_current_timestamp() {
+                tracing::error!("ERROR: rejecting expired swap");
+                let timed_out_status = whitelist_swap.timed_out_status(get_current_timestamp());
+                drop(swaps_lock);
+                if let Some(status) = timed_out_status {
+                    let _ = unlocked_state.fail_taker_swap(
+                        &payment_hash,
+                        status,
+                        s!("the HTLC was intercepted after the swap expired"),
+                    );
+                }
+                unlocked_state
+                    .channel_manager
+                    .fail_intercepted_htlc(intercept_id)
+                    .unwrap();
+                return;
+            }
 
-                if inbound_rgb_amount != Some(whitelist_swap.swap_info.qty_to)
-                    || inbound_rgb_info.map(|x| x.0) != whitelist_swap.swap_info.to_asset
-                    || net_msat_diff != Some(whitelist_swap.swap_info.qty_from)
-                {
-                    fail = true;
+            let expires_at = whitelist_swap.swap_info.expiry;
+            // quantities we would send and receive by forwarding the HTLC, if the assets match
+            let swap_info = &whitelist_swap.swap_info;
+            let fill = if swap_info.is_from_btc() {
+                let net_msat_diff = expected_outbound_amount_msat.checked_sub(inbound_amount_msat);
+                if inbound_rgb_info.map(|x| x.0) == swap_info.to_asset {
+                    net_msat_diff.zip(inbound_rgb_amount)
+                } else {
+                    None
                 }
-            } else if whitelist_swap.swap_info.is_to_btc() {
+            } else if swap_info.is_to_btc() {
                 let net_msat_diff =
                     inbound_amount_msat.saturating_sub(expected_outbound_amount_msat);
-
-                if expected_outbound_rgb_amount != Some(whitelist_swap.swap_info.qty_from)
-                    || outbound_rgb_info.map(|x| x.0) != whitelist_swap.swap_info.from_asset
-                    || net_msat_diff != whitelist_swap.swap_info.qty_to
-                {
-                    fail = true;
+                if outbound_rgb_info.map(|x| x.0) == swap_info.from_asset {
+                    expected_outbound_rgb_amount.map(|amount| (amount, net_msat_diff))
+                } else {
+                    None
                 }
             } else {
                 let net_msat_diff = inbound_amount_msat.checked_sub(expected_outbound_amount_msat);
-
-                if net_msat_diff != Some(0)
-                    || expected_outbound_rgb_amount != Some(whitelist_swap.swap_info.qty_from)
-                    || outbound_rgb_info.map(|x| x.0) != whitelist_swap.swap_info.from_asset
-                    || inbound_rgb_amount != Some(whitelist_swap.swap_info.qty_to)
-                    || inbound_rgb_info.map(|x| x.0) != whitelist_swap.swap_info.to_asset
+                if net_msat_diff == Some(0)
+                    && outbound_rgb_info.map(|x| x.0) == swap_info.from_asset
+                    && inbound_rgb_info.map(|x| x.0) == swap_info.to_asset
                 {
-                    fail = true;
+                    expected_outbound_rgb_amount.zip(inbound_rgb_amount)
+                } else {
+                    None
                 }
-            }
+            };
+            // partial-fill swaps accept a fraction of the quantities, at the swap price
+            let fail =
+                !fill.is_some_and(|(qty_from, qty_to)| swap_info.check_fill(qty_from, qty_to));
 
             drop(swaps_lock);
 
             if fail {
                 tracing::error!("ERROR: swap doesn't match the whitelisted info, rejecting it");
-                unlocked_state.update_taker_swap_status(&payment_hash, SwapStatus::Failed);
+                let _ = unlocked_state.fail_taker_swap(
+                    &payment_hash,
+                    SwapStatus::Failed,
+                    format!(
+                        "the HTLC doesn't match the whitelisted swap: inbound_msat={} outbound_msat={} inbound_rgb={:?} outbound_rgb={:?} inbound_contract_id={:?} outbound_contract_id={:?}",
+                        inbound_amount_msat,
+                        expected_outbound_amount_msat,
+                        inbound_rgb_amount,
+                        expected_outbound_rgb_amount,
+                        inbound_rgb_info.map(|i| i.0),
+                        outbound_rgb_info.map(|i| i.0),
+                    ),
+                );
+                unlocked_state
+                    .channel_manager
+                    .fail_intercepted_htlc(intercept_id)
+                    .unwrap();
+                return;
+            }
+
+            if inbound_rgb_amount.is_some_and(|amount| {
+                unlocked_state.exceeds_asset_htlc_limit(&inbound_channel.channel_id, amount)
+            }) {
+                tracing::error!(
+                    "ERROR: swap exceeds the asset HTLC limit of the inbound channel, rejecting it"
+                );
+                let _ = unlocked_state.fail_taker_swap(
+                    &payment_hash,
+                    SwapStatus::Failed,
+                    s!("the HTLC exceeds the asset HTLC limit of the inbound channel"),
+                );
+                unlocked_state
+                    .channel_manager
+                    .fail_intercepted_htlc(intercept_id)
+                    .unwrap();
+                return;
+            }
+
+            // a swap already forwarded or completed cannot be forwarded again
+            if unlocked_state
+                .update_taker_swap_status(&payment_hash, SwapStatus::Pending)
+                .is_err()
+            {
+                tracing::error!("ERROR: swap is not waiting anymore, rejecting it");
                 unlocked_state
                     .channel_manager
                     .fail_intercepted_htlc(intercept_id)
@@ -1131,30 +3621,60 @@ async fn handle_ldk_events(
             }
 
             tracing::debug!("Swap is whitelisted, forwarding the htlc...");
-            unlocked_state.update_taker_swap_status(&payment_hash, SwapStatus::Pending);
+            if let Some((qty_from, qty_to)) = fill {
+                if let Err(e) = unlocked_state.set_taker_swap_fill(&payment_hash, qty_from, qty_to)
+                {
+                    tracing::error!("ERROR: cannot record the swap fill: {e}");
+                }
+            }
 
-            unlocked_state
-                .channel_manager
-                .forward_intercepted_htlc(
+            if let Err(e) = unlocked_state.channel_manager.forward_intercepted_htlc(
+                intercept_id,
+                channelmanager::NextHopForward::ShortChannelId(requested_next_hop_scid),
+                outbound_channel.counterparty.node_id,
+                expected_outbound_amount_msat,
+                expected_outbound_rgb_amount,
+            ) {
+                // the HTLC stays held until it's manually failed or it's about to expire
+                tracing::error!("ERROR: failed to forward intercepted HTLC: {:?}", e);
+                if let Err(e) = unlocked_state.set_taker_swap_failure_reason(
+                    &payment_hash,
+                    format!("failed to forward the HTLC, holding it: {e:?}"),
+                ) {
+                    tracing::error!("ERROR: cannot record the swap failure reason: {e}");
+                }
+                unlocked_state.get_held_intercepts().insert(
                     intercept_id,
-                    channelmanager::NextHopForward::ShortChannelId(requested_next_hop_scid),
-                    outbound_channel.counterparty.node_id,
-                    expected_outbound_amount_msat,
-                    expected_outbound_rgb_amount,
-                )
-                .expect("Forward should be valid");
+                    HeldIntercept {
+                        payment_hash,
+                        inbound_amount_msat,
+                        expected_outbound_amount_msat,
+                        inbound_rgb_amount,
+                        expected_outbound_rgb_amount,
+                        inbound_contract_id: inbound_rgb_info.map(|i| i.0),
+                        outbound_contract_id: outbound_rgb_info.map(|i| i.0),
+                        prev_channel_id: inbound_channel.channel_id,
+                        requested_next_hop_scid,
+                        intercepted_at: get_current_timestamp(),
+                        expires_at,
+                    },
+                );
+            }
+        }
+        Event::BumpTransaction(mut event) => {
+            unlocked_state.apply_bump_fee_rate(&mut event);
+            unlocked_state.bump_tx_event_handler.handle_event(&event)
         }
-        Event::BumpTransaction(event) => unlocked_state.bump_tx_event_handler.handle_event(&event),
         Event::ConnectionNeeded { node_id, addresses } => {
+            let tor_proxy = static_state.tor_proxy;
             tokio::spawn(async move {
                 for address in addresses {
-                    if let Ok(sockaddrs) = address.to_socket_addrs() {
-                        for addr in sockaddrs {
-                            let pm = Arc::clone(&unlocked_state.peer_manager);
-                            if connect_peer_if_necessary(node_id, addr, pm).await.is_ok() {
-                                return;
-                            }
-                        }
+                    let pm = Arc::clone(&unlocked_state.peer_manager);
+                    if connect_peer_if_necessary(node_id, address, tor_proxy, pm)
+                        .await
+                        .is_ok()
+                    {
+                        return;
                     }
                 }
             });
@@ -1180,8 +3700,12 @@ impl OutputSpender for RgbOutputSpender {
             return Ok(tx.clone());
         }
 
-        let mut vout = 0;
+        // the outputs receiving the assets follow the ones requested by LDK, one for each contract
+        let mut vout = outputs.len() as u32;
         let mut vanilla_descriptor = true;
+        let mut closing_txids = vec![];
+        // the swept outputs identify the sweep, for deriving its blinding
+        let mut swept_outpoints = vec![];
 
         let mut txouts = outputs.clone();
         let mut asset_info: HashMap<ContractId, (u32, u64, String, Vec<Outpoint>)> = map![];
@@ -1193,6 +3717,9 @@ impl OutputSpender for RgbOutputSpender {
                 SpendableOutputDescriptor::StaticOutput { ref outpoint, .. } => *outpoint,
             };
 
+            swept_outpoints.extend_from_slice(&outpoint.txid[..]);
+            swept_outpoints.extend_from_slice(&outpoint.index.to_le_bytes());
+
             let txid = outpoint.txid;
             let txid_str = txid.to_string();
 
@@ -1209,6 +3736,9 @@ impl OutputSpender for RgbOutputSpender {
             }
 
             vanilla_descriptor = false;
+            if !closing_txids.contains(&txid_str) {
+                closing_txids.push(txid_str.clone());
+            }
 
             let closing_height = self
                 .rgb_wallet_wrapper
@@ -1231,7 +3761,7 @@ impl OutputSpender for RgbOutputSpender {
                 new_asset = true;
                 let receive_data = self
                     .rgb_wallet_wrapper
-                    .witness_receive(vec![self.static_state.proxy_endpoint.clone()])
+                    .witness_receive(None, None, self.static_state.proxy_endpoints.clone(), 0)
                     .unwrap();
                 let script_pubkey = script_buf_from_recipient_id(receive_data.recipient_id.clone())
                     .unwrap()
@@ -1273,7 +3803,8 @@ impl OutputSpender for RgbOutputSpender {
             );
         }
 
-        let feerate_sat_per_1000_weight = FEE_RATE as u32 * 250; // 1 sat/vB = 250 sat/kw
+        // 1 sat/vB = 250 sat/kw
+        let feerate_sat_per_1000_weight = (self.static_state.fee_rate * 250.0) as u32;
         let (psbt, _expected_max_weight) =
             SpendableOutputDescriptor::create_spendable_outputs_psbt(
                 secp_ctx,
@@ -1285,25 +3816,57 @@ impl OutputSpender for RgbOutputSpender {
             )
             .unwrap();
 
+        // a sweep built again after a restart spends the same outputs, so it gets the same blinding
+        let blinding = self.static_state.derived_blinding.then(|| {
+            derive_transfer_blinding(&self.keys_manager, SWEEP_BLINDING_TAG, &swept_outpoints)
+        });
+
         let mut asset_info_map = map![];
         for (contract_id, (vout, amt_rgb, _, input_outpoints)) in asset_info.clone() {
+            // the swept outputs can belong to channels of different contracts and schemas, each
+            // one is colored with the interface of its own contract
+            let iface = self
+                .rgb_wallet_wrapper
+                .get_asset_iface(contract_id)
+                .map_err(|e| tracing::error!("cannot sweep asset {contract_id}: {e}"))?;
             asset_info_map.insert(
                 contract_id,
                 AssetColoringInfo {
-                    iface: AssetIface::RGB20,
+                    iface,
                     output_map: HashMap::from_iter([(vout, amt_rgb)]),
                     input_outpoints,
-                    static_blinding: None,
+                    static_blinding: blinding,
                 },
             );
         }
 
         let coloring_info = ColoringInfo {
             asset_info_map,
-            static_blinding: None,
+            static_blinding: blinding,
             nonce: None,
         };
 
+        // consignments will be posted to the first of our proxies accepting them, check which ones
+        // can be used before consuming the RGB inputs. This is called from the async runtime without
+        // being able to await, so the check runs on a blocking thread with a runtime of its own
+        let proxy_pins = self.proxy_pins.lock().unwrap().pins.clone();
+        let proxy_endpoints = self.static_state.proxy_endpoints.clone();
+        let res = futures::executor::block_on(tokio::task::spawn_blocking(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(usable_proxy_endpoints(&proxy_pins, &proxy_endpoints))
+        }))
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()));
+        let proxy_endpoints = match res {
+            Ok(proxy_endpoints) => proxy_endpoints,
+            Err(e) => {
+                tracing::error!("cannot post consignment: {e}");
+                return Err(());
+            }
+        };
+
         let mut psbt = RgbLibPsbt::from_str(&psbt.to_string()).unwrap();
         let consignments = self
             .rgb_wallet_wrapper
@@ -1321,9 +3884,6 @@ impl OutputSpender for RgbOutputSpender {
 
         let closing_txid = spending_tx.txid().to_string();
 
-        let handle = Handle::current();
-        let _ = handle.enter();
-
         for consignment in consignments {
             let contract_id = consignment.contract_id();
 
@@ -1332,44 +3892,234 @@ impl OutputSpender for RgbOutputSpender {
             let consignment_path = self
                 .static_state
                 .color_source
-                .join(format!("consignment_{}", closing_txid.clone()));
+                .join(format!("consignment_{closing_txid}_{contract_id}"));
             consignment
                 .save_file(&consignment_path)
                 .expect("successful save");
-            let proxy_url = TransportEndpoint::new(self.static_state.proxy_endpoint.clone())
-                .unwrap()
-                .endpoint;
-            let rgb_wallet_wrapper_copy = self.rgb_wallet_wrapper.clone();
-            let closing_txid_copy = closing_txid.clone();
-            let consignment_path_copy = consignment_path.clone();
-            let res = futures::executor::block_on(tokio::task::spawn_blocking(move || {
-                rgb_wallet_wrapper_copy.post_consignment(
-                    &proxy_url,
-                    recipient_id,
-                    &consignment_path_copy,
-                    closing_txid_copy,
-                    Some(vout),
-                )
-            }));
-            if let Err(e) = res {
-                tracing::error!("cannot post consignment: {e}");
-                return Err(());
+            let mut posted_to = None;
+            for proxy_endpoint in &proxy_endpoints {
+                let proxy_url = TransportEndpoint::new(proxy_endpoint.clone())
+                    .unwrap()
+                    .endpoint;
+                // posting is blocking I/O done while holding the RGB wallet lock, so it runs on a
+                // blocking thread instead of a worker of the runtime
+                let rgb_wallet_wrapper = self.rgb_wallet_wrapper.clone();
+                let (recipient_id, consignment_path, closing_txid) = (
+                    recipient_id.clone(),
+                    consignment_path.clone(),
+                    closing_txid.clone(),
+                );
+                let res = futures::executor::block_on(tokio::task::spawn_blocking(move || {
+                    rgb_wallet_wrapper.post_consignment(
+                        &proxy_url,
+                        recipient_id,
+                        &consignment_path,
+                        closing_txid,
+                        Some(vout),
+                    )
+                }))
+                .map_err(|e| e.to_string())
+                .and_then(|r| r.map_err(|e| e.to_string()));
+                match res {
+                    Ok(()) => {
+                        posted_to = Some(proxy_endpoint.clone());
+                        break;
+                    }
+                    Err(e) => tracing::warn!("cannot post consignment to {proxy_endpoint}: {e}"),
+                }
             }
+            let Some(proxy_endpoint) = posted_to else {
+                tracing::error!("cannot post consignment to any of the proxies");
+                return Err(());
+            };
             fs::remove_file(&consignment_path).unwrap();
+
+            let mut consignment_proxies = self.consignment_proxies.lock().unwrap();
+            consignment_proxies
+                .proxies
+                .insert(closing_txid.clone(), proxy_endpoint);
+            self.kv_store
+                .write(
+                    "",
+                    "",
+                    CONSIGNMENT_PROXIES_FNAME,
+                    &consignment_proxies.encode(),
+                )
+                .map_err(|e| tracing::error!("cannot persist consignment proxies: {e}"))?;
         }
 
+        let mut channel_transfers = self.channel_transfers.lock().unwrap();
+        channel_transfers.transfers.insert(
+            closing_txid,
+            ChannelTransferData {
+                kind: ChannelTransferKind::Sweep,
+                channel_id: None,
+                closing_txids,
+                blinding,
+            },
+        );
+        self.kv_store
+            .write("", "", CHANNEL_TRANSFERS_FNAME, &channel_transfers.encode())
+            .map_err(|e| tracing::error!("cannot persist channel transfers: {e}"))?;
+
         txes.insert(descriptors_hash, spending_tx.clone());
-        self.fs_store
+        self.kv_store
             .write("", "", OUTPUT_SPENDER_TXES, &txes.encode())
-            .unwrap();
+            .map_err(|e| tracing::error!("cannot persist output spender TXs: {e}"))?;
 
         Ok(spending_tx)
     }
 }
 
+/// Persist the inbound payments as they change, until the node gets locked.
+///
+/// Each payment is stored under its own key and the inbound payments lock is only held to encode
+/// the entry being written, so concurrent claims and invoice creations don't wait on each other's
+/// disk writes. Changes queued while writing are coalesced, writing each payment once with its
+/// latest state.
+///
+/// Payments that fail to be written stay dirty: they're retried periodically, with the next
+/// changes and before notifying a flush, and a last time once the node gets locked.
+fn persist_inbound_payments(
+    inbound_payments: Arc<Mutex<InboundPaymentInfoStorage>>,
+    kv_store: Arc<NodeStore>,
+    updates: mpsc::Receiver<InboundPaymentUpdate>,
+) {
+    let mut dirty = HashSet::new();
+    loop {
+        let update = if dirty.is_empty() {
+            match updates.recv() {
+                Ok(update) => Some(update),
+                Err(_) => break,
+            }
+        } else {
+            match updates.recv_timeout(INBOUND_PAYMENTS_RETRY_INTERVAL) {
+                Ok(update) => Some(update),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        };
+        let mut flushes = vec![];
+        for update in update.into_iter().chain(updates.try_iter()) {
+            match update {
+                InboundPaymentUpdate::Changed(payment_hash) => {
+                    dirty.insert(payment_hash);
+                }
+                InboundPaymentUpdate::Flush(done) => flushes.push(done),
+            }
+        }
+        write_inbound_payments(&inbound_payments, &kv_store, &mut dirty);
+        if !flushes.is_empty() {
+            // the caller expects the changes to be stored, so they're retried before answering
+            for _ in 1..INBOUND_PAYMENTS_FLUSH_ATTEMPTS {
+                if dirty.is_empty() {
+                    break;
+                }
+                std::thread::sleep(INBOUND_PAYMENTS_RETRY_INTERVAL);
+                write_inbound_payments(&inbound_payments, &kv_store, &mut dirty);
+            }
+        }
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+    write_inbound_payments(&inbound_payments, &kv_store, &mut dirty);
+    if !dirty.is_empty() {
+        tracing::error!(
+            "Failed to persist {} inbound payments before locking the node",
+            dirty.len()
+        );
+    }
+}
+
+/// Write the latest state of the given inbound payments, keeping the ones that failed
+fn write_inbound_payments(
+    inbound_payments: &Mutex<InboundPaymentInfoStorage>,
+    kv_store: &NodeStore,
+    dirty: &mut HashSet<PaymentHash>,
+) {
+    dirty.retain(|payment_hash| {
+        let payment_info = lock(inbound_payments, "inbound_payments")
+            .payments
+            .get(payment_hash)
+            .map(|p| p.encode());
+        let Some(payment_info) = payment_info else {
+            return false;
+        };
+        match kv_store.write(
+            INBOUND_PAYMENTS_NAMESPACE,
+            "",
+            &hex_str(&payment_hash.0),
+            &payment_info,
+        ) {
+            Ok(()) => false,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to persist inbound payment {payment_hash}, will retry: {e}"
+                );
+                true
+            }
+        }
+    });
+}
+
+/// Statuses of the payments and swaps in the store, to check the state journal against
+fn stored_statuses(
+    inbound_payments: &InboundPaymentInfoStorage,
+    outbound_payments: &OutboundPaymentInfoStorage,
+    maker_swaps: &SwapMap,
+    taker_swaps: &SwapMap,
+    submarine_swaps: &SubmarineSwapMap,
+) -> HashMap<(JournalEntryKind, String), StoredStatus> {
+    let payment_status = |status: HTLCStatus| StoredStatus {
+        status: format!("{status:?}"),
+        is_final: !matches!(status, HTLCStatus::Pending | HTLCStatus::Claimable),
+    };
+    let swap_status = |status: &SwapStatus| StoredStatus {
+        status: format!("{status:?}"),
+        is_final: !matches!(status, SwapStatus::Waiting | SwapStatus::Pending),
+    };
+    let mut statuses = HashMap::new();
+    for (payment_hash, payment) in &inbound_payments.payments {
+        statuses.insert(
+            (JournalEntryKind::InboundPayment, hex_str(&payment_hash.0)),
+            payment_status(payment.status),
+        );
+    }
+    for (payment_id, payment) in &outbound_payments.payments {
+        statuses.insert(
+            (JournalEntryKind::OutboundPayment, hex_str(&payment_id.0)),
+            payment_status(payment.status),
+        );
+    }
+    for (kind, swaps) in [
+        (JournalEntryKind::MakerSwap, maker_swaps),
+        (JournalEntryKind::TakerSwap, taker_swaps),
+    ] {
+        for (payment_hash, swap) in &swaps.swaps {
+            statuses.insert((kind, hex_str(&payment_hash.0)), swap_status(&swap.status));
+        }
+    }
+    for (payment_hash, swap) in &submarine_swaps.swaps {
+        statuses.insert(
+            (JournalEntryKind::SubmarineSwap, hex_str(&payment_hash.0)),
+            StoredStatus {
+                status: format!("{:?}", swap.status),
+                is_final: matches!(
+                    swap.status,
+                    SubmarineSwapStatus::Succeeded
+                        | SubmarineSwapStatus::Refunded
+                        | SubmarineSwapStatus::Failed
+                ),
+            },
+        );
+    }
+    statuses
+}
+
 pub(crate) async fn start_ldk(
     app_state: Arc<AppState>,
-    mnemonic: Mnemonic,
+    ldk_keys: LdkKeys,
 ) -> Result<(LdkBackgroundServices, Arc<UnlockedAppState>), APIError> {
     let static_state = &app_state.static_state;
 
@@ -1378,7 +4128,6 @@ pub(crate) async fn start_ldk(
     let color_source_path = PathBuf::from(&color_source);
     let logger = static_state.logger.clone();
     let network = static_state.network;
-    let ldk_peer_listening_port = static_state.ldk_peer_listening_port;
     let ldk_announced_listen_addr = static_state.ldk_announced_listen_addr.clone();
     let ldk_announced_node_name = static_state.ldk_announced_node_name;
     let indexer_url = static_state.indexer_url.clone();
@@ -1392,20 +4141,99 @@ pub(crate) async fn start_ldk(
     // broadcaster.
     let broadcaster = bitcoind_client.clone();
 
+    // The LDK account key, only available when unlocking with the mnemonic
+    let ldk_xprv = match &ldk_keys {
+        LdkKeys::Mnemonic(mnemonic, _) => {
+            let xkey: ExtendedKey = mnemonic
+                .clone()
+                .into_extended_key()
+                .expect("a valid key should have been provided");
+            let master_xprv = &xkey
+                .into_xprv(network)
+                .expect("should be possible to get an extended private key");
+            Some(
+                master_xprv
+                    .ckd_priv(&Secp256k1_30::new(), ChildNumber::Hardened { index: 535 })
+                    .unwrap(),
+            )
+        }
+        LdkKeys::Relay(_) => None,
+    };
+
+    // Initialize Persistence
+    let vss_key = match &ldk_keys {
+        LdkKeys::Mnemonic(..) => ldk_xprv.as_ref().map(derive_vss_key),
+        LdkKeys::Relay(relay_keys) => relay_keys.vss_key,
+    };
+    let vss_mirror = match (&static_state.vss_url, vss_key) {
+        (Some(vss_url), Some(vss_key)) => Some(
+            VssMirror::new(vss_url, vss_key)
+                .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?,
+        ),
+        (Some(_), None) => {
+            tracing::warn!("Relay keys have no VSS key, unlock the node once to mirror to VSS");
+            None
+        }
+        (None, _) => None,
+    };
+    let storage_key = match &ldk_keys {
+        LdkKeys::Mnemonic(_, storage_key) => *storage_key,
+        LdkKeys::Relay(relay_keys) => relay_keys.storage_key,
+    };
+    let store_cipher = storage_key.as_ref().map(StoreCipher::new);
+    // the account key identifies the node in a shared store, as it's kept by node ID rotations
+    let account_xpub = match &ldk_keys {
+        LdkKeys::Mnemonic(mnemonic, _) => {
+            get_account_xpub(network.into(), &mnemonic.to_string()).unwrap()
+        }
+        LdkKeys::Relay(relay_keys) => ExtendedPubKey::from_str(&relay_keys.account_xpub)
+            .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?,
+    };
+    let kv_store = Arc::new(
+        NodeStore::new(
+            static_state.store_backend,
+            static_state.postgres_url.as_deref(),
+            &color_source_path,
+            &account_xpub.identifier().to_string(),
+            store_cipher,
+            vss_mirror,
+        )
+        .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?,
+    );
+    disk::migrate_to_encrypted_storage(&kv_store, &color_source_path)
+        .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?;
+    disk::decrypt_rgb_data_files(&color_source_path, kv_store.cipher())
+        .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?;
+
     // Initialize the KeysManager
     // The key seed that we use to derive the node privkey (that corresponds to the node pubkey) and
     // other secret key material.
-    let xkey: ExtendedKey = mnemonic
-        .clone()
-        .into_extended_key()
-        .expect("a valid key should have been provided");
-    let master_xprv = &xkey
-        .into_xprv(network)
-        .expect("should be possible to get an extended private key");
-    let xprv: ExtendedPrivKey = master_xprv
-        .ckd_priv(&Secp256k1_30::new(), ChildNumber::Hardened { index: 535 })
-        .unwrap();
-    let ldk_seed: [u8; 32] = xprv.private_key.secret_bytes();
+    let mut node_id_rotation = disk::read_node_id_rotation(&kv_store, NODE_ID_ROTATION_FNAME);
+    let (ldk_seed, mnemonic) = match ldk_keys {
+        LdkKeys::Mnemonic(mnemonic, _) => {
+            let xprv: ExtendedPrivKey = ldk_xprv.expect("derived from the mnemonic");
+            // A node ID rotation waiting for a restart switches to the new key, starting from a
+            // clean LDK state as all channels have been closed and their funds swept
+            let key_index = if let Some(rotation) = &node_id_rotation {
+                if rotation.status == NodeIdRotationStatus::RestartRequired {
+                    archive_ldk_state(&kv_store, &color_source_path, rotation.key_index - 1)
+                        .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?;
+                }
+                rotation.active_key_index()
+            } else {
+                0
+            };
+            let ldk_seed: [u8; 32] = derive_ldk_seed(&xprv, key_index);
+            (ldk_seed, Some(mnemonic))
+        }
+        LdkKeys::Relay(relay_keys) => {
+            // the rotation is completed by the next unlock, which has access to the new key
+            node_id_rotation = None;
+            (relay_keys.ldk_seed, None)
+        }
+    };
+    let relay_only = mnemonic.is_none();
+
     let cur = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap();
@@ -1416,10 +4244,27 @@ pub(crate) async fn start_ldk(
         color_source_path.clone(),
     ));
 
-    // Initialize Persistence
-    let fs_store = Arc::new(FilesystemStore::new(color_source.clone()));
+    // Keep the relay keys in memory when unlocking, for relaying once the node is locked, and drop
+    // the ones saved by earlier versions
+    if !relay_only {
+        if static_state.relay_mode {
+            *app_state.get_relay_keys() = Some(RelayKeys {
+                ldk_seed,
+                account_xpub: account_xpub.to_string(),
+                vss_key,
+                storage_key,
+            });
+        }
+        // the legacy entry is plaintext, so it's unreadable once storage encryption is enabled
+        let legacy_relay_keys = kv_store.read("", "", RELAY_KEYS_FNAME);
+        if !matches!(&legacy_relay_keys, Err(e) if e.kind() == std::io::ErrorKind::NotFound) {
+            kv_store
+                .remove("", "", RELAY_KEYS_FNAME, false)
+                .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?;
+        }
+    }
     let persister = Arc::new(MonitorUpdatingPersister::new(
-        Arc::clone(&fs_store),
+        Arc::clone(&kv_store),
         Arc::clone(&logger),
         1000,
         Arc::clone(&keys_manager),
@@ -1447,16 +4292,16 @@ pub(crate) async fn start_ldk(
         .expect("Failed to fetch best block header and best block");
 
     // Initialize routing ProbabilisticScorer
-    let network_graph_path = color_source.join("network_graph");
     let network_graph = Arc::new(disk::read_network(
-        &network_graph_path,
+        &kv_store,
+        NETWORK_GRAPH_PERSISTENCE_KEY,
         network,
         logger.clone(),
     ));
 
-    let scorer_path = color_source.join("scorer");
     let scorer = Arc::new(RwLock::new(disk::read_scorer(
-        &scorer_path,
+        &kv_store,
+        SCORER_PERSISTENCE_KEY,
         Arc::clone(&network_graph),
         Arc::clone(&logger),
     )));
@@ -1479,10 +4324,20 @@ pub(crate) async fn start_ldk(
     user_config
         .channel_handshake_config
         .negotiate_anchors_zero_fee_htlc_tx = true;
+    // accept private channels hiding their real SCID, when requested by the peer
+    user_config.channel_handshake_config.negotiate_scid_privacy = true;
     user_config.manually_accept_inbound_channels = true;
+    // inbound channels get the node limits, outbound ones can override them
+    static_state
+        .htlc_limits
+        .apply(&mut user_config.channel_handshake_config);
     let mut restarting_node = true;
     let (channel_manager_blockhash, channel_manager) = {
-        if let Ok(mut f) = fs::File::open(color_source.join("manager")) {
+        if let Ok(data) = kv_store.read(
+            CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE,
+            CHANNEL_MANAGER_PERSISTENCE_KEY,
+        ) {
             let mut channel_monitor_mut_references = Vec::new();
             for (_, channel_monitor) in channelmonitors.iter_mut() {
                 channel_monitor_mut_references.push(channel_monitor);
@@ -1500,7 +4355,7 @@ pub(crate) async fn start_ldk(
                 channel_monitor_mut_references,
                 color_source_path.clone(),
             );
-            <(BlockHash, ChannelManager)>::read(&mut f, read_args).unwrap()
+            <(BlockHash, ChannelManager)>::read(&mut &data[..], read_args).unwrap()
         } else {
             // We're starting a fresh node.
             restarting_node = false;
@@ -1530,22 +4385,21 @@ pub(crate) async fn start_ldk(
     };
 
     // Prepare the RGB wallet
-    let mnemonic_str = mnemonic.to_string();
     let bitcoin_network = network.into();
-    let account_xpub = get_account_xpub(bitcoin_network, &mnemonic_str).unwrap();
     let data_dir = static_state
         .storage_dir_path
         .clone()
         .to_string_lossy()
         .to_string();
+    let max_allocations_per_utxo = static_state.max_allocations_per_utxo;
     let mut rgb_wallet = tokio::task::spawn_blocking(move || {
         RgbLibWallet::new(WalletData {
             data_dir,
             bitcoin_network,
             database_type: DatabaseType::Sqlite,
-            max_allocations_per_utxo: 1,
+            max_allocations_per_utxo,
             pubkey: account_xpub.to_string(),
-            mnemonic: Some(mnemonic.to_string()),
+            mnemonic: mnemonic.map(|m| m.to_string()),
             vanilla_keychain: None,
         })
         .expect("valid rgb-lib wallet")
@@ -1573,18 +4427,36 @@ pub(crate) async fn start_ldk(
         rgb_online.clone(),
     ));
 
+    // Read pinned proxies, also needed to sweep RGB outputs
+    let proxy_pins = Arc::new(Mutex::new(disk::read_proxy_pins(
+        &kv_store,
+        PROXY_PINS_FNAME,
+    )));
+    let consignment_proxies = Arc::new(Mutex::new(disk::read_consignment_proxies(
+        &kv_store,
+        CONSIGNMENT_PROXIES_FNAME,
+    )));
+    let channel_transfers = Arc::new(Mutex::new(disk::read_channel_transfers(
+        &kv_store,
+        CHANNEL_TRANSFERS_FNAME,
+    )));
+
     // Initialize the OutputSweeper.
     let txes = Arc::new(Mutex::new(disk::read_output_spender_txes(
-        &color_source.join(OUTPUT_SPENDER_TXES),
+        &kv_store,
+        OUTPUT_SPENDER_TXES,
     )));
     let rgb_output_spender = Arc::new(RgbOutputSpender {
         static_state: static_state.clone(),
         rgb_wallet_wrapper: rgb_wallet_wrapper.clone(),
         keys_manager: keys_manager.clone(),
-        fs_store: fs_store.clone(),
+        kv_store: kv_store.clone(),
         txes,
+        proxy_pins: proxy_pins.clone(),
+        consignment_proxies: consignment_proxies.clone(),
+        channel_transfers: channel_transfers.clone(),
     });
-    let (sweeper_best_block, output_sweeper) = match fs_store.read(
+    let (sweeper_best_block, output_sweeper) = match kv_store.read(
         OUTPUT_SWEEPER_PERSISTENCE_PRIMARY_NAMESPACE,
         OUTPUT_SWEEPER_PERSISTENCE_SECONDARY_NAMESPACE,
         OUTPUT_SWEEPER_PERSISTENCE_KEY,
@@ -1597,7 +4469,7 @@ pub(crate) async fn start_ldk(
                 None,
                 rgb_output_spender,
                 rgb_wallet_wrapper.clone(),
-                fs_store.clone(),
+                kv_store.clone(),
                 logger.clone(),
             );
             (channel_manager.current_best_block(), sweeper)
@@ -1609,7 +4481,7 @@ pub(crate) async fn start_ldk(
                 None,
                 rgb_output_spender.clone(),
                 rgb_wallet_wrapper.clone(),
-                fs_store.clone(),
+                kv_store.clone(),
                 logger.clone(),
             );
             let mut reader = std::io::Cursor::new(&mut bytes);
@@ -1704,11 +4576,29 @@ pub(crate) async fn start_ldk(
         .unwrap()
         .as_secs();
     rand::thread_rng().fill_bytes(&mut ephemeral_bytes);
+    let channel_requests = Arc::new(Mutex::new(disk::read_channel_requests(
+        &kv_store,
+        CHANNEL_REQUESTS_FNAME,
+    )));
+    let (swap_offer_sender, swap_offer_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (submarine_swap_sender, submarine_swap_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let peer_policy = Arc::new(Mutex::new(disk::read_peer_policy(
+        &kv_store,
+        PEER_POLICY_FNAME,
+    )));
+    let peer_message_handler = Arc::new(PeerMessageHandler::new(
+        channel_requests.clone(),
+        peer_policy.clone(),
+        kv_store.clone(),
+        app_state.event_sender.clone(),
+        swap_offer_sender,
+        submarine_swap_sender,
+    ));
     let lightning_msg_handler = MessageHandler {
         chan_handler: channel_manager.clone(),
         route_handler: gossip_sync.clone(),
         onion_message_handler: onion_messenger.clone(),
-        custom_message_handler: IgnoringMessageHandler {},
+        custom_message_handler: peer_message_handler.clone(),
     };
     let peer_manager: Arc<PeerManager> = Arc::new(PeerManager::new(
         lightning_msg_handler,
@@ -1727,32 +4617,70 @@ pub(crate) async fn start_ldk(
     );
     gossip_sync.add_utxo_lookup(Some(utxo_lookup));
 
+    // Complete a node ID rotation now that the new key is in use
+    if let Some(rotation) = node_id_rotation
+        .as_mut()
+        .filter(|r| r.status == NodeIdRotationStatus::RestartRequired)
+    {
+        rotation.status = NodeIdRotationStatus::Completed;
+        rotation.new_pubkey = Some(channel_manager.get_our_node_id());
+        rotation.completed_at = Some(get_current_timestamp());
+        kv_store
+            .write("", "", NODE_ID_ROTATION_FNAME, &rotation.encode())
+            .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?;
+        tracing::info!(
+            "node ID rotated from {} to {}",
+            rotation.old_pubkey,
+            channel_manager.get_our_node_id()
+        );
+    }
+
     // ## Running LDK
     // Initialize networking
 
-    let peer_manager_connection_handler = peer_manager.clone();
-    let listening_port = ldk_peer_listening_port;
+    // the default set at runtime overrides the one given at startup
+    if let Some(announce_channels) =
+        disk::read_channel_announcement(&kv_store, CHANNEL_ANNOUNCEMENT_FNAME)
+    {
+        *app_state.get_announce_channels() = announce_channels;
+    }
+
     let stop_processing = Arc::new(AtomicBool::new(false));
-    let stop_listen = Arc::clone(&stop_processing);
-    tokio::spawn(async move {
-        let listener = tokio::net::TcpListener::bind(format!("[::]:{}", listening_port))
-            .await
-            .expect("Failed to bind to listen port - is something else already listening on it?");
-        loop {
-            let peer_mgr = peer_manager_connection_handler.clone();
-            let tcp_stream = listener.accept().await.unwrap().0;
-            if stop_listen.load(Ordering::Acquire) {
-                return;
-            }
-            tokio::spawn(async move {
-                lightning_net_tokio::setup_inbound(
-                    peer_mgr.clone(),
+    let inbound_connections = Arc::new(AtomicUsize::new(0));
+    for listen_addr in static_state.ldk_peer_listen_addrs.clone() {
+        let peer_manager_connection_handler = peer_manager.clone();
+        let peer_policy_connection_handler = peer_policy.clone();
+        let inbound_connections = Arc::clone(&inbound_connections);
+        let stop_listen = Arc::clone(&stop_processing);
+        tokio::spawn(async move {
+            let listener = tokio::net::TcpListener::bind(listen_addr).await.expect(
+                "Failed to bind to listen address - is something else already listening on it?",
+            );
+            loop {
+                let peer_mgr = peer_manager_connection_handler.clone();
+                let (tcp_stream, remote_addr) = listener.accept().await.unwrap();
+                if stop_listen.load(Ordering::Acquire) {
+                    return;
+                }
+                // the node ID is only known during the handshake, so the max number of peers is
+                // checked here and the rest of the policy by the peer message handler
+                let num_peers = num_peers(&peer_mgr, &inbound_connections);
+                if !lock(&peer_policy_connection_handler, "peer_policy").accepts_inbound(num_peers)
+                {
+                    tracing::info!(
+                        "Dropping inbound connection from {remote_addr}: too many peers"
+                    );
+                    continue;
+                }
+                inbound_connections.fetch_add(1, Ordering::AcqRel);
+                tokio::spawn(run_inbound_connection(
+                    peer_mgr,
+                    Arc::clone(&inbound_connections),
                     tcp_stream.into_std().unwrap(),
-                )
-                .await;
-            });
-        }
-    });
+                ));
+            }
+        });
+    }
 
     // Connect and Disconnect Blocks
     let output_sweeper: Arc<OutputSweeper> = Arc::new(output_sweeper);
@@ -1761,27 +4689,57 @@ pub(crate) async fn start_ldk(
     let output_sweeper_listener = output_sweeper.clone();
     let bitcoind_block_source = bitcoind_client.clone();
     let stop_listen = Arc::clone(&stop_processing);
+    let event_sender = app_state.event_sender.clone();
+    let channel_manager_notifier = channel_manager.clone();
+    let output_sweeper_notifier = output_sweeper.clone();
     tokio::spawn(async move {
         let chain_poller = poll::ChainPoller::new(bitcoind_block_source.as_ref(), network);
-        let chain_listener = (
-            chain_monitor_listener,
-            &(channel_manager_listener, output_sweeper_listener),
+        let channel_listeners = (channel_manager_listener.clone(), output_sweeper_listener);
+        let chain_listener = BlockNotifier::new(
+            (chain_monitor_listener, &channel_listeners),
+            channel_manager_notifier,
+            output_sweeper_notifier,
+            event_sender.clone(),
         );
         let mut spv_client = SpvClient::new(chain_tip, chain_poller, &mut cache, &chain_listener);
+        let mut last_sync_progress = None;
         loop {
             if stop_listen.load(Ordering::Acquire) {
                 return;
             }
             spv_client.poll_best_tip().await.unwrap();
+            if let Ok((_, Some(best_height))) = bitcoind_block_source.get_best_block().await {
+                let height = channel_manager_listener.current_best_block().height;
+                if last_sync_progress != Some((height, best_height)) {
+                    last_sync_progress = Some((height, best_height));
+                    let _ = event_sender.send(NodeEvent::SyncProgress {
+                        height,
+                        best_height,
+                        synced: height >= best_height,
+                    });
+                }
+            }
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
     });
 
     let inbound_payments = Arc::new(Mutex::new(disk::read_inbound_payment_info(
+        &kv_store,
         &color_source.join(INBOUND_PAYMENTS_FNAME),
     )));
+    let (inbound_payment_updates, inbound_payment_updates_receiver) = mpsc::channel();
+    let persist_inbound_payments_state = Arc::clone(&inbound_payments);
+    let persist_inbound_payments_store = Arc::clone(&kv_store);
+    std::thread::spawn(move || {
+        persist_inbound_payments(
+            persist_inbound_payments_state,
+            persist_inbound_payments_store,
+            inbound_payment_updates_receiver,
+        )
+    });
     let outbound_payments = Arc::new(Mutex::new(disk::read_outbound_payment_info(
-        &color_source.join(OUTBOUND_PAYMENTS_FNAME),
+        &kv_store,
+        OUTBOUND_PAYMENTS_FNAME,
     )));
 
     let bump_tx_event_handler = Arc::new(BumpTransactionEventHandler::new(
@@ -1792,30 +4750,109 @@ pub(crate) async fn start_ldk(
     ));
 
     // Persist ChannelManager and NetworkGraph
-    let persister = Arc::new(FilesystemStore::new(color_source_path.clone()));
+    let persister = Arc::clone(&kv_store);
 
     // Read swaps info
     let maker_swaps = Arc::new(Mutex::new(disk::read_swaps_info(
-        &color_source.join(MAKER_SWAPS_FNAME),
+        &kv_store,
+        MAKER_SWAPS_FNAME,
     )));
     let taker_swaps = Arc::new(Mutex::new(disk::read_swaps_info(
-        &color_source.join(TAKER_SWAPS_FNAME),
+        &kv_store,
+        TAKER_SWAPS_FNAME,
     )));
 
     // Read channel IDs info
     let channel_ids_map = Arc::new(Mutex::new(disk::read_channel_ids_info(
-        &color_source.join(CHANNEL_IDS_FNAME),
+        &kv_store,
+        CHANNEL_IDS_FNAME,
+    )));
+
+    // Read LNURL-withdraws info
+    let lnurl_withdraws = Arc::new(Mutex::new(disk::read_lnurl_withdraws_info(
+        &kv_store,
+        LNURL_WITHDRAWS_FNAME,
+    )));
+
+    // Read recurring payments
+    let schedules = Arc::new(Mutex::new(disk::read_schedules(&kv_store, SCHEDULES_FNAME)));
+    let scheduled_backups = Arc::new(Mutex::new(disk::read_scheduled_backups(
+        &kv_store,
+        SCHEDULED_BACKUPS_FNAME,
+    )));
+
+    let fee_report = Arc::new(Mutex::new(disk::read_fee_report(
+        &kv_store,
+        FEE_REPORT_FNAME,
+    )));
+
+    let forwarding_history = Arc::new(Mutex::new(disk::read_forwarding_history(
+        &kv_store,
+        FORWARDING_HISTORY_FNAME,
+    )));
+
+    let spend_history = Arc::new(Mutex::new(disk::read_spend_history(
+        &kv_store,
+        SPEND_HISTORY_FNAME,
+    )));
+
+    let fee_orders = Arc::new(Mutex::new(disk::read_fee_orders(
+        &kv_store,
+        FEE_ORDERS_FNAME,
     )));
 
+    let swap_offers = Arc::new(Mutex::new(SwapOfferBook::new(disk::read_swap_offers(
+        &kv_store,
+        SWAP_OFFERS_FNAME,
+    ))));
+
+    let submarine_swaps = Arc::new(Mutex::new(disk::read_submarine_swaps(
+        &kv_store,
+        SUBMARINE_SWAPS_FNAME,
+    )));
+
+    let asset_htlc_limits = Arc::new(Mutex::new(disk::read_asset_htlc_limits(
+        &kv_store,
+        ASSET_HTLC_LIMITS_FNAME,
+    )));
+
+    let close_addresses = Arc::new(Mutex::new(disk::read_close_addresses(
+        &kv_store,
+        CLOSE_ADDRESSES_FNAME,
+    )));
+
+    let channel_closures = Arc::new(Mutex::new(disk::read_channel_closures(
+        &kv_store,
+        CHANNEL_CLOSURES_FNAME,
+    )));
+    let channel_conversions = Arc::new(Mutex::new(disk::read_channel_conversions(
+        &kv_store,
+        CHANNEL_CONVERSIONS_FNAME,
+    )));
+
+    // Check the changes journaled before the node was last stopped against the store
+    let journal = StateJournal::open(
+        &color_source_path,
+        &stored_statuses(
+            &inbound_payments.lock().unwrap(),
+            &outbound_payments.lock().unwrap(),
+            &maker_swaps.lock().unwrap(),
+            &taker_swaps.lock().unwrap(),
+            &submarine_swaps.lock().unwrap(),
+        ),
+    )
+    .map_err(|e| APIError::FailedStartingLDK(e.to_string()))?;
+
     let unlocked_state = Arc::new(UnlockedAppState {
         channel_manager: Arc::clone(&channel_manager),
         inbound_payments,
+        inbound_payment_updates,
         keys_manager,
         network_graph,
         onion_messenger,
         outbound_payments,
         peer_manager: Arc::clone(&peer_manager),
-        fs_store: Arc::clone(&fs_store),
+        kv_store: Arc::clone(&kv_store),
         bump_tx_event_handler,
         rgb_wallet_wrapper,
         maker_swaps,
@@ -1824,6 +4861,40 @@ pub(crate) async fn start_ldk(
         output_sweeper: Arc::clone(&output_sweeper),
         rgb_send_lock: Arc::new(Mutex::new(false)),
         channel_ids_map,
+        funding_changes: Arc::new(Mutex::new(HashMap::new())),
+        funding_utxos: Arc::new(Mutex::new(HashMap::new())),
+        funding_proxies: Arc::new(Mutex::new(HashMap::new())),
+        funding_fee_rates: Arc::new(Mutex::new(HashMap::new())),
+        funding_batches: Arc::new(Mutex::new(vec![])),
+        external_funding: Arc::new(Mutex::new(HashSet::new())),
+        pending_fundings: Arc::new(Mutex::new(HashMap::new())),
+        held_intercepts: Arc::new(Mutex::new(HashMap::new())),
+        lnurl_withdraws,
+        chain_monitor: Arc::clone(&chain_monitor),
+        node_id_rotation: Arc::new(Mutex::new(node_id_rotation)),
+        peer_policy: Arc::clone(&peer_policy),
+        proxy_pins,
+        consignment_proxies,
+        channel_transfers,
+        channel_requests,
+        peer_message_handler,
+        schedules,
+        scheduled_backups,
+        fee_report,
+        forwarding_history,
+        spend_history,
+        held_spends: Arc::new(Mutex::new(HashMap::new())),
+        fee_orders,
+        swap_offers,
+        submarine_swaps,
+        asset_htlc_limits,
+        close_addresses,
+        channel_closures,
+        channel_conversions,
+        bump_fee_rates: Arc::new(Mutex::new(HashMap::new())),
+        snapshot_tracker: SnapshotTracker::default(),
+        journal,
+        relay_only,
     });
 
     let recent_payments_payment_ids = channel_manager
@@ -1838,6 +4909,76 @@ pub(crate) async fn start_ldk(
         .collect::<Vec<PaymentId>>();
     unlocked_state.fail_outbound_pending_payments(recent_payments_payment_ids);
 
+    // Regularly mark the inbound payments of expired invoices and the expired swaps as expired
+    let expire_state = Arc::clone(&unlocked_state);
+    let stop_expire = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_expire.load(Ordering::Acquire) {
+                return;
+            }
+            expire_state.expire_inbound_payments();
+            expire_state.expire_swaps();
+        }
+    });
+
+    // Keep the failover lease, which a relaying node never holds
+    if static_state.lease.is_some() && !relay_only {
+        tokio::spawn(run_lease_renewal(
+            Arc::clone(&app_state),
+            Arc::clone(&stop_processing),
+        ));
+    }
+
+    // Reopen the channels closed to be converted to announced ones
+    tokio::spawn(run_channel_conversions(
+        Arc::clone(&app_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    // Run the recurring payments, a relaying node cannot send payments
+    if !relay_only {
+        tokio::spawn(run_scheduler(
+            Arc::clone(&app_state),
+            Arc::clone(&stop_processing),
+        ));
+    }
+
+    // Back up the node data in the background, if configured, the backups being encrypted with a
+    // key that a relaying node doesn't have
+    if let (Some(_), Some(ldk_xprv)) = (&static_state.backup_schedule, &ldk_xprv) {
+        tokio::spawn(run_scheduled_backups(
+            Arc::clone(&app_state),
+            derive_backup_key(ldk_xprv),
+            Arc::clone(&stop_processing),
+        ));
+    }
+
+    // Refresh the RGB transfers in the background, if configured
+    if !relay_only {
+        tokio::spawn(run_rgb_refresh(
+            Arc::clone(&app_state),
+            Arc::clone(&stop_processing),
+        ));
+    }
+
+    // Exchange swap offers with peers, a relaying node cannot swap
+    if !relay_only {
+        tokio::spawn(run_swap_offers(
+            Arc::clone(&app_state),
+            swap_offer_receiver,
+            Arc::clone(&stop_processing),
+        ));
+        tokio::spawn(run_submarine_swaps(
+            Arc::clone(&app_state),
+            submarine_swap_receiver,
+            Arc::clone(&stop_processing),
+        ));
+    }
+
     // Handle LDK Events
     let unlocked_state_copy = Arc::clone(&unlocked_state);
     let static_state_copy = Arc::clone(static_state);
@@ -1883,28 +5024,40 @@ pub(crate) async fn start_ldk(
     let connect_cm = Arc::clone(&channel_manager);
     let connect_pm = Arc::clone(&peer_manager);
     let peer_data_path = color_source.join(CHANNEL_PEER_DATA);
+    let connect_kv_store = Arc::clone(&kv_store);
+    let connect_peer_policy = Arc::clone(&peer_policy);
     let stop_connect = Arc::clone(&stop_processing);
+    let tor_proxy = static_state.tor_proxy;
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(1));
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         loop {
             interval.tick().await;
-            match disk::read_channel_peer_data(&peer_data_path) {
+            match disk::read_channel_peer_data(&peer_data_path, connect_kv_store.cipher()) {
                 Ok(info) => {
                     for node_id in connect_cm
                         .list_channels()
                         .iter()
                         .map(|chan| chan.counterparty.node_id)
                         .filter(|id| connect_pm.peer_by_node_id(id).is_none())
+                        .filter(|id| {
+                            lock(&connect_peer_policy, "peer_policy")
+                                .refusal(id)
+                                .is_none()
+                        })
                     {
                         if stop_connect.load(Ordering::Acquire) {
                             return;
                         }
                         for (pubkey, peer_addr) in info.iter() {
                             if *pubkey == node_id {
-                                let _ =
-                                    do_connect_peer(*pubkey, *peer_addr, Arc::clone(&connect_pm))
-                                        .await;
+                                let _ = do_connect_peer(
+                                    *pubkey,
+                                    peer_addr.clone(),
+                                    tor_proxy,
+                                    Arc::clone(&connect_pm),
+                                )
+                                .await;
                             }
                         }
                     }
@@ -1992,24 +5145,46 @@ pub(crate) async fn stop_ldk(app_state: Arc<AppState>) {
         join_handle.await.unwrap().unwrap();
     }
 
-    // connect to the peer port so it can be released
-    let peer_port = &app_state.static_state.ldk_peer_listening_port;
-    let sock_addr = SocketAddr::new(
-        std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)),
-        *peer_port,
-    );
-    let _ = std::net::TcpStream::connect(sock_addr);
-    // check the peer port has been released
-    let t_0 = OffsetDateTime::now_utc();
-    loop {
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-        if TcpListener::bind(sock_addr).is_ok() {
-            break;
+    let unlocked_state = app_state.get_unlocked_app_state().await.clone();
+    if let Some(unlocked_state) = unlocked_state {
+        unlocked_state.flush_inbound_payments().await;
+        if let Some(cipher) = unlocked_state.kv_store.cipher() {
+            if let Err(e) =
+                disk::encrypt_rgb_data_files(&app_state.static_state.ldk_data_dir, cipher)
+            {
+                tracing::error!("Failed to encrypt the RGB data files: {e}");
+            }
+        }
+    }
+
+    // let a standby instance take over right away
+    if let Some(lease) = &app_state.static_state.lease {
+        if let Err(e) = lease.release() {
+            tracing::error!("Failed to release the lease: {e}");
+        }
+    }
+
+    // connect to the peer listen addresses so they can be released
+    for listen_addr in &app_state.static_state.ldk_peer_listen_addrs {
+        let mut sock_addr = *listen_addr;
+        if sock_addr.ip().is_unspecified() {
+            sock_addr.set_ip(std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)));
         }
-        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
-            panic!("LDK peer port not being released")
+        let _ = std::net::TcpStream::connect(sock_addr);
+        // check the peer listen address has been released
+        let t_0 = OffsetDateTime::now_utc();
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            if TcpListener::bind(sock_addr).is_ok() {
+                break;
+            }
+            if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+                panic!("LDK peer listen address {listen_addr} not being released")
+            }
         }
     }
 
+    log_lock_stats();
+
     tracing::info!("Stopped LDK");
 }
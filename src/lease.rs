@@ -0,0 +1,179 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs::{read_to_string, remove_file, rename, write, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::routes::do_unlock;
+use crate::utils::{get_current_timestamp, hex_str, AppState};
+
+/// Name of the lease file, in the (shared) storage directory
+pub(crate) const LEASE_FNAME: &str = "leader.lease";
+
+/// Time after which a lease that hasn't been renewed can be taken over by another instance
+pub(crate) const LEASE_TTL_SECS: u64 = 30;
+
+/// Interval between lease renewals by the active instance and acquisition attempts by the standby
+pub(crate) const LEASE_RENEW_SECS: u64 = 10;
+
+#[derive(Deserialize, Serialize)]
+struct LeaseData {
+    holder_id: String,
+    expires_at: u64,
+}
+
+/// Time-limited leadership over the node data, which only one instance at a time can hold.
+///
+/// The lease is a file on the storage shared by the instances: it is created atomically and an
+/// expired lease is moved away before being replaced, so that only one contender can take it
+/// over. Clocks of the instances are assumed to be in sync.
+pub(crate) struct Lease {
+    holder_id: String,
+    path: PathBuf,
+}
+
+impl Lease {
+    pub(crate) fn new(storage_dir: &Path) -> Self {
+        let mut random_bytes = [0; 16];
+        rand::thread_rng().fill_bytes(&mut random_bytes);
+        Self {
+            holder_id: hex_str(&random_bytes),
+            path: storage_dir.join(LEASE_FNAME),
+        }
+    }
+
+    fn current(&self) -> Result<Option<LeaseData>, APIError> {
+        match read_to_string(&self.path) {
+            Ok(content) => Ok(serde_json::from_str(&content).ok()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn data(&self) -> String {
+        serde_json::to_string(&LeaseData {
+            holder_id: self.holder_id.clone(),
+            expires_at: get_current_timestamp() + LEASE_TTL_SECS,
+        })
+        .expect("valid lease")
+    }
+
+    /// Take the lease if it's free or expired, returning whether this instance holds it
+    pub(crate) fn try_acquire(&self) -> Result<bool, APIError> {
+        match self.current()? {
+            Some(lease) if lease.holder_id == self.holder_id => return self.renew(),
+            Some(lease) if lease.expires_at > get_current_timestamp() => return Ok(false),
+            _ if self.path.exists() => {
+                // only one contender can move the expired (or unreadable) lease away
+                let stale = self
+                    .path
+                    .with_extension(format!("stale.{}", self.holder_id));
+                if rename(&self.path, &stale).is_err() {
+                    return Ok(false);
+                }
+                remove_file(stale)?;
+            }
+            _ => {}
+        }
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&self.path)
+        {
+            Ok(mut file) => {
+                file.write_all(self.data().as_bytes())?;
+                file.sync_all()?;
+                tracing::info!("Acquired the lease as {}", self.holder_id);
+                Ok(true)
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Extend the lease, returning false if it has been taken over by another instance
+    pub(crate) fn renew(&self) -> Result<bool, APIError> {
+        match self.current()? {
+            Some(lease) if lease.holder_id == self.holder_id => {
+                let tmp = self.path.with_extension(format!("tmp.{}", self.holder_id));
+                write(&tmp, self.data())?;
+                rename(tmp, &self.path)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Give up the lease, if held, so that a standby can take over right away
+    pub(crate) fn release(&self) -> Result<(), APIError> {
+        if let Some(lease) = self.current()? {
+            if lease.holder_id == self.holder_id {
+                remove_file(&self.path)?;
+                tracing::info!("Released the lease");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keep the lease while the node is running.
+///
+/// If the lease is lost the process exits right away, without persisting anything, as the state
+/// on the shared storage now belongs to the instance that took over.
+pub(crate) async fn run_lease_renewal(app_state: Arc<AppState>, stop_processing: Arc<AtomicBool>) {
+    let lease = app_state
+        .static_state
+        .lease
+        .as_ref()
+        .expect("failover enabled");
+    let mut interval = tokio::time::interval(Duration::from_secs(LEASE_RENEW_SECS));
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        match lease.renew() {
+            Ok(true) => {}
+            // released while stopping
+            Ok(false) if stop_processing.load(Ordering::Acquire) => return,
+            Ok(false) => {
+                tracing::error!("Lost the lease to another instance, exiting");
+                std::process::exit(1);
+            }
+            Err(e) => {
+                tracing::error!("Failed to renew the lease, exiting: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Wait for the lease to become free, then unlock the node with the given password.
+///
+/// While waiting the node doesn't run LDK, so it neither connects to peers nor broadcasts.
+pub(crate) async fn run_standby(app_state: Arc<AppState>, password: String) {
+    tracing::info!("Standing by until the lease is free");
+    let mut interval = tokio::time::interval(Duration::from_secs(LEASE_RENEW_SECS));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = app_state.cancel_token.cancelled() => break,
+        }
+        match do_unlock(app_state.clone(), &password).await {
+            Ok(()) => {
+                tracing::info!("Took over as the active instance");
+                break;
+            }
+            Err(APIError::StandbyNode) | Err(APIError::ChangingState) => {}
+            Err(e) => {
+                tracing::error!("Failed to take over as the active instance: {e}");
+                break;
+            }
+        }
+    }
+    *app_state.get_standby() = false;
+}
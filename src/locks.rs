@@ -0,0 +1,184 @@
+use std::sync::Mutex;
+#[cfg(not(feature = "lock-audit"))]
+use std::sync::MutexGuard;
+
+#[cfg(feature = "lock-audit")]
+pub(crate) use audit::{log_lock_stats, AuditedGuard};
+
+/// Guard returned by [`lock`], a plain [`MutexGuard`] unless the `lock-audit` feature is enabled.
+#[cfg(not(feature = "lock-audit"))]
+pub(crate) type AuditedGuard<'a, T> = MutexGuard<'a, T>;
+
+/// Acquire the given std mutex, tracking wait and hold times and the order locks are acquired in
+/// under the provided name when the `lock-audit` feature is enabled.
+#[cfg(not(feature = "lock-audit"))]
+pub(crate) fn lock<'a, T>(mutex: &'a Mutex<T>, _name: &'static str) -> AuditedGuard<'a, T> {
+    mutex.lock().unwrap()
+}
+
+#[cfg(not(feature = "lock-audit"))]
+pub(crate) fn log_lock_stats() {}
+
+#[cfg(feature = "lock-audit")]
+pub(crate) fn lock<'a, T>(mutex: &'a Mutex<T>, name: &'static str) -> AuditedGuard<'a, T> {
+    audit::lock(mutex, name)
+}
+
+#[cfg(feature = "lock-audit")]
+mod audit {
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::ops::{Deref, DerefMut};
+    use std::sync::{Mutex, MutexGuard};
+    use std::time::{Duration, Instant};
+
+    // waiting on a std mutex for longer than this blocks the async runtime noticeably
+    const WAIT_WARN_THRESHOLD: Duration = Duration::from_millis(50);
+    // holding a std mutex for longer than this is likely a sign of I/O done under the lock
+    const HOLD_WARN_THRESHOLD: Duration = Duration::from_millis(200);
+
+    #[derive(Default)]
+    struct LockStats {
+        acquisitions: u64,
+        slow_waits: u64,
+        async_blocking_waits: u64,
+        slow_holds: u64,
+        order_inversions: u64,
+        max_wait: Duration,
+        max_hold: Duration,
+    }
+
+    static LOCK_STATS: Mutex<BTreeMap<&'static str, LockStats>> = Mutex::new(BTreeMap::new());
+
+    // pairs of locks seen acquired in this order, the second one while holding the first one
+    static LOCK_ORDERS: Mutex<BTreeSet<(&'static str, &'static str)>> = Mutex::new(BTreeSet::new());
+
+    thread_local! {
+        // locks held by the current thread, in acquisition order
+        static HELD_LOCKS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Record the order the lock is acquired in relative to the ones held by the current thread,
+    /// warning when the opposite order has been seen as two threads could then deadlock
+    fn track_lock_order(name: &'static str) {
+        let held = HELD_LOCKS.with(|h| h.borrow().clone());
+        let mut inversions = 0;
+        {
+            let mut orders = LOCK_ORDERS.lock().unwrap();
+            for held_name in held.into_iter().filter(|h| *h != name) {
+                if orders.contains(&(name, held_name)) {
+                    tracing::warn!(
+                        target: "lock_audit",
+                        "lock {name} acquired while holding {held_name}, which has also been \
+                        acquired while holding {name}: potential deadlock"
+                    );
+                    inversions += 1;
+                }
+                orders.insert((held_name, name));
+            }
+        }
+        if inversions > 0 {
+            update_stats(name, |s| s.order_inversions += inversions);
+        }
+    }
+
+    fn update_stats(name: &'static str, f: impl FnOnce(&mut LockStats)) {
+        let mut stats = LOCK_STATS.lock().unwrap();
+        f(stats.entry(name).or_default());
+    }
+
+    pub(crate) struct AuditedGuard<'a, T> {
+        guard: MutexGuard<'a, T>,
+        name: &'static str,
+        acquired_at: Instant,
+    }
+
+    impl<T> Deref for AuditedGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            &self.guard
+        }
+    }
+
+    impl<T> DerefMut for AuditedGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            &mut self.guard
+        }
+    }
+
+    impl<T> Drop for AuditedGuard<'_, T> {
+        fn drop(&mut self) {
+            HELD_LOCKS.with(|h| {
+                let mut held_locks = h.borrow_mut();
+                if let Some(pos) = held_locks.iter().rposition(|n| *n == self.name) {
+                    held_locks.remove(pos);
+                }
+            });
+            let held = self.acquired_at.elapsed();
+            if held > HOLD_WARN_THRESHOLD {
+                tracing::warn!(target: "lock_audit", "lock {} held for {held:?}", self.name);
+            }
+            update_stats(self.name, |s| {
+                if held > HOLD_WARN_THRESHOLD {
+                    s.slow_holds += 1;
+                }
+                s.max_hold = s.max_hold.max(held);
+            });
+        }
+    }
+
+    pub(crate) fn lock<'a, T>(mutex: &'a Mutex<T>, name: &'static str) -> AuditedGuard<'a, T> {
+        // checked before waiting, so that a deadlock still gets reported
+        track_lock_order(name);
+        let wait_start = Instant::now();
+        let guard = mutex.lock().unwrap();
+        let acquired_at = Instant::now();
+        let waited = acquired_at - wait_start;
+
+        // a slow wait from a thread driving a tokio runtime means a worker has been blocked
+        let in_async_context = tokio::runtime::Handle::try_current().is_ok();
+        if waited > WAIT_WARN_THRESHOLD {
+            if in_async_context {
+                tracing::warn!(target: "lock_audit", "blocked async runtime for {waited:?} waiting lock {name}");
+            } else {
+                tracing::warn!(target: "lock_audit", "waited {waited:?} for lock {name}");
+            }
+        }
+        update_stats(name, |s| {
+            s.acquisitions += 1;
+            if waited > WAIT_WARN_THRESHOLD {
+                s.slow_waits += 1;
+                if in_async_context {
+                    s.async_blocking_waits += 1;
+                }
+            }
+            s.max_wait = s.max_wait.max(waited);
+        });
+
+        HELD_LOCKS.with(|h| h.borrow_mut().push(name));
+        AuditedGuard {
+            guard,
+            name,
+            acquired_at,
+        }
+    }
+
+    /// Log the statistics collected so far for each audited lock.
+    pub(crate) fn log_lock_stats() {
+        let stats = LOCK_STATS.lock().unwrap();
+        for (name, s) in stats.iter() {
+            tracing::info!(
+                target: "lock_audit",
+                "lock {name}: acquisitions={} slow_waits={} async_blocking_waits={} slow_holds={} order_inversions={} max_wait={:?} max_hold={:?}",
+                s.acquisitions,
+                s.slow_waits,
+                s.async_blocking_waits,
+                s.slow_holds,
+                s.order_inversions,
+                s.max_wait,
+                s.max_hold,
+            );
+        }
+    }
+}
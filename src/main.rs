@@ -1,13 +1,48 @@
+mod alerts;
 mod args;
+mod auth;
 mod backup;
 mod bitcoind;
+mod channel_conversion;
+mod channel_request;
+mod consignment;
+#[cfg(feature = "debug-api")]
+mod debug;
 mod disk;
+mod encryption;
 mod error;
+mod events;
+mod fee_order;
+mod fee_report;
+mod forwarding_history;
+mod journal;
+mod kv_store;
 mod ldk;
+mod lease;
+mod locks;
+mod peer_messages;
+mod peer_policy;
+mod proof;
+mod proxy;
+mod refresh;
 mod rgb;
+mod rotation;
 mod routes;
+mod scb;
+mod schedule;
+mod scheduled_backup;
+mod snapshot;
+mod spend_limits;
+mod storage_status;
+mod submarine_swap;
 mod swap;
+mod swap_offer;
+mod swap_quote;
+mod tor;
 mod utils;
+mod vss;
+#[cfg(feature = "web-ui")]
+mod web_ui;
 
 #[cfg(test)]
 mod test;
@@ -15,6 +50,7 @@ mod test;
 use anyhow::Result;
 use axum::{
     extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -23,25 +59,43 @@ use tokio::signal;
 use tower_http::cors::CorsLayer;
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::trace::{self, TraceLayer};
-use tracing_subscriber::{filter, prelude::*};
+use tracing_subscriber::{filter, prelude::*, reload, EnvFilter};
 
+use crate::alerts::{forward_alerts, AlertLayer};
 use crate::args::LdkUserInfo;
+use crate::auth::authorize;
 use crate::error::AppError;
+use crate::events::event_stream;
 use crate::ldk::stop_ldk;
 use crate::routes::{
-    address, asset_balance, backup, btc_balance, change_password, close_channel, connect_peer,
-    create_utxos, decode_ln_invoice, decode_rgb_invoice, disconnect_peer, get_asset_media,
-    get_channel_id, init, invoice_status, issue_asset_cfa, issue_asset_nia, issue_asset_uda,
-    keysend, list_assets, list_channels, list_payments, list_peers, list_swaps, list_transactions,
-    list_transfers, list_unspents, ln_invoice, lock, maker_execute, maker_init, network_info,
-    node_info, open_channel, post_asset_media, refresh_transfers, restore, rgb_invoice, send_asset,
-    send_btc, send_onion_message, send_payment, shutdown, sign_message, taker, unlock,
+    abandon_funding, abandon_payment, accept_swap_offer, address, announce_channel,
+    approve_channel_request, asset_balance, asset_loop_in, asset_loop_out, backup, backup_scb,
+    backup_seed, ban_peer, btc_balance, bump_close_tx, burn_asset, cancel_fee_order,
+    cancel_invoice, change_password, close_channel, confirm_spend, connect_peer, create_api_token,
+    create_fee_order, create_schedule, create_utxos, decode_ln_invoice, decode_rgb_invoice,
+    delete_schedule, disconnect_peer, execute_fee_order, export_contract, fail_intercept,
+    fee_report, forwarding_history, get_asset_media, get_channel_id, get_peer_policy, get_swap,
+    import_contract, init, inspect_consignment, invoice_status, issue_asset_cfa, issue_asset_nia,
+    issue_asset_uda, keysend, list_api_tokens, list_assets, list_backups, list_channel_conversions,
+    list_channel_requests, list_channels, list_closed_channels, list_fee_orders,
+    list_funding_psbts, list_payments, list_peers, list_proxy_pins, list_schedules,
+    list_submarine_swaps, list_swap_offers, list_swaps, list_transactions, list_transfers,
+    list_unspents, ln_invoice, lnurl_pay, lnurl_pay_callback, lnurl_withdraw,
+    lnurl_withdraw_callback, lnurl_withdraw_info, lock, loop_in, loop_out, maker_execute,
+    maker_init, max_sendable_asset, network_graph_channel, network_graph_export,
+    network_graph_node, network_info, node_info, open_channel, open_channels, pending_intercepts,
+    pending_sweeps, pin_proxy, post_asset_media, post_swap_offer, rebalance, refresh_transfers,
+    reject_channel_request, request_channel, restore, restore_scb, revoke_api_token, rgb_invoice,
+    rotate_node_id, send_asset, send_btc, send_onion_message, send_payment, send_to_ln_address,
+    set_asset_htlc_limit, set_channel_announcement, set_log_level, set_peer_policy, settle_invoice,
+    shutdown, sign_message, simulate_payment, storage_status, submit_funding_psbt, swap_quote,
+    taker, transfer_proof, unban_peer, unlock, unpin_proxy, update_channel_policy, update_schedule,
 };
 use crate::utils::{start_daemon, AppState, LOGS_DIR};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = args::parse_startup_args()?;
+    let mut args = args::parse_startup_args()?;
 
     // stdout logger
     let stdout_log = tracing_subscriber::fmt::layer();
@@ -57,15 +111,23 @@ async fn main() -> Result<()> {
         .with_thread_ids(true)
         .with_thread_names(true)
         .with_writer(non_blocking);
+    // the file log filter can be changed at runtime via /setloglevel
+    let (file_log_filter, log_filter) = reload::Layer::new(EnvFilter::new("debug"));
+    args.log_filter = Some(log_filter);
+
+    // alert rules logger
+    let (alert_layer, alert_receiver) = AlertLayer::new(args.alert_rules.clone());
 
     tracing_subscriber::registry()
+        .with(file_log.with_filter(file_log_filter))
         .with(stdout_log.with_filter(filter::LevelFilter::INFO))
-        .with(file_log.with_filter(filter::LevelFilter::DEBUG))
+        .with(alert_layer.with_filter(filter::LevelFilter::DEBUG))
         .init();
 
     let addr = SocketAddr::from(([0, 0, 0, 0], args.daemon_listening_port));
 
     let (router, app_state) = app(args).await?;
+    tokio::spawn(forward_alerts(alert_receiver, app_state.clone()));
 
     tracing::info!("Listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
@@ -89,51 +151,139 @@ pub(crate) async fn app(args: LdkUserInfo) -> Result<(Router, Arc<AppState>), Ap
         )
         // all routes before this will have the default body limit disabled
         .layer(DefaultBodyLimit::disable())
+        .route("/.well-known/lnurlp/:username", get(lnurl_pay))
+        .route("/abandonfunding", post(abandon_funding))
+        .route("/abandonpayment", post(abandon_payment))
+        .route("/acceptswapoffer", post(accept_swap_offer))
         .route("/address", post(address))
+        .route("/announcechannel", post(announce_channel))
+        .route("/approvechannelrequest", post(approve_channel_request))
         .route("/assetbalance", post(asset_balance))
+        .route("/assetloopin", post(asset_loop_in))
+        .route("/assetloopout", post(asset_loop_out))
         .route("/backup", post(backup))
+        .route("/backup/scb", post(backup_scb))
+        .route("/backups", get(list_backups))
+        .route("/backupseed", post(backup_seed))
         .route("/btcbalance", get(btc_balance))
+        .route("/bumpclosetx", post(bump_close_tx))
+        .route("/burnasset", post(burn_asset))
+        .route("/cancelfeeorder", post(cancel_fee_order))
+        .route("/cancelinvoice", post(cancel_invoice))
         .route("/changepassword", post(change_password))
+        .route("/channelrequests", get(list_channel_requests))
         .route("/closechannel", post(close_channel))
+        .route("/confirmspend", post(confirm_spend))
         .route("/connectpeer", post(connect_peer))
+        .route("/createapitoken", post(create_api_token))
+        .route("/createfeeorder", post(create_fee_order))
+        .route("/createschedule", post(create_schedule))
         .route("/createutxos", post(create_utxos))
         .route("/decodelninvoice", post(decode_ln_invoice))
         .route("/decodergbinvoice", post(decode_rgb_invoice))
+        .route("/deleteschedule", post(delete_schedule))
         .route("/disconnectpeer", post(disconnect_peer))
+        .route("/events", get(event_stream))
+        .route("/executefeeorder", post(execute_fee_order))
+        .route("/exportcontract", post(export_contract))
+        .route("/failintercept", post(fail_intercept))
+        .route("/feeorders", get(list_fee_orders))
+        .route("/feereport", get(fee_report))
+        .route("/forwardinghistory", get(forwarding_history))
         .route("/getassetmedia", post(get_asset_media))
         .route("/getchannelid", post(get_channel_id))
+        .route("/getswap", post(get_swap))
+        .route("/importcontract", post(import_contract))
         .route("/init", post(init))
+        .route("/inspectconsignment", post(inspect_consignment))
         .route("/invoicestatus", post(invoice_status))
         .route("/issueassetcfa", post(issue_asset_cfa))
         .route("/issueassetnia", post(issue_asset_nia))
         .route("/issueassetuda", post(issue_asset_uda))
         .route("/keysend", post(keysend))
+        .route("/listapitokens", get(list_api_tokens))
         .route("/listassets", post(list_assets))
+        .route("/listchannelconversions", get(list_channel_conversions))
         .route("/listchannels", get(list_channels))
+        .route("/listclosedchannels", get(list_closed_channels))
+        .route("/listfundingpsbts", get(list_funding_psbts))
         .route("/listpayments", get(list_payments))
         .route("/listpeers", get(list_peers))
+        .route("/listproxypins", get(list_proxy_pins))
+        .route("/listsubmarineswaps", get(list_submarine_swaps))
+        .route("/listswapoffers", get(list_swap_offers))
         .route("/listswaps", get(list_swaps))
         .route("/listtransactions", get(list_transactions))
         .route("/listtransfers", post(list_transfers))
         .route("/listunspents", get(list_unspents))
         .route("/lninvoice", post(ln_invoice))
+        .route("/lnurlp/:username/callback", get(lnurl_pay_callback))
+        .route("/lnurlw/:k1", get(lnurl_withdraw_info))
+        .route("/lnurlw/:k1/callback", get(lnurl_withdraw_callback))
+        .route("/lnurlwithdraw", post(lnurl_withdraw))
         .route("/lock", post(lock))
+        .route("/loopin", post(loop_in))
+        .route("/loopout", post(loop_out))
         .route("/makerexecute", post(maker_execute))
         .route("/makerinit", post(maker_init))
+        .route("/maxsendableasset", post(max_sendable_asset))
+        .route("/networkgraph/channel", post(network_graph_channel))
+        .route("/networkgraph/export", post(network_graph_export))
+        .route("/networkgraph/node", post(network_graph_node))
         .route("/networkinfo", get(network_info))
         .route("/nodeinfo", get(node_info))
         .route("/openchannel", post(open_channel))
+        .route("/openchannels", post(open_channels))
+        .route("/peers/policy", get(get_peer_policy))
+        .route("/peers/policy/ban", post(ban_peer))
+        .route("/peers/policy/unban", post(unban_peer))
+        .route("/peers/policy/update", post(set_peer_policy))
+        .route("/pendingintercepts", get(pending_intercepts))
+        .route("/pendingsweeps", get(pending_sweeps))
+        .route("/pinproxy", post(pin_proxy))
+        .route("/postswapoffer", post(post_swap_offer))
+        .route("/rebalance", post(rebalance))
         .route("/refreshtransfers", post(refresh_transfers))
+        .route("/rejectchannelrequest", post(reject_channel_request))
+        .route("/requestchannel", post(request_channel))
         .route("/restore", post(restore))
+        .route("/restore/scb", post(restore_scb))
+        .route("/revokeapitoken", post(revoke_api_token))
         .route("/rgbinvoice", post(rgb_invoice))
+        .route("/rotatenodeid", post(rotate_node_id))
+        .route("/schedules", get(list_schedules))
         .route("/sendasset", post(send_asset))
         .route("/sendbtc", post(send_btc))
         .route("/sendonionmessage", post(send_onion_message))
         .route("/sendpayment", post(send_payment))
+        .route("/sendtolnaddress", post(send_to_ln_address))
+        .route("/setassethtlclimit", post(set_asset_htlc_limit))
+        .route("/setchannelannouncement", post(set_channel_announcement))
+        .route("/setloglevel", post(set_log_level))
+        .route("/settleinvoice", post(settle_invoice))
         .route("/shutdown", post(shutdown))
         .route("/signmessage", post(sign_message))
+        .route("/simulate/payment", post(simulate_payment))
+        .route("/storagestatus", get(storage_status))
+        .route("/submitfundingpsbt", post(submit_funding_psbt))
+        .route("/swapquote", post(swap_quote))
         .route("/taker", post(taker))
+        .route("/transferproof", post(transfer_proof))
         .route("/unlock", post(unlock))
+        .route("/unpinproxy", post(unpin_proxy))
+        .route("/updatechannelpolicy", post(update_channel_policy))
+        .route("/updateschedule", post(update_schedule));
+    #[cfg(feature = "debug-api")]
+    let router = router
+        .route("/debug/channel/:channel_id", get(debug::channel_state))
+        .route("/debug/decodergbinfo", post(debug::decode_rgb_info))
+        .route("/debug/encodergbinfo", post(debug::encode_rgb_info));
+    #[cfg(feature = "web-ui")]
+    let router = router
+        .route("/ui", get(web_ui::index))
+        .route("/ui/:file", get(web_ui::asset));
+    let router = router
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), authorize))
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(trace::DefaultMakeSpan::new().level(tracing::Level::INFO))
@@ -0,0 +1,366 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::io::Read;
+use lightning::ln::features::{InitFeatures, NodeFeatures};
+use lightning::ln::msgs::{DecodeError, Init, LightningError};
+use lightning::ln::peer_handler::CustomMessageHandler;
+use lightning::ln::wire::{CustomMessageReader, Type};
+use lightning::util::persist::KVStore;
+use lightning::util::ser::{Readable, Writeable, Writer};
+use rand::RngCore;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::channel_request::{
+    ChannelRequestData, ChannelRequestMap, ChannelRequestMessage, CHANNEL_REQUEST_MESSAGE_TYPE,
+    MAX_PENDING_CHANNEL_REQUESTS,
+};
+use crate::disk::CHANNEL_REQUESTS_FNAME;
+use crate::events::NodeEvent;
+use crate::kv_store::NodeStore;
+use crate::locks::lock;
+use crate::peer_policy::PeerPolicy;
+use crate::routes::ChannelRequestStatus;
+use crate::submarine_swap::{
+    SubmarineSwapAcceptedMessage, SubmarineSwapFundedMessage, SubmarineSwapRequestMessage,
+    SUBMARINE_SWAP_ACCEPTED_MESSAGE_TYPE, SUBMARINE_SWAP_FUNDED_MESSAGE_TYPE,
+    SUBMARINE_SWAP_REQUEST_MESSAGE_TYPE,
+};
+use crate::swap::{
+    supports_swap_protocol, SwapPair, SwapPairsMessage, MAX_SWAP_PAIRS, SWAP_PAIRS_MESSAGE_TYPE,
+};
+use crate::swap_offer::{
+    SwapOfferAcceptMessage, SwapOfferAcceptedMessage, SwapOfferMessage,
+    SWAP_OFFER_ACCEPTED_MESSAGE_TYPE, SWAP_OFFER_ACCEPT_MESSAGE_TYPE, SWAP_OFFER_MESSAGE_TYPE,
+};
+use crate::utils::{get_current_timestamp, hex_str};
+
+/// Custom feature bit advertising support for version 1 of the swap protocol
+pub(crate) const SWAP_PROTOCOL_FEATURE_BIT: usize = 263;
+
+/// Custom feature bit advertising support for channel requests
+pub(crate) const CHANNEL_REQUEST_FEATURE_BIT: usize = 265;
+
+/// Custom feature bit advertising support for swap offers
+pub(crate) const SWAP_OFFER_FEATURE_BIT: usize = 267;
+
+/// Custom feature bit advertising support for submarine swaps
+pub(crate) const SUBMARINE_SWAP_FEATURE_BIT: usize = 269;
+
+/// Custom feature bits advertised to peers.
+///
+/// The bits are odd (i.e. optional) so peers not knowing about them won't disconnect from us.
+const CUSTOM_FEATURE_BITS: [usize; 4] = [
+    SWAP_PROTOCOL_FEATURE_BIT,
+    CHANNEL_REQUEST_FEATURE_BIT,
+    SWAP_OFFER_FEATURE_BIT,
+    SUBMARINE_SWAP_FEATURE_BIT,
+];
+
+/// Custom messages exchanged with peers
+#[derive(Clone, Debug)]
+pub(crate) enum PeerMessage {
+    ChannelRequest(ChannelRequestMessage),
+    SwapOffer(SwapOfferMessage),
+    SwapOfferAccept(SwapOfferAcceptMessage),
+    SwapOfferAccepted(SwapOfferAcceptedMessage),
+    SubmarineSwapRequest(SubmarineSwapRequestMessage),
+    SubmarineSwapAccepted(SubmarineSwapAcceptedMessage),
+    SubmarineSwapFunded(SubmarineSwapFundedMessage),
+    SwapPairs(SwapPairsMessage),
+}
+
+impl Type for PeerMessage {
+    fn type_id(&self) -> u16 {
+        match self {
+            Self::ChannelRequest(msg) => msg.type_id(),
+            Self::SwapOffer(msg) => msg.type_id(),
+            Self::SwapOfferAccept(msg) => msg.type_id(),
+            Self::SwapOfferAccepted(msg) => msg.type_id(),
+            Self::SubmarineSwapRequest(msg) => msg.type_id(),
+            Self::SubmarineSwapAccepted(msg) => msg.type_id(),
+            Self::SubmarineSwapFunded(msg) => msg.type_id(),
+            Self::SwapPairs(msg) => msg.type_id(),
+        }
+    }
+}
+
+impl Writeable for PeerMessage {
+    fn write<W: Writer>(&self, writer: &mut W) -> Result<(), lightning::io::Error> {
+        match self {
+            Self::ChannelRequest(msg) => msg.write(writer),
+            Self::SwapOffer(msg) => msg.write(writer),
+            Self::SwapOfferAccept(msg) => msg.write(writer),
+            Self::SwapOfferAccepted(msg) => msg.write(writer),
+            Self::SubmarineSwapRequest(msg) => msg.write(writer),
+            Self::SubmarineSwapAccepted(msg) => msg.write(writer),
+            Self::SubmarineSwapFunded(msg) => msg.write(writer),
+            Self::SwapPairs(msg) => msg.write(writer),
+        }
+    }
+}
+
+/// Custom message handler for the protocols this node speaks with its peers.
+///
+/// Swap capability is signaled via a custom feature bit, with the supported pairs sent to peers as
+/// a custom message once connected, while channel requests, swap offers and submarine swaps are
+/// signaled via a feature bit and exchanged as custom messages. Swap offer and submarine swap
+/// messages are handed over to the tasks running them, which need the unlocked state. Peers not
+/// accepted by the peer policy are refused during the handshake.
+pub(crate) struct PeerMessageHandler {
+    channel_requests: Arc<Mutex<ChannelRequestMap>>,
+    peer_policy: Arc<Mutex<PeerPolicy>>,
+    kv_store: Arc<NodeStore>,
+    event_sender: broadcast::Sender<NodeEvent>,
+    swap_offer_sender: mpsc::UnboundedSender<(PublicKey, PeerMessage)>,
+    submarine_swap_sender: mpsc::UnboundedSender<(PublicKey, PeerMessage)>,
+    pending_messages: Mutex<Vec<(PublicKey, PeerMessage)>>,
+    /// Pairs we advertise to peers, refreshed by the swap offers task
+    swap_pairs: Mutex<Vec<SwapPair>>,
+    /// Pairs advertised by the connected peers
+    peer_swap_pairs: Mutex<HashMap<PublicKey, Vec<SwapPair>>>,
+}
+
+impl PeerMessageHandler {
+    pub(crate) fn new(
+        channel_requests: Arc<Mutex<ChannelRequestMap>>,
+        peer_policy: Arc<Mutex<PeerPolicy>>,
+        kv_store: Arc<NodeStore>,
+        event_sender: broadcast::Sender<NodeEvent>,
+        swap_offer_sender: mpsc::UnboundedSender<(PublicKey, PeerMessage)>,
+        submarine_swap_sender: mpsc::UnboundedSender<(PublicKey, PeerMessage)>,
+    ) -> Self {
+        Self {
+            channel_requests,
+            peer_policy,
+            kv_store,
+            event_sender,
+            swap_offer_sender,
+            submarine_swap_sender,
+            pending_messages: Mutex::new(vec![]),
+            swap_pairs: Mutex::new(vec![]),
+            peer_swap_pairs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Pairs we advertise to peers
+    pub(crate) fn swap_pairs(&self) -> Vec<SwapPair> {
+        self.swap_pairs.lock().unwrap().clone()
+    }
+
+    /// Update the pairs we advertise, returning whether they changed
+    pub(crate) fn set_swap_pairs(&self, pairs: Vec<SwapPair>) -> bool {
+        let mut swap_pairs = self.swap_pairs.lock().unwrap();
+        if *swap_pairs == pairs {
+            return false;
+        }
+        *swap_pairs = pairs;
+        true
+    }
+
+    /// Pairs advertised by the given peer, if connected and supporting the swap protocol
+    pub(crate) fn peer_swap_pairs(&self, peer_pubkey: &PublicKey) -> Option<Vec<SwapPair>> {
+        self.peer_swap_pairs
+            .lock()
+            .unwrap()
+            .get(peer_pubkey)
+            .cloned()
+    }
+
+    fn handle_swap_pairs(&self, msg: SwapPairsMessage, peer_pubkey: PublicKey) {
+        if msg.pairs.len() > MAX_SWAP_PAIRS {
+            tracing::warn!("Ignoring swap pairs from {peer_pubkey}: too many pairs");
+            return;
+        }
+        self.peer_swap_pairs
+            .lock()
+            .unwrap()
+            .insert(peer_pubkey, msg.pairs);
+    }
+
+    /// Queue a message for the given peer, sent on the next peer manager event processing
+    pub(crate) fn send_message(&self, peer_pubkey: PublicKey, msg: PeerMessage) {
+        self.pending_messages
+            .lock()
+            .unwrap()
+            .push((peer_pubkey, msg));
+    }
+
+    /// Queue a channel request for the given peer
+    pub(crate) fn send_channel_request(&self, peer_pubkey: PublicKey, msg: ChannelRequestMessage) {
+        self.send_message(peer_pubkey, PeerMessage::ChannelRequest(msg));
+    }
+
+    fn handle_channel_request(&self, msg: ChannelRequestMessage, peer_pubkey: PublicKey) {
+        if msg.asset_id.is_some() != msg.asset_amount.is_some() {
+            tracing::warn!("Ignoring channel request from {peer_pubkey} with incomplete RGB info");
+            return;
+        }
+
+        let mut channel_requests = lock(&self.channel_requests, "channel_requests");
+        // a new request from a peer replaces the one it has pending
+        channel_requests.requests.retain(|_, r| {
+            !(r.peer_pubkey == peer_pubkey && r.status == ChannelRequestStatus::Pending)
+        });
+        let pending = channel_requests
+            .requests
+            .values()
+            .filter(|r| r.status == ChannelRequestStatus::Pending)
+            .count();
+        if pending >= MAX_PENDING_CHANNEL_REQUESTS {
+            tracing::warn!(
+                "Ignoring channel request from {peer_pubkey}: too many pending requests"
+            );
+            return;
+        }
+
+        let mut random_bytes = [0; 16];
+        rand::thread_rng().fill_bytes(&mut random_bytes);
+        let request_id = hex_str(&random_bytes);
+        channel_requests.requests.insert(
+            request_id.clone(),
+            ChannelRequestData {
+                peer_pubkey,
+                request: msg,
+                status: ChannelRequestStatus::Pending,
+                received_at: get_current_timestamp(),
+                temporary_channel_id: None,
+            },
+        );
+        if let Err(e) =
+            self.kv_store
+                .write("", "", CHANNEL_REQUESTS_FNAME, &channel_requests.encode())
+        {
+            tracing::error!("Failed to persist channel request from {peer_pubkey}: {e}");
+            channel_requests.requests.remove(&request_id);
+            return;
+        }
+        tracing::info!("EVENT: received channel request {request_id} from peer {peer_pubkey}");
+
+        let _ = self.event_sender.send(NodeEvent::ChannelRequestReceived {
+            request_id,
+            peer_pubkey: peer_pubkey.to_string(),
+        });
+    }
+}
+
+impl CustomMessageReader for PeerMessageHandler {
+    type CustomMessage = PeerMessage;
+
+    fn read<R: Read>(
+        &self,
+        message_type: u16,
+        buffer: &mut R,
+    ) -> Result<Option<Self::CustomMessage>, DecodeError> {
+        let msg = match message_type {
+            CHANNEL_REQUEST_MESSAGE_TYPE => {
+                PeerMessage::ChannelRequest(ChannelRequestMessage::read(buffer)?)
+            }
+            SWAP_OFFER_MESSAGE_TYPE => PeerMessage::SwapOffer(SwapOfferMessage::read(buffer)?),
+            SWAP_OFFER_ACCEPT_MESSAGE_TYPE => {
+                PeerMessage::SwapOfferAccept(SwapOfferAcceptMessage::read(buffer)?)
+            }
+            SWAP_OFFER_ACCEPTED_MESSAGE_TYPE => {
+                PeerMessage::SwapOfferAccepted(SwapOfferAcceptedMessage::read(buffer)?)
+            }
+            SUBMARINE_SWAP_REQUEST_MESSAGE_TYPE => {
+                PeerMessage::SubmarineSwapRequest(SubmarineSwapRequestMessage::read(buffer)?)
+            }
+            SUBMARINE_SWAP_ACCEPTED_MESSAGE_TYPE => {
+                PeerMessage::SubmarineSwapAccepted(SubmarineSwapAcceptedMessage::read(buffer)?)
+            }
+            SUBMARINE_SWAP_FUNDED_MESSAGE_TYPE => {
+                PeerMessage::SubmarineSwapFunded(SubmarineSwapFundedMessage::read(buffer)?)
+            }
+            SWAP_PAIRS_MESSAGE_TYPE => PeerMessage::SwapPairs(SwapPairsMessage::read(buffer)?),
+            _ => return Ok(None),
+        };
+        Ok(Some(msg))
+    }
+}
+
+impl CustomMessageHandler for PeerMessageHandler {
+    fn handle_custom_message(
+        &self,
+        msg: Self::CustomMessage,
+        sender_node_id: &PublicKey,
+    ) -> Result<(), LightningError> {
+        match msg {
+            PeerMessage::ChannelRequest(msg) => self.handle_channel_request(msg, *sender_node_id),
+            PeerMessage::SwapPairs(msg) => self.handle_swap_pairs(msg, *sender_node_id),
+            msg @ (PeerMessage::SubmarineSwapRequest(_)
+            | PeerMessage::SubmarineSwapAccepted(_)
+            | PeerMessage::SubmarineSwapFunded(_)) => {
+                let _ = self.submarine_swap_sender.send((*sender_node_id, msg));
+            }
+            msg => {
+                let _ = self.swap_offer_sender.send((*sender_node_id, msg));
+            }
+        }
+        Ok(())
+    }
+
+    fn get_and_clear_pending_msg(&self) -> Vec<(PublicKey, Self::CustomMessage)> {
+        std::mem::take(&mut *self.pending_messages.lock().unwrap())
+    }
+
+    fn peer_disconnected(&self, their_node_id: &PublicKey) {
+        self.peer_swap_pairs.lock().unwrap().remove(their_node_id);
+    }
+
+    /// Refuse the peers not accepted by the policy, before they can exchange any message, and send
+    /// our swap pairs to the accepted ones supporting the swap protocol
+    fn peer_connected(
+        &self,
+        their_node_id: &PublicKey,
+        msg: &Init,
+        inbound: bool,
+    ) -> Result<(), ()> {
+        let refusal = lock(&self.peer_policy, "peer_policy").refusal(their_node_id);
+        if let Some(reason) = refusal {
+            let direction = if inbound { "inbound" } else { "outbound" };
+            tracing::info!("Refusing {direction} peer: {reason}");
+            return Err(());
+        }
+        if supports_swap_protocol(msg.features.le_flags()) {
+            let pairs = self.swap_pairs();
+            self.send_message(
+                *their_node_id,
+                PeerMessage::SwapPairs(SwapPairsMessage { pairs }),
+            );
+        }
+        Ok(())
+    }
+
+    fn provided_node_features(&self) -> NodeFeatures {
+        let mut features = NodeFeatures::empty();
+        for bit in CUSTOM_FEATURE_BITS {
+            features
+                .set_optional_custom_bit(bit)
+                .expect("valid custom bit");
+        }
+        features
+    }
+
+    fn provided_init_features(&self, _their_node_id: &PublicKey) -> InitFeatures {
+        let mut features = InitFeatures::empty();
+        for bit in CUSTOM_FEATURE_BITS {
+            features
+                .set_optional_custom_bit(bit)
+                .expect("valid custom bit");
+        }
+        features
+    }
+}
+
+/// Whether the given little-endian feature flags signal support for the feature with the given
+/// optional bit, either as optional or required.
+pub(crate) fn supports_feature_bit(le_flags: &[u8], optional_bit: usize) -> bool {
+    let required_bit = optional_bit - 1;
+    [required_bit, optional_bit].iter().any(|bit| {
+        le_flags
+            .get(bit / 8)
+            .map(|byte| byte & (1 << (bit % 8)) != 0)
+            .unwrap_or(false)
+    })
+}
@@ -0,0 +1,153 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::impl_writeable_tlv_based;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::disk::PEER_POLICY_FNAME;
+use crate::error::APIError;
+use crate::ldk::PeerManager;
+use crate::utils::{get_current_timestamp, UnlockedAppState};
+
+/// A peer that cannot connect nor open channels, until the ban expires if it has an expiry
+#[derive(Clone, Debug)]
+pub(crate) struct PeerBan {
+    pub(crate) pubkey: PublicKey,
+    pub(crate) expires_at: Option<u64>,
+}
+
+impl_writeable_tlv_based!(PeerBan, {
+    (0, pubkey, required),
+    (2, expires_at, option),
+});
+
+impl PeerBan {
+    fn is_active(&self, now: u64) -> bool {
+        self.expires_at.map_or(true, |e| e > now)
+    }
+}
+
+/// Peers the node accepts connections and channels from
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PeerPolicy {
+    /// When not empty, only these peers are accepted
+    pub(crate) allowed: Vec<PublicKey>,
+    pub(crate) bans: Vec<PeerBan>,
+    /// Max number of connected peers, inbound connections beyond it are dropped
+    pub(crate) max_peers: Option<u32>,
+}
+
+impl_writeable_tlv_based!(PeerPolicy, {
+    (0, allowed, required_vec),
+    (2, bans, required_vec),
+    (4, max_peers, option),
+});
+
+impl PeerPolicy {
+    /// Reason why the given peer is not accepted, if it isn't
+    pub(crate) fn refusal(&self, pubkey: &PublicKey) -> Option<String> {
+        let now = get_current_timestamp();
+        if let Some(ban) = self
+            .bans
+            .iter()
+            .find(|b| &b.pubkey == pubkey && b.is_active(now))
+        {
+            return Some(match ban.expires_at {
+                Some(expires_at) => format!("peer {pubkey} is banned until {expires_at}"),
+                None => format!("peer {pubkey} is banned"),
+            });
+        }
+        if !self.allowed.is_empty() && !self.allowed.contains(pubkey) {
+            return Some(format!("peer {pubkey} is not in the allowed list"));
+        }
+        None
+    }
+
+    /// Whether a new inbound connection can be accepted with the given number of peers
+    pub(crate) fn accepts_inbound(&self, num_peers: usize) -> bool {
+        self.max_peers.map_or(true, |m| num_peers < m as usize)
+    }
+}
+
+/// Number of connected peers, counting the inbound connections still in the handshake, so that
+/// they count toward the max number of peers
+pub(crate) fn num_peers(peer_manager: &PeerManager, inbound_connections: &AtomicUsize) -> usize {
+    let outbound_peers = peer_manager
+        .list_peers()
+        .iter()
+        .filter(|p| !p.is_inbound_connection)
+        .count();
+    outbound_peers + inbound_connections.load(Ordering::Acquire)
+}
+
+/// Run an inbound connection, already counted in `inbound_connections`, until it's closed. Its
+/// peer is checked against the policy by the peer message handler once the handshake reveals its
+/// node ID, see [`crate::peer_messages::PeerMessageHandler`].
+pub(crate) async fn run_inbound_connection(
+    peer_manager: Arc<PeerManager>,
+    inbound_connections: Arc<AtomicUsize>,
+    stream: TcpStream,
+) {
+    lightning_net_tokio::setup_inbound(peer_manager, stream).await;
+    inbound_connections.fetch_sub(1, Ordering::AcqRel);
+}
+
+impl UnlockedAppState {
+    /// Fail if the given peer is not accepted by the policy
+    pub(crate) fn check_peer_policy(&self, pubkey: &PublicKey) -> Result<(), APIError> {
+        match self.get_peer_policy().refusal(pubkey) {
+            Some(reason) => Err(APIError::PeerNotAllowed(reason)),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn set_peer_policy(
+        &self,
+        allowed: Vec<PublicKey>,
+        max_peers: Option<u32>,
+    ) -> Result<(), APIError> {
+        let mut peer_policy = self.get_peer_policy();
+        peer_policy.allowed = allowed;
+        peer_policy.max_peers = max_peers;
+        self.persist(PEER_POLICY_FNAME, &*peer_policy)
+    }
+
+    /// Ban a peer, for the given time if any, disconnecting it
+    pub(crate) fn ban_peer(
+        &self,
+        pubkey: PublicKey,
+        duration_secs: Option<u64>,
+    ) -> Result<(), APIError> {
+        let now = get_current_timestamp();
+        {
+            let mut peer_policy = self.get_peer_policy();
+            // a new ban replaces the previous one, expired bans are dropped
+            peer_policy
+                .bans
+                .retain(|b| b.pubkey != pubkey && b.is_active(now));
+            peer_policy.bans.push(PeerBan {
+                pubkey,
+                expires_at: duration_secs.map(|d| now + d),
+            });
+            self.persist(PEER_POLICY_FNAME, &*peer_policy)?;
+        }
+        self.peer_manager.disconnect_by_node_id(pubkey);
+        Ok(())
+    }
+
+    pub(crate) fn unban_peer(&self, pubkey: &PublicKey) -> Result<(), APIError> {
+        let now = get_current_timestamp();
+        let mut peer_policy = self.get_peer_policy();
+        if !peer_policy
+            .bans
+            .iter()
+            .any(|b| &b.pubkey == pubkey && b.is_active(now))
+        {
+            return Err(APIError::PeerNotBanned);
+        }
+        peer_policy
+            .bans
+            .retain(|b| &b.pubkey != pubkey && b.is_active(now));
+        self.persist(PEER_POLICY_FNAME, &*peer_policy)
+    }
+}
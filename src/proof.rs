@@ -0,0 +1,106 @@
+use serde::Serialize;
+use zip::write::SimpleFileOptions;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::APIError;
+
+const MANIFEST_FNAME: &str = "manifest.json";
+const TXOUTPROOF_FNAME: &str = "txoutproof.hex";
+const WITNESS_TX_FNAME: &str = "witness_tx.hex";
+
+/// A send consignment to be included in a transfer proof
+pub(crate) struct ProofConsignment {
+    pub(crate) asset_id: String,
+    pub(crate) recipient_id: String,
+    pub(crate) path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct ManifestConsignment<'a> {
+    asset_id: &'a str,
+    recipient_id: &'a str,
+    file: String,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    txid: &'a str,
+    height: u32,
+    witness_tx: &'a str,
+    txoutproof: &'a str,
+    consignments: Vec<ManifestConsignment<'a>>,
+}
+
+/// Write a transfer proof to the provided file.
+///
+/// The proof is a zip archive holding the send consignments, the raw witness transaction and
+/// the merkle proof of its inclusion in the block (as returned by bitcoind's `gettxoutproof`),
+/// along with a manifest describing them
+pub(crate) fn write_transfer_proof(
+    proof_file: &Path,
+    txid: &str,
+    height: u32,
+    consignments: &[ProofConsignment],
+    tx_hex: &str,
+    txoutproof_hex: &str,
+) -> Result<(), APIError> {
+    let consignment_name =
+        |c: &ProofConsignment| format!("consignments/{}/{}.rgb", c.asset_id, c.recipient_id);
+    let manifest = Manifest {
+        txid,
+        height,
+        witness_tx: WITNESS_TX_FNAME,
+        txoutproof: TXOUTPROOF_FNAME,
+        consignments: consignments
+            .iter()
+            .map(|c| ManifestConsignment {
+                asset_id: &c.asset_id,
+                recipient_id: &c.recipient_id,
+                file: consignment_name(c),
+            })
+            .collect(),
+    };
+    let manifest = serde_json::to_string_pretty(&manifest).expect("valid manifest");
+
+    // setup
+    let writer = File::create(proof_file)?;
+    let mut zip = zip::ZipWriter::new(writer);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Zstd);
+    let mut buffer = [0u8; 4096];
+
+    // archive
+    for (name, content) in [
+        (MANIFEST_FNAME, manifest.as_str()),
+        (WITNESS_TX_FNAME, tx_hex),
+        (TXOUTPROOF_FNAME, txoutproof_hex),
+    ] {
+        zip.start_file(name, options)
+            .map_err(|_| APIError::Unexpected)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    for consignment in consignments {
+        let name = consignment_name(consignment);
+        tracing::debug!("adding file {:?} as {name}", consignment.path);
+        zip.start_file(name, options)
+            .map_err(|_| APIError::Unexpected)?;
+        let mut f = File::open(&consignment.path)?;
+        loop {
+            let read_count = f.read(&mut buffer)?;
+            if read_count != 0 {
+                zip.write_all(&buffer[..read_count])?;
+            } else {
+                break;
+            }
+        }
+    }
+
+    // finalize
+    let mut file = zip.finish().map_err(|_| APIError::Unexpected)?;
+    file.flush()?;
+    file.sync_all()?;
+
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use amplify::s;
+use bitcoin::hashes::{sha256, Hash};
+use lightning::impl_writeable_tlv_based;
+use rgb_lib::wallet::TransportEndpoint;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::error::APIError;
+use crate::routes::ProxyPinType;
+use crate::utils::hex_str;
+
+const PROXY_PIN_CHECK_TIMEOUT_SECS: u64 = 30;
+
+const PROXY_INFO_TIMEOUT_SECS: u64 = 10;
+
+/// A trusted RGB proxy server, identified by the SHA256 hash of its TLS certificate or of the
+/// public key (DER-encoded SubjectPublicKeyInfo) in it
+#[derive(Clone, Debug)]
+pub(crate) struct ProxyPin {
+    pub(crate) pin_type: ProxyPinType,
+    pub(crate) sha256: String,
+    pub(crate) created_at: u64,
+}
+
+impl_writeable_tlv_based!(ProxyPin, {
+    (0, pin_type, required),
+    (2, sha256, required),
+    (4, created_at, required),
+});
+
+/// Pinned proxies, keyed by host and port
+pub(crate) struct ProxyPinMap {
+    pub(crate) pins: HashMap<String, ProxyPin>,
+}
+
+impl_writeable_tlv_based!(ProxyPinMap, {
+    (0, pins, required),
+});
+
+/// Proxies the consignments posted by the node have been served by, keyed by TXID
+pub(crate) struct ConsignmentProxyMap {
+    pub(crate) proxies: HashMap<String, String>,
+}
+
+impl_writeable_tlv_based!(ConsignmentProxyMap, {
+    (0, proxies, required),
+});
+
+/// Parse a proxy endpoint (e.g. rpcs://proxy.example.com/json-rpc) into the URL it's reached at
+pub(crate) fn proxy_url(proxy_endpoint: &str) -> Result<reqwest::Url, APIError> {
+    let endpoint = TransportEndpoint::new(proxy_endpoint.to_string())
+        .map_err(|e| APIError::InvalidTransportEndpoints(e.to_string()))?;
+    reqwest::Url::parse(&endpoint.endpoint)
+        .map_err(|e| APIError::InvalidTransportEndpoints(e.to_string()))
+}
+
+/// Key the pin of the proxy at the given URL is stored under
+pub(crate) fn proxy_pin_key(url: &reqwest::Url) -> String {
+    format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+/// Check that the given proxy endpoints are pinned and that the TLS certificate they present
+/// matches their pin.
+///
+/// Pinning is opt-in: when no proxy has been pinned any endpoint is accepted
+pub(crate) async fn check_proxy_pins(
+    pins: &HashMap<String, ProxyPin>,
+    proxy_endpoints: &[String],
+) -> Result<(), APIError> {
+    if pins.is_empty() {
+        return Ok(());
+    }
+    for proxy_endpoint in proxy_endpoints {
+        let url = proxy_url(proxy_endpoint)?;
+        let pin = pins
+            .get(&proxy_pin_key(&url))
+            .ok_or_else(|| APIError::CannotUseProxy(format!("{proxy_endpoint} is not pinned")))?;
+        let certificate = fetch_certificate(&url).await.map_err(|e| {
+            APIError::CannotUseProxy(format!("cannot check pin of {proxy_endpoint}: {e}"))
+        })?;
+        let pinned_data = match pin.pin_type {
+            ProxyPinType::Certificate => &certificate[..],
+            ProxyPinType::PublicKey => certificate_spki(&certificate).ok_or_else(|| {
+                APIError::CannotUseProxy(format!(
+                    "cannot parse the certificate of {proxy_endpoint}"
+                ))
+            })?,
+        };
+        if hex_str(&sha256::Hash::hash(pinned_data).to_byte_array()) != pin.sha256 {
+            tracing::error!("TLS certificate of proxy {proxy_endpoint} doesn't match its pin");
+            return Err(APIError::CannotUseProxy(format!(
+                "{proxy_endpoint} doesn't match its pin"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The given proxy endpoints that can be used right now, in the same (priority) order: their pin
+/// (if pinning is in use) must match and they must answer a server.info call.
+///
+/// If none can be used, the error of the last one is returned
+pub(crate) async fn usable_proxy_endpoints(
+    pins: &HashMap<String, ProxyPin>,
+    proxy_endpoints: &[String],
+) -> Result<Vec<String>, APIError> {
+    let mut usable = vec![];
+    let mut last_error = APIError::CannotUseProxy(s!("no proxy configured"));
+    for proxy_endpoint in proxy_endpoints {
+        let res = match check_proxy_pins(pins, &[proxy_endpoint.clone()]).await {
+            Ok(()) => check_proxy_info(proxy_endpoint).await,
+            Err(e) => Err(e),
+        };
+        match res {
+            Ok(()) => usable.push(proxy_endpoint.clone()),
+            Err(e) => {
+                tracing::warn!("skipping proxy {proxy_endpoint}: {e}");
+                last_error = e;
+            }
+        }
+    }
+    if usable.is_empty() {
+        return Err(last_error);
+    }
+    Ok(usable)
+}
+
+/// Call the server.info method of the proxy, to check it's up
+async fn check_proxy_info(proxy_endpoint: &str) -> Result<(), APIError> {
+    let url = proxy_url(proxy_endpoint)?;
+    let unreachable =
+        |e: String| APIError::CannotUseProxy(format!("{proxy_endpoint} is unreachable: {e}"));
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(PROXY_INFO_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| unreachable(e.to_string()))?;
+    let res = client
+        .post(url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "server.info",
+            "params": null,
+        }))
+        .send()
+        .await
+        .map_err(|e| unreachable(e.to_string()))?;
+    if !res.status().is_success() {
+        return Err(unreachable(format!(
+            "server.info returned {}",
+            res.status()
+        )));
+    }
+    Ok(())
+}
+
+/// Connect to the proxy and return the DER-encoded certificate it presents
+async fn fetch_certificate(url: &reqwest::Url) -> Result<Vec<u8>, String> {
+    if url.scheme() != "https" {
+        return Err(s!("endpoint doesn't use TLS"));
+    }
+    let client = reqwest::Client::builder()
+        .tls_info(true)
+        .timeout(Duration::from_secs(PROXY_PIN_CHECK_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let res = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    res.extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|tls_info| tls_info.peer_certificate())
+        .map(|certificate| certificate.to_vec())
+        .ok_or(s!("no certificate presented"))
+}
+
+/// Split the DER element at the start of the given data into the whole element, its content and
+/// the data following it
+fn der_element(data: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let first_len_byte = *data.get(1)? as usize;
+    let (len, header_len) = if first_len_byte < 0x80 {
+        (first_len_byte, 2)
+    } else {
+        let num_len_bytes = first_len_byte & 0x7f;
+        if num_len_bytes == 0 || num_len_bytes > 4 {
+            return None;
+        }
+        let len = data
+            .get(2..2 + num_len_bytes)?
+            .iter()
+            .fold(0usize, |len, b| (len << 8) | *b as usize);
+        (len, 2 + num_len_bytes)
+    };
+    let end = header_len.checked_add(len)?;
+    let element = data.get(..end)?;
+    Some((element, &element[header_len..], &data[end..]))
+}
+
+/// Extract the DER-encoded SubjectPublicKeyInfo from an X.509 certificate
+fn certificate_spki(certificate: &[u8]) -> Option<&[u8]> {
+    let (_, certificate, _) = der_element(certificate)?;
+    let (_, tbs_certificate, _) = der_element(certificate)?;
+    let mut fields = tbs_certificate;
+    // skip the optional version, then serial number, signature, issuer, validity and subject
+    if fields.first() == Some(&0xa0) {
+        fields = der_element(fields)?.2;
+    }
+    for _ in 0..5 {
+        fields = der_element(fields)?.2;
+    }
+    Some(der_element(fields)?.0)
+}
@@ -0,0 +1,95 @@
+use futures::{stream, StreamExt};
+use rand::Rng;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::utils::{AppState, UnlockedAppState};
+
+/// Shortest interval between two background RGB refreshes
+pub(crate) const MIN_RGB_REFRESH_INTERVAL_SECS: u64 = 10;
+
+/// Max delay added to each refresh interval, as a fraction of it
+const RGB_REFRESH_JITTER_DIVISOR: u64 = 10;
+
+/// Refresh the pending RGB transfers at regular intervals, so incoming transfers and confirmations
+/// of witness TXs are detected without clients having to call `/refreshtransfers`
+pub(crate) async fn run_rgb_refresh(app_state: Arc<AppState>, stop_processing: Arc<AtomicBool>) {
+    let Some(interval) = app_state.static_state.rgb_refresh_interval else {
+        return;
+    };
+    loop {
+        // the jitter keeps nodes started together from polling the proxies at the same time
+        let jitter_ms = rand::thread_rng()
+            .gen_range(0..=interval.as_millis() as u64 / RGB_REFRESH_JITTER_DIVISOR);
+        tokio::time::sleep(interval + Duration::from_millis(jitter_ms)).await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let unlocked_state = match app_state.check_unlocked().await {
+            Ok(unlocked_state) => unlocked_state.clone().unwrap(),
+            Err(_) => continue,
+        };
+        refresh_rgb_transfers(
+            unlocked_state,
+            app_state.static_state.rgb_refresh_parallelism,
+        )
+        .await;
+    }
+}
+
+async fn refresh_rgb_transfers(unlocked_state: Arc<UnlockedAppState>, parallelism: usize) {
+    let assets = match unlocked_state.rgb_list_assets(vec![]) {
+        Ok(assets) => assets,
+        Err(e) => {
+            tracing::error!("Background RGB refresh cannot list assets: {e}");
+            return;
+        }
+    };
+    let asset_ids = assets
+        .nia
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| a.asset_id)
+        .chain(
+            assets
+                .uda
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| a.asset_id),
+        )
+        .chain(
+            assets
+                .cfa
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| a.asset_id),
+        );
+
+    // each asset is refreshed on its own, so an asset whose proxy is unreachable doesn't hold back
+    // the others
+    stream::iter(asset_ids)
+        .for_each_concurrent(parallelism, |asset_id| {
+            let unlocked_state = unlocked_state.clone();
+            async move {
+                let res = tokio::task::spawn_blocking({
+                    let asset_id = asset_id.clone();
+                    move || unlocked_state.rgb_refresh_asset(asset_id)
+                })
+                .await
+                .unwrap();
+                if let Err(e) = res {
+                    tracing::error!("Background RGB refresh of asset {asset_id} failed: {e}");
+                }
+            }
+        })
+        .await;
+
+    // incoming transfers of invoices not specifying an asset are only picked up by a full refresh
+    let res = tokio::task::spawn_blocking(move || unlocked_state.rgb_refresh())
+        .await
+        .unwrap();
+    if let Err(e) = res {
+        tracing::error!("Background RGB refresh failed: {e}");
+    }
+}
@@ -15,10 +15,11 @@ use lightning::sign::ChangeDestinationSource;
 use rgb_lib::{
     bdk::SignOptions,
     bitcoin::psbt::PartiallySignedTransaction as BitcoinPsbt,
+    utils::load_rgb_runtime,
     wallet::{
-        rust_only::ColoringInfo, AssetCFA, AssetNIA, AssetUDA, Assets, Balance, BtcBalance, Online,
-        ReceiveData, Recipient, RefreshResult, SendResult, Transaction as RgbLibTransaction,
-        Transfer, Unspent, WalletData,
+        rust_only::ColoringInfo, AssetCFA, AssetIface, AssetNIA, AssetUDA, Assets, Balance,
+        BtcBalance, Online, ReceiveData, Recipient, RefreshResult, SendResult,
+        Transaction as RgbLibTransaction, Transfer, Unspent, WalletData,
     },
     AssetSchema, Contract, ContractId, Error as RgbLibError, RgbTransfer, UpdateRes,
     Wallet as RgbLibWallet,
@@ -26,8 +27,9 @@ use rgb_lib::{
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex};
 
+use crate::locks::{lock, AuditedGuard};
 use crate::utils::UnlockedAppState;
 
 impl UnlockedAppState {
@@ -57,6 +59,13 @@ impl UnlockedAppState {
             .create_utxos(up_to, num, size, fee_rate)
     }
 
+    pub(crate) fn rgb_export_contract(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<Contract, RgbLibError> {
+        self.rgb_wallet_wrapper.export_contract(contract_id)
+    }
+
     pub(crate) fn rgb_get_address(&self) -> Result<String, RgbLibError> {
         self.rgb_wallet_wrapper.get_address()
     }
@@ -187,6 +196,10 @@ impl UnlockedAppState {
         self.rgb_wallet_wrapper.refresh()
     }
 
+    pub(crate) fn rgb_refresh_asset(&self, asset_id: String) -> Result<RefreshResult, RgbLibError> {
+        self.rgb_wallet_wrapper.refresh_asset(asset_id)
+    }
+
     pub(crate) fn rgb_save_new_asset(
         &self,
         asset_schema: &AssetSchema,
@@ -249,6 +262,21 @@ impl UnlockedAppState {
     pub(crate) fn rgb_sign_psbt(&self, unsigned_psbt: String) -> Result<String, RgbLibError> {
         self.rgb_wallet_wrapper.sign_psbt(unsigned_psbt)
     }
+
+    pub(crate) fn rgb_witness_receive(
+        &self,
+        asset_id: Option<String>,
+        duration_seconds: Option<u32>,
+        transport_endpoints: Vec<String>,
+        min_confirmations: u8,
+    ) -> Result<ReceiveData, RgbLibError> {
+        self.rgb_wallet_wrapper.witness_receive(
+            asset_id,
+            duration_seconds,
+            transport_endpoints,
+            min_confirmations,
+        )
+    }
 }
 
 pub(crate) struct RgbLibWalletWrapper {
@@ -261,8 +289,8 @@ impl RgbLibWalletWrapper {
         RgbLibWalletWrapper { wallet, online }
     }
 
-    pub(crate) fn get_rgb_wallet(&self) -> MutexGuard<RgbLibWallet> {
-        self.wallet.lock().unwrap()
+    pub(crate) fn get_rgb_wallet(&self) -> AuditedGuard<RgbLibWallet> {
+        lock(&self.wallet, "rgb_wallet")
     }
 
     pub(crate) fn blind_receive(
@@ -306,6 +334,19 @@ impl RgbLibWalletWrapper {
         )
     }
 
+    /// Contract of an asset known to the wallet, as stored in its RGB runtime
+    pub(crate) fn export_contract(&self, contract_id: ContractId) -> Result<Contract, RgbLibError> {
+        self.get_asset_iface(contract_id)?;
+        // the wallet lock is held while the runtime is loaded, as the wallet uses it too
+        let wallet = self.get_rgb_wallet();
+        let runtime = load_rgb_runtime(wallet.get_wallet_dir())?;
+        runtime
+            .export_contract(contract_id)
+            .map_err(|e| RgbLibError::Internal {
+                details: e.to_string(),
+            })
+    }
+
     pub(crate) fn get_address(&self) -> Result<String, RgbLibError> {
         self.get_rgb_wallet().get_address()
     }
@@ -412,6 +453,40 @@ impl RgbLibWalletWrapper {
         self.get_rgb_wallet().list_assets(filter_asset_schemas)
     }
 
+    /// Interface of an asset known to the wallet, needed to color TXs moving its allocations
+    pub(crate) fn get_asset_iface(
+        &self,
+        contract_id: ContractId,
+    ) -> Result<AssetIface, RgbLibError> {
+        let asset_id = contract_id.to_string();
+        let assets =
+            self.list_assets(vec![AssetSchema::Nia, AssetSchema::Cfa, AssetSchema::Uda])?;
+        if assets
+            .nia
+            .unwrap_or_default()
+            .iter()
+            .any(|a| a.asset_id == asset_id)
+        {
+            Ok(AssetIface::RGB20)
+        } else if assets
+            .cfa
+            .unwrap_or_default()
+            .iter()
+            .any(|a| a.asset_id == asset_id)
+        {
+            Ok(AssetIface::RGB25)
+        } else if assets
+            .uda
+            .unwrap_or_default()
+            .iter()
+            .any(|a| a.asset_id == asset_id)
+        {
+            Ok(AssetIface::RGB21)
+        } else {
+            Err(RgbLibError::AssetNotFound { asset_id })
+        }
+    }
+
     pub(crate) fn list_transactions(&self) -> Result<Vec<RgbLibTransaction>, RgbLibError> {
         self.get_rgb_wallet()
             .list_transactions(Some(self.online.clone()))
@@ -448,6 +523,11 @@ impl RgbLibWalletWrapper {
             .refresh(self.online.clone(), None, vec![])
     }
 
+    pub(crate) fn refresh_asset(&self, asset_id: String) -> Result<RefreshResult, RgbLibError> {
+        self.get_rgb_wallet()
+            .refresh(self.online.clone(), Some(asset_id), vec![])
+    }
+
     pub(crate) fn save_new_asset(
         &self,
         asset_schema: &AssetSchema,
@@ -530,10 +610,18 @@ impl RgbLibWalletWrapper {
 
     pub(crate) fn witness_receive(
         &self,
+        asset_id: Option<String>,
+        duration_seconds: Option<u32>,
         transport_endpoints: Vec<String>,
+        min_confirmations: u8,
     ) -> Result<ReceiveData, RgbLibError> {
-        self.get_rgb_wallet()
-            .witness_receive(None, None, None, transport_endpoints, 0)
+        self.get_rgb_wallet().witness_receive(
+            asset_id,
+            None,
+            duration_seconds,
+            transport_endpoints,
+            min_confirmations,
+        )
     }
 }
 
@@ -0,0 +1,96 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::impl_writeable_tlv_based;
+use lightning::util::persist::{
+    CHANNEL_MANAGER_PERSISTENCE_KEY, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE, OUTPUT_SWEEPER_PERSISTENCE_KEY,
+};
+use rgb_lib::bitcoin::{
+    bip32::{ChildNumber, ExtendedPrivKey},
+    secp256k1::Secp256k1 as Secp256k1_30,
+};
+use std::fs;
+use std::path::Path;
+
+use crate::kv_store::NodeStore;
+use crate::routes::NodeIdRotationStatus;
+
+pub(crate) const NODE_ID_ROTATION_ARCHIVE_DIR: &str = "node_id_rotation_archive";
+
+/// A node ID rotation job, persisted so that it can be resumed across restarts.
+///
+/// The key index is the one the node key is derived with once the rotation completes, the
+/// previous one stays in use while channels are being closed and funds swept
+#[derive(Clone, Debug)]
+pub(crate) struct NodeIdRotation {
+    pub(crate) status: NodeIdRotationStatus,
+    pub(crate) key_index: u32,
+    pub(crate) old_pubkey: PublicKey,
+    pub(crate) new_pubkey: Option<PublicKey>,
+    pub(crate) started_at: u64,
+    pub(crate) completed_at: Option<u64>,
+}
+
+impl_writeable_tlv_based!(NodeIdRotation, {
+    (0, status, required),
+    (2, key_index, required),
+    (4, old_pubkey, required),
+    (6, new_pubkey, option),
+    (8, started_at, required),
+    (10, completed_at, option),
+});
+
+impl NodeIdRotation {
+    /// Index of the key currently used by the node
+    pub(crate) fn active_key_index(&self) -> u32 {
+        match self.status {
+            NodeIdRotationStatus::ClosingChannels | NodeIdRotationStatus::SweepingFunds => {
+                self.key_index - 1
+            }
+            NodeIdRotationStatus::RestartRequired | NodeIdRotationStatus::Completed => {
+                self.key_index
+            }
+        }
+    }
+}
+
+/// Derive the LDK seed for the given key index.
+///
+/// Index 0 is the original node key, derived directly from the LDK account, so nodes that never
+/// rotated their ID keep using the same key
+pub(crate) fn derive_ldk_seed(ldk_xprv: &ExtendedPrivKey, key_index: u32) -> [u8; 32] {
+    let xprv = if key_index == 0 {
+        *ldk_xprv
+    } else {
+        ldk_xprv
+            .ckd_priv(
+                &Secp256k1_30::new(),
+                ChildNumber::Hardened { index: key_index },
+            )
+            .unwrap()
+    };
+    xprv.private_key.secret_bytes()
+}
+
+/// Move the LDK state bound to the old node key out of the way, so that the node starts fresh
+/// with the new one
+pub(crate) fn archive_ldk_state(
+    kv_store: &NodeStore,
+    ldk_data_dir: &Path,
+    old_key_index: u32,
+) -> std::io::Result<()> {
+    let archive_dir = ldk_data_dir
+        .join(NODE_ID_ROTATION_ARCHIVE_DIR)
+        .join(old_key_index.to_string());
+    fs::create_dir_all(&archive_dir)?;
+    tracing::info!("archiving LDK state of node key {old_key_index} to {archive_dir:?}");
+    kv_store.archive(
+        ldk_data_dir,
+        &[
+            CHANNEL_MANAGER_PERSISTENCE_KEY,
+            CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+            OUTPUT_SWEEPER_PERSISTENCE_KEY,
+        ],
+        &archive_dir,
+    )
+}
@@ -1,29 +1,45 @@
 use amplify::{map, s};
 use axum::{
-    extract::{Multipart, State},
+    extract::{Multipart, Path as UrlPath, Query, State},
     Json,
 };
 use axum_extra::extract::WithRejection;
+use base64::{engine::general_purpose, Engine as _};
+use bitcoin::address::Payload;
+use bitcoin::blockdata::opcodes::all::OP_RETURN;
 use bitcoin::hashes::sha256::{self, Hash as Sha256};
 use bitcoin::hashes::Hash;
-use bitcoin::secp256k1::PublicKey;
-use bitcoin::{Network, ScriptBuf};
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{PublicKey, SecretKey};
+use bitcoin::{Address, Network, OutPoint, ScriptBuf, TxOut, Txid};
 use hex::DisplayHex;
+use lightning::chain::chaininterface::{ConfirmationTarget, FeeEstimator};
 use lightning::impl_writeable_tlv_based_enum;
+use lightning::ln::channelmanager::{
+    ChannelDetails, ChannelShutdownState as LdkChannelShutdownState,
+};
 use lightning::ln::ChannelId;
 use lightning::offers::offer::{self, Offer};
 use lightning::onion_message::messenger::Destination;
 use lightning::rgb_utils::{
-    get_rgb_channel_info_path, get_rgb_payment_info_path, parse_rgb_channel_info,
-    parse_rgb_payment_info, STATIC_BLINDING,
+    get_rgb_channel_info_path, get_rgb_payment_info_path, is_channel_rgb, parse_rgb_channel_info,
+    parse_rgb_payment_info, read_rgb_transfer_info, STATIC_BLINDING,
+};
+use lightning::routing::gossip::{ChannelInfo, ChannelUpdateInfo, NodeInfo, RoutingFees};
+use lightning::routing::router::{
+    Path as LnPath, Route, RouteHint, RouteHintHop, DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA,
+};
+use lightning::sign::{
+    EntropySource, KeysManager, NodeSigner, Recipient as LdkRecipient, SpendableOutputDescriptor,
 };
-use lightning::routing::gossip::RoutingFees;
-use lightning::routing::router::{Path as LnPath, Route, RouteHint, RouteHintHop};
-use lightning::sign::EntropySource;
 use lightning::util::config::ChannelConfig;
+use lightning::util::sweep::OutputSpendStatus;
 use lightning::{
     ln::{
-        channelmanager::{PaymentId, RecipientOnionFields, Retry},
+        channelmanager::{
+            InterceptId, PaymentId, RecipientOnionFields, Retry, MIN_CLTV_EXPIRY_DELTA,
+        },
+        script::ShutdownScript,
         PaymentHash, PaymentPreimage,
     },
     rgb_utils::{write_rgb_channel_info, write_rgb_payment_info_file, RgbInfo},
@@ -37,24 +53,31 @@ use lightning::{
 use lightning_invoice::payment::{
     payment_parameters_from_invoice, payment_parameters_from_zero_amount_invoice,
 };
-use lightning_invoice::{utils::create_invoice_from_channelmanager, Currency};
-use lightning_invoice::{Bolt11Invoice, PaymentSecret};
+use lightning_invoice::{
+    utils::{
+        create_invoice_from_channelmanager,
+        create_invoice_from_channelmanager_with_description_hash,
+        create_invoice_from_channelmanager_with_payment_hash,
+    },
+    Currency, Fallback, PrivateRoute, RawTaggedField, Sha256 as InvoiceSha256, TaggedField,
+};
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescription, PaymentSecret};
 use rgb_lib::{
     generate_keys,
     utils::recipient_id_from_script_buf,
     wallet::{
         AssetCFA as RgbLibAssetCFA, AssetIface as RgbLibAssetIface, AssetNIA as RgbLibAssetNIA,
         AssetUDA as RgbLibAssetUDA, Balance as RgbLibBalance, Invoice as RgbLibInvoice,
-        Media as RgbLibMedia, Recipient, RecipientInfo, TokenLight as RgbLibTokenLight,
-        WitnessData,
+        Media as RgbLibMedia, Recipient, RecipientInfo, RecipientType,
+        TokenLight as RgbLibTokenLight, WitnessData,
     },
-    AssetSchema as RgbLibAssetSchema, BitcoinNetwork as RgbLibNetwork, ContractId,
-    Error as RgbLibError, RgbTransport,
+    AssetSchema as RgbLibAssetSchema, BitcoinNetwork as RgbLibNetwork, ConsignmentExt, Contract,
+    ContractId, Error as RgbLibError, FileContent, RgbTransport,
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    net::ToSocketAddrs,
+    fs,
     path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
@@ -63,22 +86,59 @@ use std::{
 use tokio::{
     fs::File,
     io::{AsyncReadExt, AsyncWriteExt, BufReader},
-    sync::MutexGuard as TokioMutexGuard,
+    sync::{oneshot, MutexGuard as TokioMutexGuard},
 };
+use tracing_subscriber::EnvFilter;
 
-use crate::backup::{do_backup, restore_backup};
-use crate::ldk::{start_ldk, stop_ldk, LdkBackgroundServices, MIN_CHANNEL_CONFIRMATIONS};
+use crate::backup::{
+    do_backup, do_scb_backup, export_backup, import_backup, read_scb_backup, restore_backup,
+};
+use crate::channel_conversion::ChannelConversionData;
+use crate::channel_request::ChannelRequestMessage;
+use crate::consignment::{describe_consignment, load_consignment};
+use crate::encryption::{change_storage_key_password, load_storage_key};
+use crate::fee_order::{FeeOrderData, FEE_ORDER_INVOICE_EXPIRY_SECS};
+use crate::ldk::{
+    finalize_funding_psbt, fund_channel, funding_double_spend_psbt, funding_psbt_from_utxos,
+    payment_blinding, placeholder_funding_script, start_ldk, stop_ldk, FundingBatch, FundingChange,
+    HtlcLimits, LdkBackgroundServices, LdkKeys, MIN_CHANNEL_CONFIRMATIONS,
+};
+use crate::lease::run_standby;
+use crate::peer_messages::{
+    supports_feature_bit, PeerMessage, CHANNEL_REQUEST_FEATURE_BIT, SUBMARINE_SWAP_FEATURE_BIT,
+    SWAP_OFFER_FEATURE_BIT,
+};
+use crate::proof::{write_transfer_proof, ProofConsignment};
+use crate::proxy::{proxy_pin_key, proxy_url, ProxyPin};
 use crate::rgb::get_rgb_channel_info_optional;
-use crate::swap::{SwapData, SwapInfo, SwapString};
+use crate::rotation::NodeIdRotation;
+use crate::scb::{build_scb, restore_rgb_info, StaticChannelBackup};
+use crate::schedule::{ScheduleData, MIN_SCHEDULE_INTERVAL_SECS};
+use crate::spend_limits::{SpendData, SpendOperation, SpendReservation};
+use crate::storage_status::{check_consistency, disk_usage};
+use crate::submarine_swap::{
+    check_swap_rgb_invoice, create_swap_invoice, SubmarineSwapData, SubmarineSwapRequestMessage,
+    ASSET_SUBMARINE_SWAP_AMOUNT_SAT, SUBMARINE_SWAP_MIN_SAT,
+};
+use crate::swap::{
+    supports_swap_protocol, SwapData, SwapInfo, SwapPair, SwapString, SwapTransitionData,
+};
+use crate::swap_offer::{
+    announce_swap_offers, initiate_offer_swap, save_swap_offers, SwapOfferAcceptMessage,
+    SwapOfferData, SwapOfferMessage, SWAP_OFFER_SWAP_TIMEOUT_SECS,
+};
+use crate::swap_quote::{best_offer_quote, oracle_quote};
 use crate::utils::{
     check_already_initialized, check_channel_id, check_password_strength, check_password_validity,
-    encrypt_and_save_mnemonic, get_max_local_rgb_amount, get_mnemonic_path, get_route, hex_str,
-    hex_str_to_compressed_pubkey, hex_str_to_vec, UnlockedAppState, UserOnionMessageContents,
+    check_seed_words, encrypt_and_save_mnemonic, get_fee_rate, get_max_local_rgb_amount,
+    get_mnemonic_path, get_payment_retry, get_route, get_seed_revealed_path, hex_str,
+    hex_str_to_compressed_pubkey, hex_str_to_vec, retry_details, UnlockedAppState,
+    UserOnionMessageContents,
 };
 use crate::{
-    disk::{self, CHANNEL_PEER_DATA},
-    error::APIError,
-    ldk::{PaymentInfo, FEE_RATE, UTXO_SIZE_SAT},
+    disk::{self, CHANNEL_ANNOUNCEMENT_FNAME, CHANNEL_PEER_DATA},
+    error::{APIError, LnurlError},
+    ldk::{LnurlWithdraw, PaymentInfo},
     utils::{
         connect_peer_if_necessary, get_current_timestamp, no_cancel, parse_peer_info, AppState,
     },
@@ -86,9 +146,16 @@ use crate::{
 
 const UTXO_NUM: u8 = 4;
 
-const OPENCHANNEL_MIN_SAT: u64 = 5506;
+/// Default amount of the output receiving the assets sent to a witness recipient (in sat)
+pub(crate) const WITNESS_AMOUNT_SAT: u64 = 1000;
+
+/// Amount of the unspendable output receiving burnt assets (in sat), the P2WSH dust limit
+const BURN_AMOUNT_SAT: u64 = 330;
+
+pub(crate) const OPENCHANNEL_MIN_SAT: u64 = 5506;
 const OPENCHANNEL_MAX_SAT: u64 = 16777215;
 const OPENCHANNEL_MIN_RGB_AMT: u64 = 1;
+const OPENCHANNEL_FUNDING_TIMEOUT_SECS: u64 = 30;
 
 pub const DUST_LIMIT_MSAT: u64 = 546000;
 
@@ -97,13 +164,58 @@ pub(crate) const MAX_SWAP_FEE_MSAT: u64 = HTLC_MIN_MSAT;
 
 const INVOICE_MIN_MSAT: u64 = HTLC_MIN_MSAT;
 
+const REBALANCE_INVOICE_EXPIRY_SECS: u32 = 3600;
+
+const LNURL_INVOICE_EXPIRY_SECS: u32 = 600;
+
+const LNURL_REQUEST_TIMEOUT_SECS: u64 = 30;
+
 pub(crate) const DEFAULT_FINAL_CLTV_EXPIRY_DELTA: u32 = 14;
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AbandonFundingRequest {
+    pub(crate) temporary_channel_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AbandonFundingResponse {
+    pub(crate) double_spend_txid: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AbandonPaymentRequest {
+    pub(crate) payment_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AcceptSwapOfferRequest {
+    pub(crate) offer_id: String,
+    pub(crate) qty_from: u64,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AddressResponse {
     pub(crate) address: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AnnounceChannelRequest {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ApiToken {
+    pub(crate) name: String,
+    pub(crate) read_only: bool,
+    pub(crate) created_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ApproveChannelRequestRequest {
+    pub(crate) request_id: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AssetBalanceRequest {
     pub(crate) asset_id: String,
@@ -166,6 +278,32 @@ impl From<RgbLibAssetIface> for AssetIface {
     }
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetLoopInRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetLoopInResponse {
+    pub(crate) payment_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetLoopOutRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) asset_id: String,
+    pub(crate) asset_amount: u64,
+    /// RGB invoice receiving the assets on-chain
+    pub(crate) rgb_invoice: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetLoopOutResponse {
+    pub(crate) payment_hash: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AssetNIA {
     pub(crate) asset_id: String,
@@ -206,6 +344,16 @@ pub(crate) enum AssetSchema {
     Cfa,
 }
 
+impl From<RgbLibAssetSchema> for AssetSchema {
+    fn from(value: RgbLibAssetSchema) -> Self {
+        match value {
+            RgbLibAssetSchema::Nia => Self::Nia,
+            RgbLibAssetSchema::Uda => Self::Uda,
+            RgbLibAssetSchema::Cfa => Self::Cfa,
+        }
+    }
+}
+
 impl From<AssetSchema> for RgbLibAssetSchema {
     fn from(value: AssetSchema) -> Self {
         match value {
@@ -251,10 +399,41 @@ impl From<RgbLibAssetUDA> for AssetUDA {
 
 #[derive(Deserialize, Serialize)]
 pub(crate) struct BackupRequest {
+    /// Path to write the backup to, the backup being returned when missing
+    #[serde(default)]
+    pub(crate) backup_path: Option<String>,
+    pub(crate) password: String,
+    #[serde(default)]
+    pub(crate) incremental: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BackupResponse {
+    /// The base64-encoded backup, when no path was given
+    pub(crate) backup: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BackupScbRequest {
     pub(crate) backup_path: String,
     pub(crate) password: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BackupSeedRequest {
+    pub(crate) password: String,
+    /// Words to check against the mnemonic, which is revealed when missing
+    #[serde(default)]
+    pub(crate) words: Option<Vec<SeedWord>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BackupSeedResponse {
+    pub(crate) mnemonic: Option<String>,
+    /// Whether the provided words match the mnemonic
+    pub(crate) verified: Option<bool>,
+}
+
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub(crate) enum BitcoinNetwork {
     Mainnet,
@@ -286,6 +465,19 @@ impl From<RgbLibNetwork> for BitcoinNetwork {
     }
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BanPeerRequest {
+    pub(crate) pubkey: String,
+    /// Ban forever if not set
+    pub(crate) duration_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BannedPeer {
+    pub(crate) pubkey: String,
+    pub(crate) expires_at: Option<u64>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct BlockTime {
     pub(crate) height: u32,
@@ -315,6 +507,32 @@ pub(crate) struct BtcBalanceResponse {
     pub(crate) colored: BtcBalance,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BumpCloseTxRequest {
+    pub(crate) channel_id: String,
+    pub(crate) fee_rate: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BurnAssetRequest {
+    pub(crate) asset_id: String,
+    pub(crate) amount: u64,
+    /// Defaults to the node fee rate
+    pub(crate) fee_rate: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BurnAssetResponse {
+    pub(crate) txid: String,
+    /// Witness script of the output the assets have been assigned to, hex-encoded
+    pub(crate) burn_script: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CancelFeeOrderRequest {
+    pub(crate) order_id: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct ChangePasswordRequest {
     pub(crate) old_password: String,
@@ -328,6 +546,10 @@ pub(crate) struct Channel {
     pub(crate) peer_pubkey: String,
     pub(crate) peer_alias: Option<String>,
     pub(crate) short_channel_id: Option<u64>,
+    /// Alias the peer uses for the channel, embedded in invoices instead of the real SCID
+    pub(crate) inbound_scid_alias: Option<u64>,
+    /// Alias we assigned to the channel
+    pub(crate) outbound_scid_alias: Option<u64>,
     pub(crate) ready: bool,
     pub(crate) capacity_sat: u64,
     pub(crate) local_balance_msat: u64,
@@ -340,6 +562,146 @@ pub(crate) struct Channel {
     pub(crate) asset_id: Option<String>,
     pub(crate) asset_local_amount: Option<u64>,
     pub(crate) asset_remote_amount: Option<u64>,
+    pub(crate) shutdown_state: Option<ChannelShutdownState>,
+    pub(crate) fee_base_msat: Option<u32>,
+    pub(crate) fee_proportional_millionths: Option<u32>,
+    pub(crate) cltv_expiry_delta: Option<u16>,
+    pub(crate) asset_htlc_maximum: Option<u64>,
+    pub(crate) close_address: Option<String>,
+}
+
+/// Conversion of a private channel to an announced one, see /announcechannel
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelConversion {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) status: ChannelConversionStatus,
+    pub(crate) requested_at: u64,
+    pub(crate) completed_at: Option<u64>,
+    /// Temporary ID of the announced channel, once opened
+    pub(crate) temporary_channel_id: Option<String>,
+    /// Why the announced channel could not be opened (yet)
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ChannelConversionStatus {
+    Closing,
+    Reopening,
+    Completed,
+    Failed,
+}
+
+impl_writeable_tlv_based_enum!(ChannelConversionStatus,
+    (0, Closing) => {},
+    (1, Reopening) => {},
+    (2, Completed) => {},
+    (3, Failed) => {};
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelFeeReport {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: Option<String>,
+    pub(crate) forwards: u64,
+    pub(crate) fee_earned_msat: u64,
+    pub(crate) skimmed_forwards: u64,
+    pub(crate) skimmed_fee_msat: u64,
+    pub(crate) counterparty_skimmed_payments: u64,
+    pub(crate) counterparty_skimmed_fee_msat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelForwardingStats {
+    pub(crate) channel_id: String,
+    pub(crate) inbound_forwards: u64,
+    pub(crate) outbound_forwards: u64,
+    pub(crate) inbound_amount_msat: u64,
+    pub(crate) outbound_amount_msat: u64,
+    pub(crate) fee_earned_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) inbound_asset_amount: u64,
+    pub(crate) outbound_asset_amount: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelRequest {
+    pub(crate) request_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) push_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) fee_sat: u64,
+    pub(crate) status: ChannelRequestStatus,
+    pub(crate) received_at: u64,
+    pub(crate) temporary_channel_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ChannelRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl_writeable_tlv_based_enum!(ChannelRequestStatus,
+    (0, Pending) => {},
+    (1, Approved) => {},
+    (2, Rejected) => {};
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ClosedChannel {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: Option<String>,
+    pub(crate) reason: String,
+    pub(crate) error: Option<String>,
+    pub(crate) closed_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ChannelShutdownState {
+    NotShuttingDown,
+    ShutdownInitiated,
+    ResolvingHTLCs,
+    NegotiatingClosingFee,
+    ShutdownComplete,
+}
+
+/// RGB transfer made by the node for one of its channels
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ChannelTransfer {
+    pub(crate) kind: ChannelTransferKind,
+    pub(crate) channel_id: Option<String>,
+    /// TXs of the closed channels whose outputs are swept, for sweeps
+    pub(crate) closing_txids: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ChannelTransferKind {
+    Funding,
+    Sweep,
+}
+
+impl_writeable_tlv_based_enum!(ChannelTransferKind,
+    (0, Funding) => {},
+    (1, Sweep) => {};
+);
+
+impl From<LdkChannelShutdownState> for ChannelShutdownState {
+    fn from(value: LdkChannelShutdownState) -> Self {
+        match value {
+            LdkChannelShutdownState::NotShuttingDown => Self::NotShuttingDown,
+            LdkChannelShutdownState::ShutdownInitiated => Self::ShutdownInitiated,
+            LdkChannelShutdownState::ResolvingHTLCs => Self::ResolvingHTLCs,
+            LdkChannelShutdownState::NegotiatingClosingFee => Self::NegotiatingClosingFee,
+            LdkChannelShutdownState::ShutdownComplete => Self::ShutdownComplete,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
@@ -347,6 +709,24 @@ pub(crate) struct CloseChannelRequest {
     pub(crate) channel_id: String,
     pub(crate) peer_pubkey: String,
     pub(crate) force: bool,
+    pub(crate) fee_rate: Option<f32>,
+    pub(crate) conf_target: Option<u16>,
+    pub(crate) close_address: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ConfirmSpendRequest {
+    pub(crate) token: String,
+    pub(crate) password: String,
+}
+
+/// Response of the confirmed operation, the others being unset
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ConfirmSpendResponse {
+    pub(crate) keysend: Option<KeysendResponse>,
+    pub(crate) send_asset: Option<SendAssetResponse>,
+    pub(crate) send_btc: Option<SendBtcResponse>,
+    pub(crate) send_payment: Option<SendPaymentResponse>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -354,6 +734,64 @@ pub(crate) struct ConnectPeerRequest {
     pub(crate) peer_pubkey_and_addr: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ConsignmentTransition {
+    pub(crate) opid: String,
+    pub(crate) witness_id: String,
+    pub(crate) amounts: Vec<u64>,
+    pub(crate) concealed_amounts: u32,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ConsignmentValidation {
+    Valid,
+    Pending,
+    Invalid,
+    Unvalidated,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreateApiTokenRequest {
+    pub(crate) name: String,
+    pub(crate) password: String,
+    /// Whether the token can only call the endpoints that don't move funds nor change the node
+    /// state
+    pub(crate) read_only: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreateApiTokenResponse {
+    pub(crate) token: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreateFeeOrderRequest {
+    pub(crate) request_id: String,
+    pub(crate) expiry_sec: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreateFeeOrderResponse {
+    pub(crate) order_id: String,
+    pub(crate) invoice: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreateScheduleRequest {
+    pub(crate) target_type: ScheduleTargetType,
+    pub(crate) target: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) interval_secs: u64,
+    pub(crate) start_at: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CreateScheduleResponse {
+    pub(crate) schedule_id: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct CreateUtxosRequest {
     pub(crate) up_to: bool,
@@ -362,6 +800,21 @@ pub(crate) struct CreateUtxosRequest {
     pub(crate) fee_rate: f32,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct CustomTlvRecord {
+    pub(crate) tlv_type: u64,
+    pub(crate) value: String,
+}
+
+impl From<&(u64, Vec<u8>)> for CustomTlvRecord {
+    fn from(value: &(u64, Vec<u8>)) -> Self {
+        Self {
+            tlv_type: value.0,
+            value: hex_str(&value.1),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct DecodeLNInvoiceRequest {
     pub(crate) invoice: String,
@@ -396,68 +849,254 @@ pub(crate) struct DecodeRGBInvoiceResponse {
     pub(crate) transport_endpoints: Vec<String>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DeleteScheduleRequest {
+    pub(crate) schedule_id: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct DisconnectPeerRequest {
     pub(crate) peer_pubkey: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DiskUsage {
+    pub(crate) total_bytes: u64,
+    /// LDK data in the storage dir, empty when stored in Postgres
+    pub(crate) ldk_data_bytes: u64,
+    pub(crate) logs_bytes: u64,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct EmptyResponse {}
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetAssetMediaRequest {
-    pub(crate) digest: String,
+pub(crate) struct ExecuteFeeOrderRequest {
+    pub(crate) order_id: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetAssetMediaResponse {
-    pub(crate) bytes_hex: String,
+pub(crate) struct ExportContractRequest {
+    pub(crate) asset_id: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetChannelIdRequest {
-    pub(crate) temporary_channel_id: String,
+pub(crate) struct ExportContractResponse {
+    /// Base64-encoded contract consignment
+    pub(crate) contract: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetChannelIdResponse {
-    pub(crate) channel_id: String,
+pub(crate) struct FailInterceptRequest {
+    pub(crate) intercept_id: String,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
-pub(crate) enum HTLCStatus {
-    Pending,
-    Succeeded,
-    Failed,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct FeeOrder {
+    pub(crate) order_id: String,
+    pub(crate) request_id: String,
+    pub(crate) fee_msat: u64,
+    pub(crate) payment_hash: String,
+    pub(crate) invoice: String,
+    pub(crate) status: FeeOrderStatus,
+    pub(crate) created_at: u64,
+    pub(crate) temporary_channel_id: Option<String>,
 }
 
-impl_writeable_tlv_based_enum!(HTLCStatus,
-    (0, Pending) => {},
-    (1, Succeeded) => {},
-    (2, Failed) => {};
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum FeeOrderStatus {
+    AwaitingPayment,
+    Paid,
+    ChannelOpening,
+    Completed,
+    Refunded,
+    Cancelled,
+}
+
+impl_writeable_tlv_based_enum!(FeeOrderStatus,
+    (0, AwaitingPayment) => {},
+    (1, Paid) => {},
+    (2, ChannelOpening) => {},
+    (3, Completed) => {},
+    (4, Refunded) => {},
+    (5, Cancelled) => {};
 );
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InitRequest {
-    pub(crate) password: String,
+pub(crate) struct FeeReportResponse {
+    pub(crate) channels: Vec<ChannelFeeReport>,
+    pub(crate) total_fee_earned_msat: u64,
+    pub(crate) total_skimmed_fee_msat: u64,
+    pub(crate) total_counterparty_skimmed_fee_msat: u64,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InitResponse {
-    pub(crate) mnemonic: String,
+pub(crate) struct Forward {
+    pub(crate) prev_channel_id: String,
+    pub(crate) next_channel_id: String,
+    pub(crate) payment_hash: String,
+    pub(crate) inbound_amount_msat: Option<u64>,
+    pub(crate) outbound_amount_msat: Option<u64>,
+    pub(crate) fee_earned_msat: Option<u64>,
+    pub(crate) skimmed_fee_msat: Option<u64>,
+    pub(crate) inbound_asset_id: Option<String>,
+    pub(crate) inbound_asset_amount: Option<u64>,
+    pub(crate) outbound_asset_id: Option<String>,
+    pub(crate) outbound_asset_amount: Option<u64>,
+    pub(crate) forwarded_at: u64,
 }
 
-#[derive(Clone, Copy, Deserialize, Serialize)]
-pub(crate) enum InvoiceStatus {
-    Pending,
-    Succeeded,
-    Failed,
-    Expired,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ForwardingHistoryResponse {
+    pub(crate) forwards: Vec<Forward>,
+    pub(crate) channels: Vec<ChannelForwardingStats>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InvoiceStatusRequest {
-    pub(crate) invoice: String,
+pub(crate) struct FundingPsbt {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) asset_id: Option<String>,
+    /// Unsigned funding PSBT (base64), colored for RGB channels
+    pub(crate) psbt: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetAssetMediaRequest {
+    pub(crate) digest: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetAssetMediaResponse {
+    pub(crate) bytes_hex: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetChannelIdRequest {
+    pub(crate) temporary_channel_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetChannelIdResponse {
+    pub(crate) channel_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetSwapRequest {
+    pub(crate) payment_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetSwapResponse {
+    pub(crate) swap: Swap,
+    /// Whether we are the taker of the swap
+    pub(crate) taker: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct GraphChannel {
+    pub(crate) short_channel_id: u64,
+    pub(crate) node_one: String,
+    pub(crate) node_two: String,
+    pub(crate) capacity_sat: Option<u64>,
+    pub(crate) one_to_two: Option<GraphChannelUpdate>,
+    pub(crate) two_to_one: Option<GraphChannelUpdate>,
+    pub(crate) asset_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct GraphChannelUpdate {
+    pub(crate) enabled: bool,
+    pub(crate) cltv_expiry_delta: u16,
+    pub(crate) htlc_minimum_msat: u64,
+    pub(crate) htlc_maximum_msat: u64,
+    pub(crate) fee_base_msat: u32,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) last_update: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub(crate) enum GraphExportFormat {
+    #[default]
+    Json,
+    Dot,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct GraphNode {
+    pub(crate) node_id: String,
+    pub(crate) alias: Option<String>,
+    pub(crate) addresses: Vec<String>,
+    pub(crate) last_update: Option<u32>,
+    pub(crate) short_channel_ids: Vec<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum HTLCStatus {
+    Pending,
+    Claimable,
+    Succeeded,
+    Failed,
+    Expired,
+}
+
+impl_writeable_tlv_based_enum!(HTLCStatus,
+    (0, Pending) => {},
+    (1, Succeeded) => {},
+    (2, Failed) => {},
+    (3, Claimable) => {},
+    (4, Expired) => {};
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ImportContractRequest {
+    /// Base64-encoded contract consignment
+    pub(crate) contract: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ImportContractResponse {
+    pub(crate) asset_id: String,
+    pub(crate) schema: AssetSchema,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InitRequest {
+    pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InitResponse {
+    pub(crate) mnemonic: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InspectConsignmentResponse {
+    pub(crate) consignments: Vec<InspectedConsignment>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InspectedConsignment {
+    pub(crate) asset_id: String,
+    pub(crate) schema: AssetSchema,
+    pub(crate) txid: Option<String>,
+    pub(crate) recipient_id: Option<String>,
+    pub(crate) transitions: Vec<ConsignmentTransition>,
+    pub(crate) validation: ConsignmentValidation,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub(crate) enum InvoiceStatus {
+    Pending,
+    Claimable,
+    Succeeded,
+    Failed,
+    Expired,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InvoiceStatusRequest {
+    pub(crate) invoice: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -507,12 +1146,15 @@ pub(crate) struct IssueAssetUDAResponse {
     pub(crate) asset: AssetUDA,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub(crate) struct KeysendRequest {
     pub(crate) dest_pubkey: String,
     pub(crate) amt_msat: u64,
     pub(crate) asset_id: Option<String>,
     pub(crate) asset_amount: Option<u64>,
+    pub(crate) custom_records: Option<Vec<CustomTlvRecord>>,
+    pub(crate) retry_attempts: Option<u32>,
+    pub(crate) retry_timeout_secs: Option<u64>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -522,6 +1164,11 @@ pub(crate) struct KeysendResponse {
     pub(crate) status: HTLCStatus,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListApiTokensResponse {
+    pub(crate) tokens: Vec<ApiToken>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct ListAssetsRequest {
     pub(crate) filter_asset_schemas: Vec<AssetSchema>,
@@ -534,14 +1181,45 @@ pub(crate) struct ListAssetsResponse {
     pub(crate) cfa: Option<Vec<AssetCFA>>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListBackupsResponse {
+    pub(crate) backups: Vec<ScheduledBackup>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListChannelConversionsResponse {
+    pub(crate) conversions: Vec<ChannelConversion>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListChannelRequestsResponse {
+    pub(crate) requests: Vec<ChannelRequest>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct ListChannelsResponse {
     pub(crate) channels: Vec<Channel>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListClosedChannelsResponse {
+    pub(crate) channels: Vec<ClosedChannel>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListFeeOrdersResponse {
+    pub(crate) orders: Vec<FeeOrder>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListFundingPsbtsResponse {
+    pub(crate) psbts: Vec<FundingPsbt>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct ListPaymentsResponse {
     pub(crate) payments: Vec<Payment>,
+    pub(crate) snapshot_id: u64,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -549,10 +1227,42 @@ pub(crate) struct ListPeersResponse {
     pub(crate) peers: Vec<Peer>,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListProxyPinsResponse {
+    pub(crate) pins: Vec<ProxyPinInfo>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListSchedulesResponse {
+    pub(crate) schedules: Vec<Schedule>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListSubmarineSwapsResponse {
+    pub(crate) swaps: Vec<SubmarineSwap>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListSwapOffersResponse {
+    pub(crate) offers: Vec<SwapOffer>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ListSwapsRequest {
+    pub(crate) status: Option<SwapStatus>,
+    /// Only list swaps sending or receiving this asset
+    pub(crate) asset_id: Option<String>,
+    /// Only list swaps requested at or after this timestamp
+    pub(crate) from_timestamp: Option<u64>,
+    /// Only list swaps requested at or before this timestamp
+    pub(crate) to_timestamp: Option<u64>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct ListSwapsResponse {
     pub(crate) maker: Vec<Swap>,
     pub(crate) taker: Vec<Swap>,
+    pub(crate) snapshot_id: u64,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -562,12 +1272,21 @@ pub(crate) struct ListTransactionsResponse {
 
 #[derive(Deserialize, Serialize)]
 pub(crate) struct ListTransfersRequest {
-    pub(crate) asset_id: String,
+    /// Transfers of all the known assets are listed if not set
+    pub(crate) asset_id: Option<String>,
+    pub(crate) status: Option<TransferStatus>,
+    /// Number of matching transfers to skip, oldest first
+    #[serde(default)]
+    pub(crate) offset: u32,
+    /// Max number of transfers to return, all the remaining ones if not set
+    pub(crate) limit: Option<u32>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub(crate) struct ListTransfersResponse {
     pub(crate) transfers: Vec<Transfer>,
+    /// Number of transfers matching the filters, before pagination
+    pub(crate) total: u32,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -581,6 +1300,10 @@ pub(crate) struct LNInvoiceRequest {
     pub(crate) expiry_sec: u32,
     pub(crate) asset_id: Option<String>,
     pub(crate) asset_amount: Option<u64>,
+    pub(crate) payment_hash: Option<String>,
+    pub(crate) description_hash: Option<String>,
+    pub(crate) fallback_address: Option<String>,
+    pub(crate) route_hint_channel_ids: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -588,11 +1311,102 @@ pub(crate) struct LNInvoiceResponse {
     pub(crate) invoice: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlPayCallbackRequest {
+    pub(crate) amount: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlPayCallbackResponse {
+    pub(crate) pr: String,
+    pub(crate) routes: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LnurlPayResponse {
+    pub(crate) callback: String,
+    pub(crate) max_sendable: u64,
+    pub(crate) min_sendable: u64,
+    pub(crate) metadata: String,
+    pub(crate) tag: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlStatusResponse {
+    pub(crate) status: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlWithdrawCallbackRequest {
+    pub(crate) k1: String,
+    pub(crate) pr: String,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LnurlWithdrawInfoResponse {
+    pub(crate) tag: String,
+    pub(crate) callback: String,
+    pub(crate) k1: String,
+    pub(crate) default_description: String,
+    pub(crate) min_withdrawable: u64,
+    pub(crate) max_withdrawable: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) asset_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlWithdrawRequest {
+    pub(crate) min_withdrawable_msat: u64,
+    pub(crate) max_withdrawable_msat: u64,
+    pub(crate) description: String,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) max_asset_amount: Option<u64>,
+    pub(crate) expiry_sec: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlWithdrawResponse {
+    pub(crate) k1: String,
+    pub(crate) url: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LoopInRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) amount_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LoopInResponse {
+    pub(crate) payment_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LoopOutRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) amount_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LoopOutResponse {
+    pub(crate) payment_hash: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct MakerExecuteRequest {
     pub(crate) swapstring: String,
     pub(crate) payment_secret: String,
     pub(crate) taker_pubkey: String,
+    pub(crate) first_leg_cltv_expiry_delta: Option<u32>,
+    pub(crate) second_leg_cltv_expiry_delta: Option<u32>,
+    /// Fraction of qty_from to swap, only for partial-fill swaps
+    pub(crate) fill_qty_from: Option<u64>,
 }
 
 // "from" and "to" are seen from the taker's perspective, so:
@@ -606,6 +1420,9 @@ pub(crate) struct MakerInitRequest {
     pub(crate) from_asset: Option<String>,
     pub(crate) to_asset: Option<String>,
     pub(crate) timeout_sec: u32,
+    pub(crate) partial_fill: Option<bool>,
+    /// If set, the swap is refused when the taker is known not to support it
+    pub(crate) taker_pubkey: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -615,6 +1432,21 @@ pub(crate) struct MakerInitResponse {
     pub(crate) swapstring: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct MaxSendableAssetRequest {
+    pub(crate) asset_id: String,
+    /// If set, only amounts that can be routed to this node are considered
+    pub(crate) dest_pubkey: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct MaxSendableAssetResponse {
+    /// Largest amount that can be sent with a single payment path
+    pub(crate) max_path_amount: u64,
+    /// Largest amount that can be sent over all the channels together, with a multi-path payment
+    pub(crate) max_mpp_amount: u64,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct Media {
     pub(crate) file_path: String,
@@ -632,12 +1464,61 @@ impl From<RgbLibMedia> for Media {
     }
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkGraphChannelRequest {
+    pub(crate) short_channel_id: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkGraphChannelResponse {
+    pub(crate) channel: GraphChannel,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkGraphExportRequest {
+    #[serde(default)]
+    pub(crate) format: GraphExportFormat,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkGraphExportResponse {
+    pub(crate) nodes: Vec<GraphNode>,
+    pub(crate) channels: Vec<GraphChannel>,
+    pub(crate) dot: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkGraphNodeRequest {
+    pub(crate) node_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkGraphNodeResponse {
+    pub(crate) node: GraphNode,
+    pub(crate) channels: Vec<GraphChannel>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct NetworkInfoResponse {
     pub(crate) network: BitcoinNetwork,
     pub(crate) height: u32,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum NodeIdRotationStatus {
+    ClosingChannels,
+    SweepingFunds,
+    RestartRequired,
+    Completed,
+}
+
+impl_writeable_tlv_based_enum!(NodeIdRotationStatus,
+    (0, ClosingChannels) => {},
+    (1, SweepingFunds) => {},
+    (2, RestartRequired) => {},
+    (3, Completed) => {};
+);
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct NodeInfoResponse {
     pub(crate) pubkey: String,
@@ -652,6 +1533,8 @@ pub(crate) struct NodeInfoResponse {
     pub(crate) channel_capacity_max_sat: u64,
     pub(crate) channel_asset_min_amount: u64,
     pub(crate) channel_asset_max_amount: u64,
+    /// Pairs advertised to peers supporting the swap protocol
+    pub(crate) swap_pairs: Vec<SwapPairInfo>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -661,16 +1544,43 @@ pub(crate) struct OpenChannelRequest {
     pub(crate) push_msat: u64,
     pub(crate) asset_amount: Option<u64>,
     pub(crate) asset_id: Option<String>,
-    pub(crate) public: bool,
+    pub(crate) public: Option<bool>,
     pub(crate) with_anchors: bool,
     pub(crate) fee_base_msat: Option<u32>,
     pub(crate) fee_proportional_millionths: Option<u32>,
     pub(crate) temporary_channel_id: Option<String>,
+    pub(crate) change_address: Option<String>,
+    pub(crate) close_address: Option<String>,
+    pub(crate) funding_outpoints: Option<Vec<String>>,
+    pub(crate) max_htlc_value_in_flight_percent: Option<u8>,
+    pub(crate) max_accepted_htlcs: Option<u16>,
+    pub(crate) htlc_minimum_msat: Option<u64>,
+    pub(crate) channel_reserve_proportional_millionths: Option<u32>,
+    /// Fee rate of the funding transaction, defaults to the node fee rate
+    pub(crate) fee_rate: Option<f32>,
+    /// Leave the funding PSBT to be signed externally, see /listfundingpsbts
+    #[serde(default)]
+    pub(crate) external_signing: bool,
 }
 
 #[derive(Deserialize, Serialize)]
 pub(crate) struct OpenChannelResponse {
     pub(crate) temporary_channel_id: String,
+    pub(crate) change_outpoint: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OpenChannelsRequest {
+    pub(crate) channels: Vec<OpenChannelRequest>,
+    pub(crate) funding_outpoints: Option<Vec<String>>,
+    /// Fee rate of the funding transaction, defaults to the node fee rate
+    pub(crate) fee_rate: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OpenChannelsResponse {
+    pub(crate) temporary_channel_ids: Vec<String>,
+    pub(crate) funding_txid: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -681,84 +1591,330 @@ pub(crate) struct Payment {
     pub(crate) payment_hash: String,
     pub(crate) inbound: bool,
     pub(crate) status: HTLCStatus,
+    pub(crate) custom_records: Vec<CustomTlvRecord>,
+    pub(crate) retry_attempts: Option<u32>,
+    pub(crate) retry_timeout_secs: Option<u64>,
+    pub(crate) failed_attempts: u32,
+    pub(crate) keysend: bool,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
 pub(crate) struct Peer {
     pub(crate) pubkey: String,
+    /// Pairs the peer can swap, if it advertised them
+    pub(crate) swap_pairs: Option<Vec<SwapPairInfo>>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct PostAssetMediaResponse {
-    pub(crate) digest: String,
+pub(crate) struct PeerPolicyResponse {
+    pub(crate) allowed: Vec<String>,
+    pub(crate) bans: Vec<BannedPeer>,
+    pub(crate) max_peers: Option<u32>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RestoreRequest {
-    pub(crate) backup_path: String,
-    pub(crate) password: String,
+pub(crate) struct PendingIntercept {
+    pub(crate) intercept_id: String,
+    pub(crate) payment_hash: String,
+    pub(crate) inbound_amount_msat: u64,
+    pub(crate) expected_outbound_amount_msat: u64,
+    pub(crate) inbound_asset_id: Option<String>,
+    pub(crate) inbound_asset_amount: Option<u64>,
+    pub(crate) outbound_asset_id: Option<String>,
+    pub(crate) expected_outbound_asset_amount: Option<u64>,
+    pub(crate) prev_channel_id: String,
+    pub(crate) requested_next_hop_scid: u64,
+    pub(crate) intercepted_at: u64,
+    pub(crate) expires_at: u64,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbAllocation {
-    pub(crate) asset_id: Option<String>,
-    pub(crate) amount: u64,
-    pub(crate) settled: bool,
+pub(crate) struct PendingInterceptsResponse {
+    pub(crate) intercepts: Vec<PendingIntercept>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbInvoiceRequest {
+pub(crate) struct PendingSweep {
+    pub(crate) outpoint: String,
+    pub(crate) channel_id: Option<String>,
+    pub(crate) amount_sat: u64,
     pub(crate) asset_id: Option<String>,
-    pub(crate) duration_seconds: Option<u32>,
-    pub(crate) min_confirmations: u8,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) maturity_height: Option<u32>,
+    pub(crate) status: SweepStatus,
+    pub(crate) spending_txid: Option<String>,
+    pub(crate) latest_broadcast_height: Option<u32>,
+    pub(crate) confirmation_height: Option<u32>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbInvoiceResponse {
-    pub(crate) recipient_id: String,
-    pub(crate) invoice: String,
-    pub(crate) expiration_timestamp: Option<i64>,
+pub(crate) struct PendingSweepsResponse {
+    pub(crate) sweeps: Vec<PendingSweep>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendAssetRequest {
-    pub(crate) asset_id: String,
-    pub(crate) amount: u64,
-    pub(crate) recipient_id: String,
-    pub(crate) donation: bool,
-    pub(crate) fee_rate: f32,
-    pub(crate) min_confirmations: u8,
-    pub(crate) transport_endpoints: Vec<String>,
+pub(crate) struct PinProxyRequest {
+    pub(crate) proxy_endpoint: String,
+    pub(crate) pin_type: ProxyPinType,
+    pub(crate) sha256: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendAssetResponse {
-    pub(crate) txid: String,
+pub(crate) struct PostAssetMediaResponse {
+    pub(crate) digest: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendBtcRequest {
-    pub(crate) amount: u64,
-    pub(crate) address: String,
-    pub(crate) fee_rate: f32,
+pub(crate) struct PostSwapOfferRequest {
+    pub(crate) from_asset: Option<String>,
+    pub(crate) to_asset: Option<String>,
+    pub(crate) price_from: u64,
+    pub(crate) price_to: u64,
+    pub(crate) min_qty_from: u64,
+    pub(crate) max_qty_from: u64,
+    pub(crate) total_qty_from: Option<u64>,
+    pub(crate) expiry_sec: u32,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendBtcResponse {
-    pub(crate) txid: String,
+pub(crate) struct PostSwapOfferResponse {
+    pub(crate) offer_id: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendOnionMessageRequest {
-    pub(crate) node_ids: Vec<String>,
+pub(crate) struct ProxyPinInfo {
+    pub(crate) proxy: String,
+    pub(crate) pin_type: ProxyPinType,
+    pub(crate) sha256: String,
+    pub(crate) created_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ProxyPinType {
+    Certificate,
+    PublicKey,
+}
+
+impl_writeable_tlv_based_enum!(ProxyPinType,
+    (0, Certificate) => {},
+    (1, PublicKey) => {};
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RebalanceRequest {
+    pub(crate) outbound_channel_id: String,
+    pub(crate) inbound_channel_id: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) max_fee_msat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RebalanceResponse {
+    pub(crate) payment_hash: String,
+    pub(crate) fee_msat: u64,
+    pub(crate) status: HTLCStatus,
+}
+
+/// How an RGB invoice receives the assets: on a blinded UTXO of ours or on a new output of the
+/// sender's TX, which also needs to carry some bitcoin
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub(crate) enum ReceiveMode {
+    #[default]
+    Blinded,
+    Witness,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RejectChannelRequestRequest {
+    pub(crate) request_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RequestChannelRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) push_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) fee_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RestoreRequest {
+    #[serde(default)]
+    pub(crate) backup_path: Option<String>,
+    /// The base64-encoded backup, to be given instead of `backup_path`
+    #[serde(default)]
+    pub(crate) backup: Option<String>,
+    pub(crate) password: String,
+    #[serde(default)]
+    pub(crate) incremental_backup_paths: Vec<String>,
+    #[serde(default)]
+    pub(crate) incremental_backups: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RestoreScbRequest {
+    pub(crate) backup_path: String,
+    pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RestoreScbResponse {
+    pub(crate) channels: Vec<ScbChannelRecovery>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RevokeApiTokenRequest {
+    pub(crate) name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbAllocation {
+    pub(crate) asset_id: Option<String>,
+    pub(crate) amount: u64,
+    pub(crate) settled: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbInvoiceRequest {
+    pub(crate) asset_id: Option<String>,
+    pub(crate) duration_seconds: Option<u32>,
+    pub(crate) min_confirmations: u8,
+    #[serde(default)]
+    pub(crate) receive_mode: ReceiveMode,
+    /// Empty to use the node proxies
+    #[serde(default)]
+    pub(crate) transport_endpoints: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbInvoiceResponse {
+    pub(crate) recipient_id: String,
+    pub(crate) invoice: String,
+    pub(crate) expiration_timestamp: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RotateNodeIdResponse {
+    pub(crate) status: NodeIdRotationStatus,
+    pub(crate) old_pubkey: String,
+    pub(crate) new_pubkey: Option<String>,
+    pub(crate) channels_left: usize,
+    pub(crate) balances_left: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ScbChannelRecovery {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) status: ScbChannelStatus,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ScbChannelStatus {
+    Open,
+    ForceCloseRequested,
+    PeerUnreachable,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Schedule {
+    pub(crate) schedule_id: String,
+    pub(crate) target_type: ScheduleTargetType,
+    pub(crate) target: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) interval_secs: u64,
+    pub(crate) next_run_at: u64,
+    pub(crate) enabled: bool,
+    pub(crate) created_at: u64,
+    pub(crate) runs: Vec<ScheduleRun>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ScheduleRun {
+    pub(crate) ran_at: u64,
+    pub(crate) payment_hash: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ScheduledBackup {
+    pub(crate) name: String,
+    pub(crate) created_at: u64,
+    /// Size of the encrypted backup (in bytes)
+    pub(crate) size: u64,
+    /// Path or s3:// URL of the backup
+    pub(crate) location: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ScheduleTargetType {
+    Keysend,
+    LnAddress,
+}
+
+impl_writeable_tlv_based_enum!(ScheduleTargetType,
+    (0, Keysend) => {},
+    (1, LnAddress) => {};
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SeedWord {
+    /// Position of the word in the mnemonic, starting from 1
+    pub(crate) position: u8,
+    pub(crate) word: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct SendAssetRequest {
+    pub(crate) asset_id: String,
+    pub(crate) amount: u64,
+    pub(crate) recipient_id: String,
+    pub(crate) donation: bool,
+    /// Defaults to the node fee rate
+    pub(crate) fee_rate: Option<f32>,
+    pub(crate) min_confirmations: u8,
+    pub(crate) transport_endpoints: Vec<String>,
+    /// Bitcoin amount sent along with the assets to witness recipients
+    pub(crate) witness_amount_sat: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendAssetResponse {
+    pub(crate) txid: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct SendBtcRequest {
+    pub(crate) amount: u64,
+    pub(crate) address: String,
+    /// Defaults to the node fee rate
+    pub(crate) fee_rate: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendBtcResponse {
+    pub(crate) txid: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendOnionMessageRequest {
+    pub(crate) node_ids: Vec<String>,
     pub(crate) tlv_type: u64,
     pub(crate) data: String,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub(crate) struct SendPaymentRequest {
     pub(crate) invoice: String,
     pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) retry_attempts: Option<u32>,
+    pub(crate) retry_timeout_secs: Option<u64>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -769,6 +1925,50 @@ pub(crate) struct SendPaymentResponse {
     pub(crate) status: HTLCStatus,
 }
 
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct SendToLnAddressRequest {
+    pub(crate) ln_address: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) retry_attempts: Option<u32>,
+    pub(crate) retry_timeout_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetAssetHtlcLimitRequest {
+    pub(crate) channel_id: String,
+    pub(crate) max_asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetChannelAnnouncementRequest {
+    pub(crate) public: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetLogLevelRequest {
+    /// Filter directives, e.g. `debug,lightning=trace`
+    pub(crate) filter: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetLogLevelResponse {
+    pub(crate) previous_filter: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetPeerPolicyRequest {
+    /// Only these peers are accepted, all peers not banned are if empty
+    pub(crate) allowed: Vec<String>,
+    pub(crate) max_peers: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SettleInvoiceRequest {
+    pub(crate) payment_preimage: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct SignMessageRequest {
     pub(crate) message: String,
@@ -779,6 +1979,132 @@ pub(crate) struct SignMessageResponse {
     pub(crate) signed_message: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SimulatePaymentRequest {
+    pub(crate) dest_pubkey: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SimulatePaymentResponse {
+    pub(crate) routable: bool,
+    pub(crate) success_probability: f64,
+    pub(crate) fee_msat: u64,
+    pub(crate) hops: Vec<SimulatedHop>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SimulatedHop {
+    pub(crate) pubkey: String,
+    pub(crate) short_channel_id: u64,
+    pub(crate) amt_msat: u64,
+    pub(crate) fee_msat: u64,
+    pub(crate) success_probability: f64,
+}
+
+/// Agreement of the channel monitors, the channel IDs map and the RGB channel info files with the
+/// channels known to the node, along with the leftover funding PSBTs and consignments
+#[derive(Deserialize, Serialize)]
+pub(crate) struct StorageConsistency {
+    pub(crate) consistent: bool,
+    /// Ready channels without a monitor
+    pub(crate) channels_without_monitor: Vec<String>,
+    /// Channel IDs in the channel IDs map not matching any channel
+    pub(crate) stale_channel_ids: Vec<String>,
+    /// RGB channel info files not matching any channel
+    pub(crate) orphaned_rgb_info_files: Vec<String>,
+    /// `psbt_*` and `consignment_*` files not matching any channel funding
+    pub(crate) orphaned_files: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct StorageStatusResponse {
+    pub(crate) disk_usage: DiskUsage,
+    /// Stores written since the node was unlocked
+    pub(crate) stores: Vec<StoreStatus>,
+    pub(crate) consistency: StorageConsistency,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct StoreStatus {
+    pub(crate) name: String,
+    pub(crate) last_persisted_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SubmarineSwap {
+    pub(crate) payment_hash: String,
+    pub(crate) kind: SubmarineSwapKind,
+    /// Whether we provide the swap to the peer
+    pub(crate) server: bool,
+    pub(crate) peer_pubkey: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) status: SubmarineSwapStatus,
+    pub(crate) created_at: u64,
+    pub(crate) timeout_height: Option<u32>,
+    pub(crate) htlc_address: Option<String>,
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) funding_vout: Option<u32>,
+    /// Transaction claiming or refunding the HTLC output
+    pub(crate) spending_txid: Option<String>,
+    pub(crate) failure_reason: Option<String>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    /// RGB invoice receiving the assets of an asset swap
+    pub(crate) rgb_invoice: Option<String>,
+}
+
+/// Direction of a submarine swap, from the point of view of the client
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum SubmarineSwapKind {
+    /// The client sends on-chain BTC and receives it on LN
+    LoopIn,
+    /// The client sends BTC on LN and receives it on-chain
+    LoopOut,
+}
+
+impl_writeable_tlv_based_enum!(SubmarineSwapKind,
+    (0, LoopIn) => {},
+    (1, LoopOut) => {};
+);
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum SubmarineSwapStatus {
+    Requested,
+    Accepted,
+    Paying,
+    Funded,
+    Claiming,
+    Succeeded,
+    Refunded,
+    Failed,
+}
+
+impl_writeable_tlv_based_enum!(SubmarineSwapStatus,
+    (0, Requested) => {},
+    (1, Accepted) => {},
+    (2, Paying) => {},
+    (3, Funded) => {},
+    (4, Succeeded) => {},
+    (5, Refunded) => {},
+    (6, Failed) => {},
+    (7, Claiming) => {};
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SubmitFundingPsbtRequest {
+    pub(crate) temporary_channel_id: String,
+    /// Funding PSBT (base64) with the signatures of all of its inputs
+    pub(crate) signed_psbt: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SubmitFundingPsbtResponse {
+    pub(crate) funding_txid: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct Swap {
     pub(crate) qty_from: u64,
@@ -791,6 +2117,81 @@ pub(crate) struct Swap {
     pub(crate) initiated_at: Option<u64>,
     pub(crate) expires_at: u64,
     pub(crate) completed_at: Option<u64>,
+    pub(crate) partial_fill: bool,
+    pub(crate) filled_qty_from: Option<u64>,
+    pub(crate) filled_qty_to: Option<u64>,
+    /// Why the swap failed, e.g. why its HTLC was rejected
+    pub(crate) failure_reason: Option<String>,
+    pub(crate) transitions: Vec<SwapTransition>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapOffer {
+    pub(crate) offer_id: String,
+    pub(crate) maker_pubkey: String,
+    pub(crate) from_asset: Option<String>,
+    pub(crate) to_asset: Option<String>,
+    pub(crate) price_from: u64,
+    pub(crate) price_to: u64,
+    pub(crate) min_qty_from: u64,
+    pub(crate) max_qty_from: u64,
+    pub(crate) remaining_qty_from: Option<u64>,
+    pub(crate) expiry: u64,
+    pub(crate) acceptances: Vec<SwapOfferAcceptance>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapOfferAcceptance {
+    pub(crate) taker_pubkey: String,
+    pub(crate) swapstring: String,
+    pub(crate) payment_secret: String,
+    pub(crate) accepted_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct SwapPairInfo {
+    pub(crate) from_asset: Option<String>,
+    pub(crate) to_asset: Option<String>,
+}
+
+impl From<&SwapPair> for SwapPairInfo {
+    fn from(value: &SwapPair) -> Self {
+        Self {
+            from_asset: value.from_asset.map(|a| a.to_string()),
+            to_asset: value.to_asset.map(|a| a.to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapQuoteRequest {
+    pub(crate) taker_pubkey: String,
+    pub(crate) from_asset: Option<String>,
+    pub(crate) to_asset: Option<String>,
+    pub(crate) qty_from: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapQuoteResponse {
+    pub(crate) source: SwapQuoteSource,
+    /// Offer the quote comes from, for quotes from the order book
+    pub(crate) offer_id: Option<String>,
+    pub(crate) qty_from: u64,
+    pub(crate) qty_to: u64,
+    /// Our fee, already taken from qty_to
+    pub(crate) fee: u64,
+    pub(crate) expiry: u64,
+    pub(crate) swapstring: String,
+    pub(crate) payment_hash: String,
+    pub(crate) payment_secret: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum SwapQuoteSource {
+    /// One of our swap offers
+    Offer,
+    /// The configured price oracle
+    Oracle,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -811,17 +2212,41 @@ impl_writeable_tlv_based_enum!(SwapStatus,
 );
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct TakerRequest {
-    pub(crate) swapstring: String,
+pub(crate) struct SwapTransition {
+    pub(crate) status: SwapStatus,
+    pub(crate) timestamp: u64,
+    pub(crate) reason: Option<String>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct TokenLight {
-    pub(crate) index: u32,
-    pub(crate) ticker: Option<String>,
-    pub(crate) name: Option<String>,
-    pub(crate) details: Option<String>,
-    pub(crate) embedded_media: bool,
+impl From<&SwapTransitionData> for SwapTransition {
+    fn from(value: &SwapTransitionData) -> Self {
+        Self {
+            status: value.status.clone(),
+            timestamp: value.timestamp,
+            reason: value.reason.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub(crate) enum SweepStatus {
+    PendingBroadcast,
+    PendingConfirmation,
+    Confirmed,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TakerRequest {
+    pub(crate) swapstring: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TokenLight {
+    pub(crate) index: u32,
+    pub(crate) ticker: Option<String>,
+    pub(crate) name: Option<String>,
+    pub(crate) details: Option<String>,
+    pub(crate) embedded_media: bool,
     pub(crate) media: Option<Media>,
     pub(crate) attachments: HashMap<u8, Media>,
     pub(crate) reserves: bool,
@@ -865,7 +2290,20 @@ pub(crate) enum TransactionType {
 }
 
 #[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct TransferProofRequest {
+    pub(crate) txid: String,
+    pub(crate) proof_path: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TransferProofResponse {
+    pub(crate) height: u32,
+    pub(crate) asset_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
 pub(crate) struct Transfer {
+    pub(crate) asset_id: String,
     pub(crate) idx: i32,
     pub(crate) created_at: i64,
     pub(crate) updated_at: i64,
@@ -878,6 +2316,10 @@ pub(crate) struct Transfer {
     pub(crate) change_utxo: Option<String>,
     pub(crate) expiration: Option<i64>,
     pub(crate) transport_endpoints: Vec<TransferTransportEndpoint>,
+    /// URL of the proxy the consignment has been exchanged through, once known
+    pub(crate) consignment_proxy: Option<String>,
+    /// Set for the fundings of the channels we opened and the sweeps of closed channels
+    pub(crate) channel_transfer: Option<ChannelTransfer>,
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
@@ -908,15 +2350,50 @@ pub(crate) enum TransportType {
     JsonRpc,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UnbanPeerRequest {
+    pub(crate) pubkey: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct UnlockRequest {
     pub(crate) password: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UnpinProxyRequest {
+    pub(crate) proxy_endpoint: String,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct Unspent {
     pub(crate) utxo: Utxo,
     pub(crate) rgb_allocations: Vec<RgbAllocation>,
+    /// Whether the UTXO is reserved for funding a channel being opened
+    pub(crate) locked_for_funding: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UpdateChannelPolicyRequest {
+    pub(crate) channel_id: Option<String>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) fee_base_msat: Option<u32>,
+    pub(crate) fee_proportional_millionths: Option<u32>,
+    pub(crate) cltv_expiry_delta: Option<u16>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UpdateChannelPolicyResponse {
+    pub(crate) channel_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UpdateScheduleRequest {
+    pub(crate) schedule_id: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) interval_secs: Option<u64>,
+    pub(crate) enabled: Option<bool>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -938,7 +2415,8 @@ impl AppState {
         &self,
     ) -> Result<TokioMutexGuard<Option<Arc<UnlockedAppState>>>, APIError> {
         let unlocked_app_state = self.get_unlocked_app_state().await;
-        if unlocked_app_state.is_some() {
+        // a node that is only relaying HTLCs is still locked
+        if unlocked_app_state.as_ref().is_some_and(|s| !s.relay_only) {
             Err(APIError::UnlockedNode)
         } else {
             self.check_changing_state()?;
@@ -946,11 +2424,11 @@ impl AppState {
         }
     }
 
-    async fn check_unlocked(
+    pub(crate) async fn check_unlocked(
         &self,
     ) -> Result<TokioMutexGuard<Option<Arc<UnlockedAppState>>>, APIError> {
         let unlocked_app_state = self.get_unlocked_app_state().await;
-        if unlocked_app_state.is_none() {
+        if unlocked_app_state.as_ref().map_or(true, |s| s.relay_only) {
             Err(APIError::LockedNode)
         } else {
             self.check_changing_state()?;
@@ -1007,6 +2485,214 @@ impl From<RgbLibError> for APIError {
     }
 }
 
+pub(crate) async fn abandon_funding(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AbandonFundingRequest>, APIError>,
+) -> Result<Json<AbandonFundingResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let temporary_channel_id = check_channel_id(&payload.temporary_channel_id)?;
+        // the funding transaction gets broadcast when the channel becomes pending
+        let channel_ids = unlocked_state.channel_ids();
+        let funding_broadcast = channel_ids.contains_key(&temporary_channel_id);
+        let channel_id = channel_ids
+            .get(&temporary_channel_id)
+            .copied()
+            .unwrap_or(temporary_channel_id);
+        let channel = unlocked_state
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .find(|c| c.channel_id == channel_id)
+            .ok_or(APIError::UnknownTemporaryChannelId)?;
+        if !channel.is_outbound {
+            return Err(APIError::CannotAbandonFunding(s!(
+                "the channel has not been funded by this node"
+            )));
+        }
+        if channel.confirmations.unwrap_or(0) > 0 {
+            return Err(APIError::CannotAbandonFunding(s!(
+                "the funding transaction has already been confirmed"
+            )));
+        }
+
+        let mut double_spend_txid = None;
+        if let Some(funding_txo) = channel.funding_txo {
+            let psbt_path =
+                disk::funding_psbt_path(&state.static_state.ldk_data_dir, funding_txo.txid);
+            if funding_broadcast && psbt_path.exists() {
+                let psbt_str =
+                    disk::read_funding_psbt(&psbt_path, unlocked_state.kv_store.cipher())?;
+                let funding_psbt = Psbt::from_str(&psbt_str).expect("valid funding PSBT");
+
+                // inputs holding RGB allocations cannot be spent without burning the assets
+                let inputs = if is_channel_rgb(&channel_id, &state.static_state.ldk_data_dir) {
+                    let vanilla_outpoints = unlocked_state
+                        .rgb_list_unspents()?
+                        .into_iter()
+                        .filter(|u| !u.utxo.colorable)
+                        .map(|u| u.utxo.outpoint.to_string())
+                        .collect::<Vec<_>>();
+                    funding_psbt
+                        .unsigned_tx
+                        .input
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, i)| vanilla_outpoints.contains(&i.previous_output.to_string()))
+                        .map(|(idx, _)| idx)
+                        .collect::<Vec<_>>()
+                } else {
+                    (0..funding_psbt.inputs.len()).collect()
+                };
+                if inputs.is_empty() {
+                    return Err(APIError::CannotAbandonFunding(s!(
+                        "the funding transaction has no input that can be double-spent"
+                    )));
+                }
+
+                let script_pubkey = Address::from_str(&unlocked_state.rgb_get_address()?)
+                    .expect("valid address")
+                    .assume_checked()
+                    .script_pubkey();
+                let unsigned_psbt = funding_double_spend_psbt(
+                    &funding_psbt,
+                    &inputs,
+                    script_pubkey,
+                    state.static_state.fee_rate,
+                )?;
+                let signed_psbt = unlocked_state.rgb_sign_psbt(unsigned_psbt.to_string())?;
+                let unlocked_state_copy = unlocked_state.clone();
+                let txid = tokio::task::spawn_blocking(move || {
+                    unlocked_state_copy.rgb_send_btc_end(signed_psbt)
+                })
+                .await
+                .unwrap()?;
+                tracing::info!(
+                    "EVENT: double-spent funding TX {} with TX {txid}",
+                    funding_txo.txid
+                );
+                double_spend_txid = Some(txid);
+            }
+            if psbt_path.exists() {
+                fs::remove_file(psbt_path)?;
+            }
+        }
+
+        unlocked_state
+            .channel_manager
+            .force_close_without_broadcasting_txn(&channel_id, &channel.counterparty.node_id)
+            .map_err(|e| APIError::FailedClosingChannel(format!("{:?}", e)))?;
+
+        *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+        tracing::debug!("RGB send lock set to false (funding abandoned)");
+
+        Ok(Json(AbandonFundingResponse { double_spend_txid }))
+    })
+    .await
+}
+
+pub(crate) async fn abandon_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AbandonPaymentRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let payment_id = hex_str_to_vec(&payload.payment_id)
+            .and_then(|id| id.try_into().ok())
+            .map(PaymentId)
+            .ok_or(APIError::InvalidPaymentId)?;
+        let payment = unlocked_state
+            .outbound_payments()
+            .get(&payment_id)
+            .cloned()
+            .ok_or(APIError::UnknownPaymentId)?;
+        if payment.status != HTLCStatus::Pending {
+            return Err(APIError::CannotAbandonPayment(format!(
+                "payment is not pending ({:?})",
+                payment.status
+            )));
+        }
+
+        // no more attempts will be made, HTLCs still in flight are released once they fail and
+        // a PaymentSent event still marks the payment as succeeded if the payee claims them
+        unlocked_state.channel_manager.abandon_payment(payment_id);
+        unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed)?;
+
+        tracing::info!("Abandoned payment {}", payload.payment_id);
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn accept_swap_offer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AcceptSwapOfferRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let mut book = unlocked_state.get_swap_offers();
+        let received = book
+            .received
+            .get(&payload.offer_id)
+            .ok_or(APIError::UnknownSwapOffer)?
+            .clone();
+        if received.offer.is_expired() {
+            return Err(APIError::ExpiredSwapOffer);
+        }
+        received
+            .offer
+            .check_qty_from(payload.qty_from)
+            .map_err(APIError::CannotAcceptSwapOffer)?;
+
+        // We are selling assets, check if we have enough
+        if let Some(from_asset) = received.offer.from_asset {
+            let max_balance = get_max_local_rgb_amount(
+                from_asset,
+                &state.static_state.ldk_data_dir,
+                unlocked_state.channel_manager.list_channels().iter(),
+            );
+            if payload.qty_from > max_balance {
+                return Err(APIError::InsufficientAssets);
+            }
+        }
+
+        // the acceptance is sent as a custom message, the maker must be connected and understand it
+        let peer = unlocked_state
+            .peer_manager
+            .peer_by_node_id(&received.maker_pubkey)
+            .ok_or_else(|| APIError::CannotAcceptSwapOffer(s!("maker is not connected")))?;
+        if !supports_feature_bit(peer.init_features.le_flags(), SWAP_OFFER_FEATURE_BIT) {
+            return Err(APIError::CannotAcceptSwapOffer(s!(
+                "maker doesn't support swap offers"
+            )));
+        }
+
+        book.pending_acceptances
+            .insert(payload.offer_id.clone(), payload.qty_from);
+        drop(book);
+
+        unlocked_state.peer_message_handler.send_message(
+            received.maker_pubkey,
+            PeerMessage::SwapOfferAccept(SwapOfferAcceptMessage {
+                offer_id: payload.offer_id.clone(),
+                qty_from: payload.qty_from,
+            }),
+        );
+        unlocked_state.peer_manager.process_events();
+
+        tracing::info!(
+            "Accepted swap offer {} of maker {}",
+            payload.offer_id,
+            received.maker_pubkey
+        );
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
 pub(crate) async fn address(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<AddressResponse>, APIError> {
@@ -1017,6 +2703,186 @@ pub(crate) async fn address(
     Ok(Json(AddressResponse { address }))
 }
 
+/// Convert one of our private channels to an announced one, by closing it cooperatively and
+/// reopening it as announced once its funds are back in the wallet
+pub(crate) async fn announce_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AnnounceChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let channel_id = check_channel_id(&payload.channel_id)?;
+        let peer_pubkey =
+            hex_str_to_compressed_pubkey(&payload.peer_pubkey).ok_or(APIError::InvalidPubkey)?;
+        let chan = unlocked_state
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .find(|c| c.channel_id == channel_id && c.counterparty.node_id == peer_pubkey)
+            .ok_or(APIError::UnknownChannelId)?;
+        if chan.is_public {
+            return Err(APIError::CannotAnnounceChannel(s!(
+                "the channel is already announced"
+            )));
+        }
+        // the channel is reopened with our funds, so the peer has to convert the ones it opened
+        if !chan.is_outbound {
+            return Err(APIError::CannotAnnounceChannel(s!(
+                "only the channels opened by the node can be converted"
+            )));
+        }
+        if !chan.is_usable {
+            return Err(APIError::CannotAnnounceChannel(s!(
+                "the channel is not usable"
+            )));
+        }
+        if unlocked_state
+            .channel_conversions()
+            .get(&channel_id)
+            .is_some_and(|c| c.status == ChannelConversionStatus::Closing)
+        {
+            return Err(APIError::CannotAnnounceChannel(s!(
+                "the channel is already being converted"
+            )));
+        }
+
+        let (asset_id, asset_amount) = match get_rgb_channel_info_optional(
+            &channel_id,
+            &state.static_state.ldk_data_dir,
+            false,
+        ) {
+            Some((rgb_info, _)) => {
+                if rgb_info.local_rgb_amount < OPENCHANNEL_MIN_RGB_AMT {
+                    return Err(APIError::CannotAnnounceChannel(format!(
+                        "the local asset amount is lower than {OPENCHANNEL_MIN_RGB_AMT}, the \
+                        minimum to reopen the channel"
+                    )));
+                }
+                (
+                    Some(rgb_info.contract_id.to_string()),
+                    Some(rgb_info.local_rgb_amount),
+                )
+            }
+            None => (None, None),
+        };
+        // the channel is closed to the address committed when opening it, if any, and the
+        // announced one commits to it as well
+        let close_address = unlocked_state.close_address(&channel_id);
+        let shutdown_script = match &close_address {
+            Some(close_address) => {
+                let address = Address::from_str(close_address)
+                    .map_err(|e| APIError::InvalidAddress(e.to_string()))?
+                    .require_network(state.static_state.network)
+                    .map_err(|e| APIError::InvalidAddress(e.to_string()))?;
+                Some(
+                    ShutdownScript::try_from(address.script_pubkey()).map_err(|_| {
+                        APIError::InvalidAddress(s!("unsupported close address type"))
+                    })?,
+                )
+            }
+            None => None,
+        };
+
+        unlocked_state.add_channel_conversion(
+            channel_id,
+            ChannelConversionData {
+                peer_pubkey,
+                capacity_sat: chan.channel_value_satoshis,
+                asset_id,
+                asset_amount,
+                fee_base_msat: chan.config.map(|c| c.forwarding_fee_base_msat),
+                fee_proportional_millionths: chan
+                    .config
+                    .map(|c| c.forwarding_fee_proportional_millionths),
+                close_address,
+                status: ChannelConversionStatus::Closing,
+                requested_at: get_current_timestamp(),
+                completed_at: None,
+                temporary_channel_id: None,
+                error: None,
+            },
+        )?;
+        if let Err(e) = unlocked_state
+            .channel_manager
+            .close_channel_with_feerate_and_script(&channel_id, &peer_pubkey, None, shutdown_script)
+        {
+            let error = format!("{:?}", e);
+            unlocked_state.update_channel_conversion(&channel_id, |c| {
+                c.status = ChannelConversionStatus::Failed;
+                c.completed_at = Some(get_current_timestamp());
+                c.error = Some(error.clone());
+            })?;
+            return Err(APIError::FailedClosingChannel(error));
+        }
+        tracing::info!("EVENT: closing channel {channel_id} to reopen it as announced");
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn approve_channel_request(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ApproveChannelRequestRequest>, APIError>,
+) -> Result<Json<OpenChannelResponse>, APIError> {
+    no_cancel(async move {
+        let response = open_requested_channel(state, &payload.request_id).await?;
+        Ok(Json(response))
+    })
+    .await
+}
+
+/// Approve the given channel request, opening the requested channel
+async fn open_requested_channel(
+    state: Arc<AppState>,
+    request_id: &str,
+) -> Result<OpenChannelResponse, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    // marking the request as approved upfront prevents it from being approved twice
+    let request_data =
+        unlocked_state.handle_channel_request(request_id, ChannelRequestStatus::Approved)?;
+    let request = request_data.request;
+    let open_channel_request = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: request_data.peer_pubkey.to_string(),
+        capacity_sat: request.capacity_sat,
+        push_msat: request.push_msat,
+        asset_amount: request.asset_amount,
+        asset_id: request.asset_id.map(|a| a.to_string()),
+        public: None,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    match do_open_channel(state.clone(), open_channel_request, false).await {
+        Ok(response) => {
+            unlocked_state.update_channel_request(
+                request_id,
+                ChannelRequestStatus::Approved,
+                Some(response.temporary_channel_id.clone()),
+            );
+            tracing::info!("Approved channel request {}", request_id);
+            Ok(response)
+        }
+        Err(e) => {
+            // leave the request pending so it can be approved again or rejected
+            unlocked_state.update_channel_request(request_id, ChannelRequestStatus::Pending, None);
+            Err(e)
+        }
+    }
+}
+
 pub(crate) async fn asset_balance(
     State(state): State<Arc<AppState>>,
     WithRejection(Json(payload), _): WithRejection<Json<AssetBalanceRequest>, APIError>,
@@ -1049,875 +2915,5137 @@ pub(crate) async fn asset_balance(
     }))
 }
 
-pub(crate) async fn backup(
+pub(crate) async fn asset_loop_in(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<BackupRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<AssetLoopInRequest>, APIError>,
+) -> Result<Json<AssetLoopInResponse>, APIError> {
     no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let _mnemonic =
-            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
 
-        do_backup(
-            &state.static_state.storage_dir_path,
-            Path::new(&payload.backup_path),
-            &payload.password,
+        let payment_hash = request_submarine_swap(
+            &state,
+            &unlocked_state,
+            &payload.peer_pubkey,
+            ASSET_SUBMARINE_SWAP_AMOUNT_SAT,
+            Some((contract_id, payload.asset_amount)),
+            None,
+            SubmarineSwapKind::LoopIn,
         )?;
 
-        Ok(Json(EmptyResponse {}))
+        Ok(Json(AssetLoopInResponse {
+            payment_hash: hex_str(&payment_hash.0),
+        }))
     })
     .await
 }
 
-pub(crate) async fn btc_balance(
+pub(crate) async fn asset_loop_out(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<BtcBalanceResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
-
-    let btc_balance = unlocked_state.rgb_get_btc_balance()?;
-
-    let vanilla = BtcBalance {
-        settled: btc_balance.vanilla.settled,
-        future: btc_balance.vanilla.future,
-        spendable: btc_balance.vanilla.spendable,
-    };
+    WithRejection(Json(payload), _): WithRejection<Json<AssetLoopOutRequest>, APIError>,
+) -> Result<Json<AssetLoopOutResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let colored = BtcBalance {
-        settled: btc_balance.colored.settled,
-        future: btc_balance.colored.future,
-        spendable: btc_balance.colored.spendable,
-    };
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+        check_swap_rgb_invoice(&payload.rgb_invoice, &contract_id, payload.asset_amount)
+            .map_err(APIError::InvalidInvoice)?;
+
+        let payment_hash = request_submarine_swap(
+            &state,
+            &unlocked_state,
+            &payload.peer_pubkey,
+            ASSET_SUBMARINE_SWAP_AMOUNT_SAT,
+            Some((contract_id, payload.asset_amount)),
+            Some(payload.rgb_invoice),
+            SubmarineSwapKind::LoopOut,
+        )?;
 
-    Ok(Json(BtcBalanceResponse { vanilla, colored }))
+        Ok(Json(AssetLoopOutResponse {
+            payment_hash: hex_str(&payment_hash.0),
+        }))
+    })
+    .await
 }
 
-pub(crate) async fn change_password(
+pub(crate) async fn backup(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ChangePasswordRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<BackupRequest>, APIError>,
+) -> Result<Json<BackupResponse>, APIError> {
     no_cancel(async move {
         let _unlocked_state = state.check_locked().await?;
 
-        check_password_strength(payload.new_password.clone())?;
-
-        let mnemonic =
-            check_password_validity(&payload.old_password, &state.static_state.storage_dir_path)?;
+        let _mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
 
-        encrypt_and_save_mnemonic(
-            payload.new_password,
-            mnemonic.to_string(),
-            &get_mnemonic_path(&state.static_state.storage_dir_path),
-        )?;
+        let backup = if let Some(backup_path) = payload.backup_path {
+            do_backup(
+                &state.static_state.storage_dir_path,
+                Path::new(&backup_path),
+                &payload.password,
+                payload.incremental,
+            )?;
+            None
+        } else {
+            let backup = export_backup(
+                &state.static_state.storage_dir_path,
+                &payload.password,
+                payload.incremental,
+            )?;
+            Some(general_purpose::STANDARD.encode(backup))
+        };
 
-        Ok(Json(EmptyResponse {}))
+        Ok(Json(BackupResponse { backup }))
     })
     .await
 }
 
-pub(crate) async fn close_channel(
+pub(crate) async fn backup_scb(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<CloseChannelRequest>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<BackupScbRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let channel_id_vec = hex_str_to_vec(&payload.channel_id);
-        if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
-            return Err(APIError::InvalidChannelID);
-        }
-        let mut channel_id = [0; 32];
-        channel_id.copy_from_slice(&channel_id_vec.unwrap());
-
-        let peer_pubkey_vec = match hex_str_to_vec(&payload.peer_pubkey) {
-            Some(peer_pubkey_vec) => peer_pubkey_vec,
-            None => return Err(APIError::InvalidPubkey),
-        };
-        let peer_pubkey = match PublicKey::from_slice(&peer_pubkey_vec) {
-            Ok(peer_pubkey) => peer_pubkey,
-            Err(_) => return Err(APIError::InvalidPubkey),
-        };
+        let _mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
 
-        if payload.force {
-            match unlocked_state
-                .channel_manager
-                .force_close_broadcasting_latest_txn(&ChannelId(channel_id), &peer_pubkey)
-            {
-                Ok(()) => tracing::info!("EVENT: initiating channel force-close"),
-                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
-            }
-        } else {
-            match unlocked_state
-                .channel_manager
-                .close_channel(&ChannelId(channel_id), &peer_pubkey)
-            {
-                Ok(()) => tracing::info!("EVENT: initiating channel close"),
-                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
-            }
-        }
+        let scb = build_scb(&unlocked_state, &state.static_state.ldk_data_dir)?;
+        let scb_json = serde_json::to_vec(&scb).map_err(|_| APIError::Unexpected)?;
+        do_scb_backup(
+            &scb_json,
+            Path::new(&payload.backup_path),
+            &payload.password,
+        )?;
 
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn connect_peer(
+pub(crate) async fn backup_seed(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ConnectPeerRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<BackupSeedRequest>, APIError>,
+) -> Result<Json<BackupSeedResponse>, APIError> {
     no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+        state.check_changing_state()?;
 
-        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
+        let mnemonic = check_password_validity(
+            &payload.password,
+            &state.static_state.storage_dir_path,
+        )
+        .inspect_err(
+            |e| tracing::warn!(target: "seed_audit", "Refused a mnemonic backup request: {e}"),
+        )?;
 
-        if let Some(peer_addr) = peer_addr {
-            connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
-                .await?;
-            disk::persist_channel_peer(
-                &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
-                &peer_pubkey,
-                &peer_addr,
-            )?;
-        } else {
-            return Err(APIError::InvalidPeerInfo(s!(
-                "incorrectly formatted peer info. Should be formatted as: `pubkey@host:port`"
-            )));
+        if let Some(words) = payload.words {
+            let verified = check_seed_words(&mnemonic, &words)?;
+            if verified {
+                tracing::info!(
+                    target: "seed_audit",
+                    "Verified {} words of the mnemonic backup",
+                    words.len()
+                );
+            } else {
+                tracing::warn!(
+                    target: "seed_audit",
+                    "The words provided don't match the mnemonic"
+                );
+            }
+            return Ok(Json(BackupSeedResponse {
+                mnemonic: None,
+                verified: Some(verified),
+            }));
         }
 
-        Ok(Json(EmptyResponse {}))
+        // the marker can only be created once, so that concurrent requests can't both reveal it
+        let revealed_path = get_seed_revealed_path(&state.static_state.storage_dir_path);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(revealed_path)
+        {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                tracing::warn!(
+                    target: "seed_audit",
+                    "Refused to reveal the mnemonic again"
+                );
+                return Err(APIError::SeedAlreadyRevealed);
+            }
+            Err(e) => return Err(e.into()),
+        }
+        tracing::warn!(target: "seed_audit", "Revealed the mnemonic");
+
+        Ok(Json(BackupSeedResponse {
+            mnemonic: Some(mnemonic.to_string()),
+            verified: None,
+        }))
     })
     .await
 }
 
-pub(crate) async fn create_utxos(
+pub(crate) async fn ban_peer(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<CreateUtxosRequest>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<BanPeerRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        unlocked_state.rgb_create_utxos(
-            payload.up_to,
-            payload.num.unwrap_or(UTXO_NUM),
-            payload.size.unwrap_or(UTXO_SIZE_SAT),
-            payload.fee_rate,
-        )?;
-        tracing::debug!("UTXO creation complete");
+        let pubkey = PublicKey::from_str(&payload.pubkey).map_err(|_| APIError::InvalidPubkey)?;
+        if payload.duration_secs == Some(0) {
+            return Err(APIError::InvalidPeerPolicy(s!(
+                "the ban duration must be greater than 0"
+            )));
+        }
+        unlocked_state.ban_peer(pubkey, payload.duration_secs)?;
 
+        tracing::info!("Banned peer {}", pubkey);
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn decode_ln_invoice(
+pub(crate) async fn btc_balance(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DecodeLNInvoiceRequest>, APIError>,
-) -> Result<Json<DecodeLNInvoiceResponse>, APIError> {
-    let _unlocked_app_state = state.get_unlocked_app_state();
-
-    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v,
-    };
+) -> Result<Json<BtcBalanceResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    Ok(Json(DecodeLNInvoiceResponse {
-        amt_msat: invoice.amount_milli_satoshis(),
-        expiry_sec: invoice.expiry_time().as_secs(),
-        timestamp: invoice
-            .timestamp()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-        asset_id: invoice.rgb_contract_id().map(|c| c.to_string()),
-        asset_amount: invoice.rgb_amount(),
-        payment_hash: hex_str(&invoice.payment_hash().to_byte_array()),
-        payment_secret: hex_str(&invoice.payment_secret().0),
-        payee_pubkey: invoice.payee_pub_key().map(|p| p.to_string()),
-        network: invoice.network().into(),
-    }))
-}
+    let btc_balance = unlocked_state.rgb_get_btc_balance()?;
 
-pub(crate) async fn decode_rgb_invoice(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DecodeRGBInvoiceRequest>, APIError>,
-) -> Result<Json<DecodeRGBInvoiceResponse>, APIError> {
-    let _unlocked_app_state = state.get_unlocked_app_state();
+    let vanilla = BtcBalance {
+        settled: btc_balance.vanilla.settled,
+        future: btc_balance.vanilla.future,
+        spendable: btc_balance.vanilla.spendable,
+    };
 
-    let invoice_data = match RgbLibInvoice::new(payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v.invoice_data(),
+    let colored = BtcBalance {
+        settled: btc_balance.colored.settled,
+        future: btc_balance.colored.future,
+        spendable: btc_balance.colored.spendable,
     };
 
-    Ok(Json(DecodeRGBInvoiceResponse {
-        recipient_id: invoice_data.recipient_id,
-        asset_iface: invoice_data.asset_iface.map(|i| i.into()),
-        asset_id: invoice_data.asset_id,
-        amount: invoice_data.amount,
-        network: invoice_data.network.into(),
-        expiration_timestamp: invoice_data.expiration_timestamp,
-        transport_endpoints: invoice_data.transport_endpoints,
-    }))
+    Ok(Json(BtcBalanceResponse { vanilla, colored }))
 }
 
-pub(crate) async fn disconnect_peer(
+pub(crate) async fn bump_close_tx(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DisconnectPeerRequest>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<BumpCloseTxRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let peer_pubkey = match PublicKey::from_str(&payload.peer_pubkey) {
-            Ok(pubkey) => pubkey,
-            Err(_e) => return Err(APIError::InvalidPubkey),
-        };
+        let channel_id = check_channel_id(&payload.channel_id)?;
 
-        //check for open channels with peer
-        for channel in unlocked_state.channel_manager.list_channels() {
-            if channel.counterparty.node_id == peer_pubkey {
-                return Err(APIError::FailedPeerDisconnection(s!(
-                    "node has an active channel with this peer, close any channels first"
-                )));
-            }
+        if payload.fee_rate < 1.0 {
+            return Err(APIError::InvalidFeeRate(s!(
+                "fee rate cannot be lower than 1 sat/vB"
+            )));
         }
 
-        disk::delete_channel_peer(
-            &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
-            payload.peer_pubkey,
-        )?;
-
-        //check the pubkey matches a valid connected peer
         if unlocked_state
-            .peer_manager
-            .peer_by_node_id(&peer_pubkey)
-            .is_none()
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.channel_id == channel_id)
         {
-            return Err(APIError::FailedPeerDisconnection(format!(
-                "Could not find peer {}",
-                peer_pubkey
+            return Err(APIError::CannotBumpCloseTx(s!(
+                "the channel has not been force-closed"
             )));
         }
 
-        unlocked_state
-            .peer_manager
-            .disconnect_by_node_id(peer_pubkey);
+        // monitors are kept after the channel is closed, until all claims are resolved
+        let (funding_txo, _) = unlocked_state
+            .chain_monitor
+            .list_monitors()
+            .into_iter()
+            .find(|(_, id)| *id == channel_id)
+            .ok_or(APIError::UnknownChannelId)?;
+        let monitor = unlocked_state
+            .chain_monitor
+            .get_monitor(funding_txo)
+            .map_err(|_| APIError::UnknownChannelId)?;
+        if !monitor
+            .channel_type_features()
+            .supports_anchors_zero_fee_htlc_tx()
+        {
+            return Err(APIError::CannotBumpCloseTx(s!(
+                "only transactions of anchor channels can be fee-bumped"
+            )));
+        }
+        if monitor.get_claimable_balances().is_empty() {
+            return Err(APIError::CannotBumpCloseTx(s!(
+                "the channel has no pending claims"
+            )));
+        }
+        drop(monitor);
+
+        // 1 sat/vB = 250 sat/kw
+        unlocked_state.bump_close_fee_rate(
+            funding_txo.into_bitcoin_outpoint(),
+            (payload.fee_rate * 250.0) as u32,
+        );
+        tracing::info!(
+            "Requested fee bump of the close transactions of channel {} to {} sat/vB",
+            payload.channel_id,
+            payload.fee_rate
+        );
 
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn get_asset_media(
+pub(crate) async fn burn_asset(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<GetAssetMediaRequest>, APIError>,
-) -> Result<Json<GetAssetMediaResponse>, APIError> {
-    let file_path = state
-        .check_unlocked()
-        .await?
-        .clone()
-        .unwrap()
-        .rgb_get_media_dir()
-        .join(payload.digest.to_lowercase());
-    if !file_path.exists() {
-        return Err(APIError::InvalidMediaDigest);
-    }
+    WithRejection(Json(payload), _): WithRejection<Json<BurnAssetRequest>, APIError>,
+) -> Result<Json<BurnAssetResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut buf_reader = BufReader::new(File::open(file_path).await?);
-    let mut file_bytes = Vec::new();
-    buf_reader.read_to_end(&mut file_bytes).await?;
-    let bytes_hex = hex_str(&file_bytes);
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
 
-    Ok(Json(GetAssetMediaResponse { bytes_hex }))
-}
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id.clone()))?;
+        if payload.amount == 0 {
+            return Err(APIError::InvalidAmount(s!("amount must be greater than 0")));
+        }
+        if payload.amount > unlocked_state.rgb_get_asset_balance(contract_id)?.spendable {
+            return Err(APIError::InsufficientAssets);
+        }
+        let fee_rate = get_fee_rate(state.static_state.fee_rate, payload.fee_rate)?;
+        let transport_endpoints = unlocked_state
+            .available_proxy_endpoints(&state.static_state.proxy_endpoints)
+            .await?;
+
+        // the assets are assigned to a P2WSH output whose witness script fails, so anyone given
+        // the script can check that they cannot be spent anymore
+        let burn_script = ScriptBuf::builder().push_opcode(OP_RETURN).into_script();
+        let script_buf = ScriptBuf::new_v0_p2wsh(&burn_script.wscript_hash());
+        let recipient_id =
+            recipient_id_from_script_buf(script_buf, state.static_state.network.into());
+        let recipient_map = map! {
+            payload.asset_id => vec![Recipient {
+                recipient_id,
+                witness_data: Some(WitnessData {
+                    amount_sat: BURN_AMOUNT_SAT,
+                    blinding: None,
+                }),
+                amount: payload.amount,
+                transport_endpoints,
+            }]
+        };
 
-pub(crate) async fn get_channel_id(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<GetChannelIdRequest>, APIError>,
-) -> Result<Json<GetChannelIdResponse>, APIError> {
-    let tmp_chan_id = check_channel_id(&payload.temporary_channel_id)?;
-    let channel_ids = state.check_unlocked().await?.clone().unwrap().channel_ids();
-    let channel_id = if let Some(channel_id) = channel_ids.get(&tmp_chan_id) {
-        channel_id.0.as_hex().to_string()
-    } else {
-        return Err(APIError::UnknownTemporaryChannelId);
-    };
+        // nobody will accept the transfer, so it's sent as a donation
+        let send_result = tokio::task::spawn_blocking(move || {
+            unlocked_state.rgb_send(recipient_map, true, fee_rate, 1)
+        })
+        .await
+        .unwrap()?;
+        tracing::info!(
+            "Burnt {} of asset {} in TX {}",
+            payload.amount,
+            contract_id,
+            send_result.txid
+        );
 
-    Ok(Json(GetChannelIdResponse { channel_id }))
+        Ok(Json(BurnAssetResponse {
+            txid: send_result.txid,
+            burn_script: hex_str(burn_script.as_bytes()),
+        }))
+    })
+    .await
 }
 
-pub(crate) async fn init(
+pub(crate) async fn cancel_fee_order(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<InitRequest>, APIError>,
-) -> Result<Json<InitResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<CancelFeeOrderRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        check_password_strength(payload.password.clone())?;
+        let channels = unlocked_state.channel_manager.list_channels();
+        let channel_ids = unlocked_state.channel_ids();
+        let order = unlocked_state.update_fee_order(&payload.order_id, |order| {
+            order.status = match order.status {
+                FeeOrderStatus::AwaitingPayment => FeeOrderStatus::Cancelled,
+                FeeOrderStatus::Paid => FeeOrderStatus::Refunded,
+                FeeOrderStatus::ChannelOpening => {
+                    // the channel could have gone away before its ID was recorded in the order
+                    let opening = channels.iter().any(|c| {
+                        let tmp_chan_id = channel_ids
+                            .iter()
+                            .find_map(|(tmp, chan)| (*chan == c.channel_id).then_some(*tmp))
+                            .unwrap_or(c.channel_id);
+                        order.temporary_channel_id.as_ref()
+                            == Some(&tmp_chan_id.0.as_hex().to_string())
+                    });
+                    if opening {
+                        return Err(APIError::CannotCancelFeeOrder(s!(
+                            "the channel is being opened"
+                        )));
+                    }
+                    FeeOrderStatus::Refunded
+                }
+                FeeOrderStatus::Completed
+                | FeeOrderStatus::Refunded
+                | FeeOrderStatus::Cancelled => {
+                    return Err(APIError::CannotCancelFeeOrder(s!("order already closed")))
+                }
+            };
+            Ok(())
+        })?;
+        unlocked_state.refund_fee_order(&order);
 
-        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
-        check_already_initialized(&mnemonic_path)?;
+        tracing::info!("Cancelled fee order {}", payload.order_id);
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
 
-        let keys = generate_keys(state.static_state.network.into());
+pub(crate) async fn cancel_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CancelInvoiceRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let mnemonic = keys.mnemonic;
+        let payment_hash = hex_str_to_vec(&payload.payment_hash)
+            .and_then(|h| h.try_into().ok())
+            .map(PaymentHash)
+            .ok_or(APIError::InvalidPaymentHash)?;
 
-        encrypt_and_save_mnemonic(payload.password, mnemonic.clone(), &mnemonic_path)?;
+        match unlocked_state.inbound_payment(&payment_hash) {
+            Some(payment) => match payment.status {
+                HTLCStatus::Pending | HTLCStatus::Claimable => {}
+                HTLCStatus::Succeeded => {
+                    return Err(APIError::CannotCancelInvoice(s!("invoice already settled")))
+                }
+                HTLCStatus::Failed => {
+                    return Err(APIError::CannotCancelInvoice(s!("invoice already failed")))
+                }
+                HTLCStatus::Expired => {
+                    return Err(APIError::CannotCancelInvoice(s!("invoice already expired")))
+                }
+            },
+            None => return Err(APIError::UnknownLNInvoice),
+        }
 
-        Ok(Json(InitResponse { mnemonic }))
+        // mark the payment as failed first so HTLCs arriving later get failed as well
+        unlocked_state.update_inbound_payment_status(payment_hash, HTLCStatus::Failed);
+        unlocked_state
+            .channel_manager
+            .fail_htlc_backwards(&payment_hash);
+
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn invoice_status(
+pub(crate) async fn change_password(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<InvoiceStatusRequest>, APIError>,
-) -> Result<Json<InvoiceStatusResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<ChangePasswordRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
 
-    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v,
-    };
+        check_password_strength(payload.new_password.clone())?;
 
-    let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
-    let status = match unlocked_state.inbound_payments().get(&payment_hash) {
-        Some(v) => match v.status {
-            HTLCStatus::Pending if invoice.is_expired() => InvoiceStatus::Expired,
-            HTLCStatus::Pending => InvoiceStatus::Pending,
-            HTLCStatus::Succeeded => InvoiceStatus::Succeeded,
-            HTLCStatus::Failed => InvoiceStatus::Failed,
-        },
-        None => return Err(APIError::UnknownLNInvoice),
-    };
+        let mnemonic =
+            check_password_validity(&payload.old_password, &state.static_state.storage_dir_path)?;
 
-    Ok(Json(InvoiceStatusResponse { status }))
+        change_storage_key_password(
+            &state.static_state.ldk_data_dir,
+            &payload.old_password,
+            &payload.new_password,
+        )?;
+
+        encrypt_and_save_mnemonic(
+            payload.new_password,
+            mnemonic.to_string(),
+            &get_mnemonic_path(&state.static_state.storage_dir_path),
+        )?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
 }
 
-pub(crate) async fn issue_asset_cfa(
+pub(crate) async fn close_channel(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetCFARequest>, APIError>,
-) -> Result<Json<IssueAssetCFAResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<CloseChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
+        let channel_id_vec = hex_str_to_vec(&payload.channel_id);
+        if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
+            return Err(APIError::InvalidChannelID);
         }
+        let mut channel_id = [0; 32];
+        channel_id.copy_from_slice(&channel_id_vec.unwrap());
 
-        let file_path = payload.file_digest.map(|d: String| {
-            unlocked_state
-                .rgb_get_media_dir()
-                .join(d.to_lowercase())
-                .to_string_lossy()
-                .to_string()
-        });
+        let peer_pubkey_vec = match hex_str_to_vec(&payload.peer_pubkey) {
+            Some(peer_pubkey_vec) => peer_pubkey_vec,
+            None => return Err(APIError::InvalidPubkey),
+        };
+        let peer_pubkey = match PublicKey::from_slice(&peer_pubkey_vec) {
+            Ok(peer_pubkey) => peer_pubkey,
+            Err(_) => return Err(APIError::InvalidPubkey),
+        };
 
-        let asset = unlocked_state.rgb_issue_asset_cfa(
-            payload.name,
-            payload.details,
-            payload.precision,
-            payload.amounts,
-            file_path,
-        )?;
+        if payload.force
+            && (payload.fee_rate.is_some()
+                || payload.conf_target.is_some()
+                || payload.close_address.is_some())
+        {
+            return Err(APIError::CannotCloseChannel(s!(
+                "fee rate, confirmation target and close address cannot be set for force-closes"
+            )));
+        }
 
-        Ok(Json(IssueAssetCFAResponse {
-            asset: asset.into(),
-        }))
+        if payload.force {
+            match unlocked_state
+                .channel_manager
+                .force_close_broadcasting_latest_txn(&ChannelId(channel_id), &peer_pubkey)
+            {
+                Ok(()) => tracing::info!("EVENT: initiating channel force-close"),
+                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
+            }
+        } else {
+            // the min fee rate is enforced by the fee estimator, the max one caps our proposal
+            let min_fee_rate = state.static_state.min_closing_fee_rate;
+            let max_fee_rate = state.static_state.max_closing_fee_rate;
+            if payload.fee_rate.is_some() && payload.conf_target.is_some() {
+                return Err(APIError::InvalidFeeRate(s!(
+                    "only one of fee_rate and conf_target can be set"
+                )));
+            }
+            // a fee rate estimated for the confirmation target is kept within the allowed range
+            let conf_target_fee_rate = if let Some(conf_target) = payload.conf_target {
+                if conf_target == 0 {
+                    return Err(APIError::InvalidFeeRate(s!("conf_target must be positive")));
+                }
+                let estimate = state
+                    .static_state
+                    .bitcoind_client
+                    .estimate_fee_rate(conf_target)
+                    .await
+                    .ok_or_else(|| {
+                        APIError::FailedClosingChannel(format!(
+                            "no fee rate estimate for a {conf_target} blocks confirmation target"
+                        ))
+                    })? as f32
+                    / 250.0;
+                let estimate = estimate.max(min_fee_rate);
+                Some(max_fee_rate.map_or(estimate, |max| estimate.min(max)))
+            } else {
+                None
+            };
+            if let Some(fee_rate) = payload.fee_rate {
+                if fee_rate < min_fee_rate {
+                    return Err(APIError::InvalidFeeRate(format!(
+                        "closing fee rate cannot be lower than {min_fee_rate} sat/vB"
+                    )));
+                }
+                if max_fee_rate.is_some_and(|max| fee_rate > max) {
+                    return Err(APIError::InvalidFeeRate(format!(
+                        "closing fee rate cannot be higher than {} sat/vB",
+                        max_fee_rate.unwrap()
+                    )));
+                }
+            }
+            let committed_address = unlocked_state.close_address(&ChannelId(channel_id));
+            let shutdown_script = if let Some(close_address) =
+                payload.close_address.or(committed_address.clone())
+            {
+                // RGB allocations can only be moved to outputs of our wallet
+                if is_channel_rgb(&ChannelId(channel_id), &state.static_state.ldk_data_dir) {
+                    return Err(APIError::CannotCloseChannel(s!(
+                        "a close address can only be set for vanilla channels"
+                    )));
+                }
+                let address = Address::from_str(&close_address)
+                    .map_err(|e| APIError::InvalidAddress(e.to_string()))?
+                    .require_network(state.static_state.network)
+                    .map_err(|e| APIError::InvalidAddress(e.to_string()))?;
+                if let Some(committed_address) = committed_address {
+                    if committed_address != address.to_string() {
+                        return Err(APIError::CannotCloseChannel(format!(
+                            "the channel can only be closed to {committed_address}, set when opening it"
+                        )));
+                    }
+                }
+                Some(
+                    ShutdownScript::try_from(address.script_pubkey()).map_err(|_| {
+                        APIError::InvalidAddress(s!("unsupported close address type"))
+                    })?,
+                )
+            } else {
+                None
+            };
+            let target_fee_rate = payload
+                .fee_rate
+                .or(conf_target_fee_rate)
+                .or(max_fee_rate.map(|max| {
+                    let estimate = state
+                        .static_state
+                        .bitcoind_client
+                        .get_est_sat_per_1000_weight(ConfirmationTarget::NonAnchorChannelFee)
+                        as f32
+                        / 250.0;
+                    estimate.min(max)
+                }));
+            // 1 sat/vB = 250 sat/kw
+            let target_feerate_sat_per_1000_weight = target_fee_rate.map(|r| (r * 250.0) as u32);
+            match unlocked_state
+                .channel_manager
+                .close_channel_with_feerate_and_script(
+                    &ChannelId(channel_id),
+                    &peer_pubkey,
+                    target_feerate_sat_per_1000_weight,
+                    shutdown_script,
+                ) {
+                Ok(()) => tracing::info!("EVENT: initiating channel close"),
+                Err(e) => return Err(APIError::FailedClosingChannel(format!("{:?}", e))),
+            }
+        }
+
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn issue_asset_nia(
+/// Account an operation against the spend limits, holding it until it's confirmed if it goes over
+/// them
+fn check_spend_limits(
+    state: &AppState,
+    unlocked_state: &Arc<UnlockedAppState>,
+    spend: SpendData,
+    operation: impl FnOnce() -> SpendOperation,
+) -> Result<SpendReservation, APIError> {
+    unlocked_state
+        .reserve_spend(
+            state.static_state.spend_limits.as_ref(),
+            spend.clone(),
+            false,
+        )
+        .map_err(|e| match e {
+            APIError::SpendLimitExceeded(reason) => {
+                tracing::info!("Holding an operation until it's confirmed: {reason}");
+                let token = unlocked_state.hold_spend(operation(), spend);
+                APIError::SpendConfirmationRequired { reason, token }
+            }
+            e => e,
+        })
+}
+
+/// Account an operation that cannot be confirmed later against the spend limits, refusing it if
+/// it goes over them
+fn charge_spend_limits(
+    state: &AppState,
+    unlocked_state: &Arc<UnlockedAppState>,
+    spend: SpendData,
+) -> Result<SpendReservation, APIError> {
+    unlocked_state.reserve_spend(state.static_state.spend_limits.as_ref(), spend, false)
+}
+
+pub(crate) async fn confirm_spend(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetNIARequest>, APIError>,
-) -> Result<Json<IssueAssetNIAResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<ConfirmSpendRequest>, APIError>,
+) -> Result<Json<ConfirmSpendResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
-        }
+        check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
 
-        let asset = unlocked_state.rgb_issue_asset_nia(
-            payload.ticker,
-            payload.name,
-            payload.precision,
-            payload.amounts,
+        let held_spend = unlocked_state.take_held_spend(&payload.token)?;
+        let reservation = unlocked_state.reserve_spend(
+            state.static_state.spend_limits.as_ref(),
+            held_spend.spend,
+            true,
         )?;
 
-        Ok(Json(IssueAssetNIAResponse {
-            asset: asset.into(),
-        }))
+        let mut response = ConfirmSpendResponse {
+            keysend: None,
+            send_asset: None,
+            send_btc: None,
+            send_payment: None,
+        };
+        match held_spend.operation {
+            SpendOperation::Keysend(request) => {
+                response.keysend = Some(do_keysend(state, request).await?)
+            }
+            SpendOperation::SendAsset(request) => {
+                response.send_asset = Some(do_send_asset(state, request).await?)
+            }
+            SpendOperation::SendBtc(request) => {
+                response.send_btc = Some(do_send_btc(state, request).await?)
+            }
+            SpendOperation::SendPayment(request) => {
+                response.send_payment = Some(do_send_payment(state, request).await?)
+            }
+            SpendOperation::SendToLnAddress(request) => {
+                response.send_payment = Some(do_send_to_ln_address(state, request).await?)
+            }
+        }
+        reservation.commit();
+
+        Ok(Json(response))
     })
     .await
 }
 
-pub(crate) async fn issue_asset_uda(
+pub(crate) async fn connect_peer(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetUDARequest>, APIError>,
-) -> Result<Json<IssueAssetUDAResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<ConnectPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
+        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
+        unlocked_state.check_peer_policy(&peer_pubkey)?;
+
+        if let Some(peer_addr) = peer_addr {
+            connect_peer_if_necessary(
+                peer_pubkey,
+                peer_addr.clone(),
+                state.static_state.tor_proxy,
+                unlocked_state.peer_manager.clone(),
+            )
+            .await?;
+            disk::persist_channel_peer(
+                &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
+                &peer_pubkey,
+                &peer_addr,
+                unlocked_state.kv_store.cipher(),
+            )?;
+        } else {
+            return Err(APIError::InvalidPeerInfo(s!(
+                "incorrectly formatted peer info. Should be formatted as: `pubkey@host:port`"
+            )));
         }
 
-        let rgb_media_dir = unlocked_state.rgb_get_media_dir();
-        let get_string_path = |d: String| {
-            rgb_media_dir
-                .join(d.to_lowercase())
-                .to_string_lossy()
-                .to_string()
-        };
-        let media_file_path = payload.media_file_digest.map(get_string_path);
-        let attachments_file_paths = payload
-            .attachments_file_digests
-            .into_iter()
-            .map(get_string_path)
-            .collect();
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
 
-        let asset = unlocked_state.rgb_issue_asset_uda(
-            payload.ticker,
-            payload.name,
-            payload.details,
-            payload.precision,
-            media_file_path,
-            attachments_file_paths,
-        )?;
+pub(crate) async fn create_api_token(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateApiTokenRequest>, APIError>,
+) -> Result<Json<CreateApiTokenResponse>, APIError> {
+    no_cancel(async move {
+        check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
 
-        Ok(Json(IssueAssetUDAResponse {
-            asset: asset.into(),
-        }))
+        if payload.name.trim().is_empty() {
+            return Err(APIError::InvalidName(s!("cannot be empty")));
+        }
+        let token = state.create_api_token(payload.name, payload.read_only)?;
+
+        Ok(Json(CreateApiTokenResponse { token }))
     })
     .await
 }
 
-pub(crate) async fn keysend(
+fn check_schedule_amount(amt_msat: u64) -> Result<(), APIError> {
+    if amt_msat < HTLC_MIN_MSAT {
+        return Err(APIError::InvalidAmount(format!(
+            "amt_msat cannot be less than {HTLC_MIN_MSAT}"
+        )));
+    }
+    Ok(())
+}
+
+fn check_schedule_interval(interval_secs: u64) -> Result<(), APIError> {
+    if interval_secs < MIN_SCHEDULE_INTERVAL_SECS {
+        return Err(APIError::InvalidSchedule(format!(
+            "interval cannot be less than {MIN_SCHEDULE_INTERVAL_SECS} seconds"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) async fn create_fee_order(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<KeysendRequest>, APIError>,
-) -> Result<Json<KeysendResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<CreateFeeOrderRequest>, APIError>,
+) -> Result<Json<CreateFeeOrderResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
-            Some(pk) => pk,
-            None => return Err(APIError::InvalidPubkey),
-        };
-
-        let amt_msat = payload.amt_msat;
-        if amt_msat < HTLC_MIN_MSAT {
-            return Err(APIError::InvalidAmount(format!(
-                "amt_msat cannot be less than {HTLC_MIN_MSAT}"
+        let request_data = unlocked_state
+            .channel_requests()
+            .remove(&payload.request_id)
+            .ok_or(APIError::UnknownChannelRequest)?;
+        if request_data.status != ChannelRequestStatus::Pending {
+            return Err(APIError::ChannelRequestAlreadyHandled);
+        }
+        if request_data.request.fee_sat == 0 {
+            return Err(APIError::CannotCreateFeeOrder(s!(
+                "the channel request has no fee"
+            )));
+        }
+        if unlocked_state
+            .fee_orders()
+            .values()
+            .any(|o| o.request_id == payload.request_id && !o.is_closed())
+        {
+            return Err(APIError::CannotCreateFeeOrder(s!(
+                "the channel request already has an open fee order"
             )));
         }
 
+        // only we know the preimage, so the fee can be refunded until the channel is ready
         let payment_preimage =
             PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
-        let payment_hash_inner = Sha256::hash(&payment_preimage.0[..]).to_byte_array();
-        let payment_id = PaymentId(payment_hash_inner);
-        let payment_hash = PaymentHash(payment_hash_inner);
-
-        let rgb_payment = match (payload.asset_id, payload.asset_amount) {
-            (Some(asset_id), Some(rgb_amount)) => {
-                let contract_id = ContractId::from_str(&asset_id)
-                    .map_err(|_| APIError::InvalidAssetID(asset_id))?;
-
-                let rgb_payment_info = RgbPaymentInfo {
-                    contract_id,
-                    amount: rgb_amount,
-                    local_rgb_amount: 0,
-                    remote_rgb_amount: 0,
-                    swap_payment: false,
-                    inbound: false,
-                };
-
-                let is_pending = true;
-                state.static_state.color_source.lock().unwrap().save_rgb_payment_info(None, &payment_hash, is_pending, rgb_payment_info);
+        let payment_hash = PaymentHash(Sha256::hash(&payment_preimage.0[..]).to_byte_array());
+        let fee_msat = request_data.request.fee_sat * 1000;
 
-                Some((contract_id, rgb_amount))
-            }
-            (None, None) => None,
-            _ => {
-                return Err(APIError::IncompleteRGBInfo);
-            }
+        let currency = match state.static_state.network {
+            Network::Bitcoin => Currency::Bitcoin,
+            Network::Testnet => Currency::BitcoinTestnet,
+            Network::Regtest => Currency::Regtest,
+            Network::Signet => Currency::Signet,
+            _ => unimplemented!("unsupported network"),
         };
+        let invoice = create_invoice_from_channelmanager_with_payment_hash(
+            &unlocked_state.channel_manager,
+            unlocked_state.keys_manager.clone(),
+            state.static_state.logger.clone(),
+            currency,
+            Some(fee_msat),
+            format!("Upfront fee for channel request {}", payload.request_id),
+            payload.expiry_sec.unwrap_or(FEE_ORDER_INVOICE_EXPIRY_SECS),
+            payment_hash,
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?;
 
-        let route_params = RouteParameters::from_payment_params_and_value(
-            PaymentParameters::for_keysend(dest_pubkey, 40, false),
-            amt_msat,
-            rgb_payment,
-        );
-        unlocked_state.add_outbound_payment(
-            payment_id,
+        unlocked_state.add_inbound_payment(
+            payment_hash,
             PaymentInfo {
                 preimage: None,
-                secret: None,
+                secret: Some(*invoice.payment_secret()),
                 status: HTLCStatus::Pending,
-                amt_msat: Some(amt_msat),
+                amt_msat: Some(fee_msat),
+                custom_records: vec![],
+                retry_attempts: None,
+                retry_timeout_secs: None,
+                failed_attempts: 0,
+                expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                asset_amount: None,
+                keysend: false,
+                claim_deadline: None,
             },
         );
-        let status = match unlocked_state
-            .channel_manager
-            .send_spontaneous_payment_with_retry(
-                Some(payment_preimage),
-                RecipientOnionFields::spontaneous_empty(),
-                payment_id,
-                route_params,
-                Retry::Timeout(Duration::from_secs(10)),
-            ) {
-            Ok(_payment_hash) => {
-                tracing::info!(
-                    "EVENT: initiated sending {} msats to {}",
-                    amt_msat,
-                    dest_pubkey
-                );
-                HTLCStatus::Pending
-            }
-            Err(e) => {
-                tracing::error!("ERROR: failed to send payment: {:?}", e);
-                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
-                HTLCStatus::Failed
-            }
-        };
 
-        Ok(Json(KeysendResponse {
+        let order_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        unlocked_state.add_fee_order(
+            order_id.clone(),
+            FeeOrderData {
+                request_id: payload.request_id.clone(),
+                fee_msat,
+                payment_hash,
+                payment_preimage,
+                invoice: invoice.to_string(),
+                status: FeeOrderStatus::AwaitingPayment,
+                created_at: get_current_timestamp(),
+                temporary_channel_id: None,
+            },
+        )?;
+
+        tracing::info!(
+            "Created fee order {order_id} for channel request {}",
+            payload.request_id
+        );
+        Ok(Json(CreateFeeOrderResponse {
+            order_id,
+            invoice: invoice.to_string(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateScheduleRequest>, APIError>,
+) -> Result<Json<CreateScheduleResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        match payload.target_type {
+            ScheduleTargetType::Keysend => {
+                if hex_str_to_compressed_pubkey(&payload.target).is_none() {
+                    return Err(APIError::InvalidPubkey);
+                }
+            }
+            ScheduleTargetType::LnAddress => {
+                parse_ln_address(&payload.target)?;
+            }
+        }
+
+        check_schedule_amount(payload.amt_msat)?;
+        match (&payload.asset_id, payload.asset_amount) {
+            (Some(asset_id), Some(_)) => {
+                ContractId::from_str(asset_id)
+                    .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?;
+            }
+            (None, None) => {}
+            _ => {
+                return Err(APIError::IncompleteRGBInfo);
+            }
+        }
+        check_schedule_interval(payload.interval_secs)?;
+
+        let now = get_current_timestamp();
+        let schedule_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        unlocked_state.add_schedule(
+            schedule_id.clone(),
+            ScheduleData {
+                target_type: payload.target_type,
+                target: payload.target,
+                amt_msat: payload.amt_msat,
+                asset_id: payload.asset_id,
+                asset_amount: payload.asset_amount,
+                interval_secs: payload.interval_secs,
+                next_run_at: payload.start_at.unwrap_or(now),
+                enabled: true,
+                created_at: now,
+                runs: vec![],
+            },
+        )?;
+
+        tracing::info!("Created schedule {schedule_id}");
+        Ok(Json(CreateScheduleResponse { schedule_id }))
+    })
+    .await
+}
+
+pub(crate) async fn create_utxos(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateUtxosRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        unlocked_state.rgb_create_utxos(
+            payload.up_to,
+            payload.num.unwrap_or(UTXO_NUM),
+            payload.size.unwrap_or(state.static_state.utxo_size_sat),
+            payload.fee_rate,
+        )?;
+        tracing::debug!("UTXO creation complete");
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn decode_ln_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodeLNInvoiceRequest>, APIError>,
+) -> Result<Json<DecodeLNInvoiceResponse>, APIError> {
+    let _unlocked_app_state = state.get_unlocked_app_state();
+
+    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
+        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+        Ok(v) => v,
+    };
+
+    Ok(Json(DecodeLNInvoiceResponse {
+        amt_msat: invoice.amount_milli_satoshis(),
+        expiry_sec: invoice.expiry_time().as_secs(),
+        timestamp: invoice
+            .timestamp()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        asset_id: invoice.rgb_contract_id().map(|c| c.to_string()),
+        asset_amount: invoice.rgb_amount(),
+        payment_hash: hex_str(&invoice.payment_hash().to_byte_array()),
+        payment_secret: hex_str(&invoice.payment_secret().0),
+        payee_pubkey: invoice.payee_pub_key().map(|p| p.to_string()),
+        network: invoice.network().into(),
+    }))
+}
+
+pub(crate) async fn decode_rgb_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodeRGBInvoiceRequest>, APIError>,
+) -> Result<Json<DecodeRGBInvoiceResponse>, APIError> {
+    let _unlocked_app_state = state.get_unlocked_app_state();
+
+    let invoice_data = match RgbLibInvoice::new(payload.invoice) {
+        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+        Ok(v) => v.invoice_data(),
+    };
+
+    Ok(Json(DecodeRGBInvoiceResponse {
+        recipient_id: invoice_data.recipient_id,
+        asset_iface: invoice_data.asset_iface.map(|i| i.into()),
+        asset_id: invoice_data.asset_id,
+        amount: invoice_data.amount,
+        network: invoice_data.network.into(),
+        expiration_timestamp: invoice_data.expiration_timestamp,
+        transport_endpoints: invoice_data.transport_endpoints,
+    }))
+}
+
+pub(crate) async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DeleteScheduleRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        unlocked_state.remove_schedule(&payload.schedule_id)?;
+
+        tracing::info!("Deleted schedule {}", payload.schedule_id);
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn disconnect_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DisconnectPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let peer_pubkey = match PublicKey::from_str(&payload.peer_pubkey) {
+            Ok(pubkey) => pubkey,
+            Err(_e) => return Err(APIError::InvalidPubkey),
+        };
+
+        //check for open channels with peer
+        for channel in unlocked_state.channel_manager.list_channels() {
+            if channel.counterparty.node_id == peer_pubkey {
+                return Err(APIError::FailedPeerDisconnection(s!(
+                    "node has an active channel with this peer, close any channels first"
+                )));
+            }
+        }
+
+        disk::delete_channel_peer(
+            &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
+            payload.peer_pubkey,
+            unlocked_state.kv_store.cipher(),
+        )?;
+
+        //check the pubkey matches a valid connected peer
+        if unlocked_state
+            .peer_manager
+            .peer_by_node_id(&peer_pubkey)
+            .is_none()
+        {
+            return Err(APIError::FailedPeerDisconnection(format!(
+                "Could not find peer {}",
+                peer_pubkey
+            )));
+        }
+
+        unlocked_state
+            .peer_manager
+            .disconnect_by_node_id(peer_pubkey);
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn execute_fee_order(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ExecuteFeeOrderRequest>, APIError>,
+) -> Result<Json<OpenChannelResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        // moving the order on upfront prevents it from being executed twice
+        let order = unlocked_state.update_fee_order(&payload.order_id, |order| {
+            match order.status {
+                FeeOrderStatus::Paid => {}
+                FeeOrderStatus::AwaitingPayment => {
+                    return Err(APIError::CannotExecuteFeeOrder(s!(
+                        "the fee has not been paid yet"
+                    )))
+                }
+                _ => {
+                    return Err(APIError::CannotExecuteFeeOrder(s!(
+                        "order already executed"
+                    )))
+                }
+            }
+            order.status = FeeOrderStatus::ChannelOpening;
+            Ok(())
+        })?;
+
+        match open_requested_channel(state.clone(), &order.request_id).await {
+            Ok(response) => {
+                unlocked_state.update_fee_order(&payload.order_id, |order| {
+                    order.temporary_channel_id = Some(response.temporary_channel_id.clone());
+                    Ok(())
+                })?;
+                tracing::info!("Executed fee order {}", payload.order_id);
+                Ok(Json(response))
+            }
+            Err(e) => {
+                let order = unlocked_state.update_fee_order(&payload.order_id, |order| {
+                    order.status = FeeOrderStatus::Refunded;
+                    Ok(())
+                })?;
+                unlocked_state.refund_fee_order(&order);
+                Err(e)
+            }
+        }
+    })
+    .await
+}
+
+pub(crate) async fn export_contract(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ExportContractRequest>, APIError>,
+) -> Result<Json<ExportContractResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let contract_id = ContractId::from_str(&payload.asset_id)
+        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+
+    let unlocked_state_copy = unlocked_state.clone();
+    let contract =
+        tokio::task::spawn_blocking(move || unlocked_state_copy.rgb_export_contract(contract_id))
+            .await
+            .unwrap()?;
+
+    let file = tempfile::NamedTempFile::new_in(&state.static_state.ldk_data_dir)?;
+    contract.save_file(file.path()).map_err(|e| {
+        tracing::error!("cannot save contract: {e}");
+        APIError::Unexpected
+    })?;
+    let contract = general_purpose::STANDARD.encode(fs::read(file.path())?);
+
+    Ok(Json(ExportContractResponse { contract }))
+}
+
+pub(crate) async fn fail_intercept(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FailInterceptRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let intercept_id = hex_str_to_vec(&payload.intercept_id)
+            .and_then(|id| id.try_into().ok())
+            .map(InterceptId)
+            .ok_or(APIError::InvalidInterceptId)?;
+
+        let held_intercept = unlocked_state
+            .get_held_intercepts()
+            .remove(&intercept_id)
+            .ok_or(APIError::UnknownInterceptId)?;
+
+        unlocked_state
+            .channel_manager
+            .fail_intercepted_htlc(intercept_id)
+            .map_err(|_| APIError::UnknownInterceptId)?;
+        tracing::info!(
+            "EVENT: manually failed intercepted HTLC with payment hash {}",
+            held_intercept.payment_hash
+        );
+
+        if unlocked_state
+            .get_taker_swaps()
+            .swaps
+            .contains_key(&held_intercept.payment_hash)
+        {
+            let _ = unlocked_state
+                .update_taker_swap_status(&held_intercept.payment_hash, SwapStatus::Failed);
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn fee_report(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FeeReportResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut channels: Vec<ChannelFeeReport> = unlocked_state
+        .fee_report()
+        .into_iter()
+        .map(|(channel_id, c)| ChannelFeeReport {
+            channel_id,
+            peer_pubkey: c.peer_pubkey.map(|p| p.to_string()),
+            forwards: c.forwards,
+            fee_earned_msat: c.fee_earned_msat,
+            skimmed_forwards: c.skimmed_forwards,
+            skimmed_fee_msat: c.skimmed_fee_msat,
+            counterparty_skimmed_payments: c.counterparty_skimmed_payments,
+            counterparty_skimmed_fee_msat: c.counterparty_skimmed_fee_msat,
+        })
+        .collect();
+    channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+    Ok(Json(FeeReportResponse {
+        total_fee_earned_msat: channels.iter().map(|c| c.fee_earned_msat).sum(),
+        total_skimmed_fee_msat: channels.iter().map(|c| c.skimmed_fee_msat).sum(),
+        total_counterparty_skimmed_fee_msat: channels
+            .iter()
+            .map(|c| c.counterparty_skimmed_fee_msat)
+            .sum(),
+        channels,
+    }))
+}
+
+pub(crate) async fn forwarding_history(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ForwardingHistoryResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let history = unlocked_state.forwarding_history();
+
+    // fees are accounted to the outbound channel, as in the fee report
+    let mut stats: HashMap<ChannelId, ChannelForwardingStats> = HashMap::new();
+    let new_stats = |channel_id: &ChannelId| ChannelForwardingStats {
+        channel_id: channel_id.0.as_hex().to_string(),
+        inbound_forwards: 0,
+        outbound_forwards: 0,
+        inbound_amount_msat: 0,
+        outbound_amount_msat: 0,
+        fee_earned_msat: 0,
+        asset_id: None,
+        inbound_asset_amount: 0,
+        outbound_asset_amount: 0,
+    };
+    for forward in &history {
+        let inbound = stats
+            .entry(forward.prev_channel_id)
+            .or_insert_with(|| new_stats(&forward.prev_channel_id));
+        inbound.inbound_forwards += 1;
+        inbound.inbound_amount_msat += forward.inbound_amount_msat().unwrap_or(0);
+        if let Some(asset_id) = &forward.inbound_asset_id {
+            inbound.asset_id = Some(asset_id.clone());
+            inbound.inbound_asset_amount += forward.inbound_asset_amount.unwrap_or(0);
+        }
+        let outbound = stats
+            .entry(forward.next_channel_id)
+            .or_insert_with(|| new_stats(&forward.next_channel_id));
+        outbound.outbound_forwards += 1;
+        outbound.outbound_amount_msat += forward.outbound_amount_msat.unwrap_or(0);
+        outbound.fee_earned_msat += forward.fee_earned_msat.unwrap_or(0);
+        if let Some(asset_id) = &forward.outbound_asset_id {
+            outbound.asset_id = Some(asset_id.clone());
+            outbound.outbound_asset_amount += forward.outbound_asset_amount.unwrap_or(0);
+        }
+    }
+    let mut channels: Vec<ChannelForwardingStats> = stats.into_values().collect();
+    channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+    let forwards = history
+        .into_iter()
+        .map(|f| Forward {
+            prev_channel_id: f.prev_channel_id.0.as_hex().to_string(),
+            next_channel_id: f.next_channel_id.0.as_hex().to_string(),
+            payment_hash: hex_str(&f.payment_hash.0),
+            inbound_amount_msat: f.inbound_amount_msat(),
+            outbound_amount_msat: f.outbound_amount_msat,
+            fee_earned_msat: f.fee_earned_msat,
+            skimmed_fee_msat: f.skimmed_fee_msat,
+            inbound_asset_id: f.inbound_asset_id,
+            inbound_asset_amount: f.inbound_asset_amount,
+            outbound_asset_id: f.outbound_asset_id,
+            outbound_asset_amount: f.outbound_asset_amount,
+            forwarded_at: f.forwarded_at,
+        })
+        .collect();
+
+    Ok(Json(ForwardingHistoryResponse { forwards, channels }))
+}
+
+pub(crate) async fn get_asset_media(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetAssetMediaRequest>, APIError>,
+) -> Result<Json<GetAssetMediaResponse>, APIError> {
+    let file_path = state
+        .check_unlocked()
+        .await?
+        .clone()
+        .unwrap()
+        .rgb_get_media_dir()
+        .join(payload.digest.to_lowercase());
+    if !file_path.exists() {
+        return Err(APIError::InvalidMediaDigest);
+    }
+
+    let mut buf_reader = BufReader::new(File::open(file_path).await?);
+    let mut file_bytes = Vec::new();
+    buf_reader.read_to_end(&mut file_bytes).await?;
+    let bytes_hex = hex_str(&file_bytes);
+
+    Ok(Json(GetAssetMediaResponse { bytes_hex }))
+}
+
+pub(crate) async fn get_channel_id(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetChannelIdRequest>, APIError>,
+) -> Result<Json<GetChannelIdResponse>, APIError> {
+    let tmp_chan_id = check_channel_id(&payload.temporary_channel_id)?;
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    let snapshot = unlocked_state.snapshot().await;
+    let channel_id = if let Some(channel_id) = snapshot.channel_ids.get(&tmp_chan_id) {
+        channel_id.0.as_hex().to_string()
+    } else {
+        return Err(APIError::UnknownTemporaryChannelId);
+    };
+
+    Ok(Json(GetChannelIdResponse { channel_id }))
+}
+
+pub(crate) async fn get_peer_policy(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PeerPolicyResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let now = get_current_timestamp();
+    let peer_policy = unlocked_state.get_peer_policy();
+    let bans = peer_policy
+        .bans
+        .iter()
+        .filter(|b| b.expires_at.map_or(true, |e| e > now))
+        .map(|b| BannedPeer {
+            pubkey: b.pubkey.to_string(),
+            expires_at: b.expires_at,
+        })
+        .collect();
+
+    Ok(Json(PeerPolicyResponse {
+        allowed: peer_policy.allowed.iter().map(|p| p.to_string()).collect(),
+        bans,
+        max_peers: peer_policy.max_peers,
+    }))
+}
+
+pub(crate) async fn get_swap(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetSwapRequest>, APIError>,
+) -> Result<Json<GetSwapResponse>, APIError> {
+    let payment_hash = hex_str_to_vec(&payload.payment_hash)
+        .and_then(|h| h.try_into().ok())
+        .map(PaymentHash)
+        .ok_or(APIError::InvalidPaymentHash)?;
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    let snapshot = unlocked_state.snapshot().await;
+
+    let (swap_data, taker) = if let Some(swap_data) = snapshot.taker_swaps.get(&payment_hash) {
+        (swap_data, true)
+    } else if let Some(swap_data) = snapshot.maker_swaps.get(&payment_hash) {
+        (swap_data, false)
+    } else {
+        return Err(APIError::UnknownSwap);
+    };
+
+    Ok(Json(GetSwapResponse {
+        swap: map_swap(&unlocked_state, &payment_hash, swap_data, taker),
+        taker,
+    }))
+}
+
+pub(crate) async fn import_contract(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ImportContractRequest>, APIError>,
+) -> Result<Json<ImportContractResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let contract_bytes = general_purpose::STANDARD
+            .decode(&payload.contract)
+            .map_err(|_| APIError::InvalidContract(s!("not valid base64")))?;
+        let file = tempfile::NamedTempFile::new_in(&state.static_state.ldk_data_dir)?;
+        fs::write(file.path(), contract_bytes)?;
+        let contract = Contract::load_file(file.path()).map_err(|e| {
+            tracing::error!("cannot parse contract: {e}");
+            APIError::InvalidContract(s!("cannot parse the contract"))
+        })?;
+
+        let contract_id = contract.contract_id();
+        let schema = RgbLibAssetSchema::from_schema_id(contract.schema_id().to_string())
+            .map_err(|_| APIError::InvalidContract(s!("unsupported schema")))?;
+        if unlocked_state
+            .rgb_wallet_wrapper
+            .get_asset_iface(contract_id)
+            .is_ok()
+        {
+            return Err(APIError::CannotImportContract(s!(
+                "the asset is already known"
+            )));
+        }
+
+        // the contract gets validated while being imported
+        let unlocked_state_copy = unlocked_state.clone();
+        tokio::task::spawn_blocking(move || {
+            unlocked_state_copy.rgb_save_new_asset(&schema, contract_id, Some(contract))
+        })
+        .await
+        .unwrap()
+        .map_err(|e| APIError::CannotImportContract(e.to_string()))?;
+        tracing::info!("Imported contract {contract_id}");
+
+        Ok(Json(ImportContractResponse {
+            asset_id: contract_id.to_string(),
+            schema: schema.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn init(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InitRequest>, APIError>,
+) -> Result<Json<InitResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
+
+        check_password_strength(payload.password.clone())?;
+
+        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
+        check_already_initialized(&mnemonic_path)?;
+
+        let keys = generate_keys(state.static_state.network.into());
+
+        let mnemonic = keys.mnemonic;
+
+        encrypt_and_save_mnemonic(payload.password, mnemonic.clone(), &mnemonic_path)?;
+
+        Ok(Json(InitResponse { mnemonic }))
+    })
+    .await
+}
+
+pub(crate) async fn inspect_consignment(
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<Json<InspectConsignmentResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut file_bytes = None;
+    let mut txid = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| APIError::InvalidConsignment(e.body_text()))?
+    {
+        match field.name() {
+            Some("file") => {
+                file_bytes = Some(field.bytes().await.map_err(|_| APIError::Unexpected)?)
+            }
+            Some("txid") => txid = Some(field.text().await.map_err(|_| APIError::Unexpected)?),
+            _ => {}
+        }
+    }
+
+    let consignments = match (file_bytes, txid) {
+        (Some(file_bytes), None) => {
+            // the file could be anything, it's only parsed
+            let file = tempfile::NamedTempFile::new_in(&state.static_state.ldk_data_dir)?;
+            fs::write(file.path(), &file_bytes)?;
+            let consignment = load_consignment(file.path())?;
+            vec![describe_consignment(
+                &consignment,
+                None,
+                None,
+                ConsignmentValidation::Unvalidated,
+            )?]
+        }
+        (None, Some(txid)) => {
+            Txid::from_str(&txid).map_err(|_| APIError::InvalidTxid)?;
+            let mut consignments = vec![];
+
+            // consignments we sent with the TX
+            let transfers_dir = unlocked_state.rgb_get_transfers_dir().join(&txid);
+            if transfers_dir.is_dir() {
+                for entry in fs::read_dir(&transfers_dir)? {
+                    let asset_transfer_dir = entry?.path();
+                    let asset_id = match asset_transfer_dir.file_name().and_then(|n| n.to_str()) {
+                        Some(name)
+                            if asset_transfer_dir.is_dir()
+                                && ContractId::from_str(name).is_ok() =>
+                        {
+                            name.to_string()
+                        }
+                        _ => continue,
+                    };
+                    for transfer in unlocked_state.rgb_list_transfers(asset_id)? {
+                        if !matches!(transfer.kind, rgb_lib::TransferKind::Send)
+                            || transfer.txid.as_ref() != Some(&txid)
+                        {
+                            continue;
+                        }
+                        let recipient_id = transfer
+                            .recipient_id
+                            .clone()
+                            .expect("send transfer has a recipient");
+                        let path = unlocked_state
+                            .rgb_get_send_consignment_path(&asset_transfer_dir, &recipient_id);
+                        if !path.exists() {
+                            continue;
+                        }
+                        consignments.push(describe_consignment(
+                            &load_consignment(&path)?,
+                            Some(txid.clone()),
+                            Some(recipient_id),
+                            consignment_validation(&transfer.status),
+                        )?);
+                    }
+                }
+            }
+
+            // funding consignment received from the peer opening a channel
+            let consignment_path = state
+                .static_state
+                .color_source
+                .join(format!("consignment_{txid}"));
+            if consignment_path.exists() {
+                let consignment = load_consignment(&consignment_path)?;
+                let transfer = unlocked_state
+                    .rgb_list_transfers(consignment.contract_id().to_string())
+                    .ok()
+                    .and_then(|t| t.into_iter().find(|t| t.txid.as_ref() == Some(&txid)));
+                // the channel is only accepted once its funding consignment has been validated
+                let funded_channel = unlocked_state
+                    .channel_manager
+                    .list_channels()
+                    .iter()
+                    .any(|c| c.funding_txo.is_some_and(|o| o.txid.to_string() == txid));
+                let validation = match transfer {
+                    Some(transfer) => consignment_validation(&transfer.status),
+                    None if funded_channel => ConsignmentValidation::Valid,
+                    None => ConsignmentValidation::Unvalidated,
+                };
+                consignments.push(describe_consignment(
+                    &consignment,
+                    Some(txid.clone()),
+                    None,
+                    validation,
+                )?);
+            }
+
+            if consignments.is_empty() {
+                return Err(APIError::UnknownConsignment);
+            }
+            consignments
+        }
+        _ => {
+            return Err(APIError::InvalidConsignment(s!(
+                "exactly one of file and txid must be given"
+            )))
+        }
+    };
+
+    Ok(Json(InspectConsignmentResponse { consignments }))
+}
+
+/// Validation of a consignment, as assessed by the RGB wallet for the transfer it belongs to
+fn consignment_validation(status: &rgb_lib::TransferStatus) -> ConsignmentValidation {
+    match status {
+        rgb_lib::TransferStatus::WaitingCounterparty
+        | rgb_lib::TransferStatus::WaitingConfirmations => ConsignmentValidation::Pending,
+        rgb_lib::TransferStatus::Settled => ConsignmentValidation::Valid,
+        rgb_lib::TransferStatus::Failed => ConsignmentValidation::Invalid,
+    }
+}
+
+pub(crate) async fn invoice_status(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InvoiceStatusRequest>, APIError>,
+) -> Result<Json<InvoiceStatusResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
+        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+        Ok(v) => v,
+    };
+
+    let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
+    let status = match unlocked_state.inbound_payment(&payment_hash) {
+        Some(v) => match v.status {
+            HTLCStatus::Pending if invoice.is_expired() => InvoiceStatus::Expired,
+            HTLCStatus::Pending => InvoiceStatus::Pending,
+            HTLCStatus::Claimable => InvoiceStatus::Claimable,
+            HTLCStatus::Succeeded => InvoiceStatus::Succeeded,
+            HTLCStatus::Failed => InvoiceStatus::Failed,
+            HTLCStatus::Expired => InvoiceStatus::Expired,
+        },
+        None => return Err(APIError::UnknownLNInvoice),
+    };
+
+    Ok(Json(InvoiceStatusResponse { status }))
+}
+
+pub(crate) async fn issue_asset_cfa(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetCFARequest>, APIError>,
+) -> Result<Json<IssueAssetCFAResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let file_path = payload.file_digest.map(|d: String| {
+            unlocked_state
+                .rgb_get_media_dir()
+                .join(d.to_lowercase())
+                .to_string_lossy()
+                .to_string()
+        });
+
+        let asset = unlocked_state.rgb_issue_asset_cfa(
+            payload.name,
+            payload.details,
+            payload.precision,
+            payload.amounts,
+            file_path,
+        )?;
+
+        Ok(Json(IssueAssetCFAResponse {
+            asset: asset.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn issue_asset_nia(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetNIARequest>, APIError>,
+) -> Result<Json<IssueAssetNIAResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let asset = unlocked_state.rgb_issue_asset_nia(
+            payload.ticker,
+            payload.name,
+            payload.precision,
+            payload.amounts,
+        )?;
+
+        Ok(Json(IssueAssetNIAResponse {
+            asset: asset.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn issue_asset_uda(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetUDARequest>, APIError>,
+) -> Result<Json<IssueAssetUDAResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        // media must have been uploaded with /postassetmedia first
+        let rgb_media_dir = unlocked_state.rgb_get_media_dir();
+        let get_string_path = |d: String| {
+            let file_path = rgb_media_dir.join(d.to_lowercase());
+            if !file_path.is_file() {
+                return Err(APIError::InvalidMediaDigest);
+            }
+            Ok(file_path.to_string_lossy().to_string())
+        };
+        let media_file_path = payload.media_file_digest.map(get_string_path).transpose()?;
+        let attachments_file_paths = payload
+            .attachments_file_digests
+            .into_iter()
+            .map(get_string_path)
+            .collect::<Result<Vec<String>, APIError>>()?;
+
+        let asset = unlocked_state.rgb_issue_asset_uda(
+            payload.ticker,
+            payload.name,
+            payload.details,
+            payload.precision,
+            media_file_path,
+            attachments_file_paths,
+        )?;
+
+        Ok(Json(IssueAssetUDAResponse {
+            asset: asset.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn keysend(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<KeysendRequest>, APIError>,
+) -> Result<Json<KeysendResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let spend = SpendData::from_msat(
+            payload.amt_msat,
+            payload.asset_id.clone(),
+            payload.asset_amount,
+        );
+        let reservation = check_spend_limits(&state, &unlocked_state, spend, || {
+            SpendOperation::Keysend(payload.clone())
+        })?;
+
+        let response = do_keysend(state, payload).await?;
+        reservation.commit();
+
+        Ok(Json(response))
+    })
+    .await
+}
+
+/// Send a spontaneous payment, shared by the keysend API and the payment scheduler
+pub(crate) async fn do_keysend(
+    state: Arc<AppState>,
+    payload: KeysendRequest,
+) -> Result<KeysendResponse, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
+        Some(pk) => pk,
+        None => return Err(APIError::InvalidPubkey),
+    };
+
+    let amt_msat = payload.amt_msat;
+    if amt_msat < HTLC_MIN_MSAT {
+        return Err(APIError::InvalidAmount(format!(
+            "amt_msat cannot be less than {HTLC_MIN_MSAT}"
+        )));
+    }
+
+    let payment_preimage = PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
+    let payment_hash_inner = Sha256::hash(&payment_preimage.0[..]).to_byte_array();
+    let payment_id = PaymentId(payment_hash_inner);
+    let payment_hash = PaymentHash(payment_hash_inner);
+
+    let rgb_payment = match (payload.asset_id, payload.asset_amount) {
+        (Some(asset_id), Some(rgb_amount)) => {
+            let contract_id =
+                ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?;
+
+            let rgb_payment_info = RgbPaymentInfo {
+                contract_id,
+                amount: rgb_amount,
+                local_rgb_amount: 0,
+                remote_rgb_amount: 0,
+                swap_payment: false,
+                inbound: false,
+            };
+
+            let is_pending = true;
+            state
+                .static_state
+                .color_source
+                .lock()
+                .unwrap()
+                .save_rgb_payment_info(None, &payment_hash, is_pending, rgb_payment_info);
+
+            Some((contract_id, rgb_amount))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(APIError::IncompleteRGBInfo);
+        }
+    };
+
+    let mut custom_records = vec![];
+    for record in payload.custom_records.unwrap_or_default() {
+        let value = hex_str_to_vec(&record.value).ok_or(APIError::InvalidTlvType(format!(
+            "value of TLV record {} is not valid hex",
+            record.tlv_type
+        )))?;
+        custom_records.push((record.tlv_type, value));
+    }
+    custom_records.sort_by_key(|(tlv_type, _)| *tlv_type);
+    // types must be unique, in the custom range (>= 2^16) and not the keysend preimage one
+    let recipient_onion = RecipientOnionFields::spontaneous_empty()
+        .with_custom_tlvs(custom_records.clone())
+        .map_err(|_| {
+            APIError::InvalidTlvType(s!(
+                "custom TLV types must be unique and in the custom range"
+            ))
+        })?;
+
+    let retry = get_payment_retry(
+        state.static_state.payment_retry,
+        payload.retry_attempts,
+        payload.retry_timeout_secs,
+    )?;
+    let (retry_attempts, retry_timeout_secs) = retry_details(retry);
+
+    let route_params = RouteParameters::from_payment_params_and_value(
+        PaymentParameters::for_keysend(dest_pubkey, 40, false),
+        amt_msat,
+        rgb_payment,
+    );
+    unlocked_state.add_outbound_payment(
+        payment_id,
+        PaymentInfo {
+            preimage: None,
+            secret: None,
+            status: HTLCStatus::Pending,
+            amt_msat: Some(amt_msat),
+            custom_records: custom_records.clone(),
+            retry_attempts,
+            retry_timeout_secs,
+            failed_attempts: 0,
+            expires_at: None,
+            asset_amount: None,
+            keysend: true,
+            claim_deadline: None,
+        },
+    )?;
+    let status = match unlocked_state
+        .channel_manager
+        .send_spontaneous_payment_with_retry(
+            Some(payment_preimage),
+            recipient_onion,
+            payment_id,
+            route_params,
+            retry,
+        ) {
+        Ok(_payment_hash) => {
+            tracing::info!(
+                "EVENT: initiated sending {} msats to {}",
+                amt_msat,
+                dest_pubkey
+            );
+            HTLCStatus::Pending
+        }
+        Err(e) => {
+            tracing::error!("ERROR: failed to send payment: {:?}", e);
+            unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed)?;
+            HTLCStatus::Failed
+        }
+    };
+
+    Ok(KeysendResponse {
+        payment_hash: hex_str(&payment_hash.0),
+        payment_preimage: hex_str(&payment_preimage.0),
+        status,
+    })
+}
+
+pub(crate) async fn list_api_tokens(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListApiTokensResponse>, APIError> {
+    let mut tokens: Vec<ApiToken> = state
+        .get_api_tokens()
+        .tokens
+        .iter()
+        .map(|(name, t)| ApiToken {
+            name: name.clone(),
+            read_only: t.read_only,
+            created_at: t.created_at,
+        })
+        .collect();
+    tokens.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Json(ListApiTokensResponse { tokens }))
+}
+
+pub(crate) async fn list_assets(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListAssetsRequest>, APIError>,
+) -> Result<Json<ListAssetsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let rgb_assets = unlocked_state.rgb_list_assets(
+        payload
+            .filter_asset_schemas
+            .into_iter()
+            .map(|s| s.into())
+            .collect(),
+    )?;
+
+    let nia = rgb_assets
+        .nia
+        .map(|assets| assets.into_iter().map(|a| a.into()).collect());
+    let uda = rgb_assets
+        .uda
+        .map(|assets| assets.into_iter().map(|a| a.into()).collect());
+    let cfa = rgb_assets
+        .cfa
+        .map(|assets| assets.into_iter().map(|a| a.into()).collect());
+
+    Ok(Json(ListAssetsResponse { nia, uda, cfa }))
+}
+
+pub(crate) async fn list_backups(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListBackupsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut backups: Vec<ScheduledBackup> = unlocked_state
+        .scheduled_backups()
+        .into_iter()
+        .map(|(name, b)| ScheduledBackup {
+            name,
+            created_at: b.created_at,
+            size: b.size,
+            location: b.location,
+        })
+        .collect();
+    backups.sort_by_key(|b| b.created_at);
+
+    Ok(Json(ListBackupsResponse { backups }))
+}
+
+pub(crate) async fn list_channel_conversions(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListChannelConversionsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut conversions: Vec<ChannelConversion> = unlocked_state
+        .channel_conversions()
+        .into_iter()
+        .map(|(channel_id, c)| ChannelConversion {
+            channel_id: channel_id.0.as_hex().to_string(),
+            peer_pubkey: c.peer_pubkey.to_string(),
+            capacity_sat: c.capacity_sat,
+            asset_id: c.asset_id,
+            asset_amount: c.asset_amount,
+            status: c.status,
+            requested_at: c.requested_at,
+            completed_at: c.completed_at,
+            temporary_channel_id: c.temporary_channel_id.map(|id| id.0.as_hex().to_string()),
+            error: c.error,
+        })
+        .collect();
+    conversions.sort_by_key(|c| c.requested_at);
+
+    Ok(Json(ListChannelConversionsResponse { conversions }))
+}
+
+pub(crate) async fn list_channel_requests(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListChannelRequestsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut requests: Vec<ChannelRequest> = unlocked_state
+        .channel_requests()
+        .into_iter()
+        .map(|(request_id, r)| ChannelRequest {
+            request_id,
+            peer_pubkey: r.peer_pubkey.to_string(),
+            capacity_sat: r.request.capacity_sat,
+            push_msat: r.request.push_msat,
+            asset_id: r.request.asset_id.map(|a| a.to_string()),
+            asset_amount: r.request.asset_amount,
+            fee_sat: r.request.fee_sat,
+            status: r.status,
+            received_at: r.received_at,
+            temporary_channel_id: r.temporary_channel_id,
+        })
+        .collect();
+    requests.sort_by_key(|r| r.received_at);
+
+    Ok(Json(ListChannelRequestsResponse { requests }))
+}
+
+pub(crate) async fn list_channels(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListChannelsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let asset_htlc_limits = unlocked_state.asset_htlc_limits();
+    let mut channels = vec![];
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let mut channel = Channel {
+            channel_id: chan_info.channel_id.0.as_hex().to_string(),
+            peer_pubkey: hex_str(&chan_info.counterparty.node_id.serialize()),
+            ready: chan_info.is_channel_ready,
+            capacity_sat: chan_info.channel_value_satoshis,
+            local_balance_msat: chan_info.balance_msat,
+            outbound_balance_msat: chan_info.outbound_capacity_msat,
+            inbound_balance_msat: chan_info.inbound_capacity_msat,
+            next_outbound_htlc_limit_msat: chan_info.next_outbound_htlc_limit_msat,
+            next_outbound_htlc_minimum_msat: chan_info.next_outbound_htlc_minimum_msat,
+            is_usable: chan_info.is_usable,
+            public: chan_info.is_public,
+            shutdown_state: chan_info.channel_shutdown_state.map(|s| s.into()),
+            fee_base_msat: chan_info.config.map(|c| c.forwarding_fee_base_msat),
+            fee_proportional_millionths: chan_info
+                .config
+                .map(|c| c.forwarding_fee_proportional_millionths),
+            cltv_expiry_delta: chan_info.config.map(|c| c.cltv_expiry_delta),
+            asset_htlc_maximum: asset_htlc_limits.get(&chan_info.channel_id).copied(),
+            close_address: unlocked_state.close_address(&chan_info.channel_id),
+            inbound_scid_alias: chan_info.inbound_scid_alias,
+            outbound_scid_alias: chan_info.outbound_scid_alias,
+            ..Default::default()
+        };
+
+        if let Some(funding_txo) = chan_info.funding_txo {
+            channel.funding_txid = Some(funding_txo.txid.to_string());
+        }
+
+        if let Some(node_info) = unlocked_state
+            .network_graph
+            .read_only()
+            .nodes()
+            .get(&NodeId::from_pubkey(&chan_info.counterparty.node_id))
+        {
+            if let Some(announcement) = &node_info.announcement_info {
+                channel.peer_alias = Some(announcement.alias.to_string());
+            }
+        }
+
+        if let Some(id) = chan_info.short_channel_id {
+            channel.short_channel_id = Some(id);
+        }
+
+        let info_file_path = get_rgb_channel_info_path(
+            &chan_info.channel_id.0.as_hex().to_string(),
+            &state.static_state.ldk_data_dir,
+            false,
+        );
+        if info_file_path.exists() {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            channel.asset_id = Some(rgb_info.contract_id.to_string());
+            channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
+            channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
+        };
+
+        channels.push(channel);
+    }
+
+    Ok(Json(ListChannelsResponse { channels }))
+}
+
+pub(crate) async fn list_closed_channels(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListClosedChannelsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut channels: Vec<ClosedChannel> = unlocked_state
+        .channel_closures()
+        .into_iter()
+        .map(|(channel_id, closure)| ClosedChannel {
+            channel_id: channel_id.0.as_hex().to_string(),
+            peer_pubkey: closure
+                .counterparty_node_id
+                .map(|p| hex_str(&p.serialize())),
+            reason: closure.reason,
+            error: closure.error,
+            closed_at: closure.closed_at,
+        })
+        .collect();
+    channels.sort_by_key(|c| c.closed_at);
+
+    Ok(Json(ListClosedChannelsResponse { channels }))
+}
+
+pub(crate) async fn list_fee_orders(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListFeeOrdersResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut orders: Vec<FeeOrder> = unlocked_state
+        .fee_orders()
+        .into_iter()
+        .map(|(order_id, o)| FeeOrder {
+            order_id,
+            request_id: o.request_id,
+            fee_msat: o.fee_msat,
+            payment_hash: hex_str(&o.payment_hash.0),
+            invoice: o.invoice,
+            status: o.status,
+            created_at: o.created_at,
+            temporary_channel_id: o.temporary_channel_id,
+        })
+        .collect();
+    orders.sort_by_key(|o| o.created_at);
+
+    Ok(Json(ListFeeOrdersResponse { orders }))
+}
+
+pub(crate) async fn list_funding_psbts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListFundingPsbtsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let psbts = unlocked_state
+        .get_pending_fundings()
+        .iter()
+        .map(|(temporary_channel_id, funding)| FundingPsbt {
+            temporary_channel_id: temporary_channel_id.0.as_hex().to_string(),
+            peer_pubkey: funding.counterparty_node_id.to_string(),
+            asset_id: funding.rgb_transfer.as_ref().map(|(a, _)| a.clone()),
+            psbt: funding.unsigned_psbt.to_string(),
+        })
+        .collect();
+
+    Ok(Json(ListFundingPsbtsResponse { psbts }))
+}
+
+pub(crate) async fn list_payments(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListPaymentsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let snapshot = unlocked_state.snapshot().await;
+    let mut payments = vec![];
+
+    for (payment_hash, payment_info) in &snapshot.inbound_payments {
+        let rgb_payment_info_path_inbound =
+            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, true);
+
+        let (asset_amount, asset_id) = if rgb_payment_info_path_inbound.exists() {
+            let info = parse_rgb_payment_info(&rgb_payment_info_path_inbound);
+            (
+                payment_info.asset_amount.or(Some(info.amount)),
+                Some(info.contract_id.to_string()),
+            )
+        } else {
+            (None, None)
+        };
+
+        payments.push(Payment {
+            amt_msat: payment_info.amt_msat,
+            asset_amount,
+            asset_id,
+            payment_hash: hex_str(&payment_hash.0),
+            inbound: true,
+            status: payment_info.status,
+            custom_records: payment_info
+                .custom_records
+                .iter()
+                .map(|r| r.into())
+                .collect(),
+            retry_attempts: payment_info.retry_attempts,
+            retry_timeout_secs: payment_info.retry_timeout_secs,
+            failed_attempts: payment_info.failed_attempts,
+            keysend: payment_info.keysend,
+        });
+    }
+
+    for (payment_id, payment_info) in &snapshot.outbound_payments {
+        let payment_hash = &PaymentHash(payment_id.0);
+
+        let rgb_payment_info_path_outbound =
+            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, false);
+
+        let (asset_amount, asset_id) = if rgb_payment_info_path_outbound.exists() {
+            let info = parse_rgb_payment_info(&rgb_payment_info_path_outbound);
+            (Some(info.amount), Some(info.contract_id.to_string()))
+        } else {
+            (None, None)
+        };
+
+        payments.push(Payment {
+            amt_msat: payment_info.amt_msat,
+            asset_amount,
+            asset_id,
+            payment_hash: hex_str(&payment_hash.0),
+            inbound: false,
+            status: payment_info.status,
+            custom_records: payment_info
+                .custom_records
+                .iter()
+                .map(|r| r.into())
+                .collect(),
+            retry_attempts: payment_info.retry_attempts,
+            retry_timeout_secs: payment_info.retry_timeout_secs,
+            failed_attempts: payment_info.failed_attempts,
+            keysend: payment_info.keysend,
+        });
+    }
+
+    Ok(Json(ListPaymentsResponse {
+        payments,
+        snapshot_id: snapshot.id,
+    }))
+}
+
+pub(crate) async fn list_peers(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListPeersResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut peers = vec![];
+    for peer_details in unlocked_state.peer_manager.list_peers() {
+        let swap_pairs = unlocked_state
+            .peer_message_handler
+            .peer_swap_pairs(&peer_details.counterparty_node_id)
+            .map(|pairs| pairs.iter().map(SwapPairInfo::from).collect());
+        peers.push(Peer {
+            pubkey: peer_details.counterparty_node_id.to_string(),
+            swap_pairs,
+        })
+    }
+
+    Ok(Json(ListPeersResponse { peers }))
+}
+
+pub(crate) async fn list_proxy_pins(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListProxyPinsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let pins = unlocked_state
+        .proxy_pins()
+        .into_iter()
+        .map(|(proxy, pin)| ProxyPinInfo {
+            proxy,
+            pin_type: pin.pin_type,
+            sha256: pin.sha256,
+            created_at: pin.created_at,
+        })
+        .collect();
+
+    Ok(Json(ListProxyPinsResponse { pins }))
+}
+
+pub(crate) async fn list_schedules(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListSchedulesResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut schedules: Vec<Schedule> = unlocked_state
+        .schedules()
+        .into_iter()
+        .map(|(schedule_id, s)| Schedule {
+            schedule_id,
+            target_type: s.target_type,
+            target: s.target,
+            amt_msat: s.amt_msat,
+            asset_id: s.asset_id,
+            asset_amount: s.asset_amount,
+            interval_secs: s.interval_secs,
+            next_run_at: s.next_run_at,
+            enabled: s.enabled,
+            created_at: s.created_at,
+            runs: s
+                .runs
+                .into_iter()
+                .map(|r| ScheduleRun {
+                    ran_at: r.ran_at,
+                    payment_hash: r.payment_hash,
+                    error: r.error,
+                })
+                .collect(),
+        })
+        .collect();
+    schedules.sort_by_key(|s| s.created_at);
+
+    Ok(Json(ListSchedulesResponse { schedules }))
+}
+
+pub(crate) async fn list_submarine_swaps(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListSubmarineSwapsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut swaps: Vec<SubmarineSwap> = unlocked_state
+        .submarine_swaps()
+        .into_iter()
+        .map(|(payment_hash, s)| SubmarineSwap {
+            payment_hash: hex_str(&payment_hash.0),
+            kind: s.kind,
+            server: s.server,
+            peer_pubkey: s.peer_pubkey.to_string(),
+            amount_sat: s.amount_sat,
+            status: s.status,
+            created_at: s.created_at,
+            timeout_height: s.timeout_height,
+            htlc_address: s
+                .htlc_script(&payment_hash)
+                .map(|script| Address::p2wsh(&script, state.static_state.network).to_string()),
+            funding_txid: s.funding_txid.map(|t| t.to_string()),
+            funding_vout: s.funding_vout,
+            spending_txid: s.spending_txid.map(|t| t.to_string()),
+            failure_reason: s.failure_reason,
+            asset_id: s.asset_id.map(|c| c.to_string()),
+            asset_amount: s.asset_amount,
+            rgb_invoice: s.rgb_invoice,
+        })
+        .collect();
+    swaps.sort_by_key(|s| s.created_at);
+
+    Ok(Json(ListSubmarineSwapsResponse { swaps }))
+}
+
+pub(crate) async fn list_swap_offers(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListSwapOffersResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let our_pubkey = unlocked_state.channel_manager.get_our_node_id();
+    let swap_offer = |offer_id: &str,
+                      maker_pubkey: PublicKey,
+                      offer: &SwapOfferMessage,
+                      acceptances: Vec<SwapOfferAcceptance>| SwapOffer {
+        offer_id: offer_id.to_string(),
+        maker_pubkey: maker_pubkey.to_string(),
+        from_asset: offer.from_asset.map(|c| c.to_string()),
+        to_asset: offer.to_asset.map(|c| c.to_string()),
+        price_from: offer.price_from,
+        price_to: offer.price_to,
+        min_qty_from: offer.min_qty_from,
+        max_qty_from: offer.max_qty_from,
+        remaining_qty_from: offer.remaining_qty_from,
+        expiry: offer.expiry,
+        acceptances,
+    };
+
+    let book = unlocked_state.get_swap_offers();
+    // our expired offers are kept, as the swaps of their acceptances could still be executed
+    let mut offers: Vec<SwapOffer> = book
+        .own
+        .offers
+        .iter()
+        .map(|(offer_id, o)| {
+            let acceptances = o
+                .acceptances
+                .iter()
+                .map(|a| SwapOfferAcceptance {
+                    taker_pubkey: a.taker_pubkey.to_string(),
+                    swapstring: a.swapstring.clone(),
+                    payment_secret: a.payment_secret.clone(),
+                    accepted_at: a.accepted_at,
+                })
+                .collect();
+            swap_offer(offer_id, our_pubkey, &o.offer, acceptances)
+        })
+        .collect();
+    offers.extend(
+        book.received
+            .iter()
+            .filter(|(_, o)| !o.offer.is_expired())
+            .map(|(offer_id, o)| swap_offer(offer_id, o.maker_pubkey, &o.offer, vec![])),
+    );
+    drop(book);
+    offers.sort_by_key(|o| o.expiry);
+
+    Ok(Json(ListSwapOffersResponse { offers }))
+}
+
+pub(crate) async fn list_swaps(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(filter), _): WithRejection<Query<ListSwapsRequest>, APIError>,
+) -> Result<Json<ListSwapsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let matches_filter = |swap: &Swap| {
+        filter.status.as_ref().map_or(true, |s| &swap.status == s)
+            && filter.asset_id.as_ref().map_or(true, |a| {
+                swap.from_asset.as_ref() == Some(a) || swap.to_asset.as_ref() == Some(a)
+            })
+            && filter
+                .from_timestamp
+                .map_or(true, |t| swap.requested_at >= t)
+            && filter.to_timestamp.map_or(true, |t| swap.requested_at <= t)
+    };
+
+    let snapshot = unlocked_state.snapshot().await;
+
+    Ok(Json(ListSwapsResponse {
+        taker: snapshot
+            .taker_swaps
+            .iter()
+            .map(|(ph, sd)| map_swap(&unlocked_state, ph, sd, true))
+            .filter(matches_filter)
+            .collect(),
+        maker: snapshot
+            .maker_swaps
+            .iter()
+            .map(|(ph, sd)| map_swap(&unlocked_state, ph, sd, false))
+            .filter(matches_filter)
+            .collect(),
+        snapshot_id: snapshot.id,
+    }))
+}
+
+fn map_swap(
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap_data: &SwapData,
+    taker: bool,
+) -> Swap {
+    let mut status = swap_data.status.clone();
+    // swaps are also timed out in the background, this covers the ones in between checks
+    if let Some(timed_out_status) = swap_data.timed_out_status(get_current_timestamp()) {
+        status = timed_out_status;
+        if taker {
+            let _ = unlocked_state.update_taker_swap_status(payment_hash, status.clone());
+        } else {
+            let _ = unlocked_state.update_maker_swap_status(payment_hash, status.clone());
+        }
+    }
+    Swap {
+        payment_hash: payment_hash.to_string(),
+        qty_from: swap_data.swap_info.qty_from,
+        qty_to: swap_data.swap_info.qty_to,
+        from_asset: swap_data.swap_info.from_asset.map(|c| c.to_string()),
+        to_asset: swap_data.swap_info.to_asset.map(|c| c.to_string()),
+        status,
+        requested_at: swap_data.requested_at,
+        initiated_at: swap_data.initiated_at,
+        expires_at: swap_data.swap_info.expiry,
+        completed_at: swap_data.completed_at,
+        partial_fill: swap_data.swap_info.partial_fill,
+        filled_qty_from: swap_data.filled_qty_from,
+        filled_qty_to: swap_data.filled_qty_to,
+        failure_reason: swap_data.failure_reason.clone(),
+        transitions: swap_data
+            .transitions
+            .iter()
+            .map(SwapTransition::from)
+            .collect(),
+    }
+}
+
+pub(crate) async fn list_transactions(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListTransactionsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut transactions = vec![];
+    for tx in unlocked_state.rgb_list_transactions()? {
+        transactions.push(Transaction {
+            transaction_type: match tx.transaction_type {
+                rgb_lib::TransactionType::RgbSend => TransactionType::RgbSend,
+                rgb_lib::TransactionType::Drain => TransactionType::Drain,
+                rgb_lib::TransactionType::CreateUtxos => TransactionType::CreateUtxos,
+                rgb_lib::TransactionType::User => TransactionType::User,
+            },
+            txid: tx.txid,
+            received: tx.received,
+            sent: tx.sent,
+            fee: tx.fee,
+            confirmation_time: tx.confirmation_time.map(|ct| BlockTime {
+                height: ct.height,
+                timestamp: ct.timestamp,
+            }),
+        })
+    }
+
+    Ok(Json(ListTransactionsResponse { transactions }))
+}
+
+pub(crate) async fn list_transfers(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListTransfersRequest>, APIError>,
+) -> Result<Json<ListTransfersResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let asset_ids = if let Some(asset_id) = payload.asset_id {
+        vec![asset_id]
+    } else {
+        let assets = unlocked_state.rgb_list_assets(vec![])?;
+        let nia = assets
+            .nia
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id);
+        let uda = assets
+            .uda
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id);
+        let cfa = assets
+            .cfa
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id);
+        nia.chain(uda).chain(cfa).collect()
+    };
+
+    let mut asset_transfers = vec![];
+    for asset_id in asset_ids {
+        for transfer in unlocked_state.rgb_list_transfers(asset_id.clone())? {
+            asset_transfers.push((asset_id.clone(), transfer));
+        }
+    }
+
+    let mut transfers = vec![];
+    for (asset_id, transfer) in asset_transfers {
+        // channel consignments are posted by the node itself, the proxy is recorded by TXID
+        let consignment_proxy = transfer
+            .txid
+            .as_deref()
+            .and_then(|txid| unlocked_state.consignment_proxy(txid))
+            .or_else(|| {
+                transfer
+                    .transport_endpoints
+                    .iter()
+                    .find(|tte| tte.used)
+                    .map(|tte| tte.endpoint.clone())
+            });
+        let channel_transfer = transfer
+            .txid
+            .as_deref()
+            .and_then(|txid| unlocked_state.channel_transfer(txid))
+            .map(|ct| ChannelTransfer {
+                kind: ct.kind,
+                channel_id: ct.channel_id.map(|id| id.0.as_hex().to_string()),
+                closing_txids: ct.closing_txids,
+            });
+        transfers.push(Transfer {
+            asset_id,
+            idx: transfer.idx,
+            created_at: transfer.created_at,
+            updated_at: transfer.updated_at,
+            status: match transfer.status {
+                rgb_lib::TransferStatus::WaitingCounterparty => TransferStatus::WaitingCounterparty,
+                rgb_lib::TransferStatus::WaitingConfirmations => {
+                    TransferStatus::WaitingConfirmations
+                }
+                rgb_lib::TransferStatus::Settled => TransferStatus::Settled,
+                rgb_lib::TransferStatus::Failed => TransferStatus::Failed,
+            },
+            amount: transfer.amount,
+            kind: match transfer.kind {
+                rgb_lib::TransferKind::Issuance => TransferKind::Issuance,
+                rgb_lib::TransferKind::ReceiveBlind => TransferKind::ReceiveBlind,
+                rgb_lib::TransferKind::ReceiveWitness => TransferKind::ReceiveWitness,
+                rgb_lib::TransferKind::Send => TransferKind::Send,
+            },
+            txid: transfer.txid,
+            recipient_id: transfer.recipient_id,
+            receive_utxo: transfer.receive_utxo.map(|u| u.to_string()),
+            change_utxo: transfer.change_utxo.map(|u| u.to_string()),
+            expiration: transfer.expiration,
+            transport_endpoints: transfer
+                .transport_endpoints
+                .iter()
+                .map(|tte| TransferTransportEndpoint {
+                    endpoint: tte.endpoint.clone(),
+                    transport_type: match tte.transport_type {
+                        rgb_lib::TransportType::JsonRpc => TransportType::JsonRpc,
+                    },
+                    used: tte.used,
+                })
+                .collect(),
+            consignment_proxy,
+            channel_transfer,
+        })
+    }
+
+    if let Some(status) = payload.status {
+        transfers.retain(|t| t.status == status);
+    }
+    transfers.sort_by_key(|t| (t.created_at, t.idx));
+    let total = transfers.len() as u32;
+    let transfers = transfers
+        .into_iter()
+        .skip(payload.offset as usize)
+        .take(payload.limit.map_or(usize::MAX, |l| l as usize))
+        .collect();
+
+    Ok(Json(ListTransfersResponse { transfers, total }))
+}
+
+pub(crate) async fn list_unspents(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListUnspentsResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let locked_outpoints =
+        unlocked_state.funding_locked_outpoints(&state.static_state.ldk_data_dir);
+
+    let mut unspents = vec![];
+    for unspent in unlocked_state.rgb_list_unspents()? {
+        let txid = Txid::from_str(&unspent.utxo.outpoint.txid).map_err(|e| {
+            tracing::error!(
+                "cannot parse the txid of unspent {}: {e}",
+                unspent.utxo.outpoint
+            );
+            APIError::Unexpected
+        })?;
+        let locked_for_funding = locked_outpoints.contains(&OutPoint {
+            txid,
+            vout: unspent.utxo.outpoint.vout,
+        });
+        unspents.push(Unspent {
+            utxo: Utxo {
+                outpoint: unspent.utxo.outpoint.to_string(),
+                btc_amount: unspent.utxo.btc_amount,
+                colorable: unspent.utxo.colorable,
+            },
+            rgb_allocations: unspent
+                .rgb_allocations
+                .iter()
+                .map(|a| RgbAllocation {
+                    asset_id: a.asset_id.clone(),
+                    amount: a.amount,
+                    settled: a.settled,
+                })
+                .collect(),
+            locked_for_funding,
+        })
+    }
+    Ok(Json(ListUnspentsResponse { unspents }))
+}
+
+/// Convert an on-chain address to an invoice fallback
+fn address_to_fallback(address: &Address) -> Result<Fallback, APIError> {
+    match &address.payload {
+        Payload::PubkeyHash(pkh) => Ok(Fallback::PubKeyHash(*pkh)),
+        Payload::ScriptHash(sh) => Ok(Fallback::ScriptHash(*sh)),
+        Payload::WitnessProgram(program) => Ok(Fallback::SegWitProgram {
+            version: program.version(),
+            program: program.program().as_bytes().to_vec(),
+        }),
+        _ => Err(APIError::InvalidAddress(s!(
+            "unsupported fallback address type"
+        ))),
+    }
+}
+
+/// Build the route hints of an invoice from the given channels, which must be usable and, for
+/// invoices of an RGB asset, hold that asset
+fn invoice_route_hints(
+    unlocked_state: &UnlockedAppState,
+    ldk_data_dir: &Path,
+    channel_ids: &[String],
+    contract_id: Option<ContractId>,
+) -> Result<Vec<RouteHint>, APIError> {
+    let channels = unlocked_state.channel_manager.list_channels();
+    let asset_htlc_limits = unlocked_state.asset_htlc_limits();
+    let mut route_hints = vec![];
+    for channel_id_str in channel_ids {
+        let channel_id = check_channel_id(channel_id_str)?;
+        let details = channels
+            .iter()
+            .find(|c| c.channel_id == channel_id)
+            .ok_or_else(|| {
+                APIError::InvalidRouteHints(format!("unknown channel {channel_id_str}"))
+            })?;
+        let (short_channel_id, config) = match (
+            details.get_inbound_payment_scid(),
+            details.counterparty.forwarding_info.as_ref(),
+        ) {
+            (Some(scid), Some(config)) if details.is_usable => (scid, config),
+            _ => {
+                return Err(APIError::InvalidRouteHints(format!(
+                    "channel {channel_id_str} is not usable"
+                )))
+            }
+        };
+        if let Some(contract_id) = contract_id {
+            match get_rgb_channel_info_optional(&channel_id, ldk_data_dir, false) {
+                Some((rgb_info, _)) if rgb_info.contract_id == contract_id => {}
+                _ => {
+                    return Err(APIError::InvalidRouteHints(format!(
+                        "channel {channel_id_str} doesn't hold the invoice asset"
+                    )))
+                }
+            }
+        }
+        route_hints.push(RouteHint(vec![RouteHintHop {
+            src_node_id: details.counterparty.node_id,
+            short_channel_id,
+            cltv_expiry_delta: config.cltv_expiry_delta,
+            htlc_minimum_msat: details.inbound_htlc_minimum_msat,
+            htlc_maximum_msat: details.inbound_htlc_maximum_msat,
+            fees: RoutingFees {
+                base_msat: config.fee_base_msat,
+                proportional_millionths: config.fee_proportional_millionths,
+            },
+            htlc_maximum_rgb: contract_id.and(asset_htlc_limits.get(&channel_id).copied()),
+        }]));
+    }
+    Ok(route_hints)
+}
+
+/// Set the asset HTLC limits of our channels on the route hints picked by LDK for an RGB invoice,
+/// returning the updated hints if any of them changed
+fn limited_route_hints(
+    unlocked_state: &UnlockedAppState,
+    invoice: &Bolt11Invoice,
+) -> Option<Vec<RouteHint>> {
+    let asset_htlc_limits = unlocked_state.asset_htlc_limits();
+    if asset_htlc_limits.is_empty() {
+        return None;
+    }
+    let scid_limits: HashMap<u64, u64> = unlocked_state
+        .channel_manager
+        .list_channels()
+        .into_iter()
+        .filter_map(|c| {
+            let limit = asset_htlc_limits.get(&c.channel_id)?;
+            Some((c.get_inbound_payment_scid()?, *limit))
+        })
+        .collect();
+    let mut changed = false;
+    let route_hints = invoice
+        .route_hints()
+        .into_iter()
+        .map(|mut route_hint| {
+            for hop in route_hint.0.iter_mut() {
+                if let Some(limit) = scid_limits.get(&hop.short_channel_id) {
+                    hop.htlc_maximum_rgb = Some(*limit);
+                    changed = true;
+                }
+            }
+            route_hint
+        })
+        .collect();
+    changed.then_some(route_hints)
+}
+
+/// Replace the description of an invoice with its hash, add an on-chain fallback address to it
+/// and/or replace its route hints, then sign it again with the node key
+fn customize_invoice(
+    invoice: Bolt11Invoice,
+    keys_manager: &KeysManager,
+    description_hash: Option<InvoiceSha256>,
+    fallback: Option<Fallback>,
+    route_hints: Option<Vec<RouteHint>>,
+) -> Result<Bolt11Invoice, APIError> {
+    let mut raw_invoice = invoice.into_signed_raw().raw_invoice().clone();
+    let tagged_fields = &mut raw_invoice.data.tagged_fields;
+    if let Some(description_hash) = description_hash {
+        tagged_fields.retain(|f| {
+            !matches!(
+                f,
+                RawTaggedField::KnownSemantics(TaggedField::Description(_))
+            )
+        });
+        tagged_fields.push(RawTaggedField::KnownSemantics(
+            TaggedField::DescriptionHash(description_hash),
+        ));
+    }
+    if let Some(fallback) = fallback {
+        tagged_fields.push(RawTaggedField::KnownSemantics(TaggedField::Fallback(
+            fallback,
+        )));
+    }
+    if let Some(route_hints) = route_hints {
+        tagged_fields.retain(|f| {
+            !matches!(
+                f,
+                RawTaggedField::KnownSemantics(TaggedField::PrivateRoute(_))
+            )
+        });
+        for route_hint in route_hints {
+            let private_route = PrivateRoute::new(route_hint)
+                .map_err(|e| APIError::InvalidRouteHints(e.to_string()))?;
+            tagged_fields.push(RawTaggedField::KnownSemantics(TaggedField::PrivateRoute(
+                private_route,
+            )));
+        }
+    }
+
+    let signature = keys_manager
+        .sign_invoice(&raw_invoice, LdkRecipient::Node)
+        .map_err(|_| APIError::FailedInvoiceCreation(s!("failed to sign invoice")))?;
+    let signed_raw_invoice = raw_invoice
+        .sign::<_, ()>(|_| Ok(signature))
+        .expect("signature already computed");
+    Bolt11Invoice::from_signed(signed_raw_invoice)
+        .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))
+}
+
+pub(crate) async fn ln_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LNInvoiceRequest>, APIError>,
+) -> Result<Json<LNInvoiceResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let contract_id = if let Some(asset_id) = payload.asset_id {
+            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
+        } else {
+            None
+        };
+
+        // the asset amount can be left out to let the payer choose it, the asset cannot
+        if contract_id.is_none() && payload.asset_amount.is_some() {
+            return Err(APIError::IncompleteRGBInfo);
+        }
+
+        if contract_id.is_some() && payload.amt_msat.unwrap_or(0) < INVOICE_MIN_MSAT {
+            return Err(APIError::InvalidAmount(format!(
+                "amt_msat cannot be less than {INVOICE_MIN_MSAT} when transferring an RGB asset"
+            )));
+        }
+
+        let description_hash = if let Some(description_hash) = payload.description_hash {
+            let hash = hex_str_to_vec(&description_hash)
+                .and_then(|h| sha256::Hash::from_slice(&h).ok())
+                .ok_or(APIError::InvalidInvoice(s!(
+                    "description_hash must be a hex-encoded SHA256 hash"
+                )))?;
+            Some(InvoiceSha256(hash))
+        } else {
+            None
+        };
+
+        let fallback = if let Some(fallback_address) = payload.fallback_address {
+            let address = Address::from_str(&fallback_address)
+                .map_err(|e| APIError::InvalidAddress(e.to_string()))?
+                .require_network(state.static_state.network)
+                .map_err(|e| APIError::InvalidAddress(e.to_string()))?;
+            Some(address_to_fallback(&address)?)
+        } else {
+            None
+        };
+
+        // when channels are given they replace the route hints picked by the node
+        let route_hints = if let Some(channel_ids) = &payload.route_hint_channel_ids {
+            Some(invoice_route_hints(
+                &unlocked_state,
+                &state.static_state.ldk_data_dir,
+                channel_ids,
+                contract_id,
+            )?)
+        } else {
+            None
+        };
+
+        let currency = match state.static_state.network {
+            Network::Bitcoin => Currency::Bitcoin,
+            Network::Testnet => Currency::BitcoinTestnet,
+            Network::Regtest => Currency::Regtest,
+            Network::Signet => Currency::Signet,
+            _ => unimplemented!("unsupported network"),
+        };
+        let invoice = if let Some(payment_hash) = payload.payment_hash {
+            // hold invoice, payments will wait for a settleinvoice or cancelinvoice call
+            let payment_hash = hex_str_to_vec(&payment_hash)
+                .and_then(|h| h.try_into().ok())
+                .map(PaymentHash)
+                .ok_or(APIError::InvalidPaymentHash)?;
+            if unlocked_state.inbound_payment(&payment_hash).is_some() {
+                return Err(APIError::PaymentHashAlreadyUsed);
+            }
+            create_invoice_from_channelmanager_with_payment_hash(
+                &unlocked_state.channel_manager,
+                unlocked_state.keys_manager.clone(),
+                state.static_state.logger.clone(),
+                currency,
+                payload.amt_msat,
+                "ldk-tutorial-node".to_string(),
+                payload.expiry_sec,
+                payment_hash,
+                None,
+                contract_id,
+                payload.asset_amount,
+            )
+        } else {
+            create_invoice_from_channelmanager(
+                &unlocked_state.channel_manager,
+                unlocked_state.keys_manager.clone(),
+                state.static_state.logger.clone(),
+                currency,
+                payload.amt_msat,
+                "ldk-tutorial-node".to_string(),
+                payload.expiry_sec,
+                None,
+                contract_id,
+                payload.asset_amount,
+            )
+        };
+        let invoice = match invoice {
+            Ok(inv) => inv,
+            Err(e) => return Err(APIError::FailedInvoiceCreation(e.to_string())),
+        };
+        // hints picked by LDK need the asset HTLC limits of our channels
+        let route_hints = match route_hints {
+            None if contract_id.is_some() => limited_route_hints(&unlocked_state, &invoice),
+            route_hints => route_hints,
+        };
+        let invoice = if description_hash.is_some() || fallback.is_some() || route_hints.is_some() {
+            customize_invoice(
+                invoice,
+                &unlocked_state.keys_manager,
+                description_hash,
+                fallback,
+                route_hints,
+            )?
+        } else {
+            invoice
+        };
+
+        let payment_hash = PaymentHash((*invoice.payment_hash()).to_byte_array());
+        unlocked_state.add_inbound_payment(
+            payment_hash,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(*invoice.payment_secret()),
+                status: HTLCStatus::Pending,
+                amt_msat: payload.amt_msat,
+                custom_records: vec![],
+                retry_attempts: None,
+                retry_timeout_secs: None,
+                failed_attempts: 0,
+                expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                asset_amount: None,
+                keysend: false,
+                claim_deadline: None,
+            },
+        );
+
+        Ok(Json(LNInvoiceResponse {
+            invoice: invoice.to_string(),
+        }))
+    })
+    .await
+}
+
+fn lnurl_pay_info(state: &AppState, username: &str) -> Result<(String, String), APIError> {
+    let base_url = state
+        .static_state
+        .lnurl_base_url
+        .as_ref()
+        .ok_or(APIError::LnurlDisabled)?;
+    if username.is_empty()
+        || !username
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.".contains(c))
+    {
+        return Err(APIError::InvalidName(s!(
+            "username can only contain a-z, 0-9, '-', '_' and '.'"
+        )));
+    }
+
+    let domain = base_url
+        .split("://")
+        .nth(1)
+        .and_then(|u| u.split('/').next())
+        .expect("validated base URL");
+    let identifier = format!("{username}@{domain}");
+    let description = format!("Payment to {identifier}");
+    let metadata = serde_json::to_string(&[
+        ["text/plain", description.as_str()],
+        ["text/identifier", identifier.as_str()],
+    ])
+    .expect("valid metadata");
+    let callback = format!("{base_url}/lnurlp/{username}/callback");
+
+    Ok((callback, metadata))
+}
+
+pub(crate) async fn lnurl_pay(
+    State(state): State<Arc<AppState>>,
+    UrlPath(username): UrlPath<String>,
+) -> Result<Json<LnurlPayResponse>, LnurlError> {
+    let (callback, metadata) = lnurl_pay_info(&state, &username)?;
+
+    Ok(Json(LnurlPayResponse {
+        callback,
+        max_sendable: state.static_state.lnurl_max_sendable_msat,
+        min_sendable: state.static_state.lnurl_min_sendable_msat,
+        metadata,
+        tag: s!("payRequest"),
+    }))
+}
+
+pub(crate) async fn lnurl_pay_callback(
+    State(state): State<Arc<AppState>>,
+    UrlPath(username): UrlPath<String>,
+    WithRejection(Query(payload), _): WithRejection<Query<LnurlPayCallbackRequest>, LnurlError>,
+) -> Result<Json<LnurlPayCallbackResponse>, LnurlError> {
+    no_cancel(async move {
+        let (_, metadata) = lnurl_pay_info(&state, &username)?;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let contract_id = if let Some(asset_id) = payload.asset_id {
+            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
+        } else {
+            None
+        };
+        if contract_id.is_some() != payload.asset_amount.is_some() {
+            return Err(APIError::IncompleteRGBInfo.into());
+        }
+
+        let mut min_sendable = state.static_state.lnurl_min_sendable_msat;
+        if contract_id.is_some() {
+            min_sendable = min_sendable.max(INVOICE_MIN_MSAT);
+        }
+        let max_sendable = state.static_state.lnurl_max_sendable_msat;
+        if payload.amount < min_sendable || payload.amount > max_sendable {
+            return Err(APIError::InvalidAmount(format!(
+                "amount must be between {min_sendable} and {max_sendable} msat"
+            ))
+            .into());
+        }
+
+        let currency = match state.static_state.network {
+            Network::Bitcoin => Currency::Bitcoin,
+            Network::Testnet => Currency::BitcoinTestnet,
+            Network::Regtest => Currency::Regtest,
+            Network::Signet => Currency::Signet,
+            _ => unimplemented!("unsupported network"),
+        };
+        // the invoice commits to the metadata, as required by LUD-06
+        let description_hash = InvoiceSha256(sha256::Hash::hash(metadata.as_bytes()));
+        let invoice = create_invoice_from_channelmanager_with_description_hash(
+            &unlocked_state.channel_manager,
+            unlocked_state.keys_manager.clone(),
+            state.static_state.logger.clone(),
+            currency,
+            Some(payload.amount),
+            description_hash,
+            LNURL_INVOICE_EXPIRY_SECS,
+            None,
+            contract_id,
+            payload.asset_amount,
+        )
+        .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?;
+
+        let payment_hash = PaymentHash((*invoice.payment_hash()).to_byte_array());
+        unlocked_state.add_inbound_payment(
+            payment_hash,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(*invoice.payment_secret()),
+                status: HTLCStatus::Pending,
+                amt_msat: Some(payload.amount),
+                custom_records: vec![],
+                retry_attempts: None,
+                retry_timeout_secs: None,
+                failed_attempts: 0,
+                expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                asset_amount: None,
+                keysend: false,
+                claim_deadline: None,
+            },
+        );
+
+        Ok(Json(LnurlPayCallbackResponse {
+            pr: invoice.to_string(),
+            routes: vec![],
+        }))
+    })
+    .await
+}
+
+fn check_lnurl_withdraw_claimable(withdraw: &LnurlWithdraw) -> Result<(), APIError> {
+    if withdraw.claimed_payment_hash.is_some() {
+        return Err(APIError::CannotLnurlWithdraw(s!(
+            "k1 has already been used"
+        )));
+    }
+    if withdraw
+        .expires_at
+        .is_some_and(|e| e <= get_current_timestamp())
+    {
+        return Err(APIError::CannotLnurlWithdraw(s!(
+            "the withdraw has expired"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) async fn lnurl_withdraw(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LnurlWithdrawRequest>, APIError>,
+) -> Result<Json<LnurlWithdrawResponse>, APIError> {
+    no_cancel(async move {
+        let base_url = state
+            .static_state
+            .lnurl_base_url
+            .clone()
+            .ok_or(APIError::LnurlDisabled)?;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let contract_id = if let Some(asset_id) = payload.asset_id {
+            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
+        } else {
+            None
+        };
+        if contract_id.is_some() != payload.max_asset_amount.is_some() {
+            return Err(APIError::IncompleteRGBInfo);
+        }
+
+        if payload.min_withdrawable_msat == 0
+            || payload.max_withdrawable_msat < payload.min_withdrawable_msat
+        {
+            return Err(APIError::InvalidAmount(s!(
+                "max withdrawable amount cannot be lower than the min one, which must be positive"
+            )));
+        }
+        if contract_id.is_some() && payload.min_withdrawable_msat < INVOICE_MIN_MSAT {
+            return Err(APIError::InvalidAmount(format!(
+                "min withdrawable amount cannot be less than {INVOICE_MIN_MSAT} when withdrawing an RGB asset"
+            )));
+        }
+
+        let k1 = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        let created_at = get_current_timestamp();
+        unlocked_state.add_lnurl_withdraw(
+            k1.clone(),
+            LnurlWithdraw {
+                min_withdrawable_msat: payload.min_withdrawable_msat,
+                max_withdrawable_msat: payload.max_withdrawable_msat,
+                description: payload.description,
+                contract_id,
+                max_asset_amount: payload.max_asset_amount,
+                created_at,
+                expires_at: payload.expiry_sec.map(|e| created_at + e),
+                claimed_payment_hash: None,
+            },
+        )?;
+
+        Ok(Json(LnurlWithdrawResponse {
+            url: format!("{base_url}/lnurlw/{k1}"),
+            k1,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn lnurl_withdraw_callback(
+    State(state): State<Arc<AppState>>,
+    UrlPath(k1): UrlPath<String>,
+    WithRejection(Query(payload), _): WithRejection<
+        Query<LnurlWithdrawCallbackRequest>,
+        LnurlError,
+    >,
+) -> Result<Json<LnurlStatusResponse>, LnurlError> {
+    no_cancel(async move {
+        if state.static_state.lnurl_base_url.is_none() {
+            return Err(APIError::LnurlDisabled.into());
+        }
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if payload.k1 != k1 {
+            return Err(APIError::UnknownLnurlWithdraw.into());
+        }
+        let withdraw = unlocked_state
+            .lnurl_withdraws()
+            .remove(&k1)
+            .ok_or(APIError::UnknownLnurlWithdraw)?;
+        check_lnurl_withdraw_claimable(&withdraw)?;
+
+        let invoice = Bolt11Invoice::from_str(&payload.pr)
+            .map_err(|e| APIError::InvalidInvoice(e.to_string()))?;
+        let amt_msat = invoice.amount_milli_satoshis().unwrap_or(0);
+        if amt_msat < withdraw.min_withdrawable_msat || amt_msat > withdraw.max_withdrawable_msat {
+            return Err(APIError::InvalidAmount(format!(
+                "amount must be between {} and {} msat",
+                withdraw.min_withdrawable_msat, withdraw.max_withdrawable_msat
+            ))
+            .into());
+        }
+        match (
+            withdraw.contract_id,
+            invoice.rgb_contract_id(),
+            invoice.rgb_amount(),
+        ) {
+            (None, None, None) => {}
+            (Some(contract_id), Some(rgb_contract_id), Some(rgb_amount))
+                if contract_id == rgb_contract_id =>
+            {
+                let max_asset_amount = withdraw.max_asset_amount.unwrap_or(0);
+                if rgb_amount > max_asset_amount {
+                    return Err(APIError::InvalidAmount(format!(
+                        "asset amount cannot be more than {max_asset_amount}"
+                    ))
+                    .into());
+                }
+            }
+            _ => {
+                return Err(APIError::InvalidInvoice(s!(
+                    "invoice asset doesn't match the withdraw one"
+                ))
+                .into())
+            }
+        }
+
+        let (payment_hash, recipient_onion, route_params) =
+            payment_parameters_from_invoice(&invoice)
+                .map_err(|e| APIError::InvalidInvoice(format!("failed to parse invoice: {e:?}")))?;
+
+        // the withdrawing wallet cannot confirm the spend, so it's refused above the limits
+        let spend = SpendData::from_msat(
+            amt_msat,
+            invoice.rgb_contract_id().map(|c| c.to_string()),
+            invoice.rgb_amount(),
+        );
+        let reservation = charge_spend_limits(&state, &unlocked_state, spend)?;
+
+        unlocked_state.claim_lnurl_withdraw(&k1, payment_hash)?;
+
+        if let (Some(rgb_contract_id), Some(rgb_amount)) =
+            (invoice.rgb_contract_id(), invoice.rgb_amount())
+        {
+            write_rgb_payment_info_file(
+                &PathBuf::from(&state.static_state.ldk_data_dir.clone()),
+                &payment_hash,
+                rgb_contract_id,
+                rgb_amount,
+                false,
+                false,
+            );
+        }
+
+        let retry = state.static_state.payment_retry;
+        let (retry_attempts, retry_timeout_secs) = retry_details(retry);
+        let payment_id = PaymentId(payment_hash.0);
+        unlocked_state
+            .add_outbound_payment(
+                payment_id,
+                PaymentInfo {
+                    preimage: None,
+                    secret: Some(*invoice.payment_secret()),
+                    status: HTLCStatus::Pending,
+                    amt_msat: Some(amt_msat),
+                    custom_records: vec![],
+                    retry_attempts,
+                    retry_timeout_secs,
+                    failed_attempts: 0,
+                    expires_at: None,
+                    asset_amount: None,
+                    keysend: false,
+                    claim_deadline: None,
+                },
+            )
+            .inspect_err(|_| unlocked_state.release_lnurl_withdraw(&k1))?;
+
+        if let Err(e) = unlocked_state.channel_manager.send_payment(
+            payment_hash,
+            recipient_onion,
+            payment_id,
+            route_params,
+            retry,
+        ) {
+            tracing::error!("ERROR: failed to send LNURL-withdraw payment: {:?}", e);
+            // the payment never left the node, so the withdraw can be claimed again
+            unlocked_state.release_lnurl_withdraw(&k1);
+            unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed)?;
+            return Err(APIError::FailedPayment(format!("{e:?}")).into());
+        }
+        reservation.commit();
+        tracing::info!("EVENT: initiated LNURL-withdraw of {amt_msat} msats");
+
+        Ok(Json(LnurlStatusResponse { status: s!("OK") }))
+    })
+    .await
+}
+
+pub(crate) async fn lnurl_withdraw_info(
+    State(state): State<Arc<AppState>>,
+    UrlPath(k1): UrlPath<String>,
+) -> Result<Json<LnurlWithdrawInfoResponse>, LnurlError> {
+    let base_url = state
+        .static_state
+        .lnurl_base_url
+        .clone()
+        .ok_or(APIError::LnurlDisabled)?;
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let withdraw = unlocked_state
+        .lnurl_withdraws()
+        .remove(&k1)
+        .ok_or(APIError::UnknownLnurlWithdraw)?;
+    check_lnurl_withdraw_claimable(&withdraw)?;
+
+    Ok(Json(LnurlWithdrawInfoResponse {
+        tag: s!("withdrawRequest"),
+        callback: format!("{base_url}/lnurlw/{k1}/callback"),
+        k1,
+        default_description: withdraw.description,
+        min_withdrawable: withdraw.min_withdrawable_msat,
+        max_withdrawable: withdraw.max_withdrawable_msat,
+        asset_id: withdraw.contract_id.map(|c| c.to_string()),
+        max_asset_amount: withdraw.max_asset_amount,
+    }))
+}
+
+pub(crate) async fn lock(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    tracing::info!("Lock started");
+    no_cancel(async move {
+        match state.check_unlocked().await {
+            Ok(unlocked_state) => {
+                state.update_changing_state(true);
+                drop(unlocked_state);
+            }
+            Err(e) => {
+                state.update_changing_state(false);
+                return Err(e);
+            }
+        }
+
+        tracing::debug!("Stopping LDK...");
+        stop_ldk(state.clone()).await;
+        tracing::debug!("LDK stopped");
+
+        state.update_unlocked_app_state(None).await;
+
+        state.update_ldk_background_services(None);
+
+        state.update_changing_state(false);
+
+        if state.static_state.relay_mode {
+            if let Err(e) = start_relay(state.clone()).await {
+                tracing::error!("Failed to start relaying: {e}");
+            }
+        }
+
+        tracing::info!("Lock completed");
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn loop_in(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LoopInRequest>, APIError>,
+) -> Result<Json<LoopInResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let payment_hash = request_submarine_swap(
+            &state,
+            &unlocked_state,
+            &payload.peer_pubkey,
+            payload.amount_sat,
+            None,
+            None,
+            SubmarineSwapKind::LoopIn,
+        )?;
+
+        Ok(Json(LoopInResponse {
             payment_hash: hex_str(&payment_hash.0),
-            payment_preimage: hex_str(&payment_preimage.0),
-            status,
         }))
     })
     .await
 }
 
-pub(crate) async fn list_assets(
+pub(crate) async fn loop_out(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LoopOutRequest>, APIError>,
+) -> Result<Json<LoopOutResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let payment_hash = request_submarine_swap(
+            &state,
+            &unlocked_state,
+            &payload.peer_pubkey,
+            payload.amount_sat,
+            None,
+            None,
+            SubmarineSwapKind::LoopOut,
+        )?;
+
+        Ok(Json(LoopOutResponse {
+            payment_hash: hex_str(&payment_hash.0),
+        }))
+    })
+    .await
+}
+
+/// Request, as client, a submarine swap to a peer providing them
+fn request_submarine_swap(
+    state: &AppState,
+    unlocked_state: &Arc<UnlockedAppState>,
+    peer_pubkey: &str,
+    amount_sat: u64,
+    asset: Option<(ContractId, u64)>,
+    rgb_invoice: Option<String>,
+    kind: SubmarineSwapKind,
+) -> Result<PaymentHash, APIError> {
+    let peer_pubkey = match hex_str_to_compressed_pubkey(peer_pubkey) {
+        Some(pk) => pk,
+        None => return Err(APIError::InvalidPubkey),
+    };
+
+    if asset.is_some_and(|(_, asset_amount)| asset_amount == 0) {
+        return Err(APIError::InvalidAmount(s!(
+            "Submarine swap asset amount must be positive"
+        )));
+    }
+    if asset.is_none() && amount_sat < SUBMARINE_SWAP_MIN_SAT {
+        return Err(APIError::InvalidAmount(format!(
+            "Submarine swap amount must be equal or higher than {SUBMARINE_SWAP_MIN_SAT}"
+        )));
+    }
+
+    // the request is sent as a custom message, the peer must be connected and understand it
+    let peer = unlocked_state
+        .peer_manager
+        .peer_by_node_id(&peer_pubkey)
+        .ok_or_else(|| APIError::CannotStartSubmarineSwap(s!("peer is not connected")))?;
+    if !supports_feature_bit(peer.init_features.le_flags(), SUBMARINE_SWAP_FEATURE_BIT) {
+        return Err(APIError::CannotStartSubmarineSwap(s!(
+            "peer doesn't support submarine swaps"
+        )));
+    }
+
+    let usable_channels = unlocked_state.channel_manager.list_usable_channels();
+    let amt_msat = amount_sat * 1000;
+    match kind {
+        SubmarineSwapKind::LoopIn => {
+            let inbound_msat: u64 = usable_channels
+                .iter()
+                .map(|c| c.inbound_capacity_msat)
+                .sum();
+            if inbound_msat < amt_msat {
+                return Err(APIError::CannotStartSubmarineSwap(s!(
+                    "not enough inbound liquidity"
+                )));
+            }
+            if let Some((contract_id, asset_amount)) = asset {
+                let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
+                if balance.spendable < asset_amount {
+                    return Err(APIError::InsufficientAssets);
+                }
+            }
+        }
+        SubmarineSwapKind::LoopOut => {
+            let outbound_msat: u64 = usable_channels
+                .iter()
+                .map(|c| c.next_outbound_htlc_limit_msat)
+                .sum();
+            if outbound_msat < amt_msat {
+                return Err(APIError::CannotStartSubmarineSwap(s!(
+                    "not enough outbound liquidity"
+                )));
+            }
+            if let Some((contract_id, asset_amount)) = asset {
+                let max_balance = get_max_local_rgb_amount(
+                    contract_id,
+                    &state.static_state.ldk_data_dir,
+                    usable_channels.iter(),
+                );
+                if max_balance < asset_amount {
+                    return Err(APIError::InsufficientAssets);
+                }
+            }
+        }
+    }
+
+    // a loop out pays the server once it has funded the swap, so it's accounted when requested, and
+    // refused above the limits since the payment is sent in the background
+    let reservation = match kind {
+        SubmarineSwapKind::LoopIn => None,
+        SubmarineSwapKind::LoopOut => {
+            let spend = SpendData::new(
+                amount_sat,
+                asset.map(|(contract_id, _)| contract_id.to_string()),
+                asset.map(|(_, asset_amount)| asset_amount),
+            );
+            Some(charge_spend_limits(state, unlocked_state, spend)?)
+        }
+    };
+
+    let secret_key = SecretKey::from_slice(&unlocked_state.keys_manager.get_secure_random_bytes())
+        .expect("valid secret key");
+    let mut swap = SubmarineSwapData::new(kind, false, peer_pubkey, amount_sat, secret_key);
+    swap.asset_id = asset.map(|(contract_id, _)| contract_id);
+    swap.asset_amount = asset.map(|(_, asset_amount)| asset_amount);
+    swap.rgb_invoice = rgb_invoice;
+    let payment_hash = match kind {
+        SubmarineSwapKind::LoopIn => {
+            // the server pays our invoice, learning the preimage it needs to claim our HTLC output
+            let invoice = create_swap_invoice(state, unlocked_state, amount_sat, asset, None)
+                .map_err(APIError::FailedInvoiceCreation)?;
+            swap.invoice = Some(invoice.to_string());
+            PaymentHash((*invoice.payment_hash()).to_byte_array())
+        }
+        SubmarineSwapKind::LoopOut => {
+            // only we know the preimage, revealed when claiming the HTLC output of the server
+            let preimage = PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
+            swap.preimage = Some(preimage);
+            PaymentHash(Sha256::hash(&preimage.0).to_byte_array())
+        }
+    };
+    let request = SubmarineSwapRequestMessage {
+        payment_hash,
+        kind,
+        amount_sat,
+        pubkey: swap.pubkey(),
+        invoice: swap.invoice.clone(),
+        asset_id: swap.asset_id,
+        asset_amount: swap.asset_amount,
+        rgb_invoice: swap.rgb_invoice.clone(),
+        // with no HTLC output to claim, the server needs the preimage to settle our payment
+        preimage: swap.asset().and(swap.preimage),
+    };
+    unlocked_state.add_submarine_swap(payment_hash, swap)?;
+    if let Some(reservation) = reservation {
+        reservation.commit();
+    }
+
+    unlocked_state
+        .peer_message_handler
+        .send_message(peer_pubkey, PeerMessage::SubmarineSwapRequest(request));
+    unlocked_state.peer_manager.process_events();
+
+    tracing::info!("Requested submarine swap {payment_hash} ({kind:?}) to peer {peer_pubkey}");
+    Ok(payment_hash)
+}
+
+pub(crate) async fn maker_execute(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<MakerExecuteRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let swapstring = SwapString::from_str(&payload.swapstring)
+            .map_err(|e| APIError::InvalidSwapString(payload.swapstring.clone(), e.to_string()))?;
+        let payment_secret = hex_str_to_vec(&payload.payment_secret)
+            .and_then(|data| data.try_into().ok())
+            .map(PaymentSecret)
+            .ok_or(APIError::InvalidPaymentSecret)?;
+        let taker_pk =
+            PublicKey::from_str(&payload.taker_pubkey).map_err(|_| APIError::InvalidPubkey)?;
+
+        if get_current_timestamp() > swapstring.swap_info.expiry {
+            let _ = unlocked_state
+                .update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Expired);
+            return Err(APIError::ExpiredSwapOffer);
+        }
+
+        // CLTV expiry delta the taker gets to forward the swap HTLC (first leg) and the one we
+        // require when receiving it back (second leg)
+        let first_leg_cltv_expiry_delta = payload
+            .first_leg_cltv_expiry_delta
+            .unwrap_or(DEFAULT_FINAL_CLTV_EXPIRY_DELTA);
+        let second_leg_cltv_expiry_delta = payload
+            .second_leg_cltv_expiry_delta
+            .unwrap_or(DEFAULT_FINAL_CLTV_EXPIRY_DELTA);
+        if first_leg_cltv_expiry_delta < DEFAULT_FINAL_CLTV_EXPIRY_DELTA
+            || second_leg_cltv_expiry_delta < DEFAULT_FINAL_CLTV_EXPIRY_DELTA
+        {
+            return Err(APIError::InvalidSwap(format!(
+                "leg CLTV expiry delta cannot be lower than {DEFAULT_FINAL_CLTV_EXPIRY_DELTA}"
+            )));
+        }
+
+        let payment_preimage = unlocked_state
+            .channel_manager
+            .get_payment_preimage(swapstring.payment_hash, payment_secret)
+            .map_err(|_| APIError::MissingSwapPaymentPreimage)?;
+
+        // partial-fill swaps can be executed for a fraction of the quantities, at the swap price
+        let mut swap_info = swapstring.swap_info;
+        if let Some(fill_qty_from) = payload.fill_qty_from {
+            if !swap_info.partial_fill {
+                return Err(APIError::InvalidSwap(s!(
+                    "fill_qty_from can only be set for partial-fill swaps"
+                )));
+            }
+            let fill_qty_to = swap_info.fill_qty_to(fill_qty_from);
+            if !swap_info.check_fill(fill_qty_from, fill_qty_to) {
+                return Err(APIError::InvalidSwap(format!(
+                    "fill_qty_from must be positive, not higher than {} and high enough to \
+                    receive a positive quantity",
+                    swap_info.qty_from
+                )));
+            }
+            swap_info.qty_from = fill_qty_from;
+            swap_info.qty_to = fill_qty_to;
+        }
+
+        // Both legs can go over multiple hops, so the maker and the taker don't need to share
+        // channels for either asset. Hints to our channels let the taker reach us even when they
+        // aren't announced
+        let asset_htlc_limits = unlocked_state.asset_htlc_limits();
+        let receive_hints = unlocked_state
+            .channel_manager
+            .list_usable_channels()
+            .iter()
+            .filter(|details| {
+                match get_rgb_channel_info_optional(
+                    &details.channel_id,
+                    &state.static_state.ldk_data_dir,
+                    false,
+                ) {
+                    _ if swap_info.is_from_btc() => true,
+                    Some((rgb_info, _)) if Some(rgb_info.contract_id) == swap_info.from_asset => {
+                        true
+                    }
+                    _ => false,
+                }
+            })
+            .filter_map(|details| {
+                // channels can be usable before the peer sent us its forwarding info
+                let config = details.counterparty.forwarding_info.as_ref()?;
+                Some(RouteHint(vec![RouteHintHop {
+                    src_node_id: details.counterparty.node_id,
+                    short_channel_id: details.get_inbound_payment_scid()?,
+                    cltv_expiry_delta: config.cltv_expiry_delta,
+                    htlc_maximum_msat: None,
+                    htlc_minimum_msat: None,
+                    fees: RoutingFees {
+                        base_msat: config.fee_base_msat,
+                        proportional_millionths: config.fee_proportional_millionths,
+                    },
+                    htlc_maximum_rgb: swap_info
+                        .from_asset
+                        .and(asset_htlc_limits.get(&details.channel_id).copied()),
+                }]))
+            })
+            .collect();
+
+        let rgb_payment = swap_info
+            .to_asset
+            .map(|to_asset| (to_asset, swap_info.qty_to));
+        let first_leg = get_route(
+            &unlocked_state.channel_manager,
+            &unlocked_state.router,
+            unlocked_state.channel_manager.get_our_node_id(),
+            taker_pk,
+            if swap_info.is_to_btc() {
+                Some(swap_info.qty_to + HTLC_MIN_MSAT)
+            } else {
+                Some(HTLC_MIN_MSAT)
+            },
+            rgb_payment,
+            vec![],
+            first_leg_cltv_expiry_delta,
+            None,
+        );
+
+        let rgb_payment = swap_info
+            .from_asset
+            .map(|from_asset| (from_asset, swap_info.qty_from));
+        let second_leg = get_route(
+            &unlocked_state.channel_manager,
+            &unlocked_state.router,
+            taker_pk,
+            unlocked_state.channel_manager.get_our_node_id(),
+            if swap_info.is_to_btc() || swap_info.is_asset_asset() {
+                Some(HTLC_MIN_MSAT)
+            } else {
+                Some(swap_info.qty_from + HTLC_MIN_MSAT)
+            },
+            rgb_payment,
+            receive_hints,
+            second_leg_cltv_expiry_delta,
+            None,
+        );
+
+        let Some(mut first_leg) = first_leg else {
+            return Err(APIError::NoSwapRoute(format!(
+                "first ({} from maker to taker)",
+                swap_info.to_asset.map_or(s!("BTC"), |a| a.to_string())
+            )));
+        };
+        let Some(mut second_leg) = second_leg else {
+            return Err(APIError::NoSwapRoute(format!(
+                "second ({} from taker to maker)",
+                swap_info.from_asset.map_or(s!("BTC"), |a| a.to_string())
+            )));
+        };
+
+        // Set swap flag
+        second_leg.paths[0].hops[0].short_channel_id |= IS_SWAP_SCID;
+
+        // Generally in the last hop the fee_amount is set to the payment amount, so we need to
+        // override it with fee = 0
+        first_leg.paths[0]
+            .hops
+            .last_mut()
+            .expect("Path not to be empty")
+            .fee_msat = 0;
+
+        let fullpaths = first_leg.paths[0]
+            .hops
+            .clone()
+            .into_iter()
+            .map(|mut hop| {
+                if swap_info.is_to_asset() {
+                    hop.rgb_amount = Some(swap_info.qty_to);
+                }
+                hop
+            })
+            .chain(second_leg.paths[0].hops.clone().into_iter().map(|mut hop| {
+                if swap_info.is_from_asset() {
+                    hop.rgb_amount = Some(swap_info.qty_from);
+                }
+                hop
+            }))
+            .collect::<Vec<_>>();
+
+        // Each leg is routed within the max total CLTV expiry delta, check the whole swap is too
+        // so it fails now instead of being rejected mid-swap
+        let total_cltv_expiry_delta = fullpaths
+            .iter()
+            .map(|hop| hop.cltv_expiry_delta)
+            .sum::<u32>();
+        if total_cltv_expiry_delta > DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA {
+            let current_height = unlocked_state.channel_manager.current_best_block().height;
+            return Err(APIError::InvalidSwap(format!(
+                "CLTV budget exceeded: the {} hops would lock funds until block {} ({} blocks), \
+                max is {} blocks",
+                fullpaths.len(),
+                current_height + total_cltv_expiry_delta,
+                total_cltv_expiry_delta,
+                DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA,
+            )));
+        }
+
+        // Skip last fee because it's equal to the payment amount
+        let total_fee = fullpaths
+            .iter()
+            .rev()
+            .skip(1)
+            .map(|hop| hop.fee_msat)
+            .sum::<u64>();
+
+        if total_fee >= MAX_SWAP_FEE_MSAT {
+            return Err(APIError::FailedPayment(format!(
+                "Fee too high: {}",
+                total_fee
+            )));
+        }
+
+        let route = Route {
+            paths: vec![LnPath {
+                hops: fullpaths,
+                blinded_tail: None,
+            }],
+            route_params: Some(RouteParameters {
+                payment_params: PaymentParameters::for_keysend(
+                    unlocked_state.channel_manager.get_our_node_id(),
+                    second_leg_cltv_expiry_delta,
+                    false,
+                ),
+                // This value is not used anywhere, it's set by the router
+                // when creating a route, but here we are creating it manually
+                // by composing a pre-existing list of hops
+                final_value_msat: 0,
+                max_total_routing_fee_msat: None,
+                // This value is not used anywhere, same as final_value_msat
+                rgb_payment: None,
+            }),
+        };
+
+        // only swaps still waiting can be executed
+        unlocked_state.update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Pending)?;
+
+        if swap_info.is_to_asset() {
+            write_rgb_payment_info_file(
+                &state.static_state.ldk_data_dir,
+                &swapstring.payment_hash,
+                swap_info.to_asset.unwrap(),
+                swap_info.qty_to,
+                true,
+                false,
+            );
+        }
+
+        unlocked_state.set_maker_swap_fill(
+            &swapstring.payment_hash,
+            swap_info.qty_from,
+            swap_info.qty_to,
+        )?;
+
+        let (_status, err) = match unlocked_state.channel_manager.send_spontaneous_payment(
+            &route,
+            Some(payment_preimage),
+            RecipientOnionFields::spontaneous_empty(),
+            PaymentId(swapstring.payment_hash.0),
+        ) {
+            Ok(_payment_hash) => {
+                tracing::debug!("EVENT: initiated swap");
+                (HTLCStatus::Pending, None)
+            }
+            Err(e) => {
+                tracing::warn!("ERROR: failed to send payment: {:?}", e);
+                (HTLCStatus::Failed, Some(e))
+            }
+        };
+
+        match err {
+            None => Ok(Json(EmptyResponse {})),
+            Some(e) => {
+                let _ = unlocked_state.fail_maker_swap(
+                    &swapstring.payment_hash,
+                    format!("failed to send the payment: {e:?}"),
+                );
+                Err(APIError::FailedPayment(format!("{:?}", e)))
+            }
+        }
+    })
+    .await
+}
+
+pub(crate) async fn maker_init(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<MakerInitRequest>, APIError>,
+) -> Result<Json<MakerInitResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let from_asset = match &payload.from_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        let to_asset = match &payload.to_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        // prevent BTC-to-BTC swaps
+        if from_asset.is_none() && to_asset.is_none() {
+            return Err(APIError::InvalidSwap(s!("cannot swap BTC for BTC")));
+        }
+
+        // prevent swaps of same assets
+        if from_asset == to_asset {
+            return Err(APIError::InvalidSwap(s!("cannot swap the same asset")));
+        }
+
+        // Reject takers that are known not to support the swap protocol or the pair, if the taker
+        // is neither a peer nor announced we cannot know in advance
+        if let Some(taker_pubkey) = &payload.taker_pubkey {
+            let taker_pk =
+                hex_str_to_compressed_pubkey(taker_pubkey).ok_or(APIError::InvalidPubkey)?;
+            let taker_features =
+                if let Some(peer) = unlocked_state.peer_manager.peer_by_node_id(&taker_pk) {
+                    Some(peer.init_features.le_flags().to_vec())
+                } else {
+                    unlocked_state
+                        .network_graph
+                        .read_only()
+                        .nodes()
+                        .get(&NodeId::from_pubkey(&taker_pk))
+                        .and_then(|n| n.announcement_info.as_ref())
+                        .map(|a| a.features.le_flags().to_vec())
+                };
+            if let Some(taker_features) = taker_features {
+                if !supports_swap_protocol(&taker_features) {
+                    return Err(APIError::UnsupportedSwapProtocol);
+                }
+            }
+            let pair = SwapPair {
+                from_asset,
+                to_asset,
+            };
+            if let Some(taker_pairs) = unlocked_state
+                .peer_message_handler
+                .peer_swap_pairs(&taker_pk)
+            {
+                if !taker_pairs.contains(&pair) {
+                    return Err(APIError::UnsupportedSwapPair);
+                }
+            }
+        }
+
+        let qty_from = payload.qty_from;
+        let qty_to = payload.qty_to;
+
+        let expiry = get_current_timestamp() + payload.timeout_sec as u64;
+        let swap_info = SwapInfo {
+            from_asset,
+            to_asset,
+            qty_from,
+            qty_to,
+            expiry,
+            partial_fill: payload.partial_fill.unwrap_or(false),
+        };
+        let swap_data = SwapData::create_from_swap_info(&swap_info);
+
+        // Check that we have enough assets to send
+        if let Some(to_asset) = to_asset {
+            let max_balance = get_max_local_rgb_amount(
+                to_asset,
+                &state.static_state.ldk_data_dir,
+                unlocked_state.channel_manager.list_channels().iter(),
+            );
+            if swap_info.qty_to > max_balance {
+                return Err(APIError::InsufficientAssets);
+            }
+        }
+
+        let (payment_hash, payment_secret) = unlocked_state
+            .channel_manager
+            .create_inbound_payment(Some(DUST_LIMIT_MSAT), payload.timeout_sec, None)
+            .unwrap();
+        unlocked_state.add_maker_swap(payment_hash, swap_data)?;
+
+        let swapstring = SwapString::from_swap_info(&swap_info, payment_hash).to_string();
+
+        let payment_secret = payment_secret.0.as_hex().to_string();
+        let payment_hash = payment_hash.0.as_hex().to_string();
+        Ok(Json(MakerInitResponse {
+            payment_hash,
+            payment_secret,
+            swapstring,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn max_sendable_asset(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<MaxSendableAssetRequest>, APIError>,
+) -> Result<Json<MaxSendableAssetResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let contract_id = ContractId::from_str(&payload.asset_id)
+        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+    let dest_pubkey = match payload.dest_pubkey {
+        Some(pubkey) => Some(hex_str_to_compressed_pubkey(&pubkey).ok_or(APIError::InvalidPubkey)?),
+        None => None,
+    };
+
+    let mut max_path_amount = 0;
+    let mut max_mpp_amount = 0;
+    for chan_info in unlocked_state.channel_manager.list_usable_channels() {
+        let local_rgb_amount = match get_rgb_channel_info_optional(
+            &chan_info.channel_id,
+            &state.static_state.ldk_data_dir,
+            false,
+        ) {
+            Some((rgb_info, _)) if rgb_info.contract_id == contract_id => rgb_info.local_rgb_amount,
+            _ => continue,
+        };
+        let sendable = match dest_pubkey {
+            Some(dest_pubkey) => max_routable_rgb_amount(
+                &unlocked_state,
+                &chan_info,
+                dest_pubkey,
+                contract_id,
+                local_rgb_amount,
+            ),
+            None => local_rgb_amount,
+        };
+        max_path_amount = max_path_amount.max(sendable);
+        // paths leaving through different channels may share later hops, so with a destination
+        // this is an upper bound
+        max_mpp_amount += sendable;
+    }
+
+    Ok(Json(MaxSendableAssetResponse {
+        max_path_amount,
+        max_mpp_amount,
+    }))
+}
+
+/// Largest asset amount, up to the local one, that can be routed to the destination leaving
+/// through the given channel
+fn max_routable_rgb_amount(
+    unlocked_state: &UnlockedAppState,
+    chan_info: &ChannelDetails,
+    dest_pubkey: PublicKey,
+    contract_id: ContractId,
+    local_rgb_amount: u64,
+) -> u64 {
+    let is_routable = |rgb_amount: u64| {
+        get_route(
+            &unlocked_state.channel_manager,
+            &unlocked_state.router,
+            unlocked_state.channel_manager.get_our_node_id(),
+            dest_pubkey,
+            None,
+            Some((contract_id, rgb_amount)),
+            vec![],
+            DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
+            Some(&[chan_info]),
+        )
+        .is_some()
+    };
+    if local_rgb_amount == 0 || is_routable(local_rgb_amount) {
+        return local_rgb_amount;
+    }
+    // binary search of the largest routable amount, the local one is known not to be
+    let (mut routable, mut unroutable) = (0, local_rgb_amount);
+    while unroutable - routable > 1 {
+        let mid = routable + (unroutable - routable) / 2;
+        if is_routable(mid) {
+            routable = mid;
+        } else {
+            unroutable = mid;
+        }
+    }
+    routable
+}
+
+fn graph_channel_update(update: &ChannelUpdateInfo) -> GraphChannelUpdate {
+    GraphChannelUpdate {
+        enabled: update.enabled,
+        cltv_expiry_delta: update.cltv_expiry_delta,
+        htlc_minimum_msat: update.htlc_minimum_msat,
+        htlc_maximum_msat: update.htlc_maximum_msat,
+        fee_base_msat: update.fees.base_msat,
+        fee_proportional_millionths: update.fees.proportional_millionths,
+        last_update: update.last_update,
+    }
+}
+
+fn graph_channel(
+    short_channel_id: u64,
+    chan_info: &ChannelInfo,
+    rgb_assets: &HashMap<u64, String>,
+) -> GraphChannel {
+    GraphChannel {
+        short_channel_id,
+        node_one: chan_info.node_one.to_string(),
+        node_two: chan_info.node_two.to_string(),
+        capacity_sat: chan_info.capacity_sats,
+        one_to_two: chan_info.one_to_two.as_ref().map(graph_channel_update),
+        two_to_one: chan_info.two_to_one.as_ref().map(graph_channel_update),
+        asset_id: rgb_assets.get(&short_channel_id).cloned(),
+    }
+}
+
+fn graph_node(node_id: &NodeId, node_info: &NodeInfo) -> GraphNode {
+    let (alias, addresses, last_update) = match &node_info.announcement_info {
+        Some(announcement) => (
+            Some(announcement.alias.to_string()),
+            announcement
+                .addresses()
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+            Some(announcement.last_update),
+        ),
+        None => (None, vec![], None),
+    };
+    GraphNode {
+        node_id: node_id.to_string(),
+        alias,
+        addresses,
+        last_update,
+        short_channel_ids: node_info.channels.clone(),
+    }
+}
+
+fn network_graph_to_dot(nodes: &[GraphNode], channels: &[GraphChannel]) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut dot = String::from("graph network {\n");
+    for node in nodes {
+        let label = node.alias.as_deref().unwrap_or(&node.node_id);
+        dot.push_str(&format!(
+            "  \"{}\" [label=\"{}\"];\n",
+            node.node_id,
+            escape(label)
+        ));
+    }
+    for channel in channels {
+        let mut attrs = format!("label=\"{}\"", channel.short_channel_id);
+        if let Some(capacity_sat) = channel.capacity_sat {
+            attrs.push_str(&format!(", capacity_sat={capacity_sat}"));
+        }
+        if let Some(asset_id) = &channel.asset_id {
+            attrs.push_str(&format!(", asset_id=\"{}\", color=red", escape(asset_id)));
+        }
+        dot.push_str(&format!(
+            "  \"{}\" -- \"{}\" [{attrs}];\n",
+            channel.node_one, channel.node_two
+        ));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Map the short channel IDs of our RGB channels to their asset ID, the only RGB information
+/// known about graph channels
+fn rgb_channel_assets(state: &AppState, unlocked_state: &UnlockedAppState) -> HashMap<u64, String> {
+    unlocked_state
+        .channel_manager
+        .list_channels()
+        .into_iter()
+        .filter_map(|c| {
+            let scid = c.short_channel_id?;
+            let (rgb_info, _) = get_rgb_channel_info_optional(
+                &c.channel_id,
+                &state.static_state.ldk_data_dir,
+                false,
+            )?;
+            Some((scid, rgb_info.contract_id.to_string()))
+        })
+        .collect()
+}
+
+pub(crate) async fn network_graph_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<NetworkGraphChannelRequest>, APIError>,
+) -> Result<Json<NetworkGraphChannelResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let rgb_assets = rgb_channel_assets(&state, &unlocked_state);
+    let graph = unlocked_state.network_graph.read_only();
+    let chan_info = graph
+        .channel(payload.short_channel_id)
+        .ok_or(APIError::UnknownGraphChannel)?;
+
+    Ok(Json(NetworkGraphChannelResponse {
+        channel: graph_channel(payload.short_channel_id, chan_info, &rgb_assets),
+    }))
+}
+
+pub(crate) async fn network_graph_export(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<NetworkGraphExportRequest>, APIError>,
+) -> Result<Json<NetworkGraphExportResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let rgb_assets = rgb_channel_assets(&state, &unlocked_state);
+    let (nodes, channels) = {
+        let graph = unlocked_state.network_graph.read_only();
+        let nodes: Vec<GraphNode> = graph
+            .nodes()
+            .unordered_iter()
+            .map(|(node_id, node_info)| graph_node(node_id, node_info))
+            .collect();
+        let channels: Vec<GraphChannel> = graph
+            .channels()
+            .unordered_iter()
+            .map(|(scid, chan_info)| graph_channel(*scid, chan_info, &rgb_assets))
+            .collect();
+        (nodes, channels)
+    };
+
+    Ok(Json(match payload.format {
+        GraphExportFormat::Json => NetworkGraphExportResponse {
+            nodes,
+            channels,
+            dot: None,
+        },
+        GraphExportFormat::Dot => NetworkGraphExportResponse {
+            dot: Some(network_graph_to_dot(&nodes, &channels)),
+            nodes: vec![],
+            channels: vec![],
+        },
+    }))
+}
+
+pub(crate) async fn network_graph_node(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<NetworkGraphNodeRequest>, APIError>,
+) -> Result<Json<NetworkGraphNodeResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let node_pubkey = PublicKey::from_str(&payload.node_id).map_err(|_| APIError::InvalidPubkey)?;
+    let node_id = NodeId::from_pubkey(&node_pubkey);
+
+    let rgb_assets = rgb_channel_assets(&state, &unlocked_state);
+    let graph = unlocked_state.network_graph.read_only();
+    let node_info = graph.node(&node_id).ok_or(APIError::UnknownGraphNode)?;
+    let channels = node_info
+        .channels
+        .iter()
+        .filter_map(|scid| {
+            graph
+                .channel(*scid)
+                .map(|chan_info| graph_channel(*scid, chan_info, &rgb_assets))
+        })
+        .collect();
+
+    Ok(Json(NetworkGraphNodeResponse {
+        node: graph_node(&node_id, node_info),
+        channels,
+    }))
+}
+
+pub(crate) async fn network_info(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NetworkInfoResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let best_block = unlocked_state.channel_manager.current_best_block();
+
+    Ok(Json(NetworkInfoResponse {
+        network: state.static_state.network.into(),
+        height: best_block.height,
+    }))
+}
+
+pub(crate) async fn node_info(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NodeInfoResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let chans = unlocked_state.channel_manager.list_channels();
+
+    Ok(Json(NodeInfoResponse {
+        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
+        num_channels: chans.len(),
+        num_usable_channels: chans.iter().filter(|c| c.is_usable).count(),
+        local_balance_msat: chans.iter().map(|c| c.balance_msat).sum::<u64>(),
+        num_peers: unlocked_state.peer_manager.list_peers().len(),
+        onchain_pubkey: unlocked_state.rgb_get_wallet_data().pubkey,
+        max_media_upload_size_mb: state.static_state.max_media_upload_size_mb,
+        rgb_htlc_min_msat: HTLC_MIN_MSAT,
+        channel_capacity_min_sat: OPENCHANNEL_MIN_SAT,
+        channel_capacity_max_sat: OPENCHANNEL_MAX_SAT,
+        channel_asset_min_amount: OPENCHANNEL_MIN_RGB_AMT,
+        channel_asset_max_amount: u64::MAX,
+        swap_pairs: unlocked_state
+            .peer_message_handler
+            .swap_pairs()
+            .iter()
+            .map(SwapPairInfo::from)
+            .collect(),
+    }))
+}
+
+pub(crate) async fn open_channel(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ListAssetsRequest>, APIError>,
-) -> Result<Json<ListAssetsResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<OpenChannelRequest>, APIError>,
+) -> Result<Json<OpenChannelResponse>, APIError> {
+    no_cancel(async move { Ok(Json(do_open_channel(state, payload, false).await?)) }).await
+}
+
+/// Open a channel as requested, shared by the openchannel, openchannels and approvechannelrequest
+/// APIs. The RGB send lock is handled by the caller for channels opened in a batch.
+pub(crate) async fn do_open_channel(
+    state: Arc<AppState>,
+    payload: OpenChannelRequest,
+    in_batch: bool,
+) -> Result<OpenChannelResponse, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let rgb_assets = unlocked_state.rgb_list_assets(
-        payload
-            .filter_asset_schemas
-            .into_iter()
-            .map(|s| s.into())
-            .collect(),
-    )?;
+    if !in_batch && *unlocked_state.rgb_send_lock.lock().unwrap() {
+        return Err(APIError::OpenChannelInProgress);
+    }
 
-    let nia = rgb_assets
-        .nia
-        .map(|assets| assets.into_iter().map(|a| a.into()).collect());
-    let uda = rgb_assets
-        .uda
-        .map(|assets| assets.into_iter().map(|a| a.into()).collect());
-    let cfa = rgb_assets
-        .cfa
-        .map(|assets| assets.into_iter().map(|a| a.into()).collect());
+    if unlocked_state
+        .node_id_rotation()
+        .is_some_and(|r| r.status != NodeIdRotationStatus::Completed)
+    {
+        return Err(APIError::CannotOpenChannel(s!(
+            "a node ID rotation is in progress"
+        )));
+    }
 
-    Ok(Json(ListAssetsResponse { nia, uda, cfa }))
-}
+    let temporary_channel_id = if let Some(tmp_chan_id_str) = payload.temporary_channel_id {
+        let tmp_chan_id = check_channel_id(&tmp_chan_id_str)?;
+        if unlocked_state.channel_ids().contains_key(&tmp_chan_id) {
+            return Err(APIError::TemporaryChannelIdAlreadyUsed);
+        }
+        Some(tmp_chan_id)
+    } else {
+        None
+    };
 
-pub(crate) async fn list_channels(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<ListChannelsResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    // the pushed amount goes to the peer. It's refused above the limits rather than held, as the
+    // channels opened for fee orders and channel requests have no one to confirm it
+    let reservation = if payload.push_msat > 0 {
+        let spend = SpendData::from_msat(payload.push_msat, None, None);
+        Some(charge_spend_limits(&state, &unlocked_state, spend)?)
+    } else {
+        None
+    };
 
-    let mut channels = vec![];
-    for chan_info in unlocked_state.channel_manager.list_channels() {
-        let mut channel = Channel {
-            channel_id: chan_info.channel_id.0.as_hex().to_string(),
-            peer_pubkey: hex_str(&chan_info.counterparty.node_id.serialize()),
-            ready: chan_info.is_channel_ready,
-            capacity_sat: chan_info.channel_value_satoshis,
-            local_balance_msat: chan_info.balance_msat,
-            outbound_balance_msat: chan_info.outbound_capacity_msat,
-            inbound_balance_msat: chan_info.inbound_capacity_msat,
-            next_outbound_htlc_limit_msat: chan_info.next_outbound_htlc_limit_msat,
-            next_outbound_htlc_minimum_msat: chan_info.next_outbound_htlc_minimum_msat,
-            is_usable: chan_info.is_usable,
-            public: chan_info.is_public,
-            ..Default::default()
-        };
+    let colored_info = match (payload.asset_id, payload.asset_amount) {
+        (Some(_), Some(amt)) if amt < OPENCHANNEL_MIN_RGB_AMT => {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel RGB amount must be equal or higher than {OPENCHANNEL_MIN_RGB_AMT}"
+            )));
+        }
+        (Some(asset), Some(amt)) => {
+            let asset =
+                ContractId::from_str(&asset).map_err(|_| APIError::InvalidAssetID(asset))?;
+            Some((asset, amt))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(APIError::IncompleteRGBInfo);
+        }
+    };
 
-        if let Some(funding_txo) = chan_info.funding_txo {
-            channel.funding_txid = Some(funding_txo.txid.to_string());
+    let fee_rate = get_fee_rate(state.static_state.fee_rate, payload.fee_rate)?;
+
+    // the peer fetches the funding consignment from the proxy given here, so it's chosen now
+    let proxy_endpoint = if colored_info.is_some() {
+        Some(
+            unlocked_state
+                .select_proxy_endpoint(&state.static_state.proxy_endpoints)
+                .await?,
+        )
+    } else {
+        None
+    };
+
+    let change_script = if let Some(change_address) = payload.change_address {
+        if colored_info.is_some() {
+            return Err(APIError::CannotOpenChannel(s!(
+                "a change address can only be set for vanilla channels"
+            )));
         }
+        let address = Address::from_str(&change_address)
+            .map_err(|e| APIError::InvalidAddress(e.to_string()))?
+            .require_network(state.static_state.network)
+            .map_err(|e| APIError::InvalidAddress(e.to_string()))?;
+        Some(address.script_pubkey())
+    } else {
+        None
+    };
 
-        if let Some(node_info) = unlocked_state
-            .network_graph
-            .read_only()
-            .nodes()
-            .get(&NodeId::from_pubkey(&chan_info.counterparty.node_id))
-        {
-            if let Some(announcement) = &node_info.announcement_info {
-                channel.peer_alias = Some(announcement.alias.to_string());
+    // the close address is enforced by us, as the upfront shutdown script LDK would commit to with
+    // the peer is derived from the node keys
+    let close_address = if let Some(close_address) = payload.close_address {
+        // RGB allocations can only be moved to outputs of our wallet
+        if colored_info.is_some() {
+            return Err(APIError::CannotOpenChannel(s!(
+                "a close address can only be set for vanilla channels"
+            )));
+        }
+        let address = Address::from_str(&close_address)
+            .map_err(|e| APIError::InvalidAddress(e.to_string()))?
+            .require_network(state.static_state.network)
+            .map_err(|e| APIError::InvalidAddress(e.to_string()))?;
+        ShutdownScript::try_from(address.script_pubkey())
+            .map_err(|_| APIError::InvalidAddress(s!("unsupported close address type")))?;
+        Some(address.to_string())
+    } else {
+        None
+    };
+
+    if payload.capacity_sat < OPENCHANNEL_MIN_SAT {
+        return Err(APIError::InvalidAmount(format!(
+            "Channel amount must be equal or higher than {OPENCHANNEL_MIN_SAT}"
+        )));
+    }
+    if payload.capacity_sat > OPENCHANNEL_MAX_SAT {
+        return Err(APIError::InvalidAmount(format!(
+            "Channel amount must be equal or less than {OPENCHANNEL_MAX_SAT}"
+        )));
+    }
+
+    let funding_utxos = if let Some(funding_outpoints) = payload.funding_outpoints {
+        // RGB funding transactions are built by rgb-lib, which selects the inputs by itself
+        if colored_info.is_some() {
+            return Err(APIError::CannotOpenChannel(s!(
+                "funding outpoints can only be set for vanilla channels"
+            )));
+        }
+        let utxos = unlocked_state.vanilla_utxos(&parse_funding_outpoints(funding_outpoints)?)?;
+        // check the UTXOs cover the channel, the funding script is not known yet
+        let funding_output = TxOut {
+            value: payload.capacity_sat,
+            script_pubkey: placeholder_funding_script(),
+        };
+        funding_psbt_from_utxos(
+            &utxos,
+            vec![funding_output],
+            change_script
+                .clone()
+                .unwrap_or_else(placeholder_funding_script),
+            fee_rate,
+        )?;
+        Some(utxos)
+    } else {
+        None
+    };
+
+    if !payload.with_anchors {
+        return Err(APIError::AnchorsRequired);
+    }
+
+    let htlc_limits = HtlcLimits {
+        max_htlc_value_in_flight_percent: payload.max_htlc_value_in_flight_percent,
+        max_accepted_htlcs: payload.max_accepted_htlcs,
+        htlc_minimum_msat: payload.htlc_minimum_msat,
+        channel_reserve_proportional_millionths: payload.channel_reserve_proportional_millionths,
+    }
+    .or(state.static_state.htlc_limits);
+    htlc_limits
+        .validate()
+        .map_err(APIError::InvalidHtlcLimits)?;
+
+    let (peer_pubkey, mut peer_addr) =
+        parse_peer_info(payload.peer_pubkey_and_opt_addr.to_string())?;
+    unlocked_state.check_peer_policy(&peer_pubkey)?;
+
+    let peer_data_path = state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA);
+    if peer_addr.is_none() {
+        if let Some(peer) = unlocked_state.peer_manager.peer_by_node_id(&peer_pubkey) {
+            peer_addr = peer.socket_address;
+        }
+    }
+    if peer_addr.is_none() {
+        let peer_info =
+            disk::read_channel_peer_data(&peer_data_path, unlocked_state.kv_store.cipher())?;
+        for (pubkey, addr) in peer_info.into_iter() {
+            if pubkey == peer_pubkey {
+                peer_addr = Some(addr);
+                break;
             }
         }
+    }
+    if let Some(peer_addr) = peer_addr {
+        connect_peer_if_necessary(
+            peer_pubkey,
+            peer_addr.clone(),
+            state.static_state.tor_proxy,
+            unlocked_state.peer_manager.clone(),
+        )
+        .await?;
+        disk::persist_channel_peer(
+            &peer_data_path,
+            &peer_pubkey,
+            &peer_addr,
+            unlocked_state.kv_store.cipher(),
+        )?;
+    } else {
+        return Err(APIError::InvalidPeerInfo(s!(
+            "cannot find the address for the provided pubkey"
+        )));
+    }
 
-        if let Some(id) = chan_info.short_channel_id {
-            channel.short_channel_id = Some(id);
+    let mut channel_config = ChannelConfig::default();
+    if let Some(fee_base_msat) = payload.fee_base_msat {
+        channel_config.forwarding_fee_base_msat = fee_base_msat;
+    }
+    if let Some(fee_proportional_millionths) = payload.fee_proportional_millionths {
+        channel_config.forwarding_fee_proportional_millionths = fee_proportional_millionths;
+    }
+    let announced_channel = payload.public.unwrap_or(*state.get_announce_channels());
+    let mut config = UserConfig {
+        channel_handshake_limits: ChannelHandshakeLimits {
+            // lnd's max to_self_delay is 2016, so we want to be compatible.
+            their_to_self_delay: 2016,
+            ..Default::default()
+        },
+        channel_handshake_config: ChannelHandshakeConfig {
+            announced_channel,
+            // private channels are only known by their aliases outside of the peer
+            negotiate_scid_privacy: !announced_channel,
+            our_htlc_minimum_msat: HTLC_MIN_MSAT,
+            minimum_depth: MIN_CHANNEL_CONFIRMATIONS as u32,
+            negotiate_anchors_zero_fee_htlc_tx: payload.with_anchors,
+            // LDK would refuse to close to any other script than the committed one
+            commit_upfront_shutdown_pubkey: close_address.is_none(),
+            ..Default::default()
+        },
+        channel_config,
+        ..Default::default()
+    };
+    htlc_limits.apply(&mut config.channel_handshake_config);
+
+    let consignment_endpoint = if let Some((contract_id, asset_amount)) = &colored_info {
+        let balance = unlocked_state.rgb_get_asset_balance(*contract_id)?;
+        let spendable_rgb_amount = balance.spendable;
+
+        if *asset_amount > spendable_rgb_amount {
+            return Err(APIError::InsufficientAssets);
         }
 
-        let info_file_path = get_rgb_channel_info_path(
-            &chan_info.channel_id.0.as_hex().to_string(),
-            &state.static_state.ldk_data_dir,
-            false,
+        // the allocations of channels are fungible, so they can carry RGB20 and RGB25 assets
+        if matches!(
+            unlocked_state
+                .rgb_wallet_wrapper
+                .get_asset_iface(*contract_id)?,
+            RgbLibAssetIface::RGB21
+        ) {
+            return Err(APIError::CannotOpenChannel(s!(
+                "UDA assets cannot be moved to channels"
+            )));
+        }
+
+        Some(RgbTransport::from_str(proxy_endpoint.as_ref().expect("colored channel")).unwrap())
+    } else {
+        None
+    };
+
+    if let Some((contract_id, asset_amount)) = &colored_info {
+        let mut fake_p2wsh: [u8; 34] = [0; 34];
+        fake_p2wsh[1] = 32;
+        let script_buf = ScriptBuf::from_bytes(fake_p2wsh.to_vec());
+        let recipient_id =
+            recipient_id_from_script_buf(script_buf, state.static_state.network.into());
+        let asset_id = contract_id.to_string();
+        let recipient_map = map! {
+            asset_id => vec![Recipient {
+                recipient_id,
+                witness_data: Some(WitnessData {
+                    amount_sat: payload.capacity_sat,
+                    blinding: Some(STATIC_BLINDING + 1),
+                }),
+                amount: *asset_amount,
+                transport_endpoints: vec![proxy_endpoint.clone().expect("colored channel")]
+        }]};
+
+        let unlocked_state_copy = unlocked_state.clone();
+        tokio::task::spawn_blocking(move || {
+            unlocked_state_copy.rgb_send_begin(
+                recipient_map,
+                true,
+                fee_rate,
+                MIN_CHANNEL_CONFIRMATIONS,
+            )
+        })
+        .await
+        .unwrap()
+        .map_err(|e| APIError::CannotOpenChannel(format!("{:?}", e)))?;
+    }
+
+    // the change destination, funding UTXOs, proxy and fee rate are looked up by temporary channel
+    // ID when funding
+    let temporary_channel_id = if change_script.is_some()
+        || funding_utxos.is_some()
+        || proxy_endpoint.is_some()
+        || payload.fee_rate.is_some()
+        || payload.external_signing
+    {
+        Some(temporary_channel_id.unwrap_or_else(|| {
+            ChannelId::temporary_from_entropy_source(&*unlocked_state.keys_manager)
+        }))
+    } else {
+        temporary_channel_id
+    };
+    let change_outpoint_receiver = if let Some(script) = change_script {
+        let (outpoint_sender, outpoint_receiver) = oneshot::channel();
+        unlocked_state.get_funding_changes().insert(
+            temporary_channel_id.expect("set above"),
+            FundingChange {
+                script,
+                outpoint_sender,
+            },
         );
-        if info_file_path.exists() {
-            let rgb_info = parse_rgb_channel_info(&info_file_path);
-            channel.asset_id = Some(rgb_info.contract_id.to_string());
-            channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
-            channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
-        };
+        Some(outpoint_receiver)
+    } else {
+        None
+    };
+    if let Some(utxos) = funding_utxos {
+        unlocked_state
+            .get_funding_utxos()
+            .insert(temporary_channel_id.expect("set above"), utxos);
+    }
+    if let Some(proxy_endpoint) = proxy_endpoint {
+        unlocked_state
+            .get_funding_proxies()
+            .insert(temporary_channel_id.expect("set above"), proxy_endpoint);
+    }
+    if payload.fee_rate.is_some() {
+        unlocked_state
+            .get_funding_fee_rates()
+            .insert(temporary_channel_id.expect("set above"), fee_rate);
+    }
+    if payload.external_signing {
+        unlocked_state
+            .get_external_funding()
+            .insert(temporary_channel_id.expect("set above"));
+    }
 
-        channels.push(channel);
+    if !in_batch {
+        *unlocked_state.rgb_send_lock.lock().unwrap() = true;
+        tracing::debug!("RGB send lock set to true");
     }
 
-    Ok(Json(ListChannelsResponse { channels }))
+    let temporary_channel_id = unlocked_state
+        .channel_manager
+        .create_channel(
+            peer_pubkey,
+            payload.capacity_sat,
+            payload.push_msat,
+            0,
+            temporary_channel_id,
+            Some(config),
+            consignment_endpoint,
+        )
+        .map_err(|e| {
+            if let Some(temporary_channel_id) = temporary_channel_id {
+                unlocked_state
+                    .get_funding_changes()
+                    .remove(&temporary_channel_id);
+                unlocked_state
+                    .get_funding_utxos()
+                    .remove(&temporary_channel_id);
+                unlocked_state
+                    .get_funding_proxies()
+                    .remove(&temporary_channel_id);
+                unlocked_state
+                    .get_funding_fee_rates()
+                    .remove(&temporary_channel_id);
+                unlocked_state
+                    .get_external_funding()
+                    .remove(&temporary_channel_id);
+            }
+            if !in_batch {
+                *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                tracing::debug!("RGB send lock set to false (open channel failure: {e:?})");
+            }
+            APIError::FailedOpenChannel(format!("{:?}", e))
+        })?;
+    if let Some(reservation) = reservation {
+        reservation.commit();
+    }
+    if close_address.is_some() {
+        unlocked_state.set_close_address(temporary_channel_id, close_address)?;
+    }
+    let temporary_channel_id = temporary_channel_id.0.as_hex().to_string();
+    tracing::info!("EVENT: initiated channel with peer {}", peer_pubkey);
+
+    // wait for the funding transaction to be built in order to report the change outpoint
+    let change_outpoint = if let Some(outpoint_receiver) = change_outpoint_receiver {
+        tokio::time::timeout(
+            Duration::from_secs(OPENCHANNEL_FUNDING_TIMEOUT_SECS),
+            outpoint_receiver,
+        )
+        .await
+        .ok()
+        .and_then(|res| res.ok())
+        .map(|outpoint| outpoint.to_string())
+    } else {
+        None
+    };
+
+    if let Some((contract_id, asset_amount)) = &colored_info {
+        let rgb_info = RgbInfo {
+            contract_id: *contract_id,
+            local_rgb_amount: *asset_amount,
+            remote_rgb_amount: 0,
+        };
+        write_rgb_channel_info(
+            &get_rgb_channel_info_path(
+                &temporary_channel_id,
+                &state.static_state.ldk_data_dir,
+                true,
+            ),
+            &rgb_info,
+        );
+        write_rgb_channel_info(
+            &get_rgb_channel_info_path(
+                &temporary_channel_id,
+                &state.static_state.ldk_data_dir,
+                false,
+            ),
+            &rgb_info,
+        );
+    }
+
+    Ok(OpenChannelResponse {
+        temporary_channel_id,
+        change_outpoint,
+    })
 }
 
-pub(crate) async fn list_payments(
+pub(crate) async fn open_channels(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListPaymentsResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<OpenChannelsRequest>, APIError>,
+) -> Result<Json<OpenChannelsResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let inbound_payments = unlocked_state.inbound_payments();
-    let outbound_payments = unlocked_state.outbound_payments();
-    let mut payments = vec![];
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
 
-    for (payment_hash, payment_info) in &inbound_payments {
-        let rgb_payment_info_path_inbound =
-            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, true);
+        if payload.channels.is_empty() {
+            return Err(APIError::InvalidChannelBatch(s!(
+                "at least one channel must be given"
+            )));
+        }
+        let fee_rate = get_fee_rate(state.static_state.fee_rate, payload.fee_rate)?;
+        let mut temporary_channel_ids = vec![];
+        let mut funding_outputs = vec![];
+        for channel in &payload.channels {
+            // RGB funding transactions are built by rgb-lib, one per channel
+            if channel.asset_id.is_some() || channel.asset_amount.is_some() {
+                return Err(APIError::CannotOpenChannel(s!(
+                    "only vanilla channels can be opened in a batch"
+                )));
+            }
+            if channel.change_address.is_some() || channel.funding_outpoints.is_some() {
+                return Err(APIError::InvalidChannelBatch(s!(
+                    "change_address and funding_outpoints cannot be set for batched channels"
+                )));
+            }
+            if channel.fee_rate.is_some() {
+                return Err(APIError::InvalidChannelBatch(s!(
+                    "the fee rate can only be set for the whole batch"
+                )));
+            }
+            if channel.external_signing {
+                return Err(APIError::InvalidChannelBatch(s!(
+                    "external signing is not supported for batched channels"
+                )));
+            }
+            let temporary_channel_id = match &channel.temporary_channel_id {
+                Some(temporary_channel_id) => check_channel_id(temporary_channel_id)?,
+                None => ChannelId::temporary_from_entropy_source(&*unlocked_state.keys_manager),
+            };
+            if temporary_channel_ids.contains(&temporary_channel_id) {
+                return Err(APIError::InvalidChannelBatch(s!(
+                    "temporary channel IDs must be unique"
+                )));
+            }
+            temporary_channel_ids.push(temporary_channel_id);
+            // the funding scripts are not known yet
+            funding_outputs.push(TxOut {
+                value: channel.capacity_sat,
+                script_pubkey: placeholder_funding_script(),
+            });
+        }
 
-        let (asset_amount, asset_id) = if rgb_payment_info_path_inbound.exists() {
-            let info = parse_rgb_payment_info(&rgb_payment_info_path_inbound);
-            (Some(info.amount), Some(info.contract_id.to_string()))
+        let utxos = if let Some(funding_outpoints) = payload.funding_outpoints {
+            let utxos =
+                unlocked_state.vanilla_utxos(&parse_funding_outpoints(funding_outpoints)?)?;
+            funding_psbt_from_utxos(
+                &utxos,
+                funding_outputs,
+                placeholder_funding_script(),
+                fee_rate,
+            )?;
+            utxos
         } else {
-            (None, None)
+            unlocked_state.select_funding_utxos(&funding_outputs, fee_rate)?
         };
 
-        payments.push(Payment {
-            amt_msat: payment_info.amt_msat,
-            asset_amount,
-            asset_id,
-            payment_hash: hex_str(&payment_hash.0),
-            inbound: true,
-            status: payment_info.status,
+        // the funding outputs are collected by temporary channel ID, as LDK requests them
+        let (txid_sender, txid_receiver) = oneshot::channel();
+        unlocked_state.get_funding_batches().push(FundingBatch {
+            utxos,
+            temporary_channel_ids: temporary_channel_ids.clone(),
+            funding_outputs: HashMap::new(),
+            txid_sender: Some(txid_sender),
+            fee_rate,
+            funding_txid: None,
         });
-    }
+        *unlocked_state.rgb_send_lock.lock().unwrap() = true;
+        tracing::debug!("RGB send lock set to true");
 
-    for (payment_id, payment_info) in &outbound_payments {
-        let payment_hash = &PaymentHash(payment_id.0);
+        for (mut channel, temporary_channel_id) in
+            payload.channels.into_iter().zip(&temporary_channel_ids)
+        {
+            channel.temporary_channel_id = Some(temporary_channel_id.0.as_hex().to_string());
+            let res = do_open_channel(state.clone(), channel, true).await;
+            // the batch is dropped as soon as one of its channels gets closed
+            let aborted = !unlocked_state
+                .get_funding_batches()
+                .iter()
+                .any(|b| b.temporary_channel_ids.contains(temporary_channel_id));
+            if res.is_err() || aborted {
+                unlocked_state.abort_funding_batch(temporary_channel_id);
+                unlocked_state.close_unfunded_channels(&temporary_channel_ids);
+                res?;
+                return Err(APIError::FailedOpenChannel(s!(
+                    "a channel of the batch has been closed before being funded"
+                )));
+            }
+        }
 
-        let rgb_payment_info_path_outbound =
-            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, false);
+        // wait for the funding transaction to be built in order to report its ID
+        let funding_txid = tokio::time::timeout(
+            Duration::from_secs(OPENCHANNEL_FUNDING_TIMEOUT_SECS),
+            txid_receiver,
+        )
+        .await
+        .ok()
+        .and_then(|res| res.ok())
+        .map(|txid| txid.to_string());
 
-        let (asset_amount, asset_id) = if rgb_payment_info_path_outbound.exists() {
-            let info = parse_rgb_payment_info(&rgb_payment_info_path_outbound);
-            (Some(info.amount), Some(info.contract_id.to_string()))
-        } else {
-            (None, None)
-        };
+        Ok(Json(OpenChannelsResponse {
+            temporary_channel_ids: temporary_channel_ids
+                .iter()
+                .map(|id| id.0.as_hex().to_string())
+                .collect(),
+            funding_txid,
+        }))
+    })
+    .await
+}
 
-        payments.push(Payment {
-            amt_msat: payment_info.amt_msat,
-            asset_amount,
-            asset_id,
-            payment_hash: hex_str(&payment_hash.0),
-            inbound: false,
-            status: payment_info.status,
-        });
+/// Parse the outpoints of the UTXOs requested to fund channels
+fn parse_funding_outpoints(funding_outpoints: Vec<String>) -> Result<Vec<OutPoint>, APIError> {
+    if funding_outpoints.is_empty() {
+        return Err(APIError::InvalidFundingOutpoints(s!(
+            "at least one outpoint must be given"
+        )));
+    }
+    let mut outpoints: Vec<OutPoint> = vec![];
+    for outpoint in funding_outpoints {
+        let outpoint = OutPoint::from_str(&outpoint).map_err(|_| {
+            APIError::InvalidFundingOutpoints(format!("invalid outpoint {outpoint}"))
+        })?;
+        if outpoints.contains(&outpoint) {
+            return Err(APIError::InvalidFundingOutpoints(format!(
+                "duplicate outpoint {outpoint}"
+            )));
+        }
+        outpoints.push(outpoint);
     }
-
-    Ok(Json(ListPaymentsResponse { payments }))
+    Ok(outpoints)
 }
 
-pub(crate) async fn list_peers(
+pub(crate) async fn pending_intercepts(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListPeersResponse>, APIError> {
+) -> Result<Json<PendingInterceptsResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut peers = vec![];
-    for peer_details in unlocked_state.peer_manager.list_peers() {
-        peers.push(Peer {
-            pubkey: peer_details.counterparty_node_id.to_string(),
+    let intercepts = unlocked_state
+        .get_held_intercepts()
+        .iter()
+        .map(|(intercept_id, intercept)| PendingIntercept {
+            intercept_id: hex_str(&intercept_id.0),
+            payment_hash: hex_str(&intercept.payment_hash.0),
+            inbound_amount_msat: intercept.inbound_amount_msat,
+            expected_outbound_amount_msat: intercept.expected_outbound_amount_msat,
+            inbound_asset_id: intercept.inbound_contract_id.map(|c| c.to_string()),
+            inbound_asset_amount: intercept.inbound_rgb_amount,
+            outbound_asset_id: intercept.outbound_contract_id.map(|c| c.to_string()),
+            expected_outbound_asset_amount: intercept.expected_outbound_rgb_amount,
+            prev_channel_id: intercept.prev_channel_id.0.as_hex().to_string(),
+            requested_next_hop_scid: intercept.requested_next_hop_scid,
+            intercepted_at: intercept.intercepted_at,
+            expires_at: intercept.expires_at,
         })
-    }
+        .collect();
 
-    Ok(Json(ListPeersResponse { peers }))
+    Ok(Json(PendingInterceptsResponse { intercepts }))
 }
 
-pub(crate) async fn list_swaps(
+pub(crate) async fn pending_sweeps(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListSwapsResponse>, APIError> {
+) -> Result<Json<PendingSweepsResponse>, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let map_swap = |payment_hash: &PaymentHash, swap_data: &SwapData, taker: bool| {
-        let mut status = swap_data.status.clone();
-        if status == SwapStatus::Waiting && get_current_timestamp() > swap_data.swap_info.expiry {
-            status = SwapStatus::Expired;
-        } else if status == SwapStatus::Pending
-            && get_current_timestamp() > swap_data.initiated_at.unwrap() + 86400
-        {
-            status = SwapStatus::Failed;
-        }
-        if status != swap_data.status {
-            if taker {
-                unlocked_state.update_taker_swap_status(payment_hash, status.clone());
-            } else {
-                unlocked_state.update_maker_swap_status(payment_hash, status.clone());
+    let mut sweeps = vec![];
+    for output in unlocked_state.output_sweeper.tracked_spendable_outputs() {
+        let (outpoint, txout, to_self_delay) = match &output.descriptor {
+            SpendableOutputDescriptor::StaticPaymentOutput(descriptor) => {
+                (descriptor.outpoint, &descriptor.output, None)
             }
-        }
-        Swap {
-            payment_hash: payment_hash.to_string(),
-            qty_from: swap_data.swap_info.qty_from,
-            qty_to: swap_data.swap_info.qty_to,
-            from_asset: swap_data.swap_info.from_asset.map(|c| c.to_string()),
-            to_asset: swap_data.swap_info.to_asset.map(|c| c.to_string()),
-            status,
-            requested_at: swap_data.requested_at,
-            initiated_at: swap_data.initiated_at,
-            expires_at: swap_data.swap_info.expiry,
-            completed_at: swap_data.completed_at,
-        }
-    };
+            SpendableOutputDescriptor::DelayedPaymentOutput(descriptor) => (
+                descriptor.outpoint,
+                &descriptor.output,
+                Some(descriptor.to_self_delay),
+            ),
+            SpendableOutputDescriptor::StaticOutput {
+                outpoint, output, ..
+            } => (*outpoint, output, None),
+        };
+        let txid_str = outpoint.txid.to_string();
+
+        // the RGB allocations of a closing output are described by the transfer info of its TX
+        let transfer_info_path = state
+            .static_state
+            .color_source
+            .join(format!("{txid_str}_transfer_info"));
+        let (asset_id, asset_amount) = if transfer_info_path.exists() {
+            let transfer_info = read_rgb_transfer_info(&transfer_info_path);
+            (
+                Some(transfer_info.contract_id.to_string()),
+                Some(transfer_info.rgb_amount),
+            )
+        } else {
+            (None, None)
+        };
 
-    let taker_swaps = unlocked_state.taker_swaps();
-    let maker_swaps = unlocked_state.maker_swaps();
+        // delayed outputs can only be spent once the CSV delay has passed
+        let maturity_height = unlocked_state
+            .rgb_wallet_wrapper
+            .get_tx_height(txid_str)?
+            .map(|h| h + to_self_delay.unwrap_or(0) as u32);
 
-    Ok(Json(ListSwapsResponse {
-        taker: taker_swaps
-            .iter()
-            .map(|(ph, sd)| map_swap(ph, sd, true))
-            .collect(),
-        maker: maker_swaps
-            .iter()
-            .map(|(ph, sd)| map_swap(ph, sd, false))
-            .collect(),
-    }))
+        let (status, spending_txid, latest_broadcast_height, confirmation_height) =
+            match &output.status {
+                OutputSpendStatus::PendingInitialBroadcast { .. } => {
+                    (SweepStatus::PendingBroadcast, None, None, None)
+                }
+                OutputSpendStatus::PendingFirstConfirmation {
+                    latest_broadcast_height,
+                    latest_spending_tx,
+                    ..
+                } => (
+                    SweepStatus::PendingConfirmation,
+                    Some(latest_spending_tx.txid().to_string()),
+                    Some(*latest_broadcast_height),
+                    None,
+                ),
+                OutputSpendStatus::PendingThresholdConfirmations {
+                    latest_broadcast_height,
+                    latest_spending_tx,
+                    confirmation_height,
+                    ..
+                } => (
+                    SweepStatus::Confirmed,
+                    Some(latest_spending_tx.txid().to_string()),
+                    Some(*latest_broadcast_height),
+                    Some(*confirmation_height),
+                ),
+            };
+
+        sweeps.push(PendingSweep {
+            outpoint: format!("{}:{}", outpoint.txid, outpoint.index),
+            channel_id: output.channel_id.map(|c| c.0.as_hex().to_string()),
+            amount_sat: txout.value,
+            asset_id,
+            asset_amount,
+            maturity_height,
+            status,
+            spending_txid,
+            latest_broadcast_height,
+            confirmation_height,
+        });
+    }
+
+    Ok(Json(PendingSweepsResponse { sweeps }))
 }
 
-pub(crate) async fn list_transactions(
+pub(crate) async fn pin_proxy(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListTransactionsResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<PinProxyRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut transactions = vec![];
-    for tx in unlocked_state.rgb_list_transactions()? {
-        transactions.push(Transaction {
-            transaction_type: match tx.transaction_type {
-                rgb_lib::TransactionType::RgbSend => TransactionType::RgbSend,
-                rgb_lib::TransactionType::Drain => TransactionType::Drain,
-                rgb_lib::TransactionType::CreateUtxos => TransactionType::CreateUtxos,
-                rgb_lib::TransactionType::User => TransactionType::User,
+        let url = proxy_url(&payload.proxy_endpoint)?;
+        if url.scheme() != "https" {
+            return Err(APIError::InvalidProxyPin(s!(
+                "only TLS (rpcs) endpoints can be pinned"
+            )));
+        }
+        let sha256 = payload.sha256.to_lowercase();
+        if hex_str_to_vec(&sha256).map(|h| h.len()) != Some(32) {
+            return Err(APIError::InvalidProxyPin(s!(
+                "sha256 must be a 32-byte hex string"
+            )));
+        }
+
+        unlocked_state.add_proxy_pin(
+            proxy_pin_key(&url),
+            ProxyPin {
+                pin_type: payload.pin_type,
+                sha256,
+                created_at: get_current_timestamp(),
             },
-            txid: tx.txid,
-            received: tx.received,
-            sent: tx.sent,
-            fee: tx.fee,
-            confirmation_time: tx.confirmation_time.map(|ct| BlockTime {
-                height: ct.height,
-                timestamp: ct.timestamp,
-            }),
-        })
-    }
+        )?;
 
-    Ok(Json(ListTransactionsResponse { transactions }))
+        tracing::info!("Pinned proxy {}", payload.proxy_endpoint);
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
 }
 
-pub(crate) async fn list_transfers(
+pub(crate) async fn post_asset_media(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ListTransfersRequest>, APIError>,
-) -> Result<Json<ListTransfersResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    mut multipart: Multipart,
+) -> Result<Json<PostAssetMediaResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut transfers = vec![];
-    for transfer in unlocked_state.rgb_list_transfers(payload.asset_id)? {
-        transfers.push(Transfer {
-            idx: transfer.idx,
-            created_at: transfer.created_at,
-            updated_at: transfer.updated_at,
-            status: match transfer.status {
-                rgb_lib::TransferStatus::WaitingCounterparty => TransferStatus::WaitingCounterparty,
-                rgb_lib::TransferStatus::WaitingConfirmations => {
-                    TransferStatus::WaitingConfirmations
+        let digest = if let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|_| APIError::MediaFileNotProvided)?
+        {
+            let file_bytes = field.bytes().await.map_err(|_| APIError::Unexpected)?;
+            if file_bytes.is_empty() {
+                return Err(APIError::MediaFileEmpty);
+            }
+            let file_hash: sha256::Hash = Hash::hash(&file_bytes[..]);
+            let digest = file_hash.to_string();
+
+            let file_path = unlocked_state.rgb_get_media_dir().join(&digest);
+            let mut write = true;
+            if file_path.exists() {
+                let mut buf_reader = BufReader::new(File::open(&file_path).await?);
+                let mut existing_file_bytes = Vec::new();
+                buf_reader.read_to_end(&mut existing_file_bytes).await?;
+                if file_bytes != existing_file_bytes {
+                    tokio::fs::remove_file(&file_path).await?;
+                } else {
+                    write = false;
                 }
-                rgb_lib::TransferStatus::Settled => TransferStatus::Settled,
-                rgb_lib::TransferStatus::Failed => TransferStatus::Failed,
-            },
-            amount: transfer.amount,
-            kind: match transfer.kind {
-                rgb_lib::TransferKind::Issuance => TransferKind::Issuance,
-                rgb_lib::TransferKind::ReceiveBlind => TransferKind::ReceiveBlind,
-                rgb_lib::TransferKind::ReceiveWitness => TransferKind::ReceiveWitness,
-                rgb_lib::TransferKind::Send => TransferKind::Send,
-            },
-            txid: transfer.txid,
-            recipient_id: transfer.recipient_id,
-            receive_utxo: transfer.receive_utxo.map(|u| u.to_string()),
-            change_utxo: transfer.change_utxo.map(|u| u.to_string()),
-            expiration: transfer.expiration,
-            transport_endpoints: transfer
-                .transport_endpoints
-                .iter()
-                .map(|tte| TransferTransportEndpoint {
-                    endpoint: tte.endpoint.clone(),
-                    transport_type: match tte.transport_type {
-                        rgb_lib::TransportType::JsonRpc => TransportType::JsonRpc,
-                    },
-                    used: tte.used,
-                })
-                .collect(),
-        })
-    }
-    Ok(Json(ListTransfersResponse { transfers }))
+            }
+            if write {
+                let mut file = File::create(&file_path).await?;
+                file.write_all(&file_bytes).await?;
+            }
+            digest
+        } else {
+            return Err(APIError::MediaFileNotProvided);
+        };
+
+        Ok(Json(PostAssetMediaResponse { digest }))
+    })
+    .await
 }
 
-pub(crate) async fn list_unspents(
+pub(crate) async fn post_swap_offer(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListUnspentsResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<PostSwapOfferRequest>, APIError>,
+) -> Result<Json<PostSwapOfferResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let mut unspents = vec![];
-    for unspent in unlocked_state.rgb_list_unspents()? {
-        unspents.push(Unspent {
-            utxo: Utxo {
-                outpoint: unspent.utxo.outpoint.to_string(),
-                btc_amount: unspent.utxo.btc_amount,
-                colorable: unspent.utxo.colorable,
+        let from_asset = match &payload.from_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        let to_asset = match &payload.to_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        // prevent BTC-to-BTC swaps
+        if from_asset.is_none() && to_asset.is_none() {
+            return Err(APIError::InvalidSwap(s!("cannot swap BTC for BTC")));
+        }
+
+        // prevent swaps of same assets
+        if from_asset == to_asset {
+            return Err(APIError::InvalidSwap(s!("cannot swap the same asset")));
+        }
+
+        if payload.price_from == 0 || payload.price_to == 0 {
+            return Err(APIError::InvalidSwapOffer(s!("prices must be positive")));
+        }
+        if payload.min_qty_from == 0 || payload.min_qty_from > payload.max_qty_from {
+            return Err(APIError::InvalidSwapOffer(s!(
+                "min_qty_from must be positive and not higher than max_qty_from"
+            )));
+        }
+        if payload
+            .total_qty_from
+            .is_some_and(|total| total < payload.min_qty_from)
+        {
+            return Err(APIError::InvalidSwapOffer(s!(
+                "total_qty_from cannot be lower than min_qty_from"
+            )));
+        }
+        if payload.expiry_sec == 0 {
+            return Err(APIError::InvalidSwapOffer(s!(
+                "expiry_sec must be positive"
+            )));
+        }
+
+        let offer_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        let offer = SwapOfferMessage {
+            offer_id: offer_id.clone(),
+            from_asset,
+            to_asset,
+            price_from: payload.price_from,
+            price_to: payload.price_to,
+            min_qty_from: payload.min_qty_from,
+            max_qty_from: payload.max_qty_from,
+            expiry: get_current_timestamp() + payload.expiry_sec as u64,
+            remaining_qty_from: payload.total_qty_from,
+        };
+        if offer.qty_to(offer.max_qty_from) == 0 {
+            return Err(APIError::InvalidSwapOffer(s!(
+                "max_qty_from is too low for the offer price"
+            )));
+        }
+
+        let mut book = unlocked_state.get_swap_offers();
+        book.own.offers.insert(
+            offer_id.clone(),
+            SwapOfferData {
+                offer,
+                created_at: get_current_timestamp(),
+                acceptances: vec![],
             },
-            rgb_allocations: unspent
-                .rgb_allocations
-                .iter()
-                .map(|a| RgbAllocation {
-                    asset_id: a.asset_id.clone(),
-                    amount: a.amount,
-                    settled: a.settled,
-                })
-                .collect(),
-        })
-    }
-    Ok(Json(ListUnspentsResponse { unspents }))
+        );
+        save_swap_offers(&unlocked_state, &book)?;
+        drop(book);
+
+        announce_swap_offers(&unlocked_state);
+        unlocked_state.peer_manager.process_events();
+
+        tracing::info!("Posted swap offer {offer_id}");
+        Ok(Json(PostSwapOfferResponse { offer_id }))
+    })
+    .await
 }
 
-pub(crate) async fn ln_invoice(
+pub(crate) async fn rebalance(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<LNInvoiceRequest>, APIError>,
-) -> Result<Json<LNInvoiceResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<RebalanceRequest>, APIError>,
+) -> Result<Json<RebalanceResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let contract_id = if let Some(asset_id) = payload.asset_id {
-            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
-        } else {
-            None
-        };
-
-        if contract_id.is_some() && payload.amt_msat.unwrap_or(0) < INVOICE_MIN_MSAT {
-            return Err(APIError::InvalidAmount(format!(
-                "amt_msat cannot be less than {INVOICE_MIN_MSAT} when transferring an RGB asset"
+        let outbound_channel_id = check_channel_id(&payload.outbound_channel_id)?;
+        let inbound_channel_id = check_channel_id(&payload.inbound_channel_id)?;
+        if outbound_channel_id == inbound_channel_id {
+            return Err(APIError::InvalidRebalance(s!(
+                "outbound and inbound channels must be different"
             )));
         }
 
+        let channels = unlocked_state.channel_manager.list_channels();
+        let find_usable_channel = |channel_id: &ChannelId, channel_id_str: &str| {
+            let Some(details) = channels.iter().find(|c| c.channel_id == *channel_id) else {
+                return Err(APIError::UnknownChannelId);
+            };
+            if !details.is_usable {
+                return Err(APIError::InvalidRebalance(format!(
+                    "channel {channel_id_str} is not usable"
+                )));
+            }
+            Ok(details)
+        };
+        let outbound_channel =
+            find_usable_channel(&outbound_channel_id, &payload.outbound_channel_id)?;
+        let inbound_channel =
+            find_usable_channel(&inbound_channel_id, &payload.inbound_channel_id)?;
+        let (inbound_scid, inbound_config) = match (
+            inbound_channel.get_inbound_payment_scid(),
+            inbound_channel.counterparty.forwarding_info.as_ref(),
+        ) {
+            (Some(scid), Some(config)) => (scid, config),
+            _ => {
+                return Err(APIError::InvalidRebalance(format!(
+                    "channel {} is not usable",
+                    payload.inbound_channel_id
+                )))
+            }
+        };
+
+        // asset rebalances move the asset between two channels of the same contract
+        let contract_id = if let Some(asset_amount) = payload.asset_amount {
+            let ldk_data_dir = &state.static_state.ldk_data_dir;
+            let outbound_rgb_info =
+                get_rgb_channel_info_optional(&outbound_channel_id, ldk_data_dir, false);
+            let inbound_rgb_info =
+                get_rgb_channel_info_optional(&inbound_channel_id, ldk_data_dir, false);
+            let contract_id = match (outbound_rgb_info, inbound_rgb_info) {
+                (Some((outbound_info, _)), Some((inbound_info, _)))
+                    if outbound_info.contract_id == inbound_info.contract_id =>
+                {
+                    outbound_info.contract_id
+                }
+                _ => {
+                    return Err(APIError::InvalidRebalance(s!(
+                        "channels must hold the same RGB asset"
+                    )))
+                }
+            };
+            if asset_amount == 0 {
+                return Err(APIError::InvalidAmount(s!("asset_amount must be positive")));
+            }
+            if payload.amt_msat < INVOICE_MIN_MSAT {
+                return Err(APIError::InvalidAmount(format!(
+                    "amt_msat cannot be less than {INVOICE_MIN_MSAT} when rebalancing an RGB asset"
+                )));
+            }
+            Some(contract_id)
+        } else {
+            None
+        };
+        let rgb_payment = contract_id.zip(payload.asset_amount);
+
         let currency = match state.static_state.network {
             Network::Bitcoin => Currency::Bitcoin,
             Network::Testnet => Currency::BitcoinTestnet,
@@ -1925,1024 +8053,1680 @@ pub(crate) async fn ln_invoice(
             Network::Signet => Currency::Signet,
             _ => unimplemented!("unsupported network"),
         };
-        let invoice = match create_invoice_from_channelmanager(
+        let invoice = create_invoice_from_channelmanager(
             &unlocked_state.channel_manager,
             unlocked_state.keys_manager.clone(),
             state.static_state.logger.clone(),
             currency,
-            payload.amt_msat,
-            "ldk-tutorial-node".to_string(),
-            payload.expiry_sec,
+            Some(payload.amt_msat),
+            "rebalance".to_string(),
+            REBALANCE_INVOICE_EXPIRY_SECS,
             None,
             contract_id,
             payload.asset_amount,
-        ) {
-            Ok(inv) => inv,
-            Err(e) => return Err(APIError::FailedInvoiceCreation(e.to_string())),
-        };
-
+        )
+        .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?;
         let payment_hash = PaymentHash((*invoice.payment_hash()).to_byte_array());
+        let payment_secret = *invoice.payment_secret();
+        let final_cltv_expiry_delta = invoice.min_final_cltv_expiry_delta() as u32;
+
+        // the router cannot find routes to ourselves: route to the peer of the inbound channel,
+        // leaving through the outbound channel, then add the hop back to us
+        let inbound_fee_msat = inbound_config.fee_base_msat as u64
+            + payload.amt_msat * inbound_config.fee_proportional_millionths as u64 / 1_000_000;
+        let mut route = get_route(
+            &unlocked_state.channel_manager,
+            &unlocked_state.router,
+            unlocked_state.channel_manager.get_our_node_id(),
+            inbound_channel.counterparty.node_id,
+            Some(payload.amt_msat + inbound_fee_msat),
+            rgb_payment,
+            vec![],
+            inbound_config.cltv_expiry_delta as u32,
+            Some(&[outbound_channel]),
+        )
+        .ok_or(APIError::NoRoute)?;
+        let hops = &mut route.paths[0].hops;
+        let peer_hop = hops.last_mut().expect("path not to be empty");
+        peer_hop.fee_msat = inbound_fee_msat;
+        peer_hop.cltv_expiry_delta = inbound_config.cltv_expiry_delta as u32;
+        let mut last_hop = peer_hop.clone();
+        last_hop.pubkey = unlocked_state.channel_manager.get_our_node_id();
+        last_hop.node_features = unlocked_state.channel_manager.node_features();
+        last_hop.short_channel_id = inbound_scid;
+        last_hop.maybe_announced_channel = inbound_channel.is_public;
+        last_hop.fee_msat = payload.amt_msat;
+        last_hop.cltv_expiry_delta = final_cltv_expiry_delta;
+        hops.push(last_hop);
+        for hop in hops.iter_mut() {
+            hop.rgb_amount = payload.asset_amount;
+        }
+
+        let total_cltv_expiry_delta = hops.iter().map(|hop| hop.cltv_expiry_delta).sum::<u32>();
+        if total_cltv_expiry_delta > DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA {
+            return Err(APIError::InvalidRebalance(format!(
+                "CLTV budget exceeded: {total_cltv_expiry_delta} blocks, max is \
+                {DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA} blocks"
+            )));
+        }
+        // skip the last hop, its fee is the payment amount
+        let fee_msat = hops
+            .iter()
+            .rev()
+            .skip(1)
+            .map(|hop| hop.fee_msat)
+            .sum::<u64>();
+        if fee_msat > payload.max_fee_msat {
+            return Err(APIError::InvalidRebalance(format!(
+                "fee of {fee_msat} msat exceeds max_fee_msat"
+            )));
+        }
+        route.route_params = Some(RouteParameters {
+            payment_params: PaymentParameters::from_node_id(
+                unlocked_state.channel_manager.get_our_node_id(),
+                final_cltv_expiry_delta,
+            ),
+            final_value_msat: payload.amt_msat,
+            max_total_routing_fee_msat: Some(payload.max_fee_msat),
+            rgb_payment,
+        });
+
+        // both sides of the rebalance appear at once
+        let update = unlocked_state.snapshot_tracker.begin_update();
         unlocked_state.add_inbound_payment(
             payment_hash,
             PaymentInfo {
                 preimage: None,
-                secret: Some(*invoice.payment_secret()),
+                secret: Some(payment_secret),
                 status: HTLCStatus::Pending,
-                amt_msat: payload.amt_msat,
+                amt_msat: Some(payload.amt_msat),
+                custom_records: vec![],
+                retry_attempts: None,
+                retry_timeout_secs: None,
+                failed_attempts: 0,
+                expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                asset_amount: None,
+                keysend: false,
+                claim_deadline: None,
             },
         );
+        if let Some((contract_id, asset_amount)) = rgb_payment {
+            write_rgb_payment_info_file(
+                &state.static_state.ldk_data_dir,
+                &payment_hash,
+                contract_id,
+                asset_amount,
+                false,
+                false,
+            );
+        }
+        let payment_id = PaymentId(payment_hash.0);
+        unlocked_state.add_outbound_payment(
+            payment_id,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(payment_secret),
+                status: HTLCStatus::Pending,
+                amt_msat: Some(payload.amt_msat),
+                custom_records: vec![],
+                retry_attempts: None,
+                retry_timeout_secs: None,
+                failed_attempts: 0,
+                expires_at: None,
+                asset_amount: None,
+                keysend: false,
+                claim_deadline: None,
+            },
+        )?;
+        drop(update);
 
-        Ok(Json(LNInvoiceResponse {
-            invoice: invoice.to_string(),
+        let status = match unlocked_state.channel_manager.send_payment_with_route(
+            &route,
+            payment_hash,
+            RecipientOnionFields::secret_only(payment_secret),
+            payment_id,
+        ) {
+            Ok(()) => {
+                tracing::info!(
+                    "EVENT: initiated rebalance of {} msats from channel {} to channel {}",
+                    payload.amt_msat,
+                    payload.outbound_channel_id,
+                    payload.inbound_channel_id
+                );
+                HTLCStatus::Pending
+            }
+            Err(e) => {
+                tracing::error!("ERROR: failed to send rebalance payment: {:?}", e);
+                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed)?;
+                HTLCStatus::Failed
+            }
+        };
+
+        Ok(Json(RebalanceResponse {
+            payment_hash: hex_str(&payment_hash.0),
+            fee_msat,
+            status,
         }))
     })
     .await
 }
 
-pub(crate) async fn lock(
+pub(crate) async fn refresh_transfers(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<EmptyResponse>, APIError> {
-    tracing::info!("Lock started");
     no_cancel(async move {
-        match state.check_unlocked().await {
-            Ok(unlocked_state) => {
-                state.update_changing_state(true);
-                drop(unlocked_state);
-            }
-            Err(e) => {
-                state.update_changing_state(false);
-                return Err(e);
-            }
-        }
-
-        tracing::debug!("Stopping LDK...");
-        stop_ldk(state.clone()).await;
-        tracing::debug!("LDK stopped");
-
-        state.update_unlocked_app_state(None).await;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        state.update_ldk_background_services(None);
+        unlocked_state
+            .select_proxy_endpoint(&state.static_state.proxy_endpoints)
+            .await?;
 
-        state.update_changing_state(false);
+        tokio::task::spawn_blocking(move || unlocked_state.rgb_refresh())
+            .await
+            .unwrap()?;
 
-        tracing::info!("Lock completed");
+        tracing::info!("Refresh complete");
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn maker_execute(
+pub(crate) async fn reject_channel_request(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<MakerExecuteRequest>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<RejectChannelRequestRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let swapstring = SwapString::from_str(&payload.swapstring)
-            .map_err(|e| APIError::InvalidSwapString(payload.swapstring.clone(), e.to_string()))?;
-        let payment_secret = hex_str_to_vec(&payload.payment_secret)
-            .and_then(|data| data.try_into().ok())
-            .map(PaymentSecret)
-            .ok_or(APIError::InvalidPaymentSecret)?;
-        let taker_pk =
-            PublicKey::from_str(&payload.taker_pubkey).map_err(|_| APIError::InvalidPubkey)?;
+        unlocked_state
+            .handle_channel_request(&payload.request_id, ChannelRequestStatus::Rejected)?;
 
-        if get_current_timestamp() > swapstring.swap_info.expiry {
-            unlocked_state.update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Expired);
-            return Err(APIError::ExpiredSwapOffer);
-        }
+        tracing::info!("Rejected channel request {}", payload.request_id);
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
 
-        let payment_preimage = unlocked_state
-            .channel_manager
-            .get_payment_preimage(swapstring.payment_hash, payment_secret)
-            .map_err(|_| APIError::MissingSwapPaymentPreimage)?;
+pub(crate) async fn request_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<RequestChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let swap_info = swapstring.swap_info;
+        let peer_pubkey = match hex_str_to_compressed_pubkey(&payload.peer_pubkey) {
+            Some(pk) => pk,
+            None => return Err(APIError::InvalidPubkey),
+        };
 
-        let receive_hints = unlocked_state
-            .channel_manager
-            .list_usable_channels()
-            .iter()
-            .filter(|details| {
-                match get_rgb_channel_info_optional(
-                    &details.channel_id,
-                    &state.static_state.ldk_data_dir,
-                    false,
-                ) {
-                    _ if swap_info.is_from_btc() => true,
-                    Some((rgb_info, _)) if Some(rgb_info.contract_id) == swap_info.from_asset => {
-                        true
-                    }
-                    _ => false,
-                }
-            })
-            .map(|details| {
-                let config = details.counterparty.forwarding_info.as_ref().unwrap();
-                RouteHint(vec![RouteHintHop {
-                    src_node_id: details.counterparty.node_id,
-                    short_channel_id: details.short_channel_id.unwrap(),
-                    cltv_expiry_delta: config.cltv_expiry_delta,
-                    htlc_maximum_msat: None,
-                    htlc_minimum_msat: None,
-                    fees: RoutingFees {
-                        base_msat: config.fee_base_msat,
-                        proportional_millionths: config.fee_proportional_millionths,
-                    },
-                    htlc_maximum_rgb: None,
-                }])
-            })
-            .collect();
+        let asset_id = match (payload.asset_id, payload.asset_amount) {
+            (Some(asset), Some(_)) => {
+                Some(ContractId::from_str(&asset).map_err(|_| APIError::InvalidAssetID(asset))?)
+            }
+            (None, None) => None,
+            _ => {
+                return Err(APIError::IncompleteRGBInfo);
+            }
+        };
 
-        let rgb_payment = swap_info
-            .to_asset
-            .map(|to_asset| (to_asset, swap_info.qty_to));
-        let first_leg = get_route(
-            &unlocked_state.channel_manager,
-            &unlocked_state.router,
-            unlocked_state.channel_manager.get_our_node_id(),
-            taker_pk,
-            if swap_info.is_to_btc() {
-                Some(swap_info.qty_to + HTLC_MIN_MSAT)
-            } else {
-                Some(HTLC_MIN_MSAT)
-            },
-            rgb_payment,
-            vec![],
-        );
+        if payload.capacity_sat < OPENCHANNEL_MIN_SAT {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel amount must be equal or higher than {OPENCHANNEL_MIN_SAT}"
+            )));
+        }
+        if payload.capacity_sat > OPENCHANNEL_MAX_SAT {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel amount must be equal or less than {OPENCHANNEL_MAX_SAT}"
+            )));
+        }
 
-        let rgb_payment = swap_info
-            .from_asset
-            .map(|from_asset| (from_asset, swap_info.qty_from));
-        let second_leg = get_route(
-            &unlocked_state.channel_manager,
-            &unlocked_state.router,
-            taker_pk,
-            unlocked_state.channel_manager.get_our_node_id(),
-            if swap_info.is_to_btc() || swap_info.is_asset_asset() {
-                Some(HTLC_MIN_MSAT)
-            } else {
-                Some(swap_info.qty_from + HTLC_MIN_MSAT)
+        // the request is sent as a custom message, the peer must be connected and understand it
+        let peer = unlocked_state
+            .peer_manager
+            .peer_by_node_id(&peer_pubkey)
+            .ok_or_else(|| APIError::CannotRequestChannel(s!("peer is not connected")))?;
+        if !supports_feature_bit(peer.init_features.le_flags(), CHANNEL_REQUEST_FEATURE_BIT) {
+            return Err(APIError::CannotRequestChannel(s!(
+                "peer doesn't support channel requests"
+            )));
+        }
+
+        unlocked_state.peer_message_handler.send_channel_request(
+            peer_pubkey,
+            ChannelRequestMessage {
+                capacity_sat: payload.capacity_sat,
+                push_msat: payload.push_msat,
+                asset_id,
+                asset_amount: payload.asset_amount,
+                fee_sat: payload.fee_sat,
             },
-            rgb_payment,
-            receive_hints,
         );
+        unlocked_state.peer_manager.process_events();
 
-        let (mut first_leg, mut second_leg) = match (first_leg, second_leg) {
-            (Some(f), Some(s)) => (f, s),
-            _ => {
-                return Err(APIError::NoRoute);
-            }
-        };
+        tracing::info!("Requested a channel to peer {peer_pubkey}");
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
 
-        // Set swap flag
-        second_leg.paths[0].hops[0].short_channel_id |= IS_SWAP_SCID;
+pub(crate) async fn restore(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<RestoreRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
 
-        // Generally in the last hop the fee_amount is set to the payment amount, so we need to
-        // override it with fee = 0
-        first_leg.paths[0]
-            .hops
-            .last_mut()
-            .expect("Path not to be empty")
-            .fee_msat = 0;
+        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
+        check_already_initialized(&mnemonic_path)?;
 
-        let fullpaths = first_leg.paths[0]
-            .hops
-            .clone()
-            .into_iter()
-            .map(|mut hop| {
-                if swap_info.is_to_asset() {
-                    hop.rgb_amount = Some(swap_info.qty_to);
+        match (payload.backup_path, payload.backup) {
+            (Some(backup_path), None) => {
+                if !payload.incremental_backups.is_empty() {
+                    return Err(APIError::InvalidBackup(s!(
+                        "incremental backups must be given as paths along with backup_path"
+                    )));
                 }
-                hop
-            })
-            .chain(second_leg.paths[0].hops.clone().into_iter().map(|mut hop| {
-                if swap_info.is_from_asset() {
-                    hop.rgb_amount = Some(swap_info.qty_from);
+                let incremental_backup_paths: Vec<PathBuf> = payload
+                    .incremental_backup_paths
+                    .iter()
+                    .map(PathBuf::from)
+                    .collect();
+                restore_backup(
+                    Path::new(&backup_path),
+                    &incremental_backup_paths,
+                    &payload.password,
+                    &state.static_state.storage_dir_path,
+                )?;
+            }
+            (None, Some(backup)) => {
+                if !payload.incremental_backup_paths.is_empty() {
+                    return Err(APIError::InvalidBackup(s!(
+                        "incremental backups must be given as data along with backup"
+                    )));
                 }
-                hop
-            }))
-            .collect::<Vec<_>>();
+                let decode = |b: &String| {
+                    general_purpose::STANDARD
+                        .decode(b)
+                        .map_err(|_| APIError::InvalidBackup(s!("not valid base64")))
+                };
+                let incremental_backups = payload
+                    .incremental_backups
+                    .iter()
+                    .map(decode)
+                    .collect::<Result<Vec<_>, _>>()?;
+                import_backup(
+                    &decode(&backup)?,
+                    &incremental_backups,
+                    &payload.password,
+                    &state.static_state.storage_dir_path,
+                )?;
+            }
+            _ => {
+                return Err(APIError::InvalidBackup(s!(
+                    "exactly one of backup_path and backup must be given"
+                )))
+            }
+        }
 
-        // Skip last fee because it's equal to the payment amount
-        let total_fee = fullpaths
-            .iter()
-            .rev()
-            .skip(1)
-            .map(|hop| hop.fee_msat)
-            .sum::<u64>();
+        let _mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
 
-        if total_fee >= MAX_SWAP_FEE_MSAT {
-            return Err(APIError::FailedPayment(format!(
-                "Fee too high: {}",
-                total_fee
-            )));
-        }
+pub(crate) async fn restore_scb(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<RestoreScbRequest>, APIError>,
+) -> Result<Json<RestoreScbResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let route = Route {
-            paths: vec![LnPath {
-                hops: fullpaths,
-                blinded_tail: None,
-            }],
-            route_params: Some(RouteParameters {
-                payment_params: PaymentParameters::for_keysend(
-                    unlocked_state.channel_manager.get_our_node_id(),
-                    DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
-                    false,
-                ),
-                // This value is not used anywhere, it's set by the router
-                // when creating a route, but here we are creating it manually
-                // by composing a pre-existing list of hops
-                final_value_msat: 0,
-                max_total_routing_fee_msat: None,
-                // This value is not used anywhere, same as final_value_msat
-                rgb_payment: None,
-            }),
-        };
+        let scb_json = read_scb_backup(Path::new(&payload.backup_path), &payload.password)?;
+        let scb: StaticChannelBackup =
+            serde_json::from_slice(&scb_json).map_err(|e| APIError::InvalidScb(e.to_string()))?;
+        // peers only force-close when contacted with the node ID the channel was opened with
+        if scb.node_pubkey != unlocked_state.channel_manager.get_our_node_id().to_string() {
+            return Err(APIError::InvalidScb(s!("backup belongs to another node")));
+        }
 
-        if swap_info.is_to_asset() {
-            write_rgb_payment_info_file(
-                &state.static_state.ldk_data_dir,
-                &swapstring.payment_hash,
-                swap_info.to_asset.unwrap(),
-                swap_info.qty_to,
-                true,
-                false,
+        let ldk_data_dir = &state.static_state.ldk_data_dir;
+        let peer_data_path = ldk_data_dir.join(CHANNEL_PEER_DATA);
+        let open_channel_ids: Vec<String> = unlocked_state
+            .channel_manager
+            .list_channels()
+            .iter()
+            .map(|c| hex_str(&c.channel_id.0))
+            .collect();
+        let mut channels = vec![];
+        for channel in scb.channels {
+            let status = if open_channel_ids.contains(&channel.channel_id) {
+                ScbChannelStatus::Open
+            } else {
+                restore_rgb_info(&channel, ldk_data_dir)?;
+                let peer_info = match &channel.peer_address {
+                    Some(peer_address) => format!("{}@{}", channel.peer_pubkey, peer_address),
+                    None => channel.peer_pubkey.clone(),
+                };
+                match parse_peer_info(peer_info)? {
+                    (peer_pubkey, Some(peer_addr)) => {
+                        // on reconnection the peer tries to resume the channel, which is unknown
+                        // to LDK, so the peer is told to force-close it
+                        disk::persist_channel_peer(
+                            &peer_data_path,
+                            &peer_pubkey,
+                            &peer_addr,
+                            unlocked_state.kv_store.cipher(),
+                        )?;
+                        match connect_peer_if_necessary(
+                            peer_pubkey,
+                            peer_addr,
+                            state.static_state.tor_proxy,
+                            unlocked_state.peer_manager.clone(),
+                        )
+                        .await
+                        {
+                            Ok(()) => ScbChannelStatus::ForceCloseRequested,
+                            Err(_) => ScbChannelStatus::PeerUnreachable,
+                        }
+                    }
+                    (_, None) => ScbChannelStatus::PeerUnreachable,
+                }
+            };
+            tracing::info!(
+                "static channel backup recovery for channel {}: {:?}",
+                channel.channel_id,
+                status
             );
+            channels.push(ScbChannelRecovery {
+                channel_id: channel.channel_id,
+                peer_pubkey: channel.peer_pubkey,
+                status,
+            });
         }
 
-        unlocked_state.update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Pending);
+        Ok(Json(RestoreScbResponse { channels }))
+    })
+    .await
+}
 
-        let (_status, err) = match unlocked_state.channel_manager.send_spontaneous_payment(
-            &route,
-            Some(payment_preimage),
-            RecipientOnionFields::spontaneous_empty(),
-            PaymentId(swapstring.payment_hash.0),
-        ) {
-            Ok(_payment_hash) => {
-                tracing::debug!("EVENT: initiated swap");
-                (HTLCStatus::Pending, None)
-            }
-            Err(e) => {
-                tracing::warn!("ERROR: failed to send payment: {:?}", e);
-                (HTLCStatus::Failed, Some(e))
-            }
-        };
+pub(crate) async fn revoke_api_token(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<RevokeApiTokenRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        state.revoke_api_token(&payload.name)?;
 
-        match err {
-            None => Ok(Json(EmptyResponse {})),
-            Some(e) => {
-                unlocked_state
-                    .update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Failed);
-                Err(APIError::FailedPayment(format!("{:?}", e)))
-            }
-        }
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn maker_init(
+pub(crate) async fn rgb_invoice(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<MakerInitRequest>, APIError>,
-) -> Result<Json<MakerInitResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<RgbInvoiceRequest>, APIError>,
+) -> Result<Json<RgbInvoiceResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let from_asset = match &payload.from_asset {
-            None => None,
-            Some(asset) => Some(
-                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
-            ),
-        };
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
 
-        let to_asset = match &payload.to_asset {
-            None => None,
-            Some(asset) => Some(
-                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
-            ),
+        let proxy_endpoints = if payload.transport_endpoints.is_empty() {
+            // unavailable proxies are left out, the sender would fail to post the consignment to
+            // them
+            unlocked_state
+                .available_proxy_endpoints(&state.static_state.proxy_endpoints)
+                .await?
+        } else {
+            unlocked_state
+                .check_proxy_endpoints(&payload.transport_endpoints)
+                .await?;
+            payload.transport_endpoints
         };
 
-        // prevent BTC-to-BTC swaps
-        if from_asset.is_none() && to_asset.is_none() {
-            return Err(APIError::InvalidSwap(s!("cannot swap BTC for BTC")));
-        }
+        let receive_data = match payload.receive_mode {
+            ReceiveMode::Blinded => unlocked_state.rgb_blind_receive(
+                payload.asset_id,
+                payload.duration_seconds,
+                proxy_endpoints,
+                payload.min_confirmations,
+            )?,
+            ReceiveMode::Witness => unlocked_state.rgb_witness_receive(
+                payload.asset_id,
+                payload.duration_seconds,
+                proxy_endpoints,
+                payload.min_confirmations,
+            )?,
+        };
 
-        // prevent swaps of same assets
-        if from_asset == to_asset {
-            return Err(APIError::InvalidSwap(s!("cannot swap the same asset")));
-        }
+        Ok(Json(RgbInvoiceResponse {
+            recipient_id: receive_data.recipient_id,
+            invoice: receive_data.invoice,
+            expiration_timestamp: receive_data.expiration_timestamp,
+        }))
+    })
+    .await
+}
 
-        let qty_from = payload.qty_from;
-        let qty_to = payload.qty_to;
+pub(crate) async fn rotate_node_id(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<RotateNodeIdResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let expiry = get_current_timestamp() + payload.timeout_sec as u64;
-        let swap_info = SwapInfo {
-            from_asset,
-            to_asset,
-            qty_from,
-            qty_to,
-            expiry,
+        // resume the rotation in progress or start a new one
+        let mut rotation = match unlocked_state.node_id_rotation() {
+            Some(rotation) if rotation.status != NodeIdRotationStatus::Completed => rotation,
+            previous => {
+                tracing::info!("Node ID rotation started");
+                NodeIdRotation {
+                    status: NodeIdRotationStatus::ClosingChannels,
+                    key_index: previous.map(|r| r.key_index).unwrap_or(0) + 1,
+                    old_pubkey: unlocked_state.channel_manager.get_our_node_id(),
+                    new_pubkey: None,
+                    started_at: get_current_timestamp(),
+                    completed_at: None,
+                }
+            }
         };
-        let swap_data = SwapData::create_from_swap_info(&swap_info);
 
-        // Check that we have enough assets to send
-        if let Some(to_asset) = to_asset {
-            let max_balance = get_max_local_rgb_amount(
-                to_asset,
-                &state.static_state.ldk_data_dir,
-                unlocked_state.channel_manager.list_channels().iter(),
-            );
-            if swap_info.qty_to > max_balance {
-                return Err(APIError::InsufficientAssets);
+        let channels = unlocked_state.channel_manager.list_channels();
+        if rotation.status == NodeIdRotationStatus::ClosingChannels {
+            if channels.is_empty() {
+                rotation.status = NodeIdRotationStatus::SweepingFunds;
+            }
+            // channels with an offline peer can't be closed cooperatively and are left to the
+            // user, who can either wait for the peer to reconnect or force-close them
+            for chan in channels.iter().filter(|c| {
+                c.is_usable
+                    && matches!(
+                        c.channel_shutdown_state,
+                        Some(LdkChannelShutdownState::NotShuttingDown)
+                    )
+            }) {
+                match unlocked_state
+                    .channel_manager
+                    .close_channel(&chan.channel_id, &chan.counterparty.node_id)
+                {
+                    Ok(()) => tracing::info!("EVENT: initiating channel close"),
+                    Err(e) => tracing::error!("ERROR: failed to close channel: {:?}", e),
+                }
             }
         }
 
-        let (payment_hash, payment_secret) = unlocked_state
-            .channel_manager
-            .create_inbound_payment(Some(DUST_LIMIT_MSAT), payload.timeout_sec, None)
-            .unwrap();
-        unlocked_state.add_maker_swap(payment_hash, swap_data);
+        let balances_left = unlocked_state
+            .chain_monitor
+            .get_claimable_balances(&[])
+            .len()
+            + unlocked_state
+                .output_sweeper
+                .tracked_spendable_outputs()
+                .len();
+        if rotation.status == NodeIdRotationStatus::SweepingFunds && balances_left == 0 {
+            // the new key is used by LDK from the next unlock
+            rotation.status = NodeIdRotationStatus::RestartRequired;
+        }
 
-        let swapstring = SwapString::from_swap_info(&swap_info, payment_hash).to_string();
+        unlocked_state.save_node_id_rotation(rotation.clone())?;
 
-        let payment_secret = payment_secret.0.as_hex().to_string();
-        let payment_hash = payment_hash.0.as_hex().to_string();
-        Ok(Json(MakerInitResponse {
-            payment_hash,
-            payment_secret,
-            swapstring,
+        Ok(Json(RotateNodeIdResponse {
+            status: rotation.status,
+            old_pubkey: rotation.old_pubkey.to_string(),
+            new_pubkey: rotation.new_pubkey.map(|p| p.to_string()),
+            channels_left: channels.len(),
+            balances_left,
         }))
     })
     .await
 }
 
-pub(crate) async fn network_info(
+pub(crate) async fn send_asset(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<NetworkInfoResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SendAssetRequest>, APIError>,
+) -> Result<Json<SendAssetResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let recipient_info = RecipientInfo::new(payload.recipient_id.clone())?;
+        let witness_amount_sat = match recipient_info.recipient_type {
+            RecipientType::Blind => 0,
+            RecipientType::Witness => payload.witness_amount_sat.unwrap_or(WITNESS_AMOUNT_SAT),
+        };
+        let spend = SpendData::new(
+            witness_amount_sat,
+            Some(payload.asset_id.clone()),
+            Some(payload.amount),
+        );
+        let reservation = check_spend_limits(&state, &unlocked_state, spend, || {
+            SpendOperation::SendAsset(payload.clone())
+        })?;
+
+        let response = do_send_asset(state, payload).await?;
+        reservation.commit();
+
+        Ok(Json(response))
+    })
+    .await
+}
+
+/// Send assets on-chain, shared by the send asset API and the spend confirmation
+pub(crate) async fn do_send_asset(
+    state: Arc<AppState>,
+    payload: SendAssetRequest,
+) -> Result<SendAssetResponse, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let best_block = unlocked_state.channel_manager.current_best_block();
+    if *unlocked_state.rgb_send_lock.lock().unwrap() {
+        return Err(APIError::OpenChannelInProgress);
+    }
 
-    Ok(Json(NetworkInfoResponse {
-        network: state.static_state.network.into(),
-        height: best_block.height,
-    }))
+    let recipient_info = RecipientInfo::new(payload.recipient_id.clone())?;
+    let fee_rate = get_fee_rate(state.static_state.fee_rate, payload.fee_rate)?;
+    unlocked_state
+        .check_proxy_endpoints(&payload.transport_endpoints)
+        .await?;
+    // witness recipients receive the assets on a new output of the TX, carrying some bitcoin
+    let witness_data = match recipient_info.recipient_type {
+        RecipientType::Blind => None,
+        RecipientType::Witness => Some(WitnessData {
+            amount_sat: payload.witness_amount_sat.unwrap_or(WITNESS_AMOUNT_SAT),
+            blinding: payment_blinding(
+                &state.static_state,
+                &unlocked_state.keys_manager,
+                &payload.recipient_id,
+            ),
+        }),
+    };
+    let recipient_map = map! {
+        payload.asset_id => vec![Recipient {
+            recipient_id: payload.recipient_id,
+            witness_data,
+            amount: payload.amount,
+            transport_endpoints: payload.transport_endpoints,
+        }]
+    };
+
+    let send_result = tokio::task::spawn_blocking(move || {
+        unlocked_state.rgb_send(
+            recipient_map,
+            payload.donation,
+            fee_rate,
+            payload.min_confirmations,
+        )
+    })
+    .await
+    .unwrap()?;
+
+    Ok(SendAssetResponse {
+        txid: send_result.txid,
+    })
 }
 
-pub(crate) async fn node_info(
+pub(crate) async fn send_btc(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<NodeInfoResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SendBtcRequest>, APIError>,
+) -> Result<Json<SendBtcResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let spend = SpendData::new(payload.amount, None, None);
+        let reservation = check_spend_limits(&state, &unlocked_state, spend, || {
+            SpendOperation::SendBtc(payload.clone())
+        })?;
+
+        let response = do_send_btc(state, payload).await?;
+        reservation.commit();
+
+        Ok(Json(response))
+    })
+    .await
+}
+
+/// Send bitcoin on-chain, shared by the send BTC API and the spend confirmation
+pub(crate) async fn do_send_btc(
+    state: Arc<AppState>,
+    payload: SendBtcRequest,
+) -> Result<SendBtcResponse, APIError> {
     let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let chans = unlocked_state.channel_manager.list_channels();
+    let fee_rate = get_fee_rate(state.static_state.fee_rate, payload.fee_rate)?;
+    let txid = unlocked_state.rgb_send_btc(payload.address, payload.amount, fee_rate)?;
 
-    Ok(Json(NodeInfoResponse {
-        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
-        num_channels: chans.len(),
-        num_usable_channels: chans.iter().filter(|c| c.is_usable).count(),
-        local_balance_msat: chans.iter().map(|c| c.balance_msat).sum::<u64>(),
-        num_peers: unlocked_state.peer_manager.list_peers().len(),
-        onchain_pubkey: unlocked_state.rgb_get_wallet_data().pubkey,
-        max_media_upload_size_mb: state.static_state.max_media_upload_size_mb,
-        rgb_htlc_min_msat: HTLC_MIN_MSAT,
-        channel_capacity_min_sat: OPENCHANNEL_MIN_SAT,
-        channel_capacity_max_sat: OPENCHANNEL_MAX_SAT,
-        channel_asset_min_amount: OPENCHANNEL_MIN_RGB_AMT,
-        channel_asset_max_amount: u64::MAX,
-    }))
+    Ok(SendBtcResponse { txid })
 }
 
-pub(crate) async fn open_channel(
+pub(crate) async fn send_onion_message(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<OpenChannelRequest>, APIError>,
-) -> Result<Json<OpenChannelResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SendOnionMessageRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
+        if payload.node_ids.is_empty() {
+            return Err(APIError::InvalidNodeIds(s!(
+                "sendonionmessage requires at least one node id for the path"
+            )));
+        }
+
+        let mut intermediate_nodes = Vec::new();
+        for pk_str in payload.node_ids {
+            let node_pubkey_vec = match hex_str_to_vec(&pk_str) {
+                Some(peer_pubkey_vec) => peer_pubkey_vec,
+                None => {
+                    return Err(APIError::InvalidNodeIds(format!(
+                        "Couldn't parse peer_pubkey '{}'",
+                        pk_str
+                    )))
+                }
+            };
+            let node_pubkey = match PublicKey::from_slice(&node_pubkey_vec) {
+                Ok(peer_pubkey) => peer_pubkey,
+                Err(_) => {
+                    return Err(APIError::InvalidNodeIds(format!(
+                        "Couldn't parse peer_pubkey '{}'",
+                        pk_str
+                    )))
+                }
+            };
+            intermediate_nodes.push(node_pubkey);
+        }
+
+        if payload.tlv_type < 64 {
+            return Err(APIError::InvalidTlvType(s!(
+                "need an integral message type above 64"
+            )));
         }
 
-        let temporary_channel_id = if let Some(tmp_chan_id_str) = payload.temporary_channel_id {
-            let tmp_chan_id = check_channel_id(&tmp_chan_id_str)?;
-            if unlocked_state.channel_ids().contains_key(&tmp_chan_id) {
-                return Err(APIError::TemporaryChannelIdAlreadyUsed);
-            }
-            Some(tmp_chan_id)
-        } else {
-            None
-        };
+        let data = hex_str_to_vec(&payload.data)
+            .ok_or(APIError::InvalidOnionData(s!("need a hex data string")))?;
 
-        let colored_info = match (payload.asset_id, payload.asset_amount) {
-            (Some(_), Some(amt)) if amt < OPENCHANNEL_MIN_RGB_AMT => {
-                return Err(APIError::InvalidAmount(format!(
-                    "Channel RGB amount must be equal or higher than {OPENCHANNEL_MIN_RGB_AMT}"
-                )));
-            }
-            (Some(asset), Some(amt)) => {
-                let asset =
-                    ContractId::from_str(&asset).map_err(|_| APIError::InvalidAssetID(asset))?;
-                Some((asset, amt))
-            }
-            (None, None) => None,
-            _ => {
-                return Err(APIError::IncompleteRGBInfo);
-            }
-        };
+        let destination = Destination::Node(intermediate_nodes.pop().unwrap());
 
-        if payload.capacity_sat < OPENCHANNEL_MIN_SAT {
-            return Err(APIError::InvalidAmount(format!(
-                "Channel amount must be equal or higher than {OPENCHANNEL_MIN_SAT}"
+        unlocked_state
+            .onion_messenger
+            .send_onion_message(
+                UserOnionMessageContents {
+                    tlv_type: payload.tlv_type,
+                    data,
+                },
+                destination,
+                None,
+            )
+            .map_err(|e| APIError::FailedSendingOnionMessage(format!("{:?}", e)))?;
+
+        tracing::info!("SUCCESS: forwarded onion message to first hop");
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+/// Start paying a BOLT11 invoice, tracking it as an outbound payment
+pub(crate) fn pay_bolt11_invoice(
+    state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    invoice: &Bolt11Invoice,
+    amt_msat: Option<u64>,
+    asset_amount: Option<u64>,
+    retry: Retry,
+    max_total_cltv_expiry_delta: Option<u32>,
+) -> Result<(PaymentId, PaymentHash, Option<PaymentSecret>, HTLCStatus), APIError> {
+    let mut status = HTLCStatus::Pending;
+    let (retry_attempts, retry_timeout_secs) = retry_details(retry);
+
+    let payment_id = PaymentId((*invoice.payment_hash()).to_byte_array());
+    let payment_secret = Some(*invoice.payment_secret());
+    let zero_amt_invoice =
+        invoice.amount_milli_satoshis().is_none() || invoice.amount_milli_satoshis() == Some(0);
+    let (pay_params_opt, amt_msat) = if zero_amt_invoice {
+        if let Some(amt_msat) = amt_msat {
+            (
+                payment_parameters_from_zero_amount_invoice(invoice, amt_msat),
+                amt_msat,
+            )
+        } else {
+            return Err(APIError::InvalidAmount(s!(
+                "need an amount for the given 0-value invoice"
             )));
         }
-        if payload.capacity_sat > OPENCHANNEL_MAX_SAT {
+    } else {
+        if amt_msat.is_some() && invoice.amount_milli_satoshis() != amt_msat {
             return Err(APIError::InvalidAmount(format!(
-                "Channel amount must be equal or less than {OPENCHANNEL_MAX_SAT}"
+                "amount didn't match invoice value of {}msat",
+                invoice.amount_milli_satoshis().unwrap_or(0)
             )));
         }
-
-        if !payload.with_anchors {
-            return Err(APIError::AnchorsRequired);
+        (
+            payment_parameters_from_invoice(invoice),
+            invoice.amount_milli_satoshis().unwrap_or(0),
+        )
+    };
+    let (payment_hash, recipient_onion, mut route_params) = match pay_params_opt {
+        Ok(res) => res,
+        Err(e) => {
+            return Err(APIError::InvalidInvoice(format!(
+                "failed to parse invoice: {e:?}"
+            )));
         }
+    };
+    if let Some(max_total_cltv_expiry_delta) = max_total_cltv_expiry_delta {
+        route_params.payment_params.max_total_cltv_expiry_delta = max_total_cltv_expiry_delta;
+    }
 
-        let (peer_pubkey, mut peer_addr) =
-            parse_peer_info(payload.peer_pubkey_and_opt_addr.to_string())?;
-
-        let peer_data_path = state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA);
-        if peer_addr.is_none() {
-            if let Some(peer) = unlocked_state.peer_manager.peer_by_node_id(&peer_pubkey) {
-                if let Some(socket_address) = peer.socket_address {
-                    if let Ok(mut socket_addrs) = socket_address.to_socket_addrs() {
-                        // assuming there's only one IP address
-                        peer_addr = socket_addrs.next();
-                    }
-                }
+    match (invoice.rgb_contract_id(), invoice.rgb_amount()) {
+        (Some(rgb_contract_id), invoice_rgb_amount) => {
+            if amt_msat < INVOICE_MIN_MSAT {
+                return Err(APIError::InvalidAmount(format!(
+                    "msat amount in invoice sending an RGB asset cannot be less than {INVOICE_MIN_MSAT}"
+                )));
             }
-        }
-        if peer_addr.is_none() {
-            let peer_info = disk::read_channel_peer_data(&peer_data_path)?;
-            for (pubkey, addr) in peer_info.into_iter() {
-                if pubkey == peer_pubkey {
-                    peer_addr = Some(addr);
-                    break;
+            // invoices without an RGB amount let the payer choose how much of the asset to send
+            let rgb_amount = match (invoice_rgb_amount, asset_amount) {
+                (Some(rgb_amount), None) => rgb_amount,
+                (Some(rgb_amount), Some(asset_amount)) if asset_amount == rgb_amount => rgb_amount,
+                (Some(rgb_amount), Some(_)) => {
+                    return Err(APIError::InvalidAmount(format!(
+                        "asset amount didn't match invoice value of {rgb_amount}"
+                    )));
                 }
-            }
-        }
-        if let Some(peer_addr) = peer_addr {
-            connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
-                .await?;
-            disk::persist_channel_peer(&peer_data_path, &peer_pubkey, &peer_addr)?;
-        } else {
-            return Err(APIError::InvalidPeerInfo(s!(
-                "cannot find the address for the provided pubkey"
-            )));
+                (None, Some(asset_amount)) if asset_amount > 0 => {
+                    route_params.rgb_payment = Some((rgb_contract_id, asset_amount));
+                    asset_amount
+                }
+                (None, _) => {
+                    return Err(APIError::InvalidAmount(s!(
+                        "need an asset amount for the given invoice without an RGB amount"
+                    )));
+                }
+            };
+            write_rgb_payment_info_file(
+                &PathBuf::from(&state.static_state.ldk_data_dir.clone()),
+                &payment_hash,
+                rgb_contract_id,
+                rgb_amount,
+                false,
+                false,
+            );
         }
-
-        let mut channel_config = ChannelConfig::default();
-        if let Some(fee_base_msat) = payload.fee_base_msat {
-            channel_config.forwarding_fee_base_msat = fee_base_msat;
+        (None, None) if asset_amount.is_some() => {
+            return Err(APIError::InvalidAmount(s!(
+                "cannot send an asset amount for an invoice without an RGB asset"
+            )))
         }
-        if let Some(fee_proportional_millionths) = payload.fee_proportional_millionths {
-            channel_config.forwarding_fee_proportional_millionths = fee_proportional_millionths;
+        (None, None) => {}
+        (None, Some(_)) => {
+            return Err(APIError::InvalidInvoice(s!(
+                "invoice has an RGB amount but not an RGB contract ID"
+            )))
         }
-        let config = UserConfig {
-            channel_handshake_limits: ChannelHandshakeLimits {
-                // lnd's max to_self_delay is 2016, so we want to be compatible.
-                their_to_self_delay: 2016,
-                ..Default::default()
-            },
-            channel_handshake_config: ChannelHandshakeConfig {
-                announced_channel: payload.public,
-                our_htlc_minimum_msat: HTLC_MIN_MSAT,
-                minimum_depth: MIN_CHANNEL_CONFIRMATIONS as u32,
-                negotiate_anchors_zero_fee_htlc_tx: payload.with_anchors,
-                ..Default::default()
-            },
-            channel_config,
-            ..Default::default()
-        };
-
-        let consignment_endpoint = if let Some((contract_id, asset_amount)) = &colored_info {
-            let balance = unlocked_state.rgb_get_asset_balance(*contract_id)?;
-            let spendable_rgb_amount = balance.spendable;
-
-            if *asset_amount > spendable_rgb_amount {
-                return Err(APIError::InsufficientAssets);
-            }
+    }
 
-            Some(RgbTransport::from_str(&state.static_state.proxy_endpoint).unwrap())
-        } else {
-            None
-        };
+    let secret = payment_secret;
+    unlocked_state.add_outbound_payment(
+        payment_id,
+        PaymentInfo {
+            preimage: None,
+            secret,
+            status,
+            amt_msat: invoice.amount_milli_satoshis(),
+            custom_records: vec![],
+            retry_attempts,
+            retry_timeout_secs,
+            failed_attempts: 0,
+            expires_at: None,
+            asset_amount: None,
+            keysend: false,
+            claim_deadline: None,
+        },
+    )?;
 
-        if let Some((contract_id, asset_amount)) = &colored_info {
-            let mut fake_p2wsh: [u8; 34] = [0; 34];
-            fake_p2wsh[1] = 32;
-            let script_buf = ScriptBuf::from_bytes(fake_p2wsh.to_vec());
-            let recipient_id =
-                recipient_id_from_script_buf(script_buf, state.static_state.network.into());
-            let asset_id = contract_id.to_string();
-            let recipient_map = map! {
-                asset_id => vec![Recipient {
-                    recipient_id,
-                    witness_data: Some(WitnessData {
-                        amount_sat: payload.capacity_sat,
-                        blinding: Some(STATIC_BLINDING + 1),
-                    }),
-                    amount: *asset_amount,
-                    transport_endpoints: vec![state.static_state.proxy_endpoint.clone()]
-            }]};
-
-            let unlocked_state_copy = unlocked_state.clone();
-            tokio::task::spawn_blocking(move || {
-                unlocked_state_copy.rgb_send_begin(
-                    recipient_map,
-                    true,
-                    FEE_RATE,
-                    MIN_CHANNEL_CONFIRMATIONS,
-                )
-            })
-            .await
-            .unwrap()
-            .map_err(|e| APIError::CannotOpenChannel(format!("{:?}", e)))?;
+    match unlocked_state.channel_manager.send_payment(
+        payment_hash,
+        recipient_onion,
+        payment_id,
+        route_params,
+        retry,
+    ) {
+        Ok(_) => {
+            let payee_pubkey = invoice.recover_payee_pub_key();
+            let amt_msat = invoice.amount_milli_satoshis().unwrap();
+            tracing::info!(
+                "EVENT: initiated sending {} msats to {}",
+                amt_msat,
+                payee_pubkey
+            );
+        }
+        Err(e) => {
+            tracing::error!("ERROR: failed to send payment: {:?}", e);
+            status = HTLCStatus::Failed;
+            unlocked_state.update_outbound_payment_status(payment_id, status)?;
         }
+    };
 
-        *unlocked_state.rgb_send_lock.lock().unwrap() = true;
-        tracing::debug!("RGB send lock set to true");
+    Ok((payment_id, payment_hash, secret, status))
+}
 
-        let temporary_channel_id = unlocked_state
-            .channel_manager
-            .create_channel(
-                peer_pubkey,
-                payload.capacity_sat,
-                payload.push_msat,
-                0,
-                temporary_channel_id,
-                Some(config),
-                consignment_endpoint,
-            )
-            .map_err(|e| {
-                *unlocked_state.rgb_send_lock.lock().unwrap() = false;
-                tracing::debug!("RGB send lock set to false (open channel failure: {e:?})");
-                APIError::FailedOpenChannel(format!("{:?}", e))
-            })?;
-        let temporary_channel_id = temporary_channel_id.0.as_hex().to_string();
-        tracing::info!("EVENT: initiated channel with peer {}", peer_pubkey);
+pub(crate) async fn send_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SendPaymentRequest>, APIError>,
+) -> Result<Json<SendPaymentResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if let Some((contract_id, asset_amount)) = &colored_info {
-            let rgb_info = RgbInfo {
-                contract_id: *contract_id,
-                local_rgb_amount: *asset_amount,
-                remote_rgb_amount: 0,
-            };
-            write_rgb_channel_info(
-                &get_rgb_channel_info_path(
-                    &temporary_channel_id,
-                    &state.static_state.ldk_data_dir,
-                    true,
-                ),
-                &rgb_info,
-            );
-            write_rgb_channel_info(
-                &get_rgb_channel_info_path(
-                    &temporary_channel_id,
-                    &state.static_state.ldk_data_dir,
-                    false,
-                ),
-                &rgb_info,
-            );
-        }
+        let spend = payment_spend(&payload)?;
+        let reservation = check_spend_limits(&state, &unlocked_state, spend, || {
+            SpendOperation::SendPayment(payload.clone())
+        })?;
 
-        Ok(Json(OpenChannelResponse {
-            temporary_channel_id,
-        }))
+        let response = do_send_payment(state, payload).await?;
+        reservation.commit();
+
+        Ok(Json(response))
     })
     .await
 }
 
-pub(crate) async fn post_asset_media(
-    State(state): State<Arc<AppState>>,
-    mut multipart: Multipart,
-) -> Result<Json<PostAssetMediaResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+/// Amounts spent paying the invoice or offer of the given request
+fn payment_spend(payload: &SendPaymentRequest) -> Result<SpendData, APIError> {
+    if let Ok(offer) = Offer::from_str(&payload.invoice) {
+        let amt_msat = match offer.amount() {
+            Some(offer::Amount::Bitcoin { amount_msats }) => *amount_msats,
+            _ => payload.amt_msat.unwrap_or(0),
+        };
+        return Ok(SpendData::from_msat(amt_msat, None, None));
+    }
+    let invoice = Bolt11Invoice::from_str(&payload.invoice)
+        .map_err(|e| APIError::InvalidInvoice(e.to_string()))?;
+    let amt_msat = invoice
+        .amount_milli_satoshis()
+        .filter(|a| *a > 0)
+        .or(payload.amt_msat)
+        .unwrap_or(0);
+    Ok(SpendData::from_msat(
+        amt_msat,
+        invoice.rgb_contract_id().map(|c| c.to_string()),
+        invoice.rgb_amount().or(payload.asset_amount),
+    ))
+}
+
+/// Pay an invoice or offer, shared by the send payment API and the spend confirmation
+pub(crate) async fn do_send_payment(
+    state: Arc<AppState>,
+    payload: SendPaymentRequest,
+) -> Result<SendPaymentResponse, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let digest = if let Some(field) = multipart
-            .next_field()
-            .await
-            .map_err(|_| APIError::MediaFileNotProvided)?
-        {
-            let file_bytes = field.bytes().await.map_err(|_| APIError::Unexpected)?;
-            if file_bytes.is_empty() {
-                return Err(APIError::MediaFileEmpty);
-            }
-            let file_hash: sha256::Hash = Hash::hash(&file_bytes[..]);
-            let digest = file_hash.to_string();
+    let mut status = HTLCStatus::Pending;
 
-            let file_path = unlocked_state.rgb_get_media_dir().join(&digest);
-            let mut write = true;
-            if file_path.exists() {
-                let mut buf_reader = BufReader::new(File::open(&file_path).await?);
-                let mut existing_file_bytes = Vec::new();
-                buf_reader.read_to_end(&mut existing_file_bytes).await?;
-                if file_bytes != existing_file_bytes {
-                    tokio::fs::remove_file(&file_path).await?;
-                } else {
-                    write = false;
+    let retry = get_payment_retry(
+        state.static_state.payment_retry,
+        payload.retry_attempts,
+        payload.retry_timeout_secs,
+    )?;
+    let (retry_attempts, retry_timeout_secs) = retry_details(retry);
+
+    let (payment_id, payment_hash, payment_secret) =
+        if let Ok(offer) = Offer::from_str(&payload.invoice) {
+            let random_bytes = unlocked_state.keys_manager.get_secure_random_bytes();
+            let payment_id = PaymentId(random_bytes);
+
+            let amt_msat = match (offer.amount(), payload.amt_msat) {
+                (Some(offer::Amount::Bitcoin { amount_msats }), _) => *amount_msats,
+                (_, Some(amt)) => amt,
+                (amt, _) => {
+                    return Err(APIError::InvalidAmount(format!(
+                        "cannot process non-Bitcoin-denominated offer value {amt:?}"
+                    )));
                 }
+            };
+            if payload.amt_msat.is_some() && payload.amt_msat != Some(amt_msat) {
+                return Err(APIError::InvalidAmount(format!(
+                    "amount didn't match offer of {amt_msat}msat"
+                )));
             }
-            if write {
-                let mut file = File::create(&file_path).await?;
-                file.write_all(&file_bytes).await?;
+
+            // TODO: add and check RGB amount after enabling RGB support for offers
+
+            let secret = None;
+
+            unlocked_state.add_outbound_payment(
+                payment_id,
+                PaymentInfo {
+                    preimage: None,
+                    secret,
+                    status,
+                    amt_msat: Some(amt_msat),
+                    custom_records: vec![],
+                    retry_attempts,
+                    retry_timeout_secs,
+                    failed_attempts: 0,
+                    expires_at: None,
+                    asset_amount: None,
+                    keysend: false,
+                    claim_deadline: None,
+                },
+            )?;
+
+            let amt = Some(amt_msat);
+            let pay = unlocked_state
+                .channel_manager
+                .pay_for_offer(&offer, None, amt, None, payment_id, retry, None);
+            if pay.is_err() {
+                tracing::error!("ERROR: failed to pay: {:?}", pay);
+                status = HTLCStatus::Failed;
+                unlocked_state.update_outbound_payment_status(payment_id, status)?;
             }
-            digest
+            (payment_id, None, secret)
         } else {
-            return Err(APIError::MediaFileNotProvided);
+            let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
+                Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+                Ok(v) => v,
+            };
+
+            let (payment_id, payment_hash, payment_secret, invoice_status) = pay_bolt11_invoice(
+                &state,
+                &unlocked_state,
+                &invoice,
+                payload.amt_msat,
+                payload.asset_amount,
+                retry,
+                None,
+            )?;
+            status = invoice_status;
+
+            (payment_id, Some(payment_hash), payment_secret)
         };
 
-        Ok(Json(PostAssetMediaResponse { digest }))
+    Ok(SendPaymentResponse {
+        payment_id: hex_str(&payment_id.0),
+        payment_hash: payment_hash.map(|h| hex_str(&h.0)),
+        payment_secret: payment_secret.map(|s| hex_str(&s.0)),
+        status,
     })
-    .await
 }
 
-pub(crate) async fn refresh_transfers(
+/// Send a GET request to an LNURL service, turning its error responses into an APIError
+async fn lnurl_request<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    query: &[(&str, String)],
+) -> Result<T, APIError> {
+    let res = client
+        .get(url)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| APIError::FailedLnurlRequest(e.to_string()))?;
+    let body: serde_json::Value = res
+        .json()
+        .await
+        .map_err(|e| APIError::FailedLnurlRequest(e.to_string()))?;
+    if body["status"] == "ERROR" {
+        let reason = body["reason"].as_str().unwrap_or("unknown error");
+        return Err(APIError::FailedLnurlRequest(reason.to_string()));
+    }
+    serde_json::from_value(body).map_err(|e| APIError::FailedLnurlRequest(e.to_string()))
+}
+
+pub(crate) async fn send_to_ln_address(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SendToLnAddressRequest>, APIError>,
+) -> Result<Json<SendPaymentResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        tokio::task::spawn_blocking(move || unlocked_state.rgb_refresh())
-            .await
-            .unwrap()?;
+        let spend = SpendData::from_msat(
+            payload.amt_msat,
+            payload.asset_id.clone(),
+            payload.asset_amount,
+        );
+        let reservation = check_spend_limits(&state, &unlocked_state, spend, || {
+            SpendOperation::SendToLnAddress(payload.clone())
+        })?;
 
-        tracing::info!("Refresh complete");
-        Ok(Json(EmptyResponse {}))
+        let response = do_send_to_ln_address(state, payload).await?;
+        reservation.commit();
+
+        Ok(Json(response))
+    })
+    .await
+}
+
+/// Split a lightning address into its username and domain
+pub(crate) fn parse_ln_address(ln_address: &str) -> Result<(&str, &str), APIError> {
+    let (username, domain) = ln_address
+        .trim()
+        .split_once('@')
+        .filter(|(u, d)| !u.is_empty() && !d.is_empty() && !d.contains('/'))
+        .ok_or(APIError::InvalidLightningAddress(s!(
+            "must be in the user@domain format"
+        )))?;
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.+".contains(c))
+    {
+        return Err(APIError::InvalidLightningAddress(s!(
+            "username can only contain a-z, 0-9, '-', '_', '.' and '+'"
+        )));
+    }
+    Ok((username, domain))
+}
+
+/// Pay a lightning address, shared by the sendtolnaddress API and the payment scheduler
+pub(crate) async fn do_send_to_ln_address(
+    state: Arc<AppState>,
+    payload: SendToLnAddressRequest,
+) -> Result<SendPaymentResponse, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let retry = get_payment_retry(
+        state.static_state.payment_retry,
+        payload.retry_attempts,
+        payload.retry_timeout_secs,
+    )?;
+
+    let (username, domain) = parse_ln_address(&payload.ln_address)?;
+
+    let contract_id = if let Some(asset_id) = &payload.asset_id {
+        Some(
+            ContractId::from_str(asset_id)
+                .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?,
+        )
+    } else {
+        None
+    };
+    if contract_id.is_some() != payload.asset_amount.is_some() {
+        return Err(APIError::IncompleteRGBInfo);
+    }
+
+    // LUD-16 requires HTTPS except for onion services, plain HTTP is also allowed on regtest
+    let scheme = if domain.ends_with(".onion") || state.static_state.network == Network::Regtest {
+        "http"
+    } else {
+        "https"
+    };
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(LNURL_REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| APIError::FailedLnurlRequest(e.to_string()))?;
+
+    let pay_request: LnurlPayResponse = lnurl_request(
+        &client,
+        &format!("{scheme}://{domain}/.well-known/lnurlp/{username}"),
+        &[],
+    )
+    .await?;
+    if pay_request.tag != "payRequest" {
+        return Err(APIError::FailedLnurlRequest(format!(
+            "unexpected tag {}",
+            pay_request.tag
+        )));
+    }
+    if payload.amt_msat < pay_request.min_sendable || payload.amt_msat > pay_request.max_sendable {
+        return Err(APIError::InvalidAmount(format!(
+            "amount must be between {} and {} msat",
+            pay_request.min_sendable, pay_request.max_sendable
+        )));
+    }
+
+    let mut query = vec![("amount", payload.amt_msat.to_string())];
+    if let (Some(asset_id), Some(asset_amount)) = (&payload.asset_id, payload.asset_amount) {
+        query.push(("asset_id", asset_id.clone()));
+        query.push(("asset_amount", asset_amount.to_string()));
+    }
+    let callback: LnurlPayCallbackResponse =
+        lnurl_request(&client, &pay_request.callback, &query).await?;
+
+    // don't trust the service, the invoice has to match what has been requested
+    let invoice = Bolt11Invoice::from_str(&callback.pr)
+        .map_err(|e| APIError::InvalidInvoice(e.to_string()))?;
+    if invoice.amount_milli_satoshis() != Some(payload.amt_msat) {
+        return Err(APIError::InvalidInvoice(s!(
+            "invoice amount doesn't match the requested one"
+        )));
+    }
+    let description_hash = InvoiceSha256(sha256::Hash::hash(pay_request.metadata.as_bytes()));
+    if !matches!(invoice.description(), Bolt11InvoiceDescription::Hash(h) if *h == description_hash)
+    {
+        return Err(APIError::InvalidInvoice(s!(
+            "invoice description hash doesn't match the LNURL metadata"
+        )));
+    }
+    if invoice.rgb_contract_id() != contract_id || invoice.rgb_amount() != payload.asset_amount {
+        return Err(APIError::InvalidInvoice(s!(
+            "invoice RGB info doesn't match the requested one"
+        )));
+    }
+
+    let (payment_id, payment_hash, payment_secret, status) =
+        pay_bolt11_invoice(&state, &unlocked_state, &invoice, None, None, retry, None)?;
+    tracing::info!("paying {} via its LNURL-pay invoice", payload.ln_address);
+
+    Ok(SendPaymentResponse {
+        payment_id: hex_str(&payment_id.0),
+        payment_hash: Some(hex_str(&payment_hash.0)),
+        payment_secret: payment_secret.map(|s| hex_str(&s.0)),
+        status,
     })
-    .await
 }
 
-pub(crate) async fn restore(
+pub(crate) async fn set_asset_htlc_limit(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<RestoreRequest>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetAssetHtlcLimitRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
-
-        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
-        check_already_initialized(&mnemonic_path)?;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        restore_backup(
-            Path::new(&payload.backup_path),
-            &payload.password,
-            &state.static_state.storage_dir_path,
-        )?;
+        let channel_id = check_channel_id(&payload.channel_id)?;
+        if !unlocked_state
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.channel_id == channel_id)
+        {
+            return Err(APIError::UnknownChannelId);
+        }
+        if !is_channel_rgb(&channel_id, &state.static_state.ldk_data_dir) {
+            return Err(APIError::InvalidChannelPolicy(s!(
+                "asset HTLC limits can only be set on RGB channels"
+            )));
+        }
+        if payload.max_asset_amount == Some(0) {
+            return Err(APIError::InvalidChannelPolicy(s!(
+                "max_asset_amount must be positive"
+            )));
+        }
 
-        let _mnemonic =
-            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+        unlocked_state.set_asset_htlc_limit(channel_id, payload.max_asset_amount)?;
 
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn rgb_invoice(
+pub(crate) async fn set_channel_announcement(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<RgbInvoiceRequest>, APIError>,
-) -> Result<Json<RgbInvoiceResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SetChannelAnnouncementRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
-        }
-
-        let receive_data = unlocked_state.rgb_blind_receive(
-            payload.asset_id,
-            payload.duration_seconds,
-            vec![state.static_state.proxy_endpoint.clone()],
-            payload.min_confirmations,
-        )?;
+        // the preference of existing channels has been negotiated when opening them, so the
+        // default only applies to the channels opened from now on
+        unlocked_state.persist(CHANNEL_ANNOUNCEMENT_FNAME, &payload.public)?;
+        *state.get_announce_channels() = payload.public;
+        tracing::info!(
+            "New channels will be announced by default: {}",
+            payload.public
+        );
 
-        Ok(Json(RgbInvoiceResponse {
-            recipient_id: receive_data.recipient_id,
-            invoice: receive_data.invoice,
-            expiration_timestamp: receive_data.expiration_timestamp,
-        }))
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn send_asset(
+pub(crate) async fn set_log_level(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendAssetRequest>, APIError>,
-) -> Result<Json<SendAssetResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
-
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
-        }
-
-        RecipientInfo::new(payload.recipient_id.clone())?;
-        let recipient_map = map! {
-            payload.asset_id => vec![Recipient {
-                recipient_id: payload.recipient_id,
-                witness_data: None,
-                amount: payload.amount,
-                transport_endpoints: payload.transport_endpoints,
-            }]
-        };
+    WithRejection(Json(payload), _): WithRejection<Json<SetLogLevelRequest>, APIError>,
+) -> Result<Json<SetLogLevelResponse>, APIError> {
+    let log_filter = state
+        .static_state
+        .log_filter
+        .as_ref()
+        .ok_or(APIError::CannotSetLogLevel(s!(
+            "the log filter is not reloadable"
+        )))?;
+
+    if payload.filter.trim().is_empty() {
+        return Err(APIError::InvalidLogFilter(s!("cannot be empty")));
+    }
+    let filter = EnvFilter::try_new(&payload.filter)
+        .map_err(|e| APIError::InvalidLogFilter(e.to_string()))?;
 
-        let send_result = tokio::task::spawn_blocking(move || {
-            unlocked_state.rgb_send(
-                recipient_map,
-                payload.donation,
-                payload.fee_rate,
-                payload.min_confirmations,
-            )
-        })
-        .await
-        .unwrap()?;
+    let previous_filter = log_filter
+        .with_current(|f| f.to_string())
+        .map_err(|e| APIError::CannotSetLogLevel(e.to_string()))?;
+    log_filter
+        .reload(filter)
+        .map_err(|e| APIError::CannotSetLogLevel(e.to_string()))?;
 
-        Ok(Json(SendAssetResponse {
-            txid: send_result.txid,
-        }))
-    })
-    .await
+    tracing::info!(
+        "Changed the log filter from {} to {}",
+        previous_filter,
+        payload.filter
+    );
+    Ok(Json(SetLogLevelResponse { previous_filter }))
 }
 
-pub(crate) async fn send_btc(
+pub(crate) async fn set_peer_policy(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendBtcRequest>, APIError>,
-) -> Result<Json<SendBtcResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SetPeerPolicyRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let txid =
-            unlocked_state.rgb_send_btc(payload.address, payload.amount, payload.fee_rate)?;
+        let allowed = payload
+            .allowed
+            .iter()
+            .map(|p| PublicKey::from_str(p).map_err(|_| APIError::InvalidPubkey))
+            .collect::<Result<Vec<_>, _>>()?;
+        if payload.max_peers == Some(0) {
+            return Err(APIError::InvalidPeerPolicy(s!(
+                "the max number of peers must be greater than 0"
+            )));
+        }
+        unlocked_state.set_peer_policy(allowed, payload.max_peers)?;
 
-        Ok(Json(SendBtcResponse { txid }))
+        tracing::info!("Updated the peer policy");
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn send_onion_message(
+pub(crate) async fn settle_invoice(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendOnionMessageRequest>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<SettleInvoiceRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        if payload.node_ids.is_empty() {
-            return Err(APIError::InvalidNodeIds(s!(
-                "sendonionmessage requires at least one node id for the path"
-            )));
-        }
-
-        let mut intermediate_nodes = Vec::new();
-        for pk_str in payload.node_ids {
-            let node_pubkey_vec = match hex_str_to_vec(&pk_str) {
-                Some(peer_pubkey_vec) => peer_pubkey_vec,
-                None => {
-                    return Err(APIError::InvalidNodeIds(format!(
-                        "Couldn't parse peer_pubkey '{}'",
-                        pk_str
-                    )))
-                }
-            };
-            let node_pubkey = match PublicKey::from_slice(&node_pubkey_vec) {
-                Ok(peer_pubkey) => peer_pubkey,
-                Err(_) => {
-                    return Err(APIError::InvalidNodeIds(format!(
-                        "Couldn't parse peer_pubkey '{}'",
-                        pk_str
-                    )))
-                }
-            };
-            intermediate_nodes.push(node_pubkey);
-        }
-
-        if payload.tlv_type < 64 {
-            return Err(APIError::InvalidTlvType(s!(
-                "need an integral message type above 64"
-            )));
+        let payment_preimage = hex_str_to_vec(&payload.payment_preimage)
+            .and_then(|p| p.try_into().ok())
+            .map(PaymentPreimage)
+            .ok_or(APIError::InvalidPaymentPreimage)?;
+        let payment_hash = PaymentHash(Sha256::hash(&payment_preimage.0).to_byte_array());
+
+        match unlocked_state.inbound_payment(&payment_hash) {
+            Some(payment) if payment.status == HTLCStatus::Claimable => {}
+            Some(_) => {
+                return Err(APIError::CannotSettleInvoice(s!(
+                    "no payment is waiting to be claimed"
+                )))
+            }
+            None => return Err(APIError::UnknownLNInvoice),
         }
 
-        let data = hex_str_to_vec(&payload.data)
-            .ok_or(APIError::InvalidOnionData(s!("need a hex data string")))?;
-
-        let destination = Destination::Node(intermediate_nodes.pop().unwrap());
+        unlocked_state.channel_manager.claim_funds(payment_preimage);
 
-        unlocked_state
-            .onion_messenger
-            .send_onion_message(
-                UserOnionMessageContents {
-                    tlv_type: payload.tlv_type,
-                    data,
-                },
-                destination,
-                None,
-            )
-            .map_err(|e| APIError::FailedSendingOnionMessage(format!("{:?}", e)))?;
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
 
-        tracing::info!("SUCCESS: forwarded onion message to first hop");
+pub(crate) async fn shutdown(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_app_state = state.get_unlocked_app_state();
+        state.check_changing_state()?;
 
+        state.cancel_token.cancel();
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn send_payment(
+pub(crate) async fn sign_message(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendPaymentRequest>, APIError>,
-) -> Result<Json<SendPaymentResponse>, APIError> {
-    no_cancel(async move {
-        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<SignMessageRequest>, APIError>,
+) -> Result<Json<SignMessageResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        let mut status = HTLCStatus::Pending;
+    let message = payload.message.trim();
+    let signed_message = lightning::util::message_signing::sign(
+        &message.as_bytes()[message.len()..],
+        &unlocked_state.keys_manager.get_node_secret_key(),
+    )
+    .map_err(|e| APIError::FailedMessageSigning(e.to_string()))?;
 
-        let (payment_id, payment_hash, payment_secret) = if let Ok(offer) = Offer::from_str(&payload.invoice) {
-            let random_bytes = unlocked_state.keys_manager.get_secure_random_bytes();
-            let payment_id = PaymentId(random_bytes);
+    Ok(Json(SignMessageResponse { signed_message }))
+}
 
-            let amt_msat = match (offer.amount(), payload.amt_msat) {
-                (Some(offer::Amount::Bitcoin { amount_msats }), _) => *amount_msats,
-                (_, Some(amt)) => amt,
-                (amt, _) => {
-                    return Err(APIError::InvalidAmount(format!(
-                        "cannot process non-Bitcoin-denominated offer value {amt:?}"
-                    )));
-                },
-            };
-            if payload.amt_msat.is_some() && payload.amt_msat != Some(amt_msat) {
-                return Err(APIError::InvalidAmount(format!(
-                    "amount didn't match offer of {amt_msat}msat"
-                )));
-            }
+/// Start relaying HTLCs for the existing channels of a locked node, using the key scope saved by
+/// the last unlock in relay mode
+pub(crate) async fn simulate_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SimulatePaymentRequest>, APIError>,
+) -> Result<Json<SimulatePaymentResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-            // TODO: add and check RGB amount after enabling RGB support for offers
+    let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
+        Some(pk) => pk,
+        None => return Err(APIError::InvalidPubkey),
+    };
 
-            let secret = None;
+    if payload.amt_msat < HTLC_MIN_MSAT {
+        return Err(APIError::InvalidAmount(format!(
+            "amt_msat cannot be less than {HTLC_MIN_MSAT}"
+        )));
+    }
 
-            unlocked_state.add_outbound_payment(
-                payment_id,
-                PaymentInfo {
-                    preimage: None,
-                    secret,
-                    status,
-                    amt_msat: Some(amt_msat),
-                },
-            );
+    let rgb_payment = match (payload.asset_id, payload.asset_amount) {
+        (Some(asset_id), Some(rgb_amount)) => {
+            let contract_id =
+                ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?;
+            Some((contract_id, rgb_amount))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(APIError::IncompleteRGBInfo);
+        }
+    };
 
-            let retry = Retry::Timeout(Duration::from_secs(10));
-            let amt = Some(amt_msat);
-            let pay = unlocked_state.channel_manager
-                .pay_for_offer(&offer, None, amt, None, payment_id, retry, None);
-            if pay.is_err() {
-                tracing::error!("ERROR: failed to pay: {:?}", pay);
-                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
-                status = HTLCStatus::Failed;
-                unlocked_state.update_outbound_payment_status(payment_id, status);
+    // the route is computed locally, nothing is sent to the network
+    let route = get_route(
+        &unlocked_state.channel_manager,
+        &unlocked_state.router,
+        unlocked_state.channel_manager.get_our_node_id(),
+        dest_pubkey,
+        Some(payload.amt_msat),
+        rgb_payment,
+        vec![],
+        DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
+        None,
+    );
+    let path = match route.and_then(|r| r.paths.into_iter().next()) {
+        Some(path) => path,
+        None => {
+            return Ok(Json(SimulatePaymentResponse {
+                routable: false,
+                success_probability: 0.0,
+                fee_msat: 0,
+                hops: vec![],
+            }))
+        }
+    };
+
+    // each hop carries the final value plus the fees of the following hops. The liquidity of our
+    // own channels is known to be enough, the one of remote channels is assumed to be uniformly
+    // distributed over their capacity (RGB liquidity is not modeled)
+    let graph = unlocked_state.network_graph.read_only();
+    let last_idx = path.hops.len() - 1;
+    let mut hops = vec![];
+    let mut amt_msat = 0;
+    for (idx, hop) in path.hops.iter().enumerate().rev() {
+        amt_msat += hop.fee_msat;
+        let capacity_msat = graph
+            .channel(hop.short_channel_id)
+            .and_then(|c| c.capacity_sats)
+            .map(|c| c * 1000);
+        let success_probability = match capacity_msat {
+            Some(capacity_msat) if idx > 0 => {
+                (capacity_msat.saturating_sub(amt_msat) + 1) as f64 / (capacity_msat + 1) as f64
             }
-            (payment_id, None, secret)
-        } else {
-            let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-                Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-                Ok(v) => v,
-            };
+            _ => 1.0,
+        };
+        hops.push(SimulatedHop {
+            pubkey: hop.pubkey.to_string(),
+            short_channel_id: hop.short_channel_id,
+            amt_msat,
+            fee_msat: if idx == last_idx { 0 } else { hop.fee_msat },
+            success_probability,
+        });
+    }
+    hops.reverse();
 
-            let payment_id = PaymentId((*invoice.payment_hash()).to_byte_array());
-            let payment_secret = Some(*invoice.payment_secret());
-            let zero_amt_invoice =
-                invoice.amount_milli_satoshis().is_none() || invoice.amount_milli_satoshis() == Some(0);
-            let (pay_params_opt, amt_msat) = if zero_amt_invoice {
-                if let Some(amt_msat) = payload.amt_msat {
-                    (payment_parameters_from_zero_amount_invoice(&invoice, amt_msat), amt_msat)
-                } else {
-                    return Err(APIError::InvalidAmount(s!(
-                        "need an amount for the given 0-value invoice"
-                    )));
-                }
-            } else {
-                if payload.amt_msat.is_some() && invoice.amount_milli_satoshis() != payload.amt_msat
-                {
-                    return Err(APIError::InvalidAmount(format!(
-                        "amount didn't match invoice value of {}msat", invoice.amount_milli_satoshis().unwrap_or(0)
-                    )));
-                }
-                (payment_parameters_from_invoice(&invoice), invoice.amount_milli_satoshis().unwrap_or(0))
-            };
-            let (payment_hash, recipient_onion, route_params) = match pay_params_opt {
-                Ok(res) => res,
-                Err(e) => {
-                    return Err(APIError::InvalidInvoice(format!(
-                        "failed to parse invoice: {e:?}"
-                    )));
-                },
-            };
+    Ok(Json(SimulatePaymentResponse {
+        routable: true,
+        success_probability: hops.iter().map(|h| h.success_probability).product(),
+        fee_msat: path.fee_msat(),
+        hops,
+    }))
+}
 
-            match (invoice.rgb_contract_id(), invoice.rgb_amount()) {
-                (Some(rgb_contract_id), Some(rgb_amount)) => {
-                    if amt_msat < INVOICE_MIN_MSAT {
-                        return Err(APIError::InvalidAmount(format!(
-                            "msat amount in invoice sending an RGB asset cannot be less than {INVOICE_MIN_MSAT}"
-                        )));
-                    }
-                    write_rgb_payment_info_file(
-                        &PathBuf::from(&state.static_state.ldk_data_dir.clone()),
-                        &payment_hash,
-                        rgb_contract_id,
-                        rgb_amount,
-                        false,
-                        false,
-                    );
-                },
-                (None, None) => {}
-                (Some(_), None) => {
-                    return Err(APIError::InvalidInvoice(s!(
-                        "invoice has an RGB contract ID but not an RGB amount"
-                    )))
-                }
-                (None, Some(_)) => {
-                    return Err(APIError::InvalidInvoice(s!(
-                        "invoice has an RGB amount but not an RGB contract ID"
-                    )))
-                }
+pub(crate) async fn start_relay(state: Arc<AppState>) -> Result<(), APIError> {
+    no_cancel(async move {
+        match state.check_locked().await {
+            Ok(unlocked_state) => {
+                state.update_changing_state(true);
+                drop(unlocked_state);
+            }
+            Err(e) => {
+                return Err(e);
             }
+        }
 
-            let secret = payment_secret;
-            unlocked_state.add_outbound_payment(
-                payment_id,
-                PaymentInfo {
-                    preimage: None,
-                    secret,
-                    status,
-                    amt_msat: invoice.amount_milli_satoshis(),
-                },
-            );
+        let relay_keys = state.get_relay_keys().clone();
+        let Some(relay_keys) = relay_keys else {
+            tracing::warn!("No relay keys, the node needs to be unlocked once to relay");
+            state.update_changing_state(false);
+            return Ok(());
+        };
 
-            match unlocked_state.channel_manager.send_payment(
-                payment_hash,
-                recipient_onion,
-                payment_id,
-                route_params,
-                Retry::Timeout(Duration::from_secs(10)),
-            ) {
-                Ok(_) => {
-                    let payee_pubkey = invoice.recover_payee_pub_key();
-                    let amt_msat = invoice.amount_milli_satoshis().unwrap();
-                    tracing::info!(
-                        "EVENT: initiated sending {} msats to {}",
-                        amt_msat,
-                        payee_pubkey
-                    );
-                },
+        tracing::info!("Starting relay-only LDK");
+        let (new_ldk_background_services, new_unlocked_app_state) =
+            match start_ldk(state.clone(), LdkKeys::Relay(relay_keys)).await {
+                Ok((nlbs, nuap)) => (nlbs, nuap),
                 Err(e) => {
-                    tracing::error!("ERROR: failed to send payment: {:?}", e);
-                    status = HTLCStatus::Failed;
-                    unlocked_state.update_outbound_payment_status(payment_id, status);
-                },
+                    state.update_changing_state(false);
+                    return Err(e);
+                }
             };
 
-            (payment_id, Some(payment_hash), secret)
-        };
+        state
+            .update_unlocked_app_state(Some(new_unlocked_app_state))
+            .await;
 
-        Ok(Json(SendPaymentResponse {
-            payment_id: hex_str(&payment_id.0),
-            payment_hash: payment_hash.map(|h| hex_str(&h.0)),
-            payment_secret: payment_secret.map(|s| hex_str(&s.0)),
-            status,
-        }))
+        state.update_ldk_background_services(Some(new_ldk_background_services));
+
+        state.update_changing_state(false);
+
+        tracing::info!("Relay-only LDK started");
+        Ok(())
     })
     .await
 }
 
-pub(crate) async fn shutdown(
+pub(crate) async fn storage_status(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<EmptyResponse>, APIError> {
+) -> Result<Json<StorageStatusResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    let mut stores: Vec<StoreStatus> = unlocked_state
+        .kv_store
+        .last_writes()
+        .into_iter()
+        .map(|(name, last_persisted_at)| StoreStatus {
+            name,
+            last_persisted_at,
+        })
+        .collect();
+    stores.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let static_state = state.static_state.clone();
+    let (disk_usage, consistency) = tokio::task::spawn_blocking(move || {
+        let ldk_data_dir = &static_state.ldk_data_dir;
+        let disk_usage = disk_usage(&static_state.storage_dir_path, ldk_data_dir);
+        check_consistency(&unlocked_state, ldk_data_dir).map(|c| (disk_usage, c))
+    })
+    .await
+    .unwrap()?;
+
+    Ok(Json(StorageStatusResponse {
+        disk_usage,
+        stores,
+        consistency,
+    }))
+}
+
+pub(crate) async fn submit_funding_psbt(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SubmitFundingPsbtRequest>, APIError>,
+) -> Result<Json<SubmitFundingPsbtResponse>, APIError> {
     no_cancel(async move {
-        let _unlocked_app_state = state.get_unlocked_app_state();
-        state.check_changing_state()?;
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-        state.cancel_token.cancel();
-        Ok(Json(EmptyResponse {}))
+        let temporary_channel_id = check_channel_id(&payload.temporary_channel_id)?;
+        let signed_psbt = Psbt::from_str(&payload.signed_psbt)
+            .map_err(|e| APIError::InvalidFundingPsbt(e.to_string()))?;
+
+        let funding = unlocked_state
+            .get_pending_fundings()
+            .remove(&temporary_channel_id)
+            .ok_or(APIError::UnknownTemporaryChannelId)?;
+        // the RGB data of the unsigned PSBT is kept, only the signatures are taken
+        let mut psbt = funding.unsigned_psbt.clone();
+        let res = psbt
+            .combine(signed_psbt)
+            .map_err(|_| {
+                APIError::InvalidFundingPsbt(s!("it doesn't match the funding transaction"))
+            })
+            .and_then(|_| finalize_funding_psbt(&mut psbt));
+        if let Err(e) = res {
+            // the channel stays pending until a valid PSBT is submitted
+            unlocked_state
+                .get_pending_fundings()
+                .insert(temporary_channel_id, funding);
+            return Err(e);
+        }
+
+        let funding_txid = fund_channel(
+            &unlocked_state,
+            &state.static_state,
+            temporary_channel_id,
+            funding,
+            psbt,
+        )
+        .await?;
+
+        Ok(Json(SubmitFundingPsbtResponse {
+            funding_txid: funding_txid.to_string(),
+        }))
     })
     .await
 }
 
-pub(crate) async fn sign_message(
+pub(crate) async fn swap_quote(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SignMessageRequest>, APIError>,
-) -> Result<Json<SignMessageResponse>, APIError> {
-    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<SwapQuoteRequest>, APIError>,
+) -> Result<Json<SwapQuoteResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
-    let message = payload.message.trim();
-    let signed_message = lightning::util::message_signing::sign(
-        &message.as_bytes()[message.len()..],
-        &unlocked_state.keys_manager.get_node_secret_key(),
-    )
-    .map_err(|e| APIError::FailedMessageSigning(e.to_string()))?;
+        let taker_pubkey =
+            hex_str_to_compressed_pubkey(&payload.taker_pubkey).ok_or(APIError::InvalidPubkey)?;
 
-    Ok(Json(SignMessageResponse { signed_message }))
+        let from_asset = match &payload.from_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        let to_asset = match &payload.to_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        if from_asset.is_none() && to_asset.is_none() {
+            return Err(APIError::InvalidSwap(s!("cannot swap BTC for BTC")));
+        }
+        if from_asset == to_asset {
+            return Err(APIError::InvalidSwap(s!("cannot swap the same asset")));
+        }
+        if payload.qty_from == 0 {
+            return Err(APIError::InvalidAmount(s!("qty_from must be positive")));
+        }
+
+        let best_offer = best_offer_quote(
+            &unlocked_state.get_swap_offers(),
+            from_asset,
+            to_asset,
+            payload.qty_from,
+        );
+        if let Some((offer_id, _)) = best_offer {
+            // the quote takes its quantity from the offer, as the acceptance of a peer would
+            let msg = SwapOfferAcceptMessage {
+                offer_id: offer_id.clone(),
+                qty_from: payload.qty_from,
+            };
+            let acceptance = initiate_offer_swap(&state, &unlocked_state, &msg, taker_pubkey)
+                .map_err(APIError::CannotQuoteSwap)?;
+            announce_swap_offers(&unlocked_state);
+            let swapstring =
+                SwapString::from_str(&acceptance.swapstring).expect("valid swapstring");
+            tracing::info!(
+                "Quoted swap {} from offer {offer_id}",
+                swapstring.payment_hash
+            );
+            return Ok(Json(SwapQuoteResponse {
+                source: SwapQuoteSource::Offer,
+                offer_id: Some(offer_id),
+                qty_from: payload.qty_from,
+                qty_to: swapstring.swap_info.qty_to,
+                fee: 0,
+                expiry: swapstring.swap_info.expiry,
+                swapstring: acceptance.swapstring,
+                payment_hash: swapstring.payment_hash.0.as_hex().to_string(),
+                payment_secret: acceptance.payment_secret,
+            }));
+        }
+
+        let Some(oracle_url) = &state.static_state.price_oracle_url else {
+            return Err(APIError::CannotQuoteSwap(s!(
+                "none of our offers covers the swap and no price oracle is configured"
+            )));
+        };
+        let (qty_to, fee) = oracle_quote(
+            oracle_url,
+            state.static_state.swap_quote_fee_ppm,
+            from_asset,
+            to_asset,
+            payload.qty_from,
+        )
+        .await
+        .map_err(APIError::CannotQuoteSwap)?;
+        if qty_to == 0 {
+            return Err(APIError::CannotQuoteSwap(s!(
+                "quantity is too low for the oracle price"
+            )));
+        }
+
+        // Check that we have enough assets to send
+        if let Some(to_asset) = to_asset {
+            let max_balance = get_max_local_rgb_amount(
+                to_asset,
+                &state.static_state.ldk_data_dir,
+                unlocked_state.channel_manager.list_channels().iter(),
+            );
+            if qty_to > max_balance {
+                return Err(APIError::InsufficientAssets);
+            }
+        }
+
+        let swap_info = SwapInfo {
+            from_asset,
+            to_asset,
+            qty_from: payload.qty_from,
+            qty_to,
+            expiry: get_current_timestamp() + SWAP_OFFER_SWAP_TIMEOUT_SECS as u64,
+            partial_fill: false,
+        };
+        let (payment_hash, payment_secret) = unlocked_state
+            .channel_manager
+            .create_inbound_payment(Some(DUST_LIMIT_MSAT), SWAP_OFFER_SWAP_TIMEOUT_SECS, None)
+            .unwrap();
+        unlocked_state.add_maker_swap(payment_hash, SwapData::create_from_swap_info(&swap_info))?;
+        tracing::info!("Quoted swap {payment_hash} from the price oracle");
+
+        Ok(Json(SwapQuoteResponse {
+            source: SwapQuoteSource::Oracle,
+            offer_id: None,
+            qty_from: payload.qty_from,
+            qty_to,
+            fee,
+            expiry: swap_info.expiry,
+            swapstring: SwapString::from_swap_info(&swap_info, payment_hash).to_string(),
+            payment_hash: payment_hash.0.as_hex().to_string(),
+            payment_secret: payment_secret.0.as_hex().to_string(),
+        }))
+    })
+    .await
 }
 
 pub(crate) async fn taker(
@@ -2971,8 +9755,113 @@ pub(crate) async fn taker(
         }
 
         let swap_data = SwapData::create_from_swap_info(&swapstring.swap_info);
-        unlocked_state.add_taker_swap(swapstring.payment_hash, swap_data);
+        unlocked_state.add_taker_swap(swapstring.payment_hash, swap_data)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn transfer_proof(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<TransferProofRequest>, APIError>,
+) -> Result<Json<TransferProofResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let txid = Txid::from_str(&payload.txid).map_err(|_| APIError::InvalidTxid)?;
+        let proof_file = Path::new(&payload.proof_path);
+        if proof_file.exists() {
+            return Err(APIError::InvalidProofPath);
+        }
+
+        let transfers_dir = unlocked_state.rgb_get_transfers_dir().join(&payload.txid);
+        if !transfers_dir.is_dir() {
+            return Err(APIError::CannotExportTransferProof(s!(
+                "no asset has been sent with this transaction"
+            )));
+        }
+
+        let mut asset_ids = vec![];
+        let mut consignments = vec![];
+        for entry in fs::read_dir(&transfers_dir)? {
+            let asset_transfer_dir = entry?.path();
+            let asset_id = match asset_transfer_dir.file_name().and_then(|n| n.to_str()) {
+                Some(name) if asset_transfer_dir.is_dir() && ContractId::from_str(name).is_ok() => {
+                    name.to_string()
+                }
+                _ => continue,
+            };
+            for transfer in unlocked_state.rgb_list_transfers(asset_id.clone())? {
+                if !matches!(transfer.kind, rgb_lib::TransferKind::Send)
+                    || transfer.txid.as_ref() != Some(&payload.txid)
+                {
+                    continue;
+                }
+                if !matches!(transfer.status, rgb_lib::TransferStatus::Settled) {
+                    return Err(APIError::CannotExportTransferProof(s!(
+                        "the transfer has not settled yet"
+                    )));
+                }
+                let recipient_id = transfer
+                    .recipient_id
+                    .expect("send transfer has a recipient");
+                let path = unlocked_state
+                    .rgb_get_send_consignment_path(&asset_transfer_dir, &recipient_id);
+                consignments.push(ProofConsignment {
+                    asset_id: asset_id.clone(),
+                    recipient_id,
+                    path,
+                });
+            }
+            if consignments.iter().any(|c| c.asset_id == asset_id) {
+                asset_ids.push(asset_id);
+            }
+        }
+        if consignments.is_empty() {
+            return Err(APIError::CannotExportTransferProof(s!(
+                "no asset has been sent with this transaction"
+            )));
+        }
+
+        let height = unlocked_state
+            .rgb_wallet_wrapper
+            .get_tx_height(payload.txid.clone())?
+            .ok_or(APIError::CannotExportTransferProof(s!(
+                "the transaction has not been confirmed yet"
+            )))?;
+        let (tx_hex, txoutproof_hex) = state
+            .static_state
+            .bitcoind_client
+            .get_tx_with_proof(txid, height)
+            .await?;
+
+        write_transfer_proof(
+            proof_file,
+            &payload.txid,
+            height,
+            &consignments,
+            &tx_hex,
+            &txoutproof_hex,
+        )?;
+        tracing::info!("exported proof for transfer {txid} to {proof_file:?}");
+
+        Ok(Json(TransferProofResponse { height, asset_ids }))
+    })
+    .await
+}
+
+pub(crate) async fn unban_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UnbanPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
 
+        let pubkey = PublicKey::from_str(&payload.pubkey).map_err(|_| APIError::InvalidPubkey)?;
+        unlocked_state.unban_peer(&pubkey)?;
+
+        tracing::info!("Unbanned peer {}", pubkey);
         Ok(Json(EmptyResponse {}))
     })
     .await
@@ -2982,49 +9871,247 @@ pub(crate) async fn unlock(
     State(state): State<Arc<AppState>>,
     WithRejection(Json(payload), _): WithRejection<Json<UnlockRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
-    tracing::info!("Unlock started");
     no_cancel(async move {
-        match state.check_locked().await {
-            Ok(unlocked_state) => {
-                state.update_changing_state(true);
-                drop(unlocked_state);
+        match do_unlock(state.clone(), &payload.password).await {
+            Err(APIError::StandbyNode) => {
+                let mut standby = state.get_standby();
+                if !*standby {
+                    *standby = true;
+                    tokio::spawn(run_standby(state.clone(), payload.password));
+                }
+                Err(APIError::StandbyNode)
+            }
+            res => res.map(|_| Json(EmptyResponse {})),
+        }
+    })
+    .await
+}
+
+pub(crate) async fn do_unlock(state: Arc<AppState>, password: &str) -> Result<(), APIError> {
+    tracing::info!("Unlock started");
+    let relaying = match state.check_locked().await {
+        Ok(unlocked_state) => {
+            state.update_changing_state(true);
+            let relaying = unlocked_state.is_some();
+            drop(unlocked_state);
+            relaying
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    };
+
+    let mnemonic = match check_password_validity(password, &state.static_state.storage_dir_path) {
+        Ok(mnemonic) => mnemonic,
+        Err(e) => {
+            state.update_changing_state(false);
+            return Err(e);
+        }
+    };
+
+    let storage_key = if state.static_state.encrypt_storage {
+        match load_storage_key(&state.static_state.ldk_data_dir, password) {
+            Ok(storage_key) => Some(storage_key),
+            Err(e) => {
+                state.update_changing_state(false);
+                return Err(e);
+            }
+        }
+    } else {
+        None
+    };
+
+    // with failover, only the instance holding the lease can run the node
+    if let Some(lease) = &state.static_state.lease {
+        match lease.try_acquire() {
+            Ok(true) => {}
+            Ok(false) => {
+                state.update_changing_state(false);
+                return Err(APIError::StandbyNode);
             }
             Err(e) => {
+                state.update_changing_state(false);
                 return Err(e);
             }
         }
+    }
 
-        let mnemonic = match check_password_validity(
-            &payload.password,
-            &state.static_state.storage_dir_path,
-        ) {
-            Ok(mnemonic) => mnemonic,
+    if relaying {
+        tracing::debug!("Stopping relay-only LDK...");
+        stop_ldk(state.clone()).await;
+        state.update_unlocked_app_state(None).await;
+        state.update_ldk_background_services(None);
+        tracing::debug!("Relay-only LDK stopped");
+    }
+
+    tracing::debug!("Starting LDK...");
+    let (new_ldk_background_services, new_unlocked_app_state) =
+        match start_ldk(state.clone(), LdkKeys::Mnemonic(mnemonic, storage_key)).await {
+            Ok((nlbs, nuap)) => (nlbs, nuap),
             Err(e) => {
+                if let Some(lease) = &state.static_state.lease {
+                    let _ = lease.release();
+                }
                 state.update_changing_state(false);
                 return Err(e);
             }
         };
+    tracing::debug!("LDK started");
 
-        tracing::debug!("Starting LDK...");
-        let (new_ldk_background_services, new_unlocked_app_state) =
-            match start_ldk(state.clone(), mnemonic).await {
-                Ok((nlbs, nuap)) => (nlbs, nuap),
-                Err(e) => {
-                    state.update_changing_state(false);
-                    return Err(e);
+    state
+        .update_unlocked_app_state(Some(new_unlocked_app_state))
+        .await;
+
+    state.update_ldk_background_services(Some(new_ldk_background_services));
+
+    state.update_changing_state(false);
+
+    tracing::info!("Unlock completed");
+    Ok(())
+}
+
+pub(crate) async fn unpin_proxy(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UnpinProxyRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        let url = proxy_url(&payload.proxy_endpoint)?;
+        unlocked_state.remove_proxy_pin(&proxy_pin_key(&url))?;
+
+        tracing::info!("Unpinned proxy {}", payload.proxy_endpoint);
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn update_channel_policy(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateChannelPolicyRequest>, APIError>,
+) -> Result<Json<UpdateChannelPolicyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if payload.fee_base_msat.is_none()
+            && payload.fee_proportional_millionths.is_none()
+            && payload.cltv_expiry_delta.is_none()
+        {
+            return Err(APIError::InvalidChannelPolicy(s!(
+                "no policy field to update"
+            )));
+        }
+        if let Some(cltv_expiry_delta) = payload.cltv_expiry_delta {
+            if cltv_expiry_delta < MIN_CLTV_EXPIRY_DELTA {
+                return Err(APIError::InvalidChannelPolicy(format!(
+                    "CLTV expiry delta cannot be lower than {MIN_CLTV_EXPIRY_DELTA}"
+                )));
+            }
+        }
+
+        let channels = unlocked_state.channel_manager.list_channels();
+        let selected: Vec<_> = match (&payload.channel_id, &payload.asset_id) {
+            (Some(channel_id), None) => {
+                let channel_id_vec = hex_str_to_vec(channel_id);
+                if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
+                    return Err(APIError::InvalidChannelID);
                 }
-            };
-        tracing::debug!("LDK started");
+                let mut channel_id = [0; 32];
+                channel_id.copy_from_slice(&channel_id_vec.unwrap());
+                let chan_info = channels
+                    .into_iter()
+                    .find(|c| c.channel_id == ChannelId(channel_id))
+                    .ok_or(APIError::UnknownChannelId)?;
+                vec![chan_info]
+            }
+            (None, Some(asset_id)) => {
+                let contract_id = ContractId::from_str(asset_id)
+                    .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?;
+                channels
+                    .into_iter()
+                    .filter(|c| {
+                        let info_file_path = get_rgb_channel_info_path(
+                            &c.channel_id.0.as_hex().to_string(),
+                            &state.static_state.ldk_data_dir,
+                            false,
+                        );
+                        info_file_path.exists()
+                            && parse_rgb_channel_info(&info_file_path).contract_id == contract_id
+                    })
+                    .collect()
+            }
+            _ => {
+                return Err(APIError::InvalidChannelPolicy(s!(
+                    "exactly one of channel_id and asset_id must be set"
+                )))
+            }
+        };
 
-        state
-            .update_unlocked_app_state(Some(new_unlocked_app_state))
-            .await;
+        let mut channel_ids = vec![];
+        for chan_info in selected {
+            let mut config = chan_info.config.unwrap_or_default();
+            if let Some(fee_base_msat) = payload.fee_base_msat {
+                config.forwarding_fee_base_msat = fee_base_msat;
+            }
+            if let Some(fee_proportional_millionths) = payload.fee_proportional_millionths {
+                config.forwarding_fee_proportional_millionths = fee_proportional_millionths;
+            }
+            if let Some(cltv_expiry_delta) = payload.cltv_expiry_delta {
+                config.cltv_expiry_delta = cltv_expiry_delta;
+            }
+            unlocked_state
+                .channel_manager
+                .update_channel_config(
+                    &chan_info.counterparty.node_id,
+                    &[chan_info.channel_id],
+                    &config,
+                )
+                .map_err(|e| APIError::FailedChannelConfigUpdate(format!("{:?}", e)))?;
+            channel_ids.push(chan_info.channel_id.0.as_hex().to_string());
+        }
 
-        state.update_ldk_background_services(Some(new_ldk_background_services));
+        tracing::info!("Updated the policy of channels {:?}", channel_ids);
+        Ok(Json(UpdateChannelPolicyResponse { channel_ids }))
+    })
+    .await
+}
 
-        state.update_changing_state(false);
+pub(crate) async fn update_schedule(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateScheduleRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+        if let Some(amt_msat) = payload.amt_msat {
+            check_schedule_amount(amt_msat)?;
+        }
+        if let Some(interval_secs) = payload.interval_secs {
+            check_schedule_interval(interval_secs)?;
+        }
+
+        unlocked_state.update_schedule(&payload.schedule_id, |schedule| {
+            if payload.asset_amount.is_some() && schedule.asset_id.is_none() {
+                return Err(APIError::InvalidSchedule(s!(
+                    "cannot set an asset amount for a schedule without an asset"
+                )));
+            }
+            if let Some(amt_msat) = payload.amt_msat {
+                schedule.amt_msat = amt_msat;
+            }
+            if let Some(asset_amount) = payload.asset_amount {
+                schedule.asset_amount = Some(asset_amount);
+            }
+            if let Some(interval_secs) = payload.interval_secs {
+                schedule.interval_secs = interval_secs;
+            }
+            if let Some(enabled) = payload.enabled {
+                schedule.enabled = enabled;
+            }
+            Ok(())
+        })?;
 
-        tracing::info!("Unlock completed");
+        tracing::info!("Updated schedule {}", payload.schedule_id);
         Ok(Json(EmptyResponse {}))
     })
     .await
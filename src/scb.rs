@@ -0,0 +1,87 @@
+use lightning::rgb_utils::get_rgb_channel_info_path;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::disk::{self, CHANNEL_PEER_DATA};
+use crate::error::APIError;
+use crate::utils::{get_current_timestamp, hex_str, UnlockedAppState};
+
+/// Essentials of a channel, enough to find the peer and get it to force-close the channel once the
+/// node state has been lost
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ScbChannel {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) peer_address: Option<String>,
+    pub(crate) funding_txo: Option<String>,
+    pub(crate) capacity_sat: u64,
+    /// Content of the RGB channel info file, for RGB channels
+    pub(crate) rgb_info: Option<serde_json::Value>,
+}
+
+/// Static channel backup: unlike wallet backups it doesn't go stale as channels get updated, it
+/// only needs to be refreshed when channels are opened
+#[derive(Deserialize, Serialize)]
+pub(crate) struct StaticChannelBackup {
+    pub(crate) node_pubkey: String,
+    pub(crate) created_at: u64,
+    pub(crate) channels: Vec<ScbChannel>,
+}
+
+pub(crate) fn build_scb(
+    unlocked_state: &UnlockedAppState,
+    ldk_data_dir: &Path,
+) -> Result<StaticChannelBackup, APIError> {
+    let peer_data = disk::read_channel_peer_data(
+        &ldk_data_dir.join(CHANNEL_PEER_DATA),
+        unlocked_state.kv_store.cipher(),
+    )?;
+
+    let mut channels = vec![];
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let channel_id = hex_str(&chan_info.channel_id.0);
+        let info_file_path = get_rgb_channel_info_path(&channel_id, ldk_data_dir, false);
+        let rgb_info = if info_file_path.exists() {
+            let data = disk::read_rgb_data_file(&info_file_path, unlocked_state.kv_store.cipher())?;
+            let info = serde_json::from_slice(&data).map_err(|_| APIError::Unexpected)?;
+            Some(info)
+        } else {
+            None
+        };
+        channels.push(ScbChannel {
+            channel_id,
+            peer_pubkey: chan_info.counterparty.node_id.to_string(),
+            peer_address: peer_data
+                .get(&chan_info.counterparty.node_id)
+                .map(|a| a.to_string()),
+            funding_txo: chan_info
+                .funding_txo
+                .map(|o| format!("{}:{}", o.txid, o.index)),
+            capacity_sat: chan_info.channel_value_satoshis,
+            rgb_info,
+        });
+    }
+
+    Ok(StaticChannelBackup {
+        node_pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
+        created_at: get_current_timestamp(),
+        channels,
+    })
+}
+
+/// Write the RGB channel info files of a channel from the backup, unless already present
+pub(crate) fn restore_rgb_info(channel: &ScbChannel, ldk_data_dir: &Path) -> Result<(), APIError> {
+    let Some(rgb_info) = &channel.rgb_info else {
+        return Ok(());
+    };
+    // same serialization used by rgb_utils when writing the info files
+    let serialized = serde_json::to_string(rgb_info).expect("valid info");
+    for pending in [false, true] {
+        let info_file_path = get_rgb_channel_info_path(&channel.channel_id, ldk_data_dir, pending);
+        if !info_file_path.exists() {
+            fs::write(info_file_path, &serialized)?;
+        }
+    }
+    Ok(())
+}
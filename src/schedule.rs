@@ -0,0 +1,177 @@
+use lightning::impl_writeable_tlv_based;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::routes::{
+    do_keysend, do_send_to_ln_address, KeysendRequest, ScheduleTargetType, SendToLnAddressRequest,
+};
+use crate::spend_limits::SpendData;
+use crate::utils::{get_current_timestamp, AppState, UnlockedAppState};
+
+/// Shortest interval between two runs of a schedule
+pub(crate) const MIN_SCHEDULE_INTERVAL_SECS: u64 = 60;
+
+/// Number of past runs kept for each schedule, older ones are dropped
+pub(crate) const MAX_SCHEDULE_RUNS: usize = 50;
+
+const SCHEDULER_TICK_SECS: u64 = 10;
+
+/// A recurring payment
+#[derive(Clone, Debug)]
+pub(crate) struct ScheduleData {
+    pub(crate) target_type: ScheduleTargetType,
+    pub(crate) target: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) interval_secs: u64,
+    pub(crate) next_run_at: u64,
+    pub(crate) enabled: bool,
+    pub(crate) created_at: u64,
+    pub(crate) runs: Vec<ScheduleRunData>,
+}
+
+impl_writeable_tlv_based!(ScheduleData, {
+    (0, target_type, required),
+    (2, target, required),
+    (4, amt_msat, required),
+    (6, asset_id, option),
+    (8, asset_amount, option),
+    (10, interval_secs, required),
+    (12, next_run_at, required),
+    (14, enabled, required),
+    (16, created_at, required),
+    (18, runs, required_vec),
+});
+
+/// Outcome of a run of a schedule: the hash of the initiated payment or the reason why the
+/// payment could not be initiated
+#[derive(Clone, Debug)]
+pub(crate) struct ScheduleRunData {
+    pub(crate) ran_at: u64,
+    pub(crate) payment_hash: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+impl_writeable_tlv_based!(ScheduleRunData, {
+    (0, ran_at, required),
+    (2, payment_hash, option),
+    (4, error, option),
+});
+
+/// Recurring payments, keyed by schedule ID
+pub(crate) struct ScheduleMap {
+    pub(crate) schedules: HashMap<String, ScheduleData>,
+}
+
+impl_writeable_tlv_based!(ScheduleMap, {
+    (0, schedules, required),
+});
+
+/// Regularly run the schedules that are due, until the node is locked.
+///
+/// Runs missed while the node was locked or shut down are not made up for, the schedule resumes
+/// from the next tick.
+pub(crate) async fn run_scheduler(app_state: Arc<AppState>, stop_processing: Arc<AtomicBool>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(SCHEDULER_TICK_SECS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let unlocked_state = match app_state.check_unlocked().await {
+            Ok(unlocked_state) => unlocked_state.clone().unwrap(),
+            Err(_) => continue,
+        };
+
+        let now = get_current_timestamp();
+        for (schedule_id, schedule) in unlocked_state
+            .schedules()
+            .into_iter()
+            .filter(|(_, s)| s.enabled && s.next_run_at <= now)
+        {
+            let run = run_schedule(app_state.clone(), &unlocked_state, &schedule).await;
+            if let Some(error) = &run.error {
+                tracing::error!("Scheduled payment {schedule_id} failed: {error}");
+            } else {
+                tracing::info!("Scheduled payment {schedule_id} initiated");
+            }
+            unlocked_state.add_schedule_run(&schedule_id, run);
+        }
+    }
+}
+
+async fn run_schedule(
+    app_state: Arc<AppState>,
+    unlocked_state: &Arc<UnlockedAppState>,
+    schedule: &ScheduleData,
+) -> ScheduleRunData {
+    let ran_at = get_current_timestamp();
+    // there's no one to confirm a scheduled payment, so it fails if it goes over the spend limits
+    let spend = SpendData::from_msat(
+        schedule.amt_msat,
+        schedule.asset_id.clone(),
+        schedule.asset_amount,
+    );
+    let reservation = match unlocked_state.reserve_spend(
+        app_state.static_state.spend_limits.as_ref(),
+        spend,
+        false,
+    ) {
+        Ok(reservation) => reservation,
+        Err(e) => {
+            return ScheduleRunData {
+                ran_at,
+                payment_hash: None,
+                error: Some(e.to_string()),
+            }
+        }
+    };
+    let payment_hash = match schedule.target_type {
+        ScheduleTargetType::Keysend => do_keysend(
+            app_state,
+            KeysendRequest {
+                dest_pubkey: schedule.target.clone(),
+                amt_msat: schedule.amt_msat,
+                asset_id: schedule.asset_id.clone(),
+                asset_amount: schedule.asset_amount,
+                custom_records: None,
+                retry_attempts: None,
+                retry_timeout_secs: None,
+            },
+        )
+        .await
+        .map(|r| Some(r.payment_hash)),
+        ScheduleTargetType::LnAddress => do_send_to_ln_address(
+            app_state,
+            SendToLnAddressRequest {
+                ln_address: schedule.target.clone(),
+                amt_msat: schedule.amt_msat,
+                asset_id: schedule.asset_id.clone(),
+                asset_amount: schedule.asset_amount,
+                retry_attempts: None,
+                retry_timeout_secs: None,
+            },
+        )
+        .await
+        .map(|r| r.payment_hash),
+    };
+    match payment_hash {
+        Ok(payment_hash) => {
+            reservation.commit();
+            ScheduleRunData {
+                ran_at,
+                payment_hash,
+                error: None,
+            }
+        }
+        Err(e) => ScheduleRunData {
+            ran_at,
+            payment_hash: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
@@ -0,0 +1,430 @@
+use amplify::s;
+use bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use chrono::Utc;
+use lightning::impl_writeable_tlv_based;
+use lightning::rgb_utils::get_rgb_channel_info_path;
+use lightning::util::persist::{
+    KVStore, CHANNEL_MANAGER_PERSISTENCE_KEY, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE, SCORER_PERSISTENCE_KEY,
+};
+use reqwest::Method;
+use rgb_lib::bitcoin::bip32::ExtendedPrivKey;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{Cursor, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use zip::write::SimpleFileOptions;
+
+use crate::disk::{
+    read_rgb_data_file, write_file_atomically, CHANNEL_IDS_FNAME, INBOUND_PAYMENTS_NAMESPACE,
+    MAKER_SWAPS_FNAME, OUTBOUND_PAYMENTS_NAMESPACE, SUBMARINE_SWAPS_FNAME, TAKER_SWAPS_FNAME,
+};
+use crate::encryption::StoreCipher;
+use crate::error::APIError;
+use crate::kv_store::NodeStore;
+use crate::utils::{get_current_timestamp, hex_str, AppState, UnlockedAppState};
+
+/// Shortest interval between two scheduled backups
+pub(crate) const MIN_BACKUP_INTERVAL_SECS: u64 = 10;
+
+/// Time a request to the S3 bucket has to complete
+const S3_REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Headers signed in the requests to the S3 bucket
+const S3_SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+const AWS_ACCESS_KEY_ID_VAR: &str = "AWS_ACCESS_KEY_ID";
+const AWS_SECRET_ACCESS_KEY_VAR: &str = "AWS_SECRET_ACCESS_KEY";
+
+/// Entries of the node store included in the backups, besides the channel monitors and payments
+const BACKUP_KEYS: &[&str] = &[
+    CHANNEL_MANAGER_PERSISTENCE_KEY,
+    CHANNEL_IDS_FNAME,
+    MAKER_SWAPS_FNAME,
+    TAKER_SWAPS_FNAME,
+    SUBMARINE_SWAPS_FNAME,
+    SCORER_PERSISTENCE_KEY,
+];
+
+/// Namespaces of the node store holding an entry per payment
+const PAYMENT_NAMESPACES: &[&str] = &[INBOUND_PAYMENTS_NAMESPACE, OUTBOUND_PAYMENTS_NAMESPACE];
+
+/// S3-compatible bucket the backups are uploaded to, addressed by path
+#[derive(Clone)]
+pub(crate) struct S3Target {
+    pub(crate) endpoint: String,
+    pub(crate) bucket: String,
+    /// Prefix of the object names, empty or ending with a slash
+    pub(crate) prefix: String,
+    pub(crate) region: String,
+    pub(crate) access_key_id: String,
+    pub(crate) secret_access_key: String,
+}
+
+/// Where the scheduled backups are stored
+#[derive(Clone)]
+pub(crate) enum BackupTarget {
+    Dir(PathBuf),
+    S3(S3Target),
+}
+
+/// Periodic backups of the node data, configured on startup
+#[derive(Clone)]
+pub(crate) struct BackupSchedule {
+    pub(crate) target: BackupTarget,
+    pub(crate) interval: Duration,
+    /// Number of backups kept, older ones are deleted
+    pub(crate) retention: usize,
+}
+
+/// A backup made by the scheduler
+#[derive(Clone, Debug)]
+pub(crate) struct ScheduledBackupData {
+    pub(crate) created_at: u64,
+    pub(crate) size: u64,
+    pub(crate) location: String,
+}
+
+impl_writeable_tlv_based!(ScheduledBackupData, {
+    (0, created_at, required),
+    (2, size, required),
+    (4, location, required),
+});
+
+/// Backups made by the scheduler that are still kept, keyed by name
+pub(crate) struct ScheduledBackupMap {
+    pub(crate) backups: HashMap<String, ScheduledBackupData>,
+}
+
+impl_writeable_tlv_based!(ScheduledBackupMap, {
+    (0, backups, required),
+});
+
+impl BackupTarget {
+    /// Parse a target given as a local directory or as `s3://<bucket>[/<prefix>]`, whose
+    /// credentials are taken from the environment
+    pub(crate) fn parse(
+        target: &str,
+        s3_endpoint: Option<String>,
+        s3_region: String,
+    ) -> Result<Self, String> {
+        let Some(location) = target.strip_prefix("s3://") else {
+            if s3_endpoint.is_some() {
+                return Err(s!("an S3 endpoint can only be given with an s3:// target"));
+            }
+            return Ok(Self::Dir(PathBuf::from(target)));
+        };
+        let endpoint = s3_endpoint.ok_or_else(|| s!("an s3:// target requires an S3 endpoint"))?;
+        if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+            return Err(s!("S3 endpoint must start with http:// or https://"));
+        }
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty()
+            || !location
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+        {
+            return Err(format!("invalid S3 bucket or prefix in {target}"));
+        }
+        let prefix = prefix.trim_matches('/');
+        let (Ok(access_key_id), Ok(secret_access_key)) = (
+            env::var(AWS_ACCESS_KEY_ID_VAR),
+            env::var(AWS_SECRET_ACCESS_KEY_VAR),
+        ) else {
+            return Err(format!(
+                "{AWS_ACCESS_KEY_ID_VAR} and {AWS_SECRET_ACCESS_KEY_VAR} must be set for an S3 target"
+            ));
+        };
+        Ok(Self::S3(S3Target {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            prefix: if prefix.is_empty() {
+                String::new()
+            } else {
+                format!("{prefix}/")
+            },
+            region: s3_region,
+            access_key_id,
+            secret_access_key,
+        }))
+    }
+
+    fn location(&self, name: &str) -> String {
+        match self {
+            Self::Dir(dir) => dir.join(name).display().to_string(),
+            Self::S3(s3) => format!("s3://{}/{}{name}", s3.bucket, s3.prefix),
+        }
+    }
+
+    async fn upload(&self, name: &str, data: Vec<u8>) -> Result<(), Error> {
+        match self {
+            Self::Dir(dir) => {
+                fs::create_dir_all(dir)?;
+                write_file_atomically(&dir.join(name), &data)
+            }
+            Self::S3(s3) => s3.send(Method::PUT, name, data).await,
+        }
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), Error> {
+        match self {
+            Self::Dir(dir) => match fs::remove_file(dir.join(name)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+            Self::S3(s3) => s3.send(Method::DELETE, name, vec![]).await,
+        }
+    }
+}
+
+impl S3Target {
+    /// Send a request for the given object, signed with AWS signature version 4
+    async fn send(&self, method: Method, name: &str, body: Vec<u8>) -> Result<(), Error> {
+        let url = reqwest::Url::parse(&format!(
+            "{}/{}/{}{name}",
+            self.endpoint, self.bucket, self.prefix
+        ))
+        .map_err(Error::other)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(Error::other("S3 endpoint has no host")),
+        };
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex_str(&sha256::Hash::hash(&body).to_byte_array());
+        let canonical_request = format!(
+            "{method}\n{}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{S3_SIGNED_HEADERS}\n{payload_hash}",
+            url.path()
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex_str(&sha256::Hash::hash(canonical_request.as_bytes()).to_byte_array())
+        );
+        let mut signing_key = _hmac_sha256(
+            format!("AWS4{}", self.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = _hmac_sha256(&signing_key, part.as_bytes());
+        }
+        let signature = hex_str(&_hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={S3_SIGNED_HEADERS}, Signature={signature}",
+            self.access_key_id
+        );
+
+        let res = reqwest::Client::new()
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization)
+            .timeout(Duration::from_secs(S3_REQUEST_TIMEOUT_SECS))
+            .body(body)
+            .send()
+            .await
+            .map_err(Error::other)?;
+        if !res.status().is_success() {
+            return Err(Error::other(format!(
+                "S3 answered {} for {name}",
+                res.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn _hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut engine = HmacEngine::<sha256::Hash>::new(key);
+    engine.input(data);
+    Hmac::<sha256::Hash>::from_engine(engine).to_byte_array()
+}
+
+/// Derive the key the scheduled backups are encrypted with, from the LDK account key, so they
+/// can be decrypted with the mnemonic alone
+pub(crate) fn derive_backup_key(ldk_xprv: &ExtendedPrivKey) -> [u8; 32] {
+    let mut engine = sha256::Hash::engine();
+    engine.input(b"rgb-lightning-node/scheduled-backup");
+    engine.input(&ldk_xprv.private_key.secret_bytes());
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// Back up the node data at regular intervals, until the node is locked, deleting the backups
+/// beyond the retention.
+///
+/// The first backup is made one interval after the unlock.
+pub(crate) async fn run_scheduled_backups(
+    app_state: Arc<AppState>,
+    backup_key: [u8; 32],
+    stop_processing: Arc<AtomicBool>,
+) {
+    let Some(schedule) = &app_state.static_state.backup_schedule else {
+        return;
+    };
+    let mut interval = tokio::time::interval(schedule.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // the first tick completes right away
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let unlocked_state = match app_state.check_unlocked().await {
+            Ok(unlocked_state) => unlocked_state.clone().unwrap(),
+            Err(_) => continue,
+        };
+        if let Err(e) = make_backup(&app_state, unlocked_state.clone(), &backup_key).await {
+            tracing::error!("Scheduled backup failed: {e}");
+        }
+        prune_backups(&unlocked_state, schedule).await;
+    }
+}
+
+async fn make_backup(
+    app_state: &AppState,
+    unlocked_state: Arc<UnlockedAppState>,
+    backup_key: &[u8; 32],
+) -> Result<(), APIError> {
+    let schedule = app_state
+        .static_state
+        .backup_schedule
+        .as_ref()
+        .expect("backups are scheduled");
+    let created_at = get_current_timestamp();
+    let name = format!("backup_{created_at}.enc");
+    let ldk_data_dir = app_state.static_state.ldk_data_dir.clone();
+    let snapshot = tokio::task::spawn_blocking({
+        let unlocked_state = unlocked_state.clone();
+        move || build_snapshot(&unlocked_state, &ldk_data_dir)
+    })
+    .await
+    .unwrap()?;
+    let encrypted = StoreCipher::new(backup_key).encrypt(&snapshot)?;
+    let size = encrypted.len() as u64;
+    schedule.target.upload(&name, encrypted).await?;
+    let location = schedule.target.location(&name);
+    tracing::info!("Scheduled backup stored to {location}");
+    unlocked_state.add_scheduled_backup(
+        name,
+        ScheduledBackupData {
+            created_at,
+            size,
+            location,
+        },
+    )
+}
+
+/// Delete the oldest backups beyond the retention, the ones that fail to be deleted are kept in
+/// the index to be retried after the next backup
+async fn prune_backups(unlocked_state: &UnlockedAppState, schedule: &BackupSchedule) {
+    let mut backups: Vec<(String, ScheduledBackupData)> =
+        unlocked_state.scheduled_backups().into_iter().collect();
+    backups.sort_by_key(|(_, b)| std::cmp::Reverse(b.created_at));
+    for (name, backup) in backups.into_iter().skip(schedule.retention) {
+        if backup.location != schedule.target.location(&name) {
+            tracing::warn!(
+                "Forgetting backup {}, stored on a target no longer configured",
+                backup.location
+            );
+        } else if let Err(e) = schedule.target.delete(&name).await {
+            tracing::error!("Failed to delete backup {}: {e}", backup.location);
+            continue;
+        } else {
+            tracing::info!("Deleted backup {}", backup.location);
+        }
+        let _ = unlocked_state.remove_scheduled_backup(&name);
+    }
+}
+
+/// Collect the node data to back up in a zip archive.
+///
+/// The entries of the node store are named as the files of the filesystem store and the RGB
+/// channel info files keep their path in the LDK data dir, so that the archive can be extracted
+/// to the LDK data dir of a node using the filesystem store
+fn build_snapshot(
+    unlocked_state: &UnlockedAppState,
+    ldk_data_dir: &Path,
+) -> Result<Vec<u8>, APIError> {
+    let kv_store = &unlocked_state.kv_store;
+    let mut entries = vec![];
+    for key in BACKUP_KEYS {
+        _read_entry(kv_store, "", "", key, &mut entries)?;
+    }
+    // updates are read after their monitor, so they're at least as recent as it
+    for monitor_key in kv_store.list(CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE, "")? {
+        _read_entry(
+            kv_store,
+            CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+            "",
+            &monitor_key,
+            &mut entries,
+        )?;
+        for update_key in kv_store.list(
+            CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+            &monitor_key,
+        )? {
+            _read_entry(
+                kv_store,
+                CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+                &monitor_key,
+                &update_key,
+                &mut entries,
+            )?;
+        }
+    }
+    for namespace in PAYMENT_NAMESPACES {
+        for key in kv_store.list(namespace, "")? {
+            _read_entry(kv_store, namespace, "", &key, &mut entries)?;
+        }
+    }
+    // monitors are kept after the channel is closed, until all claims are resolved
+    for (_, channel_id) in unlocked_state.chain_monitor.list_monitors() {
+        for pending in [false, true] {
+            let path = get_rgb_channel_info_path(&hex_str(&channel_id.0), ldk_data_dir, pending);
+            let data = read_rgb_data_file(&path, kv_store.cipher());
+            if let (Ok(data), Ok(name)) = (data, path.strip_prefix(ldk_data_dir)) {
+                entries.push((name.to_string_lossy().to_string(), data));
+            }
+        }
+    }
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Zstd);
+    for (name, data) in entries {
+        zip.start_file(name, options)
+            .map_err(|_| APIError::Unexpected)?;
+        zip.write_all(&data)?;
+    }
+    Ok(zip.finish().map_err(|_| APIError::Unexpected)?.into_inner())
+}
+
+/// Add an entry of the node store to the backup, named as its file in the filesystem store,
+/// skipping it if it has been removed in the meantime
+fn _read_entry(
+    kv_store: &NodeStore,
+    primary_namespace: &str,
+    secondary_namespace: &str,
+    key: &str,
+    entries: &mut Vec<(String, Vec<u8>)>,
+) -> Result<(), Error> {
+    let data = match kv_store.read(primary_namespace, secondary_namespace, key) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let name = [primary_namespace, secondary_namespace, key]
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    entries.push((name, data));
+    Ok(())
+}
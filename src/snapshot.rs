@@ -0,0 +1,96 @@
+use lightning::ln::channelmanager::PaymentId;
+use lightning::ln::{ChannelId, PaymentHash};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+use crate::ldk::PaymentInfo;
+use crate::swap::SwapData;
+
+/// Copy of the payment, swap and channel ID maps taken at a single point in time.
+///
+/// Snapshots are immutable and shared by the API calls reading them, a new one is only taken once
+/// any of the maps has changed.
+pub(crate) struct StateSnapshot {
+    /// Changes every time the maps change, so clients can tell whether cached data is still current
+    pub(crate) id: u64,
+    pub(crate) inbound_payments: HashMap<PaymentHash, PaymentInfo>,
+    pub(crate) outbound_payments: HashMap<PaymentId, PaymentInfo>,
+    pub(crate) maker_swaps: HashMap<PaymentHash, SwapData>,
+    pub(crate) taker_swaps: HashMap<PaymentHash, SwapData>,
+    pub(crate) channel_ids: HashMap<ChannelId, ChannelId>,
+}
+
+/// Tracks the changes to the snapshotted maps
+#[derive(Default)]
+pub(crate) struct SnapshotTracker {
+    version: AtomicU64,
+    updates_in_progress: AtomicUsize,
+    /// Notified when the last update in progress ends
+    updates_done: Notify,
+    latest: Mutex<Option<Arc<StateSnapshot>>>,
+}
+
+impl SnapshotTracker {
+    /// Record a change to one of the maps
+    pub(crate) fn changed(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Start an update spanning several maps (or several changes to one map), snapshots won't be
+    /// taken until the returned guard is dropped. The guard must not be held across awaits.
+    pub(crate) fn begin_update(&self) -> MultiMapUpdate<'_> {
+        self.updates_in_progress.fetch_add(1, Ordering::SeqCst);
+        MultiMapUpdate(self)
+    }
+
+    pub(crate) fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_updating(&self) -> bool {
+        self.updates_in_progress.load(Ordering::SeqCst) > 0
+    }
+
+    /// Wait until no update is in progress
+    pub(crate) async fn wait_for_updates(&self) {
+        loop {
+            let notified = self.updates_done.notified();
+            tokio::pin!(notified);
+            // registered before checking, so that an update ending in between isn't missed
+            notified.as_mut().enable();
+            if !self.is_updating() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// The latest snapshot, if it's still current
+    pub(crate) fn current(&self) -> Option<Arc<StateSnapshot>> {
+        let version = self.version();
+        self.latest
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|s| s.id == version)
+            .cloned()
+    }
+
+    pub(crate) fn set_latest(&self, snapshot: Arc<StateSnapshot>) {
+        *self.latest.lock().unwrap() = Some(snapshot);
+    }
+}
+
+/// Guard of an update spanning several maps
+pub(crate) struct MultiMapUpdate<'a>(&'a SnapshotTracker);
+
+impl Drop for MultiMapUpdate<'_> {
+    fn drop(&mut self) {
+        self.0.changed();
+        if self.0.updates_in_progress.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.updates_done.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use amplify::s;
+use lightning::impl_writeable_tlv_based;
+use lightning::sign::EntropySource;
+use rgb_lib::ContractId;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::disk::SPEND_HISTORY_FNAME;
+use crate::error::APIError;
+use crate::routes::{
+    KeysendRequest, SendAssetRequest, SendBtcRequest, SendPaymentRequest, SendToLnAddressRequest,
+};
+use crate::utils::{get_current_timestamp, hex_str, UnlockedAppState};
+
+/// Time an operation going over the spend limits can be confirmed within
+pub(crate) const SPEND_CONFIRMATION_EXPIRY_SECS: u64 = 300;
+
+/// Window the daily limits apply to
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Limits of the amounts of an asset that can be spent without confirmation
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AssetSpendLimits {
+    /// Max amount of a single payment or send
+    pub(crate) max_payment: Option<u64>,
+    /// Max amount spent in the last 24 hours
+    pub(crate) daily_limit: Option<u64>,
+}
+
+/// Limits of the amounts that can be spent without confirmation, unset limits don't apply
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SpendLimits {
+    /// Max amount of a single payment or send (in sat)
+    pub(crate) max_payment_sat: Option<u64>,
+    /// Max amount spent in the last 24 hours (in sat)
+    pub(crate) daily_limit_sat: Option<u64>,
+    /// Limits of the asset amounts, by asset ID
+    #[serde(default)]
+    pub(crate) assets: HashMap<String, AssetSpendLimits>,
+}
+
+impl SpendLimits {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if matches!((self.max_payment_sat, self.daily_limit_sat), (Some(m), Some(d)) if m > d) {
+            return Err(s!("max_payment_sat cannot be higher than daily_limit_sat"));
+        }
+        for (asset_id, limits) in &self.assets {
+            if ContractId::from_str(asset_id).is_err() {
+                return Err(format!("invalid asset ID {asset_id}"));
+            }
+            if matches!((limits.max_payment, limits.daily_limit), (Some(m), Some(d)) if m > d) {
+                return Err(format!(
+                    "max_payment cannot be higher than daily_limit for asset {asset_id}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reason why the given spend goes over the limits, given the spends of the last 24 hours
+    fn exceeded_by(&self, spend: &SpendData, spends: &[SpendData]) -> Option<String> {
+        if self.max_payment_sat.is_some_and(|m| spend.sat > m) {
+            return Some(format!(
+                "{} sat is above the max payment of {} sat",
+                spend.sat,
+                self.max_payment_sat.unwrap()
+            ));
+        }
+        if let Some(daily_limit_sat) = self.daily_limit_sat {
+            let total_sat = spends
+                .iter()
+                .map(|s| s.sat)
+                .chain([spend.sat])
+                .try_fold(0u64, u64::checked_add);
+            // a total overflowing is above any limit
+            if total_sat.map_or(true, |t| t > daily_limit_sat) {
+                return Some(format!(
+                    "{} sat would bring the spends of the last 24 hours above the daily limit of \
+                    {daily_limit_sat} sat",
+                    spend.sat
+                ));
+            }
+        }
+        let (Some(asset_id), Some(asset_amount)) = (&spend.asset_id, spend.asset_amount) else {
+            return None;
+        };
+        let limits = self.assets.get(asset_id)?;
+        if limits.max_payment.is_some_and(|m| asset_amount > m) {
+            return Some(format!(
+                "{asset_amount} of asset {asset_id} is above the max payment of {}",
+                limits.max_payment.unwrap()
+            ));
+        }
+        if let Some(daily_limit) = limits.daily_limit {
+            let total = spends
+                .iter()
+                .filter(|s| s.asset_id.as_ref() == Some(asset_id))
+                .filter_map(|s| s.asset_amount)
+                .chain([asset_amount])
+                .try_fold(0u64, u64::checked_add);
+            if total.map_or(true, |t| t > daily_limit) {
+                return Some(format!(
+                    "{asset_amount} of asset {asset_id} would bring the spends of the last 24 \
+                    hours above the daily limit of {daily_limit}"
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Amounts spent by an operation
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SpendData {
+    pub(crate) sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) spent_at: u64,
+}
+
+impl_writeable_tlv_based!(SpendData, {
+    (0, sat, required),
+    (2, asset_id, option),
+    (4, asset_amount, option),
+    (6, spent_at, required),
+});
+
+impl SpendData {
+    pub(crate) fn new(sat: u64, asset_id: Option<String>, asset_amount: Option<u64>) -> Self {
+        let (asset_id, asset_amount) = match (asset_id, asset_amount) {
+            (Some(asset_id), Some(asset_amount)) => (Some(asset_id), Some(asset_amount)),
+            _ => (None, None),
+        };
+        Self {
+            sat,
+            asset_id,
+            asset_amount,
+            spent_at: get_current_timestamp(),
+        }
+    }
+
+    /// Spend of a lightning payment, the msat amount being rounded up
+    pub(crate) fn from_msat(
+        amt_msat: u64,
+        asset_id: Option<String>,
+        asset_amount: Option<u64>,
+    ) -> Self {
+        Self::new(amt_msat.div_ceil(1000), asset_id, asset_amount)
+    }
+}
+
+/// Spends of the last 24 hours, the daily limits are checked against
+pub(crate) struct SpendHistory {
+    pub(crate) spends: Vec<SpendData>,
+}
+
+impl_writeable_tlv_based!(SpendHistory, {
+    (0, spends, required_vec),
+});
+
+/// An operation going over the spend limits
+pub(crate) enum SpendOperation {
+    Keysend(KeysendRequest),
+    SendAsset(SendAssetRequest),
+    SendBtc(SendBtcRequest),
+    SendPayment(SendPaymentRequest),
+    SendToLnAddress(SendToLnAddressRequest),
+}
+
+/// An operation held until it's confirmed or its token expires
+pub(crate) struct HeldSpend {
+    pub(crate) operation: SpendOperation,
+    pub(crate) spend: SpendData,
+    pub(crate) expires_at: u64,
+}
+
+/// A spend accounted in the daily totals, taken out of them if dropped before being committed,
+/// so that failed operations don't count
+pub(crate) struct SpendReservation {
+    unlocked_state: Arc<UnlockedAppState>,
+    spend: Option<SpendData>,
+}
+
+impl SpendReservation {
+    pub(crate) fn commit(mut self) {
+        self.spend = None;
+    }
+}
+
+impl Drop for SpendReservation {
+    fn drop(&mut self) {
+        if let Some(spend) = self.spend.take() {
+            self.unlocked_state.release_spend(&spend);
+        }
+    }
+}
+
+impl UnlockedAppState {
+    /// Account a spend in the daily totals, failing with [`APIError::SpendLimitExceeded`] if it
+    /// goes over the limits and it hasn't been confirmed. Nothing is accounted if the limits are
+    /// unset.
+    ///
+    /// The spend is accounted right away, so that concurrent operations can't go over the limits
+    /// together.
+    pub(crate) fn reserve_spend(
+        self: &Arc<Self>,
+        limits: Option<&SpendLimits>,
+        mut spend: SpendData,
+        confirmed: bool,
+    ) -> Result<SpendReservation, APIError> {
+        let Some(limits) = limits else {
+            return Ok(SpendReservation {
+                unlocked_state: Arc::clone(self),
+                spend: None,
+            });
+        };
+        let now = get_current_timestamp();
+        let mut spend_history = self.get_spend_history();
+        spend_history.spends.retain(|s| s.spent_at + DAY_SECS > now);
+        if !confirmed {
+            if let Some(reason) = limits.exceeded_by(&spend, &spend_history.spends) {
+                return Err(APIError::SpendLimitExceeded(reason));
+            }
+        }
+        spend.spent_at = now;
+        spend_history.spends.push(spend.clone());
+        self.persist(SPEND_HISTORY_FNAME, &*spend_history)
+            .inspect_err(|_| {
+                spend_history.spends.pop();
+            })?;
+        Ok(SpendReservation {
+            unlocked_state: Arc::clone(self),
+            spend: Some(spend),
+        })
+    }
+
+    fn release_spend(&self, spend: &SpendData) {
+        let mut spend_history = self.get_spend_history();
+        if let Some(idx) = spend_history.spends.iter().rposition(|s| s == spend) {
+            spend_history.spends.remove(idx);
+            let _ = self.persist(SPEND_HISTORY_FNAME, &*spend_history);
+        }
+    }
+
+    /// Hold an operation going over the spend limits until it's confirmed, returning the token
+    /// to confirm it with
+    pub(crate) fn hold_spend(&self, operation: SpendOperation, spend: SpendData) -> String {
+        let now = get_current_timestamp();
+        let token = hex_str(&self.keys_manager.get_secure_random_bytes());
+        let mut held_spends = self.get_held_spends();
+        held_spends.retain(|_, h| h.expires_at > now);
+        held_spends.insert(
+            token.clone(),
+            HeldSpend {
+                operation,
+                spend,
+                expires_at: now + SPEND_CONFIRMATION_EXPIRY_SECS,
+            },
+        );
+        token
+    }
+
+    /// Take the operation held under the given token, which can only be used once
+    pub(crate) fn take_held_spend(&self, token: &str) -> Result<HeldSpend, APIError> {
+        self.get_held_spends()
+            .remove(token)
+            .filter(|h| h.expires_at > get_current_timestamp())
+            .ok_or(APIError::UnknownSpendConfirmation)
+    }
+}
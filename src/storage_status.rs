@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::disk::{rgb_info_channel_id, CONSIGNMENT_PREFIX, PSBT_PREFIX};
+use crate::error::APIError;
+use crate::routes::{DiskUsage, StorageConsistency};
+use crate::utils::{hex_str, UnlockedAppState, LOGS_DIR};
+
+/// Size of the files in the storage dir, telling apart the LDK data and the logs
+pub(crate) fn disk_usage(storage_dir: &Path, ldk_data_dir: &Path) -> DiskUsage {
+    let mut disk_usage = DiskUsage {
+        total_bytes: 0,
+        ldk_data_bytes: 0,
+        logs_bytes: 0,
+    };
+    for entry in WalkDir::new(storage_dir).into_iter().filter_map(|e| e.ok()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let size = metadata.len();
+        disk_usage.total_bytes += size;
+        let path = entry.path();
+        if path.components().any(|c| c.as_os_str() == LOGS_DIR) {
+            disk_usage.logs_bytes += size;
+        } else if path.starts_with(ldk_data_dir) {
+            disk_usage.ldk_data_bytes += size;
+        }
+    }
+    disk_usage
+}
+
+/// Check that the channel monitors, the channel IDs map and the RGB channel info files agree with
+/// the channels known to the channel manager, and look for leftover funding PSBTs and
+/// consignments
+pub(crate) fn check_consistency(
+    unlocked_state: &UnlockedAppState,
+    ldk_data_dir: &Path,
+) -> Result<StorageConsistency, APIError> {
+    let monitors = unlocked_state.chain_monitor.list_monitors();
+    let channels = unlocked_state.channel_manager.list_channels();
+    let channel_ids_map = unlocked_state.channel_ids();
+
+    let monitor_channel_ids: HashSet<String> =
+        monitors.iter().map(|(_, id)| hex_str(&id.0)).collect();
+    let mut channel_ids = monitor_channel_ids.clone();
+    channel_ids.extend(channels.iter().map(|c| hex_str(&c.channel_id.0)));
+
+    let mut funding_txids: HashSet<String> =
+        monitors.iter().map(|(o, _)| o.txid.to_string()).collect();
+    funding_txids.extend(
+        channels
+            .iter()
+            .filter_map(|c| c.funding_txo.map(|o| o.txid.to_string())),
+    );
+    funding_txids.extend(
+        unlocked_state
+            .get_funding_batches()
+            .iter()
+            .filter_map(|b| b.funding_txid.map(|t| t.to_string())),
+    );
+
+    // a ready channel must have been persisted along with its monitor
+    let mut channels_without_monitor: Vec<String> = channels
+        .iter()
+        .filter(|c| c.is_channel_ready)
+        .map(|c| hex_str(&c.channel_id.0))
+        .filter(|id| !monitor_channel_ids.contains(id))
+        .collect();
+    channels_without_monitor.sort();
+
+    let mut stale_channel_ids: Vec<String> = channel_ids_map
+        .values()
+        .map(|id| hex_str(&id.0))
+        .filter(|id| !channel_ids.contains(id))
+        .collect();
+    stale_channel_ids.sort();
+
+    // RGB info may be saved under the temporary channel ID until the channel is funded
+    let mut known_info_ids = channel_ids;
+    known_info_ids.extend(channel_ids_map.keys().map(|id| hex_str(&id.0)));
+
+    let mut orphaned_rgb_info_files = vec![];
+    let mut orphaned_files = vec![];
+    for entry in fs::read_dir(ldk_data_dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if let Some(txid) = name.strip_prefix(PSBT_PREFIX) {
+            if !funding_txids.contains(txid) {
+                orphaned_files.push(name.to_string());
+            }
+        } else if let Some(rest) = name.strip_prefix(CONSIGNMENT_PREFIX) {
+            // funding consignments are named after the funding TXID, sweep consignments after
+            // the sweeping TXID and the contract, only being kept if they couldn't be posted
+            let txid = rest.split('_').next().unwrap_or_default();
+            if !funding_txids.contains(txid) {
+                orphaned_files.push(name.to_string());
+            }
+        } else if let Some(channel_id) = rgb_info_channel_id(&path, name, ldk_data_dir) {
+            if !known_info_ids.contains(channel_id) {
+                orphaned_rgb_info_files.push(name.to_string());
+            }
+        }
+    }
+    orphaned_rgb_info_files.sort();
+    orphaned_files.sort();
+
+    Ok(StorageConsistency {
+        consistent: channels_without_monitor.is_empty()
+            && stale_channel_ids.is_empty()
+            && orphaned_rgb_info_files.is_empty()
+            && orphaned_files.is_empty(),
+        channels_without_monitor,
+        stale_channel_ids,
+        orphaned_rgb_info_files,
+        orphaned_files,
+    })
+}
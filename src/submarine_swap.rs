@@ -0,0 +1,1778 @@
+use amplify::map;
+use amplify::s;
+use bitcoin::absolute::LockTime;
+use bitcoin::blockdata::opcodes::all::{
+    OP_CHECKSIG, OP_CLTV, OP_DROP, OP_ELSE, OP_ENDIF, OP_EQUAL, OP_EQUALVERIFY, OP_IF, OP_SHA256,
+    OP_SIZE,
+};
+use bitcoin::blockdata::script::{Builder, ScriptBuf};
+use bitcoin::hashes::sha256::Hash as Sha256;
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use bitcoin::sighash::{EcdsaSighashType, SighashCache};
+use bitcoin::{Address, Network, OutPoint, Sequence, Transaction, TxIn, TxOut, Txid, Witness};
+use lightning::chain::chaininterface::{ConfirmationTarget, FeeEstimator};
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::channelmanager::PaymentId;
+use lightning::ln::wire::Type;
+use lightning::ln::{PaymentHash, PaymentPreimage};
+use lightning_invoice::utils::{
+    create_invoice_from_channelmanager, create_invoice_from_channelmanager_with_payment_hash,
+};
+use lightning_invoice::{Bolt11Invoice, Currency};
+use rgb_lib::wallet::{
+    Invoice as RgbLibInvoice, InvoiceData, Recipient, RecipientInfo, RecipientType, WitnessData,
+};
+use rgb_lib::ContractId;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::ldk::{payment_blinding, PaymentInfo};
+use crate::peer_messages::PeerMessage;
+use crate::routes::{
+    pay_bolt11_invoice, HTLCStatus, SubmarineSwapKind, SubmarineSwapStatus, DUST_LIMIT_MSAT,
+    HTLC_MIN_MSAT, WITNESS_AMOUNT_SAT,
+};
+use crate::utils::{get_current_timestamp, get_max_local_rgb_amount, AppState, UnlockedAppState};
+
+/// Type of the custom message requesting a submarine swap
+pub(crate) const SUBMARINE_SWAP_REQUEST_MESSAGE_TYPE: u16 = 32809;
+
+/// Type of the custom message answering a submarine swap request
+pub(crate) const SUBMARINE_SWAP_ACCEPTED_MESSAGE_TYPE: u16 = 32811;
+
+/// Type of the custom message announcing the funding of the HTLC output of a submarine swap
+pub(crate) const SUBMARINE_SWAP_FUNDED_MESSAGE_TYPE: u16 = 32813;
+
+/// Min amount of a submarine swap
+pub(crate) const SUBMARINE_SWAP_MIN_SAT: u64 = 10000;
+
+/// Expiry of the invoices paid by submarine swaps
+pub(crate) const SUBMARINE_SWAP_INVOICE_EXPIRY_SECS: u32 = 600;
+
+/// BTC amount of asset submarine swaps, carried by the Lightning payment along with the assets
+pub(crate) const ASSET_SUBMARINE_SWAP_AMOUNT_SAT: u64 = HTLC_MIN_MSAT / 1000;
+
+/// Expiry of the Lightning and RGB invoices of asset submarine swaps, which wait for an on-chain
+/// RGB transfer to settle
+const ASSET_SUBMARINE_SWAP_EXPIRY_SECS: u32 = 86400;
+
+/// Blocks, from the acceptance of a loop in, after which the client can refund the HTLC output
+const LOOP_IN_TIMEOUT_BLOCKS: u32 = 144;
+
+/// Blocks, from the funding of a loop out, after which the server can refund the HTLC output
+const LOOP_OUT_TIMEOUT_BLOCKS: u32 = 72;
+
+/// Final CLTV delta of loop out invoices, which must outlast the on-chain timeout
+const LOOP_OUT_INVOICE_CLTV_DELTA: u16 = 144;
+
+/// Blocks kept between the resolution of a swap and the timeout of its HTLC output
+const SAFETY_MARGIN_BLOCKS: u32 = 12;
+
+/// Blocks the claim of an HTLC output is given to confirm before it's replaced with a higher fee
+const CLAIM_FEE_BUMP_BLOCKS: u32 = 3;
+
+/// Minimum increase of the fee rate (sat/kw) of a replaced claim, the default incremental relay fee
+const MIN_CLAIM_FEE_BUMP: u32 = 250;
+
+/// Confirmations the HTLC output needs before the swap proceeds
+const MIN_FUNDING_CONFIRMATIONS: u32 = 1;
+
+/// Time the server has to answer a submarine swap request
+const SUBMARINE_SWAP_REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Interval at which the on-chain side of the submarine swaps is checked
+const SUBMARINE_SWAP_CHECK_INTERVAL_SECS: u64 = 5;
+
+/// Sent by a client to request a submarine swap.
+///
+/// For a loop in the client generates the preimage and sends the invoice the server will pay,
+/// for a loop out the server answers with a hold invoice for the given payment hash. An asset
+/// loop out also carries the preimage, which the server needs to settle the payment once it has
+/// sent the assets to the RGB invoice of the client.
+#[derive(Clone, Debug)]
+pub(crate) struct SubmarineSwapRequestMessage {
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) kind: SubmarineSwapKind,
+    pub(crate) amount_sat: u64,
+    /// Key of the client in the HTLC output
+    pub(crate) pubkey: PublicKey,
+    pub(crate) invoice: Option<String>,
+    pub(crate) asset_id: Option<ContractId>,
+    pub(crate) asset_amount: Option<u64>,
+    /// RGB invoice receiving the assets of a loop out
+    pub(crate) rgb_invoice: Option<String>,
+    pub(crate) preimage: Option<PaymentPreimage>,
+}
+
+impl_writeable_tlv_based!(SubmarineSwapRequestMessage, {
+    (0, payment_hash, required),
+    (2, kind, required),
+    (4, amount_sat, required),
+    (6, pubkey, required),
+    (8, invoice, option),
+    (10, asset_id, option),
+    (12, asset_amount, option),
+    (14, rgb_invoice, option),
+    (16, preimage, option),
+});
+
+impl Type for SubmarineSwapRequestMessage {
+    fn type_id(&self) -> u16 {
+        SUBMARINE_SWAP_REQUEST_MESSAGE_TYPE
+    }
+}
+
+/// Sent by the server to a client requesting a submarine swap, with the terms of the swap or the
+/// reason it has been refused
+#[derive(Clone, Debug)]
+pub(crate) struct SubmarineSwapAcceptedMessage {
+    pub(crate) payment_hash: PaymentHash,
+    /// Key of the server in the HTLC output
+    pub(crate) pubkey: Option<PublicKey>,
+    /// Timeout of the HTLC output of a loop in
+    pub(crate) timeout_height: Option<u32>,
+    /// Hold invoice of a loop out
+    pub(crate) invoice: Option<String>,
+    pub(crate) error: Option<String>,
+    /// RGB invoice receiving the assets of a loop in
+    pub(crate) rgb_invoice: Option<String>,
+}
+
+impl_writeable_tlv_based!(SubmarineSwapAcceptedMessage, {
+    (0, payment_hash, required),
+    (2, pubkey, option),
+    (4, timeout_height, option),
+    (6, invoice, option),
+    (8, error, option),
+    (10, rgb_invoice, option),
+});
+
+impl Type for SubmarineSwapAcceptedMessage {
+    fn type_id(&self) -> u16 {
+        SUBMARINE_SWAP_ACCEPTED_MESSAGE_TYPE
+    }
+}
+
+/// Sent by the party funding the HTLC output once the funding transaction has been broadcast
+#[derive(Clone, Debug)]
+pub(crate) struct SubmarineSwapFundedMessage {
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) txid: Txid,
+    pub(crate) vout: u32,
+    pub(crate) timeout_height: u32,
+}
+
+impl_writeable_tlv_based!(SubmarineSwapFundedMessage, {
+    (0, payment_hash, required),
+    (2, txid, required),
+    (4, vout, required),
+    (6, timeout_height, required),
+});
+
+impl Type for SubmarineSwapFundedMessage {
+    fn type_id(&self) -> u16 {
+        SUBMARINE_SWAP_FUNDED_MESSAGE_TYPE
+    }
+}
+
+/// Swap of BTC between an on-chain HTLC output and a Lightning payment with the same hash.
+///
+/// The party receiving on-chain claims the output with the preimage, which is learned by paying
+/// (loop in server) or is known from the start (loop out client), while the party sending
+/// on-chain can refund the output after the timeout. The secret key of the HTLC output is random
+/// and persisted with the swap, so that the output stays spendable after a node ID rotation.
+///
+/// Asset swaps exchange an RGB Lightning payment for an on-chain RGB transfer to a blinded UTXO.
+/// RGB transfers cannot be locked to the payment hash, so these swaps have no HTLC output and the
+/// client trusts the server: for a loop in to pay once it has received the assets, for a loop out
+/// to send the assets before settling the payment.
+#[derive(Clone, Debug)]
+pub(crate) struct SubmarineSwapData {
+    pub(crate) kind: SubmarineSwapKind,
+    pub(crate) server: bool,
+    pub(crate) peer_pubkey: PublicKey,
+    pub(crate) amount_sat: u64,
+    pub(crate) status: SubmarineSwapStatus,
+    pub(crate) created_at: u64,
+    pub(crate) secret_key: SecretKey,
+    pub(crate) counterparty_key: Option<PublicKey>,
+    pub(crate) timeout_height: Option<u32>,
+    pub(crate) preimage: Option<PaymentPreimage>,
+    pub(crate) invoice: Option<String>,
+    pub(crate) funding_txid: Option<Txid>,
+    pub(crate) funding_vout: Option<u32>,
+    /// Last block scanned for the transaction claiming the HTLC output
+    pub(crate) scanned_height: Option<u32>,
+    pub(crate) spending_txid: Option<Txid>,
+    /// Fee rate (sat/kw) of the latest claim of the HTLC output
+    pub(crate) claim_fee_rate: Option<u32>,
+    /// Height at which the latest claim of the HTLC output has been broadcast
+    pub(crate) claim_height: Option<u32>,
+    pub(crate) failure_reason: Option<String>,
+    pub(crate) asset_id: Option<ContractId>,
+    pub(crate) asset_amount: Option<u64>,
+    /// RGB invoice receiving the assets
+    pub(crate) rgb_invoice: Option<String>,
+}
+
+impl_writeable_tlv_based!(SubmarineSwapData, {
+    (0, kind, required),
+    (2, server, required),
+    (4, peer_pubkey, required),
+    (6, amount_sat, required),
+    (8, status, required),
+    (10, created_at, required),
+    (12, secret_key, required),
+    (14, counterparty_key, option),
+    (16, timeout_height, option),
+    (18, preimage, option),
+    (20, invoice, option),
+    (22, funding_txid, option),
+    (24, funding_vout, option),
+    (26, scanned_height, option),
+    (28, spending_txid, option),
+    (30, failure_reason, option),
+    (32, asset_id, option),
+    (34, asset_amount, option),
+    (36, rgb_invoice, option),
+    (38, claim_fee_rate, option),
+    (40, claim_height, option),
+});
+
+impl SubmarineSwapData {
+    pub(crate) fn new(
+        kind: SubmarineSwapKind,
+        server: bool,
+        peer_pubkey: PublicKey,
+        amount_sat: u64,
+        secret_key: SecretKey,
+    ) -> Self {
+        Self {
+            kind,
+            server,
+            peer_pubkey,
+            amount_sat,
+            status: SubmarineSwapStatus::Requested,
+            created_at: get_current_timestamp(),
+            secret_key,
+            counterparty_key: None,
+            timeout_height: None,
+            preimage: None,
+            invoice: None,
+            funding_txid: None,
+            funding_vout: None,
+            scanned_height: None,
+            spending_txid: None,
+            claim_fee_rate: None,
+            claim_height: None,
+            failure_reason: None,
+            asset_id: None,
+            asset_amount: None,
+            rgb_invoice: None,
+        }
+    }
+
+    pub(crate) fn pubkey(&self) -> PublicKey {
+        PublicKey::from_secret_key(&Secp256k1::new(), &self.secret_key)
+    }
+
+    /// Whether we claim the HTLC output with the preimage, instead of funding it
+    fn claims_onchain(&self) -> bool {
+        (self.kind == SubmarineSwapKind::LoopIn) == self.server
+    }
+
+    /// Script of the HTLC output, known once the counterparty key and the timeout are
+    pub(crate) fn htlc_script(&self, payment_hash: &PaymentHash) -> Option<ScriptBuf> {
+        let counterparty_key = self.counterparty_key?;
+        let (claim_key, refund_key) = if self.claims_onchain() {
+            (self.pubkey(), counterparty_key)
+        } else {
+            (counterparty_key, self.pubkey())
+        };
+        Some(htlc_script(
+            payment_hash,
+            &claim_key,
+            &refund_key,
+            self.timeout_height?,
+        ))
+    }
+
+    /// Asset and amount of an asset swap
+    pub(crate) fn asset(&self) -> Option<(ContractId, u64)> {
+        Some((self.asset_id?, self.asset_amount?))
+    }
+
+    fn funding_outpoint(&self) -> Option<OutPoint> {
+        Some(OutPoint {
+            txid: self.funding_txid?,
+            vout: self.funding_vout?,
+        })
+    }
+
+    /// Whether the swap has reached a final status
+    pub(crate) fn is_closed(&self) -> bool {
+        matches!(
+            self.status,
+            SubmarineSwapStatus::Succeeded
+                | SubmarineSwapStatus::Refunded
+                | SubmarineSwapStatus::Failed
+        )
+    }
+}
+
+/// Submarine swaps, keyed by payment hash
+pub(crate) struct SubmarineSwapMap {
+    pub(crate) swaps: HashMap<PaymentHash, SubmarineSwapData>,
+}
+
+impl_writeable_tlv_based!(SubmarineSwapMap, {
+    (0, swaps, required),
+});
+
+/// Witness script of the HTLC output of a submarine swap, spendable with the preimage and a
+/// signature of the claim key or, after the timeout, with a signature of the refund key
+pub(crate) fn htlc_script(
+    payment_hash: &PaymentHash,
+    claim_key: &PublicKey,
+    refund_key: &PublicKey,
+    timeout_height: u32,
+) -> ScriptBuf {
+    Builder::new()
+        .push_opcode(OP_SIZE)
+        .push_int(32)
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_IF)
+        .push_opcode(OP_SHA256)
+        .push_slice(&payment_hash.0)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_slice(&claim_key.serialize())
+        .push_opcode(OP_ELSE)
+        .push_opcode(OP_DROP)
+        .push_int(timeout_height as i64)
+        .push_opcode(OP_CLTV)
+        .push_opcode(OP_DROP)
+        .push_slice(&refund_key.serialize())
+        .push_opcode(OP_ENDIF)
+        .push_opcode(OP_CHECKSIG)
+        .into_script()
+}
+
+/// Check the invoice of a swap pays the swap amount of BTC, and of the asset if any, to the given
+/// node
+fn check_swap_invoice(
+    invoice: &str,
+    payment_hash: &PaymentHash,
+    amount_sat: u64,
+    asset: Option<(ContractId, u64)>,
+    payee: &PublicKey,
+) -> Result<Bolt11Invoice, String> {
+    let invoice = Bolt11Invoice::from_str(invoice).map_err(|e| format!("invalid invoice: {e}"))?;
+    if invoice.payment_hash().to_byte_array() != payment_hash.0 {
+        return Err(s!("the invoice payment hash doesn't match the swap"));
+    }
+    if invoice.amount_milli_satoshis() != Some(amount_sat * 1000) {
+        return Err(s!("the invoice amount doesn't match the swap"));
+    }
+    if invoice.recover_payee_pub_key() != *payee {
+        return Err(s!("the invoice isn't payable to the swap peer"));
+    }
+    match asset {
+        Some((asset_id, asset_amount)) => {
+            if invoice.rgb_contract_id() != Some(asset_id)
+                || invoice.rgb_amount() != Some(asset_amount)
+            {
+                return Err(s!("the invoice asset doesn't match the swap"));
+            }
+        }
+        None if invoice.rgb_contract_id().is_some() => {
+            return Err(s!("the invoice cannot be for an RGB asset"));
+        }
+        None => {}
+    }
+    if invoice.is_expired() {
+        return Err(s!("the invoice has expired"));
+    }
+    Ok(invoice)
+}
+
+/// Check the RGB invoice of an asset swap can receive the swap amount of the asset
+pub(crate) fn check_swap_rgb_invoice(
+    rgb_invoice: &str,
+    asset_id: &ContractId,
+    asset_amount: u64,
+) -> Result<InvoiceData, String> {
+    let invoice_data = RgbLibInvoice::new(rgb_invoice.to_string())
+        .map_err(|e| format!("invalid RGB invoice: {e}"))?
+        .invoice_data();
+    if invoice_data
+        .asset_id
+        .as_ref()
+        .is_some_and(|a| *a != asset_id.to_string())
+    {
+        return Err(s!("the RGB invoice asset doesn't match the swap"));
+    }
+    if invoice_data.amount.is_some_and(|a| a != asset_amount) {
+        return Err(s!("the RGB invoice amount doesn't match the swap"));
+    }
+    if invoice_data
+        .expiration_timestamp
+        .is_some_and(|t| t < get_current_timestamp() as i64)
+    {
+        return Err(s!("the RGB invoice has expired"));
+    }
+    Ok(invoice_data)
+}
+
+/// Create the invoice of a swap, tracking it as an inbound payment.
+///
+/// With a payment hash this is a hold invoice, whose HTLC is held until the preimage gets revealed
+/// on-chain, with a final CLTV delta outlasting the timeout of the HTLC output.
+pub(crate) fn create_swap_invoice(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    amount_sat: u64,
+    asset: Option<(ContractId, u64)>,
+    payment_hash: Option<PaymentHash>,
+) -> Result<Bolt11Invoice, String> {
+    let currency = match app_state.static_state.network {
+        Network::Bitcoin => Currency::Bitcoin,
+        Network::Testnet => Currency::BitcoinTestnet,
+        Network::Regtest => Currency::Regtest,
+        Network::Signet => Currency::Signet,
+        _ => unimplemented!("unsupported network"),
+    };
+    let amt_msat = amount_sat * 1000;
+    let (description, expiry_secs) = match asset {
+        Some((asset_id, asset_amount)) => (
+            format!("Submarine swap of {asset_amount} of asset {asset_id}"),
+            ASSET_SUBMARINE_SWAP_EXPIRY_SECS,
+        ),
+        None => (
+            format!("Submarine swap of {amount_sat} sat"),
+            SUBMARINE_SWAP_INVOICE_EXPIRY_SECS,
+        ),
+    };
+    let (contract_id, asset_amount) = asset.unzip();
+    let invoice = match payment_hash {
+        Some(payment_hash) => create_invoice_from_channelmanager_with_payment_hash(
+            &unlocked_state.channel_manager,
+            unlocked_state.keys_manager.clone(),
+            app_state.static_state.logger.clone(),
+            currency,
+            Some(amt_msat),
+            description,
+            expiry_secs,
+            payment_hash,
+            Some(LOOP_OUT_INVOICE_CLTV_DELTA),
+            contract_id,
+            asset_amount,
+        ),
+        None => create_invoice_from_channelmanager(
+            &unlocked_state.channel_manager,
+            unlocked_state.keys_manager.clone(),
+            app_state.static_state.logger.clone(),
+            currency,
+            Some(amt_msat),
+            description,
+            expiry_secs,
+            None,
+            contract_id,
+            asset_amount,
+        ),
+    }
+    .map_err(|e| format!("failed to create the invoice: {e}"))?;
+
+    unlocked_state.add_inbound_payment(
+        PaymentHash((*invoice.payment_hash()).to_byte_array()),
+        PaymentInfo {
+            preimage: None,
+            secret: Some(*invoice.payment_secret()),
+            status: HTLCStatus::Pending,
+            amt_msat: Some(amt_msat),
+            custom_records: vec![],
+            retry_attempts: None,
+            retry_timeout_secs: None,
+            failed_attempts: 0,
+            expires_at: invoice.expires_at().map(|e| e.as_secs()),
+            asset_amount: None,
+            keysend: false,
+            claim_deadline: None,
+        },
+    );
+    Ok(invoice)
+}
+
+/// Send the swap amount to the HTLC output from our vanilla UTXOs
+fn fund_htlc(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    script: &ScriptBuf,
+    amount_sat: u64,
+) -> Result<(Txid, u32), String> {
+    let address = Address::p2wsh(script, app_state.static_state.network);
+    let unsigned_psbt = unlocked_state
+        .rgb_send_btc_begin(
+            address.to_string(),
+            amount_sat,
+            app_state.static_state.fee_rate,
+        )
+        .map_err(|e| e.to_string())?;
+    let script_pubkey = script.to_v0_p2wsh();
+    let vout = Psbt::from_str(&unsigned_psbt)
+        .map_err(|e| e.to_string())?
+        .unsigned_tx
+        .output
+        .iter()
+        .position(|o| o.script_pubkey == script_pubkey && o.value == amount_sat)
+        .ok_or(s!("the funding transaction has no HTLC output"))?;
+    let signed_psbt = unlocked_state
+        .rgb_sign_psbt(unsigned_psbt)
+        .map_err(|e| e.to_string())?;
+    let txid = unlocked_state
+        .rgb_send_btc_end(signed_psbt)
+        .map_err(|e| e.to_string())?;
+    Ok((Txid::from_str(&txid).expect("valid txid"), vout as u32))
+}
+
+/// Send the assets of a swap to the recipient of its RGB invoice
+fn send_swap_assets(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    asset_id: &ContractId,
+    asset_amount: u64,
+    rgb_invoice: &str,
+) -> Result<Txid, String> {
+    let invoice_data = check_swap_rgb_invoice(rgb_invoice, asset_id, asset_amount)?;
+    let recipient_info =
+        RecipientInfo::new(invoice_data.recipient_id.clone()).map_err(|e| e.to_string())?;
+    let witness_data = match recipient_info.recipient_type {
+        RecipientType::Blind => None,
+        RecipientType::Witness => Some(WitnessData {
+            amount_sat: WITNESS_AMOUNT_SAT,
+            blinding: payment_blinding(
+                &app_state.static_state,
+                &unlocked_state.keys_manager,
+                &invoice_data.recipient_id,
+            ),
+        }),
+    };
+    let recipient_map = map! {
+        asset_id.to_string() => vec![Recipient {
+            recipient_id: invoice_data.recipient_id,
+            witness_data,
+            amount: asset_amount,
+            transport_endpoints: invoice_data.transport_endpoints,
+        }]
+    };
+    // as a donation the transfer is broadcast without waiting for the recipient to accept it
+    let send_result = unlocked_state
+        .rgb_send(
+            recipient_map,
+            true,
+            app_state.static_state.fee_rate,
+            MIN_FUNDING_CONFIRMATIONS as u8,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(Txid::from_str(&send_result.txid).expect("valid txid"))
+}
+
+fn htlc_spending_fee_rate(app_state: &AppState) -> u32 {
+    app_state
+        .static_state
+        .bitcoind_client
+        .get_est_sat_per_1000_weight(ConfirmationTarget::OutputSpendingFee)
+}
+
+/// Spend the HTLC output to our wallet at the given fee rate (sat/kw), claiming it with the given
+/// preimage or, without one, refunding it after the timeout.
+///
+/// The transaction signals RBF, so that a claim can be replaced with a higher fee
+async fn spend_htlc(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: &SubmarineSwapData,
+    preimage: Option<PaymentPreimage>,
+    fee_rate: u32,
+) -> Result<Txid, String> {
+    let script = swap.htlc_script(payment_hash).expect("known HTLC script");
+    let outpoint = swap.funding_outpoint().expect("funded HTLC");
+    let destination = Address::from_str(
+        &unlocked_state
+            .rgb_get_address()
+            .map_err(|e| e.to_string())?,
+    )
+    .expect("valid address")
+    .assume_checked()
+    .script_pubkey();
+    let lock_time = match preimage {
+        Some(_) => LockTime::ZERO,
+        None => LockTime::from_height(swap.timeout_height.unwrap()).expect("valid height"),
+    };
+    let mut tx = Transaction {
+        version: 2,
+        lock_time,
+        input: vec![TxIn {
+            previous_output: outpoint,
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: swap.amount_sat,
+            script_pubkey: destination,
+        }],
+    };
+    // an empty preimage selects the refund branch of the script
+    let preimage = preimage.map(|p| p.0.to_vec()).unwrap_or_default();
+
+    // the fee is computed on the weight with a signature of the max size
+    tx.input[0].witness = Witness::from_slice(&[vec![0; 73], preimage.clone(), script.to_bytes()]);
+    let fee_sat = tx.weight().to_wu() * fee_rate as u64 / 1000;
+    if swap.amount_sat < fee_sat + DUST_LIMIT_MSAT / 1000 {
+        return Err(s!("the HTLC output cannot pay the fee to spend it"));
+    }
+    tx.output[0].value = swap.amount_sat - fee_sat;
+
+    let sighash = SighashCache::new(&tx)
+        .segwit_signature_hash(0, &script, swap.amount_sat, EcdsaSighashType::All)
+        .map_err(|e| e.to_string())?;
+    let sig = Secp256k1::new().sign_ecdsa(
+        &Message::from_slice(&sighash[..]).expect("32 bytes"),
+        &swap.secret_key,
+    );
+    let mut sig = sig.serialize_der().to_vec();
+    sig.push(EcdsaSighashType::All as u8);
+    tx.input[0].witness = Witness::from_slice(&[sig, preimage, script.to_bytes()]);
+
+    app_state
+        .static_state
+        .bitcoind_client
+        .send_raw_transaction(&tx)
+        .await
+        .map_err(|e| format!("failed to broadcast the transaction: {e}"))
+}
+
+/// Claim the HTLC output with the preimage at the given fee rate, the swap then waiting for the
+/// claim to confirm
+async fn claim_htlc(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: &SubmarineSwapData,
+    preimage: PaymentPreimage,
+    fee_rate: u32,
+) -> Result<Txid, String> {
+    let txid = spend_htlc(
+        app_state,
+        unlocked_state,
+        payment_hash,
+        swap,
+        Some(preimage),
+        fee_rate,
+    )
+    .await?;
+    let height = unlocked_state.channel_manager.current_best_block().height;
+    unlocked_state.update_submarine_swap(payment_hash, |s| {
+        s.preimage = Some(preimage);
+        s.spending_txid = Some(txid);
+        s.claim_fee_rate = Some(fee_rate);
+        s.claim_height = Some(height);
+        s.status = SubmarineSwapStatus::Claiming;
+    });
+    Ok(txid)
+}
+
+/// Complete the swap once the claim of the HTLC output confirms, replacing the claim with a
+/// higher fee when it has been dropped from the mempool or doesn't confirm in time
+async fn check_htlc_claim(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    let claim_txid = swap.spending_txid.expect("broadcast claim");
+    let claim_output = app_state
+        .static_state
+        .bitcoind_client
+        .get_tx_out(&OutPoint {
+            txid: claim_txid,
+            vout: 0,
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    let height = unlocked_state.channel_manager.current_best_block().height;
+    match claim_output {
+        Some(tx_out) if tx_out.confirmations > 0 => {
+            tracing::info!("EVENT: claim {claim_txid} of submarine swap {payment_hash} confirmed");
+            unlocked_state.update_submarine_swap(payment_hash, |s| {
+                s.status = SubmarineSwapStatus::Succeeded;
+            });
+            return Ok(());
+        }
+        Some(_) if height < swap.claim_height.unwrap() + CLAIM_FEE_BUMP_BLOCKS => return Ok(()),
+        Some(_) => {}
+        None => {
+            // the output of a confirmed claim may have been spent already, while only we can
+            // spend the HTLC output before the timeout
+            if check_htlc_output(app_state, payment_hash, &swap)
+                .await?
+                .is_none()
+            {
+                if height < swap.timeout_height.unwrap() {
+                    tracing::info!("EVENT: claim of submarine swap {payment_hash} confirmed");
+                    unlocked_state.update_submarine_swap(payment_hash, |s| {
+                        s.status = SubmarineSwapStatus::Succeeded;
+                    });
+                } else {
+                    unlocked_state.fail_submarine_swap(
+                        payment_hash,
+                        s!("the HTLC output has been spent after the timeout"),
+                    );
+                }
+                return Ok(());
+            }
+        }
+    }
+    let previous_fee_rate = swap.claim_fee_rate.unwrap();
+    let fee_rate = std::cmp::max(
+        htlc_spending_fee_rate(app_state),
+        previous_fee_rate + std::cmp::max(previous_fee_rate / 4, MIN_CLAIM_FEE_BUMP),
+    );
+    let txid = claim_htlc(
+        app_state,
+        unlocked_state,
+        payment_hash,
+        &swap,
+        swap.preimage.expect("claim with the preimage"),
+        fee_rate,
+    )
+    .await?;
+    tracing::info!("EVENT: replaced the claim of submarine swap {payment_hash} with TX {txid}");
+    Ok(())
+}
+
+/// Check the HTLC output is unspent and matches the swap, returning whether it's confirmed
+async fn check_htlc_output(
+    app_state: &AppState,
+    payment_hash: &PaymentHash,
+    swap: &SubmarineSwapData,
+) -> Result<Option<bool>, String> {
+    let outpoint = swap.funding_outpoint().expect("funded HTLC");
+    let Some(tx_out) = app_state
+        .static_state
+        .bitcoind_client
+        .get_tx_out(&outpoint)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    let script_pubkey = swap
+        .htlc_script(payment_hash)
+        .expect("known HTLC script")
+        .to_v0_p2wsh();
+    if tx_out.script_pubkey != script_pubkey || tx_out.value_sat != swap.amount_sat {
+        return Err(s!("the HTLC output doesn't match the swap"));
+    }
+    Ok(Some(tx_out.confirmations >= MIN_FUNDING_CONFIRMATIONS))
+}
+
+/// Handle the submarine swap messages received from peers and regularly check the on-chain and
+/// Lightning sides of the open swaps
+pub(crate) async fn run_submarine_swaps(
+    app_state: Arc<AppState>,
+    mut receiver: mpsc::UnboundedReceiver<(PublicKey, PeerMessage)>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(SUBMARINE_SWAP_CHECK_INTERVAL_SECS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        let received = tokio::select! {
+            msg = receiver.recv() => match msg {
+                Some(msg) => Some(msg),
+                None => return,
+            },
+            _ = interval.tick() => None,
+        };
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let unlocked_state = match app_state.check_unlocked().await {
+            Ok(unlocked_state) => unlocked_state.clone().unwrap(),
+            Err(_) => continue,
+        };
+        match received {
+            Some((peer_pubkey, PeerMessage::SubmarineSwapRequest(msg))) => {
+                handle_submarine_swap_request(&app_state, &unlocked_state, msg, peer_pubkey)
+            }
+            Some((peer_pubkey, PeerMessage::SubmarineSwapAccepted(msg))) => {
+                handle_submarine_swap_accepted(&app_state, &unlocked_state, msg, peer_pubkey)
+            }
+            Some((peer_pubkey, PeerMessage::SubmarineSwapFunded(msg))) => {
+                handle_submarine_swap_funded(&unlocked_state, msg, peer_pubkey)
+            }
+            Some((_, PeerMessage::ChannelRequest(_)))
+            | Some((_, PeerMessage::SwapOffer(_)))
+            | Some((_, PeerMessage::SwapOfferAccept(_)))
+            | Some((_, PeerMessage::SwapOfferAccepted(_)))
+            | Some((_, PeerMessage::SwapPairs(_))) => {}
+            None => check_submarine_swaps(&app_state, &unlocked_state).await,
+        }
+        unlocked_state.peer_manager.process_events();
+    }
+}
+
+fn handle_submarine_swap_request(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: SubmarineSwapRequestMessage,
+    peer_pubkey: PublicKey,
+) {
+    let payment_hash = msg.payment_hash;
+    let answer = match accept_submarine_swap(app_state, unlocked_state, msg, peer_pubkey) {
+        Ok(answer) => {
+            tracing::info!("EVENT: accepted submarine swap {payment_hash} of peer {peer_pubkey}");
+            answer
+        }
+        Err(e) => {
+            tracing::warn!("Refused submarine swap {payment_hash} of peer {peer_pubkey}: {e}");
+            SubmarineSwapAcceptedMessage {
+                payment_hash,
+                pubkey: None,
+                timeout_height: None,
+                invoice: None,
+                error: Some(e),
+                rgb_invoice: None,
+            }
+        }
+    };
+    unlocked_state
+        .peer_message_handler
+        .send_message(peer_pubkey, PeerMessage::SubmarineSwapAccepted(answer));
+}
+
+/// Set up, as server, the swap requested by a client
+fn accept_submarine_swap(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: SubmarineSwapRequestMessage,
+    peer_pubkey: PublicKey,
+) -> Result<SubmarineSwapAcceptedMessage, String> {
+    if !app_state.static_state.submarine_swap_server {
+        return Err(s!("the node doesn't provide submarine swaps"));
+    }
+    let asset = match (msg.asset_id, msg.asset_amount) {
+        (Some(asset_id), Some(asset_amount)) => Some((asset_id, asset_amount)),
+        (None, None) => None,
+        _ => return Err(s!("incomplete asset info")),
+    };
+    match asset {
+        Some(_) if msg.amount_sat != ASSET_SUBMARINE_SWAP_AMOUNT_SAT => {
+            return Err(format!(
+                "the amount of asset swaps must be {ASSET_SUBMARINE_SWAP_AMOUNT_SAT}"
+            ));
+        }
+        None if msg.amount_sat < SUBMARINE_SWAP_MIN_SAT => {
+            return Err(format!(
+                "the amount must be equal or higher than {SUBMARINE_SWAP_MIN_SAT}"
+            ));
+        }
+        _ => {}
+    }
+    if unlocked_state.submarine_swap(&msg.payment_hash).is_some()
+        || unlocked_state.inbound_payment(&msg.payment_hash).is_some()
+    {
+        return Err(s!("the payment hash has already been used"));
+    }
+
+    let secret_key = SecretKey::from_slice(&unlocked_state.keys_manager.get_secure_random_bytes())
+        .expect("valid secret key");
+    let mut swap = SubmarineSwapData::new(msg.kind, true, peer_pubkey, msg.amount_sat, secret_key);
+    swap.counterparty_key = Some(msg.pubkey);
+    swap.asset_id = msg.asset_id;
+    swap.asset_amount = msg.asset_amount;
+    let mut answer = SubmarineSwapAcceptedMessage {
+        payment_hash: msg.payment_hash,
+        pubkey: Some(swap.pubkey()),
+        timeout_height: None,
+        invoice: None,
+        error: None,
+        rgb_invoice: None,
+    };
+    match msg.kind {
+        SubmarineSwapKind::LoopIn => {
+            let invoice = msg.invoice.ok_or(s!("missing invoice"))?;
+            check_swap_invoice(
+                &invoice,
+                &msg.payment_hash,
+                msg.amount_sat,
+                asset,
+                &peer_pubkey,
+            )?;
+            let outbound_limit_msat: u64 = unlocked_state
+                .channel_manager
+                .list_usable_channels()
+                .iter()
+                .map(|c| c.next_outbound_htlc_limit_msat)
+                .sum();
+            if outbound_limit_msat < msg.amount_sat * 1000 {
+                return Err(s!("the server doesn't have enough outbound liquidity"));
+            }
+            if let Some((asset_id, asset_amount)) = asset {
+                let max_asset_amount = get_max_local_rgb_amount(
+                    asset_id,
+                    &app_state.static_state.ldk_data_dir,
+                    unlocked_state.channel_manager.list_channels().iter(),
+                );
+                if max_asset_amount < asset_amount {
+                    return Err(s!("the server doesn't have enough assets in channels"));
+                }
+                if *unlocked_state.rgb_send_lock.lock().unwrap() {
+                    return Err(s!("the server is busy, try again later"));
+                }
+                // the client sends the assets to a blinded UTXO of ours, then we pay its invoice
+                let receive_data = unlocked_state
+                    .rgb_blind_receive(
+                        Some(asset_id.to_string()),
+                        Some(ASSET_SUBMARINE_SWAP_EXPIRY_SECS),
+                        app_state.static_state.proxy_endpoints.clone(),
+                        MIN_FUNDING_CONFIRMATIONS as u8,
+                    )
+                    .map_err(|e| e.to_string())?;
+                swap.rgb_invoice = Some(receive_data.invoice.clone());
+                answer.rgb_invoice = Some(receive_data.invoice);
+            } else {
+                let height = unlocked_state.channel_manager.current_best_block().height;
+                swap.timeout_height = Some(height + LOOP_IN_TIMEOUT_BLOCKS);
+                answer.timeout_height = swap.timeout_height;
+            }
+            swap.invoice = Some(invoice);
+        }
+        SubmarineSwapKind::LoopOut => {
+            if let Some((asset_id, asset_amount)) = asset {
+                // we're trusted to send the assets before settling the payment with the preimage
+                let preimage = msg.preimage.ok_or(s!("missing preimage"))?;
+                if Sha256::hash(&preimage.0).to_byte_array() != msg.payment_hash.0 {
+                    return Err(s!("the preimage doesn't match the payment hash"));
+                }
+                let rgb_invoice = msg.rgb_invoice.ok_or(s!("missing RGB invoice"))?;
+                check_swap_rgb_invoice(&rgb_invoice, &asset_id, asset_amount)?;
+                let asset_balance = unlocked_state
+                    .rgb_get_asset_balance(asset_id)
+                    .map_err(|e| e.to_string())?;
+                if asset_balance.spendable < asset_amount {
+                    return Err(s!("the server doesn't have enough assets on-chain"));
+                }
+                swap.preimage = Some(preimage);
+                swap.rgb_invoice = Some(rgb_invoice);
+            } else {
+                let btc_balance = unlocked_state
+                    .rgb_get_btc_balance()
+                    .map_err(|e| e.to_string())?;
+                if btc_balance.vanilla.spendable < msg.amount_sat {
+                    return Err(s!("the server doesn't have enough on-chain funds"));
+                }
+            }
+            let invoice = create_swap_invoice(
+                app_state,
+                unlocked_state,
+                msg.amount_sat,
+                asset,
+                Some(msg.payment_hash),
+            )?
+            .to_string();
+            swap.invoice = Some(invoice.clone());
+            answer.invoice = Some(invoice);
+        }
+    }
+    swap.status = SubmarineSwapStatus::Accepted;
+    unlocked_state
+        .add_submarine_swap(msg.payment_hash, swap)
+        .map_err(|e| e.to_string())?;
+    Ok(answer)
+}
+
+fn handle_submarine_swap_accepted(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: SubmarineSwapAcceptedMessage,
+    peer_pubkey: PublicKey,
+) {
+    let payment_hash = msg.payment_hash;
+    let swap = match unlocked_state.submarine_swap(&payment_hash) {
+        Some(swap)
+            if !swap.server
+                && swap.peer_pubkey == peer_pubkey
+                && swap.status == SubmarineSwapStatus::Requested =>
+        {
+            swap
+        }
+        _ => {
+            tracing::warn!("Ignoring answer from {peer_pubkey} to unknown submarine swap");
+            return;
+        }
+    };
+    if let Some(error) = msg.error {
+        unlocked_state.fail_submarine_swap(
+            &payment_hash,
+            format!("the swap has been refused by the server: {error}"),
+        );
+        return;
+    }
+    let res = match swap.kind {
+        SubmarineSwapKind::LoopIn if swap.asset().is_some() => {
+            fund_asset_loop_in(app_state, unlocked_state, msg, swap)
+        }
+        SubmarineSwapKind::LoopIn => fund_loop_in(app_state, unlocked_state, msg, swap),
+        SubmarineSwapKind::LoopOut => pay_loop_out(app_state, unlocked_state, msg, swap),
+    };
+    if let Err(e) = res {
+        unlocked_state.fail_submarine_swap(&payment_hash, e);
+    }
+}
+
+/// Fund, as loop in client, the HTLC output the server will claim once it has paid us
+fn fund_loop_in(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: SubmarineSwapAcceptedMessage,
+    mut swap: SubmarineSwapData,
+) -> Result<(), String> {
+    let payment_hash = msg.payment_hash;
+    let server_key = msg.pubkey.ok_or(s!("missing server key"))?;
+    let timeout_height = msg.timeout_height.ok_or(s!("missing timeout"))?;
+    // a later timeout would lock our funds for longer than agreed
+    let height = unlocked_state.channel_manager.current_best_block().height;
+    if timeout_height + SAFETY_MARGIN_BLOCKS < height + LOOP_IN_TIMEOUT_BLOCKS
+        || timeout_height > height + LOOP_IN_TIMEOUT_BLOCKS + SAFETY_MARGIN_BLOCKS
+    {
+        return Err(format!("invalid timeout height {timeout_height}"));
+    }
+    swap.counterparty_key = Some(server_key);
+    swap.timeout_height = Some(timeout_height);
+    let script = swap.htlc_script(&payment_hash).expect("known HTLC script");
+
+    let (txid, vout) = fund_htlc(app_state, unlocked_state, &script, swap.amount_sat)?;
+    tracing::info!("EVENT: funded HTLC output {txid}:{vout} of loop in {payment_hash}");
+    unlocked_state.update_submarine_swap(&payment_hash, |s| {
+        s.counterparty_key = Some(server_key);
+        s.timeout_height = Some(timeout_height);
+        s.funding_txid = Some(txid);
+        s.funding_vout = Some(vout);
+        s.status = SubmarineSwapStatus::Funded;
+    });
+    unlocked_state.peer_message_handler.send_message(
+        swap.peer_pubkey,
+        PeerMessage::SubmarineSwapFunded(SubmarineSwapFundedMessage {
+            payment_hash,
+            txid,
+            vout,
+            timeout_height,
+        }),
+    );
+    Ok(())
+}
+
+/// Send, as asset loop in client, the assets to the RGB invoice of the server, which will then pay
+/// us
+fn fund_asset_loop_in(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: SubmarineSwapAcceptedMessage,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    let payment_hash = msg.payment_hash;
+    let (asset_id, asset_amount) = swap.asset().expect("asset swap");
+    let rgb_invoice = msg.rgb_invoice.ok_or(s!("missing RGB invoice"))?;
+    if *unlocked_state.rgb_send_lock.lock().unwrap() {
+        return Err(s!("an RGB send is already in progress"));
+    }
+    let txid = send_swap_assets(
+        app_state,
+        unlocked_state,
+        &asset_id,
+        asset_amount,
+        &rgb_invoice,
+    )
+    .map_err(|e| format!("failed to send the assets: {e}"))?;
+    tracing::info!("EVENT: sent the assets of loop in {payment_hash} with TX {txid}");
+    unlocked_state.update_submarine_swap(&payment_hash, |s| {
+        s.rgb_invoice = Some(rgb_invoice);
+        s.funding_txid = Some(txid);
+        s.status = SubmarineSwapStatus::Funded;
+    });
+    Ok(())
+}
+
+/// Pay, as loop out client, the hold invoice of the server, which will fund the HTLC output
+fn pay_loop_out(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: SubmarineSwapAcceptedMessage,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    let payment_hash = msg.payment_hash;
+    let server_key = msg.pubkey.ok_or(s!("missing server key"))?;
+    let invoice_str = msg.invoice.ok_or(s!("missing invoice"))?;
+    let invoice = check_swap_invoice(
+        &invoice_str,
+        &payment_hash,
+        swap.amount_sat,
+        swap.asset(),
+        &swap.peer_pubkey,
+    )?;
+    unlocked_state.update_submarine_swap(&payment_hash, |s| {
+        s.counterparty_key = Some(server_key);
+        s.invoice = Some(invoice_str);
+        s.status = SubmarineSwapStatus::Paying;
+    });
+    pay_bolt11_invoice(
+        app_state,
+        unlocked_state,
+        &invoice,
+        None,
+        None,
+        app_state.static_state.payment_retry,
+        None,
+    )
+    .map_err(|e| format!("failed to pay the invoice: {e}"))?;
+    tracing::info!("EVENT: paying the invoice of loop out {payment_hash}");
+    Ok(())
+}
+
+fn handle_submarine_swap_funded(
+    unlocked_state: &UnlockedAppState,
+    msg: SubmarineSwapFundedMessage,
+    peer_pubkey: PublicKey,
+) {
+    let payment_hash = msg.payment_hash;
+    let swap = match unlocked_state.submarine_swap(&payment_hash) {
+        Some(swap) if swap.peer_pubkey == peer_pubkey && !swap.claims_onchain() => {
+            tracing::warn!("Ignoring funding of submarine swap {payment_hash} we fund");
+            return;
+        }
+        Some(swap) if swap.peer_pubkey == peer_pubkey => swap,
+        _ => {
+            tracing::warn!("Ignoring funding from {peer_pubkey} of unknown submarine swap");
+            return;
+        }
+    };
+    let expected_status = match swap.kind {
+        SubmarineSwapKind::LoopIn => SubmarineSwapStatus::Accepted,
+        SubmarineSwapKind::LoopOut => SubmarineSwapStatus::Paying,
+    };
+    if swap.status != expected_status {
+        tracing::warn!("Ignoring unexpected funding of submarine swap {payment_hash}");
+        return;
+    }
+    if swap.kind == SubmarineSwapKind::LoopIn && swap.timeout_height != Some(msg.timeout_height) {
+        unlocked_state.fail_submarine_swap(
+            &payment_hash,
+            s!("the HTLC output timeout doesn't match the swap"),
+        );
+        return;
+    }
+    tracing::info!(
+        "EVENT: HTLC output {}:{} of submarine swap {payment_hash} has been funded",
+        msg.txid,
+        msg.vout
+    );
+    unlocked_state.update_submarine_swap(&payment_hash, |s| {
+        s.timeout_height = Some(msg.timeout_height);
+        s.funding_txid = Some(msg.txid);
+        s.funding_vout = Some(msg.vout);
+        s.status = SubmarineSwapStatus::Funded;
+    });
+}
+
+/// Move forward the open swaps according to the on-chain and Lightning events
+async fn check_submarine_swaps(app_state: &AppState, unlocked_state: &UnlockedAppState) {
+    let now = get_current_timestamp();
+    for (payment_hash, swap) in unlocked_state.submarine_swaps() {
+        if swap.is_closed() {
+            continue;
+        }
+        if swap.status == SubmarineSwapStatus::Requested {
+            if swap.created_at + SUBMARINE_SWAP_REQUEST_TIMEOUT_SECS < now {
+                unlocked_state
+                    .fail_submarine_swap(&payment_hash, s!("the server didn't answer in time"));
+            }
+            continue;
+        }
+        let res = match (swap.kind, swap.server, swap.asset().is_some()) {
+            (SubmarineSwapKind::LoopIn, false, false) => {
+                check_loop_in_client(app_state, unlocked_state, &payment_hash, swap).await
+            }
+            (SubmarineSwapKind::LoopIn, true, false) => {
+                check_loop_in_server(app_state, unlocked_state, &payment_hash, swap).await
+            }
+            (SubmarineSwapKind::LoopOut, false, false) => {
+                check_loop_out_client(app_state, unlocked_state, &payment_hash, swap).await
+            }
+            (SubmarineSwapKind::LoopOut, true, false) => {
+                check_loop_out_server(app_state, unlocked_state, &payment_hash, swap).await
+            }
+            (SubmarineSwapKind::LoopIn, false, true) => {
+                check_asset_loop_in_client(unlocked_state, &payment_hash, swap)
+            }
+            (SubmarineSwapKind::LoopIn, true, true) => {
+                check_asset_loop_in_server(app_state, unlocked_state, &payment_hash, swap)
+            }
+            (SubmarineSwapKind::LoopOut, false, true) => {
+                check_asset_loop_out_client(unlocked_state, &payment_hash, swap)
+            }
+            (SubmarineSwapKind::LoopOut, true, true) => {
+                check_asset_loop_out_server(app_state, unlocked_state, &payment_hash, swap)
+            }
+        };
+        // errors are temporary, the swap is checked again at the next round
+        if let Err(e) = res {
+            tracing::error!("Failed to check submarine swap {payment_hash}: {e}");
+        }
+    }
+}
+
+/// Complete the loop in once the server has paid us or refund it after the timeout
+async fn check_loop_in_client(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    if swap.status != SubmarineSwapStatus::Funded {
+        return Ok(());
+    }
+    if unlocked_state
+        .inbound_payment(payment_hash)
+        .is_some_and(|p| p.status == HTLCStatus::Succeeded)
+    {
+        tracing::info!("EVENT: loop in {payment_hash} succeeded");
+        unlocked_state.update_submarine_swap(payment_hash, |s| {
+            s.status = SubmarineSwapStatus::Succeeded;
+        });
+        return Ok(());
+    }
+    let height = unlocked_state.channel_manager.current_best_block().height;
+    if height < swap.timeout_height.unwrap() {
+        return Ok(());
+    }
+    // a spent output has been claimed by the server, which then paid us
+    if check_htlc_output(app_state, payment_hash, &swap)
+        .await?
+        .is_none()
+    {
+        return Ok(());
+    }
+    let fee_rate = htlc_spending_fee_rate(app_state);
+    let txid = spend_htlc(
+        app_state,
+        unlocked_state,
+        payment_hash,
+        &swap,
+        None,
+        fee_rate,
+    )
+    .await?;
+    tracing::info!("EVENT: refunded loop in {payment_hash} with TX {txid}");
+    unlocked_state.update_submarine_swap(payment_hash, |s| {
+        s.spending_txid = Some(txid);
+        s.status = SubmarineSwapStatus::Refunded;
+        s.failure_reason = Some(s!("the server didn't pay before the timeout"));
+    });
+    Ok(())
+}
+
+/// Pay the client once its HTLC output is confirmed, then claim the output with the preimage
+async fn check_loop_in_server(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    let height = unlocked_state.channel_manager.current_best_block().height;
+    let timeout_height = swap.timeout_height.unwrap();
+    match swap.status {
+        SubmarineSwapStatus::Accepted => {
+            if height + SAFETY_MARGIN_BLOCKS >= timeout_height {
+                unlocked_state.fail_submarine_swap(
+                    payment_hash,
+                    s!("the HTLC output has not been funded in time"),
+                );
+            }
+        }
+        SubmarineSwapStatus::Funded => {
+            // the payment must be resolved before the client can refund the output
+            if height + 2 * SAFETY_MARGIN_BLOCKS >= timeout_height {
+                unlocked_state.fail_submarine_swap(
+                    payment_hash,
+                    s!("the HTLC output has not been confirmed in time"),
+                );
+                return Ok(());
+            }
+            match check_htlc_output(app_state, payment_hash, &swap).await {
+                Ok(Some(true)) => {}
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    unlocked_state.fail_submarine_swap(payment_hash, e);
+                    return Ok(());
+                }
+            }
+            let invoice =
+                Bolt11Invoice::from_str(swap.invoice.as_ref().unwrap()).expect("checked invoice");
+            unlocked_state.update_submarine_swap(payment_hash, |s| {
+                s.status = SubmarineSwapStatus::Paying;
+            });
+            if let Err(e) = pay_bolt11_invoice(
+                app_state,
+                unlocked_state,
+                &invoice,
+                None,
+                None,
+                app_state.static_state.payment_retry,
+                Some(timeout_height - height - SAFETY_MARGIN_BLOCKS),
+            ) {
+                unlocked_state
+                    .fail_submarine_swap(payment_hash, format!("failed to pay the invoice: {e}"));
+                return Ok(());
+            }
+            tracing::info!("EVENT: paying the invoice of loop in {payment_hash}");
+        }
+        SubmarineSwapStatus::Paying => {
+            let payment = unlocked_state
+                .outbound_payments()
+                .get(&PaymentId(payment_hash.0))
+                .cloned();
+            match payment {
+                Some(payment) if payment.status == HTLCStatus::Succeeded => {
+                    let preimage = payment.preimage.expect("preimage of a sent payment");
+                    let txid = claim_htlc(
+                        app_state,
+                        unlocked_state,
+                        payment_hash,
+                        &swap,
+                        preimage,
+                        htlc_spending_fee_rate(app_state),
+                    )
+                    .await?;
+                    tracing::info!("EVENT: claiming loop in {payment_hash} with TX {txid}");
+                }
+                Some(payment) if payment.status == HTLCStatus::Failed => {
+                    unlocked_state
+                        .fail_submarine_swap(payment_hash, s!("the payment to the client failed"));
+                }
+                _ => {}
+            }
+        }
+        SubmarineSwapStatus::Claiming => {
+            check_htlc_claim(app_state, unlocked_state, payment_hash, swap).await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Claim, with the preimage, the HTLC output funded by the server once we've paid its invoice
+async fn check_loop_out_client(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    // once claimed the output is ours, whatever happens to the payment
+    if swap.status == SubmarineSwapStatus::Claiming {
+        return check_htlc_claim(app_state, unlocked_state, payment_hash, swap).await;
+    }
+    let payment_failed = unlocked_state
+        .outbound_payments()
+        .get(&PaymentId(payment_hash.0))
+        .is_some_and(|p| p.status == HTLCStatus::Failed);
+    if payment_failed {
+        unlocked_state.fail_submarine_swap(payment_hash, s!("the payment to the server failed"));
+        return Ok(());
+    }
+    if swap.status != SubmarineSwapStatus::Funded {
+        return Ok(());
+    }
+    let height = unlocked_state.channel_manager.current_best_block().height;
+    if height + SAFETY_MARGIN_BLOCKS >= swap.timeout_height.unwrap() {
+        unlocked_state.fail_submarine_swap(
+            payment_hash,
+            s!("not enough time left to claim the HTLC output"),
+        );
+        return Ok(());
+    }
+    match check_htlc_output(app_state, payment_hash, &swap).await {
+        Ok(Some(true)) => {}
+        Ok(_) => return Ok(()),
+        Err(e) => {
+            unlocked_state.fail_submarine_swap(payment_hash, e);
+            return Ok(());
+        }
+    }
+    let txid = claim_htlc(
+        app_state,
+        unlocked_state,
+        payment_hash,
+        &swap,
+        swap.preimage.expect("preimage of a loop out"),
+        htlc_spending_fee_rate(app_state),
+    )
+    .await?;
+    tracing::info!("EVENT: claiming loop out {payment_hash} with TX {txid}");
+    Ok(())
+}
+
+/// Fund the HTLC output once the client payment is held, then settle the payment with the
+/// preimage revealed by the client claim or refund the output after the timeout
+async fn check_loop_out_server(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    let height = unlocked_state.channel_manager.current_best_block().height;
+    match swap.status {
+        SubmarineSwapStatus::Accepted => {
+            let Some(payment) = unlocked_state.inbound_payment(payment_hash) else {
+                return Ok(());
+            };
+            match payment.status {
+                HTLCStatus::Claimable => {}
+                HTLCStatus::Failed | HTLCStatus::Expired => {
+                    unlocked_state
+                        .fail_submarine_swap(payment_hash, s!("the invoice has not been paid"));
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            }
+            let timeout_height = height + LOOP_OUT_TIMEOUT_BLOCKS;
+            // the held payment must outlast the output, to be settled once the client claims it
+            if payment
+                .claim_deadline
+                .is_some_and(|deadline| deadline < timeout_height + SAFETY_MARGIN_BLOCKS)
+            {
+                unlocked_state.refund_submarine_swap_payment(payment_hash);
+                unlocked_state.fail_submarine_swap(
+                    payment_hash,
+                    s!("the payment expires too soon to fund the HTLC output"),
+                );
+                return Ok(());
+            }
+            let mut swap = swap;
+            swap.timeout_height = Some(timeout_height);
+            let script = swap.htlc_script(payment_hash).expect("known HTLC script");
+            let (txid, vout) = match fund_htlc(app_state, unlocked_state, &script, swap.amount_sat)
+            {
+                Ok(funding) => funding,
+                Err(e) => {
+                    unlocked_state.refund_submarine_swap_payment(payment_hash);
+                    unlocked_state.fail_submarine_swap(
+                        payment_hash,
+                        format!("failed to fund the HTLC output: {e}"),
+                    );
+                    return Ok(());
+                }
+            };
+            tracing::info!("EVENT: funded HTLC output {txid}:{vout} of loop out {payment_hash}");
+            unlocked_state.update_submarine_swap(payment_hash, |s| {
+                s.timeout_height = Some(timeout_height);
+                s.funding_txid = Some(txid);
+                s.funding_vout = Some(vout);
+                s.scanned_height = Some(height);
+                s.status = SubmarineSwapStatus::Funded;
+            });
+            unlocked_state.peer_message_handler.send_message(
+                swap.peer_pubkey,
+                PeerMessage::SubmarineSwapFunded(SubmarineSwapFundedMessage {
+                    payment_hash: *payment_hash,
+                    txid,
+                    vout,
+                    timeout_height,
+                }),
+            );
+        }
+        SubmarineSwapStatus::Funded => {
+            if let Some((preimage, txid)) =
+                scan_htlc_claim(app_state, unlocked_state, payment_hash, &swap, height).await?
+            {
+                tracing::info!("EVENT: loop out {payment_hash} claimed on-chain with TX {txid}");
+                unlocked_state.channel_manager.claim_funds(preimage);
+                unlocked_state.update_submarine_swap(payment_hash, |s| {
+                    s.preimage = Some(preimage);
+                    s.spending_txid = Some(txid);
+                    s.status = SubmarineSwapStatus::Succeeded;
+                });
+                return Ok(());
+            }
+            if height < swap.timeout_height.unwrap()
+                || check_htlc_output(app_state, payment_hash, &swap)
+                    .await?
+                    .is_none()
+            {
+                return Ok(());
+            }
+            let fee_rate = htlc_spending_fee_rate(app_state);
+            let txid = spend_htlc(
+                app_state,
+                unlocked_state,
+                payment_hash,
+                &swap,
+                None,
+                fee_rate,
+            )
+            .await?;
+            tracing::info!("EVENT: refunded loop out {payment_hash} with TX {txid}");
+            unlocked_state.refund_submarine_swap_payment(payment_hash);
+            unlocked_state.update_submarine_swap(payment_hash, |s| {
+                s.spending_txid = Some(txid);
+                s.status = SubmarineSwapStatus::Refunded;
+                s.failure_reason = Some(s!("the client didn't claim before the timeout"));
+            });
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Complete the asset loop in once the server has paid us
+fn check_asset_loop_in_client(
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    if swap.status != SubmarineSwapStatus::Funded {
+        return Ok(());
+    }
+    match unlocked_state
+        .inbound_payment(payment_hash)
+        .map(|p| p.status)
+    {
+        Some(HTLCStatus::Succeeded) => {
+            tracing::info!("EVENT: asset loop in {payment_hash} succeeded");
+            unlocked_state.update_submarine_swap(payment_hash, |s| {
+                s.status = SubmarineSwapStatus::Succeeded;
+            });
+        }
+        Some(HTLCStatus::Failed) | Some(HTLCStatus::Expired) => {
+            unlocked_state.fail_submarine_swap(
+                payment_hash,
+                s!("the server didn't pay before the invoice expiry"),
+            );
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Pay the client once its assets have been received on-chain
+fn check_asset_loop_in_server(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    match swap.status {
+        SubmarineSwapStatus::Accepted => {
+            let (asset_id, asset_amount) = swap.asset().expect("asset swap");
+            let recipient_id = RgbLibInvoice::new(swap.rgb_invoice.clone().unwrap())
+                .expect("valid RGB invoice")
+                .invoice_data()
+                .recipient_id;
+            // transfers are refreshed in the background
+            let transfer = unlocked_state
+                .rgb_list_transfers(asset_id.to_string())
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .find(|t| t.recipient_id.as_ref() == Some(&recipient_id));
+            let Some(transfer) = transfer else {
+                return Ok(());
+            };
+            match transfer.status {
+                rgb_lib::TransferStatus::Settled => {}
+                rgb_lib::TransferStatus::Failed => {
+                    unlocked_state
+                        .fail_submarine_swap(payment_hash, s!("the assets have not been received"));
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            }
+            if transfer.amount < asset_amount {
+                unlocked_state.fail_submarine_swap(
+                    payment_hash,
+                    format!(
+                        "received {} of the asset instead of {asset_amount}",
+                        transfer.amount
+                    ),
+                );
+                return Ok(());
+            }
+            let invoice =
+                Bolt11Invoice::from_str(swap.invoice.as_ref().unwrap()).expect("checked invoice");
+            unlocked_state.update_submarine_swap(payment_hash, |s| {
+                s.funding_txid = transfer.txid.and_then(|t| Txid::from_str(&t).ok());
+                s.status = SubmarineSwapStatus::Paying;
+            });
+            if let Err(e) = pay_bolt11_invoice(
+                app_state,
+                unlocked_state,
+                &invoice,
+                None,
+                None,
+                app_state.static_state.payment_retry,
+                None,
+            ) {
+                unlocked_state
+                    .fail_submarine_swap(payment_hash, format!("failed to pay the invoice: {e}"));
+                return Ok(());
+            }
+            tracing::info!("EVENT: paying the invoice of asset loop in {payment_hash}");
+        }
+        SubmarineSwapStatus::Paying => {
+            let payment = unlocked_state
+                .outbound_payments()
+                .get(&PaymentId(payment_hash.0))
+                .cloned();
+            match payment {
+                Some(payment) if payment.status == HTLCStatus::Succeeded => {
+                    tracing::info!("EVENT: asset loop in {payment_hash} succeeded");
+                    unlocked_state.update_submarine_swap(payment_hash, |s| {
+                        s.preimage = payment.preimage;
+                        s.status = SubmarineSwapStatus::Succeeded;
+                    });
+                }
+                Some(payment) if payment.status == HTLCStatus::Failed => {
+                    unlocked_state
+                        .fail_submarine_swap(payment_hash, s!("the payment to the client failed"));
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Complete the asset loop out once the server has settled our payment
+fn check_asset_loop_out_client(
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    if swap.status != SubmarineSwapStatus::Paying {
+        return Ok(());
+    }
+    let payment_status = unlocked_state
+        .outbound_payments()
+        .get(&PaymentId(payment_hash.0))
+        .map(|p| p.status);
+    match payment_status {
+        Some(HTLCStatus::Succeeded) => {
+            tracing::info!("EVENT: asset loop out {payment_hash} succeeded");
+            unlocked_state.update_submarine_swap(payment_hash, |s| {
+                s.status = SubmarineSwapStatus::Succeeded;
+            });
+        }
+        Some(HTLCStatus::Failed) => {
+            unlocked_state
+                .fail_submarine_swap(payment_hash, s!("the payment to the server failed"));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Send the assets to the RGB invoice of the client once its payment is held, then settle the
+/// payment
+fn check_asset_loop_out_server(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: SubmarineSwapData,
+) -> Result<(), String> {
+    if swap.status != SubmarineSwapStatus::Accepted {
+        return Ok(());
+    }
+    let Some(payment) = unlocked_state.inbound_payment(payment_hash) else {
+        return Ok(());
+    };
+    match payment.status {
+        HTLCStatus::Claimable => {}
+        HTLCStatus::Failed | HTLCStatus::Expired => {
+            unlocked_state.fail_submarine_swap(payment_hash, s!("the invoice has not been paid"));
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+    // the send is retried at the next round
+    if *unlocked_state.rgb_send_lock.lock().unwrap() {
+        return Ok(());
+    }
+    let (asset_id, asset_amount) = swap.asset().expect("asset swap");
+    let txid = match send_swap_assets(
+        app_state,
+        unlocked_state,
+        &asset_id,
+        asset_amount,
+        swap.rgb_invoice.as_ref().unwrap(),
+    ) {
+        Ok(txid) => txid,
+        Err(e) => {
+            unlocked_state.refund_submarine_swap_payment(payment_hash);
+            unlocked_state
+                .fail_submarine_swap(payment_hash, format!("failed to send the assets: {e}"));
+            return Ok(());
+        }
+    };
+    tracing::info!("EVENT: sent the assets of loop out {payment_hash} with TX {txid}");
+    unlocked_state
+        .channel_manager
+        .claim_funds(swap.preimage.expect("preimage of an asset loop out"));
+    unlocked_state.update_submarine_swap(payment_hash, |s| {
+        s.funding_txid = Some(txid);
+        s.status = SubmarineSwapStatus::Succeeded;
+    });
+    Ok(())
+}
+
+/// Look, in the blocks not scanned yet, for the transaction claiming the HTLC output, returning
+/// the preimage it reveals
+async fn scan_htlc_claim(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    payment_hash: &PaymentHash,
+    swap: &SubmarineSwapData,
+    height: u32,
+) -> Result<Option<(PaymentPreimage, Txid)>, String> {
+    let outpoint = swap.funding_outpoint().expect("funded HTLC");
+    let start_height = swap.scanned_height.unwrap() + 1;
+    let mut claim = None;
+    for block_height in start_height..=height {
+        let block = app_state
+            .static_state
+            .bitcoind_client
+            .get_block_at_height(block_height)
+            .await
+            .map_err(|e| e.to_string())?;
+        claim = block.txdata.iter().find_map(|tx| {
+            let input = tx.input.iter().find(|i| i.previous_output == outpoint)?;
+            let preimage: [u8; 32] = input.witness.nth(1)?.try_into().ok()?;
+            (Sha256::hash(&preimage).to_byte_array() == payment_hash.0)
+                .then_some((PaymentPreimage(preimage), tx.txid()))
+        });
+        if claim.is_some() {
+            break;
+        }
+    }
+    if start_height <= height {
+        unlocked_state.update_submarine_swap(payment_hash, |s| s.scanned_height = Some(height));
+    }
+    Ok(claim)
+}
@@ -1,14 +1,29 @@
+use lightning::ln::channelmanager::ChannelDetails;
+use lightning::ln::wire::Type;
 use lightning::{impl_writeable_tlv_based, ln::PaymentHash};
 use rgb_lib::ContractId;
 use std::convert::TryInto;
 use std::fmt;
+use std::path::Path;
 use std::str::FromStr;
 
 use crate::{
+    error::APIError,
+    peer_messages::{supports_feature_bit, SWAP_PROTOCOL_FEATURE_BIT},
+    rgb::get_rgb_channel_info_optional,
     routes::SwapStatus,
     utils::{get_current_timestamp, hex_str_to_vec},
 };
 
+/// Time after which a swap still pending is considered failed
+const SWAP_PENDING_TIMEOUT_SECS: u64 = 86400;
+
+/// Type of the custom message advertising the pairs a node can swap
+pub(crate) const SWAP_PAIRS_MESSAGE_TYPE: u16 = 32815;
+
+/// Max number of pairs advertised to or accepted from a peer
+pub(crate) const MAX_SWAP_PAIRS: usize = 256;
+
 #[derive(Debug, Clone)]
 pub(crate) struct SwapData {
     pub(crate) swap_info: SwapInfo,
@@ -16,6 +31,13 @@ pub(crate) struct SwapData {
     pub(crate) requested_at: u64,
     pub(crate) initiated_at: Option<u64>,
     pub(crate) completed_at: Option<u64>,
+    /// Quantities actually swapped, set once the swap is initiated
+    pub(crate) filled_qty_from: Option<u64>,
+    pub(crate) filled_qty_to: Option<u64>,
+    /// Why the swap failed, when it was rejected or its payment didn't go through
+    pub(crate) failure_reason: Option<String>,
+    /// Statuses the swap went through, starting from Waiting
+    pub(crate) transitions: Vec<SwapTransitionData>,
 }
 
 impl_writeable_tlv_based!(SwapData, {
@@ -24,20 +46,130 @@ impl_writeable_tlv_based!(SwapData, {
     (2, requested_at, required),
     (3, initiated_at, option),
     (4, completed_at, option),
+    (5, filled_qty_from, option),
+    (7, filled_qty_to, option),
+    (9, failure_reason, option),
+    (11, transitions, optional_vec),
 });
 
 impl SwapData {
     pub(crate) fn create_from_swap_info(swap_info: &SwapInfo) -> Self {
+        let requested_at = get_current_timestamp();
         Self {
             swap_info: swap_info.clone(),
             status: SwapStatus::Waiting,
-            requested_at: get_current_timestamp(),
+            requested_at,
             initiated_at: None,
             completed_at: None,
+            filled_qty_from: None,
+            filled_qty_to: None,
+            failure_reason: None,
+            transitions: vec![SwapTransitionData {
+                status: SwapStatus::Waiting,
+                timestamp: requested_at,
+                reason: None,
+            }],
+        }
+    }
+
+    /// Move the swap to the given status, recording when and, for failures, why
+    pub(crate) fn transition(
+        &mut self,
+        status: SwapStatus,
+        reason: Option<String>,
+    ) -> Result<(), SwapTransitionError> {
+        if !self.status.can_transition_to(&status) {
+            return Err(SwapTransitionError::InvalidTransition(
+                self.status.clone(),
+                status,
+            ));
+        }
+        let now = get_current_timestamp();
+        match &status {
+            SwapStatus::Pending => self.initiated_at = Some(now),
+            _ => self.completed_at = Some(now),
+        }
+        if reason.is_some() {
+            self.failure_reason = reason.clone();
+        }
+        self.transitions.push(SwapTransitionData {
+            status: status.clone(),
+            timestamp: now,
+            reason,
+        });
+        self.status = status;
+        Ok(())
+    }
+
+    /// Status the swap moves to once it has timed out, if it has
+    pub(crate) fn timed_out_status(&self, now: u64) -> Option<SwapStatus> {
+        match self.status {
+            SwapStatus::Waiting if now > self.swap_info.expiry => Some(SwapStatus::Expired),
+            SwapStatus::Pending
+                if now
+                    > self.initiated_at.unwrap_or(self.requested_at)
+                        + SWAP_PENDING_TIMEOUT_SECS =>
+            {
+                Some(SwapStatus::Failed)
+            }
+            _ => None,
         }
     }
 }
 
+/// A status change of a swap
+#[derive(Debug, Clone)]
+pub(crate) struct SwapTransitionData {
+    pub(crate) status: SwapStatus,
+    pub(crate) timestamp: u64,
+    pub(crate) reason: Option<String>,
+}
+
+impl_writeable_tlv_based!(SwapTransitionData, {
+    (0, status, required),
+    (2, timestamp, required),
+    (4, reason, option),
+});
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SwapTransitionError {
+    #[error(transparent)]
+    FailedPersisting(#[from] APIError),
+
+    #[error("cannot move the swap from {0:?} to {1:?}")]
+    InvalidTransition(SwapStatus, SwapStatus),
+
+    #[error("unknown swap")]
+    UnknownSwap,
+}
+
+impl From<SwapTransitionError> for APIError {
+    fn from(error: SwapTransitionError) -> Self {
+        match error {
+            SwapTransitionError::FailedPersisting(e) => e,
+            SwapTransitionError::InvalidTransition(..) => APIError::InvalidSwap(error.to_string()),
+            SwapTransitionError::UnknownSwap => APIError::UnknownSwap,
+        }
+    }
+}
+
+impl SwapStatus {
+    /// Swaps start Waiting, become Pending once their HTLCs are sent (or forwarded) and end
+    /// Succeeded, Failed or Expired, never leaving these
+    pub(crate) fn can_transition_to(&self, status: &SwapStatus) -> bool {
+        matches!(
+            (self, status),
+            (
+                SwapStatus::Waiting,
+                SwapStatus::Pending | SwapStatus::Failed | SwapStatus::Expired
+            ) | (
+                SwapStatus::Pending,
+                SwapStatus::Succeeded | SwapStatus::Failed | SwapStatus::Expired
+            )
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct SwapInfo {
     pub(crate) qty_from: u64,
@@ -45,6 +177,8 @@ pub(crate) struct SwapInfo {
     pub(crate) from_asset: Option<ContractId>,
     pub(crate) to_asset: Option<ContractId>,
     pub(crate) expiry: u64,
+    /// Whether the swap can be executed for a fraction of the quantities, at the same price
+    pub(crate) partial_fill: bool,
 }
 
 impl_writeable_tlv_based!(SwapInfo, {
@@ -53,6 +187,7 @@ impl_writeable_tlv_based!(SwapInfo, {
     (2, from_asset, required),
     (3, to_asset, required),
     (4, expiry, required),
+    (5, partial_fill, (default_value, false)),
 });
 
 impl From<SwapData> for SwapInfo {
@@ -63,6 +198,7 @@ impl From<SwapData> for SwapInfo {
             from_asset: value.swap_info.from_asset,
             to_asset: value.swap_info.to_asset,
             expiry: value.swap_info.expiry,
+            partial_fill: value.swap_info.partial_fill,
         }
     }
 }
@@ -91,6 +227,23 @@ impl SwapInfo {
     pub(crate) fn is_asset_asset(&self) -> bool {
         self.is_from_asset() && self.is_to_asset()
     }
+
+    /// Quantity to receive for the given fraction of qty_from, at the swap price rounded down
+    pub(crate) fn fill_qty_to(&self, fill_qty_from: u64) -> u64 {
+        (fill_qty_from as u128 * self.qty_to as u128 / self.qty_from as u128) as u64
+    }
+
+    /// Check the quantities actually swapped against the swap terms: the full quantities, or a
+    /// fraction of them at the swap price for partial-fill swaps
+    pub(crate) fn check_fill(&self, fill_qty_from: u64, fill_qty_to: u64) -> bool {
+        if !self.partial_fill {
+            return fill_qty_from == self.qty_from && fill_qty_to == self.qty_to;
+        }
+        fill_qty_from > 0
+            && fill_qty_from <= self.qty_from
+            && fill_qty_to > 0
+            && fill_qty_to >= self.fill_qty_to(fill_qty_from)
+    }
 }
 
 #[derive(Debug)]
@@ -112,7 +265,7 @@ impl fmt::Display for SwapString {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "{}/{}/{}/{}/{}/{}",
+            "{}/{}/{}/{}/{}/{}{}",
             self.swap_info.qty_from,
             self.swap_info
                 .from_asset
@@ -125,6 +278,11 @@ impl fmt::Display for SwapString {
                 .unwrap_or("btc".into()),
             self.swap_info.expiry,
             self.payment_hash,
+            if self.swap_info.partial_fill {
+                "/partial"
+            } else {
+                ""
+            },
         )
     }
 }
@@ -140,6 +298,11 @@ impl FromStr for SwapString {
         let to_asset = iter.next();
         let expiry = iter.next();
         let payment_hash = iter.next();
+        let partial_fill = match iter.next() {
+            None => false,
+            Some("partial") => true,
+            Some(_) => return Err("Unable to parse"),
+        };
 
         if payment_hash.is_none() || iter.next().is_some() {
             return Err("Wrong number of parts");
@@ -189,6 +352,7 @@ impl FromStr for SwapString {
             from_asset,
             to_asset,
             expiry,
+            partial_fill,
         };
 
         if swap_info.same_asset() {
@@ -201,3 +365,70 @@ impl FromStr for SwapString {
         })
     }
 }
+
+/// Whether the given little-endian feature flags signal support for the swap protocol, either as
+/// optional or required.
+pub(crate) fn supports_swap_protocol(le_flags: &[u8]) -> bool {
+    supports_feature_bit(le_flags, SWAP_PROTOCOL_FEATURE_BIT)
+}
+
+/// Pair of assets a node can swap, with the same convention as swaps (BTC when the asset is None)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SwapPair {
+    pub(crate) from_asset: Option<ContractId>,
+    pub(crate) to_asset: Option<ContractId>,
+}
+
+impl_writeable_tlv_based!(SwapPair, {
+    (0, from_asset, option),
+    (2, to_asset, option),
+});
+
+/// Sent to peers supporting the swap protocol when they connect and whenever our pairs change
+#[derive(Clone, Debug)]
+pub(crate) struct SwapPairsMessage {
+    pub(crate) pairs: Vec<SwapPair>,
+}
+
+impl_writeable_tlv_based!(SwapPairsMessage, {
+    (0, pairs, required_vec),
+});
+
+impl Type for SwapPairsMessage {
+    fn type_id(&self) -> u16 {
+        SWAP_PAIRS_MESSAGE_TYPE
+    }
+}
+
+/// Pairs we can swap, i.e. any two of BTC and the assets of our usable channels, as swaps can only
+/// go through channels
+pub(crate) fn supported_swap_pairs<'r>(
+    ldk_data_dir_path: &Path,
+    channels: impl Iterator<Item = &'r ChannelDetails>,
+) -> Vec<SwapPair> {
+    let mut assets: Vec<Option<ContractId>> = vec![None];
+    for chan_info in channels.filter(|c| c.is_usable) {
+        if let Some((rgb_info, _)) =
+            get_rgb_channel_info_optional(&chan_info.channel_id, ldk_data_dir_path, false)
+        {
+            if !assets.contains(&Some(rgb_info.contract_id)) {
+                assets.push(Some(rgb_info.contract_id));
+            }
+        }
+    }
+    // keep the pairs in a stable order, so they're only advertised again when they change
+    assets.sort_by_key(|a| a.map(|a| a.to_string()));
+
+    let mut pairs = vec![];
+    for from_asset in &assets {
+        for to_asset in &assets {
+            if from_asset != to_asset && pairs.len() < MAX_SWAP_PAIRS {
+                pairs.push(SwapPair {
+                    from_asset: *from_asset,
+                    to_asset: *to_asset,
+                });
+            }
+        }
+    }
+    pairs
+}
@@ -0,0 +1,537 @@
+use amplify::s;
+use bitcoin::secp256k1::PublicKey;
+use hex::DisplayHex;
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::wire::Type;
+use rgb_lib::ContractId;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::disk::SWAP_OFFERS_FNAME;
+use crate::error::APIError;
+use crate::events::NodeEvent;
+use crate::peer_messages::{supports_feature_bit, PeerMessage, SWAP_OFFER_FEATURE_BIT};
+use crate::routes::DUST_LIMIT_MSAT;
+use crate::swap::{
+    supported_swap_pairs, supports_swap_protocol, SwapData, SwapInfo, SwapPairsMessage, SwapString,
+};
+use crate::utils::{get_current_timestamp, get_max_local_rgb_amount, AppState, UnlockedAppState};
+
+/// Type of the custom message carrying a swap offer
+pub(crate) const SWAP_OFFER_MESSAGE_TYPE: u16 = 32803;
+
+/// Type of the custom message accepting a swap offer
+pub(crate) const SWAP_OFFER_ACCEPT_MESSAGE_TYPE: u16 = 32805;
+
+/// Type of the custom message answering the acceptance of a swap offer
+pub(crate) const SWAP_OFFER_ACCEPTED_MESSAGE_TYPE: u16 = 32807;
+
+/// Interval at which our offers are sent again to the connected peers
+const SWAP_OFFER_ANNOUNCE_INTERVAL_SECS: u64 = 60;
+
+/// Time the maker has to execute the swap of an accepted offer
+pub(crate) const SWAP_OFFER_SWAP_TIMEOUT_SECS: u32 = 600;
+
+/// Max number of offers kept from peers, further offers are dropped until some expire
+const MAX_RECEIVED_SWAP_OFFERS: usize = 1000;
+
+/// Offer of a maker to swap any quantity of an asset within the given limits, at a fixed price.
+///
+/// As for swaps, "from" is what the taker sends and the maker receives, while "to" is what the
+/// taker receives and the maker sends.
+#[derive(Clone, Debug)]
+pub(crate) struct SwapOfferMessage {
+    pub(crate) offer_id: String,
+    pub(crate) from_asset: Option<ContractId>,
+    pub(crate) to_asset: Option<ContractId>,
+    /// The maker sends `price_to` for every `price_from` it receives
+    pub(crate) price_from: u64,
+    pub(crate) price_to: u64,
+    pub(crate) min_qty_from: u64,
+    pub(crate) max_qty_from: u64,
+    pub(crate) expiry: u64,
+    /// Quantity still available to takers, if the offer has a total quantity
+    pub(crate) remaining_qty_from: Option<u64>,
+}
+
+impl_writeable_tlv_based!(SwapOfferMessage, {
+    (0, offer_id, required),
+    (2, from_asset, option),
+    (4, to_asset, option),
+    (6, price_from, required),
+    (8, price_to, required),
+    (10, min_qty_from, required),
+    (12, max_qty_from, required),
+    (14, expiry, required),
+    (15, remaining_qty_from, option),
+});
+
+impl Type for SwapOfferMessage {
+    fn type_id(&self) -> u16 {
+        SWAP_OFFER_MESSAGE_TYPE
+    }
+}
+
+impl SwapOfferMessage {
+    /// Quantity the maker sends for the given one, rounded down
+    pub(crate) fn qty_to(&self, qty_from: u64) -> u64 {
+        (qty_from as u128 * self.price_to as u128 / self.price_from as u128) as u64
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.expiry <= get_current_timestamp()
+    }
+
+    /// Check the quantity a taker wants to swap against the offer terms
+    pub(crate) fn check_qty_from(&self, qty_from: u64) -> Result<u64, String> {
+        if self.is_expired() {
+            return Err(s!("the offer has expired"));
+        }
+        // a taker can fill any fraction of what remains, the rest staying open for other takers
+        let max_qty_from = self
+            .remaining_qty_from
+            .map_or(self.max_qty_from, |r| r.min(self.max_qty_from));
+        if max_qty_from < self.min_qty_from {
+            return Err(s!("the offer has been filled"));
+        }
+        if qty_from < self.min_qty_from || qty_from > max_qty_from {
+            return Err(format!(
+                "quantity must be between {} and {max_qty_from}",
+                self.min_qty_from
+            ));
+        }
+        let qty_to = self.qty_to(qty_from);
+        if qty_to == 0 {
+            return Err(s!("quantity is too low for the offer price"));
+        }
+        Ok(qty_to)
+    }
+}
+
+/// Sent by a taker to the maker of an offer to swap the given quantity
+#[derive(Clone, Debug)]
+pub(crate) struct SwapOfferAcceptMessage {
+    pub(crate) offer_id: String,
+    pub(crate) qty_from: u64,
+}
+
+impl_writeable_tlv_based!(SwapOfferAcceptMessage, {
+    (0, offer_id, required),
+    (2, qty_from, required),
+});
+
+impl Type for SwapOfferAcceptMessage {
+    fn type_id(&self) -> u16 {
+        SWAP_OFFER_ACCEPT_MESSAGE_TYPE
+    }
+}
+
+/// Sent by the maker to a taker accepting its offer, with the swapstring of the swap or the reason
+/// it has been refused
+#[derive(Clone, Debug)]
+pub(crate) struct SwapOfferAcceptedMessage {
+    pub(crate) offer_id: String,
+    pub(crate) swapstring: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+impl_writeable_tlv_based!(SwapOfferAcceptedMessage, {
+    (0, offer_id, required),
+    (2, swapstring, option),
+    (4, error, option),
+});
+
+impl Type for SwapOfferAcceptedMessage {
+    fn type_id(&self) -> u16 {
+        SWAP_OFFER_ACCEPTED_MESSAGE_TYPE
+    }
+}
+
+/// Swap initiated for a taker accepting one of our offers, to be executed with `/makerexecute`
+#[derive(Clone, Debug)]
+pub(crate) struct SwapOfferAcceptanceData {
+    pub(crate) taker_pubkey: PublicKey,
+    pub(crate) swapstring: String,
+    pub(crate) payment_secret: String,
+    pub(crate) accepted_at: u64,
+}
+
+impl_writeable_tlv_based!(SwapOfferAcceptanceData, {
+    (0, taker_pubkey, required),
+    (2, swapstring, required),
+    (4, payment_secret, required),
+    (6, accepted_at, required),
+});
+
+/// One of our offers, with the swaps initiated for it
+#[derive(Clone, Debug)]
+pub(crate) struct SwapOfferData {
+    pub(crate) offer: SwapOfferMessage,
+    pub(crate) created_at: u64,
+    pub(crate) acceptances: Vec<SwapOfferAcceptanceData>,
+}
+
+impl_writeable_tlv_based!(SwapOfferData, {
+    (0, offer, required),
+    (2, created_at, required),
+    (4, acceptances, required_vec),
+});
+
+/// Our offers, keyed by offer ID
+pub(crate) struct SwapOfferMap {
+    pub(crate) offers: HashMap<String, SwapOfferData>,
+}
+
+impl_writeable_tlv_based!(SwapOfferMap, {
+    (0, offers, required),
+});
+
+/// An offer received from a peer
+#[derive(Clone, Debug)]
+pub(crate) struct ReceivedSwapOffer {
+    pub(crate) maker_pubkey: PublicKey,
+    pub(crate) offer: SwapOfferMessage,
+}
+
+/// Our offers, which are persisted, and the ones received from peers, which are sent again by
+/// their makers as long as they're valid
+pub(crate) struct SwapOfferBook {
+    pub(crate) own: SwapOfferMap,
+    pub(crate) received: HashMap<String, ReceivedSwapOffer>,
+    /// Quantities we asked to swap, by offer ID, waiting for the maker answer
+    pub(crate) pending_acceptances: HashMap<String, u64>,
+}
+
+impl SwapOfferBook {
+    pub(crate) fn new(own: SwapOfferMap) -> Self {
+        Self {
+            own,
+            received: HashMap::new(),
+            pending_acceptances: HashMap::new(),
+        }
+    }
+}
+
+pub(crate) fn save_swap_offers(
+    unlocked_state: &UnlockedAppState,
+    book: &SwapOfferBook,
+) -> Result<(), APIError> {
+    unlocked_state.persist(SWAP_OFFERS_FNAME, &book.own)
+}
+
+/// Send our valid offers to the connected peers supporting them
+pub(crate) fn announce_swap_offers(unlocked_state: &UnlockedAppState) {
+    let offers: Vec<SwapOfferMessage> = unlocked_state
+        .get_swap_offers()
+        .own
+        .offers
+        .values()
+        .filter(|o| !o.offer.is_expired())
+        .map(|o| o.offer.clone())
+        .collect();
+    if offers.is_empty() {
+        return;
+    }
+    for peer in unlocked_state.peer_manager.list_peers() {
+        if !supports_feature_bit(peer.init_features.le_flags(), SWAP_OFFER_FEATURE_BIT) {
+            continue;
+        }
+        for offer in &offers {
+            unlocked_state.peer_message_handler.send_message(
+                peer.counterparty_node_id,
+                PeerMessage::SwapOffer(offer.clone()),
+            );
+        }
+    }
+}
+
+/// Send our swap pairs to the connected peers supporting the swap protocol, if they changed since
+/// they were last advertised
+pub(crate) fn announce_swap_pairs(unlocked_state: &UnlockedAppState, ldk_data_dir: &Path) {
+    let pairs = supported_swap_pairs(
+        ldk_data_dir,
+        unlocked_state.channel_manager.list_channels().iter(),
+    );
+    if !unlocked_state
+        .peer_message_handler
+        .set_swap_pairs(pairs.clone())
+    {
+        return;
+    }
+    for peer in unlocked_state.peer_manager.list_peers() {
+        if !supports_swap_protocol(peer.init_features.le_flags()) {
+            continue;
+        }
+        unlocked_state.peer_message_handler.send_message(
+            peer.counterparty_node_id,
+            PeerMessage::SwapPairs(SwapPairsMessage {
+                pairs: pairs.clone(),
+            }),
+        );
+    }
+}
+
+/// Handle the swap offer messages received from peers and regularly announce our offers and swap
+/// pairs
+pub(crate) async fn run_swap_offers(
+    app_state: Arc<AppState>,
+    mut receiver: mpsc::UnboundedReceiver<(PublicKey, PeerMessage)>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(SWAP_OFFER_ANNOUNCE_INTERVAL_SECS));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        let received = tokio::select! {
+            msg = receiver.recv() => match msg {
+                Some(msg) => Some(msg),
+                None => return,
+            },
+            _ = interval.tick() => None,
+        };
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        let unlocked_state = match app_state.check_unlocked().await {
+            Ok(unlocked_state) => unlocked_state.clone().unwrap(),
+            Err(_) => continue,
+        };
+        match received {
+            Some((peer_pubkey, PeerMessage::SwapOffer(msg))) => {
+                handle_swap_offer(&unlocked_state, msg, peer_pubkey)
+            }
+            Some((peer_pubkey, PeerMessage::SwapOfferAccept(msg))) => {
+                handle_swap_offer_accept(&app_state, &unlocked_state, msg, peer_pubkey)
+            }
+            Some((peer_pubkey, PeerMessage::SwapOfferAccepted(msg))) => {
+                handle_swap_offer_accepted(&unlocked_state, msg, peer_pubkey)
+            }
+            Some((_, PeerMessage::ChannelRequest(_))) | Some((_, PeerMessage::SwapPairs(_))) => {}
+            Some((_, PeerMessage::SubmarineSwapRequest(_)))
+            | Some((_, PeerMessage::SubmarineSwapAccepted(_)))
+            | Some((_, PeerMessage::SubmarineSwapFunded(_))) => {}
+            None => {
+                announce_swap_offers(&unlocked_state);
+                announce_swap_pairs(&unlocked_state, &app_state.static_state.ldk_data_dir);
+            }
+        }
+        unlocked_state.peer_manager.process_events();
+    }
+}
+
+fn handle_swap_offer(
+    unlocked_state: &UnlockedAppState,
+    msg: SwapOfferMessage,
+    peer_pubkey: PublicKey,
+) {
+    if msg.from_asset == msg.to_asset
+        || msg.price_from == 0
+        || msg.price_to == 0
+        || msg.min_qty_from == 0
+        || msg.min_qty_from > msg.max_qty_from
+        || msg.is_expired()
+    {
+        tracing::warn!(
+            "Ignoring invalid swap offer {} from {peer_pubkey}",
+            msg.offer_id
+        );
+        return;
+    }
+
+    let mut book = unlocked_state.get_swap_offers();
+    book.received.retain(|_, o| !o.offer.is_expired());
+    if !book.received.contains_key(&msg.offer_id) && book.received.len() >= MAX_RECEIVED_SWAP_OFFERS
+    {
+        tracing::warn!("Ignoring swap offer from {peer_pubkey}: too many offers");
+        return;
+    }
+    // an offer ID can only be updated by the peer that sent it first
+    if let Some(received) = book.received.get(&msg.offer_id) {
+        if received.maker_pubkey != peer_pubkey {
+            tracing::warn!("Ignoring swap offer {} from {peer_pubkey}", msg.offer_id);
+            return;
+        }
+    }
+    book.received.insert(
+        msg.offer_id.clone(),
+        ReceivedSwapOffer {
+            maker_pubkey: peer_pubkey,
+            offer: msg,
+        },
+    );
+}
+
+fn handle_swap_offer_accept(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: SwapOfferAcceptMessage,
+    peer_pubkey: PublicKey,
+) {
+    let res = initiate_offer_swap(app_state, unlocked_state, &msg, peer_pubkey);
+    let (swapstring, error) = match res {
+        Ok(acceptance) => {
+            let swapstring = acceptance.swapstring;
+            tracing::info!(
+                "EVENT: swap offer {} accepted by peer {peer_pubkey}",
+                msg.offer_id
+            );
+            let _ = app_state.event_sender.send(NodeEvent::SwapOfferAccepted {
+                offer_id: msg.offer_id.clone(),
+                taker_pubkey: peer_pubkey.to_string(),
+                swapstring: swapstring.clone(),
+            });
+            (Some(swapstring), None)
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Refused acceptance of swap offer {} from {peer_pubkey}: {e}",
+                msg.offer_id
+            );
+            (None, Some(e))
+        }
+    };
+    let accepted = swapstring.is_some();
+    unlocked_state.peer_message_handler.send_message(
+        peer_pubkey,
+        PeerMessage::SwapOfferAccepted(SwapOfferAcceptedMessage {
+            offer_id: msg.offer_id,
+            swapstring,
+            error,
+        }),
+    );
+    // let the other takers know what remains of the offer
+    if accepted {
+        announce_swap_offers(unlocked_state);
+    }
+}
+
+/// Initiate, as maker, the swap for a taker accepting one of our offers
+pub(crate) fn initiate_offer_swap(
+    app_state: &AppState,
+    unlocked_state: &UnlockedAppState,
+    msg: &SwapOfferAcceptMessage,
+    peer_pubkey: PublicKey,
+) -> Result<SwapOfferAcceptanceData, String> {
+    let mut book = unlocked_state.get_swap_offers();
+    let offer_data = book
+        .own
+        .offers
+        .get_mut(&msg.offer_id)
+        .ok_or(s!("unknown offer"))?;
+    let qty_to = offer_data.offer.check_qty_from(msg.qty_from)?;
+
+    let swap_info = SwapInfo {
+        from_asset: offer_data.offer.from_asset,
+        to_asset: offer_data.offer.to_asset,
+        qty_from: msg.qty_from,
+        qty_to,
+        expiry: get_current_timestamp() + SWAP_OFFER_SWAP_TIMEOUT_SECS as u64,
+        partial_fill: false,
+    };
+    if let Some(to_asset) = swap_info.to_asset {
+        let max_balance = get_max_local_rgb_amount(
+            to_asset,
+            &app_state.static_state.ldk_data_dir,
+            unlocked_state.channel_manager.list_channels().iter(),
+        );
+        if qty_to > max_balance {
+            return Err(s!("the maker doesn't have enough assets"));
+        }
+    }
+
+    let (payment_hash, payment_secret) = unlocked_state
+        .channel_manager
+        .create_inbound_payment(Some(DUST_LIMIT_MSAT), SWAP_OFFER_SWAP_TIMEOUT_SECS, None)
+        .unwrap();
+    unlocked_state
+        .add_maker_swap(payment_hash, SwapData::create_from_swap_info(&swap_info))
+        .map_err(|e| e.to_string())?;
+
+    let swapstring = SwapString::from_swap_info(&swap_info, payment_hash).to_string();
+    if let Some(remaining_qty_from) = offer_data.offer.remaining_qty_from.as_mut() {
+        *remaining_qty_from -= msg.qty_from;
+    }
+    let acceptance = SwapOfferAcceptanceData {
+        taker_pubkey: peer_pubkey,
+        swapstring,
+        payment_secret: payment_secret.0.as_hex().to_string(),
+        accepted_at: get_current_timestamp(),
+    };
+    offer_data.acceptances.push(acceptance.clone());
+    save_swap_offers(unlocked_state, &book).map_err(|e| e.to_string())?;
+    Ok(acceptance)
+}
+
+/// Whitelist, as taker, the swap initiated by the maker of an offer we accepted
+fn handle_swap_offer_accepted(
+    unlocked_state: &UnlockedAppState,
+    msg: SwapOfferAcceptedMessage,
+    peer_pubkey: PublicKey,
+) {
+    let mut book = unlocked_state.get_swap_offers();
+    let offer = match book.received.get(&msg.offer_id) {
+        Some(received) if received.maker_pubkey == peer_pubkey => received.offer.clone(),
+        _ => {
+            tracing::warn!("Ignoring answer from {peer_pubkey} to unknown swap offer");
+            return;
+        }
+    };
+    let Some(qty_from) = book.pending_acceptances.remove(&msg.offer_id) else {
+        tracing::warn!("Ignoring unexpected answer to swap offer {}", msg.offer_id);
+        return;
+    };
+    drop(book);
+
+    let swapstring = match (msg.swapstring, msg.error) {
+        (Some(swapstring), _) => swapstring,
+        (None, error) => {
+            tracing::error!(
+                "Swap offer {} has been refused by the maker: {}",
+                msg.offer_id,
+                error.unwrap_or_default()
+            );
+            return;
+        }
+    };
+    let swapstring = match SwapString::from_str(&swapstring) {
+        Ok(swapstring) => swapstring,
+        Err(e) => {
+            tracing::error!("Invalid swapstring for swap offer {}: {e}", msg.offer_id);
+            return;
+        }
+    };
+    // the swap must match what we accepted
+    let swap_info = &swapstring.swap_info;
+    if swap_info.from_asset != offer.from_asset
+        || swap_info.to_asset != offer.to_asset
+        || swap_info.qty_from != qty_from
+        || swap_info.qty_to != offer.qty_to(qty_from)
+        || swap_info.expiry <= get_current_timestamp()
+    {
+        tracing::error!(
+            "Swap of offer {} doesn't match the offer terms",
+            msg.offer_id
+        );
+        return;
+    }
+
+    if unlocked_state
+        .add_taker_swap(
+            swapstring.payment_hash,
+            SwapData::create_from_swap_info(swap_info),
+        )
+        .is_err()
+    {
+        return;
+    }
+    tracing::info!(
+        "Whitelisted swap {} of offer {}",
+        swapstring.payment_hash,
+        msg.offer_id
+    );
+}
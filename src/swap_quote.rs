@@ -0,0 +1,71 @@
+use amplify::s;
+use rgb_lib::ContractId;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::swap_offer::SwapOfferBook;
+
+/// Time the price oracle has to answer a quote request
+const PRICE_ORACLE_TIMEOUT_SECS: u64 = 10;
+
+/// Price returned by the oracle: `price_to` of the "to" asset for every `price_from` of the
+/// "from" one, with BTC quantities in msat
+#[derive(Deserialize)]
+struct PriceOracleResponse {
+    price_from: u64,
+    price_to: u64,
+}
+
+/// Our offer giving the taker the most for the given quantity, with the quantity it gives
+pub(crate) fn best_offer_quote(
+    book: &SwapOfferBook,
+    from_asset: Option<ContractId>,
+    to_asset: Option<ContractId>,
+    qty_from: u64,
+) -> Option<(String, u64)> {
+    book.own
+        .offers
+        .iter()
+        .filter(|(_, o)| o.offer.from_asset == from_asset && o.offer.to_asset == to_asset)
+        .filter_map(|(offer_id, o)| {
+            Some((offer_id.clone(), o.offer.check_qty_from(qty_from).ok()?))
+        })
+        .max_by_key(|(_, qty_to)| *qty_to)
+}
+
+/// Quote the given quantity at the price of the oracle, returning the quantity to send net of our
+/// fee along with the fee
+pub(crate) async fn oracle_quote(
+    oracle_url: &str,
+    fee_ppm: u32,
+    from_asset: Option<ContractId>,
+    to_asset: Option<ContractId>,
+    qty_from: u64,
+) -> Result<(u64, u64), String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(PRICE_ORACLE_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let asset_param = |asset: Option<ContractId>| asset.map_or(s!("BTC"), |a| a.to_string());
+    let price: PriceOracleResponse = client
+        .get(oracle_url)
+        .query(&[
+            ("from_asset", asset_param(from_asset)),
+            ("to_asset", asset_param(to_asset)),
+            ("qty_from", qty_from.to_string()),
+        ])
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| format!("the price oracle request failed: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("invalid price oracle response: {e}"))?;
+    if price.price_from == 0 || price.price_to == 0 {
+        return Err(s!("the price oracle has no price for the pair"));
+    }
+    let gross_qty_to =
+        (qty_from as u128 * price.price_to as u128 / price.price_from as u128) as u64;
+    let fee = (gross_qty_to as u128 * fee_ppm as u128 / 1_000_000) as u64;
+    Ok((gross_qty_to - fee, fee))
+}
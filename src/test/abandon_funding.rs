@@ -0,0 +1,113 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/abandon_funding/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn abandon_funding() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    println!("\nabandoning funding of an unknown channel");
+    let payload = AbandonFundingRequest {
+        temporary_channel_id: s!(
+            "a8b60c8ce3067b5fc881d4831323e24751daec3b64353c8df3205ec5d838f1c5"
+        ),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/abandonfunding", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown temporary channel ID",
+    )
+    .await;
+
+    println!("\nopening vanilla channel without mining the funding");
+    stop_mining();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id;
+
+    let t_0 = OffsetDateTime::now_utc();
+    let funding_txid = loop {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels.first().and_then(|c| c.funding_txid.clone()) {
+            if !_get_txout(&funding_txid).is_empty() {
+                break funding_txid;
+            }
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+
+    println!("\nabandoning funding {funding_txid}");
+    let payload = AbandonFundingRequest {
+        temporary_channel_id,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/abandonfunding", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let double_spend_txid = _check_response_is_ok(res)
+        .await
+        .json::<AbandonFundingResponse>()
+        .await
+        .unwrap()
+        .double_spend_txid;
+    assert!(double_spend_txid.is_some());
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    mine_n_blocks(true, 1);
+
+    // the funding transaction has been replaced and can never confirm
+    assert!(_get_txout(&funding_txid).is_empty());
+}
@@ -0,0 +1,81 @@
+use bitcoin::hashes::{sha256, Hash};
+
+use crate::routes::AbandonPaymentRequest;
+use crate::utils::hex_str;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/abandon_payment/";
+
+async fn abandon_payment_raw(node_address: SocketAddr, payment_id: &str) -> reqwest::Response {
+    println!("abandoning payment {payment_id} on node {node_address}");
+    let payload = AbandonPaymentRequest {
+        payment_id: payment_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/abandonpayment", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn abandon_payment() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // a payment to a hold invoice stays pending until the payee settles or cancels it
+    let payment_hash = hex_str(&sha256::Hash::hash(&[1; 32]).to_byte_array());
+    let LNInvoiceResponse { invoice } = hold_ln_invoice(node2_addr, 3000000, &payment_hash).await;
+    let send_payment = _send_payment_raw(node1_addr, invoice).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Claimable).await;
+
+    let res = abandon_payment_raw(node1_addr, &send_payment.payment_id).await;
+    _check_response_is_ok(res).await;
+    assert!(
+        check_payment_status(node1_addr, &payment_hash, HTLCStatus::Failed)
+            .await
+            .is_some()
+    );
+
+    // the HTLC still in flight is released once the payee fails it back
+    cancel_invoice(node2_addr, &payment_hash).await;
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+
+    let res = abandon_payment_raw(node1_addr, &send_payment.payment_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot abandon payment: payment is not pending (Failed)",
+    )
+    .await;
+
+    let res = abandon_payment_raw(node1_addr, &"00".repeat(32)).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown payment ID").await;
+
+    let res = abandon_payment_raw(node1_addr, "invalid").await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid payment ID").await;
+}
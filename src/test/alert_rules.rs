@@ -0,0 +1,66 @@
+use tracing::Level;
+
+use crate::alerts::{AlertEngine, AlertLogLevel, AlertRule, AlertSeverity};
+
+fn rule(name: &str, pattern: &str, threshold: u32, window_secs: u64) -> AlertRule {
+    AlertRule {
+        name: name.to_string(),
+        pattern: pattern.to_string(),
+        target: None,
+        min_level: None,
+        severity: AlertSeverity::Warning,
+        threshold,
+        window_secs,
+    }
+}
+
+#[test]
+fn alert_rules() {
+    let rules: Vec<AlertRule> = serde_json::from_str(
+        r#"[{"name": "posts", "pattern": "cannot post consignment", "severity": "Critical"}]"#,
+    )
+    .unwrap();
+    assert_eq!(rules[0].threshold, 1);
+    assert_eq!(rules[0].window_secs, 60);
+    assert!(rule("empty", "", 1, 60).validate().is_err());
+    assert!(rule("no_window", "x", 1, 0).validate().is_err());
+
+    let mut lock_rule = rule("locks", "blocked async runtime", 1, 60);
+    lock_rule.target = Some("lock_audit".to_string());
+    lock_rule.min_level = Some(AlertLogLevel::Warn);
+    let engine = AlertEngine::new(vec![
+        rule("posts", "cannot post consignment", 3, 600),
+        lock_rule,
+    ]);
+
+    // the threshold must be reached within the window
+    let msg = "cannot post consignment: proxy unreachable";
+    assert!(engine.process(&Level::ERROR, "rln", msg, 1000).is_empty());
+    assert!(engine.process(&Level::ERROR, "rln", msg, 1500).is_empty());
+    assert!(engine.process(&Level::ERROR, "rln", msg, 1700).is_empty());
+    assert!(engine
+        .process(&Level::ERROR, "rln", "other", 1800)
+        .is_empty());
+    let alerts = engine.process(&Level::ERROR, "rln", msg, 2000);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].rule, "posts");
+    assert_eq!(alerts[0].severity, AlertSeverity::Warning);
+    assert_eq!(alerts[0].message, msg);
+    assert_eq!(alerts[0].occurrences, 3);
+    assert_eq!(alerts[0].raised_at, 2000);
+
+    // the count starts over after an alert
+    assert!(engine.process(&Level::ERROR, "rln", msg, 2001).is_empty());
+
+    // target and level are checked
+    let msg = "blocked async runtime for 2s waiting lock channel_ids";
+    assert!(engine
+        .process(&Level::WARN, "rln::ldk", msg, 3000)
+        .is_empty());
+    assert!(engine
+        .process(&Level::INFO, "lock_audit", msg, 3000)
+        .is_empty());
+    let alerts = engine.process(&Level::WARN, "lock_audit", msg, 3000);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].rule, "locks");
+}
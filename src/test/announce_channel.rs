@@ -0,0 +1,168 @@
+use crate::routes::{
+    AnnounceChannelRequest, ChannelConversionStatus, ListChannelConversionsResponse,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/announce_channel/";
+
+async fn announce_channel_raw(
+    node_address: SocketAddr,
+    channel_id: &str,
+    peer_pubkey: &str,
+) -> reqwest::Response {
+    println!("announcing channel {channel_id} from node {node_address}");
+    let payload = AnnounceChannelRequest {
+        channel_id: channel_id.to_string(),
+        peer_pubkey: peer_pubkey.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/announcechannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_channel_conversions(node_address: SocketAddr) -> ListChannelConversionsResponse {
+    println!("listing channel conversions for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listchannelconversions", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListChannelConversionsResponse>()
+        .await
+        .unwrap()
+}
+
+/// Wait for a channel with the given peer and announcement to be funded, then mine it until ready
+async fn wait_for_ready_channel(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    public: bool,
+) -> Channel {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node_address).await;
+        if let Some(channel) = channels
+            .iter()
+            .find(|c| c.peer_pubkey == peer_pubkey && c.public == public)
+        {
+            if channel.ready {
+                return channel.clone();
+            }
+            if let Some(funding_txid) = &channel.funding_txid {
+                if !_get_txout(funding_txid).is_empty() {
+                    mine_n_blocks(true, 6);
+                }
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 120.0 {
+            panic!("channel is taking too long to be ready")
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn announce_channel() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    println!("opening private channel from node {node1_addr}");
+    stop_mining();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: None,
+        with_anchors: true,
+        fee_base_msat: Some(1000),
+        fee_proportional_millionths: Some(100),
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let channel = wait_for_ready_channel(node1_addr, &node2_pubkey, false).await;
+    wait_for_usable_channels(node1_addr, 1).await;
+
+    // only the node that opened the channel can reopen it
+    let res = announce_channel_raw(node2_addr, &channel.channel_id, &node1_pubkey).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot announce channel: only the channels opened by the node can be converted",
+    )
+    .await;
+
+    stop_mining();
+    let res = announce_channel_raw(node1_addr, &channel.channel_id, &node2_pubkey).await;
+    _check_response_is_ok(res).await;
+    let conversions = list_channel_conversions(node1_addr).await.conversions;
+    assert_eq!(conversions.len(), 1);
+    assert_eq!(conversions[0].channel_id, channel.channel_id);
+    assert_eq!(conversions[0].capacity_sat, 100_000);
+
+    // the channel is closed, then reopened as announced with the same fees
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if !channels.iter().any(|c| c.channel_id == channel.channel_id) {
+            mine_n_blocks(true, 6);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel is taking too long to close")
+        }
+    }
+    let announced_channel = wait_for_ready_channel(node1_addr, &node2_pubkey, true).await;
+    assert_eq!(announced_channel.capacity_sat, 100_000);
+    assert_eq!(announced_channel.fee_base_msat, Some(1000));
+    assert_eq!(announced_channel.fee_proportional_millionths, Some(100));
+
+    let conversions = list_channel_conversions(node1_addr).await.conversions;
+    assert_eq!(conversions[0].status, ChannelConversionStatus::Completed);
+    assert!(conversions[0].temporary_channel_id.is_some());
+    assert!(conversions[0].completed_at.is_some());
+
+    // the announced channel cannot be converted again
+    let res = announce_channel_raw(node1_addr, &announced_channel.channel_id, &node2_pubkey).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot announce channel: the channel is already announced",
+    )
+    .await;
+}
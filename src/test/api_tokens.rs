@@ -0,0 +1,195 @@
+use crate::routes::{
+    ApiToken, CreateApiTokenRequest, CreateApiTokenResponse, ListApiTokensResponse,
+    RevokeApiTokenRequest,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/api_tokens/";
+
+async fn create_api_token(
+    node_address: SocketAddr,
+    name: &str,
+    password: &str,
+    read_only: bool,
+) -> String {
+    println!("creating API token {name} for node {node_address}");
+    let payload = CreateApiTokenRequest {
+        name: name.to_string(),
+        password: password.to_string(),
+        read_only,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/createapitoken", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<CreateApiTokenResponse>()
+        .await
+        .unwrap()
+        .token
+}
+
+async fn list_api_tokens(node_address: SocketAddr, token: &str) -> Vec<ApiToken> {
+    println!("listing API tokens for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listapitokens", node_address))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListApiTokensResponse>()
+        .await
+        .unwrap()
+        .tokens
+}
+
+async fn revoke_api_token(node_address: SocketAddr, name: &str, token: &str) {
+    println!("revoking API token {name} for node {node_address}");
+    let payload = RevokeApiTokenRequest {
+        name: name.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/revokeapitoken", node_address))
+        .bearer_auth(token)
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn node_info_raw(node_address: SocketAddr, token: Option<&str>) -> reqwest::Response {
+    let mut request = reqwest::Client::new().get(format!("http://{}/nodeinfo", node_address));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await.unwrap()
+}
+
+async fn send_btc_raw(node_address: SocketAddr, token: &str) -> reqwest::Response {
+    let payload = SendBtcRequest {
+        amount: 1000,
+        address: s!("bcrt1qnc5y6j6dmejrkwy93farhvpezk0lf46gk7aecs"),
+        fee_rate: Some(FEE_RATE),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/sendbtc", node_address))
+        .bearer_auth(token)
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn api_tokens() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        require_api_token: true,
+        ..Default::default()
+    };
+    let (node1_addr, node1_password) = start_node_with_args(args, false).await;
+
+    println!("\ntokens are created with the node password");
+    let payload = CreateApiTokenRequest {
+        name: s!("full"),
+        password: s!("wrong password"),
+        read_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/createapitoken", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+    )
+    .await;
+    let full_token = create_api_token(node1_addr, "full", &node1_password, false).await;
+    let read_only_token = create_api_token(node1_addr, "read", &node1_password, true).await;
+    let tokens = list_api_tokens(node1_addr, &full_token).await;
+    assert_eq!(tokens.len(), 2);
+    assert!(!tokens[0].read_only && tokens[0].name == "full");
+    assert!(tokens[1].read_only && tokens[1].name == "read");
+
+    println!("\nrequests need a valid token");
+    let res = node_info_raw(node1_addr, None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "An API token is required",
+    )
+    .await;
+    let res = node_info_raw(node1_addr, Some("not a token")).await;
+    check_response_is_nok(res, reqwest::StatusCode::UNAUTHORIZED, "Invalid API token").await;
+    let res = node_info_raw(node1_addr, Some(&full_token)).await;
+    _check_response_is_ok(res).await;
+
+    println!("\nread-only tokens cannot move funds");
+    let res = node_info_raw(node1_addr, Some(&read_only_token)).await;
+    _check_response_is_ok(res).await;
+    let res = send_btc_raw(node1_addr, &read_only_token).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The API token is read-only",
+    )
+    .await;
+
+    println!("\nrevoked tokens are refused");
+    revoke_api_token(node1_addr, "read", &full_token).await;
+    let res = node_info_raw(node1_addr, Some(&read_only_token)).await;
+    check_response_is_nok(res, reqwest::StatusCode::UNAUTHORIZED, "Invalid API token").await;
+    let tokens = list_api_tokens(node1_addr, &full_token).await;
+    assert_eq!(tokens.len(), 1);
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn api_tokens_enforced_once_created() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}enforced_node1");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    println!("\nrequests without a token are allowed while there are no tokens");
+    let res = node_info_raw(node1_addr, None).await;
+    _check_response_is_ok(res).await;
+
+    println!("\nrequests without a token are refused once a token exists");
+    let token = create_api_token(node1_addr, "full", &node1_password, false).await;
+    let res = node_info_raw(node1_addr, None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "An API token is required",
+    )
+    .await;
+    let res = node_info_raw(node1_addr, Some(&token)).await;
+    _check_response_is_ok(res).await;
+
+    println!("\nrequests without a token are allowed again once all tokens are revoked");
+    revoke_api_token(node1_addr, "full", &token).await;
+    let res = node_info_raw(node1_addr, None).await;
+    _check_response_is_ok(res).await;
+}
@@ -0,0 +1,124 @@
+use crate::routes::SetAssetHtlcLimitRequest;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_htlc_limit/";
+
+async fn set_asset_htlc_limit_raw(
+    node_address: SocketAddr,
+    channel_id: &str,
+    max_asset_amount: Option<u64>,
+) -> reqwest::Response {
+    println!("setting asset HTLC limit {max_asset_amount:?} on channel {channel_id} of node {node_address}");
+    let payload = SetAssetHtlcLimitRequest {
+        channel_id: channel_id.to_string(),
+        max_asset_amount,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/setassethtlclimit", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn set_asset_htlc_limit(
+    node_address: SocketAddr,
+    channel_id: &str,
+    max_asset_amount: Option<u64>,
+) {
+    let res = set_asset_htlc_limit_raw(node_address, channel_id, max_asset_amount).await;
+    _check_response_is_ok(res).await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_htlc_limit() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let rgb_channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let vanilla_channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    set_asset_htlc_limit(node2_addr, &rgb_channel.channel_id, Some(100)).await;
+    let channels = list_channels(node2_addr).await;
+    let chan = channels
+        .iter()
+        .find(|c| c.channel_id == rgb_channel.channel_id)
+        .unwrap();
+    assert_eq!(chan.asset_htlc_maximum, Some(100));
+
+    println!("\nsending an asset payment over the limit");
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(150), 900).await;
+    send_payment_with_status(node1_addr, invoice, HTLCStatus::Failed).await;
+
+    println!("\nsending an asset payment within the limit");
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(50), 900).await;
+    send_payment(node1_addr, invoice).await;
+    assert_eq!(
+        asset_balance_offchain_outbound(node1_addr, &asset_id).await,
+        550
+    );
+
+    println!("\nremoving the limit");
+    set_asset_htlc_limit(node2_addr, &rgb_channel.channel_id, None).await;
+    let channels = list_channels(node2_addr).await;
+    let chan = channels
+        .iter()
+        .find(|c| c.channel_id == rgb_channel.channel_id)
+        .unwrap();
+    assert_eq!(chan.asset_htlc_maximum, None);
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(150), 900).await;
+    send_payment(node1_addr, invoice).await;
+
+    let res = set_asset_htlc_limit_raw(node2_addr, &vanilla_channel.channel_id, Some(100)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel policy: asset HTLC limits can only be set on RGB channels",
+    )
+    .await;
+
+    let res = set_asset_htlc_limit_raw(node2_addr, &rgb_channel.channel_id, Some(0)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel policy: max_asset_amount must be positive",
+    )
+    .await;
+
+    let res = set_asset_htlc_limit_raw(node2_addr, "invalid", Some(100)).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid channel ID").await;
+}
@@ -0,0 +1,243 @@
+use crate::routes::{
+    AssetLoopInRequest, AssetLoopInResponse, AssetLoopOutRequest, AssetLoopOutResponse,
+    ListSubmarineSwapsResponse, SubmarineSwap, SubmarineSwapKind, SubmarineSwapStatus,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_submarine_swaps/";
+
+async fn asset_loop_in_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    asset_id: &str,
+    asset_amount: u64,
+) -> reqwest::Response {
+    println!(
+        "starting loop in of {asset_amount} of asset {asset_id} with peer {peer_pubkey} for node \
+        {node_address}"
+    );
+    let payload = AssetLoopInRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        asset_id: asset_id.to_string(),
+        asset_amount,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/assetloopin", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn asset_loop_in(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    asset_id: &str,
+    asset_amount: u64,
+) -> String {
+    let res = asset_loop_in_raw(node_address, peer_pubkey, asset_id, asset_amount).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<AssetLoopInResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+}
+
+async fn asset_loop_out_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    asset_id: &str,
+    asset_amount: u64,
+    rgb_invoice: &str,
+) -> reqwest::Response {
+    println!(
+        "starting loop out of {asset_amount} of asset {asset_id} with peer {peer_pubkey} for node \
+        {node_address}"
+    );
+    let payload = AssetLoopOutRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        asset_id: asset_id.to_string(),
+        asset_amount,
+        rgb_invoice: rgb_invoice.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/assetloopout", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn asset_loop_out(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    asset_id: &str,
+    asset_amount: u64,
+    rgb_invoice: &str,
+) -> String {
+    let res = asset_loop_out_raw(
+        node_address,
+        peer_pubkey,
+        asset_id,
+        asset_amount,
+        rgb_invoice,
+    )
+    .await;
+    _check_response_is_ok(res)
+        .await
+        .json::<AssetLoopOutResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+}
+
+async fn wait_for_submarine_swap_status(
+    node_address: SocketAddr,
+    payment_hash: &str,
+    expected_status: SubmarineSwapStatus,
+) -> SubmarineSwap {
+    println!(
+        "waiting for status for submarine swap with payment hash {payment_hash} to become \
+        {expected_status:?} on node {node_address}",
+    );
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .get(format!("http://{}/listsubmarineswaps", node_address))
+            .send()
+            .await
+            .unwrap();
+        let swap = _check_response_is_ok(res)
+            .await
+            .json::<ListSubmarineSwapsResponse>()
+            .await
+            .unwrap()
+            .swaps
+            .into_iter()
+            .find(|s| s.payment_hash == payment_hash)
+            .unwrap();
+        if swap.status == expected_status {
+            return swap;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 70.0 {
+            panic!(
+                "status ({:?}) is not becoming the expected one ({expected_status:?})",
+                swap.status
+            );
+        }
+        tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_submarine_swaps() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        submarine_swap_server: true,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let recipient_id = rgb_invoice(node2_addr, Some(asset_id.clone()))
+        .await
+        .recipient_id;
+    send_asset(node1_addr, &asset_id, 200, recipient_id).await;
+    mine(false);
+    wait_for_balance(node2_addr, &asset_id, 200).await;
+    wait_for_balance(node1_addr, &asset_id, 800).await;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(50000000),
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    println!("\nasset loop in");
+    let payment_hash = asset_loop_in(node2_addr, &node1_pubkey, &asset_id, 100).await;
+    let swap =
+        wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Funded)
+            .await;
+    assert_eq!(swap.kind, SubmarineSwapKind::LoopIn);
+    assert_eq!(swap.asset_id, Some(asset_id.clone()));
+    assert_eq!(swap.asset_amount, Some(100));
+    assert_eq!(swap.htlc_address, None);
+    assert!(swap.rgb_invoice.is_some());
+    assert!(swap.funding_txid.is_some());
+    refresh_transfers(node1_addr).await;
+    mine(false);
+    refresh_transfers(node1_addr).await;
+    let swap =
+        wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Succeeded)
+            .await;
+    assert!(swap.server);
+    assert_eq!(swap.failure_reason, None);
+    wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Succeeded).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+
+    println!("\nasset loop out to cold storage");
+    let cold_storage_invoice = rgb_invoice(node2_addr, Some(asset_id.clone()))
+        .await
+        .invoice;
+    let payment_hash = asset_loop_out(
+        node2_addr,
+        &node1_pubkey,
+        &asset_id,
+        50,
+        &cold_storage_invoice,
+    )
+    .await;
+    let swap =
+        wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Succeeded)
+            .await;
+    assert_eq!(swap.kind, SubmarineSwapKind::LoopOut);
+    assert!(swap.funding_txid.is_some());
+    wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Succeeded).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 50).await;
+    refresh_transfers(node2_addr).await;
+    mine(false);
+    wait_for_balance(node2_addr, &asset_id, 150).await;
+
+    println!("\ninvalid asset swaps");
+    let res = asset_loop_in_raw(node2_addr, &node1_pubkey, "bad asset ID", 10).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid asset ID: bad asset ID",
+    )
+    .await;
+    let res = asset_loop_out_raw(
+        node2_addr,
+        &node1_pubkey,
+        &asset_id,
+        500,
+        &cold_storage_invoice,
+    )
+    .await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Not enough assets").await;
+    let res = asset_loop_out_raw(node2_addr, &node1_pubkey, &asset_id, 10, "invalid").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,49 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/background_refresh/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn background_refresh() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let mut node_addrs = vec![];
+    for (test_dir, peer_port) in [
+        (test_dir_node1, NODE1_PEER_PORT),
+        (test_dir_node2, NODE2_PEER_PORT),
+    ] {
+        let args = LdkUserInfo {
+            storage_dir_path: test_dir.into(),
+            ldk_peer_listening_port: peer_port,
+            rgb_refresh_interval: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        node_addrs.push(start_node_with_args(args, false).await.0);
+    }
+    let (node1_addr, node2_addr) = (node_addrs[0], node_addrs[1]);
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    // the transfer completes without ever calling /refreshtransfers
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 400, recipient_id).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        mine(false);
+        if asset_balance_spendable(node2_addr, &asset_id).await == 400
+            && asset_balance_spendable(node1_addr, &asset_id).await == 600
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 90.0 {
+            panic!("transfer has not been refreshed in the background")
+        }
+    }
+}
@@ -51,8 +51,9 @@ async fn backup_and_restore() {
 
     // check InvalidBackupPath error
     let payload = BackupRequest {
-        backup_path: node1_backup_path.clone(),
+        backup_path: Some(node1_backup_path.clone()),
         password: node1_password.clone(),
+        incremental: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/backup", node1_addr))
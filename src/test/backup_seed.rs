@@ -0,0 +1,117 @@
+use crate::routes::{BackupSeedRequest, BackupSeedResponse, SeedWord};
+use crate::utils::check_password_validity;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/backup_seed/";
+
+async fn backup_seed_raw(
+    node_address: SocketAddr,
+    password: &str,
+    words: Option<Vec<(u8, &str)>>,
+) -> reqwest::Response {
+    println!("backing up the seed of node {node_address}");
+    let payload = BackupSeedRequest {
+        password: password.to_string(),
+        words: words.map(|words| {
+            words
+                .into_iter()
+                .map(|(position, word)| SeedWord {
+                    position,
+                    word: word.to_string(),
+                })
+                .collect()
+        }),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/backupseed", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn backup_seed(
+    node_address: SocketAddr,
+    password: &str,
+    words: Option<Vec<(u8, &str)>>,
+) -> BackupSeedResponse {
+    let res = backup_seed_raw(node_address, password, words).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<BackupSeedResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn backup_seed_reveal_and_verify() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let mnemonic = check_password_validity(&node1_password, Path::new(&test_dir_node1))
+        .unwrap()
+        .to_string();
+    let words: Vec<&str> = mnemonic.split_whitespace().collect();
+
+    println!("\nthe password is required");
+    let res = backup_seed_raw(node1_addr, "wrong password", None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+    )
+    .await;
+
+    println!("\nverify the words of a backup");
+    let response = backup_seed(
+        node1_addr,
+        &node1_password,
+        Some(vec![(1, words[0]), (7, words[6].to_uppercase().as_str())]),
+    )
+    .await;
+    assert_eq!(response.verified, Some(true));
+    assert!(response.mnemonic.is_none());
+    let wrong_word = if words[2] == "abandon" {
+        "zoo"
+    } else {
+        "abandon"
+    };
+    let response = backup_seed(
+        node1_addr,
+        &node1_password,
+        Some(vec![(1, words[0]), (3, wrong_word)]),
+    )
+    .await;
+    assert_eq!(response.verified, Some(false));
+    let res = backup_seed_raw(node1_addr, &node1_password, Some(vec![(25, "zoo")])).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!(
+            "Invalid seed words: position 25 is out of range (1-{})",
+            words.len()
+        ),
+    )
+    .await;
+
+    println!("\nthe mnemonic is revealed only once");
+    let response = backup_seed(node1_addr, &node1_password, None).await;
+    assert_eq!(response.mnemonic, Some(mnemonic));
+    assert!(response.verified.is_none());
+    let res = backup_seed_raw(node1_addr, &node1_password, None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The mnemonic has already been revealed",
+    )
+    .await;
+
+    println!("\nbackups can still be verified after the reveal");
+    let response = backup_seed(node1_addr, &node1_password, Some(vec![(2, words[1])])).await;
+    assert_eq!(response.verified, Some(true));
+}
@@ -0,0 +1,111 @@
+use crate::routes::BumpCloseTxRequest;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/bump_close_tx/";
+
+async fn bump_close_tx_raw(
+    node_address: SocketAddr,
+    channel_id: &str,
+    fee_rate: f32,
+) -> reqwest::Response {
+    println!("bumping close TX of channel {channel_id} on node {node_address}");
+    let payload = BumpCloseTxRequest {
+        channel_id: channel_id.to_string(),
+        fee_rate,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/bumpclosetx", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn bump_close_tx() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let res = bump_close_tx_raw(node1_addr, &channel.channel_id, 10.0).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot bump close transaction: the channel has not been force-closed",
+    )
+    .await;
+
+    // force-close without mining, so that the commitment TX is still unconfirmed
+    stop_mining();
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        fee_rate: None,
+        conf_target: None,
+        close_address: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/closechannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while list_channels(node1_addr)
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel.channel_id)
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel is taking too long to close")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    let res = bump_close_tx_raw(node1_addr, &channel.channel_id, 0.5).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid fee rate: fee rate cannot be lower than 1 sat/vB",
+    )
+    .await;
+
+    let res = bump_close_tx_raw(node1_addr, &channel.channel_id, 20.0).await;
+    _check_response_is_ok(res).await;
+
+    mine_n_blocks(true, 144);
+    wait_for_balance(node1_addr, &asset_id, 1000).await;
+
+    let res = bump_close_tx_raw(
+        node1_addr,
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        10.0,
+    )
+    .await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown channel ID").await;
+}
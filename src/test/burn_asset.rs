@@ -0,0 +1,73 @@
+use crate::routes::{
+    BurnAssetRequest, BurnAssetResponse, TransferProofRequest, TransferProofResponse,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/burn_asset/";
+
+async fn burn_asset_raw(
+    node_address: SocketAddr,
+    asset_id: &str,
+    amount: u64,
+) -> reqwest::Response {
+    println!("burning {amount} of asset {asset_id} on node {node_address}");
+    let payload = BurnAssetRequest {
+        asset_id: asset_id.to_string(),
+        amount,
+        fee_rate: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/burnasset", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn burn_asset() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let res = burn_asset_raw(node1_addr, &asset_id, 2000).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Not enough assets").await;
+
+    let res = burn_asset_raw(node1_addr, &asset_id, 300).await;
+    let BurnAssetResponse { txid, burn_script } = _check_response_is_ok(res)
+        .await
+        .json::<BurnAssetResponse>()
+        .await
+        .unwrap();
+    assert_eq!(burn_script, "6a");
+
+    mine(false);
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 700);
+
+    // the burn is proven like any other transfer
+    let payload = TransferProofRequest {
+        txid,
+        proof_path: format!("{test_dir_node1}/burn_proof.zip"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/transferproof", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let proof = _check_response_is_ok(res)
+        .await
+        .json::<TransferProofResponse>()
+        .await
+        .unwrap();
+    assert_eq!(proof.asset_ids, vec![asset_id]);
+}
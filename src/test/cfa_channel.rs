@@ -0,0 +1,82 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/cfa_channel/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn cfa_channel() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_cfa(node1_addr, None).await.asset_id;
+    let uda_asset_id = issue_asset_uda(node1_addr, None).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    println!("\nopening UDA channel");
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: Some(1),
+        asset_id: Some(uda_asset_id),
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot open channel: UDA assets cannot be moved to channels",
+    )
+    .await;
+
+    println!("\nopening CFA channel");
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3000000),
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    assert_eq!(channel.asset_id, Some(asset_id.clone()));
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1400);
+
+    keysend(node1_addr, &node2_pubkey, None, Some(&asset_id), Some(150)).await;
+    keysend(node2_addr, &node1_pubkey, None, Some(&asset_id), Some(50)).await;
+
+    // the closing outputs are colored with the RGB25 interface
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, true).await;
+    wait_for_balance(node1_addr, &asset_id, 1900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+}
@@ -0,0 +1,116 @@
+use crate::routes::SetChannelAnnouncementRequest;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_announcement/";
+
+async fn set_channel_announcement_raw(node_address: SocketAddr, public: bool) -> reqwest::Response {
+    println!("setting channel announcement (public: {public}) on node {node_address}");
+    let payload = SetChannelAnnouncementRequest { public };
+    reqwest::Client::new()
+        .post(format!("http://{}/setchannelannouncement", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn open_channel_with_public(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    dest_peer_port: u16,
+    public: Option<bool>,
+) -> bool {
+    println!("opening channel (public: {public:?}) from node {node_address} to {dest_peer_pubkey}");
+    let channel_ids: Vec<String> = list_channels(node_address)
+        .await
+        .into_iter()
+        .map(|c| c.channel_id)
+        .collect();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{dest_peer_pubkey}@127.0.0.1:{dest_peer_port}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public,
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap();
+    // the channel ID changes once funded, so look for the new channel instead
+    list_channels(node_address)
+        .await
+        .iter()
+        .find(|c| !channel_ids.contains(&c.channel_id))
+        .unwrap()
+        .public
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_announcement() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node2.into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        announce_channels: true,
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // channels are private by default, unless the node is started with --announce-channels
+    assert!(!open_channel_with_public(node1_addr, &node2_pubkey, NODE2_PEER_PORT, None).await);
+    assert!(open_channel_with_public(node2_addr, &node1_pubkey, NODE1_PEER_PORT, None).await);
+
+    // the node default can be overridden when opening a channel
+    assert!(open_channel_with_public(node1_addr, &node2_pubkey, NODE2_PEER_PORT, Some(true)).await);
+    assert!(
+        !open_channel_with_public(node2_addr, &node1_pubkey, NODE1_PEER_PORT, Some(false)).await
+    );
+
+    // the node default can be changed at runtime
+    let res = set_channel_announcement_raw(node1_addr, true).await;
+    _check_response_is_ok(res).await;
+    assert!(open_channel_with_public(node1_addr, &node2_pubkey, NODE2_PEER_PORT, None).await);
+
+    // existing channels keep the preference negotiated when opening them
+    assert!(list_channels(node1_addr).await.iter().any(|c| !c.public));
+
+    // the default set at runtime survives a restart
+    shutdown(&[node1_addr]).await;
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, true).await;
+    assert!(open_channel_with_public(node1_addr, &node2_pubkey, NODE2_PEER_PORT, None).await);
+}
@@ -0,0 +1,148 @@
+use crate::routes::{UpdateChannelPolicyRequest, UpdateChannelPolicyResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_policy/";
+
+async fn update_channel_policy_raw(
+    node_address: SocketAddr,
+    payload: &UpdateChannelPolicyRequest,
+) -> reqwest::Response {
+    println!("updating channel policy on node {node_address}");
+    reqwest::Client::new()
+        .post(format!("http://{}/updatechannelpolicy", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn update_channel_policy(
+    node_address: SocketAddr,
+    payload: &UpdateChannelPolicyRequest,
+) -> Vec<String> {
+    let res = update_channel_policy_raw(node_address, payload).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<UpdateChannelPolicyResponse>()
+        .await
+        .unwrap()
+        .channel_ids
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_policy() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let rgb_channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let vanilla_channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    // the asset selects its channels only
+    let channel_ids = update_channel_policy(
+        node1_addr,
+        &UpdateChannelPolicyRequest {
+            channel_id: None,
+            asset_id: Some(asset_id.clone()),
+            fee_base_msat: Some(2000),
+            fee_proportional_millionths: Some(150),
+            cltv_expiry_delta: None,
+        },
+    )
+    .await;
+    assert_eq!(channel_ids, vec![rgb_channel.channel_id.clone()]);
+
+    let channel_ids = update_channel_policy(
+        node1_addr,
+        &UpdateChannelPolicyRequest {
+            channel_id: Some(vanilla_channel.channel_id.clone()),
+            asset_id: None,
+            fee_base_msat: None,
+            fee_proportional_millionths: None,
+            cltv_expiry_delta: Some(144),
+        },
+    )
+    .await;
+    assert_eq!(channel_ids, vec![vanilla_channel.channel_id.clone()]);
+
+    let channels = list_channels(node1_addr).await;
+    let rgb_chan = channels
+        .iter()
+        .find(|c| c.channel_id == rgb_channel.channel_id)
+        .unwrap();
+    assert_eq!(rgb_chan.fee_base_msat, Some(2000));
+    assert_eq!(rgb_chan.fee_proportional_millionths, Some(150));
+    assert_eq!(rgb_chan.cltv_expiry_delta, rgb_channel.cltv_expiry_delta);
+    let vanilla_chan = channels
+        .iter()
+        .find(|c| c.channel_id == vanilla_channel.channel_id)
+        .unwrap();
+    assert_eq!(vanilla_chan.fee_base_msat, vanilla_channel.fee_base_msat);
+    assert_eq!(vanilla_chan.cltv_expiry_delta, Some(144));
+
+    let res = update_channel_policy_raw(
+        node1_addr,
+        &UpdateChannelPolicyRequest {
+            channel_id: Some(vanilla_channel.channel_id.clone()),
+            asset_id: Some(asset_id),
+            fee_base_msat: Some(0),
+            fee_proportional_millionths: None,
+            cltv_expiry_delta: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel policy: exactly one of channel_id and asset_id must be set",
+    )
+    .await;
+
+    let res = update_channel_policy_raw(
+        node1_addr,
+        &UpdateChannelPolicyRequest {
+            channel_id: Some(vanilla_channel.channel_id),
+            asset_id: None,
+            fee_base_msat: None,
+            fee_proportional_millionths: None,
+            cltv_expiry_delta: Some(10),
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel policy: CLTV expiry delta cannot be lower than 72",
+    )
+    .await;
+}
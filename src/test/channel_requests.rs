@@ -0,0 +1,202 @@
+use crate::routes::{
+    ApproveChannelRequestRequest, ChannelRequest, ChannelRequestStatus,
+    ListChannelRequestsResponse, RejectChannelRequestRequest, RequestChannelRequest,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_requests/";
+
+async fn request_channel_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    capacity_sat: u64,
+) -> reqwest::Response {
+    println!(
+        "requesting a {capacity_sat} sat channel to peer {peer_pubkey} from node {node_address}"
+    );
+    let payload = RequestChannelRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        capacity_sat,
+        push_msat: 0,
+        asset_id: None,
+        asset_amount: None,
+        fee_sat: 1000,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/requestchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_channel_requests(node_address: SocketAddr) -> Vec<ChannelRequest> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/channelrequests", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListChannelRequestsResponse>()
+        .await
+        .unwrap()
+        .requests
+}
+
+async fn wait_for_pending_request(node_address: SocketAddr, peer_pubkey: &str) -> ChannelRequest {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(request) = list_channel_requests(node_address)
+            .await
+            .into_iter()
+            .find(|r| r.peer_pubkey == peer_pubkey && r.status == ChannelRequestStatus::Pending)
+        {
+            return request;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel request from {peer_pubkey} has not been received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn approve_channel_request_raw(
+    node_address: SocketAddr,
+    request_id: &str,
+) -> reqwest::Response {
+    let payload = ApproveChannelRequestRequest {
+        request_id: request_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/approvechannelrequest", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn reject_channel_request_raw(
+    node_address: SocketAddr,
+    request_id: &str,
+) -> reqwest::Response {
+    let payload = RejectChannelRequestRequest {
+        request_id: request_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/rejectchannelrequest", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_requests() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // requests can only be sent to connected peers
+    let res = request_channel_raw(node2_addr, &node1_pubkey, 100000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot request channel: peer is not connected",
+    )
+    .await;
+
+    connect_peer(
+        node2_addr,
+        &node1_pubkey,
+        &format!("127.0.0.1:{NODE1_PEER_PORT}"),
+    )
+    .await;
+
+    println!("\nrejecting a channel request");
+    let res = request_channel_raw(node2_addr, &node1_pubkey, 100000).await;
+    _check_response_is_ok(res).await;
+    let request = wait_for_pending_request(node1_addr, &node2_pubkey).await;
+    assert_eq!(request.capacity_sat, 100000);
+    assert_eq!(request.fee_sat, 1000);
+    let res = reject_channel_request_raw(node1_addr, &request.request_id).await;
+    _check_response_is_ok(res).await;
+    let res = approve_channel_request_raw(node1_addr, &request.request_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Channel request has already been handled",
+    )
+    .await;
+
+    println!("\napproving a channel request");
+    let res = request_channel_raw(node2_addr, &node1_pubkey, 100000).await;
+    _check_response_is_ok(res).await;
+    let request = wait_for_pending_request(node1_addr, &node2_pubkey).await;
+    let res = approve_channel_request_raw(node1_addr, &request.request_id).await;
+    let OpenChannelResponse {
+        temporary_channel_id,
+        ..
+    } = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap();
+    let requests = list_channel_requests(node1_addr).await;
+    let request = requests
+        .iter()
+        .find(|r| r.request_id == request.request_id)
+        .unwrap();
+    assert_eq!(request.status, ChannelRequestStatus::Approved);
+    assert_eq!(request.temporary_channel_id, Some(temporary_channel_id));
+    assert_eq!(
+        requests
+            .iter()
+            .filter(|r| r.status == ChannelRequestStatus::Rejected)
+            .count(),
+        1
+    );
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels
+            .iter()
+            .find(|c| c.peer_pubkey == node2_pubkey)
+            .and_then(|c| c.funding_txid.clone())
+        {
+            if !_get_txout(&funding_txid).is_empty() {
+                mine_n_blocks(true, 6);
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    wait_for_usable_channels(node1_addr, 1).await;
+    wait_for_usable_channels(node2_addr, 1).await;
+    let channel = list_channels(node2_addr).await.pop().unwrap();
+    assert_eq!(channel.capacity_sat, 100000);
+    assert_eq!(channel.peer_pubkey, node1_pubkey);
+
+    let res = reject_channel_request_raw(node1_addr, "unknown").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel request",
+    )
+    .await;
+}
@@ -0,0 +1,102 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/close_coop_address/";
+
+async fn close_channel_raw(
+    node_address: SocketAddr,
+    payload: &CloseChannelRequest,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/closechannel", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn close_coop_address() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(600_000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    let close_address = address(node3_addr).await;
+
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        fee_rate: None,
+        conf_target: None,
+        close_address: Some(close_address.clone()),
+    };
+    let res = close_channel_raw(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot close channel: fee rate, confirmation target and close address cannot be set for force-closes",
+    )
+    .await;
+
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: false,
+        fee_rate: Some(2.0),
+        conf_target: Some(6),
+        close_address: None,
+    };
+    let res = close_channel_raw(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid fee rate: only one of fee_rate and conf_target can be set",
+    )
+    .await;
+
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: false,
+        fee_rate: Some(2.0),
+        conf_target: None,
+        close_address: Some(close_address),
+    };
+    close_channel_with_payload(node1_addr, &payload).await;
+
+    // our side of the channel is paid to the close address
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let balance = btc_balance(node3_addr).await.vanilla.settled;
+        if balance > 590_000 {
+            assert!(balance < 600_000);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("close output not received")
+        }
+    }
+}
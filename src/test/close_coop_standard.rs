@@ -1,7 +1,24 @@
+use crate::routes::{ClosedChannel, ListClosedChannelsResponse};
+
 use super::*;
 
 const TEST_DIR_BASE: &str = "tmp/close_coop_standard/";
 
+async fn list_closed_channels(node_address: SocketAddr) -> Vec<ClosedChannel> {
+    println!("listing closed channels for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listclosedchannels", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListClosedChannelsResponse>()
+        .await
+        .unwrap()
+        .channels
+}
+
 #[serial_test::serial]
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 #[traced_test]
@@ -34,6 +51,12 @@ async fn close_coop_standard() {
     assert_eq!(assets.nia.unwrap().len(), 1);
     assert_eq!(assets.uda.unwrap().len(), 0);
     assert_eq!(assets.cfa.unwrap().len(), 0);
+    let unspents = list_unspents(node1_addr).await;
+    assert!(unspents.iter().any(|u| u.utxo.colorable
+        && u.rgb_allocations.iter().any(|a| {
+            a.asset_id.as_deref() == Some(&asset_id) && a.amount == 1000 && a.settled
+        })));
+    assert!(unspents.iter().all(|u| !u.locked_for_funding));
 
     let node1_pubkey = node_info(node1_addr).await.pubkey;
     let node2_pubkey = node_info(node2_addr).await.pubkey;
@@ -93,10 +116,70 @@ async fn close_coop_standard() {
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 390);
     assert_eq!(asset_balance_spendable(node3_addr, &asset_id).await, 10);
 
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(
+        channels.first().unwrap().shutdown_state,
+        Some(ChannelShutdownState::NotShuttingDown)
+    );
+
+    // a closing fee rate below the configured min one should be refused
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: false,
+        fee_rate: Some(0.5),
+        conf_target: None,
+        close_address: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/closechannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid fee rate: closing fee rate cannot be lower than 1 sat/vB",
+    )
+    .await;
+
+    // assets cannot be moved to an external close address
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: false,
+        fee_rate: None,
+        conf_target: None,
+        close_address: Some(address(node3_addr).await),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/closechannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot close channel: a close address can only be set for vanilla channels",
+    )
+    .await;
+
     close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
     wait_for_balance(node1_addr, &asset_id, 890).await;
     wait_for_balance(node2_addr, &asset_id, 100).await;
 
+    // the closed channel is kept with the reason it has been closed for
+    let closed_channels = list_closed_channels(node1_addr).await;
+    let closed_channel = closed_channels
+        .iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap();
+    assert_eq!(closed_channel.peer_pubkey.as_ref(), Some(&node2_pubkey));
+    assert!(!closed_channel.reason.is_empty());
+    assert!(closed_channel.error.is_none());
+
     let peers = list_peers(node1_addr).await;
     assert!(peers.iter().any(|p| p.pubkey == node2_pubkey));
     disconnect_peer(node1_addr, &node2_pubkey).await;
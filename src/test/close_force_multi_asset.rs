@@ -0,0 +1,53 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/close_force_multi_asset/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn close_force_multi_asset() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let nia_asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let cfa_asset_id = issue_asset_cfa(node1_addr, None).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // channels of assets with different schemas, whose outputs can be swept together
+    let mut channels = vec![];
+    for asset_id in [&nia_asset_id, &cfa_asset_id] {
+        let channel = open_channel(
+            node1_addr,
+            &node2_pubkey,
+            Some(NODE2_PEER_PORT),
+            None,
+            Some(3000000),
+            Some(600),
+            Some(asset_id),
+        )
+        .await;
+        keysend(node1_addr, &node2_pubkey, None, Some(asset_id), Some(150)).await;
+        channels.push(channel);
+    }
+
+    // this sleep prevents non-deterministic issue where force close broadcasts an old commitment
+    // TX (one that still has an HTLC output)
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+    for channel in &channels {
+        close_channel(node1_addr, &channel.channel_id, &node2_pubkey, true).await;
+    }
+    // the NIA asset is issued with 1000 units, the CFA one with 2000
+    wait_for_balance(node1_addr, &nia_asset_id, 850).await;
+    wait_for_balance(node1_addr, &cfa_asset_id, 1850).await;
+    wait_for_balance(node2_addr, &nia_asset_id, 150).await;
+    wait_for_balance(node2_addr, &cfa_asset_id, 150).await;
+}
@@ -73,6 +73,9 @@ async fn concurrent_btc_payments() {
     let payload_1 = SendPaymentRequest {
         invoice: invoice_1.clone(),
         amt_msat: None,
+        asset_amount: None,
+        retry_attempts: None,
+        retry_timeout_secs: None,
     };
     let res_1 = reqwest::Client::new()
         .post(format!("http://{}/sendpayment", node3_addr))
@@ -86,6 +89,9 @@ async fn concurrent_btc_payments() {
     let payload_2 = SendPaymentRequest {
         invoice: invoice_2.clone(),
         amt_msat: None,
+        asset_amount: None,
+        retry_attempts: None,
+        retry_timeout_secs: None,
     };
     let res_2 = reqwest::Client::new()
         .post(format!("http://{}/sendpayment", node4_addr))
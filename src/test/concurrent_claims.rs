@@ -0,0 +1,75 @@
+use futures::future::join_all;
+
+use crate::routes::DEFAULT_FINAL_CLTV_EXPIRY_DELTA;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/concurrent_claims/";
+
+const NUM_PAYMENTS: usize = 20;
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn concurrent_claims() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, password) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // large enough for all the HTLCs to be in flight at the same time
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(1_000_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let mut invoices = vec![];
+    for _ in 0..NUM_PAYMENTS {
+        let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+        invoices.push(invoice);
+    }
+
+    println!("\nsending {NUM_PAYMENTS} payments at once");
+    let start_height = network_info(node2_addr).await.height;
+    let payments = join_all(
+        invoices
+            .into_iter()
+            .map(|invoice| _send_payment_raw(node1_addr, invoice)),
+    )
+    .await;
+    let payment_hashes: Vec<String> = payments
+        .into_iter()
+        .map(|p| p.payment_hash.unwrap())
+        .collect();
+
+    // all claims complete before any HTLC gets close to its expiry
+    for payment_hash in &payment_hashes {
+        _wait_for_ln_payment(node2_addr, payment_hash, HTLCStatus::Succeeded).await;
+    }
+    let claim_blocks = network_info(node2_addr).await.height - start_height;
+    assert!(claim_blocks < DEFAULT_FINAL_CLTV_EXPIRY_DELTA);
+    for payment_hash in &payment_hashes {
+        _wait_for_ln_payment(node1_addr, payment_hash, HTLCStatus::Succeeded).await;
+    }
+
+    // the settled payments are persisted
+    lock(node2_addr).await;
+    unlock(node2_addr, &password).await;
+    let payments = list_payments(node2_addr).await;
+    assert!(payment_hashes.iter().all(|h| payments
+        .iter()
+        .any(|p| &p.payment_hash == h && p.inbound && p.status == HTLCStatus::Succeeded)));
+}
@@ -0,0 +1,103 @@
+use crate::routes::{
+    AssetSchema, ExportContractRequest, ExportContractResponse, ImportContractRequest,
+    ImportContractResponse,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/contract_export_import/";
+
+async fn export_contract(node_address: SocketAddr, asset_id: &str) -> String {
+    println!("exporting contract {asset_id} from node {node_address}");
+    let payload = ExportContractRequest {
+        asset_id: asset_id.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/exportcontract", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ExportContractResponse>()
+        .await
+        .unwrap()
+        .contract
+}
+
+async fn import_contract_raw(node_address: SocketAddr, contract: &str) -> reqwest::Response {
+    println!("importing contract on node {node_address}");
+    let payload = ImportContractRequest {
+        contract: contract.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/importcontract", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn contract_export_import() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let contract = export_contract(node1_addr, &asset_id).await;
+
+    let res = import_contract_raw(node2_addr, &contract).await;
+    let ImportContractResponse {
+        asset_id: imported_asset_id,
+        schema,
+    } = _check_response_is_ok(res)
+        .await
+        .json::<ImportContractResponse>()
+        .await
+        .unwrap();
+    assert_eq!(imported_asset_id, asset_id);
+    assert!(matches!(schema, AssetSchema::Nia));
+    let assets = list_assets(node2_addr).await;
+    assert!(assets.nia.unwrap().iter().any(|a| a.asset_id == asset_id));
+
+    let res = import_contract_raw(node2_addr, &contract).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot import contract: the asset is already known",
+    )
+    .await;
+
+    let res = import_contract_raw(node2_addr, "not base64!").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid contract: not valid base64",
+    )
+    .await;
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/exportcontract", node2_addr))
+        .json(&ExportContractRequest {
+            asset_id: s!("rgb:2dkSTbr-jFhznbPmo-TQafzswCN-av4gTsJjX-ttx6CNou5-M98k8Zd"),
+        })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown RGB contract ID",
+    )
+    .await;
+}
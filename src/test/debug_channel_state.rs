@@ -0,0 +1,70 @@
+use crate::debug::ChannelStateResponse;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/debug_channel_state/";
+
+async fn channel_state_raw(node_address: SocketAddr, channel_id: &str) -> reqwest::Response {
+    println!("getting state of channel {channel_id} for node {node_address}");
+    reqwest::Client::new()
+        .get(format!(
+            "http://{}/debug/channel/{}",
+            node_address, channel_id
+        ))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn debug_channel_state() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let res = channel_state_raw(node1_addr, &channel.channel_id).await;
+    let state = _check_response_is_ok(res)
+        .await
+        .json::<ChannelStateResponse>()
+        .await
+        .unwrap();
+    assert_eq!(state.channel_id, channel.channel_id);
+    assert_eq!(state.peer_pubkey, node2_pubkey);
+    assert!(state.is_outbound);
+    assert!(state.is_usable);
+    assert_eq!(state.pending_inbound_htlcs, 0);
+    assert_eq!(state.pending_outbound_htlcs, 0);
+    let monitor = state.monitor.unwrap();
+    assert_eq!(monitor.pending_updates, 0);
+    assert!(monitor.latest_update_id > 0);
+    assert!(monitor.best_block_height > 0);
+    let rgb_info = state.rgb_info.unwrap();
+    assert_eq!(rgb_info["local_rgb_amount"], 600);
+    assert_eq!(rgb_info["remote_rgb_amount"], 0);
+    assert!(state.rgb_info_pending.is_some());
+
+    let res = channel_state_raw(node1_addr, "invalid").await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid channel ID").await;
+    let res = channel_state_raw(node1_addr, &"00".repeat(32)).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown channel ID").await;
+}
@@ -0,0 +1,136 @@
+use crate::debug::{
+    DecodeRgbInfoRequest, DecodeRgbInfoResponse, EncodeRgbInfoRequest, EncodeRgbInfoResponse,
+    RgbInfoKind,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/debug_rgb_info/";
+
+async fn decode_rgb_info(
+    node_address: SocketAddr,
+    kind: RgbInfoKind,
+    data: &str,
+) -> serde_json::Value {
+    println!("decoding RGB info {kind:?} for node {node_address}");
+    let payload = DecodeRgbInfoRequest {
+        kind,
+        data: data.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/debug/decodergbinfo", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<DecodeRgbInfoResponse>()
+        .await
+        .unwrap()
+        .info
+}
+
+async fn encode_rgb_info(
+    node_address: SocketAddr,
+    kind: RgbInfoKind,
+    info: serde_json::Value,
+) -> String {
+    println!("encoding RGB info {kind:?} for node {node_address}");
+    let payload = EncodeRgbInfoRequest { kind, info };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/debug/encodergbinfo", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EncodeRgbInfoResponse>()
+        .await
+        .unwrap()
+        .data
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn debug_rgb_info() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let payment_info = serde_json::json!({
+        "contract_id": asset_id,
+        "amount": 100,
+        "local_rgb_amount": 600,
+        "remote_rgb_amount": 0,
+        "swap_payment": false,
+        "inbound": true,
+    });
+    let data = encode_rgb_info(node1_addr, RgbInfoKind::PaymentInfo, payment_info).await;
+    let decoded = decode_rgb_info(node1_addr, RgbInfoKind::PaymentInfo, &data).await;
+    assert_eq!(decoded["amount"], 100);
+    assert_eq!(decoded["local_rgb_amount"], 600);
+    assert_eq!(decoded["inbound"], true);
+    // encoding is deterministic
+    let reencoded = encode_rgb_info(node1_addr, RgbInfoKind::PaymentInfo, decoded).await;
+    assert_eq!(reencoded, data);
+
+    let transfer_info = serde_json::json!({
+        "contract_id": asset_id,
+        "rgb_amount": 42,
+    });
+    let data = encode_rgb_info(node1_addr, RgbInfoKind::TransferInfo, transfer_info).await;
+    let decoded = decode_rgb_info(node1_addr, RgbInfoKind::TransferInfo, &data).await;
+    assert_eq!(decoded["rgb_amount"], 42);
+    let reencoded = encode_rgb_info(node1_addr, RgbInfoKind::TransferInfo, decoded).await;
+    assert_eq!(reencoded, data);
+
+    // payment info data cannot be decoded as transfer info
+    let data = encode_rgb_info(
+        node1_addr,
+        RgbInfoKind::PaymentInfo,
+        serde_json::json!({
+            "contract_id": asset_id,
+            "amount": 1,
+            "local_rgb_amount": 0,
+            "remote_rgb_amount": 0,
+            "swap_payment": false,
+            "inbound": false,
+        }),
+    )
+    .await;
+    let payload = DecodeRgbInfoRequest {
+        kind: RgbInfoKind::TransferInfo,
+        data,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/debug/decodergbinfo", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let payload = DecodeRgbInfoRequest {
+        kind: RgbInfoKind::PaymentInfo,
+        data: s!("zz"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/debug/decodergbinfo", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid RGB info: data is not a valid hex string",
+    )
+    .await;
+}
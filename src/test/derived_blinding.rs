@@ -0,0 +1,91 @@
+use lightning::rgb_utils::STATIC_BLINDING;
+use lightning::util::ser::Readable;
+
+use crate::disk::CHANNEL_TRANSFERS_FNAME;
+use crate::ldk::ChannelTransferMap;
+use crate::routes::ChannelTransferKind;
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/derived_blinding/";
+
+/// Blindings of the channel fundings persisted by the node, sorted
+fn funding_blindings(ldk_data_dir: &Path) -> Vec<Option<u64>> {
+    let data = std::fs::read(ldk_data_dir.join(CHANNEL_TRANSFERS_FNAME)).unwrap();
+    let channel_transfers = ChannelTransferMap::read(&mut &data[..]).unwrap();
+    let mut blindings: Vec<Option<u64>> = channel_transfers
+        .transfers
+        .values()
+        .filter(|t| t.kind == ChannelTransferKind::Funding)
+        .map(|t| t.blinding)
+        .collect();
+    blindings.sort();
+    blindings
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn derived_blinding() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let node1_args = || LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        derived_blinding: true,
+        ..Default::default()
+    };
+    let (node1_addr, node1_password) = start_node_with_args(node1_args(), false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    println!("\nfundings blinded with derived factors are accepted by the peer");
+    for asset_amount in [600, 300] {
+        open_channel(
+            node1_addr,
+            &node2_pubkey,
+            Some(NODE2_PEER_PORT),
+            None,
+            None,
+            Some(asset_amount),
+            Some(&asset_id),
+        )
+        .await;
+    }
+
+    println!("\neach funding has its own blinding, persisted with the channel transfer");
+    let ldk_data_dir = PathBuf::from(&test_dir_node1).join(LDK_DIR);
+    let blindings = funding_blindings(&ldk_data_dir);
+    assert_eq!(blindings.len(), 2);
+    assert!(blindings
+        .iter()
+        .all(|b| b.is_some() && *b != Some(STATIC_BLINDING)));
+    assert_ne!(blindings[0], blindings[1]);
+
+    println!("\nthe blindings are kept across a restart and the channels stay usable");
+    shutdown(&[node1_addr]).await;
+    let node1_addr = start_daemon_with_args(node1_args()).await;
+    unlock(node1_addr, &node1_password).await;
+    assert_eq!(funding_blindings(&ldk_data_dir), blindings);
+    wait_for_usable_channels(node1_addr, 2).await;
+
+    keysend(node1_addr, &node2_pubkey, None, Some(&asset_id), Some(100)).await;
+
+    let channels = list_channels(node2_addr).await;
+    assert_eq!(channels.len(), 2);
+    assert_eq!(
+        channels
+            .iter()
+            .map(|c| c.asset_remote_amount)
+            .sum::<Option<u64>>(),
+        Some(800)
+    );
+}
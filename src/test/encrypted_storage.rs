@@ -0,0 +1,180 @@
+use lightning::rgb_utils::get_rgb_channel_info_path;
+
+use crate::disk::{CHANNEL_PEER_DATA, INBOUND_PAYMENTS_NAMESPACE};
+use crate::encryption::{STORAGE_KEY_FNAME, STORAGE_MIGRATION_FNAME};
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/encrypted_storage/";
+
+async fn unlock_raw(node_address: SocketAddr, password: &str) -> reqwest::Response {
+    let payload = UnlockRequest {
+        password: password.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/unlock", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_channel_ready(node_address: SocketAddr, channel_id: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node_address).await;
+        let channel = channels
+            .iter()
+            .find(|c| c.channel_id == channel_id)
+            .unwrap();
+        if channel.ready {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("cannot find re-established channel")
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn encrypted_storage() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let node1_args = || LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        encrypt_storage: true,
+        ..Default::default()
+    };
+    let (node1_addr, node1_password) = start_node_with_args(node1_args(), false).await;
+    let (node2_addr, node2_password) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let ldk_data_dir = PathBuf::from(&test_dir_node1).join(LDK_DIR);
+    assert!(ldk_data_dir.join(STORAGE_KEY_FNAME).exists());
+    assert!(!ldk_data_dir.join(STORAGE_MIGRATION_FNAME).exists());
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+
+    println!("\nthe LDK data is encrypted");
+    let manager = std::fs::read(ldk_data_dir.join("manager")).unwrap();
+    assert!(manager.starts_with(b"RLNE"));
+    let peer_data = std::fs::read(ldk_data_dir.join(CHANNEL_PEER_DATA)).unwrap();
+    assert!(peer_data.starts_with(b"RLNE"));
+    let info_path = get_rgb_channel_info_path(&channel.channel_id, &ldk_data_dir, false);
+    assert!(!std::fs::read(&info_path).unwrap().starts_with(b"RLNE"));
+
+    println!("\nchange the password and unlock with the new one");
+    lock(node1_addr).await;
+    assert!(std::fs::read(&info_path).unwrap().starts_with(b"RLNE"));
+    let payment_info_paths: Vec<PathBuf> = std::fs::read_dir(&ldk_data_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| {
+            p.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with(&payment.payment_hash)
+        })
+        .collect();
+    assert!(!payment_info_paths.is_empty());
+    for path in payment_info_paths {
+        assert!(std::fs::read(path).unwrap().starts_with(b"RLNE"));
+    }
+    let new_password = format!("{node1_password}.new");
+    change_password(node1_addr, &node1_password, &new_password).await;
+    let res = unlock_raw(node1_addr, &node1_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+    )
+    .await;
+    unlock(node1_addr, &new_password).await;
+    wait_for_channel_ready(node1_addr, &channel.channel_id).await;
+    assert!(!std::fs::read(&info_path).unwrap().starts_with(b"RLNE"));
+    let payments = list_payments(node1_addr).await;
+    let restored_payment = payments
+        .iter()
+        .find(|p| p.payment_hash == payment.payment_hash)
+        .unwrap();
+    assert_eq!(restored_payment.status, HTLCStatus::Succeeded);
+
+    println!("\nrefuse to start without the storage encryption");
+    shutdown(&[node1_addr]).await;
+    let node1_addr = start_daemon_with_args(LdkUserInfo {
+        encrypt_storage: false,
+        ..node1_args()
+    })
+    .await;
+    let res = unlock_raw(node1_addr, &new_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to start LDK: the node data is encrypted, start with --encrypt-storage",
+    )
+    .await;
+    shutdown(&[node1_addr]).await;
+
+    println!("\nclose the channel after a last restart");
+    let node1_addr = start_daemon_with_args(node1_args()).await;
+    unlock(node1_addr, &new_password).await;
+    wait_for_channel_ready(node1_addr, &channel.channel_id).await;
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+
+    println!("\nenable the storage encryption on a node with unencrypted data");
+    shutdown(&[node2_addr]).await;
+    let node2_ldk_data_dir = PathBuf::from(&test_dir_node2).join(LDK_DIR);
+    let inbound_payment_path = node2_ldk_data_dir
+        .join(INBOUND_PAYMENTS_NAMESPACE)
+        .join(&payment.payment_hash);
+    assert!(!std::fs::read(&inbound_payment_path)
+        .unwrap()
+        .starts_with(b"RLNE"));
+    let node2_addr = start_daemon_with_args(LdkUserInfo {
+        storage_dir_path: test_dir_node2.clone().into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        encrypt_storage: true,
+        ..Default::default()
+    })
+    .await;
+    unlock(node2_addr, &node2_password).await;
+    assert!(std::fs::read(&inbound_payment_path)
+        .unwrap()
+        .starts_with(b"RLNE"));
+    assert!(node2_ldk_data_dir.join(STORAGE_KEY_FNAME).exists());
+    assert!(!node2_ldk_data_dir.join(STORAGE_MIGRATION_FNAME).exists());
+    let payments = list_payments(node2_addr).await;
+    assert!(payments
+        .iter()
+        .any(|p| p.payment_hash == payment.payment_hash));
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+}
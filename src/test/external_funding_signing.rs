@@ -0,0 +1,226 @@
+use bitcoin::psbt::Psbt;
+use rgb_lib::utils::get_account_xpub;
+use rgb_lib::wallet::{DatabaseType, Wallet as RgbLibWallet, WalletData};
+use rgb_lib::BitcoinNetwork;
+
+use crate::routes::{
+    FundingPsbt, ListFundingPsbtsResponse, SubmitFundingPsbtRequest, SubmitFundingPsbtResponse,
+};
+use crate::utils::check_password_validity;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/external_funding_signing/";
+
+async fn list_funding_psbts(node_address: SocketAddr) -> Vec<FundingPsbt> {
+    println!("listing funding PSBTs for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listfundingpsbts", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListFundingPsbtsResponse>()
+        .await
+        .unwrap()
+        .psbts
+}
+
+async fn submit_funding_psbt_raw(
+    node_address: SocketAddr,
+    temporary_channel_id: &str,
+    signed_psbt: &str,
+) -> reqwest::Response {
+    println!("submitting funding PSBT of channel {temporary_channel_id} for node {node_address}");
+    let payload = SubmitFundingPsbtRequest {
+        temporary_channel_id: temporary_channel_id.to_string(),
+        signed_psbt: signed_psbt.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/submitfundingpsbt", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_funding_psbt(node_address: SocketAddr) -> FundingPsbt {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(psbt) = list_funding_psbts(node_address).await.pop() {
+            return psbt;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("funding PSBT not available")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn open_externally_signed_channel(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    dest_peer_port: u16,
+) -> OpenChannelResponse {
+    stop_mining();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", dest_peer_pubkey, dest_peer_port),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: true,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+}
+
+/// A wallet with the keys of the given node but its own data, standing for an external signer
+fn external_signer(node_test_dir: &str, password: &str) -> RgbLibWallet {
+    let mnemonic = check_password_validity(password, Path::new(node_test_dir))
+        .unwrap()
+        .to_string();
+    let data_dir = PathBuf::from(TEST_DIR_BASE).join("signer");
+    if data_dir.is_dir() {
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+    std::fs::create_dir_all(&data_dir).unwrap();
+    RgbLibWallet::new(WalletData {
+        data_dir: data_dir.to_string_lossy().to_string(),
+        bitcoin_network: BitcoinNetwork::Regtest,
+        database_type: DatabaseType::Sqlite,
+        max_allocations_per_utxo: 1,
+        pubkey: get_account_xpub(BitcoinNetwork::Regtest, &mnemonic)
+            .unwrap()
+            .to_string(),
+        mnemonic: Some(mnemonic),
+        vanilla_keychain: None,
+    })
+    .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn external_funding_signing() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    println!("\nthe funding PSBT waits for the external signatures");
+    let OpenChannelResponse {
+        temporary_channel_id,
+        ..
+    } = open_externally_signed_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT).await;
+    let funding_psbt = wait_for_funding_psbt(node1_addr).await;
+    assert_eq!(funding_psbt.temporary_channel_id, temporary_channel_id);
+    assert_eq!(funding_psbt.peer_pubkey, node2_pubkey);
+    assert_eq!(funding_psbt.asset_id, None);
+    assert!(list_channels(node1_addr)
+        .await
+        .iter()
+        .all(|c| c.funding_txid.is_none()));
+
+    println!("\nrefuse PSBTs that are not signed or don't match");
+    let res = submit_funding_psbt_raw(node1_addr, &temporary_channel_id, &funding_psbt.psbt).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid funding PSBT: input 0 is not signed",
+    )
+    .await;
+    let res = submit_funding_psbt_raw(node1_addr, &temporary_channel_id, "not a PSBT").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let signer = external_signer(&test_dir_node1, &node1_password);
+    let signed_psbt = signer.sign_psbt(funding_psbt.psbt.clone(), None).unwrap();
+    let res = submit_funding_psbt_raw(node1_addr, &"ab".repeat(32), &signed_psbt).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown temporary channel ID",
+    )
+    .await;
+    let mut other_psbt = Psbt::from_str(&signed_psbt).unwrap();
+    other_psbt.unsigned_tx.output[0].value -= 1;
+    let res =
+        submit_funding_psbt_raw(node1_addr, &temporary_channel_id, &other_psbt.to_string()).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid funding PSBT: it doesn't match the funding transaction",
+    )
+    .await;
+
+    println!("\nfund the channel with the externally signed PSBT");
+    let res = submit_funding_psbt_raw(node1_addr, &temporary_channel_id, &signed_psbt).await;
+    let SubmitFundingPsbtResponse { funding_txid } = _check_response_is_ok(res)
+        .await
+        .json::<SubmitFundingPsbtResponse>()
+        .await
+        .unwrap();
+    assert!(list_funding_psbts(node1_addr).await.is_empty());
+    let t_0 = OffsetDateTime::now_utc();
+    while _get_txout(&funding_txid).is_empty() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    mine_n_blocks(true, 6);
+    let t_0 = OffsetDateTime::now_utc();
+    let channel = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(channel) = channels
+            .into_iter()
+            .find(|c| c.funding_txid.as_ref() == Some(&funding_txid) && c.ready)
+        {
+            break channel;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel is taking too long to be ready")
+        }
+    };
+
+    println!("\na channel closed before being signed drops its PSBT");
+    let OpenChannelResponse {
+        temporary_channel_id,
+        ..
+    } = open_externally_signed_channel(node1_addr, &node2_pubkey, NODE2_PEER_PORT).await;
+    wait_for_funding_psbt(node1_addr).await;
+    close_channel(node1_addr, &temporary_channel_id, &node2_pubkey, true).await;
+    assert!(list_funding_psbts(node1_addr).await.is_empty());
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+}
@@ -0,0 +1,89 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/failover/";
+
+async fn unlock_raw(node_address: SocketAddr, password: &str) -> reqwest::Response {
+    let payload = UnlockRequest {
+        password: password.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/unlock", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_unlocked(node_address: SocketAddr) -> String {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let res = reqwest::Client::new()
+            .get(format!("http://{}/nodeinfo", node_address))
+            .send()
+            .await
+            .unwrap();
+        if res.status() == reqwest::StatusCode::OK {
+            return res.json::<NodeInfoResponse>().await.unwrap().pubkey;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("standby node has not taken over")
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn failover() {
+    initialize();
+
+    // both instances share the same storage directory
+    let test_dir_node = format!("{TEST_DIR_BASE}node");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        failover: true,
+        ..Default::default()
+    };
+    let (active_addr, password) = start_node_with_args(args, false).await;
+    let pubkey = node_info(active_addr).await.pubkey;
+
+    let standby_addr = start_daemon_with_args(LdkUserInfo {
+        storage_dir_path: test_dir_node.into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        failover: true,
+        ..Default::default()
+    })
+    .await;
+
+    println!("\nunlocking the standby instance while the lease is held");
+    let expected_error =
+        "Node is on standby: it will unlock once the lease held by another instance is free";
+    let res = unlock_raw(standby_addr, &password).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, expected_error).await;
+    tokio::time::sleep(std::time::Duration::from_secs(15)).await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/nodeinfo", standby_addr))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Node is locked (hint: call unlock)",
+    )
+    .await;
+
+    println!("\nlocking the active instance, releasing the lease");
+    lock(active_addr).await;
+    assert_eq!(wait_for_unlocked(standby_addr).await, pubkey);
+
+    // the former active instance is now the standby one
+    let res = unlock_raw(active_addr, &password).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, expected_error).await;
+
+    println!("\nshutting down the new active instance");
+    shutdown(&[standby_addr]).await;
+    assert_eq!(wait_for_unlocked(active_addr).await, pubkey);
+}
@@ -0,0 +1,248 @@
+use crate::routes::{
+    CancelFeeOrderRequest, ChannelRequest, ChannelRequestStatus, CreateFeeOrderRequest,
+    CreateFeeOrderResponse, ExecuteFeeOrderRequest, FeeOrder, FeeOrderStatus,
+    ListChannelRequestsResponse, ListFeeOrdersResponse, RequestChannelRequest,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/fee_orders/";
+
+async fn request_channel(node_address: SocketAddr, peer_pubkey: &str) {
+    println!("requesting a channel to peer {peer_pubkey} from node {node_address}");
+    let payload = RequestChannelRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        capacity_sat: 100000,
+        push_msat: 0,
+        asset_id: None,
+        asset_amount: None,
+        fee_sat: 1000,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/requestchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+}
+
+async fn wait_for_pending_request(node_address: SocketAddr, peer_pubkey: &str) -> ChannelRequest {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .get(format!("http://{}/channelrequests", node_address))
+            .send()
+            .await
+            .unwrap();
+        if let Some(request) = _check_response_is_ok(res)
+            .await
+            .json::<ListChannelRequestsResponse>()
+            .await
+            .unwrap()
+            .requests
+            .into_iter()
+            .find(|r| r.peer_pubkey == peer_pubkey && r.status == ChannelRequestStatus::Pending)
+        {
+            return request;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel request from {peer_pubkey} has not been received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn create_fee_order(node_address: SocketAddr, request_id: &str) -> CreateFeeOrderResponse {
+    println!("creating fee order for channel request {request_id} on node {node_address}");
+    let payload = CreateFeeOrderRequest {
+        request_id: request_id.to_string(),
+        expiry_sec: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/createfeeorder", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<CreateFeeOrderResponse>()
+        .await
+        .unwrap()
+}
+
+async fn execute_fee_order_raw(node_address: SocketAddr, order_id: &str) -> reqwest::Response {
+    println!("executing fee order {order_id} on node {node_address}");
+    let payload = ExecuteFeeOrderRequest {
+        order_id: order_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/executefeeorder", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn cancel_fee_order_raw(node_address: SocketAddr, order_id: &str) -> reqwest::Response {
+    println!("cancelling fee order {order_id} on node {node_address}");
+    let payload = CancelFeeOrderRequest {
+        order_id: order_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/cancelfeeorder", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn fee_order(node_address: SocketAddr, order_id: &str) -> FeeOrder {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/feeorders", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListFeeOrdersResponse>()
+        .await
+        .unwrap()
+        .orders
+        .into_iter()
+        .find(|o| o.order_id == order_id)
+        .unwrap()
+}
+
+async fn wait_for_fee_order_status(
+    node_address: SocketAddr,
+    order_id: &str,
+    expected_status: FeeOrderStatus,
+) -> FeeOrder {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let order = fee_order(node_address, order_id).await;
+        if order.status == expected_status {
+            return order;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!(
+                "fee order {order_id} is {:?}, not {expected_status:?}",
+                order.status
+            )
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn fee_orders() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // the client pays the fee over an existing channel
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    println!("\nrefunding a cancelled fee order");
+    request_channel(node2_addr, &node1_pubkey).await;
+    let request = wait_for_pending_request(node1_addr, &node2_pubkey).await;
+    let CreateFeeOrderResponse { order_id, invoice } =
+        create_fee_order(node1_addr, &request.request_id).await;
+    let res = execute_fee_order_raw(node1_addr, &order_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot execute fee order: the fee has not been paid yet",
+    )
+    .await;
+    _send_payment_raw(node2_addr, invoice).await;
+    let order = wait_for_fee_order_status(node1_addr, &order_id, FeeOrderStatus::Paid).await;
+    assert_eq!(order.fee_msat, 1000000);
+    let res = cancel_fee_order_raw(node1_addr, &order_id).await;
+    _check_response_is_ok(res).await;
+    _wait_for_ln_payment(node2_addr, &order.payment_hash, HTLCStatus::Failed).await;
+    assert_eq!(
+        fee_order(node1_addr, &order_id).await.status,
+        FeeOrderStatus::Refunded
+    );
+    let res = cancel_fee_order_raw(node1_addr, &order_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot cancel fee order: order already closed",
+    )
+    .await;
+
+    println!("\nopening a channel with a prepaid fee");
+    let CreateFeeOrderResponse { order_id, invoice } =
+        create_fee_order(node1_addr, &request.request_id).await;
+    _send_payment_raw(node2_addr, invoice).await;
+    wait_for_fee_order_status(node1_addr, &order_id, FeeOrderStatus::Paid).await;
+    let res = execute_fee_order_raw(node1_addr, &order_id).await;
+    let OpenChannelResponse {
+        temporary_channel_id,
+        ..
+    } = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap();
+    let order = fee_order(node1_addr, &order_id).await;
+    assert_eq!(order.status, FeeOrderStatus::ChannelOpening);
+    assert_eq!(order.temporary_channel_id, Some(temporary_channel_id));
+    let res = execute_fee_order_raw(node1_addr, &order_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot execute fee order: order already executed",
+    )
+    .await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels
+            .iter()
+            .find(|c| !c.ready)
+            .and_then(|c| c.funding_txid.clone())
+        {
+            if !_get_txout(&funding_txid).is_empty() {
+                mine_n_blocks(true, 6);
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    wait_for_usable_channels(node1_addr, 2).await;
+    wait_for_fee_order_status(node1_addr, &order_id, FeeOrderStatus::Completed).await;
+    _wait_for_ln_payment(node2_addr, &order.payment_hash, HTLCStatus::Succeeded).await;
+    _wait_for_ln_payment(node1_addr, &order.payment_hash, HTLCStatus::Succeeded).await;
+
+    let res = cancel_fee_order_raw(node1_addr, "unknown").await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown fee order").await;
+}
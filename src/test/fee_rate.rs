@@ -0,0 +1,109 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/fee_rate/";
+
+const FEE_RATE_ERR: &str = "Invalid fee rate: fee rate must be between 1 and 1000 sat/vB";
+
+async fn send_btc_raw(
+    node_address: SocketAddr,
+    address: &str,
+    fee_rate: Option<f32>,
+) -> reqwest::Response {
+    println!("sending on-chain BTC from node {node_address} with fee rate {fee_rate:?}");
+    let payload = SendBtcRequest {
+        amount: 1000,
+        address: address.to_string(),
+        fee_rate,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/sendbtc", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn open_channel_raw(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    fee_rate: Option<f32>,
+) -> reqwest::Response {
+    println!("opening channel from node {node_address} with fee rate {fee_rate:?}");
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", dest_peer_pubkey, NODE2_PEER_PORT),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate,
+        external_signing: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn fee_rate() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node2_address = address(node2_addr).await;
+
+    // sends use the node fee rate unless requested otherwise
+    let res = send_btc_raw(node1_addr, &node2_address, None).await;
+    _check_response_is_ok(res).await;
+    let res = send_btc_raw(node1_addr, &node2_address, Some(2.5)).await;
+    _check_response_is_ok(res).await;
+    mine(false);
+
+    for fee_rate in [0.5, 1001.0] {
+        let res = send_btc_raw(node1_addr, &node2_address, Some(fee_rate)).await;
+        check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, FEE_RATE_ERR).await;
+    }
+
+    let res = open_channel_raw(node1_addr, &node2_pubkey, Some(0.5)).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, FEE_RATE_ERR).await;
+
+    let res = open_channel_raw(node1_addr, &node2_pubkey, Some(3.0)).await;
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels.first().and_then(|c| c.funding_txid.clone()) {
+            if !_get_txout(&funding_txid).is_empty() {
+                mine_n_blocks(true, 6);
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    wait_for_usable_channels(node1_addr, 1).await;
+}
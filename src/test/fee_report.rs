@@ -0,0 +1,96 @@
+use crate::routes::FeeReportResponse;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/fee_report/";
+
+async fn fee_report(node_address: SocketAddr) -> FeeReportResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/feereport", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<FeeReportResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn fee_report_forward() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, node2_password) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+    open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    let report = fee_report(node2_addr).await;
+    assert!(report.channels.is_empty());
+    assert_eq!(report.total_fee_earned_msat, 0);
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node1_addr, Some(4000000), None, None, 900).await;
+    send_payment(node3_addr, invoice).await;
+
+    // the forward is accounted to the outbound channel, towards node1
+    let outbound_channel = list_channels(node1_addr).await.pop().unwrap();
+    let report = fee_report(node2_addr).await;
+    assert_eq!(report.channels.len(), 1);
+    let channel_report = &report.channels[0];
+    assert_eq!(channel_report.channel_id, outbound_channel.channel_id);
+    assert_eq!(channel_report.peer_pubkey, Some(node1_pubkey));
+    assert_eq!(channel_report.forwards, 1);
+    assert!(channel_report.fee_earned_msat > 0);
+    assert_eq!(channel_report.skimmed_forwards, 0);
+    assert_eq!(channel_report.skimmed_fee_msat, 0);
+    assert_eq!(report.total_fee_earned_msat, channel_report.fee_earned_msat);
+    assert_eq!(report.total_skimmed_fee_msat, 0);
+    assert_eq!(report.total_counterparty_skimmed_fee_msat, 0);
+
+    // the fee report is persisted
+    lock(node2_addr).await;
+    unlock(node2_addr, &node2_password).await;
+    let persisted_report = fee_report(node2_addr).await;
+    assert_eq!(persisted_report.channels.len(), 1);
+    assert_eq!(
+        persisted_report.total_fee_earned_msat,
+        report.total_fee_earned_msat
+    );
+
+    // nodes not forwarding have nothing to report
+    assert!(fee_report(node1_addr).await.channels.is_empty());
+    assert!(fee_report(node3_addr).await.channels.is_empty());
+}
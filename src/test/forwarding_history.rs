@@ -0,0 +1,119 @@
+use crate::routes::ForwardingHistoryResponse;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/forwarding_history/";
+
+async fn forwarding_history(node_address: SocketAddr) -> ForwardingHistoryResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/forwardinghistory", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ForwardingHistoryResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn forwarding_history_per_channel() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, node2_password) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let outbound_channel = open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+    let inbound_channel = open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    let history = forwarding_history(node2_addr).await;
+    assert!(history.forwards.is_empty());
+    assert!(history.channels.is_empty());
+
+    for _ in 0..2 {
+        let LNInvoiceResponse { invoice } =
+            ln_invoice(node1_addr, Some(3000000), None, None, 900).await;
+        send_payment(node3_addr, invoice).await;
+    }
+
+    let history = forwarding_history(node2_addr).await;
+    assert_eq!(history.forwards.len(), 2);
+    for forward in &history.forwards {
+        assert_eq!(forward.prev_channel_id, inbound_channel.channel_id);
+        assert_eq!(forward.next_channel_id, outbound_channel.channel_id);
+        assert_eq!(forward.outbound_amount_msat, Some(3000000));
+        assert!(forward.fee_earned_msat.unwrap() > 0);
+        assert_eq!(
+            forward.inbound_amount_msat,
+            Some(3000000 + forward.fee_earned_msat.unwrap())
+        );
+        assert_eq!(forward.inbound_asset_id, None);
+        assert_eq!(forward.outbound_asset_id, None);
+    }
+    assert_eq!(history.channels.len(), 2);
+    let inbound_stats = history
+        .channels
+        .iter()
+        .find(|c| c.channel_id == inbound_channel.channel_id)
+        .unwrap();
+    assert_eq!(inbound_stats.inbound_forwards, 2);
+    assert_eq!(inbound_stats.outbound_forwards, 0);
+    assert_eq!(inbound_stats.fee_earned_msat, 0);
+    let outbound_stats = history
+        .channels
+        .iter()
+        .find(|c| c.channel_id == outbound_channel.channel_id)
+        .unwrap();
+    assert_eq!(outbound_stats.inbound_forwards, 0);
+    assert_eq!(outbound_stats.outbound_forwards, 2);
+    assert_eq!(outbound_stats.outbound_amount_msat, 6000000);
+    assert_eq!(
+        inbound_stats.inbound_amount_msat,
+        outbound_stats.outbound_amount_msat + outbound_stats.fee_earned_msat
+    );
+
+    // the history is persisted
+    lock(node2_addr).await;
+    unlock(node2_addr, &node2_password).await;
+    let persisted_history = forwarding_history(node2_addr).await;
+    assert_eq!(persisted_history.forwards.len(), 2);
+    assert_eq!(
+        persisted_history.forwards[0].payment_hash,
+        history.forwards[0].payment_hash
+    );
+
+    // the payer and the payee have forwarded nothing
+    assert!(forwarding_history(node1_addr).await.forwards.is_empty());
+    assert!(forwarding_history(node3_addr).await.forwards.is_empty());
+}
@@ -0,0 +1,135 @@
+use bitcoin::hashes::{sha256, Hash};
+
+use crate::utils::hex_str;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/hold_invoice/";
+
+fn preimage_and_hash(seed: u8) -> (String, String) {
+    let preimage = [seed; 32];
+    let payment_hash = sha256::Hash::hash(&preimage).to_byte_array();
+    (hex_str(&preimage), hex_str(&payment_hash))
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn hold_invoice() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // a held payment waits for the invoice to be settled
+    let (payment_preimage, payment_hash) = preimage_and_hash(1);
+    let LNInvoiceResponse { invoice } = hold_ln_invoice(node2_addr, 3000000, &payment_hash).await;
+    _send_payment_raw(node1_addr, invoice.clone()).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Claimable).await;
+    assert!(matches!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Claimable
+    ));
+    assert!(
+        check_payment_status(node1_addr, &payment_hash, HTLCStatus::Pending)
+            .await
+            .is_some()
+    );
+
+    // a wrong preimage cannot settle the invoice
+    let (wrong_preimage, _) = preimage_and_hash(2);
+    let payload = SettleInvoiceRequest {
+        payment_preimage: wrong_preimage,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/settleinvoice", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown LN invoice").await;
+
+    settle_invoice(node2_addr, &payment_preimage).await;
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    // a settled invoice cannot be cancelled
+    let payload = CancelInvoiceRequest {
+        payment_hash: payment_hash.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/cancelinvoice", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot cancel invoice: invoice already settled",
+    )
+    .await;
+
+    // a cancelled invoice fails the held payment back
+    let (_, payment_hash) = preimage_and_hash(3);
+    let LNInvoiceResponse { invoice } = hold_ln_invoice(node2_addr, 3000000, &payment_hash).await;
+    _send_payment_raw(node1_addr, invoice.clone()).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Claimable).await;
+    cancel_invoice(node2_addr, &payment_hash).await;
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert!(matches!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Failed
+    ));
+
+    // a payment hash cannot be reused for another hold invoice
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        payment_hash: Some(payment_hash),
+        description_hash: None,
+        fallback_address: None,
+        route_hint_channel_ids: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node2_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Payment hash already used",
+    )
+    .await;
+
+    // a held payment not settled before its claim deadline expires
+    let (_, payment_hash) = preimage_and_hash(4);
+    let LNInvoiceResponse { invoice } = hold_ln_invoice(node2_addr, 3000000, &payment_hash).await;
+    _send_payment_raw(node1_addr, invoice.clone()).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Claimable).await;
+    mine_n_blocks(false, 200);
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Expired).await;
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+}
@@ -0,0 +1,97 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/htlc_limits/";
+
+async fn open_channel_with_limits_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    max_htlc_value_in_flight_percent: Option<u8>,
+    htlc_minimum_msat: Option<u64>,
+) -> reqwest::Response {
+    println!("opening channel with custom HTLC limits on node {node_address}");
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", peer_pubkey, NODE2_PEER_PORT),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent,
+        max_accepted_htlcs: Some(30),
+        htlc_minimum_msat,
+        channel_reserve_proportional_millionths: Some(20_000),
+        fee_rate: None,
+        external_signing: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn htlc_limits() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let res = open_channel_with_limits_raw(node1_addr, &node2_pubkey, Some(0), None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid HTLC limits: max HTLC value in flight must be between 1 and 100 percent",
+    )
+    .await;
+
+    let res = open_channel_with_limits_raw(node1_addr, &node2_pubkey, None, Some(0)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid HTLC limits: HTLC minimum must be positive",
+    )
+    .await;
+
+    println!("\nopening channel with an HTLC minimum of 5000000 msat");
+    stop_mining();
+    let res =
+        open_channel_with_limits_raw(node1_addr, &node2_pubkey, Some(50), Some(5_000_000)).await;
+    _check_response_is_ok(res).await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels.first().and_then(|c| c.funding_txid.clone()) {
+            if !_get_txout(&funding_txid).is_empty() {
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    mine_n_blocks(true, 6);
+    wait_for_usable_channels(node2_addr, 1).await;
+
+    // the peer cannot send HTLCs below our minimum
+    let channels = list_channels(node2_addr).await;
+    assert_eq!(channels[0].next_outbound_htlc_minimum_msat, 5_000_000);
+}
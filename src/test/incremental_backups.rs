@@ -0,0 +1,158 @@
+use super::*;
+use regex::RegexSet;
+
+const TEST_DIR_BASE: &str = "tmp/incremental_backups/";
+
+async fn backup_raw(
+    node_address: SocketAddr,
+    backup_path: &str,
+    password: &str,
+    incremental: bool,
+) -> reqwest::Response {
+    println!(
+        "performing backup (incremental: {incremental}) for node {node_address} on {backup_path}"
+    );
+    let payload = BackupRequest {
+        backup_path: Some(backup_path.to_string()),
+        password: password.to_string(),
+        incremental,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/backup", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn restore_raw(
+    node_address: SocketAddr,
+    backup_path: &str,
+    incremental_backup_paths: &[&str],
+    password: &str,
+) -> reqwest::Response {
+    println!("restoring backup for node {node_address} from {backup_path}");
+    let payload = RestoreRequest {
+        backup_path: Some(backup_path.to_string()),
+        backup: None,
+        password: password.to_string(),
+        incremental_backup_paths: incremental_backup_paths
+            .iter()
+            .map(|p| p.to_string())
+            .collect(),
+        incremental_backups: vec![],
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/restore", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn incremental_backups() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let backup_paths: Vec<String> = ["full", "incremental1", "incremental2"]
+        .iter()
+        .map(|n| format!("{TEST_DIR_BASE}node1_backup_{n}"))
+        .collect();
+    for backup_path in &backup_paths {
+        if Path::new(backup_path).exists() {
+            std::fs::remove_file(backup_path).unwrap();
+        }
+    }
+
+    lock(node1_addr).await;
+
+    // an incremental backup needs a previous backup
+    let res = backup_raw(node1_addr, &backup_paths[1], &node1_password, true).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot create incremental backup: no previous backup found",
+    )
+    .await;
+
+    let res = backup_raw(node1_addr, &backup_paths[0], &node1_password, false).await;
+    _check_response_is_ok(res).await;
+
+    // each incremental backup follows some RGB activity
+    for backup_path in &backup_paths[1..] {
+        unlock(node1_addr, &node1_password).await;
+        issue_asset_nia(node1_addr).await;
+        lock(node1_addr).await;
+        let res = backup_raw(node1_addr, backup_path, &node1_password, true).await;
+        _check_response_is_ok(res).await;
+    }
+    assert!(
+        std::fs::metadata(&backup_paths[1]).unwrap().len()
+            < std::fs::metadata(&backup_paths[0]).unwrap().len()
+    );
+
+    shutdown(&[node1_addr]).await;
+
+    let old_test_dir_node1 = format!("{test_dir_node1}_old");
+    let old_test_dir_node1_path = Path::new(&old_test_dir_node1);
+    if old_test_dir_node1_path.exists() {
+        std::fs::remove_dir_all(&old_test_dir_node1).unwrap();
+    }
+    std::fs::rename(test_dir_node1.clone(), old_test_dir_node1.clone()).unwrap();
+
+    let node1_addr = start_daemon(&test_dir_node1, NODE1_PEER_PORT).await;
+
+    // the chain is validated before restoring anything
+    let res = restore_raw(
+        node1_addr,
+        &backup_paths[0],
+        &[&backup_paths[2], &backup_paths[1]],
+        &node1_password,
+    )
+    .await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let api_error_response = res.json::<APIErrorResponse>().await.unwrap();
+    assert!(api_error_response
+        .error
+        .ends_with("does not follow the previous one"));
+    let res = restore_raw(
+        node1_addr,
+        &backup_paths[1],
+        &[&backup_paths[2]],
+        &node1_password,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid backup chain: the first backup must be a full backup",
+    )
+    .await;
+
+    let res = restore_raw(
+        node1_addr,
+        &backup_paths[0],
+        &[&backup_paths[1], &backup_paths[2]],
+        &node1_password,
+    )
+    .await;
+    _check_response_is_ok(res).await;
+
+    let ignores = RegexSet::new([r"log*"]).unwrap();
+    let cmp = dircmp::Comparison::new(ignores);
+    let diff = cmp
+        .compare(old_test_dir_node1_path, Path::new(&test_dir_node1))
+        .unwrap();
+    assert!(diff.is_empty());
+
+    unlock(node1_addr, &node1_password).await;
+
+    assert_eq!(list_assets(node1_addr).await.nia.unwrap().len(), 2);
+}
@@ -0,0 +1,163 @@
+use crate::routes::{AssetSchema, ConsignmentValidation, InspectConsignmentResponse};
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/inspect_consignment/";
+
+async fn inspect_consignment_raw(
+    node_address: SocketAddr,
+    form: reqwest::multipart::Form,
+) -> reqwest::Response {
+    println!("inspecting consignment on node {node_address}");
+    reqwest::Client::new()
+        .post(format!("http://{}/inspectconsignment", node_address))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn inspect_consignment(
+    node_address: SocketAddr,
+    form: reqwest::multipart::Form,
+) -> InspectConsignmentResponse {
+    let res = inspect_consignment_raw(node_address, form).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<InspectConsignmentResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn inspect_consignment_success() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let funding_txid = list_channels(node1_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap()
+        .funding_txid
+        .unwrap();
+
+    // the opener sent the funding consignment
+    let form = reqwest::multipart::Form::new().text("txid", funding_txid.clone());
+    let consignments = inspect_consignment(node1_addr, form).await.consignments;
+    assert_eq!(consignments.len(), 1);
+    let consignment = &consignments[0];
+    assert_eq!(consignment.asset_id, asset_id);
+    assert!(matches!(consignment.schema, AssetSchema::Nia));
+    assert_eq!(consignment.txid, Some(funding_txid.clone()));
+    assert!(consignment.recipient_id.is_some());
+    assert!(consignment
+        .transitions
+        .iter()
+        .any(|t| t.amounts.contains(&600)));
+    assert_eq!(consignment.validation, ConsignmentValidation::Valid);
+
+    // the acceptor validated it before accepting the channel
+    let form = reqwest::multipart::Form::new().text("txid", funding_txid.clone());
+    let consignments = inspect_consignment(node2_addr, form).await.consignments;
+    assert_eq!(consignments.len(), 1);
+    assert_eq!(consignments[0].asset_id, asset_id);
+    assert_eq!(consignments[0].recipient_id, None);
+    assert_eq!(consignments[0].validation, ConsignmentValidation::Valid);
+
+    // uploaded files are only parsed
+    let file_bytes = std::fs::read(
+        PathBuf::from(&test_dir_node2)
+            .join(LDK_DIR)
+            .join(format!("consignment_{funding_txid}")),
+    )
+    .unwrap();
+    let form =
+        reqwest::multipart::Form::new().part("file", reqwest::multipart::Part::bytes(file_bytes));
+    let consignments = inspect_consignment(node1_addr, form).await.consignments;
+    assert_eq!(consignments.len(), 1);
+    assert_eq!(consignments[0].asset_id, asset_id);
+    assert_eq!(consignments[0].txid, None);
+    assert!(consignments[0]
+        .transitions
+        .iter()
+        .any(|t| t.amounts.contains(&600)));
+    assert_eq!(
+        consignments[0].validation,
+        ConsignmentValidation::Unvalidated
+    );
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn inspect_consignment_fail() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}fail_node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let res = inspect_consignment_raw(node1_addr, reqwest::multipart::Form::new()).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid consignment: exactly one of file and txid must be given",
+    )
+    .await;
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(vec![1, 2, 3]))
+        .text("txid", "invalid");
+    let res = inspect_consignment_raw(node1_addr, form).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid consignment: exactly one of file and txid must be given",
+    )
+    .await;
+
+    let form = reqwest::multipart::Form::new().text("txid", "invalid");
+    let res = inspect_consignment_raw(node1_addr, form).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid txid").await;
+
+    let form = reqwest::multipart::Form::new().text(
+        "txid",
+        "efed66f5309396ff43c8a09941c8103d9d5bbffd473ad9f13013ac89fb6b4671",
+    );
+    let res = inspect_consignment_raw(node1_addr, form).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown consignment").await;
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(vec![1, 2, 3]));
+    let res = inspect_consignment_raw(node1_addr, form).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid consignment: cannot parse the file",
+    )
+    .await;
+}
@@ -1,3 +1,6 @@
+use bitcoin::hashes::{sha256, Hash};
+use lightning_invoice::Bolt11InvoiceDescription;
+
 use super::*;
 
 const TEST_DIR_BASE: &str = "tmp/invoice/";
@@ -21,6 +24,10 @@ async fn invoice() {
         expiry_sec: 900,
         asset_id: Some(asset_id.clone()),
         asset_amount: Some(1),
+        payment_hash: None,
+        description_hash: None,
+        fallback_address: None,
+        route_hint_channel_ids: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/lninvoice", node1_addr))
@@ -36,6 +43,10 @@ async fn invoice() {
         expiry_sec: 900,
         asset_id: Some(asset_id.clone()),
         asset_amount: Some(1),
+        payment_hash: None,
+        description_hash: None,
+        fallback_address: None,
+        route_hint_channel_ids: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/lninvoice", node1_addr))
@@ -51,6 +62,10 @@ async fn invoice() {
         expiry_sec: 900,
         asset_id: None,
         asset_amount: None,
+        payment_hash: None,
+        description_hash: None,
+        fallback_address: None,
+        route_hint_channel_ids: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/lninvoice", node1_addr))
@@ -61,4 +76,82 @@ async fn invoice() {
         .json::<LNInvoiceResponse>()
         .await;
     assert!(res.is_ok());
+
+    // an invoice can commit to a description hash and include an on-chain fallback address
+    let description_hash = sha256::Hash::hash(b"invoice description");
+    let fallback_address = address(node1_addr).await;
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        payment_hash: None,
+        description_hash: Some(description_hash.to_string()),
+        fallback_address: Some(fallback_address.clone()),
+        route_hint_channel_ids: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let LNInvoiceResponse { invoice } = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap();
+    let bolt11_invoice = Bolt11Invoice::from_str(&invoice).unwrap();
+    match bolt11_invoice.description() {
+        Bolt11InvoiceDescription::Hash(hash) => assert_eq!(hash.0, description_hash),
+        Bolt11InvoiceDescription::Direct(_) => panic!("invoice should have a description hash"),
+    }
+    let fallback_addresses = bolt11_invoice.fallback_addresses();
+    assert_eq!(fallback_addresses.len(), 1);
+    assert_eq!(fallback_addresses[0].to_string(), fallback_address);
+    assert!(bolt11_invoice.check_signature().is_ok());
+
+    let payload = LNInvoiceRequest {
+        description_hash: Some(s!("not a hash")),
+        fallback_address: None,
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid invoice: description_hash must be a hex-encoded SHA256 hash",
+    )
+    .await;
+
+    // expired invoices are marked as such in the payments list
+    let LNInvoiceResponse { invoice } = ln_invoice(node1_addr, None, None, None, 1).await;
+    let payment_hash = Bolt11Invoice::from_str(&invoice)
+        .unwrap()
+        .payment_hash()
+        .to_string();
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let payment = list_payments(node1_addr)
+            .await
+            .into_iter()
+            .find(|p| p.payment_hash == payment_hash)
+            .unwrap();
+        if payment.status == HTLCStatus::Expired {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("invoice has not been marked as expired")
+        }
+    }
+    assert!(matches!(
+        invoice_status(node1_addr, &invoice).await,
+        InvoiceStatus::Expired
+    ));
 }
@@ -0,0 +1,130 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_route_hints/";
+
+async fn ln_invoice_with_route_hints_raw(
+    node_address: SocketAddr,
+    asset_id: Option<&str>,
+    route_hint_channel_ids: Vec<String>,
+) -> reqwest::Response {
+    println!(
+        "generating invoice with route hints {route_hint_channel_ids:?} for node {node_address}"
+    );
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        expiry_sec: 900,
+        asset_id: asset_id.map(|a| a.to_string()),
+        asset_amount: asset_id.map(|_| 10),
+        payment_hash: None,
+        description_hash: None,
+        fallback_address: None,
+        route_hint_channel_ids: Some(route_hint_channel_ids),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn route_hint_sources(res: reqwest::Response) -> Vec<String> {
+    let LNInvoiceResponse { invoice } = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap();
+    Bolt11Invoice::from_str(&invoice)
+        .unwrap()
+        .route_hints()
+        .iter()
+        .map(|h| h.0[0].src_node_id.to_string())
+        .collect()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_route_hints() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let asset_channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let vanilla_channel = open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    println!("\nchoosing the route hints of a vanilla invoice");
+    let res =
+        ln_invoice_with_route_hints_raw(node2_addr, None, vec![vanilla_channel.channel_id.clone()])
+            .await;
+    assert_eq!(route_hint_sources(res).await, vec![node3_pubkey.clone()]);
+    let res = ln_invoice_with_route_hints_raw(node2_addr, None, vec![]).await;
+    assert!(route_hint_sources(res).await.is_empty());
+
+    println!("\nchoosing the route hints of an RGB invoice");
+    let res = ln_invoice_with_route_hints_raw(
+        node2_addr,
+        Some(&asset_id),
+        vec![asset_channel.channel_id.clone()],
+    )
+    .await;
+    assert_eq!(route_hint_sources(res).await, vec![node1_pubkey]);
+    let res = ln_invoice_with_route_hints_raw(
+        node2_addr,
+        Some(&asset_id),
+        vec![vanilla_channel.channel_id.clone()],
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!(
+            "Invalid route hints: channel {} doesn't hold the invoice asset",
+            vanilla_channel.channel_id
+        ),
+    )
+    .await;
+
+    let unknown_channel_id = "00".repeat(32);
+    let res =
+        ln_invoice_with_route_hints_raw(node2_addr, None, vec![unknown_channel_id.clone()]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!("Invalid route hints: unknown channel {unknown_channel_id}"),
+    )
+    .await;
+}
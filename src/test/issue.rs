@@ -94,4 +94,26 @@ async fn issue() {
         "IO error: Is a directory (os error 21)",
     )
     .await;
+
+    // check /issueassetuda errors for media that has not been uploaded
+    let payload = IssueAssetUDARequest {
+        ticker: s!("UNI"),
+        name: s!("Unique"),
+        details: None,
+        precision: 0,
+        media_file_digest: Some(uda_digest.clone()),
+        attachments_file_digests: vec![s!("a")],
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/issueassetuda", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid media digest",
+    )
+    .await;
 }
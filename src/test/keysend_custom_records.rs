@@ -0,0 +1,130 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/keysend_custom_records/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn keysend_custom_records() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    // records are sent sorted by type, whatever the order they're provided in
+    let custom_records = vec![
+        CustomTlvRecord {
+            tlv_type: 65539,
+            value: s!("cafe"),
+        },
+        CustomTlvRecord {
+            tlv_type: 65537,
+            value: s!("68656c6c6f"),
+        },
+    ];
+    let payload = KeysendRequest {
+        dest_pubkey: node2_pubkey.clone(),
+        amt_msat: 3000000,
+        asset_id: Some(asset_id.clone()),
+        asset_amount: Some(100),
+        custom_records: Some(custom_records.clone()),
+        retry_attempts: None,
+        retry_timeout_secs: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/keysend", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let keysend = _check_response_is_ok(res)
+        .await
+        .json::<KeysendResponse>()
+        .await
+        .unwrap();
+    let payment =
+        _wait_for_ln_payment(node1_addr, &keysend.payment_hash, HTLCStatus::Succeeded).await;
+    let mut expected_records = custom_records;
+    expected_records.reverse();
+    assert_eq!(payment.custom_records, expected_records);
+
+    let payment =
+        _wait_for_ln_payment(node2_addr, &keysend.payment_hash, HTLCStatus::Succeeded).await;
+    assert!(payment.inbound);
+    assert!(payment.keysend);
+    assert_eq!(payment.asset_id, Some(asset_id.clone()));
+    assert_eq!(payment.asset_amount, Some(100));
+    assert_eq!(payment.custom_records, expected_records);
+
+    // types below the custom range are refused
+    let payload = KeysendRequest {
+        dest_pubkey: node2_pubkey.clone(),
+        amt_msat: 3000000,
+        asset_id: None,
+        asset_amount: None,
+        custom_records: Some(vec![CustomTlvRecord {
+            tlv_type: 42,
+            value: s!("00"),
+        }]),
+        retry_attempts: None,
+        retry_timeout_secs: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/keysend", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid tlv type: custom TLV types must be unique and in the custom range",
+    )
+    .await;
+
+    // values must be hex
+    let payload = KeysendRequest {
+        dest_pubkey: node2_pubkey,
+        amt_msat: 3000000,
+        asset_id: None,
+        asset_amount: None,
+        custom_records: Some(vec![CustomTlvRecord {
+            tlv_type: 65537,
+            value: s!("nothex"),
+        }]),
+        retry_attempts: None,
+        retry_timeout_secs: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/keysend", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid tlv type: value of TLV record 65537 is not valid hex",
+    )
+    .await;
+}
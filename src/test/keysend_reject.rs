@@ -0,0 +1,54 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/keysend_reject/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn keysend_reject() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node2.into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        reject_keysend: true,
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3500000),
+        None,
+        None,
+    )
+    .await;
+
+    println!("\nsending a keysend to a node rejecting them");
+    let keysend = _keysend_raw(node1_addr, &node2_pubkey, Some(3000000), None, None).await;
+    let payment = _wait_for_ln_payment(node1_addr, &keysend.payment_hash, HTLCStatus::Failed).await;
+    assert!(payment.keysend);
+    assert!(!payment.inbound);
+    assert!(list_payments(node2_addr).await.is_empty());
+
+    println!("\nsending a keysend to a node accepting them");
+    let payment = keysend(node2_addr, &node1_pubkey, Some(1000000), None, None).await;
+    assert!(payment.keysend);
+    let payment =
+        _wait_for_ln_payment(node1_addr, &payment.payment_hash, HTLCStatus::Succeeded).await;
+    assert!(payment.inbound);
+    assert!(payment.keysend);
+    assert_eq!(payment.amt_msat, Some(1000000));
+    assert!(payment.custom_records.is_empty());
+}
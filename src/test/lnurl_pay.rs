@@ -0,0 +1,135 @@
+use bitcoin::hashes::{sha256, Hash};
+use lightning_invoice::Bolt11InvoiceDescription;
+
+use crate::error::LnurlErrorResponse;
+use crate::routes::{LnurlPayCallbackResponse, LnurlPayResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lnurl_pay/";
+
+async fn check_lnurl_error(
+    res: reqwest::Response,
+    expected_status: reqwest::StatusCode,
+    reason: &str,
+) {
+    assert_eq!(res.status(), expected_status);
+    let error = res.json::<LnurlErrorResponse>().await.unwrap();
+    assert_eq!(error.status, "ERROR");
+    assert_eq!(error.reason, reason);
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lnurl_pay() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        lnurl_base_url: Some(s!("https://example.com")),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    println!("\ngetting pay request");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/.well-known/lnurlp/alice", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    let pay_request = _check_response_is_ok(res)
+        .await
+        .json::<LnurlPayResponse>()
+        .await
+        .unwrap();
+    assert_eq!(pay_request.tag, "payRequest");
+    assert_eq!(
+        pay_request.callback,
+        "https://example.com/lnurlp/alice/callback"
+    );
+    assert_eq!(pay_request.min_sendable, 1000);
+    assert_eq!(pay_request.max_sendable, 100_000_000);
+    let metadata: Vec<[String; 2]> = serde_json::from_str(&pay_request.metadata).unwrap();
+    assert!(metadata.contains(&[s!("text/identifier"), s!("alice@example.com")]));
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/.well-known/lnurlp/Alice", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid name: username can only contain a-z, 0-9, '-', '_' and '.'",
+    )
+    .await;
+
+    println!("\nrequesting invoices");
+    let callback_url = format!("http://{}/lnurlp/alice/callback", node1_addr);
+    let res = reqwest::Client::new()
+        .get(format!("{callback_url}?amount=500"))
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid amount: amount must be between 1000 and 100000000 msat",
+    )
+    .await;
+
+    let res = reqwest::Client::new()
+        .get(format!("{callback_url}?amount=50000"))
+        .send()
+        .await
+        .unwrap();
+    let callback = _check_response_is_ok(res)
+        .await
+        .json::<LnurlPayCallbackResponse>()
+        .await
+        .unwrap();
+    assert!(callback.routes.is_empty());
+    let invoice = Bolt11Invoice::from_str(&callback.pr).unwrap();
+    assert_eq!(invoice.amount_milli_satoshis(), Some(50000));
+    match invoice.description() {
+        Bolt11InvoiceDescription::Hash(hash) => {
+            assert_eq!(hash.0, sha256::Hash::hash(pay_request.metadata.as_bytes()))
+        }
+        Bolt11InvoiceDescription::Direct(_) => panic!("invoice should commit to the metadata"),
+    }
+    assert!(matches!(
+        invoice_status(node1_addr, &callback.pr).await,
+        InvoiceStatus::Pending
+    ));
+
+    // RGB invoices require both the asset ID and amount
+    let res = reqwest::Client::new()
+        .get(format!("{callback_url}?amount=3000000&asset_amount=10"))
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "For an RGB operation both asset_id and asset_amount must be set",
+    )
+    .await;
+
+    // LNURL is disabled unless a base URL is configured
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/.well-known/lnurlp/alice", node2_addr))
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "LNURL support is disabled (hint: set --lnurl-base-url)",
+    )
+    .await;
+}
@@ -0,0 +1,172 @@
+use crate::error::LnurlErrorResponse;
+use crate::routes::{
+    LnurlStatusResponse, LnurlWithdrawInfoResponse, LnurlWithdrawRequest, LnurlWithdrawResponse,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lnurl_withdraw/";
+
+async fn check_lnurl_error(
+    res: reqwest::Response,
+    expected_status: reqwest::StatusCode,
+    reason: &str,
+) {
+    assert_eq!(res.status(), expected_status);
+    let error = res.json::<LnurlErrorResponse>().await.unwrap();
+    assert_eq!(error.status, "ERROR");
+    assert_eq!(error.reason, reason);
+}
+
+async fn withdraw_callback(node_address: SocketAddr, k1: &str, pr: &str) -> reqwest::Response {
+    println!("claiming withdraw {k1} on node {node_address}");
+    reqwest::Client::new()
+        .get(format!("http://{}/lnurlw/{k1}/callback", node_address))
+        .query(&[("k1", k1), ("pr", pr)])
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lnurl_withdraw() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        lnurl_base_url: Some(s!("https://example.com")),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    println!("\ncreating withdraw");
+    let payload = LnurlWithdrawRequest {
+        min_withdrawable_msat: 10000,
+        max_withdrawable_msat: 100000,
+        description: s!("test withdraw"),
+        asset_id: None,
+        max_asset_amount: None,
+        expiry_sec: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lnurlwithdraw", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let LnurlWithdrawResponse { k1, url } = _check_response_is_ok(res)
+        .await
+        .json::<LnurlWithdrawResponse>()
+        .await
+        .unwrap();
+    assert_eq!(url, format!("https://example.com/lnurlw/{k1}"));
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/lnurlw/{k1}", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    let info = _check_response_is_ok(res)
+        .await
+        .json::<LnurlWithdrawInfoResponse>()
+        .await
+        .unwrap();
+    assert_eq!(info.tag, "withdrawRequest");
+    assert_eq!(info.k1, k1);
+    assert_eq!(info.callback, format!("{url}/callback"));
+    assert_eq!(info.default_description, "test withdraw");
+    assert_eq!(info.min_withdrawable, 10000);
+    assert_eq!(info.max_withdrawable, 100000);
+    assert!(info.asset_id.is_none());
+
+    println!("\nclaiming withdraw");
+    let invoice = ln_invoice(node2_addr, Some(200000), None, None, 900)
+        .await
+        .invoice;
+    let res = withdraw_callback(node1_addr, &k1, &invoice).await;
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid amount: amount must be between 10000 and 100000 msat",
+    )
+    .await;
+
+    let invoice = ln_invoice(node2_addr, Some(50000), None, None, 900)
+        .await
+        .invoice;
+    let res = withdraw_callback(node1_addr, &k1, &invoice).await;
+    let status = _check_response_is_ok(res)
+        .await
+        .json::<LnurlStatusResponse>()
+        .await
+        .unwrap();
+    assert_eq!(status.status, "OK");
+    let payment_hash = Bolt11Invoice::from_str(&invoice)
+        .unwrap()
+        .payment_hash()
+        .to_string();
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    // k1 is single-use
+    let invoice = ln_invoice(node2_addr, Some(50000), None, None, 900)
+        .await
+        .invoice;
+    let res = withdraw_callback(node1_addr, &k1, &invoice).await;
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot withdraw: k1 has already been used",
+    )
+    .await;
+
+    let res = withdraw_callback(node1_addr, "unknown", &invoice).await;
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown LNURL-withdraw k1",
+    )
+    .await;
+
+    // used k1s are persisted
+    shutdown(&[node1_addr]).await;
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        lnurl_base_url: Some(s!("https://example.com")),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, true).await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/lnurlw/{k1}", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot withdraw: k1 has already been used",
+    )
+    .await;
+}
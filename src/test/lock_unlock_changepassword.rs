@@ -1,3 +1,7 @@
+use rgb_lib::bdk::keys::bip39::Mnemonic;
+
+use crate::utils::get_mnemonic_path;
+
 use super::*;
 
 const TEST_DIR_BASE: &str = "tmp/lock_unlock_changepassword/";
@@ -12,6 +16,11 @@ async fn lock_unlock_changepassword() {
 
     let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
 
+    // the mnemonic is only kept encrypted with the password
+    let mnemonic_path = get_mnemonic_path(Path::new(&test_dir_node1));
+    let stored_mnemonic = std::fs::read_to_string(&mnemonic_path).unwrap();
+    assert!(Mnemonic::from_str(&stored_mnemonic).is_err());
+
     println!("1 - lock+unlock");
     lock(node1_addr).await;
     unlock(node1_addr, &node1_password).await;
@@ -76,6 +85,9 @@ async fn lock_unlock_changepassword() {
 
     // successful password change
     change_password(node1_addr, &node1_password, &new_password).await;
+    let reencrypted_mnemonic = std::fs::read_to_string(&mnemonic_path).unwrap();
+    assert_ne!(reencrypted_mnemonic, stored_mnemonic);
+    assert!(Mnemonic::from_str(&reencrypted_mnemonic).is_err());
 
     unlock(node1_addr, &new_password).await;
     assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
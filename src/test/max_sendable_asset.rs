@@ -0,0 +1,88 @@
+use crate::routes::{MaxSendableAssetRequest, MaxSendableAssetResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/max_sendable_asset/";
+
+async fn max_sendable_asset_raw(
+    node_address: SocketAddr,
+    asset_id: &str,
+    dest_pubkey: Option<&str>,
+) -> reqwest::Response {
+    println!("getting max sendable amount of asset {asset_id} for node {node_address}");
+    let payload = MaxSendableAssetRequest {
+        asset_id: asset_id.to_string(),
+        dest_pubkey: dest_pubkey.map(|p| p.to_string()),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/maxsendableasset", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn max_sendable_asset(
+    node_address: SocketAddr,
+    asset_id: &str,
+    dest_pubkey: Option<&str>,
+) -> MaxSendableAssetResponse {
+    let res = max_sendable_asset_raw(node_address, asset_id, dest_pubkey).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<MaxSendableAssetResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn max_sendable_asset_amount() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let other_asset_id = issue_asset_cfa(node1_addr, None).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    for asset_amount in [600, 300] {
+        open_channel(
+            node1_addr,
+            &node2_pubkey,
+            Some(NODE2_PEER_PORT),
+            None,
+            None,
+            Some(asset_amount),
+            Some(&asset_id),
+        )
+        .await;
+    }
+
+    let res = max_sendable_asset(node1_addr, &asset_id, None).await;
+    assert_eq!(res.max_path_amount, 600);
+    assert_eq!(res.max_mpp_amount, 900);
+    let res = max_sendable_asset(node1_addr, &asset_id, Some(&node2_pubkey)).await;
+    assert_eq!(res.max_path_amount, 600);
+    assert_eq!(res.max_mpp_amount, 900);
+
+    // the receiving side has nothing to send
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let res = max_sendable_asset(node2_addr, &asset_id, Some(&node1_pubkey)).await;
+    assert_eq!(res.max_path_amount, 0);
+    assert_eq!(res.max_mpp_amount, 0);
+
+    // assets without channels cannot be sent off-chain
+    let res = max_sendable_asset(node1_addr, &other_asset_id, None).await;
+    assert_eq!(res.max_path_amount, 0);
+
+    let res = max_sendable_asset_raw(node1_addr, &asset_id, Some("invalid")).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid pubkey").await;
+}
@@ -1,6 +1,7 @@
 use amplify::s;
 use bitcoin::Network;
 use electrum_client::ElectrumApi;
+use lightning::ln::channelmanager::Retry;
 use lightning_invoice::Bolt11Invoice;
 use once_cell::sync::Lazy;
 use std::net::SocketAddr;
@@ -14,24 +15,30 @@ use tokio::net::TcpListener;
 use tracing_test::traced_test;
 
 use crate::error::APIErrorResponse;
-use crate::ldk::FEE_RATE;
+use crate::kv_store::StoreBackend;
+use crate::ldk::{HtlcLimits, FEE_RATE, UTXO_SIZE_SAT};
 use crate::routes::{
-    AddressResponse, AssetBalanceRequest, AssetBalanceResponse, AssetCFA, AssetNIA, AssetUDA,
-    BackupRequest, BtcBalanceResponse, ChangePasswordRequest, Channel, CloseChannelRequest,
-    ConnectPeerRequest, CreateUtxosRequest, DecodeLNInvoiceRequest, DecodeLNInvoiceResponse,
-    DecodeRGBInvoiceRequest, DecodeRGBInvoiceResponse, DisconnectPeerRequest, EmptyResponse,
+    AbandonFundingRequest, AbandonFundingResponse, AddressResponse, AssetBalanceRequest,
+    AssetBalanceResponse, AssetCFA, AssetNIA, AssetUDA, BackupRequest, BackupResponse,
+    BtcBalanceResponse, CancelInvoiceRequest, ChangePasswordRequest, Channel, ChannelShutdownState,
+    CloseChannelRequest, ConnectPeerRequest, CreateUtxosRequest, CustomTlvRecord,
+    DecodeLNInvoiceRequest, DecodeLNInvoiceResponse, DecodeRGBInvoiceRequest,
+    DecodeRGBInvoiceResponse, DisconnectPeerRequest, EmptyResponse, FailInterceptRequest,
     GetAssetMediaRequest, GetAssetMediaResponse, GetChannelIdRequest, GetChannelIdResponse,
-    HTLCStatus, InitRequest, InitResponse, InvoiceStatus, InvoiceStatusRequest,
+    GraphExportFormat, HTLCStatus, InitRequest, InitResponse, InvoiceStatus, InvoiceStatusRequest,
     InvoiceStatusResponse, IssueAssetCFARequest, IssueAssetCFAResponse, IssueAssetNIARequest,
     IssueAssetNIAResponse, IssueAssetUDARequest, IssueAssetUDAResponse, KeysendRequest,
     KeysendResponse, LNInvoiceRequest, LNInvoiceResponse, ListAssetsRequest, ListAssetsResponse,
     ListChannelsResponse, ListPaymentsResponse, ListPeersResponse, ListSwapsResponse,
     ListTransactionsResponse, ListTransfersRequest, ListTransfersResponse, ListUnspentsResponse,
-    MakerExecuteRequest, MakerInitRequest, MakerInitResponse, NetworkInfoResponse,
-    NodeInfoResponse, OpenChannelRequest, OpenChannelResponse, Payment, Peer,
-    PostAssetMediaResponse, RestoreRequest, RgbInvoiceRequest, RgbInvoiceResponse,
-    SendAssetRequest, SendAssetResponse, SendBtcRequest, SendBtcResponse, SendPaymentRequest,
-    SendPaymentResponse, SwapStatus, TakerRequest, Transaction, Transfer, UnlockRequest, Unspent,
+    MakerExecuteRequest, MakerInitRequest, MakerInitResponse, NetworkGraphChannelRequest,
+    NetworkGraphChannelResponse, NetworkGraphExportRequest, NetworkGraphExportResponse,
+    NetworkGraphNodeRequest, NetworkGraphNodeResponse, NetworkInfoResponse, NodeInfoResponse,
+    OpenChannelRequest, OpenChannelResponse, Payment, Peer, PendingIntercept,
+    PendingInterceptsResponse, PostAssetMediaResponse, ReceiveMode, RestoreRequest,
+    RgbInvoiceRequest, RgbInvoiceResponse, SendAssetRequest, SendAssetResponse, SendBtcRequest,
+    SendBtcResponse, SendPaymentRequest, SendPaymentResponse, SettleInvoiceRequest, SwapStatus,
+    TakerRequest, Transaction, Transfer, UnlockRequest, Unspent,
 };
 use crate::utils::{hex_str_to_vec, PROXY_ENDPOINT_REGTEST};
 
@@ -58,10 +65,46 @@ impl Default for LdkUserInfo {
             ldk_announced_listen_addr: vec![],
             ldk_announced_node_name: [0; 32],
             network: Network::Regtest,
+            proxy_endpoints: vec![],
             storage_dir_path: PathBuf::from("tmp/test_name/nodeN"),
-            daemon_listening_port: 3001,
+            daemon_listening_port: 0,
             ldk_peer_listening_port: 9735,
+            ldk_peer_listen_addrs: vec![],
             max_media_upload_size_mb: 3,
+            utxo_size_sat: UTXO_SIZE_SAT,
+            max_allocations_per_utxo: 1,
+            fee_rate: FEE_RATE,
+            min_closing_fee_rate: 1.0,
+            max_closing_fee_rate: None,
+            payment_retry: Retry::Timeout(Duration::from_secs(10)),
+            lnurl_base_url: None,
+            lnurl_min_sendable_msat: 1000,
+            lnurl_max_sendable_msat: 100_000_000,
+            relay_mode: false,
+            announce_channels: false,
+            htlc_limits: HtlcLimits::default(),
+            reject_keysend: false,
+            failover: false,
+            alert_rules: vec![],
+            alert_webhook_url: None,
+            derived_blinding: false,
+            rgb_refresh_interval: None,
+            rgb_refresh_parallelism: 4,
+            submarine_swap_server: false,
+            price_oracle_url: None,
+            swap_quote_fee_ppm: 0,
+            store_backend: StoreBackend::Filesystem,
+            postgres_url: None,
+            vss_url: None,
+            encrypt_storage: false,
+            backup_schedule: None,
+            spend_limits: None,
+            require_api_token: false,
+            tor_proxy: None,
+            tor_control: None,
+            tor_control_password: None,
+            tor_persist_onion_key: false,
+            log_filter: None,
         }
     }
 }
@@ -130,14 +173,21 @@ fn _get_txout(txid: &str) -> String {
 }
 
 async fn start_daemon(node_test_dir: &str, node_peer_port: u16) -> SocketAddr {
-    let listener = TcpListener::bind("0.0.0.0:0").await.unwrap();
-    let node_address = listener.local_addr().unwrap();
-    std::fs::create_dir_all(node_test_dir).unwrap();
-    let args = LdkUserInfo {
+    start_daemon_with_args(LdkUserInfo {
         storage_dir_path: node_test_dir.into(),
         ldk_peer_listening_port: node_peer_port,
         ..Default::default()
-    };
+    })
+    .await
+}
+
+async fn start_daemon_with_args(args: LdkUserInfo) -> SocketAddr {
+    // port 0 binds any free port
+    let listener = TcpListener::bind(("0.0.0.0", args.daemon_listening_port))
+        .await
+        .unwrap();
+    let node_address = listener.local_addr().unwrap();
+    std::fs::create_dir_all(&args.storage_dir_path).unwrap();
     tokio::spawn(async move {
         let (router, app_state) = app(args).await.unwrap();
         axum::serve(listener, router)
@@ -153,11 +203,22 @@ async fn start_node(
     node_peer_port: u16,
     keep_node_dir: bool,
 ) -> (SocketAddr, String) {
+    let args = LdkUserInfo {
+        storage_dir_path: node_test_dir.into(),
+        ldk_peer_listening_port: node_peer_port,
+        ..Default::default()
+    };
+    start_node_with_args(args, keep_node_dir).await
+}
+
+async fn start_node_with_args(args: LdkUserInfo, keep_node_dir: bool) -> (SocketAddr, String) {
+    let node_test_dir = args.storage_dir_path.to_string_lossy().to_string();
+    let node_peer_port = args.ldk_peer_listening_port;
     println!("starting node with peer port {node_peer_port}");
     if !keep_node_dir && Path::new(&node_test_dir).is_dir() {
-        std::fs::remove_dir_all(node_test_dir).unwrap();
+        std::fs::remove_dir_all(&node_test_dir).unwrap();
     }
-    let node_address = start_daemon(node_test_dir, node_peer_port).await;
+    let node_address = start_daemon_with_args(args).await;
 
     let password = format!("{node_test_dir}.{node_peer_port}");
 
@@ -230,8 +291,9 @@ async fn asset_balance_spendable(node_address: SocketAddr, asset_id: &str) -> u6
 async fn backup(node_address: SocketAddr, backup_path: &str, password: &str) {
     println!("performing backup for node {node_address} on {backup_path}");
     let payload = BackupRequest {
-        backup_path: backup_path.to_string(),
+        backup_path: Some(backup_path.to_string()),
         password: password.to_string(),
+        incremental: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/backup", node_address))
@@ -239,11 +301,13 @@ async fn backup(node_address: SocketAddr, backup_path: &str, password: &str) {
         .send()
         .await
         .unwrap();
-    _check_response_is_ok(res)
+    let backup = _check_response_is_ok(res)
         .await
-        .json::<EmptyResponse>()
+        .json::<BackupResponse>()
         .await
-        .unwrap();
+        .unwrap()
+        .backup;
+    assert!(backup.is_none());
 }
 
 async fn btc_balance(node_address: SocketAddr) -> BtcBalanceResponse {
@@ -260,6 +324,24 @@ async fn btc_balance(node_address: SocketAddr) -> BtcBalanceResponse {
         .unwrap()
 }
 
+async fn cancel_invoice(node_address: SocketAddr, payment_hash: &str) {
+    println!("cancelling invoice with payment hash {payment_hash} on node {node_address}");
+    let payload = CancelInvoiceRequest {
+        payment_hash: payment_hash.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/cancelinvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
 async fn change_password(node_address: SocketAddr, old_password: &str, new_password: &str) {
     println!("changing password for node {node_address}");
     let payload = ChangePasswordRequest {
@@ -296,19 +378,28 @@ async fn check_payment_status(
 }
 
 async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey: &str, force: bool) {
-    println!(
-        "{}closing channel {channel_id} from node {node_address}",
-        if force { "force-" } else { "cooperatively " }
-    );
-    stop_mining();
     let payload = CloseChannelRequest {
         channel_id: channel_id.to_string(),
         peer_pubkey: peer_pubkey.to_string(),
         force,
+        fee_rate: None,
+        conf_target: None,
+        close_address: None,
     };
+    close_channel_with_payload(node_address, &payload).await;
+}
+
+async fn close_channel_with_payload(node_address: SocketAddr, payload: &CloseChannelRequest) {
+    let channel_id = &payload.channel_id;
+    let force = payload.force;
+    println!(
+        "{}closing channel {channel_id} from node {node_address}",
+        if force { "force-" } else { "cooperatively " }
+    );
+    stop_mining();
     let res = reqwest::Client::new()
         .post(format!("http://{}/closechannel", node_address))
-        .json(&payload)
+        .json(payload)
         .send()
         .await
         .unwrap();
@@ -322,7 +413,7 @@ async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey:
     loop {
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         let channels = list_channels(node_address).await;
-        if !channels.iter().any(|c| c.channel_id == channel_id) {
+        if !channels.iter().any(|c| &c.channel_id == channel_id) {
             let block_num = match force {
                 true => 144,
                 false => 6,
@@ -493,6 +584,35 @@ async fn get_channel_id(node_address: SocketAddr, temp_chan_id: &str) -> String
         .channel_id
 }
 
+async fn hold_ln_invoice(
+    node_address: SocketAddr,
+    amt_msat: u64,
+    payment_hash: &str,
+) -> LNInvoiceResponse {
+    println!("generating hold invoice with payment hash {payment_hash} for node {node_address}");
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(amt_msat),
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        payment_hash: Some(payment_hash.to_string()),
+        description_hash: None,
+        fallback_address: None,
+        route_hint_channel_ids: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lninvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
 async fn invoice_status(node_address: SocketAddr, invoice: &str) -> InvoiceStatus {
     println!("getting status of invoice {invoice} for node {node_address}");
     let payload = InvoiceStatusRequest {
@@ -642,6 +762,9 @@ async fn _keysend_raw(
         amt_msat,
         asset_id: asset_id.map(|a| a.to_string()),
         asset_amount,
+        custom_records: None,
+        retry_attempts: None,
+        retry_timeout_secs: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/keysend", node_address))
@@ -783,7 +906,10 @@ async fn list_transactions(node_address: SocketAddr) -> Vec<Transaction> {
 async fn list_transfers(node_address: SocketAddr, asset_id: &str) -> Vec<Transfer> {
     println!("listing transfers for asset {asset_id} on node {node_address}");
     let payload = ListTransfersRequest {
-        asset_id: asset_id.to_string(),
+        asset_id: Some(asset_id.to_string()),
+        status: None,
+        offset: 0,
+        limit: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/listtransfers", node_address))
@@ -829,6 +955,10 @@ async fn ln_invoice(
         expiry_sec,
         asset_id: asset_id.map(|a| a.to_string()),
         asset_amount,
+        payment_hash: None,
+        description_hash: None,
+        fallback_address: None,
+        route_hint_channel_ids: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/lninvoice", node_address))
@@ -881,6 +1011,9 @@ async fn maker_execute_raw(
         swapstring,
         payment_secret,
         taker_pubkey,
+        first_leg_cltv_expiry_delta: None,
+        second_leg_cltv_expiry_delta: None,
+        fill_qty_from: None,
     };
     reqwest::Client::new()
         .post(format!("http://{}/makerexecute", node_address))
@@ -908,6 +1041,8 @@ async fn maker_init(
         from_asset: from_asset.map(|a| a.into()),
         to_asset: to_asset.map(|a| a.into()),
         timeout_sec,
+        partial_fill: None,
+        taker_pubkey: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/makerinit", node_address))
@@ -922,6 +1057,62 @@ async fn maker_init(
         .unwrap()
 }
 
+async fn network_graph_channel(
+    node_address: SocketAddr,
+    short_channel_id: u64,
+) -> NetworkGraphChannelResponse {
+    println!("getting network graph channel {short_channel_id} from node {node_address}");
+    let payload = NetworkGraphChannelRequest { short_channel_id };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/networkgraph/channel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NetworkGraphChannelResponse>()
+        .await
+        .unwrap()
+}
+
+async fn network_graph_export(
+    node_address: SocketAddr,
+    format: GraphExportFormat,
+) -> NetworkGraphExportResponse {
+    println!("exporting network graph from node {node_address}");
+    let payload = NetworkGraphExportRequest { format };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/networkgraph/export", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NetworkGraphExportResponse>()
+        .await
+        .unwrap()
+}
+
+async fn network_graph_node(node_address: SocketAddr, node_id: &str) -> NetworkGraphNodeResponse {
+    println!("getting network graph node {node_id} from node {node_address}");
+    let payload = NetworkGraphNodeRequest {
+        node_id: node_id.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/networkgraph/node", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NetworkGraphNodeResponse>()
+        .await
+        .unwrap()
+}
+
 async fn network_info(node_address: SocketAddr) -> NetworkInfoResponse {
     println!("getting network info for node {node_address}");
     let res = reqwest::Client::new()
@@ -1003,11 +1194,20 @@ async fn open_channel_with_custom_data(
         push_msat: push_msat.unwrap_or(0),
         asset_amount,
         asset_id: asset_id.map(|a| a.to_string()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat,
         fee_proportional_millionths,
         temporary_channel_id: temporary_channel_id.map(|t| t.to_string()),
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node_address))
@@ -1102,8 +1302,11 @@ async fn refresh_transfers(node_address: SocketAddr) {
 async fn restore(node_address: SocketAddr, backup_path: &str, password: &str) {
     println!("restoring backup for node {node_address} from {backup_path}");
     let payload = RestoreRequest {
-        backup_path: backup_path.to_string(),
+        backup_path: Some(backup_path.to_string()),
+        backup: None,
         password: password.to_string(),
+        incremental_backup_paths: vec![],
+        incremental_backups: vec![],
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/restore", node_address))
@@ -1131,6 +1334,8 @@ async fn rgb_invoice(node_address: SocketAddr, asset_id: Option<String>) -> RgbI
         min_confirmations: 1,
         asset_id,
         duration_seconds: None,
+        receive_mode: ReceiveMode::Blinded,
+        transport_endpoints: vec![],
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/rgbinvoice", node_address))
@@ -1154,9 +1359,10 @@ async fn send_asset(node_address: SocketAddr, asset_id: &str, amount: u64, recip
         amount,
         recipient_id,
         donation: true,
-        fee_rate: FEE_RATE,
+        fee_rate: Some(FEE_RATE),
         min_confirmations: 1,
         transport_endpoints: vec![PROXY_ENDPOINT_REGTEST.to_string()],
+        witness_amount_sat: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/sendasset", node_address))
@@ -1176,7 +1382,7 @@ async fn send_btc(node_address: SocketAddr, amount: u64, address: &str) -> Strin
     let payload = SendBtcRequest {
         amount,
         address: address.to_string(),
-        fee_rate: FEE_RATE,
+        fee_rate: Some(FEE_RATE),
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/sendbtc", node_address))
@@ -1197,6 +1403,9 @@ async fn _send_payment_raw(node_address: SocketAddr, invoice: String) -> SendPay
     let payload = SendPaymentRequest {
         invoice,
         amt_msat: None,
+        asset_amount: None,
+        retry_attempts: None,
+        retry_timeout_secs: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/sendpayment", node_address))
@@ -1254,6 +1463,24 @@ async fn send_payment_with_status(
     .await
 }
 
+async fn settle_invoice(node_address: SocketAddr, payment_preimage: &str) {
+    println!("settling invoice with payment preimage {payment_preimage} on node {node_address}");
+    let payload = SettleInvoiceRequest {
+        payment_preimage: payment_preimage.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/settleinvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
 async fn shutdown(node_sockets: &[SocketAddr]) {
     // shutdown nodes
     for node_address in node_sockets {
@@ -1556,30 +1783,108 @@ pub(crate) fn initialize() {
     });
 }
 
+mod abandon_funding;
+mod abandon_payment;
+mod alert_rules;
+mod announce_channel;
+mod api_tokens;
+mod asset_htlc_limit;
+mod asset_submarine_swaps;
+mod background_refresh;
 mod backup_and_restore;
+mod backup_seed;
+mod bump_close_tx;
+mod burn_asset;
+mod cfa_channel;
+mod channel_announcement;
+mod channel_policy;
+mod channel_requests;
+mod close_coop_address;
 mod close_coop_nobtc_acceptor;
 mod close_coop_other_side;
 mod close_coop_standard;
 mod close_coop_vanilla;
 mod close_coop_zero_balance;
+mod close_force_multi_asset;
 mod close_force_nobtc_acceptor;
 mod close_force_other_side;
 mod close_force_standard;
 mod concurrent_btc_payments;
+mod concurrent_claims;
+mod contract_export_import;
+#[cfg(feature = "debug-api")]
+mod debug_channel_state;
+#[cfg(feature = "debug-api")]
+mod debug_rgb_info;
+mod derived_blinding;
+mod encrypted_storage;
+mod external_funding_signing;
+mod failover;
+mod fee_orders;
+mod fee_rate;
+mod fee_report;
+mod forwarding_history;
 mod getchannelid;
+mod hold_invoice;
 mod htlc_amount_checks;
+mod htlc_limits;
+mod incremental_backups;
+mod inspect_consignment;
 mod invoice;
+mod invoice_route_hints;
 mod issue;
+mod keysend_custom_records;
+mod keysend_reject;
+mod lnurl_pay;
+mod lnurl_withdraw;
 mod lock_unlock_changepassword;
+mod max_sendable_asset;
 mod multi_hop;
 mod multi_open_close;
+mod networkgraph;
+mod node_migration;
 mod open_after_double_send;
+mod openchannel_batch;
+mod openchannel_change_address;
+mod openchannel_close_address;
 mod openchannel_fail;
+mod openchannel_funding_outpoints;
 mod openchannel_optional_addr;
 mod payment;
+mod payment_entries;
+mod payment_retry;
+mod peer_listen_addrs;
+mod peer_policy;
+mod pending_intercepts;
+mod pending_sweeps;
+mod persistence_errors;
+mod postgres_store;
+mod proxy_failover;
+mod proxy_pins;
+mod rebalance;
+mod receive_witness;
 mod refuse_high_fees;
+mod relay_mode;
 mod restart;
+mod rotate_node_id;
+mod scheduled_backups;
+mod schedules;
+mod scid_alias;
 mod send_receive;
+mod send_to_ln_address;
+mod set_log_level;
+mod simulate_payment;
+mod spend_limits;
+mod sqlite_store;
+mod state_journal;
+mod state_snapshots;
+mod static_channel_backup;
+mod storage_status;
+mod submarine_swaps;
+mod swap_details;
+mod swap_expiry;
+mod swap_offers;
+mod swap_quotes;
 mod swap_roundtrip_assets;
 mod swap_roundtrip_buy;
 mod swap_roundtrip_buy_same_channel;
@@ -1593,7 +1898,17 @@ mod swap_roundtrip_fail_timeout;
 mod swap_roundtrip_fail_whitelist;
 mod swap_roundtrip_multihop_asset_asset;
 mod swap_roundtrip_multihop_buy;
+mod swap_roundtrip_multihop_mixed;
 mod swap_roundtrip_multihop_sell;
+mod swap_roundtrip_partial_fill;
 mod swap_roundtrip_sell;
+mod tor_proxy;
+mod transfer_history;
+mod transfer_proof;
 mod upload_asset_media;
+mod utxo_params;
 mod vanilla_payment_on_rgb_channel;
+mod vss_mirror;
+#[cfg(feature = "web-ui")]
+mod web_ui;
+mod zero_amount_rgb_invoice;
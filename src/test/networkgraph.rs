@@ -0,0 +1,114 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/networkgraph/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn networkgraph() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let short_channel_id = channel.short_channel_id.unwrap();
+
+    // wait for the channel announcement to reach the network graph
+    let t_0 = OffsetDateTime::now_utc();
+    let graph = loop {
+        let graph = network_graph_export(node1_addr, GraphExportFormat::Json).await;
+        if graph
+            .channels
+            .iter()
+            .any(|c| c.short_channel_id == short_channel_id)
+        {
+            break graph;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel is taking too long to appear in the network graph")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert!(graph.dot.is_none());
+    assert!(graph.nodes.iter().any(|n| n.node_id == node1_pubkey));
+    assert!(graph.nodes.iter().any(|n| n.node_id == node2_pubkey));
+
+    // RGB channels we're part of are annotated with their asset
+    let graph_channel = network_graph_channel(node1_addr, short_channel_id)
+        .await
+        .channel;
+    assert_eq!(graph_channel.asset_id, Some(asset_id.clone()));
+    let mut node_ids = [graph_channel.node_one, graph_channel.node_two];
+    node_ids.sort();
+    let mut expected_node_ids = [node1_pubkey.clone(), node2_pubkey.clone()];
+    expected_node_ids.sort();
+    assert_eq!(node_ids, expected_node_ids);
+
+    let node_res = network_graph_node(node1_addr, &node2_pubkey).await;
+    assert_eq!(node_res.node.node_id, node2_pubkey);
+    assert!(node_res.node.short_channel_ids.contains(&short_channel_id));
+    assert!(node_res
+        .channels
+        .iter()
+        .any(|c| c.short_channel_id == short_channel_id));
+
+    let graph = network_graph_export(node1_addr, GraphExportFormat::Dot).await;
+    assert!(graph.nodes.is_empty());
+    assert!(graph.channels.is_empty());
+    let dot = graph.dot.unwrap();
+    assert!(dot.starts_with("graph network {"));
+    assert!(dot.contains(&format!("label=\"{short_channel_id}\"")));
+    assert!(dot.contains(&asset_id));
+
+    // unknown channel and node
+    let payload = NetworkGraphChannelRequest {
+        short_channel_id: short_channel_id + 1,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/networkgraph/channel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel in the network graph",
+    )
+    .await;
+    let payload = NetworkGraphNodeRequest {
+        node_id: s!("02270dadcd6e7ba0ef707dac72acccae1a3607453a8dd2aef36ff3be4e0d31f043"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/networkgraph/node", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown node in the network graph",
+    )
+    .await;
+}
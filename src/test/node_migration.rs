@@ -0,0 +1,161 @@
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/node_migration/";
+
+async fn export_backup(node_address: SocketAddr, password: &str) -> String {
+    println!("exporting backup for node {node_address}");
+    let payload = BackupRequest {
+        backup_path: None,
+        password: password.to_string(),
+        incremental: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/backup", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<BackupResponse>()
+        .await
+        .unwrap()
+        .backup
+        .unwrap()
+}
+
+async fn import_backup_raw(
+    node_address: SocketAddr,
+    backup: Option<String>,
+    password: &str,
+) -> reqwest::Response {
+    println!("importing backup for node {node_address}");
+    let payload = RestoreRequest {
+        backup_path: None,
+        backup,
+        password: password.to_string(),
+        incremental_backup_paths: vec![],
+        incremental_backups: vec![],
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/restore", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn node_migration() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node1_migrated = format!("{TEST_DIR_BASE}node1_migrated");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    keysend(node1_addr, &node2_pubkey, None, Some(&asset_id), Some(100)).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+
+    println!("\nexport the node state");
+    // a color source file pointing inside the storage dir, to be re-rooted on restore
+    let ldk_data_dir = std::fs::canonicalize(&test_dir_node1)
+        .unwrap()
+        .join(LDK_DIR);
+    let color_source_file = Path::new(LDK_DIR).join("test_color_source");
+    let old_consignment_path = ldk_data_dir.join("consignment").display().to_string();
+    std::fs::write(
+        Path::new(&test_dir_node1).join(&color_source_file),
+        serde_json::json!({ "path": old_consignment_path }).to_string(),
+    )
+    .unwrap();
+    lock(node1_addr).await;
+    let backup = export_backup(node1_addr, &node1_password).await;
+    shutdown(&[node1_addr]).await;
+
+    println!("\nimport the node state on a fresh daemon in another directory");
+    if Path::new(&test_dir_node1_migrated).exists() {
+        std::fs::remove_dir_all(&test_dir_node1_migrated).unwrap();
+    }
+    let node1_addr = start_daemon(&test_dir_node1_migrated, NODE1_PEER_PORT).await;
+    let res = import_backup_raw(node1_addr, None, &node1_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid backup: exactly one of backup_path and backup must be given",
+    )
+    .await;
+    let res = import_backup_raw(node1_addr, Some(s!("not base64!")), &node1_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid backup: not valid base64",
+    )
+    .await;
+    let res = import_backup_raw(node1_addr, Some(backup), &node1_password).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+
+    println!("\nthe paths in the color source have been re-rooted");
+    let migrated_data_dir = std::fs::canonicalize(&test_dir_node1_migrated)
+        .unwrap()
+        .join(LDK_DIR);
+    let color_source_json: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(Path::new(&test_dir_node1_migrated).join(&color_source_file)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        color_source_json["path"],
+        migrated_data_dir.join("consignment").display().to_string()
+    );
+    std::fs::remove_file(Path::new(&test_dir_node1_migrated).join(&color_source_file)).unwrap();
+
+    println!("\nthe migrated node takes over the channel");
+    unlock(node1_addr, &node1_password).await;
+    assert_eq!(node_info(node1_addr).await.pubkey, node1_pubkey);
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if channels
+            .iter()
+            .any(|c| c.channel_id == channel.channel_id && c.ready)
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("cannot find re-established channel")
+        }
+    }
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+}
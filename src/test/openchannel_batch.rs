@@ -0,0 +1,170 @@
+use crate::routes::{OpenChannelsRequest, OpenChannelsResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/openchannel_batch/";
+
+fn batch_channel(peer_pubkey: &str, peer_port: u16, capacity_sat: u64) -> OpenChannelRequest {
+    OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", peer_pubkey, peer_port),
+        capacity_sat,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    }
+}
+
+async fn open_channels_raw(
+    node_address: SocketAddr,
+    payload: &OpenChannelsRequest,
+) -> reqwest::Response {
+    println!(
+        "opening a batch of {} channels on node {node_address}",
+        payload.channels.len()
+    );
+    reqwest::Client::new()
+        .post(format!("http://{}/openchannels", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn openchannel_batch() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let res = open_channels_raw(
+        node1_addr,
+        &OpenChannelsRequest {
+            channels: vec![],
+            funding_outpoints: None,
+            fee_rate: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel batch: at least one channel must be given",
+    )
+    .await;
+
+    let mut rgb_channel = batch_channel(&node3_pubkey, NODE3_PEER_PORT, 100_000);
+    rgb_channel.asset_id = Some(asset_id);
+    rgb_channel.asset_amount = Some(600);
+    let res = open_channels_raw(
+        node1_addr,
+        &OpenChannelsRequest {
+            channels: vec![
+                batch_channel(&node2_pubkey, NODE2_PEER_PORT, 100_000),
+                rgb_channel,
+            ],
+            funding_outpoints: None,
+            fee_rate: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot open channel: only vanilla channels can be opened in a batch",
+    )
+    .await;
+
+    let channel_with_id = || {
+        let mut channel = batch_channel(&node2_pubkey, NODE2_PEER_PORT, 100_000);
+        channel.temporary_channel_id = Some(s!(
+            "a8b60c8ce3067b5fc881d4831323e24751daec3b64353c8df3205ec5d838f1c5"
+        ));
+        channel
+    };
+    let res = open_channels_raw(
+        node1_addr,
+        &OpenChannelsRequest {
+            channels: vec![channel_with_id(), channel_with_id()],
+            funding_outpoints: None,
+            fee_rate: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel batch: temporary channel IDs must be unique",
+    )
+    .await;
+
+    println!("\nopening channels to nodes 2 and 3 in a batch");
+    stop_mining();
+    let res = open_channels_raw(
+        node1_addr,
+        &OpenChannelsRequest {
+            channels: vec![
+                batch_channel(&node2_pubkey, NODE2_PEER_PORT, 100_000),
+                batch_channel(&node3_pubkey, NODE3_PEER_PORT, 150_000),
+            ],
+            funding_outpoints: None,
+            fee_rate: None,
+        },
+    )
+    .await;
+    let OpenChannelsResponse {
+        temporary_channel_ids,
+        funding_txid,
+    } = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelsResponse>()
+        .await
+        .unwrap();
+    assert_eq!(temporary_channel_ids.len(), 2);
+    let funding_txid = funding_txid.unwrap();
+
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(channels.len(), 2);
+    assert!(channels
+        .iter()
+        .all(|c| c.funding_txid.as_ref() == Some(&funding_txid)));
+
+    let t_0 = OffsetDateTime::now_utc();
+    while _get_txout(&funding_txid).is_empty() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    mine_n_blocks(true, 6);
+    wait_for_usable_channels(node1_addr, 2).await;
+    wait_for_usable_channels(node2_addr, 1).await;
+    wait_for_usable_channels(node3_addr, 1).await;
+}
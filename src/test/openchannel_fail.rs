@@ -29,11 +29,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(0),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -60,11 +69,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(s!("bad asset ID")),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -91,11 +109,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -122,11 +149,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -153,11 +189,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: false,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -184,11 +229,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(2000),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -210,11 +264,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -241,11 +304,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: Some(s!("ttoooosshhoorrtt")),
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -268,11 +340,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -288,11 +369,20 @@ async fn open_fail() {
         push_msat: 3_500_000,
         asset_amount: Some(100),
         asset_id: Some(asset_id),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -0,0 +1,141 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/openchannel_funding_outpoints/";
+
+async fn open_channel_from_outpoints_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    asset_id: Option<&str>,
+    funding_outpoints: Vec<String>,
+) -> reqwest::Response {
+    println!("opening channel from outpoints {funding_outpoints:?} on node {node_address}");
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", peer_pubkey, NODE2_PEER_PORT),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: asset_id.map(|_| 600),
+        asset_id: asset_id.map(|a| a.to_string()),
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: Some(funding_outpoints),
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn openchannel_funding_outpoints() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // both outputs of a payment to self belong to the vanilla wallet
+    let self_address = address(node1_addr).await;
+    let txid = send_btc(node1_addr, 200_000, &self_address).await;
+    mine(false);
+    let funding_outpoint = format!("{txid}:0");
+
+    let res = open_channel_from_outpoints_raw(
+        node1_addr,
+        &node2_pubkey,
+        Some(&asset_id),
+        vec![funding_outpoint.clone()],
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot open channel: funding outpoints can only be set for vanilla channels",
+    )
+    .await;
+
+    let res = open_channel_from_outpoints_raw(node1_addr, &node2_pubkey, None, vec![]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid funding outpoints: at least one outpoint must be given",
+    )
+    .await;
+
+    let res = open_channel_from_outpoints_raw(
+        node1_addr,
+        &node2_pubkey,
+        None,
+        vec![funding_outpoint.clone(), funding_outpoint.clone()],
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!("Invalid funding outpoints: duplicate outpoint {funding_outpoint}"),
+    )
+    .await;
+
+    let unknown_outpoint = format!("{txid}:7");
+    let res = open_channel_from_outpoints_raw(
+        node1_addr,
+        &node2_pubkey,
+        None,
+        vec![unknown_outpoint.clone()],
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!(
+            "Invalid funding outpoints: {unknown_outpoint} is not a confirmed UTXO of the vanilla wallet"
+        ),
+    )
+    .await;
+
+    println!("\nopening vanilla channel from outpoint {funding_outpoint}");
+    stop_mining();
+    let res =
+        open_channel_from_outpoints_raw(node1_addr, &node2_pubkey, None, vec![funding_outpoint])
+            .await;
+    _check_response_is_ok(res).await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels.first().and_then(|c| c.funding_txid.clone()) {
+            if !_get_txout(&funding_txid).is_empty() {
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    // the requested UTXO has been spent by the funding TX
+    assert!(_get_txout(&txid).is_empty());
+    mine_n_blocks(true, 6);
+    wait_for_usable_channels(node1_addr, 1).await;
+}
@@ -30,11 +30,20 @@ async fn openchannel_optional_addr_forward() {
         push_msat: 3_500_000,
         asset_amount: Some(600),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node1_addr))
@@ -112,11 +121,20 @@ async fn openchannel_optional_addr_reverse() {
         push_msat: 3_500_000,
         asset_amount: Some(600),
         asset_id: Some(asset_id.clone()),
-        public: true,
+        public: Some(true),
         with_anchors: true,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{}/openchannel", node2_addr))
@@ -0,0 +1,84 @@
+use lightning::ln::channelmanager::PaymentId;
+use lightning::util::ser::{Readable, Writeable};
+use std::collections::HashMap;
+
+use crate::disk::{OUTBOUND_PAYMENTS_FNAME, OUTBOUND_PAYMENTS_NAMESPACE};
+use crate::ldk::{OutboundPaymentInfoStorage, PaymentInfo};
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_entries/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_entries() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(3000000), None, None, 900).await;
+    let payment_1 = send_payment(node1_addr, invoice).await;
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(4000000), None, None, 900).await;
+    let payment_2 = send_payment(node1_addr, invoice).await;
+
+    println!("\neach outbound payment is stored in its own entry");
+    let ldk_data_dir = PathBuf::from(&test_dir_node1).join(LDK_DIR);
+    let payments_dir = ldk_data_dir.join(OUTBOUND_PAYMENTS_NAMESPACE);
+    let payment_1_path = payments_dir.join(&payment_1.payment_hash);
+    let payment_2_path = payments_dir.join(&payment_2.payment_hash);
+    assert!(payment_1_path.is_file());
+    assert!(payment_2_path.is_file());
+    assert!(!ldk_data_dir.join(OUTBOUND_PAYMENTS_FNAME).exists());
+
+    println!("\nmove the payments stored in a single entry to their own entries");
+    shutdown(&[node1_addr]).await;
+    let mut legacy = OutboundPaymentInfoStorage {
+        payments: HashMap::new(),
+    };
+    for (payment_hash, path) in [
+        (&payment_1.payment_hash, &payment_1_path),
+        (&payment_2.payment_hash, &payment_2_path),
+    ] {
+        let payment_id = PaymentId(hex_str_to_vec(payment_hash).unwrap().try_into().unwrap());
+        let data = std::fs::read(path).unwrap();
+        let payment_info = PaymentInfo::read(&mut &data[..]).unwrap();
+        legacy.payments.insert(payment_id, payment_info);
+    }
+    std::fs::remove_dir_all(&payments_dir).unwrap();
+    std::fs::write(ldk_data_dir.join(OUTBOUND_PAYMENTS_FNAME), legacy.encode()).unwrap();
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, true).await;
+    assert!(payment_1_path.is_file());
+    assert!(payment_2_path.is_file());
+    assert!(!ldk_data_dir.join(OUTBOUND_PAYMENTS_FNAME).exists());
+    let payments = list_payments(node1_addr).await;
+    for payment in [&payment_1, &payment_2] {
+        assert!(payments
+            .iter()
+            .any(|p| p.payment_hash == payment.payment_hash
+                && !p.inbound
+                && p.status == HTLCStatus::Succeeded));
+    }
+}
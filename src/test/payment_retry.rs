@@ -0,0 +1,95 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_retry/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_retry() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the node-wide default retry policy is used when none is provided
+    let payment = keysend(node1_addr, &node2_pubkey, None, None, None).await;
+    assert_eq!(payment.retry_attempts, None);
+    assert_eq!(payment.retry_timeout_secs, Some(10));
+    assert_eq!(payment.failed_attempts, 0);
+
+    let invoice = ln_invoice(node2_addr, Some(50000), None, None, 900)
+        .await
+        .invoice;
+    let payload = SendPaymentRequest {
+        invoice: invoice.clone(),
+        amt_msat: None,
+        asset_amount: None,
+        retry_attempts: Some(3),
+        retry_timeout_secs: Some(30),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid payment retry: retry attempts and retry timeout cannot be set together",
+    )
+    .await;
+
+    let payload = SendPaymentRequest {
+        invoice,
+        amt_msat: None,
+        asset_amount: None,
+        retry_attempts: Some(3),
+        retry_timeout_secs: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let send_payment = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap();
+    let payment = _wait_for_ln_payment(
+        node1_addr,
+        &send_payment.payment_hash.unwrap(),
+        HTLCStatus::Succeeded,
+    )
+    .await;
+    assert_eq!(payment.retry_attempts, Some(3));
+    assert_eq!(payment.retry_timeout_secs, None);
+    assert_eq!(payment.failed_attempts, 0);
+
+    // retry details are only tracked for outbound payments
+    let payment =
+        _wait_for_ln_payment(node2_addr, &payment.payment_hash, HTLCStatus::Succeeded).await;
+    assert!(payment.inbound);
+    assert_eq!(payment.retry_attempts, None);
+    assert_eq!(payment.retry_timeout_secs, None);
+}
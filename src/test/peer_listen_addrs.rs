@@ -0,0 +1,50 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_listen_addrs/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_listen_addrs() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    // node 1 listens on two loopback sockets
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        ldk_peer_listen_addrs: vec![
+            SocketAddr::from(([127, 0, 0, 1], NODE1_PEER_PORT)),
+            SocketAddr::from(([127, 0, 0, 1], NODE4_PEER_PORT)),
+        ],
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+
+    connect_peer(
+        node2_addr,
+        &node1_pubkey,
+        &format!("127.0.0.1:{NODE1_PEER_PORT}"),
+    )
+    .await;
+    connect_peer(
+        node3_addr,
+        &node1_pubkey,
+        &format!("127.0.0.1:{NODE4_PEER_PORT}"),
+    )
+    .await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    while list_peers(node1_addr).await.len() < 2 {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("peers didn't connect on both listen addresses")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
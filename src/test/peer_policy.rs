@@ -0,0 +1,185 @@
+use serde::Serialize;
+
+use crate::routes::{BanPeerRequest, PeerPolicyResponse, SetPeerPolicyRequest, UnbanPeerRequest};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_policy/";
+
+async fn get_peer_policy(node_address: SocketAddr) -> PeerPolicyResponse {
+    println!("getting the peer policy of node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/peers/policy", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PeerPolicyResponse>()
+        .await
+        .unwrap()
+}
+
+async fn post_peer_policy_raw<T: Serialize>(
+    node_address: SocketAddr,
+    path: &str,
+    payload: &T,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/peers/policy/{path}", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn post_peer_policy<T: Serialize>(node_address: SocketAddr, path: &str, payload: &T) {
+    println!("calling /peers/policy/{path} on node {node_address}");
+    let res = post_peer_policy_raw(node_address, path, payload).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn connect_peer_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    peer_port: u16,
+) -> reqwest::Response {
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: format!("{peer_pubkey}@127.0.0.1:{peer_port}"),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/connectpeer", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn has_peer(node_address: SocketAddr, pubkey: &str) -> bool {
+    list_peers(node_address)
+        .await
+        .iter()
+        .any(|p| p.pubkey == pubkey)
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_policy() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let policy = get_peer_policy(node1_addr).await;
+    assert!(policy.allowed.is_empty() && policy.bans.is_empty());
+    assert!(policy.max_peers.is_none());
+
+    println!("\nbanned peers are disconnected and cannot reconnect");
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+    let payload = BanPeerRequest {
+        pubkey: node2_pubkey.clone(),
+        duration_secs: None,
+    };
+    post_peer_policy(node1_addr, "ban", &payload).await;
+    assert!(!has_peer(node1_addr, &node2_pubkey).await);
+    let res = connect_peer_raw(node1_addr, &node2_pubkey, NODE2_PEER_PORT).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        &format!("Peer not allowed: peer {node2_pubkey} is banned"),
+    )
+    .await;
+    let _ = connect_peer_raw(node2_addr, &node1_pubkey, NODE1_PEER_PORT).await;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    assert!(!has_peer(node1_addr, &node2_pubkey).await);
+    let policy = get_peer_policy(node1_addr).await;
+    assert_eq!(policy.bans.len(), 1);
+    assert_eq!(policy.bans[0].pubkey, node2_pubkey);
+    assert!(policy.bans[0].expires_at.is_none());
+
+    println!("\nunbanned peers can connect again");
+    let payload = UnbanPeerRequest {
+        pubkey: node2_pubkey.clone(),
+    };
+    post_peer_policy(node1_addr, "unban", &payload).await;
+    let res = post_peer_policy_raw(node1_addr, "unban", &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The peer is not banned",
+    )
+    .await;
+    connect_peer(
+        node2_addr,
+        &node1_pubkey,
+        &format!("127.0.0.1:{NODE1_PEER_PORT}"),
+    )
+    .await;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    assert!(has_peer(node1_addr, &node2_pubkey).await);
+    disconnect_peer(node1_addr, &node2_pubkey).await;
+
+    println!("\nonly allowed peers are accepted");
+    let payload = SetPeerPolicyRequest {
+        allowed: vec![node3_pubkey.clone()],
+        max_peers: Some(0),
+    };
+    let res = post_peer_policy_raw(node1_addr, "update", &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid peer policy: the max number of peers must be greater than 0",
+    )
+    .await;
+    let payload = SetPeerPolicyRequest {
+        allowed: vec![node3_pubkey.clone()],
+        max_peers: None,
+    };
+    post_peer_policy(node1_addr, "update", &payload).await;
+    let res = connect_peer_raw(node1_addr, &node2_pubkey, NODE2_PEER_PORT).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        &format!("Peer not allowed: peer {node2_pubkey} is not in the allowed list"),
+    )
+    .await;
+    connect_peer(
+        node1_addr,
+        &node3_pubkey,
+        &format!("127.0.0.1:{NODE3_PEER_PORT}"),
+    )
+    .await;
+    assert_eq!(
+        get_peer_policy(node1_addr).await.allowed,
+        vec![node3_pubkey]
+    );
+
+    println!("\ninbound connections are dropped above the max number of peers");
+    let payload = SetPeerPolicyRequest {
+        allowed: vec![],
+        max_peers: Some(1),
+    };
+    post_peer_policy(node1_addr, "update", &payload).await;
+    let _ = connect_peer_raw(node2_addr, &node1_pubkey, NODE1_PEER_PORT).await;
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    assert!(!has_peer(node1_addr, &node2_pubkey).await);
+    assert_eq!(list_peers(node1_addr).await.len(), 1);
+}
@@ -0,0 +1,57 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/pending_intercepts/";
+
+async fn pending_intercepts(node_address: SocketAddr) -> Vec<PendingIntercept> {
+    println!("listing pending intercepts for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/pendingintercepts", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PendingInterceptsResponse>()
+        .await
+        .unwrap()
+        .intercepts
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn pending_intercepts_api() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    assert!(pending_intercepts(node1_addr).await.is_empty());
+
+    let payload = FailInterceptRequest {
+        intercept_id: s!("invalid"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/failintercept", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid intercept ID",
+    )
+    .await;
+
+    let payload = FailInterceptRequest {
+        intercept_id: s!("0d5b6c0a3b1e8f2c4d7a9e6b5c3f1a2d8e4b7c9a6f3d1e5b2c8a4f7d9e6b3c1a"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/failintercept", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown intercept ID").await;
+}
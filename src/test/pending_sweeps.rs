@@ -0,0 +1,103 @@
+use crate::routes::{PendingSweep, PendingSweepsResponse, SweepStatus};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/pending_sweeps/";
+
+async fn pending_sweeps(node_address: SocketAddr) -> Vec<PendingSweep> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/pendingsweeps", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PendingSweepsResponse>()
+        .await
+        .unwrap()
+        .sweeps
+}
+
+async fn wait_for_sweep_status(
+    node_address: SocketAddr,
+    channel_id: &str,
+    expected_status: SweepStatus,
+) -> PendingSweep {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(sweep) = pending_sweeps(node_address)
+            .await
+            .into_iter()
+            .find(|s| s.channel_id.as_deref() == Some(channel_id) && s.status == expected_status)
+        {
+            return sweep;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("sweep of channel {channel_id} is not {expected_status:?}")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn pending_sweeps_after_force_close() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    assert!(pending_sweeps(node1_addr).await.is_empty());
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, true).await;
+
+    // the delayed output has matured and its sweep has been broadcast
+    let sweep = wait_for_sweep_status(
+        node1_addr,
+        &channel.channel_id,
+        SweepStatus::PendingConfirmation,
+    )
+    .await;
+    assert_eq!(sweep.asset_id, Some(asset_id.clone()));
+    assert_eq!(sweep.asset_amount, Some(600));
+    assert!(sweep.amount_sat > 0);
+    assert!(sweep.maturity_height.is_some());
+    assert!(sweep.spending_txid.is_some());
+    assert!(sweep.confirmation_height.is_none());
+
+    mine(false);
+    let sweep =
+        wait_for_sweep_status(node1_addr, &channel.channel_id, SweepStatus::Confirmed).await;
+    assert!(sweep.confirmation_height.is_some());
+
+    // sweeps stop being tracked once they can no longer be reorged out
+    mine_n_blocks(false, 6);
+    let t_0 = OffsetDateTime::now_utc();
+    while !pending_sweeps(node1_addr).await.is_empty() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("sweep is still tracked")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    wait_for_balance(node1_addr, &asset_id, 1000).await;
+}
@@ -0,0 +1,83 @@
+use crate::routes::{
+    CreateScheduleRequest, CreateScheduleResponse, ListSchedulesResponse, Schedule,
+    ScheduleTargetType,
+};
+use crate::utils::{get_current_timestamp, LDK_DIR};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/persistence_errors/";
+
+async fn create_schedule_raw(node_address: SocketAddr, target: &str) -> reqwest::Response {
+    let payload = CreateScheduleRequest {
+        target_type: ScheduleTargetType::Keysend,
+        target: target.to_string(),
+        amt_msat: 50000,
+        asset_id: None,
+        asset_amount: None,
+        interval_secs: 3600,
+        start_at: Some(get_current_timestamp() + 3600),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/createschedule", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_schedules(node_address: SocketAddr) -> Vec<Schedule> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/schedules", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSchedulesResponse>()
+        .await
+        .unwrap()
+        .schedules
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn persistence_errors() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+
+    println!("\nfail to persist a schedule");
+    // a directory where the schedules are stored makes their writes fail
+    let schedules_path = PathBuf::from(&test_dir_node1)
+        .join(LDK_DIR)
+        .join("schedules");
+    std::fs::create_dir_all(schedules_path.join("blocker")).unwrap();
+    let res = create_schedule_raw(node1_addr, &node1_pubkey).await;
+    assert_eq!(res.status(), reqwest::StatusCode::INTERNAL_SERVER_ERROR);
+    let api_error_response = res.json::<APIErrorResponse>().await.unwrap();
+    assert!(api_error_response
+        .error
+        .starts_with("Failed to persist the node data: "));
+    assert!(list_schedules(node1_addr).await.is_empty());
+
+    println!("\npersist a schedule once the store is writable again");
+    std::fs::remove_dir_all(&schedules_path).unwrap();
+    let res = create_schedule_raw(node1_addr, &node1_pubkey).await;
+    let CreateScheduleResponse { schedule_id } = _check_response_is_ok(res)
+        .await
+        .json::<CreateScheduleResponse>()
+        .await
+        .unwrap();
+    assert!(schedules_path.is_file());
+
+    println!("\nthe schedule survives a restart");
+    shutdown(&[node1_addr]).await;
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, true).await;
+    let schedules = list_schedules(node1_addr).await;
+    assert_eq!(schedules.len(), 1);
+    assert_eq!(schedules[0].schedule_id, schedule_id);
+}
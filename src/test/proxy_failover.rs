@@ -0,0 +1,67 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/proxy_failover/";
+
+/// Nothing listens here, so the proxy is always down
+const DOWN_PROXY_ENDPOINT: &str = "rpc://127.0.0.1:3999/json-rpc";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn proxy_failover() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        proxy_endpoints: vec![
+            DOWN_PROXY_ENDPOINT.to_string(),
+            PROXY_ENDPOINT_REGTEST.to_string(),
+        ],
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // invoices only list the proxies that are up
+    let invoice = rgb_invoice(node1_addr, None).await.invoice;
+    let decoded = decode_rgb_invoice(node1_addr, &invoice).await;
+    assert_eq!(decoded.transport_endpoints, vec![PROXY_ENDPOINT_REGTEST]);
+
+    // the channel is funded through the first proxy that is up
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let funding_txid = list_channels(node1_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap()
+        .funding_txid;
+    let funding_transfer = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.txid == funding_txid)
+        .unwrap();
+    assert_eq!(
+        funding_transfer.consignment_proxy.as_deref(),
+        Some("http://127.0.0.1:3000/json-rpc")
+    );
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 400);
+}
@@ -0,0 +1,141 @@
+use crate::routes::{
+    ListProxyPinsResponse, PinProxyRequest, ProxyPinType, ReceiveMode, UnpinProxyRequest,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/proxy_pins/";
+
+const PINNED_PROXY_ENDPOINT: &str = "rpcs://proxy.example.com/json-rpc";
+const PIN_SHA256: &str = "4a6cc6e7e8d4e4d0b5c0f0a3c8f1e5bc1b0d49a0c2f84c3e6a53b2d1f8a7e901";
+
+async fn pin_proxy_raw(
+    node_address: SocketAddr,
+    proxy_endpoint: &str,
+    sha256: &str,
+) -> reqwest::Response {
+    println!("pinning proxy {proxy_endpoint} on node {node_address}");
+    let payload = PinProxyRequest {
+        proxy_endpoint: proxy_endpoint.to_string(),
+        pin_type: ProxyPinType::PublicKey,
+        sha256: sha256.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/pinproxy", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_proxy_pins(node_address: SocketAddr) -> ListProxyPinsResponse {
+    println!("listing proxy pins for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listproxypins", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListProxyPinsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn unpin_proxy_raw(node_address: SocketAddr, proxy_endpoint: &str) -> reqwest::Response {
+    println!("unpinning proxy {proxy_endpoint} on node {node_address}");
+    let payload = UnpinProxyRequest {
+        proxy_endpoint: proxy_endpoint.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/unpinproxy", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn proxy_pins() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    assert!(list_proxy_pins(node1_addr).await.pins.is_empty());
+
+    // plain HTTP endpoints and malformed hashes cannot be pinned
+    let res = pin_proxy_raw(node1_addr, PROXY_ENDPOINT_REGTEST, PIN_SHA256).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid proxy pin: only TLS (rpcs) endpoints can be pinned",
+    )
+    .await;
+    let res = pin_proxy_raw(node1_addr, PINNED_PROXY_ENDPOINT, "deadbeef").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid proxy pin: sha256 must be a 32-byte hex string",
+    )
+    .await;
+
+    let res = pin_proxy_raw(node1_addr, PINNED_PROXY_ENDPOINT, PIN_SHA256).await;
+    _check_response_is_ok(res).await;
+    let pins = list_proxy_pins(node1_addr).await.pins;
+    assert_eq!(pins.len(), 1);
+    assert_eq!(pins[0].proxy, "proxy.example.com:443");
+    assert_eq!(pins[0].pin_type, ProxyPinType::PublicKey);
+    assert_eq!(pins[0].sha256, PIN_SHA256);
+
+    // pins are kept across restarts
+    lock(node1_addr).await;
+    unlock(node1_addr, &password).await;
+    assert_eq!(list_proxy_pins(node1_addr).await.pins.len(), 1);
+
+    // the regtest proxy is not pinned, so no consignment can be exchanged with it
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/refreshtransfers", node1_addr))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        &format!("Cannot use proxy: {PROXY_ENDPOINT_REGTEST} is not pinned"),
+    )
+    .await;
+    let payload = RgbInvoiceRequest {
+        min_confirmations: 1,
+        asset_id: None,
+        duration_seconds: None,
+        receive_mode: ReceiveMode::Blinded,
+        transport_endpoints: vec![],
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/rgbinvoice", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        &format!("Cannot use proxy: {PROXY_ENDPOINT_REGTEST} is not pinned"),
+    )
+    .await;
+
+    let res = unpin_proxy_raw(node1_addr, PINNED_PROXY_ENDPOINT).await;
+    _check_response_is_ok(res).await;
+    assert!(list_proxy_pins(node1_addr).await.pins.is_empty());
+    let res = unpin_proxy_raw(node1_addr, PINNED_PROXY_ENDPOINT).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown proxy pin").await;
+
+    // without pins any proxy is accepted again
+    refresh_transfers(node1_addr).await;
+    rgb_invoice(node1_addr, None).await;
+}
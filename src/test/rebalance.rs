@@ -0,0 +1,156 @@
+use crate::routes::{RebalanceRequest, RebalanceResponse, HTLC_MIN_MSAT};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/rebalance/";
+
+async fn rebalance_raw(node_address: SocketAddr, payload: &RebalanceRequest) -> reqwest::Response {
+    println!(
+        "rebalancing {} msat and {:?} assets from channel {} to channel {} on node {node_address}",
+        payload.amt_msat,
+        payload.asset_amount,
+        payload.outbound_channel_id,
+        payload.inbound_channel_id
+    );
+    reqwest::Client::new()
+        .post(format!("http://{}/rebalance", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn rebalance(node_address: SocketAddr, payload: &RebalanceRequest) -> RebalanceResponse {
+    let res = rebalance_raw(node_address, payload).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<RebalanceResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn rebalance_channels() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 200, recipient_id).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    wait_for_balance(node2_addr, &asset_id, 200).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel_12 = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let channel_21 = open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        None,
+        None,
+        Some(100),
+        Some(&asset_id),
+    )
+    .await;
+
+    println!("\nrebalancing assets from channel 12 to channel 21");
+    let RebalanceResponse {
+        payment_hash,
+        fee_msat,
+        status,
+    } = rebalance(
+        node1_addr,
+        &RebalanceRequest {
+            outbound_channel_id: channel_12.channel_id.clone(),
+            inbound_channel_id: channel_21.channel_id.clone(),
+            amt_msat: HTLC_MIN_MSAT,
+            asset_amount: Some(50),
+            max_fee_msat: 10000,
+        },
+    )
+    .await;
+    assert_eq!(status, HTLCStatus::Pending);
+    assert!(fee_msat > 0);
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    let payments: Vec<Payment> = list_payments(node1_addr)
+        .await
+        .into_iter()
+        .filter(|p| p.payment_hash == payment_hash)
+        .collect();
+    assert_eq!(payments.len(), 2);
+    assert!(payments
+        .iter()
+        .all(|p| p.status == HTLCStatus::Succeeded && p.amt_msat == Some(HTLC_MIN_MSAT)));
+
+    let channels = list_channels(node1_addr).await;
+    let chan_12 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_12.channel_id)
+        .unwrap();
+    let chan_21 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_21.channel_id)
+        .unwrap();
+    assert_eq!(chan_12.asset_local_amount, Some(550));
+    assert_eq!(chan_21.asset_local_amount, Some(50));
+
+    let res = rebalance_raw(
+        node1_addr,
+        &RebalanceRequest {
+            outbound_channel_id: channel_12.channel_id.clone(),
+            inbound_channel_id: channel_21.channel_id.clone(),
+            amt_msat: HTLC_MIN_MSAT,
+            asset_amount: Some(50),
+            max_fee_msat: 0,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!("Invalid rebalance: fee of {fee_msat} msat exceeds max_fee_msat"),
+    )
+    .await;
+
+    let res = rebalance_raw(
+        node1_addr,
+        &RebalanceRequest {
+            outbound_channel_id: channel_12.channel_id.clone(),
+            inbound_channel_id: channel_12.channel_id,
+            amt_msat: HTLC_MIN_MSAT,
+            asset_amount: None,
+            max_fee_msat: 10000,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid rebalance: outbound and inbound channels must be different",
+    )
+    .await;
+}
@@ -0,0 +1,66 @@
+use crate::routes::TransferKind;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/receive_witness/";
+
+async fn rgb_invoice_witness(node_address: SocketAddr, asset_id: &str) -> RgbInvoiceResponse {
+    println!("generating witness RGB invoice for node {node_address}");
+    let payload = RgbInvoiceRequest {
+        min_confirmations: 1,
+        asset_id: Some(asset_id.to_string()),
+        duration_seconds: Some(3600),
+        receive_mode: ReceiveMode::Witness,
+        transport_endpoints: vec![PROXY_ENDPOINT_REGTEST.to_string()],
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/rgbinvoice", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<RgbInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn receive_witness() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    // the receiver doesn't need UTXOs of its own
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let RgbInvoiceResponse {
+        recipient_id,
+        invoice,
+        expiration_timestamp,
+    } = rgb_invoice_witness(node2_addr, &asset_id).await;
+    assert!(expiration_timestamp.is_some());
+    let decoded = decode_rgb_invoice(node2_addr, &invoice).await;
+    assert_eq!(decoded.recipient_id, recipient_id);
+    assert_eq!(decoded.transport_endpoints, vec![PROXY_ENDPOINT_REGTEST]);
+
+    send_asset(node1_addr, &asset_id, 400, recipient_id).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 600);
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 400);
+
+    let transfers = list_transfers(node2_addr, &asset_id).await;
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].kind, TransferKind::ReceiveWitness);
+}
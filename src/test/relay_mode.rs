@@ -0,0 +1,95 @@
+use crate::disk::RELAY_KEYS_FNAME;
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/relay_mode/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn relay_mode() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node2.clone().into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        relay_mode: true,
+        encrypt_storage: true,
+        ..Default::default()
+    };
+    let (node2_addr, password) = start_node_with_args(args, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3500000),
+        None,
+        None,
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        Some(3500000),
+        None,
+        None,
+    )
+    .await;
+
+    println!("\nlocking the relay node");
+    lock(node2_addr).await;
+    // the relay keys are only kept in memory
+    assert!(!PathBuf::from(&test_dir_node2)
+        .join(LDK_DIR)
+        .join(RELAY_KEYS_FNAME)
+        .exists());
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/nodeinfo", node2_addr))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Node is locked (hint: call unlock)",
+    )
+    .await;
+
+    // a second lock is refused as the node is already locked
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/lock", node2_addr))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Node is locked (hint: call unlock)",
+    )
+    .await;
+
+    println!("\npaying through the locked node");
+    let LNInvoiceResponse { invoice } = ln_invoice(node3_addr, None, None, None, 900).await;
+    let _ = send_payment(node1_addr, invoice).await;
+
+    println!("\nunlocking the relay node");
+    unlock(node2_addr, &password).await;
+    wait_for_usable_channels(node2_addr, 2).await;
+}
@@ -0,0 +1,132 @@
+use crate::routes::{NodeIdRotationStatus, RotateNodeIdResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/rotate_node_id/";
+
+async fn rotate_node_id(node_address: SocketAddr) -> RotateNodeIdResponse {
+    println!("rotating node ID of node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/rotatenodeid", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<RotateNodeIdResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn rotate_node_id_roundtrip() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    println!("\nstarting rotation");
+    stop_mining();
+    let rotation = rotate_node_id(node1_addr).await;
+    assert_eq!(rotation.status, NodeIdRotationStatus::ClosingChannels);
+    assert_eq!(rotation.old_pubkey, node1_pubkey);
+    assert_eq!(rotation.new_pubkey, None);
+    assert_eq!(rotation.channels_left, 1);
+
+    // no channels can be opened while the rotation is in progress
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot open channel: a node ID rotation is in progress",
+    )
+    .await;
+
+    // calls resume the rotation until the funds are back in the on-chain wallet
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        mine_n_blocks(true, 1);
+        let rotation = rotate_node_id(node1_addr).await;
+        if rotation.status == NodeIdRotationStatus::RestartRequired {
+            assert_eq!(rotation.channels_left, 0);
+            assert_eq!(rotation.balances_left, 0);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 120.0 {
+            panic!("node ID rotation is taking too long")
+        }
+    }
+    assert_eq!(node_info(node1_addr).await.pubkey, node1_pubkey);
+
+    println!("\nrestarting with the new node key");
+    lock(node1_addr).await;
+    unlock(node1_addr, &password).await;
+    let new_pubkey = node_info(node1_addr).await.pubkey;
+    assert_ne!(new_pubkey, node1_pubkey);
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    // the new key is kept across restarts
+    lock(node1_addr).await;
+    unlock(node1_addr, &password).await;
+    assert_eq!(node_info(node1_addr).await.pubkey, new_pubkey);
+
+    // channels can be opened again with the new node ID
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+}
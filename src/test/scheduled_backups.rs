@@ -0,0 +1,105 @@
+use crate::routes::{ListBackupsResponse, ScheduledBackup};
+use crate::scheduled_backup::{BackupSchedule, BackupTarget};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/scheduled_backups/";
+
+async fn list_backups(node_address: SocketAddr) -> Vec<ScheduledBackup> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/backups", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListBackupsResponse>()
+        .await
+        .unwrap()
+        .backups
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn scheduled_backups() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let backup_dir = PathBuf::from(format!("{TEST_DIR_BASE}backups"));
+    let node1_args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        backup_schedule: Some(BackupSchedule {
+            target: BackupTarget::Dir(backup_dir.clone()),
+            interval: std::time::Duration::from_secs(10),
+            retention: 2,
+        }),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(node1_args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    println!("\nbackups are made in the background and the oldest ones deleted");
+    let first_backup = {
+        let t_0 = OffsetDateTime::now_utc();
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            if let Some(backup) = list_backups(node1_addr).await.into_iter().next() {
+                break backup;
+            }
+            if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+                panic!("no backup has been made")
+            }
+        }
+    };
+    let t_0 = OffsetDateTime::now_utc();
+    let backups = loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let backups = list_backups(node1_addr).await;
+        if backups.iter().all(|b| b.name != first_backup.name) {
+            break backups;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("the first backup has not been deleted")
+        }
+    };
+    assert_eq!(backups.len(), 2);
+    assert!(!backup_dir.join(&first_backup.name).exists());
+    let mut stored: Vec<String> = std::fs::read_dir(&backup_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    stored.sort();
+    let mut listed: Vec<String> = backups.iter().map(|b| b.name.clone()).collect();
+    listed.sort();
+    assert_eq!(stored, listed);
+
+    println!("\nbackups are encrypted");
+    for backup in &backups {
+        assert_eq!(
+            PathBuf::from(&backup.location),
+            backup_dir.join(&backup.name)
+        );
+        let data = std::fs::read(&backup.location).unwrap();
+        assert_eq!(data.len() as u64, backup.size);
+        assert!(data.starts_with(b"RLNE"));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::routes::{
+    CreateScheduleRequest, CreateScheduleResponse, DeleteScheduleRequest, ListSchedulesResponse,
+    Schedule, ScheduleTargetType, UpdateScheduleRequest,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/schedules/";
+
+async fn create_schedule_raw(
+    node_address: SocketAddr,
+    target: &str,
+    amt_msat: u64,
+    interval_secs: u64,
+) -> reqwest::Response {
+    println!("scheduling payments of {amt_msat} msat to {target} from node {node_address}");
+    let payload = CreateScheduleRequest {
+        target_type: ScheduleTargetType::Keysend,
+        target: target.to_string(),
+        amt_msat,
+        asset_id: None,
+        asset_amount: None,
+        interval_secs,
+        start_at: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/createschedule", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_schedules(node_address: SocketAddr) -> Vec<Schedule> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/schedules", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSchedulesResponse>()
+        .await
+        .unwrap()
+        .schedules
+}
+
+async fn update_schedule_raw(
+    node_address: SocketAddr,
+    schedule_id: &str,
+    enabled: Option<bool>,
+    asset_amount: Option<u64>,
+) -> reqwest::Response {
+    let payload = UpdateScheduleRequest {
+        schedule_id: schedule_id.to_string(),
+        amt_msat: None,
+        asset_amount,
+        interval_secs: None,
+        enabled,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/updateschedule", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn delete_schedule_raw(node_address: SocketAddr, schedule_id: &str) -> reqwest::Response {
+    let payload = DeleteScheduleRequest {
+        schedule_id: schedule_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/deleteschedule", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn schedules() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let res = create_schedule_raw(node1_addr, &node2_pubkey, 50000, 30).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid schedule: interval cannot be less than 60 seconds",
+    )
+    .await;
+    let res = create_schedule_raw(node1_addr, "invalid", 50000, 60).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid pubkey").await;
+
+    println!("\nscheduling a recurring keysend");
+    let res = create_schedule_raw(node1_addr, &node2_pubkey, 50000, 3600).await;
+    let CreateScheduleResponse { schedule_id } = _check_response_is_ok(res)
+        .await
+        .json::<CreateScheduleResponse>()
+        .await
+        .unwrap();
+
+    // the first payment is due immediately
+    let t_0 = OffsetDateTime::now_utc();
+    let schedule = loop {
+        let schedule = list_schedules(node1_addr).await.pop().unwrap();
+        if !schedule.runs.is_empty() {
+            break schedule;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("scheduled payment has not been run")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(schedule.schedule_id, schedule_id);
+    assert_eq!(schedule.runs.len(), 1);
+    let run = &schedule.runs[0];
+    assert!(run.error.is_none());
+    assert!(schedule.next_run_at >= run.ran_at + 3600);
+    let payment_hash = run.payment_hash.clone().unwrap();
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    println!("\npausing the schedule");
+    let res = update_schedule_raw(node1_addr, &schedule_id, Some(false), None).await;
+    _check_response_is_ok(res).await;
+    let res = update_schedule_raw(node1_addr, &schedule_id, None, Some(10)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid schedule: cannot set an asset amount for a schedule without an asset",
+    )
+    .await;
+
+    // schedules are persisted
+    lock(node1_addr).await;
+    unlock(node1_addr, &password).await;
+    let schedules = list_schedules(node1_addr).await;
+    assert_eq!(schedules.len(), 1);
+    assert!(!schedules[0].enabled);
+    assert_eq!(schedules[0].runs.len(), 1);
+
+    println!("\ndeleting the schedule");
+    let res = delete_schedule_raw(node1_addr, &schedule_id).await;
+    _check_response_is_ok(res).await;
+    assert!(list_schedules(node1_addr).await.is_empty());
+    let res = delete_schedule_raw(node1_addr, &schedule_id).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown schedule").await;
+}
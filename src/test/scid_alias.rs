@@ -0,0 +1,89 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/scid_alias/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn scid_alias() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    println!("\nopening private channel");
+    stop_mining();
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{}@127.0.0.1:{}", node2_pubkey, NODE2_PEER_PORT),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(false),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels.first().and_then(|c| c.funding_txid.clone()) {
+            if !_get_txout(&funding_txid).is_empty() {
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    mine_n_blocks(true, 6);
+    wait_for_usable_channels(node1_addr, 1).await;
+    wait_for_usable_channels(node2_addr, 1).await;
+
+    // each side knows the alias assigned by the other one
+    let channel_1 = list_channels(node1_addr).await.remove(0);
+    let channel_2 = list_channels(node2_addr).await.remove(0);
+    assert!(!channel_2.public);
+    let inbound_alias = channel_2.inbound_scid_alias.unwrap();
+    assert_eq!(channel_1.outbound_scid_alias, Some(inbound_alias));
+    assert_eq!(channel_2.outbound_scid_alias, channel_1.inbound_scid_alias);
+    assert_ne!(channel_2.short_channel_id, Some(inbound_alias));
+
+    println!("\ninvoices embed the alias instead of the real SCID");
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(3000000), None, None, 900).await;
+    let route_hint_scids: Vec<u64> = Bolt11Invoice::from_str(&invoice)
+        .unwrap()
+        .route_hints()
+        .iter()
+        .map(|h| h.0[0].short_channel_id)
+        .collect();
+    assert_eq!(route_hint_scids, vec![inbound_alias]);
+
+    send_payment(node1_addr, invoice).await;
+}
@@ -0,0 +1,111 @@
+use crate::routes::SendToLnAddressRequest;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/send_to_ln_address/";
+
+const NODE2_DAEMON_PORT: u16 = 3102;
+
+async fn send_to_ln_address_raw(
+    node_address: SocketAddr,
+    ln_address: &str,
+    amt_msat: u64,
+) -> reqwest::Response {
+    println!("paying {amt_msat} msat to {ln_address} from node {node_address}");
+    let payload = SendToLnAddressRequest {
+        ln_address: ln_address.to_string(),
+        amt_msat,
+        asset_id: None,
+        asset_amount: None,
+        retry_attempts: None,
+        retry_timeout_secs: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/sendtolnaddress", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn send_to_ln_address() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node2.into(),
+        daemon_listening_port: NODE2_DAEMON_PORT,
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        lnurl_base_url: Some(format!("http://127.0.0.1:{NODE2_DAEMON_PORT}")),
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let ln_address = format!("alice@127.0.0.1:{NODE2_DAEMON_PORT}");
+
+    println!("\npaying the lightning address");
+    let res = send_to_ln_address_raw(node1_addr, &ln_address, 50000).await;
+    let payment = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap();
+    let payment_hash = payment.payment_hash.unwrap();
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    assert!(list_payments(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.payment_hash == payment_hash && !p.inbound));
+
+    // amounts outside of the advertised range are refused before requesting an invoice
+    let res = send_to_ln_address_raw(node1_addr, &ln_address, 500).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid amount: amount must be between 1000 and 100000000 msat",
+    )
+    .await;
+
+    let res = send_to_ln_address_raw(node1_addr, "alice", 50000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid lightning address: must be in the user@domain format",
+    )
+    .await;
+
+    // errors returned by the LNURL service are reported
+    let res = send_to_ln_address_raw(
+        node2_addr,
+        &format!("alice@127.0.0.1:{}", node1_addr.port()),
+        50000,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed LNURL request: LNURL support is disabled (hint: set --lnurl-base-url)",
+    )
+    .await;
+}
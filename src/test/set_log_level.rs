@@ -0,0 +1,80 @@
+use tracing_subscriber::{reload, EnvFilter};
+
+use crate::routes::{SetLogLevelRequest, SetLogLevelResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/set_log_level/";
+
+async fn set_log_level_raw(node_address: SocketAddr, filter: &str) -> reqwest::Response {
+    println!("setting log filter {filter} on node {node_address}");
+    let payload = SetLogLevelRequest {
+        filter: filter.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/setloglevel", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn set_log_level(node_address: SocketAddr, filter: &str) -> String {
+    let res = set_log_level_raw(node_address, filter).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<SetLogLevelResponse>()
+        .await
+        .unwrap()
+        .previous_filter
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn set_log_level_at_runtime() {
+    initialize();
+
+    // the tests install their own logger, so the reloadable filter is kept apart
+    let (_file_log_filter, log_filter) = reload::Layer::new(EnvFilter::new("debug"));
+    let node1_addr = start_daemon_with_args(LdkUserInfo {
+        storage_dir_path: format!("{TEST_DIR_BASE}node1").into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        log_filter: Some(log_filter.clone()),
+        ..Default::default()
+    })
+    .await;
+    let node2_addr = start_daemon(&format!("{TEST_DIR_BASE}node2"), NODE2_PEER_PORT).await;
+
+    println!("\nchange the filter and restore it");
+    let previous_filter = set_log_level(node1_addr, "info,rgb_lightning_node::ldk=trace").await;
+    assert_eq!(previous_filter, "debug");
+    let current_filter = log_filter.with_current(|f| f.to_string()).unwrap();
+    assert!(current_filter.contains("rgb_lightning_node::ldk=trace"));
+    let previous_filter = set_log_level(node1_addr, "debug").await;
+    assert_eq!(previous_filter, current_filter);
+    let current_filter = log_filter.with_current(|f| f.to_string()).unwrap();
+    assert_eq!(current_filter, "debug");
+
+    println!("\ninvalid filters are refused");
+    let res = set_log_level_raw(node1_addr, " ").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid log filter: cannot be empty",
+    )
+    .await;
+    let res = set_log_level_raw(node1_addr, "lightning=loud").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let current_filter = log_filter.with_current(|f| f.to_string()).unwrap();
+    assert_eq!(current_filter, "debug");
+
+    println!("\nthe filter cannot be changed without a reloadable logger");
+    let res = set_log_level_raw(node2_addr, "debug").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot set log level: the log filter is not reloadable",
+    )
+    .await;
+}
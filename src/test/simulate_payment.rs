@@ -0,0 +1,123 @@
+use crate::routes::{SimulatePaymentRequest, SimulatePaymentResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/simulate_payment/";
+
+async fn simulate_payment_raw(
+    node_address: SocketAddr,
+    dest_pubkey: &str,
+    amt_msat: u64,
+) -> reqwest::Response {
+    println!("simulating a payment of {amt_msat} msat to {dest_pubkey} from node {node_address}");
+    let payload = SimulatePaymentRequest {
+        dest_pubkey: dest_pubkey.to_string(),
+        amt_msat,
+        asset_id: None,
+        asset_amount: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/simulate/payment", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn simulate_payment(
+    node_address: SocketAddr,
+    dest_pubkey: &str,
+    amt_msat: u64,
+) -> SimulatePaymentResponse {
+    let res = simulate_payment_raw(node_address, dest_pubkey, amt_msat).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<SimulatePaymentResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn simulate_payment_multihop() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        Some(3500000),
+        None,
+        None,
+    )
+    .await;
+
+    // wait for the second channel to reach the network graph of node 1
+    let t_0 = OffsetDateTime::now_utc();
+    let simulation = loop {
+        let simulation = simulate_payment(node1_addr, &node3_pubkey, 3000000).await;
+        if simulation.routable {
+            break simulation;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("route to node 3 is taking too long to become available")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(simulation.hops.len(), 2);
+    assert_eq!(simulation.hops[0].pubkey, node2_pubkey);
+    assert_eq!(simulation.hops[1].pubkey, node3_pubkey);
+    assert_eq!(simulation.hops[1].amt_msat, 3000000);
+    assert_eq!(simulation.hops[1].fee_msat, 0);
+    assert_eq!(simulation.fee_msat, simulation.hops[0].fee_msat);
+    assert_eq!(simulation.hops[0].amt_msat, 3000000 + simulation.fee_msat);
+    // our own channel is known to have enough liquidity
+    assert_eq!(simulation.hops[0].success_probability, 1.0);
+    assert!(simulation.success_probability > 0.0 && simulation.success_probability < 1.0);
+
+    // simulations don't send anything
+    assert!(list_payments(node1_addr).await.is_empty());
+
+    // amounts exceeding the available liquidity cannot be routed
+    let simulation = simulate_payment(node1_addr, &node3_pubkey, 1_000_000_000_000).await;
+    assert!(!simulation.routable);
+    assert_eq!(simulation.success_probability, 0.0);
+    assert!(simulation.hops.is_empty());
+
+    let res = simulate_payment_raw(node1_addr, "invalid", 3000000).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid pubkey").await;
+
+    let res = simulate_payment_raw(node1_addr, &node3_pubkey, 1000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid amount: amt_msat cannot be less than 3000000",
+    )
+    .await;
+}
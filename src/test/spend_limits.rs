@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use crate::routes::{ConfirmSpendRequest, ConfirmSpendResponse};
+use crate::spend_limits::{AssetSpendLimits, SpendLimits};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/spend_limits/";
+
+fn node1_args(test_dir: &str, assets: HashMap<String, AssetSpendLimits>) -> LdkUserInfo {
+    LdkUserInfo {
+        storage_dir_path: test_dir.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        spend_limits: Some(SpendLimits {
+            max_payment_sat: Some(50_000),
+            daily_limit_sat: Some(80_000),
+            assets,
+        }),
+        ..Default::default()
+    }
+}
+
+async fn send_btc_raw(node_address: SocketAddr, amount: u64, address: &str) -> reqwest::Response {
+    println!("sending {amount} on-chain BTC from node {node_address} to address {address}");
+    let payload = SendBtcRequest {
+        amount,
+        address: address.to_string(),
+        fee_rate: Some(FEE_RATE),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/sendbtc", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn confirm_spend_raw(
+    node_address: SocketAddr,
+    token: &str,
+    password: &str,
+) -> reqwest::Response {
+    println!("confirming spend {token} for node {node_address}");
+    let payload = ConfirmSpendRequest {
+        token: token.to_string(),
+        password: password.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/confirmspend", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn confirm_spend(
+    node_address: SocketAddr,
+    token: &str,
+    password: &str,
+) -> ConfirmSpendResponse {
+    let res = confirm_spend_raw(node_address, token, password).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<ConfirmSpendResponse>()
+        .await
+        .unwrap()
+}
+
+/// Check that the operation has been held for the given reason, returning its token
+async fn check_spend_held(res: reqwest::Response, reason: &str) -> String {
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+    let api_error_response = res.json::<APIErrorResponse>().await.unwrap();
+    assert_eq!(
+        api_error_response.error,
+        format!("Spend limits exceeded ({reason}), the operation needs to be confirmed")
+    );
+    api_error_response.confirmation_token.unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn spend_limits() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) =
+        start_node_with_args(node1_args(&test_dir_node1, HashMap::new()), false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_address = address(node2_addr).await;
+
+    println!("\nsends within the limits are not held");
+    send_btc(node1_addr, 10_000, &node2_address).await;
+
+    println!("\nhold a send above the max payment until it's confirmed");
+    let res = send_btc_raw(node1_addr, 60_000, &node2_address).await;
+    let token = check_spend_held(res, "60000 sat is above the max payment of 50000 sat").await;
+    let res = confirm_spend_raw(node1_addr, &token, "wrong password").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+    )
+    .await;
+    let response = confirm_spend(node1_addr, &token, &node1_password).await;
+    assert!(response.send_btc.is_some());
+    assert!(response.keysend.is_none() && response.send_payment.is_none());
+    let res = confirm_spend_raw(node1_addr, &token, &node1_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown or expired spend confirmation token",
+    )
+    .await;
+
+    println!("\nhold a send bringing the spends above the daily limit");
+    let res = send_btc_raw(node1_addr, 20_000, &node2_address).await;
+    check_spend_held(
+        res,
+        "20000 sat would bring the spends of the last 24 hours above the daily limit of 80000 sat",
+    )
+    .await;
+
+    println!("\nthe daily totals and the asset limits apply after a restart");
+    shutdown(&[node1_addr]).await;
+    let assets = HashMap::from([(
+        asset_id.clone(),
+        AssetSpendLimits {
+            max_payment: Some(50),
+            daily_limit: None,
+        },
+    )]);
+    let (node1_addr, _) = start_node_with_args(node1_args(&test_dir_node1, assets), true).await;
+    let res = send_btc_raw(node1_addr, 20_000, &node2_address).await;
+    check_spend_held(
+        res,
+        "20000 sat would bring the spends of the last 24 hours above the daily limit of 80000 sat",
+    )
+    .await;
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    let payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        amount: 100,
+        recipient_id,
+        donation: true,
+        fee_rate: Some(FEE_RATE),
+        min_confirmations: 1,
+        transport_endpoints: vec![PROXY_ENDPOINT_REGTEST.to_string()],
+        witness_amount_sat: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/sendasset", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let token = check_spend_held(
+        res,
+        &format!("100 of asset {asset_id} is above the max payment of 50"),
+    )
+    .await;
+    let response = confirm_spend(node1_addr, &token, &node1_password).await;
+    assert!(response.send_asset.is_some());
+
+    println!("\nrefuse pushing an amount above the limits when opening a channel");
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 60_000_000,
+        asset_amount: None,
+        asset_id: None,
+        public: Some(true),
+        with_anchors: true,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        change_address: None,
+        close_address: None,
+        funding_outpoints: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        htlc_minimum_msat: None,
+        channel_reserve_proportional_millionths: None,
+        fee_rate: None,
+        external_signing: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/openchannel", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Spend limits exceeded: 60000 sat is above the max payment of 50000 sat",
+    )
+    .await;
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    println!("\nhold a send whose amount overflows the daily total");
+    shutdown(&[node1_addr]).await;
+    let args = LdkUserInfo {
+        spend_limits: Some(SpendLimits {
+            max_payment_sat: None,
+            daily_limit_sat: Some(80_000),
+            assets: HashMap::new(),
+        }),
+        ..node1_args(&test_dir_node1, HashMap::new())
+    };
+    let (node1_addr, _) = start_node_with_args(args, true).await;
+    let amount = u64::MAX - 1_000;
+    let res = send_btc_raw(node1_addr, amount, &node2_address).await;
+    check_spend_held(
+        res,
+        &format!(
+            "{amount} sat would bring the spends of the last 24 hours above the daily limit of \
+            80000 sat"
+        ),
+    )
+    .await;
+}
@@ -0,0 +1,118 @@
+use crate::kv_store::SQLITE_STORE_FNAME;
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/sqlite_store/";
+
+async fn unlock_raw(node_address: SocketAddr, password: &str) -> reqwest::Response {
+    let payload = UnlockRequest {
+        password: password.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/unlock", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_channel_ready(node_address: SocketAddr, channel_id: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node_address).await;
+        let channel = channels
+            .iter()
+            .find(|c| c.channel_id == channel_id)
+            .unwrap();
+        if channel.ready {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("cannot find re-established channel")
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn sqlite_store() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let node1_args = || LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        store_backend: StoreBackend::Sqlite,
+        ..Default::default()
+    };
+    let (node1_addr, node1_password) = start_node_with_args(node1_args(), false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let ldk_data_dir = PathBuf::from(&test_dir_node1).join(LDK_DIR);
+    assert!(ldk_data_dir.join(SQLITE_STORE_FNAME).exists());
+    assert!(!ldk_data_dir.join("manager").exists());
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+
+    println!("\nrestart the node with the SQLite store");
+    shutdown(&[node1_addr]).await;
+    let (node1_addr, _) = start_node_with_args(node1_args(), true).await;
+    wait_for_channel_ready(node1_addr, &channel.channel_id).await;
+    assert!(!ldk_data_dir.join("manager").exists());
+    let payments = list_payments(node1_addr).await;
+    let restored_payment = payments
+        .iter()
+        .find(|p| p.payment_hash == payment.payment_hash)
+        .unwrap();
+    assert_eq!(restored_payment.status, HTLCStatus::Succeeded);
+    assert_eq!(restored_payment.asset_amount, Some(100));
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+
+    println!("\nrefuse to switch the backend of existing data");
+    shutdown(&[node1_addr]).await;
+    let node1_addr = start_daemon_with_args(LdkUserInfo {
+        store_backend: StoreBackend::Filesystem,
+        ..node1_args()
+    })
+    .await;
+    let res = unlock_raw(node1_addr, &node1_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to start LDK: the node data is stored in SQLite, start with --store-backend sqlite",
+    )
+    .await;
+    shutdown(&[node1_addr]).await;
+
+    println!("\nclose the channel after a last restart");
+    let (node1_addr, _) = start_node_with_args(node1_args(), true).await;
+    wait_for_channel_ready(node1_addr, &channel.channel_id).await;
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+}
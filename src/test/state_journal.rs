@@ -0,0 +1,108 @@
+use crate::journal::{read_journal, JournalEntryKind, JournalRecord, STATE_JOURNAL_FNAME};
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/state_journal/";
+
+fn journaled_statuses(
+    test_dir: &str,
+    kind: JournalEntryKind,
+    id: &str,
+) -> (Vec<JournalRecord>, Vec<String>) {
+    let journal_path = PathBuf::from(test_dir)
+        .join(LDK_DIR)
+        .join(STATE_JOURNAL_FNAME);
+    let records = read_journal(&journal_path).unwrap();
+    let statuses = records
+        .iter()
+        .filter(|r| r.kind == kind && r.id == id)
+        .map(|r| r.status.clone())
+        .collect();
+    (records, statuses)
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn state_journal() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    println!("\njournal the status changes of a payment on both sides");
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    let (_, statuses) = journaled_statuses(
+        &test_dir_node1,
+        JournalEntryKind::OutboundPayment,
+        &payment.payment_hash,
+    );
+    assert_eq!(statuses, vec![s!("Pending"), s!("Succeeded")]);
+    let (_, statuses) = journaled_statuses(
+        &test_dir_node2,
+        JournalEntryKind::InboundPayment,
+        &payment.payment_hash,
+    );
+    assert_eq!(statuses.first().unwrap(), "Pending");
+    assert_eq!(statuses.last().unwrap(), "Succeeded");
+
+    println!("\nkeep only the in-flight entries after a restart");
+    let LNInvoiceResponse { invoice } = ln_invoice(node1_addr, Some(50000), None, None, 900).await;
+    let pending_hash = Bolt11Invoice::from_str(&invoice)
+        .unwrap()
+        .payment_hash()
+        .to_string();
+    // a change journaled right before a crash, never applied to the store
+    let unknown_id = "cd".repeat(32);
+    let journal_path = PathBuf::from(&test_dir_node1)
+        .join(LDK_DIR)
+        .join(STATE_JOURNAL_FNAME);
+    let mut journal = std::fs::read_to_string(&journal_path).unwrap();
+    journal.push_str(&format!(
+        "{{\"timestamp\":0,\"kind\":\"outbound_payment\",\"id\":\"{unknown_id}\",\"status\":\"Pending\"}}\n"
+    ));
+    std::fs::write(&journal_path, journal).unwrap();
+    shutdown(&[node1_addr]).await;
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, true).await;
+    let (records, statuses) = journaled_statuses(
+        &test_dir_node1,
+        JournalEntryKind::InboundPayment,
+        &pending_hash,
+    );
+    assert_eq!(statuses, vec![s!("Pending")]);
+    assert_eq!(records.len(), 1);
+
+    println!("\njournal the changes made after the restart");
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    let (_, statuses) = journaled_statuses(
+        &test_dir_node1,
+        JournalEntryKind::OutboundPayment,
+        &payment.payment_hash,
+    );
+    assert_eq!(statuses, vec![s!("Pending"), s!("Succeeded")]);
+}
@@ -0,0 +1,65 @@
+use crate::routes::ListPaymentsResponse;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/state_snapshots/";
+
+async fn list_payments_response(node_address: SocketAddr) -> ListPaymentsResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listpayments", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListPaymentsResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn state_snapshots() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3500000),
+        None,
+        None,
+    )
+    .await;
+
+    // nothing changed, the same snapshot is shared by all reads
+    let before = list_payments_response(node1_addr).await;
+    assert!(before.payments.is_empty());
+    assert_eq!(
+        list_payments_response(node1_addr).await.snapshot_id,
+        before.snapshot_id
+    );
+    assert_eq!(list_swaps(node1_addr).await.snapshot_id, before.snapshot_id);
+
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    send_payment(node1_addr, invoice).await;
+
+    let after = list_payments_response(node1_addr).await;
+    assert_eq!(after.payments.len(), 1);
+    assert!(after.snapshot_id > before.snapshot_id);
+    assert_eq!(
+        list_payments_response(node1_addr).await.snapshot_id,
+        after.snapshot_id
+    );
+}
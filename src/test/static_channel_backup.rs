@@ -0,0 +1,148 @@
+use crate::routes::{BackupScbRequest, RestoreScbRequest, RestoreScbResponse, ScbChannelStatus};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/static_channel_backup/";
+
+async fn backup_scb_raw(
+    node_address: SocketAddr,
+    backup_path: &str,
+    password: &str,
+) -> reqwest::Response {
+    println!("performing static channel backup for node {node_address} on {backup_path}");
+    let payload = BackupScbRequest {
+        backup_path: backup_path.to_string(),
+        password: password.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/backup/scb", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn restore_scb_raw(
+    node_address: SocketAddr,
+    backup_path: &str,
+    password: &str,
+) -> reqwest::Response {
+    println!("recovering channels for node {node_address} from {backup_path}");
+    let payload = RestoreScbRequest {
+        backup_path: backup_path.to_string(),
+        password: password.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/restore/scb", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn static_channel_backup() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, node2_password) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_backup_path = format!("{TEST_DIR_BASE}node1_backup");
+    let node1_scb_path = format!("{TEST_DIR_BASE}node1_scb");
+    for path in [&node1_backup_path, &node1_scb_path] {
+        if Path::new(path).exists() {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    // a wallet backup taken before the channel is opened will lack its state
+    lock(node1_addr).await;
+    backup(node1_addr, &node1_backup_path, &node1_password).await;
+    unlock(node1_addr, &node1_password).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let res = backup_scb_raw(node1_addr, &node1_scb_path, "wrong password").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+    )
+    .await;
+    let res = backup_scb_raw(node1_addr, &node1_scb_path, &node1_password).await;
+    _check_response_is_ok(res).await;
+
+    // the backup can only be used by the node it was made for
+    let res = restore_scb_raw(node2_addr, &node1_scb_path, &node1_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid static channel backup: backup belongs to another node",
+    )
+    .await;
+    let res = restore_scb_raw(node2_addr, &node1_backup_path, &node2_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid static channel backup: file is not a static channel backup",
+    )
+    .await;
+
+    // channels that are still open are left alone
+    let res = restore_scb_raw(node1_addr, &node1_scb_path, &node1_password).await;
+    let recovery = _check_response_is_ok(res)
+        .await
+        .json::<RestoreScbResponse>()
+        .await
+        .unwrap();
+    assert_eq!(recovery.channels.len(), 1);
+    assert_eq!(recovery.channels[0].status, ScbChannelStatus::Open);
+
+    println!("\nlosing the channel state of node1");
+    shutdown(&[node1_addr]).await;
+    std::fs::remove_dir_all(&test_dir_node1).unwrap();
+    let node1_addr = start_daemon(&test_dir_node1, NODE1_PEER_PORT).await;
+    restore(node1_addr, &node1_backup_path, &node1_password).await;
+    unlock(node1_addr, &node1_password).await;
+    assert!(list_channels(node1_addr).await.is_empty());
+
+    let res = restore_scb_raw(node1_addr, &node1_scb_path, &node1_password).await;
+    let recovery = _check_response_is_ok(res)
+        .await
+        .json::<RestoreScbResponse>()
+        .await
+        .unwrap();
+    assert_eq!(recovery.channels.len(), 1);
+    let recovered = &recovery.channels[0];
+    assert_eq!(recovered.channel_id, channel.channel_id);
+    assert_eq!(recovered.peer_pubkey, node2_pubkey);
+    assert_eq!(recovered.status, ScbChannelStatus::ForceCloseRequested);
+
+    // the peer force-closes the channel
+    let t_0 = OffsetDateTime::now_utc();
+    while !list_channels(node2_addr).await.is_empty() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel has not been force-closed by the peer")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
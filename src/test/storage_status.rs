@@ -0,0 +1,97 @@
+use lightning::rgb_utils::get_rgb_channel_info_path;
+
+use crate::routes::StorageStatusResponse;
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/storage_status/";
+
+async fn get_storage_status(node_address: SocketAddr) -> StorageStatusResponse {
+    println!("getting storage status for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/storagestatus", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<StorageStatusResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn storage_status() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    println!("\nthe storage of a node with an open channel is consistent");
+    let status = get_storage_status(node1_addr).await;
+    assert!(status.consistency.consistent);
+    assert!(status.disk_usage.total_bytes > 0);
+    assert!(status.disk_usage.ldk_data_bytes > 0);
+    assert!(status.disk_usage.ldk_data_bytes < status.disk_usage.total_bytes);
+    let manager = status.stores.iter().find(|s| s.name == "manager").unwrap();
+    assert!(manager.last_persisted_at > 0);
+    assert!(status.stores.iter().any(|s| s.name == "monitors"));
+
+    println!("\nflag the files not matching any channel");
+    let ldk_data_dir = PathBuf::from(&test_dir_node1).join(LDK_DIR);
+    let unknown_id = "ab".repeat(32);
+    let orphaned_psbt = format!("psbt_{unknown_id}");
+    let orphaned_consignment = format!("consignment_{unknown_id}");
+    std::fs::write(ldk_data_dir.join(&orphaned_psbt), "").unwrap();
+    std::fs::write(ldk_data_dir.join(&orphaned_consignment), "").unwrap();
+    let orphaned_info_path = get_rgb_channel_info_path(&unknown_id, &ldk_data_dir, false);
+    std::fs::write(&orphaned_info_path, "{}").unwrap();
+    let status = get_storage_status(node1_addr).await;
+    assert!(!status.consistency.consistent);
+    assert_eq!(
+        status.consistency.orphaned_files,
+        vec![orphaned_consignment.clone(), orphaned_psbt.clone()]
+    );
+    assert_eq!(
+        status.consistency.orphaned_rgb_info_files,
+        vec![orphaned_info_path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()]
+    );
+    assert!(status.consistency.channels_without_monitor.is_empty());
+    assert!(status.consistency.stale_channel_ids.is_empty());
+
+    println!("\nthe storage is consistent again once the files are removed");
+    std::fs::remove_file(ldk_data_dir.join(&orphaned_psbt)).unwrap();
+    std::fs::remove_file(ldk_data_dir.join(&orphaned_consignment)).unwrap();
+    std::fs::remove_file(&orphaned_info_path).unwrap();
+    let status = get_storage_status(node1_addr).await;
+    assert!(status.consistency.consistent);
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+}
@@ -0,0 +1,224 @@
+use crate::routes::{
+    ListSubmarineSwapsResponse, LoopInRequest, LoopInResponse, LoopOutRequest, LoopOutResponse,
+    SubmarineSwap, SubmarineSwapKind, SubmarineSwapStatus,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/submarine_swaps/";
+
+async fn loop_in_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    amount_sat: u64,
+) -> reqwest::Response {
+    println!(
+        "starting loop in of {amount_sat} sat with peer {peer_pubkey} for node {node_address}"
+    );
+    let payload = LoopInRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        amount_sat,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/loopin", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn loop_in(node_address: SocketAddr, peer_pubkey: &str, amount_sat: u64) -> String {
+    let res = loop_in_raw(node_address, peer_pubkey, amount_sat).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<LoopInResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+}
+
+async fn loop_out_raw(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    amount_sat: u64,
+) -> reqwest::Response {
+    println!(
+        "starting loop out of {amount_sat} sat with peer {peer_pubkey} for node {node_address}"
+    );
+    let payload = LoopOutRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        amount_sat,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/loopout", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn loop_out(node_address: SocketAddr, peer_pubkey: &str, amount_sat: u64) -> String {
+    let res = loop_out_raw(node_address, peer_pubkey, amount_sat).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<LoopOutResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+}
+
+async fn get_submarine_swap(node_address: SocketAddr, payment_hash: &str) -> SubmarineSwap {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listsubmarineswaps", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSubmarineSwapsResponse>()
+        .await
+        .unwrap()
+        .swaps
+        .into_iter()
+        .find(|s| s.payment_hash == payment_hash)
+        .unwrap()
+}
+
+async fn wait_for_submarine_swap_status(
+    node_address: SocketAddr,
+    payment_hash: &str,
+    expected_status: SubmarineSwapStatus,
+) -> SubmarineSwap {
+    println!(
+        "waiting for status for submarine swap with payment hash {payment_hash} to become \
+        {expected_status:?} on node {node_address}",
+    );
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let swap = get_submarine_swap(node_address, payment_hash).await;
+        if swap.status == expected_status {
+            return swap;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 70.0 {
+            panic!(
+                "status ({:?}) is not becoming the expected one ({expected_status:?})",
+                swap.status
+            );
+        }
+        tokio::time::sleep(std::time::Duration::from_secs_f32(0.5)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn submarine_swaps() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        submarine_swap_server: true,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(1000000),
+        Some(400000000),
+        None,
+        None,
+    )
+    .await;
+
+    println!("\nloop out");
+    let payment_hash = loop_out(node2_addr, &node1_pubkey, 50000).await;
+    wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Funded).await;
+    let swap =
+        wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Funded)
+            .await;
+    assert_eq!(swap.kind, SubmarineSwapKind::LoopOut);
+    assert!(!swap.server);
+    assert_eq!(swap.peer_pubkey, node1_pubkey);
+    assert_eq!(swap.amount_sat, 50000);
+    assert!(swap.htlc_address.is_some());
+    assert!(swap.funding_txid.is_some());
+    mine(false);
+    let swap =
+        wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Claiming)
+            .await;
+    assert!(swap.spending_txid.is_some());
+    mine(false);
+    wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Succeeded).await;
+    let swap =
+        wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Succeeded)
+            .await;
+    assert!(swap.server);
+    assert_eq!(swap.failure_reason, None);
+
+    println!("\nloop in");
+    let payment_hash = loop_in(node2_addr, &node1_pubkey, 30000).await;
+    wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Funded).await;
+    wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Funded).await;
+    mine(false);
+    let swap =
+        wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Claiming)
+            .await;
+    assert_eq!(swap.kind, SubmarineSwapKind::LoopIn);
+    assert!(swap.spending_txid.is_some());
+    wait_for_submarine_swap_status(node2_addr, &payment_hash, SubmarineSwapStatus::Succeeded).await;
+    mine(false);
+    wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Succeeded).await;
+    let payments = list_payments(node2_addr).await;
+    let payment = payments
+        .iter()
+        .find(|p| p.payment_hash == payment_hash)
+        .unwrap();
+    assert!(payment.inbound);
+    assert_eq!(payment.status, HTLCStatus::Succeeded);
+
+    println!("\nswap refused by a peer not providing submarine swaps");
+    let payment_hash = loop_out(node1_addr, &node2_pubkey, 20000).await;
+    let swap =
+        wait_for_submarine_swap_status(node1_addr, &payment_hash, SubmarineSwapStatus::Failed)
+            .await;
+    assert_eq!(
+        swap.failure_reason.unwrap(),
+        "the swap has been refused by the server: the node doesn't provide submarine swaps"
+    );
+
+    println!("\ninvalid swaps");
+    let res = loop_in_raw(node2_addr, &node1_pubkey, 9999).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid amount: Submarine swap amount must be equal or higher than 10000",
+    )
+    .await;
+    let res = loop_out_raw(node2_addr, "invalid", 20000).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid pubkey").await;
+    let res = loop_out_raw(
+        node2_addr,
+        "03b79a4bc1ec365524b4fab9a39eb133753646babb5a1da5c4bc94c53110b7795d",
+        20000,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot start submarine swap: peer is not connected",
+    )
+    .await;
+}
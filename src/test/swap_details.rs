@@ -0,0 +1,216 @@
+use crate::routes::{GetSwapRequest, GetSwapResponse};
+use crate::utils::get_current_timestamp;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/swap_details/";
+
+async fn get_swap_raw(node_address: SocketAddr, payment_hash: &str) -> reqwest::Response {
+    println!("getting swap {payment_hash} from node {node_address}");
+    let payload = GetSwapRequest {
+        payment_hash: payment_hash.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/getswap", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn get_swap(node_address: SocketAddr, payment_hash: &str) -> GetSwapResponse {
+    let res = get_swap_raw(node_address, payment_hash).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<GetSwapResponse>()
+        .await
+        .unwrap()
+}
+
+async fn list_swaps_filtered(node_address: SocketAddr, query: &str) -> ListSwapsResponse {
+    println!("listing swaps matching {query} for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listswaps?{query}", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await.json().await.unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swap_details() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(5000000),
+        Some(546000),
+        None,
+        None,
+    )
+    .await;
+
+    let maker_addr = node1_addr;
+    let taker_addr = node2_addr;
+
+    println!("\nswap rejected by the taker");
+    let maker_init_response = maker_init(maker_addr, 50000, None, 10, Some(&asset_id), 3600).await;
+    // the taker expects more assets than the maker will send
+    let forged_swapstring = maker_init_response.swapstring.replacen("/10/", "/20/", 1);
+    taker(taker_addr, forged_swapstring).await;
+    maker_execute(
+        maker_addr,
+        maker_init_response.swapstring,
+        maker_init_response.payment_secret,
+        node2_pubkey.clone(),
+    )
+    .await;
+    let failed_hash = maker_init_response.payment_hash;
+    wait_for_swap_status(taker_addr, &failed_hash, SwapStatus::Failed).await;
+    wait_for_swap_status(maker_addr, &failed_hash, SwapStatus::Failed).await;
+
+    let GetSwapResponse {
+        swap,
+        taker: is_taker,
+    } = get_swap(taker_addr, &failed_hash).await;
+    assert!(is_taker);
+    assert_eq!(swap.payment_hash, failed_hash);
+    assert_eq!(swap.qty_to, 20);
+    assert_eq!(swap.status, SwapStatus::Failed);
+    let transitions = swap
+        .transitions
+        .iter()
+        .map(|t| t.status.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(transitions, vec![SwapStatus::Waiting, SwapStatus::Failed]);
+    assert_eq!(swap.transitions.last().unwrap().reason, swap.failure_reason);
+    assert!(swap
+        .failure_reason
+        .unwrap()
+        .starts_with("the HTLC doesn't match the whitelisted swap"));
+    let GetSwapResponse {
+        swap,
+        taker: is_taker,
+    } = get_swap(maker_addr, &failed_hash).await;
+    assert!(!is_taker);
+    assert_eq!(swap.status, SwapStatus::Failed);
+    assert!(swap
+        .failure_reason
+        .unwrap()
+        .starts_with("the payment failed"));
+
+    println!("\nsuccessful swap");
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    let t_swap = get_current_timestamp();
+    let maker_init_response = maker_init(maker_addr, 50000, None, 10, Some(&asset_id), 3600).await;
+    taker(taker_addr, maker_init_response.swapstring.clone()).await;
+    maker_execute(
+        maker_addr,
+        maker_init_response.swapstring.clone(),
+        maker_init_response.payment_secret.clone(),
+        node2_pubkey.clone(),
+    )
+    .await;
+    let succeeded_hash = maker_init_response.payment_hash;
+    wait_for_swap_status(taker_addr, &succeeded_hash, SwapStatus::Succeeded).await;
+    let swap = get_swap(taker_addr, &succeeded_hash).await.swap;
+    assert_eq!(swap.status, SwapStatus::Succeeded);
+    assert_eq!(swap.failure_reason, None);
+    wait_for_swap_status(maker_addr, &succeeded_hash, SwapStatus::Succeeded).await;
+    let swap = get_swap(maker_addr, &succeeded_hash).await.swap;
+    let transitions = swap
+        .transitions
+        .iter()
+        .map(|t| t.status.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        transitions,
+        vec![
+            SwapStatus::Waiting,
+            SwapStatus::Pending,
+            SwapStatus::Succeeded
+        ]
+    );
+    assert!(swap
+        .transitions
+        .windows(2)
+        .all(|t| t[0].timestamp <= t[1].timestamp));
+
+    println!("\nexecute a completed swap again");
+    let res = maker_execute_raw(
+        maker_addr,
+        maker_init_response.swapstring,
+        maker_init_response.payment_secret,
+        node2_pubkey.clone(),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid swap: cannot move the swap from Succeeded to Pending",
+    )
+    .await;
+
+    println!("\nfilter swaps");
+    let swaps = list_swaps_filtered(taker_addr, "status=Failed").await;
+    assert!(swaps.maker.is_empty());
+    assert_eq!(swaps.taker.len(), 1);
+    assert_eq!(swaps.taker.first().unwrap().payment_hash, failed_hash);
+    let swaps = list_swaps_filtered(maker_addr, "status=Succeeded").await;
+    assert_eq!(swaps.maker.len(), 1);
+    assert_eq!(swaps.maker.first().unwrap().payment_hash, succeeded_hash);
+    let swaps = list_swaps_filtered(taker_addr, &format!("asset_id={asset_id}")).await;
+    assert_eq!(swaps.taker.len(), 2);
+    let swaps = list_swaps_filtered(taker_addr, "asset_id=unknown").await;
+    assert!(swaps.taker.is_empty());
+    let swaps = list_swaps_filtered(taker_addr, &format!("from_timestamp={t_swap}")).await;
+    assert_eq!(swaps.taker.len(), 1);
+    assert_eq!(swaps.taker.first().unwrap().payment_hash, succeeded_hash);
+    let swaps = list_swaps_filtered(taker_addr, &format!("to_timestamp={}", t_swap - 1)).await;
+    assert_eq!(swaps.taker.len(), 1);
+    assert_eq!(swaps.taker.first().unwrap().payment_hash, failed_hash);
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listswaps?status=Unknown", taker_addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    println!("\nget unknown swaps");
+    let res = get_swap_raw(taker_addr, "invalid").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid payment hash",
+    )
+    .await;
+    let res = get_swap_raw(taker_addr, &"00".repeat(32)).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown swap").await;
+}
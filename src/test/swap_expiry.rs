@@ -0,0 +1,56 @@
+use crate::utils::get_current_timestamp;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/swap_expiry/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swap_expiry() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let maker_addr = node1_addr;
+    let taker_addr = node2_addr;
+
+    let maker_init_response = maker_init(maker_addr, 50000, None, 10, Some(&asset_id), 2).await;
+    taker(taker_addr, maker_init_response.swapstring.clone()).await;
+
+    // swaps get expired in the background, without anything else happening
+    tokio::time::sleep(Duration::from_secs(15)).await;
+    let listed_at = get_current_timestamp();
+
+    for swap in [
+        list_swaps(maker_addr).await.maker,
+        list_swaps(taker_addr).await.taker,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        assert_eq!(swap.payment_hash, maker_init_response.payment_hash);
+        assert_eq!(swap.status, SwapStatus::Expired);
+        assert!(swap.completed_at.unwrap() < listed_at);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::routes::{
+    AcceptSwapOfferRequest, ListSwapOffersResponse, PostSwapOfferRequest, PostSwapOfferResponse,
+    SwapOffer,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/swap_offers/";
+
+async fn post_swap_offer_raw(
+    node_address: SocketAddr,
+    payload: &PostSwapOfferRequest,
+) -> reqwest::Response {
+    println!("posting swap offer on node {node_address}");
+    reqwest::Client::new()
+        .post(format!("http://{}/postswapoffer", node_address))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn accept_swap_offer_raw(
+    node_address: SocketAddr,
+    offer_id: &str,
+    qty_from: u64,
+) -> reqwest::Response {
+    println!("accepting swap offer {offer_id} for {qty_from} from node {node_address}");
+    let payload = AcceptSwapOfferRequest {
+        offer_id: offer_id.to_string(),
+        qty_from,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/acceptswapoffer", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_swap_offers(node_address: SocketAddr) -> Vec<SwapOffer> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/listswapoffers", node_address))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSwapOffersResponse>()
+        .await
+        .unwrap()
+        .offers
+}
+
+async fn wait_for_swap_offer(node_address: SocketAddr, offer_id: &str) -> SwapOffer {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(offer) = list_swap_offers(node_address)
+            .await
+            .into_iter()
+            .find(|o| o.offer_id == offer_id)
+        {
+            return offer;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("swap offer {offer_id} has not been received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swap_offers() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(5000000),
+        Some(546000),
+        None,
+        None,
+    )
+    .await;
+
+    let maker_addr = node1_addr;
+    let taker_addr = node2_addr;
+
+    println!("\npost invalid swap offers");
+    let mut payload = PostSwapOfferRequest {
+        from_asset: None,
+        to_asset: None,
+        price_from: 5000,
+        price_to: 1,
+        min_qty_from: 10000,
+        max_qty_from: 100000,
+        total_qty_from: None,
+        expiry_sec: 3600,
+    };
+    let res = post_swap_offer_raw(maker_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid swap: cannot swap BTC for BTC",
+    )
+    .await;
+    payload.to_asset = Some(asset_id.clone());
+    payload.min_qty_from = 200000;
+    let res = post_swap_offer_raw(maker_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid swap offer: min_qty_from must be positive and not higher than max_qty_from",
+    )
+    .await;
+
+    println!("\npost swap offer");
+    payload.min_qty_from = 10000;
+    let res = post_swap_offer_raw(maker_addr, &payload).await;
+    let offer_id = _check_response_is_ok(res)
+        .await
+        .json::<PostSwapOfferResponse>()
+        .await
+        .unwrap()
+        .offer_id;
+
+    let offer = wait_for_swap_offer(taker_addr, &offer_id).await;
+    assert_eq!(offer.maker_pubkey, node1_pubkey);
+    assert_eq!(offer.from_asset, None);
+    assert_eq!(offer.to_asset, Some(asset_id.clone()));
+    assert_eq!(offer.price_from, 5000);
+    assert_eq!(offer.price_to, 1);
+    assert_eq!(offer.min_qty_from, 10000);
+    assert_eq!(offer.max_qty_from, 100000);
+    assert!(offer.acceptances.is_empty());
+
+    println!("\naccept swap offer");
+    let res = accept_swap_offer_raw(taker_addr, "unknown", 50000).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Unknown swap offer").await;
+    let res = accept_swap_offer_raw(taker_addr, &offer_id, 5000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot accept swap offer: quantity must be between 10000 and 100000",
+    )
+    .await;
+    let res = accept_swap_offer_raw(taker_addr, &offer_id, 50000).await;
+    _check_response_is_ok(res).await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    let swap_taker = loop {
+        if let Some(swap) = list_swaps(taker_addr).await.taker.into_iter().next() {
+            break swap;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("swap of the accepted offer has not been whitelisted")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(swap_taker.qty_from, 50000);
+    assert_eq!(swap_taker.qty_to, 10);
+    assert_eq!(swap_taker.from_asset, None);
+    assert_eq!(swap_taker.to_asset, Some(asset_id.clone()));
+    assert_eq!(swap_taker.status, SwapStatus::Waiting);
+
+    let offer = list_swap_offers(maker_addr)
+        .await
+        .into_iter()
+        .find(|o| o.offer_id == offer_id)
+        .unwrap();
+    assert_eq!(offer.acceptances.len(), 1);
+    let acceptance = offer.acceptances.first().unwrap();
+    assert_eq!(acceptance.taker_pubkey, node2_pubkey);
+    assert!(acceptance.swapstring.ends_with(&swap_taker.payment_hash));
+    let swaps_maker = list_swaps(maker_addr).await;
+    assert_eq!(swaps_maker.maker.len(), 1);
+    assert_eq!(
+        swaps_maker.maker.first().unwrap().payment_hash,
+        swap_taker.payment_hash
+    );
+
+    println!("\nexecute swap");
+    maker_execute(
+        maker_addr,
+        acceptance.swapstring.clone(),
+        acceptance.payment_secret.clone(),
+        node2_pubkey.clone(),
+    )
+    .await;
+    wait_for_swap_status(taker_addr, &swap_taker.payment_hash, SwapStatus::Succeeded).await;
+
+    wait_for_ln_balance(maker_addr, &asset_id, 590).await;
+    wait_for_ln_balance(taker_addr, &asset_id, 10).await;
+}
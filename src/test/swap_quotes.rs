@@ -0,0 +1,195 @@
+use crate::routes::{
+    PostSwapOfferRequest, PostSwapOfferResponse, SwapQuoteRequest, SwapQuoteResponse,
+    SwapQuoteSource,
+};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/swap_quotes/";
+
+async fn start_price_oracle() -> String {
+    let router = axum::Router::new().route(
+        "/price",
+        axum::routing::get(|| async {
+            axum::Json(serde_json::json!({"price_from": 5000, "price_to": 1}))
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let oracle_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{oracle_addr}/price")
+}
+
+async fn swap_quote_raw(
+    node_address: SocketAddr,
+    taker_pubkey: &str,
+    to_asset: Option<&str>,
+    qty_from: u64,
+) -> reqwest::Response {
+    println!("quoting swap of {qty_from} for taker {taker_pubkey} on node {node_address}");
+    let payload = SwapQuoteRequest {
+        taker_pubkey: taker_pubkey.to_string(),
+        from_asset: None,
+        to_asset: to_asset.map(|a| a.to_string()),
+        qty_from,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/swapquote", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn swap_quote(
+    node_address: SocketAddr,
+    taker_pubkey: &str,
+    to_asset: &str,
+    qty_from: u64,
+) -> SwapQuoteResponse {
+    let res = swap_quote_raw(node_address, taker_pubkey, Some(to_asset), qty_from).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<SwapQuoteResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swap_quotes() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        price_oracle_url: Some(start_price_oracle().await),
+        swap_quote_fee_ppm: 100000,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(5000000),
+        Some(546000),
+        None,
+        None,
+    )
+    .await;
+
+    let maker_addr = node1_addr;
+    let taker_addr = node2_addr;
+
+    println!("\nquote from an offer");
+    let payload = PostSwapOfferRequest {
+        from_asset: None,
+        to_asset: Some(asset_id.clone()),
+        price_from: 4000,
+        price_to: 1,
+        min_qty_from: 10000,
+        max_qty_from: 100000,
+        total_qty_from: None,
+        expiry_sec: 3600,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/postswapoffer", maker_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let offer_id = _check_response_is_ok(res)
+        .await
+        .json::<PostSwapOfferResponse>()
+        .await
+        .unwrap()
+        .offer_id;
+    let quote = swap_quote(maker_addr, &node2_pubkey, &asset_id, 40000).await;
+    assert_eq!(quote.source, SwapQuoteSource::Offer);
+    assert_eq!(quote.offer_id, Some(offer_id));
+    assert_eq!(quote.qty_from, 40000);
+    assert_eq!(quote.qty_to, 10);
+    assert_eq!(quote.fee, 0);
+    assert!(quote.swapstring.ends_with(&quote.payment_hash));
+    taker(taker_addr, quote.swapstring.clone()).await;
+    maker_execute(
+        maker_addr,
+        quote.swapstring,
+        quote.payment_secret,
+        node2_pubkey.clone(),
+    )
+    .await;
+    wait_for_swap_status(taker_addr, &quote.payment_hash, SwapStatus::Succeeded).await;
+    wait_for_ln_balance(taker_addr, &asset_id, 10).await;
+
+    println!("\nquote from the price oracle");
+    // the offer doesn't cover quantities above its max, so the oracle price is used
+    let quote = swap_quote(maker_addr, &node2_pubkey, &asset_id, 500000).await;
+    assert_eq!(quote.source, SwapQuoteSource::Oracle);
+    assert_eq!(quote.offer_id, None);
+    assert_eq!(quote.qty_to, 90);
+    assert_eq!(quote.fee, 10);
+    taker(taker_addr, quote.swapstring.clone()).await;
+    maker_execute(
+        maker_addr,
+        quote.swapstring,
+        quote.payment_secret,
+        node2_pubkey.clone(),
+    )
+    .await;
+    wait_for_swap_status(taker_addr, &quote.payment_hash, SwapStatus::Succeeded).await;
+    wait_for_ln_balance(taker_addr, &asset_id, 100).await;
+    wait_for_ln_balance(maker_addr, &asset_id, 500).await;
+
+    println!("\ninvalid quotes");
+    let res = swap_quote_raw(maker_addr, &node2_pubkey, None, 40000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid swap: cannot swap BTC for BTC",
+    )
+    .await;
+    let res = swap_quote_raw(maker_addr, &node2_pubkey, Some("invalid"), 40000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid asset ID: invalid",
+    )
+    .await;
+    let res = swap_quote_raw(maker_addr, "invalid", Some(&asset_id), 40000).await;
+    check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, "Invalid pubkey").await;
+    let res = swap_quote_raw(maker_addr, &node2_pubkey, Some(&asset_id), 4000000).await;
+    check_response_is_nok(res, reqwest::StatusCode::FORBIDDEN, "Not enough assets").await;
+    // a node without offers nor oracle cannot quote
+    let res = swap_quote_raw(taker_addr, &node1_pubkey, Some(&asset_id), 40000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot quote swap: none of our offers covers the swap and no price oracle is configured",
+    )
+    .await;
+}
@@ -96,6 +96,29 @@ async fn swap_roundtrip_buy() {
     assert_eq!(swap_taker.payment_hash, maker_init_response.payment_hash);
     assert_eq!(swap_taker.status, SwapStatus::Waiting);
 
+    println!("\nexecute swap exceeding the CLTV budget");
+    let payload = MakerExecuteRequest {
+        swapstring: maker_init_response.swapstring.clone(),
+        payment_secret: maker_init_response.payment_secret.clone(),
+        taker_pubkey: node2_pubkey.clone(),
+        first_leg_cltv_expiry_delta: Some(1000),
+        second_leg_cltv_expiry_delta: None,
+        fill_qty_from: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/makerexecute", maker_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert!(res.text().await.unwrap().contains("CLTV budget exceeded"));
+    let swaps_maker = list_swaps(maker_addr).await;
+    assert_eq!(
+        swaps_maker.maker.first().unwrap().status,
+        SwapStatus::Waiting
+    );
+
     println!("\nexecute swap");
     maker_execute(
         maker_addr,
@@ -0,0 +1,144 @@
+use self::routes::HTLC_MIN_MSAT;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/swap_roundtrip_multihop_mixed/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swap_roundtrip_multihop_mixed() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 400, recipient_id).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 600);
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    // the asset leg goes through node2, while the BTC leg has a direct channel
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(50000),
+        None,
+        Some(500),
+        Some(&asset_id),
+    )
+    .await;
+    let channel_23 = open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        Some(50000),
+        None,
+        Some(300),
+        Some(&asset_id),
+    )
+    .await;
+
+    let maker_addr = node1_addr;
+    let taker_addr = node3_addr;
+    let qty_from = 36000;
+    let qty_to = 10;
+
+    println!("\nswap without a route for the BTC leg");
+    let maker_init_response =
+        maker_init(maker_addr, qty_from, None, qty_to, Some(&asset_id), 500).await;
+    taker(taker_addr, maker_init_response.swapstring.clone()).await;
+    let res = maker_execute_raw(
+        maker_addr,
+        maker_init_response.swapstring.clone(),
+        maker_init_response.payment_secret.clone(),
+        node3_pubkey.clone(),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "No route found for the swap second (BTC from taker to maker) leg",
+    )
+    .await;
+
+    println!("\nexecute swap");
+    let channel_31 = open_channel(
+        node3_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        Some(50000),
+        Some(546000),
+        None,
+        None,
+    )
+    .await;
+    let chan_3_23_before = list_channels(node3_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel_23.channel_id)
+        .unwrap();
+    let chan_3_31_before = list_channels(node3_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel_31.channel_id)
+        .unwrap();
+    maker_execute(
+        maker_addr,
+        maker_init_response.swapstring,
+        maker_init_response.payment_secret,
+        node3_pubkey.clone(),
+    )
+    .await;
+    wait_for_swap_status(
+        taker_addr,
+        &maker_init_response.payment_hash,
+        SwapStatus::Succeeded,
+    )
+    .await;
+    wait_for_swap_status(
+        maker_addr,
+        &maker_init_response.payment_hash,
+        SwapStatus::Succeeded,
+    )
+    .await;
+
+    wait_for_ln_balance(maker_addr, &asset_id, 490).await;
+    wait_for_ln_balance(taker_addr, &asset_id, 10).await;
+
+    let chan_3_23 = list_channels(node3_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel_23.channel_id)
+        .unwrap();
+    let chan_3_31 = list_channels(node3_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel_31.channel_id)
+        .unwrap();
+    assert_eq!(
+        chan_3_23.local_balance_msat,
+        chan_3_23_before.local_balance_msat + HTLC_MIN_MSAT
+    );
+    assert_eq!(
+        chan_3_31.local_balance_msat,
+        chan_3_31_before.local_balance_msat - HTLC_MIN_MSAT - qty_from
+    );
+}
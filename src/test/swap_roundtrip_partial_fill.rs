@@ -0,0 +1,175 @@
+use crate::routes::{MakerExecuteRequest, MakerInitRequest, MakerInitResponse, SwapPairInfo};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/swap_roundtrip_partial_fill/";
+
+async fn maker_execute_fill_raw(
+    node_address: SocketAddr,
+    maker_init_response: &MakerInitResponse,
+    taker_pubkey: &str,
+    fill_qty_from: u64,
+) -> reqwest::Response {
+    println!("executing swap for {fill_qty_from} from node {node_address}");
+    let payload = MakerExecuteRequest {
+        swapstring: maker_init_response.swapstring.clone(),
+        payment_secret: maker_init_response.payment_secret.clone(),
+        taker_pubkey: taker_pubkey.to_string(),
+        first_leg_cltv_expiry_delta: None,
+        second_leg_cltv_expiry_delta: None,
+        fill_qty_from: Some(fill_qty_from),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/makerexecute", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn swap_roundtrip_partial_fill() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(5000000),
+        Some(546000),
+        None,
+        None,
+    )
+    .await;
+
+    let maker_addr = node1_addr;
+    let taker_addr = node2_addr;
+
+    println!("\ncheck advertised swap pairs");
+    let pair = SwapPairInfo {
+        from_asset: None,
+        to_asset: Some(asset_id.clone()),
+    };
+    assert!(node_info(taker_addr).await.swap_pairs.contains(&pair));
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let peers = list_peers(maker_addr).await;
+        let taker = peers.iter().find(|p| p.pubkey == node2_pubkey).unwrap();
+        if taker.swap_pairs.as_ref().is_some_and(|p| p.contains(&pair)) {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("taker swap pairs not received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    // the taker has no channel with the other asset, so it can't swap it
+    let other_asset_id = issue_asset_nia(maker_addr).await.asset_id;
+    let payload = MakerInitRequest {
+        qty_from: 50000,
+        qty_to: 10,
+        from_asset: None,
+        to_asset: Some(other_asset_id),
+        timeout_sec: 3600,
+        partial_fill: None,
+        taker_pubkey: Some(node2_pubkey.clone()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/makerinit", maker_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The counterparty node doesn't support the swap pair",
+    )
+    .await;
+
+    println!("\nsetup partial-fill swap");
+    let payload = MakerInitRequest {
+        qty_from: 50000,
+        qty_to: 10,
+        from_asset: None,
+        to_asset: Some(asset_id.clone()),
+        timeout_sec: 3600,
+        partial_fill: Some(true),
+        taker_pubkey: Some(node2_pubkey.clone()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/makerinit", maker_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let maker_init_response = _check_response_is_ok(res)
+        .await
+        .json::<MakerInitResponse>()
+        .await
+        .unwrap();
+    assert!(maker_init_response.swapstring.ends_with("/partial"));
+    taker(taker_addr, maker_init_response.swapstring.clone()).await;
+
+    let res = maker_execute_fill_raw(maker_addr, &maker_init_response, &node2_pubkey, 60000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid swap: fill_qty_from must be positive, not higher than 50000 and high enough \
+        to receive a positive quantity",
+    )
+    .await;
+
+    println!("\nexecute half of the swap");
+    let res = maker_execute_fill_raw(maker_addr, &maker_init_response, &node2_pubkey, 25000).await;
+    _check_response_is_ok(res).await;
+    wait_for_swap_status(
+        taker_addr,
+        &maker_init_response.payment_hash,
+        SwapStatus::Succeeded,
+    )
+    .await;
+
+    wait_for_ln_balance(maker_addr, &asset_id, 595).await;
+    wait_for_ln_balance(taker_addr, &asset_id, 5).await;
+
+    for swap in [
+        list_swaps(maker_addr).await.maker,
+        list_swaps(taker_addr).await.taker,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        assert!(swap.partial_fill);
+        assert_eq!(swap.qty_from, 50000);
+        assert_eq!(swap.qty_to, 10);
+        assert_eq!(swap.filled_qty_from, Some(25000));
+        assert_eq!(swap.filled_qty_to, Some(5));
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/tor_proxy/";
+
+const ONION_HOST: &str = "duckduckgogg42xjoc72x3sjasowoarfbgcmvfimaftt6twagswzczad.onion";
+
+/// A SOCKS5 proxy relaying the connections to the requested host, recording them
+async fn start_socks5_proxy() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(vec![]));
+    let proxy_requests = Arc::clone(&requests);
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let requests = Arc::clone(&proxy_requests);
+            tokio::spawn(async move {
+                let mut greeting = [0u8; 3];
+                stream.read_exact(&mut greeting).await.unwrap();
+                assert_eq!(greeting, [0x05, 0x01, 0x00]);
+                stream.write_all(&[0x05, 0x00]).await.unwrap();
+                let mut request = [0u8; 5];
+                stream.read_exact(&mut request).await.unwrap();
+                assert_eq!(request[..4], [0x05, 0x01, 0x00, 0x03]);
+                let mut host = vec![0u8; request[4] as usize];
+                stream.read_exact(&mut host).await.unwrap();
+                let port = stream.read_u16().await.unwrap();
+                let target = format!("{}:{port}", String::from_utf8(host).unwrap());
+                requests.lock().unwrap().push(target.clone());
+                let Ok(mut target_stream) = TcpStream::connect(target).await else {
+                    // connection refused
+                    let _ = stream
+                        .write_all(&[0x05, 0x05, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                        .await;
+                    return;
+                };
+                stream
+                    .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                    .await
+                    .unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut stream, &mut target_stream).await;
+            });
+        }
+    });
+    (proxy_addr, requests)
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn tor_proxy() {
+    initialize();
+
+    let (proxy_addr, proxy_requests) = start_socks5_proxy().await;
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        tor_proxy: Some(proxy_addr),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    println!("\nconnect to a peer through the proxy");
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+    assert!(list_peers(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node2_pubkey));
+    assert_eq!(
+        *proxy_requests.lock().unwrap(),
+        vec![format!("127.0.0.1:{NODE2_PEER_PORT}")]
+    );
+
+    println!("\nconnect to a peer by host name, resolved by the proxy");
+    disconnect_peer(node1_addr, &node2_pubkey).await;
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("localhost:{NODE2_PEER_PORT}"),
+    )
+    .await;
+    assert!(list_peers(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node2_pubkey));
+    assert_eq!(
+        proxy_requests.lock().unwrap().last().unwrap(),
+        &format!("localhost:{NODE2_PEER_PORT}")
+    );
+
+    println!("\nonion addresses are passed to the proxy");
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: format!("{node2_pubkey}@{ONION_HOST}:9735"),
+    };
+    disconnect_peer(node1_addr, &node2_pubkey).await;
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/connectpeer", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(
+        proxy_requests.lock().unwrap().last().unwrap(),
+        &format!("{ONION_HOST}:9735")
+    );
+
+    println!("\nfail to connect when the proxy can't reach the peer");
+    shutdown(&[node2_addr]).await;
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/connectpeer", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(res.status(), reqwest::StatusCode::OK);
+    assert_eq!(proxy_requests.lock().unwrap().len(), 4);
+}
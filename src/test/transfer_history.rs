@@ -0,0 +1,113 @@
+use crate::routes::{ChannelTransferKind, TransferKind, TransferStatus};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/transfer_history/";
+
+async fn list_transfers_page(
+    node_address: SocketAddr,
+    asset_id: Option<&str>,
+    status: Option<TransferStatus>,
+    offset: u32,
+    limit: Option<u32>,
+) -> ListTransfersResponse {
+    println!("listing transfers page on node {node_address}");
+    let payload = ListTransfersRequest {
+        asset_id: asset_id.map(|a| a.to_string()),
+        status,
+        offset,
+        limit,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/listtransfers", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListTransfersResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn transfer_history() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let other_asset_id = issue_asset_cfa(node1_addr, None).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 100, recipient_id).await;
+    mine(false);
+    refresh_transfers(node1_addr).await;
+    rgb_invoice(node1_addr, Some(asset_id.clone())).await;
+
+    // the funding transfer is linked to its channel
+    let all = list_transfers_page(node1_addr, Some(&asset_id), None, 0, None).await;
+    assert_eq!(all.total, 4);
+    assert_eq!(all.transfers.len(), 4);
+    assert!(matches!(all.transfers[0].kind, TransferKind::Issuance));
+    let channel_transfer = all.transfers[1].channel_transfer.as_ref().unwrap();
+    assert_eq!(channel_transfer.kind, ChannelTransferKind::Funding);
+    assert_eq!(channel_transfer.channel_id, Some(channel.channel_id));
+    assert!(matches!(all.transfers[2].kind, TransferKind::Send));
+    assert!(all.transfers[2].channel_transfer.is_none());
+    assert!(all.transfers.iter().all(|t| t.asset_id == asset_id));
+
+    // pending transfers only
+    let pending = list_transfers_page(
+        node1_addr,
+        Some(&asset_id),
+        Some(TransferStatus::WaitingCounterparty),
+        0,
+        None,
+    )
+    .await;
+    assert_eq!(pending.total, 1);
+    assert!(matches!(
+        pending.transfers[0].kind,
+        TransferKind::ReceiveBlind
+    ));
+
+    // pagination
+    let page = list_transfers_page(node1_addr, Some(&asset_id), None, 1, Some(1)).await;
+    assert_eq!(page.total, 4);
+    assert_eq!(page.transfers.len(), 1);
+    assert_eq!(page.transfers[0].idx, all.transfers[1].idx);
+    let page = list_transfers_page(node1_addr, Some(&asset_id), None, 4, Some(1)).await;
+    assert_eq!(page.total, 4);
+    assert!(page.transfers.is_empty());
+
+    // all assets
+    let all_assets = list_transfers_page(node1_addr, None, None, 0, None).await;
+    assert_eq!(all_assets.total, 5);
+    assert!(all_assets
+        .transfers
+        .iter()
+        .any(|t| t.asset_id == other_asset_id));
+}
@@ -0,0 +1,119 @@
+use crate::routes::{TransferKind, TransferProofRequest, TransferProofResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/transfer_proof/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn transfer_proof() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let recipient_id = rgb_invoice(node2_addr, None).await.recipient_id;
+    send_asset(node1_addr, &asset_id, 10, recipient_id.clone()).await;
+    let txid = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.kind == TransferKind::Send)
+        .unwrap()
+        .txid
+        .unwrap();
+
+    let proof_path = format!("{test_dir_node1}/transfer_proof.zip");
+    let payload = TransferProofRequest {
+        txid: txid.clone(),
+        proof_path: proof_path.clone(),
+    };
+
+    println!("\nexporting proof of unsettled transfer");
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/transferproof", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot export transfer proof: the transfer has not settled yet",
+    )
+    .await;
+
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+
+    println!("\nexporting proof of settled transfer");
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/transferproof", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let proof = _check_response_is_ok(res)
+        .await
+        .json::<TransferProofResponse>()
+        .await
+        .unwrap();
+    assert_eq!(proof.asset_ids, vec![asset_id.clone()]);
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(&proof_path).unwrap()).unwrap();
+    let mut manifest = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("manifest.json").unwrap(),
+        &mut manifest,
+    )
+    .unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+    assert_eq!(manifest["txid"], txid);
+    assert_eq!(manifest["height"], proof.height);
+    assert!(archive.by_name("witness_tx.hex").is_ok());
+    assert!(archive.by_name("txoutproof.hex").is_ok());
+    assert!(archive
+        .by_name(&format!("consignments/{asset_id}/{recipient_id}.rgb"))
+        .is_ok());
+
+    // an existing file is never overwritten
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/transferproof", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid transfer proof path",
+    )
+    .await;
+
+    // a transaction that didn't send any asset has no proof
+    let payload = TransferProofRequest {
+        txid: s!("0000000000000000000000000000000000000000000000000000000000000000"),
+        proof_path: format!("{test_dir_node1}/other_proof.zip"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{}/transferproof", node1_addr))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot export transfer proof: no asset has been sent with this transaction",
+    )
+    .await;
+}
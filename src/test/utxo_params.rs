@@ -0,0 +1,40 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/utxo_params/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn utxo_params() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let args = LdkUserInfo {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        utxo_size_sat: 10000,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+
+    // UTXOs get the configured size by default
+    fund_and_create_utxos(node1_addr, Some(3)).await;
+    let unspents = list_unspents(node1_addr).await;
+    let colorable: Vec<_> = unspents.iter().filter(|u| u.utxo.colorable).collect();
+    assert_eq!(colorable.len(), 3);
+    assert!(colorable.iter().all(|u| u.utxo.btc_amount == 10000));
+
+    // the size can still be chosen for single calls
+    create_utxos(node1_addr, false, Some(2), Some(20000)).await;
+    mine(false);
+    let unspents = list_unspents(node1_addr).await;
+    let colorable: Vec<_> = unspents.iter().filter(|u| u.utxo.colorable).collect();
+    assert_eq!(colorable.len(), 5);
+    assert_eq!(
+        colorable
+            .iter()
+            .filter(|u| u.utxo.btc_amount == 20000)
+            .count(),
+        2
+    );
+}
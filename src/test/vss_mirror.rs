@@ -0,0 +1,216 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::utils::{LDK_DIR, LOGS_DIR};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/vss_mirror/";
+
+type VssObjects = Arc<Mutex<HashMap<(String, String), (i64, String)>>>;
+
+fn _store_key(body: &Value, key: &Value) -> (String, String) {
+    (
+        body["store_id"].as_str().unwrap().to_string(),
+        key.as_str().unwrap().to_string(),
+    )
+}
+
+async fn get_object(
+    State(objects): State<VssObjects>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    let key = _store_key(&body, &body["key"]);
+    let objects = objects.lock().unwrap();
+    let (version, value) = objects.get(&key).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(
+        json!({"value": {"key": key.1, "version": version, "value": value}}),
+    ))
+}
+
+async fn put_objects(State(objects): State<VssObjects>, Json(body): Json<Value>) -> StatusCode {
+    let mut objects = objects.lock().unwrap();
+    let items = body["transaction_items"].as_array().unwrap();
+    for item in items {
+        let key = _store_key(&body, &item["key"]);
+        let current_version = objects.get(&key).map(|(v, _)| *v).unwrap_or(0);
+        if item["version"].as_i64().unwrap() != current_version {
+            return StatusCode::CONFLICT;
+        }
+    }
+    for item in items {
+        let key = _store_key(&body, &item["key"]);
+        let version = objects.get(&key).map(|(v, _)| *v).unwrap_or(0);
+        let value = item["value"].as_str().unwrap().to_string();
+        objects.insert(key, (version + 1, value));
+    }
+    StatusCode::OK
+}
+
+async fn delete_object(State(objects): State<VssObjects>, Json(body): Json<Value>) -> StatusCode {
+    let key = _store_key(&body, &body["key_value"]["key"]);
+    objects.lock().unwrap().remove(&key);
+    StatusCode::OK
+}
+
+async fn list_key_versions(
+    State(objects): State<VssObjects>,
+    Json(body): Json<Value>,
+) -> Json<Value> {
+    let store_id = body["store_id"].as_str().unwrap();
+    let key_versions: Vec<Value> = objects
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|((s, _), _)| s == store_id)
+        .map(|((_, key), (version, _))| json!({"key": key, "version": version}))
+        .collect();
+    Json(json!({ "key_versions": key_versions }))
+}
+
+async fn wait_for_payment_success(node_address: SocketAddr, payment_hash: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let payments = list_payments(node_address).await;
+        if payments
+            .iter()
+            .any(|p| p.payment_hash == payment_hash && p.status == HTLCStatus::Succeeded)
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("payment has not succeeded")
+        }
+    }
+}
+
+/// Start a VSS server keeping the objects in memory, on its own runtime as the node blocks while
+/// waiting for channel monitors to be mirrored
+fn start_vss_server(objects: VssObjects) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let vss_addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let router = axum::Router::new()
+                .route("/vss/getObject", axum::routing::post(get_object))
+                .route("/vss/putObjects", axum::routing::post(put_objects))
+                .route("/vss/deleteObject", axum::routing::post(delete_object))
+                .route(
+                    "/vss/listKeyVersions",
+                    axum::routing::post(list_key_versions),
+                )
+                .with_state(objects);
+            listener.set_nonblocking(true).unwrap();
+            let listener = TcpListener::from_std(listener).unwrap();
+            axum::serve(listener, router).await.unwrap()
+        });
+    });
+    format!("http://{vss_addr}/vss")
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn vss_mirror() {
+    initialize();
+
+    let objects = VssObjects::default();
+    let vss_url = start_vss_server(objects.clone());
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let node1_args = || LdkUserInfo {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        vss_url: Some(vss_url.clone()),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(node1_args(), false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        Some(50000000),
+        None,
+        None,
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(3000000), None, None, 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    wait_for_payment_success(node1_addr, &payment.payment_hash).await;
+
+    println!("\nchannel monitors and payments are mirrored encrypted");
+    {
+        let objects = objects.lock().unwrap();
+        let keys: Vec<&String> = objects.keys().map(|(_, k)| k).collect();
+        assert!(keys.iter().any(|k| k.starts_with("monitors//")));
+        assert!(keys.iter().any(|k| k.as_str() == "//manager"));
+        assert!(keys
+            .iter()
+            .any(|k| k.starts_with("outbound_payment_info//")));
+        assert!(!keys.iter().any(|k| k.as_str() == "//network_graph"));
+        assert!(objects
+            .values()
+            .all(|(_, value)| !value.contains(&payment.payment_hash)));
+    }
+
+    println!("\nrestore after losing the LDK data");
+    shutdown(&[node1_addr]).await;
+    let ldk_data_dir = PathBuf::from(&test_dir_node1).join(LDK_DIR);
+    for entry in std::fs::read_dir(&ldk_data_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.file_name().unwrap() == LOGS_DIR {
+            continue;
+        }
+        if path.is_dir() {
+            std::fs::remove_dir_all(path).unwrap();
+        } else {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+    let (node1_addr, _) = start_node_with_args(node1_args(), true).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if channels
+            .iter()
+            .any(|c| c.channel_id == channel.channel_id && c.ready)
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel has not been restored")
+        }
+    }
+    let payments = list_payments(node1_addr).await;
+    assert!(payments
+        .iter()
+        .any(|p| p.payment_hash == payment.payment_hash && p.status == HTLCStatus::Succeeded));
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(3000000), None, None, 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+    wait_for_payment_success(node1_addr, &payment.payment_hash).await;
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+}
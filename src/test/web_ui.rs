@@ -0,0 +1,38 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/web_ui/";
+
+async fn get_ui_file(node_address: SocketAddr, path: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("http://{}{}", node_address, path))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn web_ui() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    for (path, content_type) in [
+        ("/ui", "text/html; charset=utf-8"),
+        ("/ui/app.js", "text/javascript; charset=utf-8"),
+        ("/ui/style.css", "text/css; charset=utf-8"),
+    ] {
+        let res = get_ui_file(node1_addr, path).await;
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            res.headers().get(reqwest::header::CONTENT_TYPE).unwrap(),
+            content_type
+        );
+        assert!(!res.text().await.unwrap().is_empty());
+    }
+
+    let res = get_ui_file(node1_addr, "/ui/unknown.js").await;
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+}
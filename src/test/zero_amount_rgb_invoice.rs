@@ -0,0 +1,102 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/zero_amount_rgb_invoice/";
+
+async fn send_payment_with_asset_amount_raw(
+    node_address: SocketAddr,
+    invoice: String,
+    asset_amount: Option<u64>,
+) -> reqwest::Response {
+    println!("sending {asset_amount:?} of asset for invoice {invoice} from node {node_address}");
+    let payload = SendPaymentRequest {
+        invoice,
+        amt_msat: None,
+        asset_amount,
+        retry_attempts: None,
+        retry_timeout_secs: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{}/sendpayment", node_address))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn zero_amount_rgb_invoice() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3500000),
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), None, 900).await;
+    let decoded = decode_ln_invoice(node1_addr, &invoice).await;
+    assert_eq!(decoded.asset_id, Some(asset_id.clone()));
+    assert_eq!(decoded.asset_amount, None);
+
+    // the payer needs to choose the asset amount
+    let res = send_payment_with_asset_amount_raw(node1_addr, invoice.clone(), None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid amount: need an asset amount for the given invoice without an RGB amount",
+    )
+    .await;
+
+    let res = send_payment_with_asset_amount_raw(node1_addr, invoice.clone(), Some(120)).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+        .unwrap();
+    _wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    _wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 480).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 120).await;
+
+    let payment = list_payments(node2_addr)
+        .await
+        .into_iter()
+        .find(|p| p.payment_hash == payment_hash)
+        .unwrap();
+    assert_eq!(payment.asset_id, Some(asset_id.clone()));
+    assert_eq!(payment.asset_amount, Some(120));
+    assert!(payment.inbound);
+
+    // the asset amount of invoices setting one cannot be changed
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(50), 900).await;
+    let res = send_payment_with_asset_amount_raw(node1_addr, invoice, Some(60)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid amount: asset amount didn't match invoice value of 50",
+    )
+    .await;
+}
@@ -0,0 +1,322 @@
+use amplify::s;
+use bitcoin::secp256k1::PublicKey;
+use futures::Future;
+use lightning::ln::msgs::SocketAddress;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::disk::write_file_atomically;
+use crate::ldk::PeerManager;
+use crate::utils::hex_str;
+
+/// Private key of the onion service, if kept so that its address doesn't change across restarts
+pub(crate) const TOR_ONION_KEY_FNAME: &str = "tor_onion_key";
+
+/// Max time a connection through the proxy or a control port command can take
+const TOR_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between the attempts to re-create the onion service once the control connection drops
+const TOR_RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Open a connection to the given host through a SOCKS5 proxy (RFC 1928) not requiring
+/// authentication. The host is resolved by the proxy.
+async fn socks5_connect(proxy: SocketAddr, host: &str, port: u16) -> Result<TcpStream, String> {
+    if host.len() > u8::MAX as usize {
+        return Err(format!("host {host} is too long"));
+    }
+    let connect = async {
+        let mut stream = TcpStream::connect(proxy).await?;
+        stream.write_all(&[0x05, 0x01, 0x00]).await?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != 0x05 {
+            return Ok(Err(s!("invalid proxy reply")));
+        }
+        if reply[1] != 0x00 {
+            return Ok(Err(s!("the proxy requires authentication")));
+        }
+        let mut request = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+        request.extend_from_slice(host.as_bytes());
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != 0x05 {
+            return Ok(Err(s!("invalid proxy reply")));
+        }
+        if reply[1] != 0x00 {
+            return Ok(Err(format!(
+                "the proxy failed to connect (error {})",
+                reply[1]
+            )));
+        }
+        // skip the address the proxy bound, which isn't needed
+        let bound_addr_len = match reply[3] {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => stream.read_u8().await? as usize,
+            _ => return Ok(Err(s!("invalid proxy reply"))),
+        };
+        let mut bound_addr = vec![0u8; bound_addr_len + 2];
+        stream.read_exact(&mut bound_addr).await?;
+        Ok::<_, std::io::Error>(Ok(stream))
+    };
+    match tokio::time::timeout(TOR_TIMEOUT, connect).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(s!("timed out connecting through the proxy")),
+    }
+}
+
+/// Host and port to request to the proxy, which resolves host names and onion addresses
+fn proxy_target(addr: &SocketAddress) -> Result<(String, u16), String> {
+    match addr {
+        SocketAddress::TcpIpV4 { addr, port } => Ok((Ipv4Addr::from(*addr).to_string(), *port)),
+        SocketAddress::TcpIpV6 { addr, port } => Ok((Ipv6Addr::from(*addr).to_string(), *port)),
+        SocketAddress::OnionV3 { port, .. } | SocketAddress::Hostname { port, .. } => {
+            let addr = addr.to_string();
+            let (host, _) = addr.rsplit_once(':').expect("formatted as host:port");
+            Ok((host.to_string(), *port))
+        }
+        SocketAddress::OnionV2(_) => Err(s!("onion v2 addresses are not supported")),
+    }
+}
+
+/// Same as [`lightning_net_tokio::connect_outbound`], connecting through a SOCKS5 proxy
+pub(crate) async fn connect_outbound_via_proxy(
+    peer_manager: Arc<PeerManager>,
+    their_node_id: PublicKey,
+    proxy: SocketAddr,
+    addr: SocketAddress,
+) -> Option<impl Future<Output = ()>> {
+    let connect = async {
+        let (host, port) = proxy_target(&addr)?;
+        socks5_connect(proxy, &host, port).await
+    };
+    let stream = match connect.await {
+        Ok(stream) => stream,
+        Err(e) => {
+            tracing::warn!("Failed to connect to {addr} through proxy {proxy}: {e}");
+            return None;
+        }
+    };
+    Some(lightning_net_tokio::setup_outbound(
+        peer_manager,
+        their_node_id,
+        stream.into_std().ok()?,
+    ))
+}
+
+/// Connection to the Tor control port. The onion services it creates are removed by Tor once
+/// it's closed.
+pub(crate) struct TorControl {
+    reader: BufReader<TcpStream>,
+}
+
+impl TorControl {
+    /// Connect and authenticate, with the password if given, else with the cookie file or
+    /// without authentication, depending on what Tor allows
+    pub(crate) async fn connect(
+        address: SocketAddr,
+        password: Option<&str>,
+    ) -> Result<Self, String> {
+        let stream = tokio::time::timeout(TOR_TIMEOUT, TcpStream::connect(address))
+            .await
+            .map_err(|_| format!("timed out connecting to {address}"))?
+            .map_err(|e| format!("cannot connect to {address}: {e}"))?;
+        let mut control = Self {
+            reader: BufReader::new(stream),
+        };
+        let authenticate = match password {
+            Some(password) => format!(
+                "AUTHENTICATE \"{}\"",
+                password.replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            None => {
+                let reply = control.command("PROTOCOLINFO 1").await?;
+                let auth = reply
+                    .iter()
+                    .find_map(|l| l.strip_prefix("AUTH METHODS="))
+                    .ok_or(s!("missing authentication methods"))?;
+                let (methods, cookie_file) = match auth.split_once(" COOKIEFILE=") {
+                    Some((methods, cookie_file)) => (methods, Some(cookie_file.trim_matches('"'))),
+                    None => (auth, None),
+                };
+                let methods: Vec<&str> = methods.split(',').collect();
+                match cookie_file {
+                    _ if methods.contains(&"NULL") => s!("AUTHENTICATE"),
+                    Some(cookie_file) if methods.contains(&"COOKIE") => {
+                        let cookie = fs::read(cookie_file)
+                            .map_err(|e| format!("cannot read cookie file {cookie_file}: {e}"))?;
+                        format!("AUTHENTICATE {}", hex_str(&cookie))
+                    }
+                    _ => return Err(s!("the control port requires a password")),
+                }
+            }
+        };
+        control.command(&authenticate).await?;
+        Ok(control)
+    }
+
+    /// Send a command, returning the lines of the reply, without status codes, if it succeeds
+    async fn command(&mut self, command: &str) -> Result<Vec<String>, String> {
+        let run = async {
+            let stream = self.reader.get_mut();
+            stream
+                .write_all(format!("{command}\r\n").as_bytes())
+                .await
+                .map_err(|e| e.to_string())?;
+            let mut lines = vec![];
+            loop {
+                let mut line = String::new();
+                if self
+                    .reader
+                    .read_line(&mut line)
+                    .await
+                    .map_err(|e| e.to_string())?
+                    == 0
+                {
+                    return Err(s!("the control connection has been closed"));
+                }
+                let line = line.trim_end();
+                if line.len() < 4 || !line.is_char_boundary(4) {
+                    return Err(format!("invalid reply {line}"));
+                }
+                if &line[..3] != "250" {
+                    return Err(line.to_string());
+                }
+                lines.push(line[4..].to_string());
+                if &line[3..4] == " " {
+                    return Ok(lines);
+                }
+            }
+        };
+        tokio::time::timeout(TOR_TIMEOUT, run)
+            .await
+            .map_err(|_| s!("timed out waiting for the control port"))?
+    }
+
+    /// Create an onion service forwarding the given port to the target, with the given key or a
+    /// new one. Returns its address and its key.
+    async fn add_onion(
+        &mut self,
+        key: Option<&str>,
+        port: u16,
+        target: SocketAddr,
+    ) -> Result<(SocketAddress, String), String> {
+        let reply = self
+            .command(&format!(
+                "ADD_ONION {} Port={port},{target}",
+                key.unwrap_or("NEW:ED25519-V3")
+            ))
+            .await?;
+        let key = match key {
+            Some(key) => key.to_string(),
+            None => reply
+                .iter()
+                .find_map(|l| l.strip_prefix("PrivateKey="))
+                .ok_or(s!("missing private key"))?
+                .to_string(),
+        };
+        let service_id = reply
+            .iter()
+            .find_map(|l| l.strip_prefix("ServiceID="))
+            .ok_or(s!("missing service ID"))?;
+        let addr = SocketAddress::from_str(&format!("{service_id}.onion:{port}"))
+            .map_err(|_| format!("invalid service ID {service_id}"))?;
+        Ok((addr, key))
+    }
+
+    /// Wait for the connection to be closed
+    async fn closed(&mut self) {
+        let mut line = String::new();
+        while matches!(self.reader.read_line(&mut line).await, Ok(n) if n > 0) {
+            line.clear();
+        }
+    }
+}
+
+/// Onion service forwarding the LN peer port to the node.
+///
+/// Tor removes the service once the control connection that created it is closed, so the
+/// connection is kept open and, if it drops, opened again to re-create the service with the same
+/// key, and so the same address. The key is only kept in memory, unless it's to be persisted.
+pub(crate) struct OnionService {
+    control_address: SocketAddr,
+    control_password: Option<String>,
+    port: u16,
+    target: SocketAddr,
+    key: String,
+    control: TorControl,
+}
+
+impl OnionService {
+    /// Create the service, returning its address. With a key path, the key is read from it if
+    /// present, else generated and saved to it.
+    pub(crate) async fn create(
+        control_address: SocketAddr,
+        control_password: Option<String>,
+        key_path: Option<PathBuf>,
+        port: u16,
+        target: SocketAddr,
+    ) -> Result<(Self, SocketAddress), String> {
+        let saved_key = key_path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|k| k.trim().to_string());
+        let mut control = TorControl::connect(control_address, control_password.as_deref()).await?;
+        let (addr, key) = control
+            .add_onion(saved_key.as_deref(), port, target)
+            .await?;
+        if let Some(key_path) = key_path.filter(|_| saved_key.is_none()) {
+            write_file_atomically(&key_path, key.as_bytes())
+                .map_err(|e| format!("cannot save the onion service key: {e}"))?;
+        }
+        let service = Self {
+            control_address,
+            control_password,
+            port,
+            target,
+            key,
+            control,
+        };
+        Ok((service, addr))
+    }
+
+    /// Keep the service up as long as the node runs, re-creating it whenever the control
+    /// connection drops
+    pub(crate) fn keep_open(mut self) {
+        tokio::spawn(async move {
+            loop {
+                self.control.closed().await;
+                tracing::error!(
+                    "The Tor control connection has been closed, the onion service is down"
+                );
+                loop {
+                    tokio::time::sleep(TOR_RECONNECT_INTERVAL).await;
+                    match self.recreate().await {
+                        Ok(()) => break,
+                        Err(e) => tracing::warn!("Failed to re-create the onion service: {e}"),
+                    }
+                }
+                tracing::info!("Re-created the onion service");
+            }
+        });
+    }
+
+    async fn recreate(&mut self) -> Result<(), String> {
+        let mut control =
+            TorControl::connect(self.control_address, self.control_password.as_deref()).await?;
+        control
+            .add_onion(Some(&self.key), self.port, self.target)
+            .await?;
+        self.control = control;
+        Ok(())
+    }
+}
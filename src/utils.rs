@@ -1,8 +1,9 @@
 use amplify::s;
 use bitcoin::secp256k1::PublicKey;
-use bitcoin::Network;
+use bitcoin::{Network, OutPoint};
 use futures::Future;
-use lightning::ln::channelmanager::ChannelDetails;
+use lightning::events::bump_transaction::Utxo;
+use lightning::ln::channelmanager::{ChannelDetails, InterceptId, Retry};
 use lightning::ln::msgs::SocketAddress;
 use lightning::ln::ChannelId;
 use lightning::rgb_utils::{BITCOIN_NETWORK_FNAME, INDEXER_URL_FNAME};
@@ -15,34 +16,62 @@ use lightning::{
     sign::KeysManager,
     util::ser::{Writeable, Writer},
 };
-use lightning_persister::fs_store::FilesystemStore;
 use magic_crypt::{new_magic_crypt, MagicCryptTrait};
 use rgb_lib::{bdk::keys::bip39::Mnemonic, BitcoinNetwork, ContractId};
 use std::{
+    collections::{HashMap, HashSet},
     fmt::Write,
     fs,
-    net::{SocketAddr, ToSocketAddrs},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
     path::Path,
     path::PathBuf,
+    pin::Pin,
     str::FromStr,
-    sync::{Arc, Mutex, MutexGuard},
+    sync::{mpsc, Arc, Mutex},
     time::{Duration, SystemTime},
 };
-use tokio::sync::{Mutex as TokioMutex, MutexGuard as TokioMutexGuard};
+use tokio::sync::{broadcast, Mutex as TokioMutex, MutexGuard as TokioMutexGuard};
 use tokio_util::sync::CancellationToken;
-
-use crate::ldk::{ChannelIdsMap, Router};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::auth::{read_api_tokens, ApiTokenMap};
+use crate::channel_conversion::ChannelConversionMap;
+use crate::channel_request::ChannelRequestMap;
+use crate::events::{new_event_sender, NodeEvent};
+use crate::fee_order::FeeOrderMap;
+use crate::fee_report::FeeReportMap;
+use crate::forwarding_history::ForwardingHistory;
+use crate::journal::StateJournal;
+use crate::kv_store::{NodeStore, StoreBackend};
+use crate::ldk::{
+    AssetHtlcLimitMap, ChainMonitor, ChannelClosureMap, ChannelIdsMap, ChannelTransferMap,
+    CloseAddressMap, FundingBatch, FundingChange, HeldIntercept, HtlcLimits, LnurlWithdrawMap,
+    PendingFunding, RelayKeys, Router, MAX_FEE_RATE, MIN_FEE_RATE,
+};
+use crate::lease::Lease;
+use crate::locks::{lock, AuditedGuard};
+use crate::peer_messages::PeerMessageHandler;
+use crate::peer_policy::PeerPolicy;
+use crate::proxy::{ConsignmentProxyMap, ProxyPinMap};
 use crate::rgb::{get_rgb_channel_info_optional, RgbLibWalletWrapper};
-use crate::routes::{DEFAULT_FINAL_CLTV_EXPIRY_DELTA, HTLC_MIN_MSAT};
+use crate::rotation::NodeIdRotation;
+use crate::routes::{SeedWord, HTLC_MIN_MSAT};
+use crate::schedule::ScheduleMap;
+use crate::scheduled_backup::{BackupSchedule, ScheduledBackupMap};
+use crate::snapshot::SnapshotTracker;
+use crate::spend_limits::{HeldSpend, SpendHistory, SpendLimits};
+use crate::submarine_swap::SubmarineSwapMap;
+use crate::swap_offer::SwapOfferBook;
+use crate::tor::{connect_outbound_via_proxy, OnionService, TOR_ONION_KEY_FNAME};
 use crate::{
     args::LdkUserInfo,
     bitcoind::BitcoindClient,
     disk::FilesystemLogger,
     error::{APIError, AppError},
     ldk::{
-        BumpTxEventHandler, ChannelManager, InboundPaymentInfoStorage, LdkBackgroundServices,
-        NetworkGraph, OnionMessenger, OutboundPaymentInfoStorage, OutputSweeper, PeerManager,
-        SwapMap,
+        BumpTxEventHandler, ChannelManager, InboundPaymentInfoStorage, InboundPaymentUpdate,
+        LdkBackgroundServices, NetworkGraph, OnionMessenger, OutboundPaymentInfoStorage,
+        OutputSweeper, PeerManager, SwapMap,
     },
 };
 
@@ -54,21 +83,49 @@ pub(crate) const PROXY_ENDPOINT_REGTEST: &str = "rpc://127.0.0.1:3000/json-rpc";
 const PROXY_ENDPOINT_TESTNET: &str = "rpcs://proxy.iriswallet.com/0.2/json-rpc";
 const PASSWORD_MIN_LENGTH: u8 = 8;
 
+/// Handle to change the filter of the file log at runtime
+pub(crate) type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
 pub(crate) struct AppState {
     pub(crate) static_state: Arc<StaticState>,
     pub(crate) cancel_token: CancellationToken,
     pub(crate) unlocked_app_state: Arc<TokioMutex<Option<Arc<UnlockedAppState>>>>,
     pub(crate) ldk_background_services: Arc<Mutex<Option<LdkBackgroundServices>>>,
     pub(crate) changing_state: Mutex<bool>,
+    pub(crate) event_sender: broadcast::Sender<NodeEvent>,
+    pub(crate) standby: Mutex<bool>,
+    /// Whether new channels are announced unless requested otherwise
+    pub(crate) announce_channels: Mutex<bool>,
+    pub(crate) api_tokens: Mutex<ApiTokenMap>,
+    /// Keys to relay with while locked, in relay mode, set by the last unlock
+    pub(crate) relay_keys: Mutex<Option<RelayKeys>>,
 }
 
 impl AppState {
-    pub(crate) fn get_changing_state(&self) -> MutexGuard<bool> {
-        self.changing_state.lock().unwrap()
+    pub(crate) fn get_announce_channels(&self) -> AuditedGuard<bool> {
+        lock(&self.announce_channels, "announce_channels")
+    }
+
+    pub(crate) fn get_api_tokens(&self) -> AuditedGuard<ApiTokenMap> {
+        lock(&self.api_tokens, "api_tokens")
+    }
+
+    pub(crate) fn get_relay_keys(&self) -> AuditedGuard<Option<RelayKeys>> {
+        lock(&self.relay_keys, "relay_keys")
     }
 
-    pub(crate) fn get_ldk_background_services(&self) -> MutexGuard<Option<LdkBackgroundServices>> {
-        self.ldk_background_services.lock().unwrap()
+    pub(crate) fn get_changing_state(&self) -> AuditedGuard<bool> {
+        lock(&self.changing_state, "changing_state")
+    }
+
+    pub(crate) fn get_ldk_background_services(
+        &self,
+    ) -> AuditedGuard<Option<LdkBackgroundServices>> {
+        lock(&self.ldk_background_services, "ldk_background_services")
+    }
+
+    pub(crate) fn get_standby(&self) -> AuditedGuard<bool> {
+        lock(&self.standby, "standby")
     }
 
     pub(crate) async fn get_unlocked_app_state(
@@ -80,6 +137,7 @@ impl AppState {
 
 pub(crate) struct StaticState {
     pub(crate) ldk_peer_listening_port: u16,
+    pub(crate) ldk_peer_listen_addrs: Vec<SocketAddr>,
     pub(crate) ldk_announced_listen_addr: Vec<SocketAddress>,
     pub(crate) ldk_announced_node_name: [u8; 32],
     pub(crate) network: Network,
@@ -87,20 +145,56 @@ pub(crate) struct StaticState {
     pub(crate) ldk_data_dir: ColorSource,
     pub(crate) logger: Arc<FilesystemLogger>,
     pub(crate) indexer_url: String,
-    pub(crate) proxy_endpoint: String,
+    /// RGB proxies, in order of priority
+    pub(crate) proxy_endpoints: Vec<String>,
     pub(crate) bitcoind_client: Arc<BitcoindClient>,
     pub(crate) max_media_upload_size_mb: u16,
+    pub(crate) utxo_size_sat: u32,
+    pub(crate) max_allocations_per_utxo: u32,
+    /// Fee rate (in sat/vB) of on-chain sends and channel fundings not requesting one
+    pub(crate) fee_rate: f32,
+    pub(crate) min_closing_fee_rate: f32,
+    pub(crate) max_closing_fee_rate: Option<f32>,
+    pub(crate) payment_retry: Retry,
+    pub(crate) lnurl_base_url: Option<String>,
+    pub(crate) lnurl_min_sendable_msat: u64,
+    pub(crate) lnurl_max_sendable_msat: u64,
+    pub(crate) relay_mode: bool,
+    pub(crate) htlc_limits: HtlcLimits,
+    pub(crate) reject_keysend: bool,
+    pub(crate) lease: Option<Lease>,
+    pub(crate) alert_webhook_url: Option<String>,
+    pub(crate) derived_blinding: bool,
+    pub(crate) rgb_refresh_interval: Option<Duration>,
+    pub(crate) rgb_refresh_parallelism: usize,
+    pub(crate) submarine_swap_server: bool,
+    pub(crate) price_oracle_url: Option<String>,
+    pub(crate) swap_quote_fee_ppm: u32,
+    pub(crate) store_backend: StoreBackend,
+    pub(crate) postgres_url: Option<String>,
+    pub(crate) vss_url: Option<String>,
+    pub(crate) encrypt_storage: bool,
+    pub(crate) backup_schedule: Option<BackupSchedule>,
+    /// Unset to disable the spend limits
+    pub(crate) spend_limits: Option<SpendLimits>,
+    /// Whether requests without an API token are refused
+    pub(crate) require_api_token: bool,
+    /// SOCKS5 proxy the outbound peer connections go through
+    pub(crate) tor_proxy: Option<SocketAddr>,
+    /// Unset when the log filter cannot be changed at runtime
+    pub(crate) log_filter: Option<LogFilterHandle>,
 }
 
 pub(crate) struct UnlockedAppState {
     pub(crate) channel_manager: Arc<ChannelManager>,
     pub(crate) inbound_payments: Arc<Mutex<InboundPaymentInfoStorage>>,
+    pub(crate) inbound_payment_updates: mpsc::Sender<InboundPaymentUpdate>,
     pub(crate) keys_manager: Arc<KeysManager>,
     pub(crate) network_graph: Arc<NetworkGraph>,
     pub(crate) onion_messenger: Arc<OnionMessenger>,
     pub(crate) outbound_payments: Arc<Mutex<OutboundPaymentInfoStorage>>,
     pub(crate) peer_manager: Arc<PeerManager>,
-    pub(crate) fs_store: Arc<FilesystemStore>,
+    pub(crate) kv_store: Arc<NodeStore>,
     pub(crate) bump_tx_event_handler: Arc<BumpTxEventHandler>,
     pub(crate) maker_swaps: Arc<Mutex<SwapMap>>,
     pub(crate) taker_swaps: Arc<Mutex<SwapMap>>,
@@ -109,27 +203,180 @@ pub(crate) struct UnlockedAppState {
     pub(crate) output_sweeper: Arc<OutputSweeper>,
     pub(crate) rgb_send_lock: Arc<Mutex<bool>>,
     pub(crate) channel_ids_map: Arc<Mutex<ChannelIdsMap>>,
+    pub(crate) funding_changes: Arc<Mutex<HashMap<ChannelId, FundingChange>>>,
+    pub(crate) funding_utxos: Arc<Mutex<HashMap<ChannelId, Vec<Utxo>>>>,
+    pub(crate) funding_proxies: Arc<Mutex<HashMap<ChannelId, String>>>,
+    pub(crate) funding_fee_rates: Arc<Mutex<HashMap<ChannelId, f32>>>,
+    pub(crate) funding_batches: Arc<Mutex<Vec<FundingBatch>>>,
+    /// Channels whose funding transaction is to be signed externally
+    pub(crate) external_funding: Arc<Mutex<HashSet<ChannelId>>>,
+    pub(crate) pending_fundings: Arc<Mutex<HashMap<ChannelId, PendingFunding>>>,
+    pub(crate) held_intercepts: Arc<Mutex<HashMap<InterceptId, HeldIntercept>>>,
+    pub(crate) lnurl_withdraws: Arc<Mutex<LnurlWithdrawMap>>,
+    pub(crate) chain_monitor: Arc<ChainMonitor>,
+    pub(crate) node_id_rotation: Arc<Mutex<Option<NodeIdRotation>>>,
+    pub(crate) peer_policy: Arc<Mutex<PeerPolicy>>,
+    pub(crate) proxy_pins: Arc<Mutex<ProxyPinMap>>,
+    pub(crate) consignment_proxies: Arc<Mutex<ConsignmentProxyMap>>,
+    pub(crate) channel_transfers: Arc<Mutex<ChannelTransferMap>>,
+    pub(crate) channel_requests: Arc<Mutex<ChannelRequestMap>>,
+    pub(crate) peer_message_handler: Arc<PeerMessageHandler>,
+    pub(crate) schedules: Arc<Mutex<ScheduleMap>>,
+    pub(crate) scheduled_backups: Arc<Mutex<ScheduledBackupMap>>,
+    pub(crate) fee_report: Arc<Mutex<FeeReportMap>>,
+    pub(crate) forwarding_history: Arc<Mutex<ForwardingHistory>>,
+    pub(crate) spend_history: Arc<Mutex<SpendHistory>>,
+    /// Operations going over the spend limits, keyed by confirmation token
+    pub(crate) held_spends: Arc<Mutex<HashMap<String, HeldSpend>>>,
+    pub(crate) fee_orders: Arc<Mutex<FeeOrderMap>>,
+    pub(crate) swap_offers: Arc<Mutex<SwapOfferBook>>,
+    pub(crate) submarine_swaps: Arc<Mutex<SubmarineSwapMap>>,
+    pub(crate) asset_htlc_limits: Arc<Mutex<AssetHtlcLimitMap>>,
+    pub(crate) close_addresses: Arc<Mutex<CloseAddressMap>>,
+    pub(crate) channel_closures: Arc<Mutex<ChannelClosureMap>>,
+    pub(crate) channel_conversions: Arc<Mutex<ChannelConversionMap>>,
+    pub(crate) bump_fee_rates: Arc<Mutex<HashMap<OutPoint, u32>>>,
+    pub(crate) snapshot_tracker: SnapshotTracker,
+    /// Write-ahead journal of the payment and swap status changes
+    pub(crate) journal: StateJournal,
+    pub(crate) relay_only: bool,
 }
 
 impl UnlockedAppState {
-    pub(crate) fn get_inbound_payments(&self) -> MutexGuard<InboundPaymentInfoStorage> {
-        self.inbound_payments.lock().unwrap()
+    pub(crate) fn get_inbound_payments(&self) -> AuditedGuard<InboundPaymentInfoStorage> {
+        lock(&self.inbound_payments, "inbound_payments")
+    }
+
+    pub(crate) fn get_outbound_payments(&self) -> AuditedGuard<OutboundPaymentInfoStorage> {
+        lock(&self.outbound_payments, "outbound_payments")
+    }
+
+    pub(crate) fn get_maker_swaps(&self) -> AuditedGuard<SwapMap> {
+        lock(&self.maker_swaps, "maker_swaps")
+    }
+
+    pub(crate) fn get_taker_swaps(&self) -> AuditedGuard<SwapMap> {
+        lock(&self.taker_swaps, "taker_swaps")
+    }
+
+    pub(crate) fn get_channel_ids_map(&self) -> AuditedGuard<ChannelIdsMap> {
+        lock(&self.channel_ids_map, "channel_ids_map")
+    }
+
+    pub(crate) fn get_funding_changes(&self) -> AuditedGuard<HashMap<ChannelId, FundingChange>> {
+        lock(&self.funding_changes, "funding_changes")
+    }
+
+    pub(crate) fn get_funding_utxos(&self) -> AuditedGuard<HashMap<ChannelId, Vec<Utxo>>> {
+        lock(&self.funding_utxos, "funding_utxos")
+    }
+
+    pub(crate) fn get_funding_proxies(&self) -> AuditedGuard<HashMap<ChannelId, String>> {
+        lock(&self.funding_proxies, "funding_proxies")
+    }
+
+    pub(crate) fn get_funding_fee_rates(&self) -> AuditedGuard<HashMap<ChannelId, f32>> {
+        lock(&self.funding_fee_rates, "funding_fee_rates")
+    }
+
+    pub(crate) fn get_funding_batches(&self) -> AuditedGuard<Vec<FundingBatch>> {
+        lock(&self.funding_batches, "funding_batches")
+    }
+
+    pub(crate) fn get_external_funding(&self) -> AuditedGuard<HashSet<ChannelId>> {
+        lock(&self.external_funding, "external_funding")
+    }
+
+    pub(crate) fn get_pending_fundings(&self) -> AuditedGuard<HashMap<ChannelId, PendingFunding>> {
+        lock(&self.pending_fundings, "pending_fundings")
+    }
+
+    pub(crate) fn get_held_intercepts(&self) -> AuditedGuard<HashMap<InterceptId, HeldIntercept>> {
+        lock(&self.held_intercepts, "held_intercepts")
+    }
+
+    pub(crate) fn get_lnurl_withdraws(&self) -> AuditedGuard<LnurlWithdrawMap> {
+        lock(&self.lnurl_withdraws, "lnurl_withdraws")
+    }
+
+    pub(crate) fn get_node_id_rotation(&self) -> AuditedGuard<Option<NodeIdRotation>> {
+        lock(&self.node_id_rotation, "node_id_rotation")
+    }
+
+    pub(crate) fn get_peer_policy(&self) -> AuditedGuard<PeerPolicy> {
+        lock(&self.peer_policy, "peer_policy")
+    }
+
+    pub(crate) fn get_proxy_pins(&self) -> AuditedGuard<ProxyPinMap> {
+        lock(&self.proxy_pins, "proxy_pins")
+    }
+
+    pub(crate) fn get_consignment_proxies(&self) -> AuditedGuard<ConsignmentProxyMap> {
+        lock(&self.consignment_proxies, "consignment_proxies")
+    }
+
+    pub(crate) fn get_channel_transfers(&self) -> AuditedGuard<ChannelTransferMap> {
+        lock(&self.channel_transfers, "channel_transfers")
+    }
+
+    pub(crate) fn get_channel_requests(&self) -> AuditedGuard<ChannelRequestMap> {
+        lock(&self.channel_requests, "channel_requests")
+    }
+
+    pub(crate) fn get_schedules(&self) -> AuditedGuard<ScheduleMap> {
+        lock(&self.schedules, "schedules")
+    }
+
+    pub(crate) fn get_scheduled_backups(&self) -> AuditedGuard<ScheduledBackupMap> {
+        lock(&self.scheduled_backups, "scheduled_backups")
+    }
+
+    pub(crate) fn get_fee_report(&self) -> AuditedGuard<FeeReportMap> {
+        lock(&self.fee_report, "fee_report")
+    }
+
+    pub(crate) fn get_forwarding_history(&self) -> AuditedGuard<ForwardingHistory> {
+        lock(&self.forwarding_history, "forwarding_history")
+    }
+
+    pub(crate) fn get_spend_history(&self) -> AuditedGuard<SpendHistory> {
+        lock(&self.spend_history, "spend_history")
+    }
+
+    pub(crate) fn get_held_spends(&self) -> AuditedGuard<HashMap<String, HeldSpend>> {
+        lock(&self.held_spends, "held_spends")
+    }
+
+    pub(crate) fn get_fee_orders(&self) -> AuditedGuard<FeeOrderMap> {
+        lock(&self.fee_orders, "fee_orders")
     }
 
-    pub(crate) fn get_outbound_payments(&self) -> MutexGuard<OutboundPaymentInfoStorage> {
-        self.outbound_payments.lock().unwrap()
+    pub(crate) fn get_swap_offers(&self) -> AuditedGuard<SwapOfferBook> {
+        lock(&self.swap_offers, "swap_offers")
     }
 
-    pub(crate) fn get_maker_swaps(&self) -> MutexGuard<SwapMap> {
-        self.maker_swaps.lock().unwrap()
+    pub(crate) fn get_submarine_swaps(&self) -> AuditedGuard<SubmarineSwapMap> {
+        lock(&self.submarine_swaps, "submarine_swaps")
     }
 
-    pub(crate) fn get_taker_swaps(&self) -> MutexGuard<SwapMap> {
-        self.taker_swaps.lock().unwrap()
+    pub(crate) fn get_asset_htlc_limits(&self) -> AuditedGuard<AssetHtlcLimitMap> {
+        lock(&self.asset_htlc_limits, "asset_htlc_limits")
     }
 
-    pub(crate) fn get_channel_ids_map(&self) -> MutexGuard<ChannelIdsMap> {
-        self.channel_ids_map.lock().unwrap()
+    pub(crate) fn get_close_addresses(&self) -> AuditedGuard<CloseAddressMap> {
+        lock(&self.close_addresses, "close_addresses")
+    }
+
+    pub(crate) fn get_channel_closures(&self) -> AuditedGuard<ChannelClosureMap> {
+        lock(&self.channel_closures, "channel_closures")
+    }
+
+    pub(crate) fn get_channel_conversions(&self) -> AuditedGuard<ChannelConversionMap> {
+        lock(&self.channel_conversions, "channel_conversions")
+    }
+
+    pub(crate) fn get_bump_fee_rates(&self) -> AuditedGuard<HashMap<OutPoint, u32>> {
+        lock(&self.bump_fee_rates, "bump_fee_rates")
     }
 }
 
@@ -183,6 +430,28 @@ pub(crate) fn check_password_validity(
     }
 }
 
+/// Whether the given words match the ones of the mnemonic at their positions
+pub(crate) fn check_seed_words(mnemonic: &Mnemonic, words: &[SeedWord]) -> Result<bool, APIError> {
+    if words.is_empty() {
+        return Err(APIError::InvalidSeedWords(s!("no words provided")));
+    }
+    let mnemonic_str = mnemonic.to_string();
+    let mnemonic_words: Vec<&str> = mnemonic_str.split_whitespace().collect();
+    if let Some(w) = words
+        .iter()
+        .find(|w| w.position == 0 || w.position as usize > mnemonic_words.len())
+    {
+        return Err(APIError::InvalidSeedWords(format!(
+            "position {} is out of range (1-{})",
+            w.position,
+            mnemonic_words.len()
+        )));
+    }
+    Ok(words
+        .iter()
+        .all(|w| w.word.trim().to_lowercase() == mnemonic_words[w.position as usize - 1]))
+}
+
 pub(crate) fn check_channel_id(channel_id_str: &str) -> Result<ChannelId, APIError> {
     if let Some(channel_id_bytes) = hex_str_to_vec(channel_id_str) {
         if channel_id_bytes.len() != 32 {
@@ -194,10 +463,50 @@ pub(crate) fn check_channel_id(channel_id_str: &str) -> Result<ChannelId, APIErr
     }
 }
 
+pub(crate) fn get_payment_retry(
+    default_retry: Retry,
+    retry_attempts: Option<u32>,
+    retry_timeout_secs: Option<u64>,
+) -> Result<Retry, APIError> {
+    match (retry_attempts, retry_timeout_secs) {
+        (Some(_), Some(_)) => Err(APIError::InvalidPaymentRetry(s!(
+            "retry attempts and retry timeout cannot be set together"
+        ))),
+        (Some(attempts), None) => Ok(Retry::Attempts(attempts)),
+        (None, Some(timeout_secs)) => Ok(Retry::Timeout(Duration::from_secs(timeout_secs))),
+        (None, None) => Ok(default_retry),
+    }
+}
+
+/// The requested fee rate (in sat/vB), if within bounds, or the default one
+pub(crate) fn get_fee_rate(default_fee_rate: f32, fee_rate: Option<f32>) -> Result<f32, APIError> {
+    match fee_rate {
+        Some(fee_rate) if !(MIN_FEE_RATE..=MAX_FEE_RATE).contains(&fee_rate) => {
+            Err(APIError::InvalidFeeRate(format!(
+                "fee rate must be between {MIN_FEE_RATE} and {MAX_FEE_RATE} sat/vB"
+            )))
+        }
+        Some(fee_rate) => Ok(fee_rate),
+        None => Ok(default_fee_rate),
+    }
+}
+
+pub(crate) fn retry_details(retry: Retry) -> (Option<u32>, Option<u64>) {
+    match retry {
+        Retry::Attempts(attempts) => (Some(attempts), None),
+        Retry::Timeout(timeout) => (None, Some(timeout.as_secs())),
+    }
+}
+
 pub(crate) fn get_mnemonic_path(storage_dir_path: &Path) -> PathBuf {
     storage_dir_path.join("mnemonic")
 }
 
+/// Marker of the mnemonic having been revealed by `/backupseed`
+pub(crate) fn get_seed_revealed_path(storage_dir_path: &Path) -> PathBuf {
+    storage_dir_path.join("seed_revealed")
+}
+
 pub(crate) fn encrypt_and_save_mnemonic(
     password: String,
     mnemonic: String,
@@ -219,7 +528,8 @@ pub(crate) fn encrypt_and_save_mnemonic(
 
 pub(crate) async fn connect_peer_if_necessary(
     pubkey: PublicKey,
-    address: SocketAddr,
+    address: SocketAddress,
+    tor_proxy: Option<SocketAddr>,
     peer_manager: Arc<PeerManager>,
 ) -> Result<(), APIError> {
     for peer_details in peer_manager.list_peers() {
@@ -227,29 +537,43 @@ pub(crate) async fn connect_peer_if_necessary(
             return Ok(());
         }
     }
-    do_connect_peer(pubkey, address, peer_manager).await?;
+    do_connect_peer(pubkey, address.clone(), tor_proxy, peer_manager).await?;
     tracing::info!("connected to peer (pubkey: {pubkey}, addr: {address})");
     Ok(())
 }
 
+/// Connect to the peer, through the SOCKS5 proxy if one is given, which then resolves its host
 pub(crate) async fn do_connect_peer(
     pubkey: PublicKey,
-    address: SocketAddr,
+    address: SocketAddress,
+    tor_proxy: Option<SocketAddr>,
     peer_manager: Arc<PeerManager>,
 ) -> Result<(), APIError> {
-    match lightning_net_tokio::connect_outbound(Arc::clone(&peer_manager), pubkey, address).await {
-        Some(connection_closed_future) => {
-            let mut connection_closed_future = Box::pin(connection_closed_future);
-            loop {
-                tokio::select! {
-                    _ = &mut connection_closed_future => return Err(APIError::FailedPeerConnection),
-                    _ = tokio::time::sleep(Duration::from_millis(10)) => {},
-                };
-                if peer_manager.peer_by_node_id(&pubkey).is_some() {
-                    return Ok(());
-                }
-            }
+    type ConnectionClosedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+    let pm = Arc::clone(&peer_manager);
+    let connection_closed_future = match tor_proxy {
+        Some(proxy) => connect_outbound_via_proxy(pm, pubkey, proxy, address)
+            .await
+            .map(|f| Box::pin(f) as ConnectionClosedFuture),
+        None => {
+            let Some(address) = address.to_socket_addrs().ok().and_then(|mut a| a.next()) else {
+                return Err(APIError::FailedPeerConnection);
+            };
+            lightning_net_tokio::connect_outbound(pm, pubkey, address)
+                .await
+                .map(|f| Box::pin(f) as ConnectionClosedFuture)
         }
+    };
+    match connection_closed_future {
+        Some(mut connection_closed_future) => loop {
+            tokio::select! {
+                _ = &mut connection_closed_future => return Err(APIError::FailedPeerConnection),
+                _ = tokio::time::sleep(Duration::from_millis(10)) => {},
+            };
+            if peer_manager.peer_by_node_id(&pubkey).is_some() {
+                return Ok(());
+            }
+        },
         None => Err(APIError::FailedPeerConnection),
     }
 }
@@ -311,20 +635,19 @@ where
     rx.await.unwrap()
 }
 
+/// Parse `pubkey[@host:port]`. The host is not resolved here, so that it can be resolved by the
+/// proxy if the node connects through one.
 pub(crate) fn parse_peer_info(
     peer_pubkey_and_ip_addr: String,
-) -> Result<(PublicKey, Option<SocketAddr>), APIError> {
+) -> Result<(PublicKey, Option<SocketAddress>), APIError> {
     let mut pubkey_and_addr = peer_pubkey_and_ip_addr.split('@');
     let pubkey = pubkey_and_addr.next();
 
     let peer_addr = if let Some(peer_addr_str) = pubkey_and_addr.next() {
-        let peer_addr = peer_addr_str.to_socket_addrs().map(|mut r| r.next());
-        if peer_addr.is_err() || peer_addr.as_ref().unwrap().is_none() {
-            return Err(APIError::InvalidPeerInfo(s!(
-                "couldn't parse pubkey@host:port into a socket address"
-            )));
-        }
-        peer_addr.unwrap()
+        let peer_addr = SocketAddress::from_str(peer_addr_str).map_err(|_| {
+            APIError::InvalidPeerInfo(s!("couldn't parse pubkey@host:port into a socket address"))
+        })?;
+        Some(peer_addr)
     } else {
         None
     };
@@ -352,6 +675,7 @@ pub(crate) async fn start_daemon(args: &LdkUserInfo) -> Result<Arc<AppState>, Ap
         args.bitcoind_rpc_password.clone(),
         tokio::runtime::Handle::current(),
         Arc::clone(&logger),
+        args.min_closing_fee_rate,
     )
     .await
     {
@@ -377,7 +701,7 @@ pub(crate) async fn start_daemon(args: &LdkUserInfo) -> Result<Arc<AppState>, Ap
     }
 
     // RGB setup
-    let (indexer_url, proxy_endpoint) = match network {
+    let (indexer_url, default_proxy_endpoint) = match network {
         bitcoin::Network::Testnet => (ELECTRUM_URL_TESTNET, PROXY_ENDPOINT_TESTNET),
         bitcoin::Network::Regtest => (ELECTRUM_URL_REGTEST, PROXY_ENDPOINT_REGTEST),
         _ => {
@@ -385,6 +709,11 @@ pub(crate) async fn start_daemon(args: &LdkUserInfo) -> Result<Arc<AppState>, Ap
         }
     };
     fs::write(args.storage_dir_path.join(INDEXER_URL_FNAME), indexer_url).expect("able to write");
+    let proxy_endpoints = if args.proxy_endpoints.is_empty() {
+        vec![default_proxy_endpoint.to_string()]
+    } else {
+        args.proxy_endpoints.clone()
+    };
     let bitcoin_network: BitcoinNetwork = network.into();
     fs::write(
         args.storage_dir_path.join(BITCOIN_NETWORK_FNAME),
@@ -394,26 +723,95 @@ pub(crate) async fn start_daemon(args: &LdkUserInfo) -> Result<Arc<AppState>, Ap
 
     let cancel_token = CancellationToken::new();
 
+    // all interfaces, unless specific addresses have been requested
+    let ldk_peer_listen_addrs = if args.ldk_peer_listen_addrs.is_empty() {
+        vec![SocketAddr::new(
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            args.ldk_peer_listening_port,
+        )]
+    } else {
+        args.ldk_peer_listen_addrs.clone()
+    };
+
+    let mut ldk_announced_listen_addr = args.ldk_announced_listen_addr.clone();
+    if let Some(tor_control) = args.tor_control {
+        // the onion service forwards to the first listen address, via loopback if unspecified
+        let mut target = ldk_peer_listen_addrs[0];
+        if target.ip().is_unspecified() {
+            target.set_ip(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        }
+        let (onion_service, onion_addr) = OnionService::create(
+            tor_control,
+            args.tor_control_password.clone(),
+            args.tor_persist_onion_key
+                .then(|| ldk_data_dir.join(TOR_ONION_KEY_FNAME)),
+            args.ldk_peer_listening_port,
+            target,
+        )
+        .await
+        .map_err(AppError::FailedOnionService)?;
+        tracing::info!("Created onion service {onion_addr}");
+        onion_service.keep_open();
+        ldk_announced_listen_addr.push(onion_addr);
+    }
+
     let static_state = Arc::new(StaticState {
         ldk_peer_listening_port: args.ldk_peer_listening_port,
-        ldk_announced_listen_addr: args.ldk_announced_listen_addr.clone(),
+        ldk_peer_listen_addrs,
+        ldk_announced_listen_addr,
         ldk_announced_node_name: args.ldk_announced_node_name,
         network,
         storage_dir_path: args.storage_dir_path.clone(),
         ldk_data_dir,
         logger,
         indexer_url: indexer_url.to_string(),
-        proxy_endpoint: proxy_endpoint.to_string(),
+        proxy_endpoints,
         bitcoind_client,
         max_media_upload_size_mb: args.max_media_upload_size_mb,
+        utxo_size_sat: args.utxo_size_sat,
+        max_allocations_per_utxo: args.max_allocations_per_utxo,
+        fee_rate: args.fee_rate,
+        min_closing_fee_rate: args.min_closing_fee_rate,
+        max_closing_fee_rate: args.max_closing_fee_rate,
+        payment_retry: args.payment_retry,
+        lnurl_base_url: args.lnurl_base_url.clone(),
+        lnurl_min_sendable_msat: args.lnurl_min_sendable_msat,
+        lnurl_max_sendable_msat: args.lnurl_max_sendable_msat,
+        relay_mode: args.relay_mode,
+        htlc_limits: args.htlc_limits,
+        reject_keysend: args.reject_keysend,
+        lease: args.failover.then(|| Lease::new(&args.storage_dir_path)),
+        alert_webhook_url: args.alert_webhook_url.clone(),
+        derived_blinding: args.derived_blinding,
+        rgb_refresh_interval: args.rgb_refresh_interval,
+        rgb_refresh_parallelism: args.rgb_refresh_parallelism,
+        submarine_swap_server: args.submarine_swap_server,
+        price_oracle_url: args.price_oracle_url.clone(),
+        swap_quote_fee_ppm: args.swap_quote_fee_ppm,
+        store_backend: args.store_backend,
+        postgres_url: args.postgres_url.clone(),
+        vss_url: args.vss_url.clone(),
+        encrypt_storage: args.encrypt_storage,
+        backup_schedule: args.backup_schedule.clone(),
+        spend_limits: args.spend_limits.clone(),
+        require_api_token: args.require_api_token,
+        tor_proxy: args.tor_proxy,
+        log_filter: args.log_filter.clone(),
     });
 
+    let api_tokens = read_api_tokens(&args.storage_dir_path).map_err(AppError::InvalidApiTokens)?;
+
     Ok(Arc::new(AppState {
         static_state,
         cancel_token,
         unlocked_app_state: Arc::new(TokioMutex::new(None)),
         ldk_background_services: Arc::new(Mutex::new(None)),
         changing_state: Mutex::new(false),
+        event_sender: new_event_sender(),
+        standby: Mutex::new(false),
+        announce_channels: Mutex::new(args.announce_channels),
+        api_tokens: Mutex::new(api_tokens),
+        relay_keys: Mutex::new(None),
     }))
 }
 
@@ -451,6 +849,8 @@ pub(crate) fn get_route(
     final_value_msat: Option<u64>,
     rgb_payment: Option<(ContractId, u64)>,
     hints: Vec<RouteHint>,
+    final_cltv_expiry_delta: u32,
+    first_hops: Option<&[&ChannelDetails]>,
 ) -> Option<Route> {
     let inflight_htlcs = channel_manager.compute_inflight_htlcs();
     let payment_params = PaymentParameters {
@@ -458,7 +858,7 @@ pub(crate) fn get_route(
             node_id: dest,
             route_hints: hints,
             features: None,
-            final_cltv_expiry_delta: DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
+            final_cltv_expiry_delta,
         },
         expiry_time: None,
         max_total_cltv_expiry_delta: DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA,
@@ -475,7 +875,7 @@ pub(crate) fn get_route(
             max_total_routing_fee_msat: None,
             rgb_payment,
         },
-        None,
+        first_hops,
         inflight_htlcs,
     );
 
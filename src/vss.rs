@@ -0,0 +1,424 @@
+use base64::{engine::general_purpose, Engine as _};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use lightning::util::persist::{
+    CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE, NETWORK_GRAPH_PERSISTENCE_KEY,
+    SCORER_PERSISTENCE_KEY,
+};
+use rgb_lib::bitcoin::bip32::ExtendedPrivKey;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Error, ErrorKind};
+use std::sync::mpsc as std_mpsc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Time a VSS request has to complete
+const VSS_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Time writes of channel monitors wait for them to be mirrored, before going on without
+const VSS_SYNC_WRITE_TIMEOUT_SECS: u64 = 15;
+
+/// Interval between attempts at mirroring writes that failed
+const VSS_RETRY_INTERVAL_SECS: u64 = 10;
+
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Deserialize, Serialize)]
+struct KeyValue {
+    key: String,
+    version: i64,
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Serialize)]
+struct StoreRequest<'a> {
+    store_id: &'a str,
+}
+
+#[derive(Serialize)]
+struct GetObjectRequest<'a> {
+    store_id: &'a str,
+    key: &'a str,
+}
+
+#[derive(Deserialize)]
+struct GetObjectResponse {
+    value: KeyValue,
+}
+
+#[derive(Serialize)]
+struct PutObjectsRequest<'a> {
+    store_id: &'a str,
+    transaction_items: Vec<KeyValue>,
+}
+
+#[derive(Serialize)]
+struct DeleteObjectRequest<'a> {
+    store_id: &'a str,
+    key_value: KeyValue,
+}
+
+#[derive(Deserialize)]
+struct ListKeyVersionsResponse {
+    key_versions: Vec<KeyValue>,
+}
+
+/// Derive the key the VSS store is identified and encrypted with, from the LDK account key.
+///
+/// It doesn't depend on the node key index, so the store survives node ID rotations
+pub(crate) fn derive_vss_key(ldk_xprv: &ExtendedPrivKey) -> [u8; 32] {
+    let mut engine = sha256::Hash::engine();
+    engine.input(b"rgb-lightning-node/vss");
+    engine.input(&ldk_xprv.private_key.secret_bytes());
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// Name of the entry in the VSS store
+fn remote_key(primary_namespace: &str, secondary_namespace: &str, key: &str) -> String {
+    format!("{primary_namespace}/{secondary_namespace}/{key}")
+}
+
+/// Whether writes of the entry wait for it to be mirrored
+fn is_critical(primary_namespace: &str) -> bool {
+    primary_namespace == CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE
+        || primary_namespace == CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE
+}
+
+/// Whether the entry is mirrored, the network graph and scorer being rebuilt from the network
+fn is_mirrored(primary_namespace: &str, key: &str) -> bool {
+    !(primary_namespace.is_empty()
+        && (key == NETWORK_GRAPH_PERSISTENCE_KEY || key == SCORER_PERSISTENCE_KEY))
+}
+
+enum VssJob {
+    Sync {
+        key: String,
+        value: Option<Vec<u8>>,
+        done: Option<std_mpsc::Sender<()>>,
+    },
+    DeleteNamespace {
+        name: String,
+    },
+    FetchAll {
+        done: std_mpsc::Sender<Result<Vec<(String, Vec<u8>)>, Error>>,
+    },
+}
+
+/// Client of a VSS server, keeping the versions of the stored entries
+struct VssClient {
+    client: reqwest::Client,
+    url: String,
+    store_id: String,
+    cipher: ChaCha20Poly1305,
+    versions: HashMap<String, i64>,
+}
+
+impl VssClient {
+    async fn post<T: Serialize>(&self, path: &str, body: &T) -> Result<reqwest::Response, Error> {
+        let res = self
+            .client
+            .post(format!("{}/{path}", self.url))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Error::other(format!("the VSS request failed: {e}")))?;
+        match res.status() {
+            reqwest::StatusCode::CONFLICT => Err(Error::new(
+                ErrorKind::AlreadyExists,
+                "version conflict, another node is writing to the VSS store",
+            )),
+            reqwest::StatusCode::NOT_FOUND => Err(Error::new(ErrorKind::NotFound, "key not found")),
+            status if !status.is_success() => Err(Error::other(format!(
+                "the VSS server answered with status {status}"
+            ))),
+            _ => Ok(res),
+        }
+    }
+
+    async fn load_versions(&mut self) -> Result<(), Error> {
+        let res: ListKeyVersionsResponse = self
+            .post(
+                "listKeyVersions",
+                &StoreRequest {
+                    store_id: &self.store_id,
+                },
+            )
+            .await?
+            .json()
+            .await
+            .map_err(|e| Error::other(format!("invalid VSS response: {e}")))?;
+        self.versions = res
+            .key_versions
+            .into_iter()
+            .map(|kv| (kv.key, kv.version))
+            .collect();
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, Error> {
+        let res: GetObjectResponse = self
+            .post(
+                "getObject",
+                &GetObjectRequest {
+                    store_id: &self.store_id,
+                    key,
+                },
+            )
+            .await?
+            .json()
+            .await
+            .map_err(|e| Error::other(format!("invalid VSS response: {e}")))?;
+        let data = general_purpose::STANDARD
+            .decode(res.value.value)
+            .map_err(|e| Error::other(format!("invalid VSS value: {e}")))?;
+        if data.len() < NONCE_LENGTH {
+            return Err(Error::other("invalid VSS value: too short"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::other("cannot decrypt the VSS value"))
+    }
+
+    async fn sync(&mut self, key: &str, value: Option<&[u8]>) -> Result<(), Error> {
+        let version = self.versions.get(key).copied().unwrap_or(0);
+        match value {
+            Some(value) => {
+                let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+                let mut data = nonce.to_vec();
+                data.extend(
+                    self.cipher
+                        .encrypt(&nonce, value)
+                        .map_err(|_| Error::other("cannot encrypt the VSS value"))?,
+                );
+                let request = PutObjectsRequest {
+                    store_id: &self.store_id,
+                    transaction_items: vec![KeyValue {
+                        key: key.to_string(),
+                        version,
+                        value: general_purpose::STANDARD.encode(data),
+                    }],
+                };
+                self.post("putObjects", &request).await?;
+                self.versions.insert(key.to_string(), version + 1);
+            }
+            None if !self.versions.contains_key(key) => {}
+            None => {
+                let request = DeleteObjectRequest {
+                    store_id: &self.store_id,
+                    key_value: KeyValue {
+                        key: key.to_string(),
+                        version,
+                        value: String::new(),
+                    },
+                };
+                self.post("deleteObject", &request).await?;
+                self.versions.remove(key);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mirror of the LDK data to a VSS server.
+///
+/// Writes are sent in order by a dedicated thread, which keeps retrying the ones that fail. Writes
+/// of channel monitors wait for them to be mirrored, the other ones don't
+pub(crate) struct VssMirror {
+    jobs: mpsc::UnboundedSender<VssJob>,
+}
+
+impl VssMirror {
+    pub(crate) fn new(url: &str, vss_key: [u8; 32]) -> Result<Self, Error> {
+        let mut vss_client = VssClient {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(VSS_REQUEST_TIMEOUT_SECS))
+                .build()
+                .map_err(Error::other)?,
+            url: url.trim_end_matches('/').to_string(),
+            store_id: sha256::Hash::hash(&vss_key).to_string(),
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&vss_key)),
+            versions: HashMap::new(),
+        };
+        let (jobs, job_receiver) = mpsc::unbounded_channel();
+        let (ready_sender, ready_receiver) = std_mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    let _ = ready_sender.send(Err(e));
+                    return;
+                }
+            };
+            runtime.block_on(async move {
+                if let Err(e) = vss_client.load_versions().await {
+                    let _ = ready_sender.send(Err(e));
+                    return;
+                }
+                let _ = ready_sender.send(Ok(()));
+                run_mirror(vss_client, job_receiver).await;
+            });
+        });
+        ready_receiver
+            .recv()
+            .map_err(|_| Error::other("the VSS mirror thread has exited"))??;
+        Ok(Self { jobs })
+    }
+
+    /// Mirror the write (or removal, without a value) of an entry
+    pub(crate) fn mirror(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        value: Option<&[u8]>,
+    ) {
+        if !is_mirrored(primary_namespace, key) {
+            return;
+        }
+        let (done, done_receiver) = if is_critical(primary_namespace) {
+            let (sender, receiver) = std_mpsc::channel();
+            (Some(sender), Some(receiver))
+        } else {
+            (None, None)
+        };
+        let job = VssJob::Sync {
+            key: remote_key(primary_namespace, secondary_namespace, key),
+            value: value.map(|v| v.to_vec()),
+            done,
+        };
+        if self.jobs.send(job).is_err() {
+            tracing::error!("the VSS mirror thread has exited, {key} is not mirrored");
+            return;
+        }
+        if let Some(done_receiver) = done_receiver {
+            if done_receiver
+                .recv_timeout(Duration::from_secs(VSS_SYNC_WRITE_TIMEOUT_SECS))
+                .is_err()
+            {
+                tracing::warn!("{key} has not been mirrored to VSS yet, going on");
+            }
+        }
+    }
+
+    /// Remove the given keys (without namespace) and primary namespaces from the mirror
+    pub(crate) fn delete(&self, names: &[&str]) {
+        for name in names {
+            let _ = self.jobs.send(VssJob::DeleteNamespace {
+                name: name.to_string(),
+            });
+        }
+    }
+
+    /// Fetch all the mirrored entries, as (primary namespace, secondary namespace, key, value)
+    pub(crate) fn fetch_all(&self) -> Result<Vec<(String, String, String, Vec<u8>)>, Error> {
+        let (done, done_receiver) = std_mpsc::channel();
+        self.jobs
+            .send(VssJob::FetchAll { done })
+            .map_err(|_| Error::other("the VSS mirror thread has exited"))?;
+        let entries = done_receiver
+            .recv()
+            .map_err(|_| Error::other("the VSS mirror thread has exited"))??;
+        entries
+            .into_iter()
+            .map(|(remote_key, value)| {
+                let mut parts = remote_key.splitn(3, '/');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(primary), Some(secondary), Some(key)) => Ok((
+                        primary.to_string(),
+                        secondary.to_string(),
+                        key.to_string(),
+                        value,
+                    )),
+                    _ => Err(Error::other(format!("invalid VSS key {remote_key}"))),
+                }
+            })
+            .collect()
+    }
+}
+
+async fn run_mirror(mut vss_client: VssClient, mut job_receiver: mpsc::UnboundedReceiver<VssJob>) {
+    let mut pending: VecDeque<(String, Option<Vec<u8>>, Option<std_mpsc::Sender<()>>)> =
+        VecDeque::new();
+    loop {
+        let job = if pending.is_empty() {
+            match job_receiver.recv().await {
+                Some(job) => Some(job),
+                None => break,
+            }
+        } else {
+            match tokio::time::timeout(
+                Duration::from_secs(VSS_RETRY_INTERVAL_SECS),
+                job_receiver.recv(),
+            )
+            .await
+            {
+                Ok(Some(job)) => Some(job),
+                Ok(None) => {
+                    tracing::error!("{} writes have not been mirrored to VSS", pending.len());
+                    break;
+                }
+                Err(_) => None,
+            }
+        };
+        match job {
+            Some(VssJob::Sync { key, value, done }) => pending.push_back((key, value, done)),
+            Some(VssJob::DeleteNamespace { name }) => {
+                let prefix = format!("{name}/");
+                let single_key = remote_key("", "", &name);
+                let keys = vss_client
+                    .versions
+                    .keys()
+                    .filter(|k| k.starts_with(&prefix) || **k == single_key)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                pending.extend(keys.into_iter().map(|k| (k, None, None)));
+            }
+            Some(VssJob::FetchAll { done }) => {
+                let mut entries = vec![];
+                let mut result = Ok(());
+                for key in vss_client.versions.keys() {
+                    match vss_client.get(key).await {
+                        Ok(value) => entries.push((key.clone(), value)),
+                        Err(e) => {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+                let _ = done.send(result.map(|_| entries));
+                continue;
+            }
+            None => {}
+        }
+        while let Some((key, value, done)) = pending.pop_front() {
+            match vss_client.sync(&key, value.as_deref()).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    tracing::error!("cannot mirror {key} to VSS: {e}");
+                }
+                Err(e) => {
+                    tracing::error!("cannot mirror {key} to VSS, retrying later: {e}");
+                    pending.push_front((key, value, done));
+                    // don't keep writers waiting while the server is unreachable
+                    for (_, _, done) in pending.iter_mut() {
+                        if let Some(done) = done.take() {
+                            let _ = done.send(());
+                        }
+                    }
+                    break;
+                }
+            }
+            if let Some(done) = done {
+                let _ = done.send(());
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+
+/// Files of the dashboard, embedded in the binary so no other file needs to be deployed
+const INDEX_HTML: &str = include_str!("../web-ui/index.html");
+const APP_JS: &str = include_str!("../web-ui/app.js");
+const STYLE_CSS: &str = include_str!("../web-ui/style.css");
+
+pub(crate) async fn index() -> Html<&'static str> {
+    Html(INDEX_HTML)
+}
+
+pub(crate) async fn asset(Path(file): Path<String>) -> Response {
+    let (content_type, content) = match file.as_str() {
+        "app.js" => ("text/javascript; charset=utf-8", APP_JS),
+        "style.css" => ("text/css; charset=utf-8", STYLE_CSS),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    ([(header::CONTENT_TYPE, content_type)], content).into_response()
+}
@@ -0,0 +1,236 @@
+'use strict';
+
+const MAX_EVENTS = 200;
+const REFRESH_INTERVAL_MS = 10000;
+
+let refreshTimer = null;
+let eventSocket = null;
+
+class ApiError extends Error {
+  constructor(status, message) {
+    super(message);
+    this.status = status;
+  }
+}
+
+async function api(method, path, body) {
+  const options = { method, headers: {} };
+  if (body !== undefined) {
+    options.headers['Content-Type'] = 'application/json';
+    options.body = JSON.stringify(body);
+  }
+  const res = await fetch(path, options);
+  const json = await res.json().catch(() => ({}));
+  if (!res.ok) {
+    throw new ApiError(res.status, json.error || res.statusText);
+  }
+  return json;
+}
+
+function showError(message) {
+  const el = document.getElementById('error');
+  el.textContent = message;
+  el.hidden = !message;
+}
+
+function cell(value, className) {
+  const td = document.createElement('td');
+  td.textContent = value === null || value === undefined ? '-' : String(value);
+  if (className) {
+    td.className = className;
+    td.title = td.textContent;
+  }
+  return td;
+}
+
+function fillTable(sectionId, rows) {
+  const tbody = document.querySelector(`#${sectionId} tbody`);
+  tbody.replaceChildren(...rows.map((cells) => {
+    const tr = document.createElement('tr');
+    tr.append(...cells);
+    return tr;
+  }));
+}
+
+function formatTimestamp(secs) {
+  return secs ? new Date(secs * 1000).toLocaleString() : null;
+}
+
+async function loadNodeInfo() {
+  const info = await api('GET', '/nodeinfo');
+  const items = [
+    ['Pubkey', info.pubkey],
+    ['Channels', `${info.num_usable_channels}/${info.num_channels} usable`],
+    ['Peers', info.num_peers],
+    ['Local balance (msat)', info.local_balance_msat],
+  ];
+  document.getElementById('node-info').replaceChildren(...items.map(([label, value]) => {
+    const div = document.createElement('div');
+    const span = document.createElement('span');
+    span.textContent = label;
+    div.append(span, String(value));
+    return div;
+  }));
+}
+
+async function loadChannels() {
+  const { channels } = await api('GET', '/listchannels');
+  fillTable('channels', channels.map((c) => [
+    cell(c.channel_id, 'id'),
+    cell(c.peer_alias || c.peer_pubkey, 'id'),
+    cell(c.capacity_sat),
+    cell(c.local_balance_msat),
+    cell(c.asset_id, 'id'),
+    cell(c.asset_local_amount),
+    cell(c.asset_remote_amount),
+    cell(c.is_usable ? 'usable' : (c.ready ? 'ready' : 'pending')),
+  ]));
+}
+
+async function loadAssets() {
+  const res = await api('POST', '/listassets', { filter_asset_schemas: [] });
+  const rows = [];
+  for (const schema of ['nia', 'uda', 'cfa']) {
+    for (const a of res[schema] || []) {
+      rows.push([
+        cell(a.asset_id, 'id'),
+        cell(schema.toUpperCase()),
+        cell(a.ticker),
+        cell(a.name),
+        cell(a.precision),
+        cell(a.balance.settled),
+        cell(a.balance.spendable),
+      ]);
+    }
+  }
+  fillTable('assets', rows);
+}
+
+async function loadPayments() {
+  const { payments } = await api('GET', '/listpayments');
+  fillTable('payments', payments.map((p) => [
+    cell(p.payment_hash, 'id'),
+    cell(p.inbound ? 'inbound' : 'outbound'),
+    cell(p.amt_msat),
+    cell(p.asset_id, 'id'),
+    cell(p.asset_amount),
+    cell(p.status),
+  ]));
+}
+
+async function loadSwaps() {
+  const { maker, taker } = await api('GET', '/listswaps');
+  const row = (side) => (s) => [
+    cell(s.payment_hash, 'id'),
+    cell(side),
+    cell(s.from_asset || 'BTC', 'id'),
+    cell(s.qty_from),
+    cell(s.to_asset || 'BTC', 'id'),
+    cell(s.qty_to),
+    cell(s.status),
+    cell(formatTimestamp(s.expires_at)),
+  ];
+  fillTable('swaps', maker.map(row('maker')).concat(taker.map(row('taker'))));
+}
+
+function addEvent(event) {
+  const list = document.querySelector('#events ul');
+  const li = document.createElement('li');
+  li.textContent = `${new Date().toLocaleTimeString()} ${JSON.stringify(event)}`;
+  list.prepend(li);
+  while (list.children.length > MAX_EVENTS) {
+    list.lastChild.remove();
+  }
+}
+
+function connectEvents() {
+  if (eventSocket) {
+    return;
+  }
+  const scheme = window.location.protocol === 'https:' ? 'wss' : 'ws';
+  eventSocket = new WebSocket(`${scheme}://${window.location.host}/events`);
+  eventSocket.onmessage = (msg) => {
+    try {
+      addEvent(JSON.parse(msg.data));
+    } catch (e) {
+      addEvent(msg.data);
+    }
+  };
+  eventSocket.onclose = () => {
+    eventSocket = null;
+  };
+}
+
+function disconnectEvents() {
+  if (eventSocket) {
+    eventSocket.close();
+    eventSocket = null;
+  }
+}
+
+function showUnlockScreen() {
+  clearInterval(refreshTimer);
+  refreshTimer = null;
+  disconnectEvents();
+  document.getElementById('dashboard').hidden = true;
+  document.getElementById('lock').hidden = true;
+  document.getElementById('unlock-screen').hidden = false;
+}
+
+function showDashboard() {
+  document.getElementById('unlock-screen').hidden = true;
+  document.getElementById('dashboard').hidden = false;
+  document.getElementById('lock').hidden = false;
+  connectEvents();
+  if (!refreshTimer) {
+    refreshTimer = setInterval(refresh, REFRESH_INTERVAL_MS);
+  }
+}
+
+async function refresh() {
+  try {
+    await loadNodeInfo();
+    await Promise.all([loadChannels(), loadAssets(), loadPayments(), loadSwaps()]);
+    showError('');
+    showDashboard();
+  } catch (e) {
+    if (e instanceof ApiError && e.status === 403) {
+      // the node is locked or not initialized yet
+      showUnlockScreen();
+    }
+    showError(e.message);
+  }
+}
+
+document.getElementById('unlock-form').addEventListener('submit', async (ev) => {
+  ev.preventDefault();
+  const password = document.getElementById('password');
+  try {
+    await api('POST', '/unlock', { password: password.value });
+    password.value = '';
+    await refresh();
+  } catch (e) {
+    showError(e.message);
+  }
+});
+
+document.getElementById('lock').addEventListener('click', async () => {
+  try {
+    await api('POST', '/lock');
+    showUnlockScreen();
+    showError('');
+  } catch (e) {
+    showError(e.message);
+  }
+});
+
+document.querySelectorAll('nav button').forEach((button) => {
+  button.addEventListener('click', () => {
+    document.querySelectorAll('nav button').forEach((b) => b.classList.toggle('active', b === button));
+    document.querySelectorAll('.tab').forEach((tab) => {
+      tab.hidden = tab.id !== button.dataset.tab;
+    });
+  });
+});
+
+refresh();